@@ -0,0 +1,113 @@
+//! Benchmarks formatting of representative documents: prose-heavy,
+//! table-heavy, CJK, and link-heavy.
+//!
+//! Run with `cargo bench --features bench`.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use hongdown::{Options, format};
+
+/// The number of sections in each generated document.
+const SECTIONS: usize = 200;
+
+/// Builds a document of long, unwrapped paragraphs with emphasis and code
+/// spans, nested lists, and block quotes.
+fn prose() -> String {
+    let mut document = String::from("Prose\n=====\n\n");
+    for i in 0..SECTIONS {
+        document.push_str(&format!("Section {i}\n----------\n\n"));
+        for _ in 0..3 {
+            document.push_str(
+                "Lorem ipsum dolor sit amet, *consectetur* adipiscing elit, sed do \
+                 eiusmod tempor incididunt ut labore et dolore magna aliqua.  Ut enim ad \
+                 minim veniam, quis **nostrud** exercitation ullamco laboris nisi ut \
+                 aliquip ex ea commodo `consequat`.  Duis aute irure dolor in \
+                 reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla \
+                 pariatur.\n\n",
+            );
+        }
+        document.push_str(
+            "* Excepteur sint occaecat cupidatat non proident, sunt in culpa qui \
+             officia deserunt mollit anim id est laborum.\n\
+             * Sed ut perspiciatis unde omnis iste natus error sit voluptatem\n  \
+             * accusantium doloremque laudantium, totam rem aperiam\n\n\
+             > Nemo enim ipsam voluptatem quia voluptas sit aspernatur aut odit aut \
+             fugit, sed quia consequuntur magni dolores eos qui ratione.\n\n",
+        );
+    }
+    document
+}
+
+/// Builds a document of unaligned tables with inline formatting in cells.
+fn tables() -> String {
+    let mut document = String::from("Tables\n======\n\n");
+    for i in 0..SECTIONS {
+        document.push_str(&format!("Table {i}\n--------\n\n"));
+        document.push_str("| Name | Type | Default | Description |\n|:-|-|-:|-|\n");
+        for row in 0..10 {
+            document.push_str(&format!(
+                "| `option_{row}` | *boolean* | {row} | Whether to enable \
+                 [feature {row}](https://example.com/{row}) \\| or not |\n"
+            ));
+        }
+        document.push('\n');
+    }
+    document
+}
+
+/// Builds a document of Korean, Japanese, and Chinese paragraphs mixed with
+/// Latin text, which exercises East Asian width handling and wrapping.
+fn cjk() -> String {
+    let mut document = String::from("다국어 문서\n===========\n\n");
+    for i in 0..SECTIONS {
+        document.push_str(&format!("섹션 {i}\n--------\n\n"));
+        document.push_str(
+            "홍다운은 마크다운 문서를 일관된 스타일로 정리하는 *포매터*입니다.  긴 문단은 \
+             지정된 너비에 맞춰 줄바꿈되며, `code` 같은 인라인 요소는 그대로 유지됩니다.  \
+             한국어와 English가 섞인 문장도 올바르게 처리해야 합니다.\n\n\
+             これは日本語の段落です。全角文字の幅を正しく計算しながら、\
+             **強調**やリンクを含む長い文を折り返します。\n\n\
+             这是一个中文段落，用于测试全角字符的宽度计算和自动换行，\
+             其中包含*强调*和`代码`等内联元素。\n\n",
+        );
+    }
+    document
+}
+
+/// Builds a document with many inline and reference-style links, images, and
+/// autolinks.
+fn links() -> String {
+    let mut document = String::from("Links\n=====\n\n");
+    for i in 0..SECTIONS {
+        document.push_str(&format!("Links {i}\n--------\n\n"));
+        document.push_str(&format!(
+            "See [the documentation](https://example.com/docs/{i}) and \
+             [the changelog][changes-{i}], or <https://example.com/{i}>.  The \
+             [API reference] covers ![a diagram](https://example.com/{i}.svg \
+             \"Diagram {i}\") as well as [issue #{i}][#{i}].\n\n\
+             [changes-{i}]: https://example.com/changes/{i}\n\
+             [#{i}]: https://github.com/dahlia/hongdown/issues/{i}\n\n"
+        ));
+    }
+    document.push_str("[API reference]: https://example.com/api\n");
+    document
+}
+
+fn bench_corpora(c: &mut Criterion) {
+    let options = Options::default();
+    let mut group = c.benchmark_group("corpora");
+    for (name, input) in [
+        ("prose", prose()),
+        ("tables", tables()),
+        ("cjk", cjk()),
+        ("links", links()),
+    ] {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &input, |b, input| {
+            b.iter(|| format(input, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_corpora);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Benchmarks formatting of documents with many reference-style links, such
+//! as long changelogs.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use hongdown::{Options, format};
+
+/// Builds a changelog-like document with `count` reference-style links and
+/// images.
+fn changelog(count: usize) -> String {
+    let mut document = String::from("Changelog\n=========\n\n");
+    for i in 0..count {
+        document.push_str(&format!(
+            " -  Fixed [issue #{i}][#{i}] reported by [contributor {i}]. ![Badge][badge-{i}]\n"
+        ));
+    }
+    document.push('\n');
+    for i in 0..count {
+        document.push_str(&format!(
+            "[#{i}]: https://github.com/dahlia/hongdown/issues/{i}\n"
+        ));
+        document.push_str(&format!(
+            "[contributor {i}]: https://example.com/users/{i}\n"
+        ));
+        document.push_str(&format!(
+            "[badge-{i}]: https://example.com/badges/{i}.svg\n"
+        ));
+    }
+    document
+}
+
+fn bench_reference_links(c: &mut Criterion) {
+    let options = Options::default();
+    let mut group = c.benchmark_group("reference_links");
+    for count in [100, 1_000, 5_000] {
+        let input = changelog(count);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &input, |b, input| {
+            b.iter(|| format(input, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_reference_links);
+criterion_main!(benches);
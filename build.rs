@@ -56,4 +56,8 @@ fn main() {
     }
 
     writeln!(f, "];").unwrap();
+
+    // Set up linker flags for the Node.js addon
+    #[cfg(feature = "napi")]
+    napi_build::setup();
 }
@@ -0,0 +1,108 @@
+/* SPDX-FileCopyrightText: 2025 Hong Minhee <https://hongminhee.org/> */
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+
+#ifndef HONGDOWN_H
+#define HONGDOWN_H
+
+/* Generated by cbindgen from src/capi.rs - DO NOT EDIT */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Status code returned by the C API functions.
+typedef enum HongdownStatus {
+  // The call succeeded.
+  HONGDOWN_STATUS_OK = 0,
+  // A required pointer argument was null.
+  HONGDOWN_STATUS_NULL_POINTER = 1,
+  // A string argument was not valid UTF-8.
+  HONGDOWN_STATUS_INVALID_UTF8 = 2,
+  // The options were invalid.
+  HONGDOWN_STATUS_INVALID_OPTIONS = 3,
+  // Formatting failed.
+  HONGDOWN_STATUS_FORMAT_ERROR = 4,
+} HongdownStatus;
+
+// Opaque formatting options.
+//
+// Create one with `hongdown_options_new()` or `hongdown_options_from_toml()`
+// and release it with `hongdown_options_free()`.
+typedef struct HongdownOptions HongdownOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create formatting options with the default settings.
+//
+// The returned pointer must be released with `hongdown_options_free()`.
+struct HongdownOptions *hongdown_options_new(void);
+
+// Create formatting options from the contents of a *.hongdown.toml* file.
+//
+// Returns null if `toml` is null, is not valid UTF-8, or does not describe
+// valid options; the reason is available from `hongdown_last_error()`.
+// The returned pointer must be released with `hongdown_options_free()`.
+//
+// # Safety
+//
+// `toml` must be null or point to a NUL-terminated string.
+struct HongdownOptions *hongdown_options_from_toml(const char *toml);
+
+// Set the line width used for wrapping.
+//
+// # Safety
+//
+// `options` must be null or a pointer returned by one of the
+// `hongdown_options_*` constructors that has not been freed.
+enum HongdownStatus hongdown_options_set_line_width(struct HongdownOptions *options, size_t width);
+
+// Release formatting options.  Passing null is a no-op.
+//
+// # Safety
+//
+// `options` must be null or a pointer returned by one of the
+// `hongdown_options_*` constructors that has not been freed.
+void hongdown_options_free(struct HongdownOptions *options);
+
+// Format Markdown according to Hong Minhee's style conventions.
+//
+// If `options` is null, the default options are used.  On success, `*output`
+// is set to the formatted Markdown, which must be released with
+// `hongdown_string_free()`.  On failure, `*output` is set to null and the
+// reason is available from `hongdown_last_error()`.
+//
+// # Safety
+//
+// `input` must point to a NUL-terminated string, `options` must be null or
+// a live options pointer, and `output` must be a valid pointer to write to.
+enum HongdownStatus hongdown_format(const char *input,
+                                    const struct HongdownOptions *options,
+                                    char **output);
+
+// Release a string returned by the C API.  Passing null is a no-op.
+//
+// # Safety
+//
+// `string` must be null or a pointer returned through an output parameter of
+// this API that has not been freed.
+void hongdown_string_free(char *string);
+
+// Return the message of the last error on the calling thread, or null if no
+// error has occurred.
+//
+// The returned string is owned by the library and stays valid until the next
+// failing call on the same thread.
+const char *hongdown_last_error(void);
+
+// Return the version of the Hongdown library as a static string.
+const char *hongdown_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HONGDOWN_H */
@@ -0,0 +1,160 @@
+/**
+ * Formatting options.  Accepts the same fields as the `@hongdown/wasm`
+ * package's `FormatOptions`, using camelCase names.
+ */
+export interface FormatOptions {
+  lineWidth?: number;
+  unicodeNormalization?: "none" | "nfc" | "nfkc";
+  bom?: "strip" | "preserve";
+  lineEnding?: "preserve" | "lf" | "crlf";
+  stripTrailingWhitespace?: boolean;
+  finalNewline?: "always" | "never" | "preserve";
+  tabWidth?: number;
+  expandTabs?: boolean;
+  maxBlankLines?: number;
+  escapeUnderscores?: boolean;
+  renumberReferences?: boolean;
+  autolinkHost?: "github" | "gitlab" | "none";
+  mentions?: "keep" | "escape" | "code";
+  profile?: "default" | "changelog";
+  linkTitleStyle?: "double" | "single" | "paren";
+  linkReferenceStyle?: "shortcut" | "collapsed";
+  referenceLabelCase?: "preserve" | "lower";
+  normalizeUrls?: boolean;
+  linkBase?: string;
+  verifyEscaping?: boolean;
+  verifyStability?: boolean;
+  setextH1?: boolean;
+  setextH2?: boolean;
+  setextUnderline?: "match" | "full-width" | number;
+  setextUnderlineMeasure?: "display-width" | "characters";
+  headingSentenceCase?: boolean;
+  headingProperNouns?: string[];
+  headingCommonNouns?: string[];
+  headingBlankLinesBefore?: [number, number, number, number, number, number];
+  headingCollapseConsecutive?: boolean;
+  headingBlankLinesAfter?: number;
+  headingOverlong?: "keep" | "warn" | "atx" | "wrap" | ("keep" | "warn" | "atx" | "wrap")[];
+  headingLinks?: "reference" | "inline" | "text";
+  headingTitleCase?: "none" | "simple" | "ap" | "chicago";
+  unorderedMarker?: "-" | "*" | "+";
+  leadingSpaces?: number;
+  trailingSpaces?: number;
+  indentWidth?: number;
+  oddLevelMarker?: "." | ")";
+  evenLevelMarker?: "." | ")";
+  orderedListPad?: "start" | "end";
+  orderedListIndentWidth?: number;
+  definitionMarkerSpaces?: number;
+  definitionIndentWidth?: number;
+  abbreviationPlacement?: "preserve" | "section" | "document";
+  frontMatterKeyOrder?: string[];
+  frontMatterNormalizeDates?: boolean;
+  frontMatterDateFormat?: string;
+  frontMatterDatetimeFormat?: string;
+  badgeLayout?: "preserve" | "line" | "stack";
+  imageConvertHtml?: boolean;
+  imageHtmlAttributes?: "skip" | "preserve";
+  figureStyle?: "preserve" | "markdown" | "html";
+  fenceChar?: "~" | "`";
+  minFenceLength?: number;
+  spaceAfterFence?: boolean;
+  defaultLanguage?: string;
+  warnLongCodeLines?: boolean;
+  codeLineWidth?: number;
+  codeExpandTabs?: Record<string, number>;
+  thematicBreakStyle?: string;
+  thematicBreakLeadingSpaces?: number;
+  tableEscapeHtml?: boolean;
+  tableBrStyle?: "preserve" | "<br>" | "<br/>" | "<br />";
+  tableRaggedRows?: "warn" | "drop" | "merge";
+  tableDelimiterStyle?: "padded" | "minimal" | "fixed";
+  tablePreserveLeftAlignment?: boolean;
+  alertSynonyms?: Record<string, string>;
+  alertConvertBoldLabels?: boolean;
+  curlyDoubleQuotes?: boolean;
+  curlySingleQuotes?: boolean;
+  curlyApostrophes?: boolean;
+  ellipsis?: boolean;
+  enDash?: false | string;
+  emDash?: false | string;
+  cjkQuotes?: "none" | "corner" | "full-width";
+  maxInputBytes?: number;
+  maxNestingDepth?: number;
+  maxTableColumns?: number;
+  descriptionLists?: boolean;
+  wikilinks?: boolean;
+}
+
+/** A warning generated during formatting. */
+export interface Warning {
+  /** Line number (1-indexed). */
+  line: number;
+  /** Column number (1-indexed), or 0 if unknown. */
+  column: number;
+  /** Machine-readable warning code (e.g., `"undefined-reference"`). */
+  code: string;
+  /** Category of the warning (e.g., `"tables"`), for filtering. */
+  kind: string;
+  /** Warning message. */
+  message: string;
+}
+
+/** Result of formatting with warnings. */
+export interface FormatResult {
+  /** Formatted Markdown output. */
+  output: string;
+  /** Warnings generated during formatting. */
+  warnings: Warning[];
+}
+
+/** A configuration file found by {@link discoverConfig}. */
+export interface DiscoveredConfig {
+  /** Path to the discovered *.hongdown.toml* file. */
+  path: string;
+  /** Formatting options from the configuration file. */
+  options: FormatOptions;
+}
+
+/**
+ * Format Markdown according to Hong Minhee's style conventions.
+ *
+ * @param input Markdown source to format.
+ * @param options Formatting options, as an object or a JSON string.
+ * @returns The formatted Markdown.
+ */
+export function format(
+  input: string,
+  options?: FormatOptions | string | null,
+): string;
+
+/**
+ * Format Markdown and return both output and warnings.
+ *
+ * @param input Markdown source to format.
+ * @param options Formatting options, as an object or a JSON string.
+ * @returns The formatted Markdown and any warnings.
+ */
+export function formatWithWarnings(
+  input: string,
+  options?: FormatOptions | string | null,
+): FormatResult;
+
+/**
+ * Load formatting options from a TOML, JSON, or YAML configuration file,
+ * e.g., *.hongdown.toml*.
+ *
+ * @param path Path to the configuration file.
+ * @returns The formatting options.
+ */
+export function loadConfig(path: string): FormatOptions;
+
+/**
+ * Find a *.hongdown.toml*, *.hongdown.json*, *hongdown.config.json*,
+ * *.hongdown.yaml*, or *.hongdown.yml* file in the given directory or its
+ * ancestors and load formatting options from it.
+ *
+ * @param startDir Directory to start searching from.
+ * @returns The discovered configuration, or `null` if none is found.
+ */
+export function discoverConfig(startDir: string): DiscoveredConfig | null;
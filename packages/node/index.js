@@ -0,0 +1,16 @@
+// Loads the native addon for the current platform.
+const path = require("node:path");
+
+const addonPath = path.join(
+  __dirname,
+  `hongdown.${process.platform}-${process.arch}.node`,
+);
+
+try {
+  module.exports = require(addonPath);
+} catch (error) {
+  throw new Error(
+    `Failed to load the Hongdown native addon for ` +
+      `${process.platform}-${process.arch}: ${error.message}`,
+  );
+}
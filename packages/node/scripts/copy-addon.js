@@ -0,0 +1,31 @@
+// Copies the native addon built by Cargo next to index.js, renaming it to
+// hongdown.<platform>-<arch>.node so that index.js can find it.
+const fs = require("node:fs");
+const path = require("node:path");
+
+const libraryNames = {
+  darwin: "libhongdown.dylib",
+  linux: "libhongdown.so",
+  win32: "hongdown.dll",
+};
+
+const libraryName = libraryNames[process.platform];
+if (libraryName == null) {
+  throw new Error(`Unsupported platform: ${process.platform}.`);
+}
+
+const source = path.join(
+  __dirname,
+  "..",
+  "..",
+  "..",
+  "target",
+  "release",
+  libraryName,
+);
+const target = path.join(
+  __dirname,
+  "..",
+  `hongdown.${process.platform}-${process.arch}.node`,
+);
+fs.copyFileSync(source, target);
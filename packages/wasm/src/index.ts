@@ -15,9 +15,11 @@
  */
 
 import type {
+  CreateFormatterOptions,
   FormatOptions,
   FormatResult,
   FormatWithCodeFormatterOptions,
+  Formatter,
 } from "./types.js";
 // @ts-expect-error: Subpath import resolved by Node.js/bundler
 import { loadWasmBuffer } from "#wasm-loader";
@@ -25,6 +27,7 @@ import init, {
   format as wasmFormat,
   formatWithWarnings as wasmFormatWithWarnings,
   formatWithCodeFormatter as wasmFormatWithCodeFormatter,
+  createFormatter as wasmCreateFormatter,
 } from "../pkg/hongdown.js";
 
 // Lazily initialized
@@ -64,8 +67,10 @@ async function ensureInitialized(): Promise<void> {
  * - `<!-- hongdown-enable -->` - Re-enable formatting.
  *
  * @param input - Markdown source to format
- * @param options - Formatting options (all optional)
+ * @param options - Formatting options (all optional), either as an object or
+ *                  as a JSON string encoding such an object
  * @returns The formatted Markdown string
+ * @throws {Error} If any option has an invalid value
  *
  * @example
  * ```typescript
@@ -84,7 +89,7 @@ async function ensureInitialized(): Promise<void> {
  */
 export async function format(
   input: string,
-  options: FormatOptions = {},
+  options: FormatOptions | string = {},
 ): Promise<string> {
   await ensureInitialized();
   return wasmFormat(input, options);
@@ -97,8 +102,10 @@ export async function format(
  * during formatting (e.g., inconsistent table column counts).
  *
  * @param input - Markdown source to format
- * @param options - Formatting options (all optional)
+ * @param options - Formatting options (all optional), either as an object or
+ *                  as a JSON string encoding such an object
  * @returns Object with formatted output and any warnings
+ * @throws {Error} If any option has an invalid value
  *
  * @example
  * ```typescript
@@ -115,7 +122,7 @@ export async function format(
  */
 export async function formatWithWarnings(
   input: string,
-  options: FormatOptions = {},
+  options: FormatOptions | string = {},
 ): Promise<FormatResult> {
   await ensureInitialized();
   return wasmFormatWithWarnings(input, options) as FormatResult;
@@ -159,13 +166,74 @@ export async function formatWithCodeFormatter(
   ) as FormatResult;
 }
 
+/**
+ * Create an incremental formatter for very large documents.
+ *
+ * The returned {@link Formatter} accepts the document in chunks via
+ * {@link Formatter.feed} and formats it when {@link Formatter.finish} is
+ * called.  Combined with the `onProgress` callback, this is suitable for
+ * running inside a Web Worker and reporting progress to the main thread.
+ *
+ * @param options - Formatting options with optional code formatter and
+ *                  progress callbacks
+ * @returns A formatter that can be fed chunks of Markdown source
+ * @throws {Error} If any option has an invalid value
+ *
+ * @example
+ * ```typescript
+ * import { createFormatter } from "@hongdown/wasm";
+ *
+ * // Inside a Web Worker:
+ * const formatter = await createFormatter({
+ *   onProgress: (done, total) => postMessage({ type: "progress", done, total }),
+ * });
+ * for await (const chunk of stream) {
+ *   formatter.feed(chunk);
+ * }
+ * const { output, warnings } = formatter.finish();
+ * ```
+ */
+export async function createFormatter(
+  options: CreateFormatterOptions = {},
+): Promise<Formatter> {
+  await ensureInitialized();
+  const { codeFormatter, onProgress, ...restOptions } = options;
+  const formatter = wasmCreateFormatter(
+    restOptions,
+    codeFormatter ?? null,
+    onProgress ?? null,
+  );
+  return {
+    feed(chunk: string): void {
+      formatter.feed(chunk);
+    },
+    get bytesFed(): number {
+      return formatter.bytesFed;
+    },
+    finish(): FormatResult {
+      return formatter.finish() as FormatResult;
+    },
+    free(): void {
+      formatter.free();
+    },
+  };
+}
+
 // Re-export types
 export type {
   CodeFormatterCallback,
+  CreateFormatterOptions,
+  Formatter,
+  ProgressCallback,
   FormatOptions,
   FormatResult,
   FormatWithCodeFormatterOptions,
   Warning,
+  UnorderedMarker,
+  OrderedMarker,
   OrderedListPad,
+  FenceChar,
   DashSetting,
+  SetextUnderline,
+  OverlongHeading,
 } from "./types.js";
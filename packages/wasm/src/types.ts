@@ -1,3 +1,18 @@
+/**
+ * Marker character for unordered lists.
+ */
+export type UnorderedMarker = "-" | "*" | "+";
+
+/**
+ * Marker character for ordered lists: `"."` for `1.` or `")"` for `1)`.
+ */
+export type OrderedMarker = "." | ")";
+
+/**
+ * Fence character for code blocks.
+ */
+export type FenceChar = "~" | "`";
+
 /**
  * Padding style for ordered list numbers.
  *
@@ -14,6 +29,25 @@ export type OrderedListPad = "start" | "end";
  */
 export type DashSetting = false | string;
 
+/**
+ * Length of the underlines of setext headings.
+ *
+ * - `"match"`: As long as the heading text
+ * - `"full-width"`: Up to the line width
+ * - `number`: A fixed number of characters
+ */
+export type SetextUnderline = "match" | "full-width" | number;
+
+/**
+ * What to do with a heading wider than the line width.
+ *
+ * - `"keep"`: Keep it on a single line
+ * - `"warn"`: Keep it on a single line, but report a warning
+ * - `"atx"`: Write a setext heading as an ATX heading instead
+ * - `"wrap"`: Wrap a setext heading over multiple lines
+ */
+export type OverlongHeading = "keep" | "warn" | "atx" | "wrap";
+
 /**
  * Formatting options for the Hongdown formatter.
  *
@@ -26,6 +60,156 @@ export interface FormatOptions {
    */
   lineWidth?: number;
 
+  /**
+   * Unicode normalization form applied to prose text, but not to code spans
+   * and code blocks, so that documents edited on different systems converge
+   * to the same bytes.
+   * @default "none"
+   */
+  unicodeNormalization?: "none" | "nfc" | "nfkc";
+
+  /**
+   * What to do with a UTF-8 byte order mark at the start of the input.
+   * It is never treated as part of the first block either way.
+   * @default "strip"
+   */
+  bom?: "strip" | "preserve";
+
+  /**
+   * Line ending of the output.  `"preserve"` uses the line ending of the
+   * first line of the input.
+   * @default "lf"
+   */
+  lineEnding?: "preserve" | "lf" | "crlf";
+
+  /**
+   * Strip trailing whitespace from every line, including those of code and
+   * HTML blocks, except the two spaces of hard line breaks.
+   * @default false
+   */
+  stripTrailingWhitespace?: boolean;
+
+  /**
+   * Whether the output ends with a newline.  `"preserve"` follows the input.
+   * @default "always"
+   */
+  finalNewline?: "always" | "never" | "preserve";
+
+  /**
+   * Width of a tab stop in columns.
+   * @default 4
+   */
+  tabWidth?: number;
+
+  /**
+   * Expand tabs in prose, including table cells, to spaces.  Code spans,
+   * code blocks, and HTML keep their tabs.
+   * @default false
+   */
+  expandTabs?: boolean;
+
+  /**
+   * Maximum number of consecutive blank lines kept between top-level blocks.
+   * Two blank lines before h2 headings are written regardless.
+   * @default 1
+   */
+  maxBlankLines?: number;
+
+  /**
+   * Escape underscores in the middle of words, e.g., `snake\_case`.
+   * If disabled, underscores between letters or digits are written as is.
+   * @default true
+   */
+  escapeUnderscores?: boolean;
+
+  /**
+   * Renumber purely numeric reference labels, e.g., `[1]`, in the order of
+   * their first use, rewriting both the links and their definitions.
+   * @default false
+   */
+  renumberReferences?: boolean;
+
+  /**
+   * The code hosting service whose shorthand references, e.g., `#123` or
+   * `owner/repo#123`, are kept intact: underscores in the repository are not
+   * escaped, and `#123` is never wrapped to the start of a line.
+   * @default "github"
+   */
+  autolinkHost?: "github" | "gitlab" | "none";
+
+  /**
+   * How `@username` mentions in prose, outside of code and links, are
+   * written: `"keep"` leaves them as they are, `"escape"` escapes the `@` so
+   * that pasting the text into an issue notifies no one, and `"code"` wraps
+   * them in code spans.
+   * @default "keep"
+   */
+  mentions?: "keep" | "escape" | "code";
+
+  /**
+   * A set of conventions for a kind of document.  With `"changelog"`,
+   * headings of version sections, e.g., `[1.0.0] - 2017-06-20` or
+   * `[Unreleased]`, are kept as they are, and the reference definitions of
+   * version sections are kept at the bottom of the document, newest first.
+   * @default "default"
+   */
+  profile?: "default" | "changelog";
+
+  /**
+   * How the titles of links and images are quoted: `"Title"`, `'Title'`, or
+   * `(Title)`.  Characters that would end a title early are escaped.
+   * @default "double"
+   */
+  linkTitleStyle?: "double" | "single" | "paren";
+
+  /**
+   * How reference links and images whose text is their label are written:
+   * `[text]` (shortcut) or `[text][]` (collapsed).
+   * @default "shortcut"
+   */
+  linkReferenceStyle?: "shortcut" | "collapsed";
+
+  /**
+   * How reference labels are cased: as first used (`"preserve"`) or
+   * lowercased (`"lower"`).  Labels differing only in case share a single
+   * definition either way.
+   * @default "preserve"
+   */
+  referenceLabelCase?: "preserve" | "lower";
+
+  /**
+   * Percent-encode spaces and other unsafe characters in link and image
+   * destinations, lowercase their scheme and host, and remove default ports.
+   * Destinations whose meaning this could change are left alone with
+   * a warning.
+   * @default false
+   */
+  normalizeUrls?: boolean;
+
+  /**
+   * A base path that relative link and image destinations are joined
+   * against, e.g., `"docs"`.  Destinations already within the base are left
+   * as they are, and ones pointing outside of it are left alone with
+   * a `"link-base"` warning.  An empty string disables rebasing.
+   * @default ""
+   */
+  linkBase?: string;
+
+  /**
+   * Re-parse the output and report an `"escaping"` warning wherever its
+   * inline content differs from the input's, which suggests missing or
+   * excessive escaping.
+   * @default false
+   */
+  verifyEscaping?: boolean;
+
+  /**
+   * Format the output once more and report an `"unstable"` warning, with
+   * the first hunk of the changes, if that changes it.
+   * @default false
+   */
+  verifyStability?: boolean;
+
   /**
    * Use setext-style (underlined) for h1 headings.
    * @default true
@@ -38,6 +222,20 @@ export interface FormatOptions {
    */
   setextH2?: boolean;
 
+  /**
+   * Length of the underlines of setext headings.
+   * @default "match"
+   */
+  setextUnderline?: SetextUnderline;
+
+  /**
+   * How the heading text is measured for `setextUnderline`: by display
+   * width, so that a wide character, e.g., a CJK one, gets two underline
+   * characters, or by the number of characters.
+   * @default "display-width"
+   */
+  setextUnderlineMeasure?: "display-width" | "characters";
+
   /**
    * Convert headings to sentence case.
    * When enabled, headings like "Getting Started With HONGDOWN" become
@@ -62,11 +260,55 @@ export interface FormatOptions {
    */
   headingCommonNouns?: string[];
 
+  /**
+   * Blank lines before top-level headings of each level, from h1 to h6.
+   * @default [1, 2, 1, 1, 1, 1]
+   */
+  headingBlankLinesBefore?: [number, number, number, number, number, number];
+
+  /**
+   * Write a single blank line between a heading and a heading right after
+   * it, regardless of `headingBlankLinesBefore`.
+   * @default true
+   */
+  headingCollapseConsecutive?: boolean;
+
+  /**
+   * Blank lines between a top-level heading and the block after it, unless
+   * that is a heading as well.
+   * @default 1
+   */
+  headingBlankLinesAfter?: number;
+
+  /**
+   * What to do with headings wider than the line width, either for all
+   * levels or as an array of six policies from h1 to h6.
+   * @default "keep"
+   */
+  headingOverlong?: OverlongHeading | OverlongHeading[];
+
+  /**
+   * How links inside headings are written: `"reference"` converts external
+   * links to reference links, `"inline"` keeps inline links inline, and
+   * `"text"` replaces links with their text.
+   * @default "reference"
+   */
+  headingLinks?: "reference" | "inline" | "text";
+
+  /**
+   * Title case style for headings: `"simple"` capitalizes every word, while
+   * `"ap"` and `"chicago"` keep articles, conjunctions, and prepositions
+   * lowercase following the AP or Chicago conventions.  Ignored when
+   * `headingSentenceCase` is enabled.
+   * @default "none"
+   */
+  headingTitleCase?: "none" | "simple" | "ap" | "chicago";
+
   /**
    * Marker character for unordered lists: `"-"`, `"*"`, or `"+"`.
    * @default "-"
    */
-  unorderedMarker?: string;
+  unorderedMarker?: UnorderedMarker;
 
   /**
    * Number of leading spaces before the list marker.
@@ -91,14 +333,14 @@ export interface FormatOptions {
    * Use `"."` for `1.` or `")"` for `1)`.
    * @default "."
    */
-  oddLevelMarker?: string;
+  oddLevelMarker?: OrderedMarker;
 
   /**
    * Marker style for ordered lists at even nesting levels.
    * Use `"."` for `1.` or `")"` for `1)`.
    * @default ")"
    */
-  evenLevelMarker?: string;
+  evenLevelMarker?: OrderedMarker;
 
   /**
    * Padding style for ordered list numbers.
@@ -112,11 +354,96 @@ export interface FormatOptions {
    */
   orderedListIndentWidth?: number;
 
+  /**
+   * Number of spaces after the `:` marker of a definition (1-3).
+   * @default 3
+   */
+  definitionMarkerSpaces?: number;
+
+  /**
+   * Indentation width for the content of a definition.
+   * @default 4
+   */
+  definitionIndentWidth?: number;
+
+  /**
+   * Where to place abbreviation definitions, e.g., `*[HTML]: HyperText
+   * Markup Language`: `"preserve"` keeps them where they are, while
+   * `"section"` and `"document"` group them, sorted alphabetically, at the
+   * end of each section or of the document.
+   * @default "preserve"
+   */
+  abbreviationPlacement?: "preserve" | "section" | "document";
+
+  /**
+   * Canonical order of the top-level keys of YAML front matter.  Listed keys
+   * are moved to the front in this order, along with the comments directly
+   * above them, and the others follow in their original order.
+   * @default []
+   */
+  frontMatterKeyOrder?: string[];
+
+  /**
+   * Whether to normalize the `date` and `updated` fields of YAML front
+   * matter.  Values that cannot be parsed as dates are kept and reported as
+   * warnings.
+   * @default false
+   */
+  frontMatterNormalizeDates?: boolean;
+
+  /**
+   * Format of normalized front matter dates without a time, using `%Y`,
+   * `%m`, and `%d`.
+   * @default "%Y-%m-%d"
+   */
+  frontMatterDateFormat?: string;
+
+  /**
+   * Format of normalized front matter dates with a time, which may also use
+   * `%H`, `%M`, `%S`, and `%z`.
+   * @default "%Y-%m-%dT%H:%M:%S%z"
+   */
+  frontMatterDatetimeFormat?: string;
+
+  /**
+   * How paragraphs that consist only of badges, i.e., links around images,
+   * are laid out: as any other paragraph, all on one line, or one per line.
+   * Unless `"preserve"`, badges also get reference labels named after their
+   * alt text, e.g., `[![CI][CI badge]][CI]`.
+   * @default "preserve"
+   */
+  badgeLayout?: "preserve" | "line" | "stack";
+
+  /**
+   * Convert HTML `<img>` elements into Markdown images, e.g.,
+   * `<img src="a.png" alt="A">` into `![A](a.png)`.
+   * @default false
+   */
+  imageConvertHtml?: boolean;
+
+  /**
+   * What to do with `<img>` elements that have attributes other than `src`,
+   * `alt`, and `title`, e.g., `width`: leave them as they are (`"skip"`), or
+   * convert them and keep the attributes in an attribute block like
+   * `{width="100"}` (`"preserve"`).
+   * @default "skip"
+   */
+  imageHtmlAttributes?: "skip" | "preserve";
+
+  /**
+   * How figures, i.e., images followed by an emphasized caption on the next
+   * line, are written: as they are (`"preserve"`), converting simple HTML
+   * `<figure>` elements into Markdown (`"markdown"`), or the other way around
+   * (`"html"`).
+   * @default "preserve"
+   */
+  figureStyle?: "preserve" | "markdown" | "html";
+
   /**
    * Fence character for code blocks: `"~"` or `` "`" ``.
    * @default "~"
    */
-  fenceChar?: string;
+  fenceChar?: FenceChar;
 
   /**
    * Minimum fence length for code blocks.
@@ -137,6 +464,26 @@ export interface FormatOptions {
    */
   defaultLanguage?: string;
 
+  /**
+   * Warn about lines of code blocks wider than `codeLineWidth`.
+   * @default false
+   */
+  warnLongCodeLines?: boolean;
+
+  /**
+   * Line width for `warnLongCodeLines`.
+   * @default lineWidth
+   */
+  codeLineWidth?: number;
+
+  /**
+   * Tab widths to expand the leading tabs of code blocks to spaces with, by
+   * language.  For example, `{ yaml: 2 }` expands the indentation of YAML
+   * code blocks with a tab stop every two columns.
+   * @default {}
+   */
+  codeExpandTabs?: Record<string, number>;
+
   /**
    * The style string for thematic breaks.
    * @default "- - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -"
@@ -149,6 +496,60 @@ export interface FormatOptions {
    */
   thematicBreakLeadingSpaces?: number;
 
+  /**
+   * Escape raw HTML in table cells, so that it is rendered as literal text
+   * and cannot break the table.
+   * @default false
+   */
+  tableEscapeHtml?: boolean;
+
+  /**
+   * How to write `<br>` tags in table cells.  `"preserve"` keeps each tag as
+   * written.
+   * @default "preserve"
+   */
+  tableBrStyle?: "preserve" | "<br>" | "<br/>" | "<br />";
+
+  /**
+   * How to handle table rows with more or fewer cells than the header row.
+   * Short rows are always padded with empty cells.  `"warn"` reports such
+   * rows and drops excess cells, `"drop"` drops them silently, and
+   * `"merge"` merges them into the last cell so that no content is lost.
+   * @default "warn"
+   */
+  tableRaggedRows?: "warn" | "drop" | "merge";
+
+  /**
+   * Style of the delimiter row of tables: `"padded"` pads the dashes to the
+   * column width (`| ------ |`), `"minimal"` uses three characters without
+   * spaces (`|---|`), and `"fixed"` uses three dashes regardless of the
+   * column width (`| --- |`).
+   * @default "padded"
+   */
+  tableDelimiterStyle?: "padded" | "minimal" | "fixed";
+
+  /**
+   * Keep the colons of tables whose columns are all left-aligned.  When
+   * `false`, such tables are written without alignment, which renders the
+   * same in most renderers.
+   * @default true
+   */
+  tablePreserveLeftAlignment?: boolean;
+
+  /**
+   * Alert types to replace with another type, matched case-insensitively.
+   * For example, `{ info: "NOTE" }` rewrites `> [!info]` as `> [!NOTE]`.
+   * @default {}
+   */
+  alertSynonyms?: Record<string, string>;
+
+  /**
+   * Rewrite paragraphs and block quotes that start with a bold label of an
+   * alert type, e.g., `**Note:**`, as GitHub alerts, e.g., `> [!NOTE]`.
+   * @default false
+   */
+  alertConvertBoldLabels?: boolean;
+
   /**
    * Convert straight double quotes to curly quotes.
    * `"text"` becomes `"text"` (U+201C and U+201D).
@@ -192,6 +593,49 @@ export interface FormatOptions {
    * @default "--"
    */
   emDash?: DashSetting;
+
+  /**
+   * Quotation marks that straight quotes around mostly CJK text become
+   * instead of curly quotes: `"corner"` for `「」` and `『』`, or
+   * `"full-width"` for `＂` and `＇`.
+   * @default "none"
+   */
+  cjkQuotes?: "none" | "corner" | "full-width";
+
+  /**
+   * Maximum size of the input in bytes.  Larger input is rejected with an
+   * error instead of being formatted.  Useful for untrusted input.  No limit
+   * by default.
+   */
+  maxInputBytes?: number;
+
+  /**
+   * Maximum nesting depth of the parsed document, e.g., of nested block
+   * quotes and lists.  Deeper documents are rejected with an error.  No
+   * limit by default.
+   */
+  maxNestingDepth?: number;
+
+  /**
+   * Maximum number of columns in a table.  Documents with wider tables are
+   * rejected with an error.  No limit by default.
+   */
+  maxTableColumns?: number;
+
+  /**
+   * Recognize definition lists, i.e., terms followed by lines starting with
+   * `: `.  Turn this off for documents whose prose merely looks like them.
+   * @default true
+   */
+  descriptionLists?: boolean;
+
+  /**
+   * Keep Obsidian-style wikilinks and embeds, e.g., `[[note|alias]]` and
+   * `![[note#section]]`, verbatim instead of escaping them and reporting
+   * them as undefined references.
+   * @default false
+   */
+  wikilinks?: boolean;
 }
 
 /**
@@ -244,6 +688,50 @@ export interface FormatWithCodeFormatterOptions extends FormatOptions {
   codeFormatter?: CodeFormatterCallback;
 }
 
+/**
+ * Callback function for reporting formatting progress.
+ *
+ * @param done - Number of top-level blocks formatted so far
+ * @param total - Total number of top-level blocks in the document
+ */
+export type ProgressCallback = (done: number, total: number) => void;
+
+/**
+ * Options for {@link createFormatter}.
+ */
+export interface CreateFormatterOptions extends FormatWithCodeFormatterOptions {
+  /**
+   * Optional callback to report progress while the document is formatted.
+   */
+  onProgress?: ProgressCallback;
+}
+
+/**
+ * An incremental formatter created by {@link createFormatter}.
+ */
+export interface Formatter {
+  /**
+   * Append a chunk of Markdown source to the document.
+   */
+  feed(chunk: string): void;
+
+  /**
+   * Number of bytes (in UTF-8) fed so far.
+   */
+  readonly bytesFed: number;
+
+  /**
+   * Format the fed document.  The formatter is reset afterwards, so it can
+   * be reused for another document with the same options.
+   */
+  finish(): FormatResult;
+
+  /**
+   * Release the memory held by the underlying WASM object.
+   */
+  free(): void;
+}
+
 /**
  * A warning generated during formatting.
  */
@@ -253,6 +741,29 @@ export interface Warning {
    */
   line: number;
 
+  /**
+   * Column number where the warning was generated (1-indexed), or `0` if
+   * the column is unknown.
+   */
+  column: number;
+
+  /**
+   * Machine-readable warning code, e.g., `"undefined-reference"`,
+   * `"table-pipe-count"`, or `"code-formatter"`.
+   */
+  code: string;
+
+  /**
+   * Category of the warning, for filtering.
+   */
+  kind:
+    | "tables"
+    | "references"
+    | "typography"
+    | "structure"
+    | "code"
+    | "verification";
+
   /**
    * Warning message.
    */
@@ -1,4 +1,9 @@
-import { format, formatWithWarnings, formatWithCodeFormatter } from "@hongdown/wasm";
+import {
+  createFormatter,
+  format,
+  formatWithCodeFormatter,
+  formatWithWarnings,
+} from "@hongdown/wasm";
 import type { FormatOptions } from "@hongdown/wasm";
 import assert from "node:assert/strict";
 import { describe, it } from "node:test";
@@ -117,6 +122,18 @@ describe("format", () => {
     const output = await format("   \n\n   ");
     assert.equal(output, "");
   });
+
+  it("accepts options as a JSON string", async () => {
+    const input = "* Item 1\n* Item 2";
+    const output = await format(input, JSON.stringify({ unorderedMarker: "*" }));
+    assert.equal(output, " *  Item 1\n *  Item 2\n");
+  });
+
+  it("rejects invalid option values", async () => {
+    await assert.rejects(format("Hello", { lineWidth: 5 }), /line_width/);
+    await assert.rejects(format("Hello", { fenceChar: "#" }), /fenceChar/);
+    await assert.rejects(format("Hello", "{ not json"), /invalid options JSON/);
+  });
 });
 
 describe("formatWithWarnings", () => {
@@ -149,6 +166,15 @@ describe("formatWithWarnings", () => {
       "Warning should mention columns",
     );
   });
+
+  it("returns line, column, and code for each warning", async () => {
+    const input = "Intro.\n\nSee [missing] here.";
+    const { warnings } = await formatWithWarnings(input);
+    assert.equal(warnings.length, 1);
+    assert.equal(warnings[0].line, 3);
+    assert.equal(warnings[0].column, 5);
+    assert.equal(warnings[0].code, "undefined-reference");
+  });
 });
 
 describe("formatWithCodeFormatter", () => {
@@ -236,6 +262,40 @@ describe("formatWithCodeFormatter", () => {
   });
 });
 
+describe("createFormatter", () => {
+  it("formats a document fed in chunks", async () => {
+    const formatter = await createFormatter();
+    formatter.feed("# Hello\n\n");
+    formatter.feed("* Item 1\n* Item 2\n");
+    const { output, warnings } = formatter.finish();
+    assert.equal(output, "Hello\n=====\n\n -  Item 1\n -  Item 2\n");
+    assert.equal(warnings.length, 0);
+    formatter.free();
+  });
+
+  it("reports progress through top-level blocks", async () => {
+    const progress: [number, number][] = [];
+    const formatter = await createFormatter({
+      onProgress: (done, total) => progress.push([done, total]),
+    });
+    formatter.feed("# Title\n\nFirst.\n\nSecond.\n");
+    formatter.finish();
+    assert.deepEqual(progress[progress.length - 1], [3, 3]);
+    assert.ok(progress.every(([done, total]) => done <= total));
+    formatter.free();
+  });
+
+  it("can be reused after finish", async () => {
+    const formatter = await createFormatter({ unorderedMarker: "*" });
+    formatter.feed("- A");
+    assert.equal(formatter.finish().output, " *  A\n");
+    assert.equal(formatter.bytesFed, 0);
+    formatter.feed("- B");
+    assert.equal(formatter.finish().output, " *  B\n");
+    formatter.free();
+  });
+});
+
 describe("options", () => {
   it("accepts all formatting options", async () => {
     const input = "# Test\n\n- item";
@@ -0,0 +1,369 @@
+//! Asynchronous formatting.
+//!
+//! [`format_with_warnings`] runs external code formatters one after another
+//! and blocks the thread while it waits for them, which stalls the worker
+//! threads of async servers such as language servers.  [`format_async`]
+//! finds the code blocks to format first, runs their formatters
+//! concurrently as Tokio subprocesses, and then formats the document with
+//! their results.
+//!
+//! Dropping the future returned by [`format_async`] kills the formatters
+//! that are still running.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::rc::Rc;
+use std::time::Duration;
+
+use indexmap::IndexSet;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::task::JoinSet;
+
+use crate::serializer::formatter::{self, FormatterError, ProcessTree, failure_warning};
+use crate::serializer::sandbox::Sandbox;
+use crate::serializer::{self, CodeFormatterCallback};
+use crate::{
+    CodeFormatter, FormatError, FormatResult, Options, Warning, WarningCode,
+    changed_output_warning, format_with_serializer, format_with_warnings,
+};
+
+/// Formats a Markdown document like [`format_with_warnings`], but runs
+/// external [`Options::code_formatters`] concurrently without blocking
+/// the thread.
+///
+/// This must be awaited within a Tokio runtime.  Dropping the returned
+/// future kills the code formatters that are still running.  Code blocks
+/// with the same language and contents are formatted only once.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{Options, format_async};
+///
+/// # tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+/// let result = format_async("# Hello\n", &Options::default()).await.unwrap();
+/// assert_eq!(result.output, "Hello\n=====\n");
+/// # });
+/// ```
+pub async fn format_async(input: &str, options: &Options) -> Result<FormatResult, FormatError> {
+    // Without these, no formatter is allowed to run
+    if options.code_formatters.is_empty() && options.allowed_code_formatters.is_none() {
+        return format_with_warnings(input, options);
+    }
+
+    let requests = code_format_requests(input, options)?;
+    let mut tasks = JoinSet::new();
+    for (index, ((_, directive_command, code), formatter)) in requests.iter().enumerate() {
+        let allowed =
+            formatter::check_allowed(&formatter.command, directive_command.is_some(), options);
+        let command = formatter.command.clone();
+        let code = code.clone();
+        let timeout = Duration::from_secs(formatter.timeout_secs);
+        let dir = options.code_formatter_dir.clone();
+        let sandbox = options.code_formatter_sandbox;
+        tasks.spawn(async move {
+            let result = async {
+                allowed?;
+                run_formatter(command, code, timeout, dir, sandbox).await
+            };
+            (index, result.await)
+        });
+    }
+    let mut results: Vec<Option<Result<String, FormatterError>>> =
+        (0..requests.len()).map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        let (index, result) = joined.map_err(|error| FormatError::Internal {
+            message: error.to_string(),
+            location: None,
+        })?;
+        results[index] = Some(result);
+    }
+
+    let mut formatted = HashMap::new();
+    let mut failures = HashMap::new();
+    for ((request, formatter), result) in requests.into_iter().zip(results) {
+        match result {
+            Some(Ok(output)) => {
+                formatted.insert(request, output);
+            }
+            Some(Err(error)) => {
+                let failure = failure_warning(&formatter, &request.0, &error);
+                failures.insert(request, failure);
+            }
+            None => {}
+        }
+    }
+
+    // The output is formatted once more below, without blocking
+    let first_pass = Options {
+        verify_stability: false,
+        ..options.clone()
+    };
+    let mut result = format_with_results(input, &first_pass, formatted, failures)?;
+    if options.verify_stability
+        && let Ok(again) = Box::pin(format_async(&result.output, &first_pass)).await
+    {
+        result
+            .warnings
+            .extend(changed_output_warning(&result.output, &again.output));
+    }
+    Ok(result)
+}
+
+/// Formats `input` with the `formatted` code of the code blocks, and reports
+/// the `failures` of their formatters at the line of each code block they
+/// occur for.
+fn format_with_results(
+    input: &str,
+    options: &Options,
+    formatted: HashMap<Request, String>,
+    failures: HashMap<Request, (WarningCode, String)>,
+) -> Result<FormatResult, FormatError> {
+    let warnings = Rc::new(RefCell::new(Vec::new()));
+    let reporter = Rc::clone(&warnings);
+    let callback: CodeFormatterCallback = Some(Box::new(
+        move |language: &str, command: Option<&[String]>, code: &str, line: usize| {
+            let request = request(language, command, code);
+            if let Some((warning_code, message)) = failures.get(&request) {
+                reporter
+                    .borrow_mut()
+                    .push(Warning::new(*warning_code, line, 0, message.clone()));
+            }
+            formatted.get(&request).cloned()
+        },
+    ));
+    let mut result = format_with_serializer(input, options, |root, input| {
+        serializer::serialize_with_code_formatter_callback(root, options, Some(input), callback)
+    })?;
+    result.warnings.extend(warnings.take());
+    Ok(result)
+}
+
+/// The language of a code block, the command chosen by
+/// a `hongdown-format-with` directive before it, if any, and its contents.
+type Request = (String, Option<Vec<String>>, String);
+
+fn request(language: &str, command: Option<&[String]>, code: &str) -> Request {
+    (
+        language.to_string(),
+        command.map(<[String]>::to_vec),
+        code.to_string(),
+    )
+}
+
+/// Returns the code blocks of `input` that code formatters would run for,
+/// along with their formatters, without running them.
+fn code_format_requests(
+    input: &str,
+    options: &Options,
+) -> Result<Vec<(Request, CodeFormatter)>, FormatError> {
+    let requests = Rc::new(RefCell::new(IndexSet::new()));
+    let recorder = Rc::clone(&requests);
+    let callback: CodeFormatterCallback = Some(Box::new(
+        move |language: &str, command: Option<&[String]>, code: &str, _line: usize| {
+            recorder
+                .borrow_mut()
+                .insert(request(language, command, code));
+            None
+        },
+    ));
+    let options = Options {
+        verify_escaping: false,
+        verify_stability: false,
+        ..options.clone()
+    };
+    format_with_serializer(input, &options, |root, input| {
+        serializer::serialize_with_code_formatter_callback(root, &options, Some(input), callback)
+    })?;
+    let requests = requests.take();
+    Ok(requests
+        .into_iter()
+        .filter_map(|request| {
+            let formatter = formatter::block_formatter(&options, &request.0, request.1.as_deref())?;
+            Some((request, formatter))
+        })
+        .collect())
+}
+
+/// Run an external formatter command with `code` as its stdin, killing it
+/// and its children if it takes longer than `timeout` or the future is
+/// dropped.  A sandboxed formatter ignores `dir`.
+async fn run_formatter(
+    command: Vec<String>,
+    code: String,
+    timeout: Duration,
+    dir: Option<PathBuf>,
+    sandbox: bool,
+) -> Result<String, FormatterError> {
+    let (program, args) = command.split_first().ok_or(FormatterError::EmptyCommand)?;
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Kept until the process exits
+    let _sandbox = if sandbox {
+        Some(Sandbox::apply(command.as_std_mut()).map_err(FormatterError::Spawn)?)
+    } else {
+        if let Some(dir) = dir {
+            command.current_dir(dir);
+        }
+        None
+    };
+    formatter::isolate(command.as_std_mut());
+    let mut child = command.spawn().map_err(FormatterError::Spawn)?;
+    // Declared after the child, so that it is dropped first, before
+    // the child is reaped
+    let mut tree = ProcessTree::new_async(&child);
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    // Write stdin while reading stdout, so that neither pipe fills up
+    let write = async move {
+        if let Some(mut stdin) = stdin {
+            stdin.write_all(code.as_bytes()).await?;
+        }
+        Ok(())
+    };
+    let run = async {
+        let (written, stdout, stderr, status) =
+            tokio::join!(write, read_pipe(stdout), read_pipe(stderr), child.wait());
+        let status = status.map_err(FormatterError::Spawn)?;
+        let stdout = stdout.map_err(FormatterError::Spawn)?;
+        let stderr = stderr.map_err(FormatterError::Spawn)?;
+        if !status.success() {
+            return Err(FormatterError::NonZeroExit {
+                code: status.code(),
+                stderr: String::from_utf8_lossy(&stderr).to_string(),
+            });
+        }
+        formatter::ignore_broken_pipe(written).map_err(FormatterError::Stdin)?;
+        String::from_utf8(stdout).map_err(FormatterError::InvalidUtf8)
+    };
+    match tokio::time::timeout(timeout, run).await {
+        Ok(result) => {
+            tree.release();
+            result
+        }
+        Err(_) => {
+            tree.kill();
+            let _ = child.kill().await;
+            Err(FormatterError::Timeout)
+        }
+    }
+}
+
+/// Read `pipe`, if any, to its end.
+async fn read_pipe(pipe: Option<impl AsyncRead + Unpin>) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    if let Some(mut pipe) = pipe {
+        pipe.read_to_end(&mut buffer).await?;
+    }
+    Ok(buffer)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::WarningCode;
+
+    fn options(command: &[&str], timeout_secs: u64) -> Options {
+        Options {
+            code_formatters: HashMap::from([(
+                "text".to_string(),
+                CodeFormatter {
+                    command: command.iter().map(|arg| arg.to_string()).collect(),
+                    timeout_secs,
+                },
+            )]),
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_format_async_is_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let options = Options::default();
+        assert_send(&format_async("", &options));
+    }
+
+    #[tokio::test]
+    async fn test_format_async() {
+        let options = options(&["tr", "a-z", "A-Z"], 5);
+        let input = "~~~~ text\nhello\n~~~~\n\n~~~~ rust\nfn main() {}\n~~~~\n";
+        let result = format_async(input, &options).await.unwrap();
+        assert_eq!(
+            result.output,
+            "~~~~ text\nHELLO\n~~~~\n\n~~~~ rust\nfn main() {}\n~~~~\n"
+        );
+        assert_eq!(
+            result.output,
+            format_with_warnings(input, &options).unwrap().output
+        );
+
+        let input = "<!-- hongdown-no-format-code -->\n\n~~~~ text\nhello\n~~~~\n\n\
+                     <!-- hongdown-format-with: tr a-z A-Z -->\n\n~~~~ rust\nfn main() {}\n~~~~\n";
+        let result = format_async(input, &options).await.unwrap();
+        assert_eq!(
+            result.output,
+            "<!-- hongdown-no-format-code -->\n\n~~~~ text\nhello\n~~~~\n\n\
+             <!-- hongdown-format-with: tr a-z A-Z -->\n\n~~~~ rust\nFN MAIN() {}\n~~~~\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_format_async_runs_formatters_concurrently() {
+        let options = options(&["sh", "-c", "sleep 1; cat"], 5);
+        let input = "~~~~ text\na\n~~~~\n\n~~~~ text\nb\n~~~~\n\n~~~~ text\nc\n~~~~\n";
+        let started = Instant::now();
+        let result = format_async(input, &options).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(2500));
+        assert_eq!(result.output, input);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_format_async_failures() {
+        let input = "~~~~ text\nhello\n~~~~\n";
+        let result = format_async(input, &options(&["sleep", "10"], 1))
+            .await
+            .unwrap();
+        assert_eq!(result.output, input);
+        assert_eq!(result.warnings[0].code, WarningCode::CodeFormatterTimeout);
+
+        // The shell exits at once, but its child keeps stdout open until it
+        // is killed along with the shell
+        let started = Instant::now();
+        let result = format_async(input, &options(&["sh", "-c", "sleep 30 &"], 1))
+            .await
+            .unwrap();
+        assert_eq!(result.warnings[0].code, WarningCode::CodeFormatterTimeout);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let not_allowed = Options {
+            allowed_code_formatters: Some(vec![vec!["true".to_string()]]),
+            ..options(&["cat"], 5)
+        };
+        let result = format_async(input, &not_allowed).await.unwrap();
+        assert!(result.warnings[0].message.contains("not among the allowed"));
+
+        let result = format_async(input, &options(&["false"], 5)).await.unwrap();
+        assert_eq!(result.output, input);
+        assert_eq!(result.warnings[0].code, WarningCode::CodeFormatter);
+        assert!(result.warnings[0].message.contains("exited with code 1"));
+
+        // Each code block is reported at its own line
+        let input = "~~~~ text\nhello\n~~~~\n\n~~~~ text\nhello\n~~~~\n";
+        let result = format_async(input, &options(&["false"], 5)).await.unwrap();
+        let lines: Vec<usize> = result.warnings.iter().map(|warning| warning.line).collect();
+        assert_eq!(lines, [1, 5]);
+    }
+}
@@ -0,0 +1,108 @@
+//! Timing of the formatting phases.
+//!
+//! [`measure`] formats a document like [`format`](crate::format) does, but
+//! reports how long each phase took, so that performance regressions can be
+//! attributed to the parser, the AST transforms, the serializer, or the
+//! external code formatters.  Only available with the `bench` feature.
+
+use std::time::{Duration, Instant};
+
+use comrak::Arena;
+
+use crate::{FormatError, Options, limits, parse_markdown, serialize_document, transform};
+
+/// How long each phase of formatting a document took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// Parsing the input into an AST.
+    pub parse: Duration,
+    /// Applying [`Options::transforms`].
+    pub transform: Duration,
+    /// Serializing the AST, excluding the time spent in code formatters.
+    pub serialize: Duration,
+    /// Running external code formatters on code blocks.
+    pub code_formatter: Duration,
+}
+
+impl Timings {
+    /// Returns the total time of all phases.
+    pub fn total(&self) -> Duration {
+        self.parse + self.transform + self.serialize + self.code_formatter
+    }
+}
+
+/// Formats the input once and returns the time spent in each phase.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{Options, measure};
+///
+/// let timings = measure("Title\n=====\n\nSome *text*.\n", &Options::default()).unwrap();
+/// assert!(timings.total() >= timings.parse);
+/// ```
+pub fn measure(input: &str, options: &Options) -> Result<Timings, FormatError> {
+    let mut timings = Timings::default();
+    if input.is_empty() {
+        return Ok(timings);
+    }
+
+    let arena = Arena::new();
+    let started = Instant::now();
+    limits::check_input(input, &options.limits)?;
+    let root = parse_markdown(&arena, input, &options.extensions);
+    limits::check_document(root, &options.limits)?;
+    timings.parse = started.elapsed();
+
+    let started = Instant::now();
+    transform::apply_transforms(root, &arena, options);
+    timings.transform = started.elapsed();
+
+    let started = Instant::now();
+    let result = serialize_document(root, input, options);
+    let elapsed = started.elapsed();
+    timings.code_formatter = result.code_formatter_time;
+    timings.serialize = elapsed.saturating_sub(result.code_formatter_time);
+    Ok(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_empty_input() {
+        let timings = measure("", &Options::default()).unwrap();
+        assert_eq!(timings, Timings::default());
+    }
+
+    #[test]
+    fn test_measure_without_code_formatters() {
+        let input = "Title\n=====\n\n```rust\nfn main() {}\n```\n";
+        let timings = measure(input, &Options::default()).unwrap();
+        assert_eq!(timings.code_formatter, Duration::ZERO);
+        assert!(timings.total() >= timings.serialize);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_measure_code_formatter_time() {
+        use crate::CodeFormatter;
+
+        let mut options = Options::default();
+        options.code_formatters.insert(
+            "text".to_string(),
+            CodeFormatter {
+                command: vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    "sleep 0.1; cat".to_string(),
+                ],
+                timeout_secs: 5,
+            },
+        );
+        let input = "```text\nHello\n```\n";
+        let timings = measure(input, &options).unwrap();
+        assert!(timings.code_formatter >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,329 @@
+//! C ABI bindings for Hongdown.
+//!
+//! This module exposes the formatter through plain C functions so that editors
+//! and languages without first-class Rust or WASM support can embed it.  The
+//! matching header, *include/hongdown.h*, is checked in, and is regenerated
+//! with cbindgen by running `mise run build:header` after the API changes.
+//!
+//! Strings passed in must be NUL-terminated UTF-8.  Strings returned through
+//! output parameters are owned by the caller and must be released with
+//! `hongdown_string_free()`.  When a function fails, a message describing the
+//! error can be retrieved with `hongdown_last_error()`.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+use crate::Options;
+use crate::config::Config;
+
+/// Status code returned by the C API functions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HongdownStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The options were invalid.
+    InvalidOptions = 3,
+    /// Formatting failed.
+    FormatError = 4,
+}
+
+/// Opaque formatting options.
+///
+/// Create one with `hongdown_options_new()` or `hongdown_options_from_toml()`
+/// and release it with `hongdown_options_free()`.
+pub struct HongdownOptions {
+    options: Options,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Record the error message returned by `hongdown_last_error()`.
+fn set_last_error(message: impl Into<String>) {
+    // Interior NUL bytes cannot be represented in a C string.
+    let message = message.into().replace('\0', " ");
+    let message = CString::new(message).expect("NUL bytes were replaced");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Borrow a C string as a `&str`, recording an error on failure.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that outlives `'a`.
+unsafe fn str_from_ptr<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, HongdownStatus> {
+    if ptr.is_null() {
+        set_last_error(format!("{} must not be null.", name));
+        return Err(HongdownStatus::NullPointer);
+    }
+    // SAFETY: The caller guarantees `ptr` is a valid NUL-terminated string.
+    let cstr = unsafe { CStr::from_ptr(ptr) };
+    cstr.to_str().map_err(|_| {
+        set_last_error(format!("{} is not valid UTF-8.", name));
+        HongdownStatus::InvalidUtf8
+    })
+}
+
+/// Create formatting options with the default settings.
+///
+/// The returned pointer must be released with `hongdown_options_free()`.
+#[unsafe(no_mangle)]
+pub extern "C" fn hongdown_options_new() -> *mut HongdownOptions {
+    Box::into_raw(Box::new(HongdownOptions {
+        options: Options::default(),
+    }))
+}
+
+/// Create formatting options from the contents of a *.hongdown.toml* file.
+///
+/// Returns null if `toml` is null, is not valid UTF-8, or does not describe
+/// valid options; the reason is available from `hongdown_last_error()`.
+/// The returned pointer must be released with `hongdown_options_free()`.
+///
+/// # Safety
+///
+/// `toml` must be null or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hongdown_options_from_toml(toml: *const c_char) -> *mut HongdownOptions {
+    // SAFETY: Forwarded from the caller.
+    let Ok(toml) = (unsafe { str_from_ptr(toml, "toml") }) else {
+        return ptr::null_mut();
+    };
+    let config = match Config::from_toml(toml) {
+        Ok(config) => config,
+        Err(e) => {
+            set_last_error(format!("Failed to parse configuration: {}", e));
+            return ptr::null_mut();
+        }
+    };
+    match config.to_options() {
+        Ok(options) => Box::into_raw(Box::new(HongdownOptions { options })),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Set the line width used for wrapping.
+///
+/// # Safety
+///
+/// `options` must be null or a pointer returned by one of the
+/// `hongdown_options_*` constructors that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hongdown_options_set_line_width(
+    options: *mut HongdownOptions,
+    width: usize,
+) -> HongdownStatus {
+    // SAFETY: The caller guarantees `options` is null or valid.
+    let Some(options) = (unsafe { options.as_mut() }) else {
+        set_last_error("options must not be null.");
+        return HongdownStatus::NullPointer;
+    };
+    match crate::LineWidth::new(width) {
+        Ok(width) => {
+            options.options.line_width = width;
+            HongdownStatus::Ok
+        }
+        Err(e) => {
+            set_last_error(e);
+            HongdownStatus::InvalidOptions
+        }
+    }
+}
+
+/// Release formatting options.  Passing null is a no-op.
+///
+/// # Safety
+///
+/// `options` must be null or a pointer returned by one of the
+/// `hongdown_options_*` constructors that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hongdown_options_free(options: *mut HongdownOptions) {
+    if !options.is_null() {
+        // SAFETY: The caller guarantees `options` came from `Box::into_raw`.
+        drop(unsafe { Box::from_raw(options) });
+    }
+}
+
+/// Format Markdown according to Hong Minhee's style conventions.
+///
+/// If `options` is null, the default options are used.  On success, `*output`
+/// is set to the formatted Markdown, which must be released with
+/// `hongdown_string_free()`.  On failure, `*output` is set to null and the
+/// reason is available from `hongdown_last_error()`.
+///
+/// # Safety
+///
+/// `input` must point to a NUL-terminated string, `options` must be null or
+/// a live options pointer, and `output` must be a valid pointer to write to.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hongdown_format(
+    input: *const c_char,
+    options: *const HongdownOptions,
+    output: *mut *mut c_char,
+) -> HongdownStatus {
+    if output.is_null() {
+        set_last_error("output must not be null.");
+        return HongdownStatus::NullPointer;
+    }
+    // SAFETY: `output` is non-null and the caller guarantees it is writable.
+    unsafe { *output = ptr::null_mut() };
+    // SAFETY: Forwarded from the caller.
+    let input = match unsafe { str_from_ptr(input, "input") } {
+        Ok(input) => input,
+        Err(status) => return status,
+    };
+    let default_options;
+    // SAFETY: The caller guarantees `options` is null or valid.
+    let options = match unsafe { options.as_ref() } {
+        Some(options) => &options.options,
+        None => {
+            default_options = Options::default();
+            &default_options
+        }
+    };
+    let formatted = match crate::format(input, options) {
+        Ok(formatted) => formatted,
+        Err(e) => {
+            set_last_error(e.to_string());
+            return HongdownStatus::FormatError;
+        }
+    };
+    match CString::new(formatted) {
+        Ok(formatted) => {
+            // SAFETY: Checked above.
+            unsafe { *output = formatted.into_raw() };
+            HongdownStatus::Ok
+        }
+        Err(_) => {
+            set_last_error("Formatted output contains a NUL byte.");
+            HongdownStatus::FormatError
+        }
+    }
+}
+
+/// Release a string returned by the C API.  Passing null is a no-op.
+///
+/// # Safety
+///
+/// `string` must be null or a pointer returned through an output parameter of
+/// this API that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hongdown_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: The caller guarantees `string` came from `CString::into_raw`.
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+/// Return the message of the last error on the calling thread, or null if no
+/// error has occurred.
+///
+/// The returned string is owned by the library and stays valid until the next
+/// failing call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn hongdown_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Return the version of the Hongdown library as a static string.
+#[unsafe(no_mangle)]
+pub extern "C" fn hongdown_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let ptr = hongdown_last_error();
+        assert!(!ptr.is_null());
+        unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string()
+    }
+
+    fn format_with(input: &CStr, options: *const HongdownOptions) -> (HongdownStatus, String) {
+        let mut output = ptr::null_mut();
+        let status = unsafe { hongdown_format(input.as_ptr(), options, &mut output) };
+        if output.is_null() {
+            return (status, String::new());
+        }
+        let formatted = unsafe { CStr::from_ptr(output) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { hongdown_string_free(output) };
+        (status, formatted)
+    }
+
+    #[test]
+    fn test_format_with_default_options() {
+        let (status, output) = format_with(c"# Hello\nWorld", ptr::null());
+        assert_eq!(status, HongdownStatus::Ok);
+        assert_eq!(output, "Hello\n=====\n\nWorld\n");
+    }
+
+    #[test]
+    fn test_format_with_toml_options() {
+        let options =
+            unsafe { hongdown_options_from_toml(c"[heading]\nsetext_h1 = false\n".as_ptr()) };
+        assert!(!options.is_null());
+        let (status, output) = format_with(c"Hello\n=====", options);
+        unsafe { hongdown_options_free(options) };
+        assert_eq!(status, HongdownStatus::Ok);
+        assert_eq!(output, "# Hello\n");
+    }
+
+    #[test]
+    fn test_options_from_invalid_toml() {
+        let options = unsafe { hongdown_options_from_toml(c"line_width = 5".as_ptr()) };
+        assert!(options.is_null());
+        assert!(last_error().contains("Failed to parse configuration"));
+    }
+
+    #[test]
+    fn test_set_line_width() {
+        let options = hongdown_options_new();
+        assert_eq!(
+            unsafe { hongdown_options_set_line_width(options, 5) },
+            HongdownStatus::InvalidOptions
+        );
+        assert_eq!(
+            unsafe { hongdown_options_set_line_width(options, 100) },
+            HongdownStatus::Ok
+        );
+        assert_eq!(unsafe { (*options).options.line_width.get() }, 100);
+        unsafe { hongdown_options_free(options) };
+    }
+
+    #[test]
+    fn test_format_null_arguments() {
+        let mut output = ptr::null_mut();
+        let status = unsafe { hongdown_format(ptr::null(), ptr::null(), &mut output) };
+        assert_eq!(status, HongdownStatus::NullPointer);
+        assert!(output.is_null());
+        assert_eq!(last_error(), "input must not be null.");
+
+        let status = unsafe { hongdown_format(c"".as_ptr(), ptr::null(), ptr::null_mut()) };
+        assert_eq!(status, HongdownStatus::NullPointer);
+    }
+
+    #[test]
+    fn test_version() {
+        let version = unsafe { CStr::from_ptr(hongdown_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    }
+}
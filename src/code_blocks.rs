@@ -0,0 +1,146 @@
+//! Extraction of the code blocks of a document.
+//!
+//! Doc-testing tools and snippet extractors need the code blocks of
+//! a document along with their languages.  [`code_blocks`] returns them
+//! from the same parse the formatter uses, with their languages determined
+//! the way code formatters are looked up.
+
+use std::ops::RangeInclusive;
+
+use comrak::Arena;
+use comrak::nodes::NodeValue;
+
+use crate::serializer::code_language;
+use crate::{FormatError, Options, guard, limits, parse_markdown, split_bom};
+
+/// A code block of a document, as returned by [`code_blocks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlockInfo {
+    /// The language of the code block: the first word of its info string,
+    /// or [`Options::default_language`] if it has none.  This is the
+    /// language [`Options::code_formatters`] are looked up by.  Empty if
+    /// neither is given.
+    pub language: String,
+    /// The whole info string, e.g., `rust title="main.rs"`.  Empty for
+    /// indented code blocks.
+    pub info: String,
+    /// The content of the code block, without its fences or indentation.
+    pub literal: String,
+    /// The 1-indexed lines the code block spans, including its fences.
+    pub line_range: RangeInclusive<usize>,
+    /// Whether the code block is fenced rather than indented.
+    pub fenced: bool,
+}
+
+/// Returns the code blocks of a document in order, including those within
+/// lists and block quotes.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{Options, code_blocks};
+///
+/// let input = "Example:\n\n~~~~ rust\nfn main() {}\n~~~~\n";
+/// let blocks = code_blocks(input, &Options::default()).unwrap();
+/// assert_eq!(blocks.len(), 1);
+/// assert_eq!(blocks[0].language, "rust");
+/// assert_eq!(blocks[0].literal, "fn main() {}\n");
+/// assert_eq!(blocks[0].line_range, 3..=5);
+/// ```
+pub fn code_blocks(input: &str, options: &Options) -> Result<Vec<CodeBlockInfo>, FormatError> {
+    let (_, input) = split_bom(input, options);
+    guard::catch(|| {
+        limits::check_input(input, &options.limits)?;
+        let arena = Arena::new();
+        let root = parse_markdown(&arena, input, &options.extensions);
+        limits::check_document(root, &options.limits)?;
+        let source_lines: Vec<&str> = input.lines().collect();
+        Ok(root
+            .descendants()
+            .filter_map(|node| {
+                let data = node.data.borrow();
+                let NodeValue::CodeBlock(ref code_block) = data.value else {
+                    return None;
+                };
+                // Comrak counts blank lines after an indented code block as
+                // part of it
+                let start = data.sourcepos.start.line;
+                let mut end = data.sourcepos.end.line;
+                while end > start
+                    && source_lines
+                        .get(end - 1)
+                        .is_some_and(|line| line.trim().is_empty())
+                {
+                    end -= 1;
+                }
+                Some(CodeBlockInfo {
+                    language: code_language(&code_block.info, options).to_string(),
+                    info: code_block.info.trim().to_string(),
+                    literal: code_block.literal.clone(),
+                    line_range: start..=end,
+                    fenced: code_block.fenced,
+                })
+            })
+            .collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_blocks() {
+        let input = "~~~~ python title=\"a.py\"
+print(1)
+~~~~
+
+    indented
+    code
+
+ -  Item:
+
+    ```
+    plain
+    ```
+";
+        let blocks = code_blocks(input, &Options::default()).unwrap();
+        assert_eq!(
+            blocks,
+            vec![
+                CodeBlockInfo {
+                    language: "python".to_string(),
+                    info: "python title=\"a.py\"".to_string(),
+                    literal: "print(1)\n".to_string(),
+                    line_range: 1..=3,
+                    fenced: true,
+                },
+                CodeBlockInfo {
+                    language: String::new(),
+                    info: String::new(),
+                    literal: "indented\ncode\n".to_string(),
+                    line_range: 5..=6,
+                    fenced: false,
+                },
+                CodeBlockInfo {
+                    language: String::new(),
+                    info: String::new(),
+                    literal: "plain\n".to_string(),
+                    line_range: 10..=12,
+                    fenced: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_default_language() {
+        let options = Options {
+            default_language: "text".to_string(),
+            ..Options::default()
+        };
+        let blocks = code_blocks("~~~~\nplain\n~~~~\n", &options).unwrap();
+        assert_eq!(blocks[0].language, "text");
+        assert_eq!(blocks[0].info, "");
+    }
+}
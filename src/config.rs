@@ -3,16 +3,30 @@
 //! Configuration file support for Hongdown.
 //!
 //! This module provides functionality for loading and parsing configuration
-//! files (`.hongdown.toml`) that control the formatter's behavior.
+//! files (`.hongdown.toml`, `.hongdown.json`, or `.hongdown.yaml`) that
+//! control the formatter's behavior.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
+use crate::{CodeFormatter, Extensions, Limits, Options, RULES, WarningCode};
+
 /// The default configuration file name.
 pub const CONFIG_FILE_NAME: &str = ".hongdown.toml";
 
+/// The configuration file names searched for by [`Config::discover`], in
+/// order of precedence.
+pub const CONFIG_FILE_NAMES: &[&str] = &[
+    CONFIG_FILE_NAME,
+    ".hongdown.json",
+    "hongdown.config.json",
+    ".hongdown.yaml",
+    ".hongdown.yml",
+];
+
 /// Default value for `git_aware` (true).
 fn default_git_aware() -> bool {
     true
@@ -25,6 +39,83 @@ pub struct Config {
     /// Maximum line width for wrapping (default: 80).
     pub line_width: LineWidth,
 
+    /// Unicode normalization form applied to prose text (default: none).
+    pub unicode_normalization: UnicodeNormalization,
+
+    /// What to do with a byte order mark at the start of a file (default:
+    /// strip).
+    pub bom: ByteOrderMark,
+
+    /// Line ending of the output (default: LF).
+    pub line_ending: LineEnding,
+
+    /// Strip trailing whitespace from every line except hard line breaks
+    /// (default: false).
+    pub strip_trailing_whitespace: bool,
+
+    /// Whether the output ends with a newline (default: always).
+    pub final_newline: FinalNewline,
+
+    /// Width of a tab stop in columns (default: 4).
+    pub tab_width: TabWidth,
+
+    /// Expand tabs in prose to spaces (default: false).
+    pub expand_tabs: bool,
+
+    /// Maximum number of consecutive blank lines kept between top-level
+    /// blocks (default: 1).
+    pub max_blank_lines: MaxBlankLines,
+
+    /// Escape underscores in the middle of words, e.g., `snake\_case`
+    /// (default: true).
+    pub escape_underscores: bool,
+
+    /// Renumber purely numeric reference labels, e.g., `[1]`, in the order
+    /// of their first use (default: false).
+    pub renumber_references: bool,
+
+    /// The code hosting service whose shorthand references, e.g., `#123`,
+    /// are kept intact: `github`, `gitlab`, or `none` (default: `github`).
+    pub autolink_host: AutolinkHost,
+
+    /// How `@username` mentions in prose are written: `keep`, `escape`, or
+    /// `code` (default: `keep`).
+    pub mentions: MentionStyle,
+
+    /// How the titles of links and images are quoted: `double`, `single`, or
+    /// `paren` (default: `double`).
+    pub link_title_style: LinkTitleStyle,
+
+    /// How reference links whose text is their label are written: `shortcut`
+    /// (`[text]`) or `collapsed` (`[text][]`) (default: `shortcut`).
+    pub link_reference_style: LinkReferenceStyle,
+
+    /// How reference labels, which match case-insensitively, are cased:
+    /// `preserve` (as first used) or `lower` (default: `preserve`).
+    pub reference_label_case: ReferenceLabelCase,
+
+    /// Percent-encode spaces and other unsafe characters in link and image
+    /// destinations, lowercase their scheme and host, and remove default
+    /// ports (default: false).
+    pub normalize_urls: bool,
+
+    /// A base path that relative link and image destinations are joined
+    /// against, e.g., `docs` (default: empty, meaning no rebasing).
+    pub link_base: String,
+
+    /// A set of conventions for a kind of document: `default` or `changelog`
+    /// (default: `default`).
+    pub profile: Profile,
+
+    /// Re-parse the output and warn where its inline content differs from
+    /// the input's, which suggests missing or excessive escaping (default:
+    /// false).
+    pub verify_escaping: bool,
+
+    /// Format the output once more and warn if that changes it (default:
+    /// false).
+    pub verify_stability: bool,
+
     /// Glob patterns for files to include (default: empty, meaning all files
     /// must be specified on command line).
     pub include: Vec<String>,
@@ -36,6 +127,11 @@ pub struct Config {
     #[serde(default = "default_git_aware")]
     pub git_aware: bool,
 
+    /// Warnings that make the command fail even if all files are formatted:
+    /// `true` for all warnings, or rule identifiers, codes, or kinds, e.g.,
+    /// `["HD001", "tables"]` (default: none).
+    pub warnings_as_errors: WarningsAsErrors,
+
     /// Heading formatting options.
     pub heading: HeadingConfig,
 
@@ -45,33 +141,630 @@ pub struct Config {
     /// Ordered list formatting options.
     pub ordered_list: OrderedListConfig,
 
+    /// Definition list formatting options.
+    pub definition_list: DefinitionListConfig,
+
+    /// Abbreviation definition formatting options.
+    pub abbreviation: AbbreviationConfig,
+
+    /// Front matter formatting options.
+    pub front_matter: FrontMatterConfig,
+
+    /// Badge formatting options.
+    pub badge: BadgeConfig,
+
+    /// Image formatting options.
+    pub image: ImageConfig,
+
+    /// Figure formatting options.
+    pub figure: FigureConfig,
+
     /// Code block formatting options.
     pub code_block: CodeBlockConfig,
 
     /// Thematic break (horizontal rule) formatting options.
     pub thematic_break: ThematicBreakConfig,
 
+    /// Table formatting options.
+    pub table: TableConfig,
+
+    /// Alert (callout) formatting options.
+    pub alert: AlertConfig,
+
     /// Punctuation transformation options (SmartyPants-style).
     pub punctuation: PunctuationConfig,
+
+    /// Limits on the input accepted by the formatter (default: no limits).
+    pub limits: Limits,
+
+    /// Markdown extensions to recognize (default: all enabled).
+    pub extensions: Extensions,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             line_width: LineWidth::default(),
+            unicode_normalization: UnicodeNormalization::default(),
+            bom: ByteOrderMark::default(),
+            line_ending: LineEnding::default(),
+            strip_trailing_whitespace: false,
+            final_newline: FinalNewline::default(),
+            tab_width: TabWidth::default(),
+            expand_tabs: false,
+            max_blank_lines: MaxBlankLines::default(),
+            escape_underscores: true,
+            renumber_references: false,
+            autolink_host: AutolinkHost::default(),
+            mentions: MentionStyle::default(),
+            link_title_style: LinkTitleStyle::default(),
+            link_reference_style: LinkReferenceStyle::default(),
+            reference_label_case: ReferenceLabelCase::default(),
+            normalize_urls: false,
+            link_base: String::new(),
+            profile: Profile::default(),
+            verify_escaping: false,
+            verify_stability: false,
             include: Vec::new(),
             exclude: Vec::new(),
             git_aware: true,
+            warnings_as_errors: WarningsAsErrors::default(),
             heading: HeadingConfig::default(),
             unordered_list: UnorderedListConfig::default(),
             ordered_list: OrderedListConfig::default(),
+            definition_list: DefinitionListConfig::default(),
+            abbreviation: AbbreviationConfig::default(),
+            front_matter: FrontMatterConfig::default(),
+            badge: BadgeConfig::default(),
+            image: ImageConfig::default(),
+            figure: FigureConfig::default(),
             code_block: CodeBlockConfig::default(),
             thematic_break: ThematicBreakConfig::default(),
+            table: TableConfig::default(),
+            alert: AlertConfig::default(),
             punctuation: PunctuationConfig::default(),
+            limits: Limits::default(),
+            extensions: Extensions::default(),
+        }
+    }
+}
+
+/// Unicode normalization form applied to prose text.
+///
+/// Documents edited on different systems may spell the same accented
+/// characters differently, e.g., macOS tends to decompose them (NFD) while
+/// Linux composes them (NFC).  Normalizing prose text makes the output the
+/// same bytes either way.  Code spans and code blocks are never normalized.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeNormalization {
+    /// Leave text as is (default).
+    #[default]
+    None,
+    /// Canonical composition (NFC).
+    Nfc,
+    /// Compatibility composition (NFKC), which also replaces compatibility
+    /// characters, e.g., full-width letters and ligatures.
+    Nfkc,
+}
+
+impl UnicodeNormalization {
+    /// Get the string representation of this form, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Nfc => "nfc",
+            Self::Nfkc => "nfkc",
+        }
+    }
+
+    /// Normalize `text` to this form.
+    pub(crate) fn apply(self, text: &str) -> Cow<'_, str> {
+        use unicode_normalization::{
+            IsNormalized, UnicodeNormalization as _, is_nfc_quick, is_nfkc_quick,
+        };
+        match self {
+            Self::None => Cow::Borrowed(text),
+            Self::Nfc if is_nfc_quick(text.chars()) == IsNormalized::Yes => Cow::Borrowed(text),
+            Self::Nfc => Cow::Owned(text.nfc().collect()),
+            Self::Nfkc if is_nfkc_quick(text.chars()) == IsNormalized::Yes => Cow::Borrowed(text),
+            Self::Nfkc => Cow::Owned(text.nfkc().collect()),
+        }
+    }
+}
+
+/// The code hosting service whose shorthand references, e.g., `#123` or
+/// `owner/repo#123`, are linked automatically and so should be kept intact.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AutolinkHost {
+    /// No shorthand references are recognized.
+    None,
+    /// GitHub: `#123`, `GH-123`, `owner/repo#123`, `owner/repo@sha`, and
+    /// commit SHAs (default).
+    #[default]
+    GitHub,
+    /// GitLab: like GitHub, but also `!123` for merge requests and nested
+    /// groups, e.g., `group/subgroup/project#123`.
+    GitLab,
+}
+
+impl AutolinkHost {
+    /// Get the string representation of this host, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::GitHub => "github",
+            Self::GitLab => "gitlab",
+        }
+    }
+}
+
+/// How `@username` mentions in prose are written.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MentionStyle {
+    /// Leave mentions as they are (default).
+    #[default]
+    Keep,
+    /// Escape the `@`, e.g., `\@username`, so that the mention does not
+    /// notify anyone when the text is pasted into an issue.
+    Escape,
+    /// Wrap mentions in code spans, e.g., `` `@username` ``.
+    Code,
+}
+
+impl MentionStyle {
+    /// Get the string representation of this style, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Keep => "keep",
+            Self::Escape => "escape",
+            Self::Code => "code",
+        }
+    }
+}
+
+/// How reference links and images whose text is their label are written.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkReferenceStyle {
+    /// Shortcut references, e.g., `[text]` (default).  Collapsed references
+    /// in the source are kept as they are.
+    #[default]
+    Shortcut,
+    /// Collapsed references, e.g., `[text][]`, which cannot be mistaken for
+    /// anything else when followed by brackets.
+    Collapsed,
+}
+
+impl LinkReferenceStyle {
+    /// Get the string representation of this style, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Shortcut => "shortcut",
+            Self::Collapsed => "collapsed",
+        }
+    }
+}
+
+/// How reference labels are cased.  Labels differing only in case refer to
+/// the same definition either way.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceLabelCase {
+    /// Keep labels as they are written, defining each reference with the
+    /// label it is first used with (default).
+    #[default]
+    Preserve,
+    /// Lowercase labels, e.g., `[Docs][API]` becomes `[Docs][api]`.  The text
+    /// of shortcut references is kept as it is.
+    Lower,
+}
+
+impl ReferenceLabelCase {
+    /// Get the string representation of this casing, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Preserve => "preserve",
+            Self::Lower => "lower",
         }
     }
 }
 
+/// How the titles of links and images are quoted.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkTitleStyle {
+    /// Double quotes, e.g., `"Title"` (default).
+    #[default]
+    Double,
+    /// Single quotes, e.g., `'Title'`.
+    Single,
+    /// Parentheses, e.g., `(Title)`.
+    Paren,
+}
+
+impl LinkTitleStyle {
+    /// Get the string representation of this style, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Double => "double",
+            Self::Single => "single",
+            Self::Paren => "paren",
+        }
+    }
+
+    /// Quote `title`, escaping the characters that would end it early.
+    pub fn quote(self, title: &str) -> String {
+        let (open, close) = match self {
+            Self::Double => ('"', '"'),
+            Self::Single => ('\'', '\''),
+            Self::Paren => ('(', ')'),
+        };
+        let mut quoted = String::with_capacity(title.len() + 2);
+        quoted.push(open);
+        let mut chars = title.chars().peekable();
+        while let Some(ch) = chars.next() {
+            // A backslash would escape the punctuation after it, including
+            // the closing quote
+            let escapes_next =
+                ch == '\\' && chars.peek().is_none_or(|next| next.is_ascii_punctuation());
+            if ch == open || ch == close || escapes_next {
+                quoted.push('\\');
+            }
+            quoted.push(ch);
+        }
+        quoted.push(close);
+        quoted
+    }
+}
+
+/// A set of conventions for a kind of document.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// No particular conventions (default).
+    #[default]
+    Default,
+    /// Conventions of changelogs following Keep a Changelog: headings of
+    /// version sections are kept as they are, and the reference definitions
+    /// of their links are kept at the bottom, newest first.
+    Changelog,
+}
+
+impl Profile {
+    /// Get the string representation of this profile, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Changelog => "changelog",
+        }
+    }
+}
+
+/// What to do with a UTF-8 byte order mark at the start of a document.
+///
+/// The byte order mark is never part of the Markdown: it is set aside
+/// before parsing either way, so that it cannot affect the first block.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ByteOrderMark {
+    /// Remove the byte order mark (default).
+    #[default]
+    Strip,
+    /// Keep the byte order mark if the input has one.
+    Preserve,
+}
+
+impl ByteOrderMark {
+    /// Get the string representation of this policy, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Strip => "strip",
+            Self::Preserve => "preserve",
+        }
+    }
+}
+
+/// Line ending of the output.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// Use the line ending of the first line of the input, or LF if it has
+    /// only one line.
+    Preserve,
+    /// Unix-style `\n` (default).
+    #[default]
+    Lf,
+    /// Windows-style `\r\n`.
+    Crlf,
+}
+
+impl LineEnding {
+    /// Get the string representation of this line ending, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Preserve => "preserve",
+            Self::Lf => "lf",
+            Self::Crlf => "crlf",
+        }
+    }
+
+    /// Convert every line ending of `output`, formatted from `input`, to
+    /// this one.
+    pub(crate) fn apply(self, input: &str, output: String) -> String {
+        let crlf = match self {
+            Self::Preserve => input
+                .find('\n')
+                .is_some_and(|end| input[..end].ends_with('\r')),
+            Self::Lf => false,
+            Self::Crlf => true,
+        };
+        // Verbatim blocks such as HTML keep the line endings of the input
+        let output = if output.contains('\r') {
+            output.replace("\r\n", "\n")
+        } else {
+            output
+        };
+        if crlf {
+            output.replace('\n', "\r\n")
+        } else {
+            output
+        }
+    }
+}
+
+/// Whether the output ends with a newline.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FinalNewline {
+    /// End with exactly one newline (default).
+    #[default]
+    Always,
+    /// End without a newline.
+    Never,
+    /// End with exactly one newline if the input ends with one, and without
+    /// one otherwise.
+    Preserve,
+}
+
+impl FinalNewline {
+    /// Get the string representation of this policy, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::Never => "never",
+            Self::Preserve => "preserve",
+        }
+    }
+
+    /// Make `output`, formatted from `input`, end as this policy says.
+    /// Empty output stays empty.
+    pub(crate) fn apply(self, input: &str, mut output: String) -> String {
+        let newline = match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Preserve => input.ends_with('\n'),
+        };
+        let len = output.trim_end_matches(['\n', '\r']).len();
+        output.truncate(len);
+        if newline && !output.is_empty() {
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Warnings that are treated as errors, i.e., make the command fail.
+///
+/// In the configuration file, either a boolean or an array of rule
+/// identifiers, e.g., `HD001`, codes, e.g., `undefined-reference`, or kinds,
+/// e.g., `references`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum WarningsAsErrors {
+    /// No warnings are errors (default).
+    #[default]
+    None,
+    /// All warnings are errors.
+    All,
+    /// Warnings of the given rules or kinds are errors.
+    Rules(Vec<String>),
+}
+
+impl WarningsAsErrors {
+    /// Whether warnings with the given code are errors.
+    pub fn contains(&self, code: WarningCode) -> bool {
+        match self {
+            Self::None => false,
+            Self::All => true,
+            Self::Rules(names) => names.iter().any(|name| rule_matches(name, code)),
+        }
+    }
+}
+
+/// Whether `name` is the identifier, code, or kind of the rule of `code`.
+fn rule_matches(name: &str, code: WarningCode) -> bool {
+    let rule = code.rule();
+    rule.id.eq_ignore_ascii_case(name)
+        || rule.code.as_str().eq_ignore_ascii_case(name)
+        || code.kind().as_str().eq_ignore_ascii_case(name)
+}
+
+impl<'de> serde::Deserialize<'de> for WarningsAsErrors {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            All(bool),
+            Rules(Vec<String>),
+        }
+
+        match Value::deserialize(deserializer).map_err(|_| {
+            serde::de::Error::custom(
+                "warnings_as_errors must be a boolean or an array of rule \
+                 identifiers, codes, or kinds.",
+            )
+        })? {
+            Value::All(true) => Ok(Self::All),
+            Value::All(false) => Ok(Self::None),
+            Value::Rules(names) => {
+                if let Some(name) = names.iter().find(|name| {
+                    !RULES
+                        .iter()
+                        .any(|rule| rule_matches(name.as_str(), rule.code))
+                }) {
+                    return Err(serde::de::Error::custom(format!(
+                        "unknown rule or kind in warnings_as_errors: {:?}; run \
+                         `hongdown explain` to list all rules.",
+                        name
+                    )));
+                }
+                Ok(Self::Rules(names))
+            }
+        }
+    }
+}
+
+/// Length of the underlines of setext headings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SetextUnderline {
+    /// As long as the heading text is wide (default).
+    #[default]
+    Match,
+    /// Up to the line width, or as long as the heading text if that is wider.
+    FullWidth,
+    /// A fixed number of characters, regardless of the heading text.
+    Fixed(usize),
+}
+
+impl SetextUnderline {
+    /// Parse the name of a mode, either `match` or `full-width`.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "match" => Ok(Self::Match),
+            "full-width" => Ok(Self::FullWidth),
+            _ => Err(format!(
+                "setext_underline must be \"match\", \"full-width\", or a number, got {:?}.",
+                name
+            )),
+        }
+    }
+
+    /// Create a fixed underline length, which must be at least 1.
+    pub fn fixed(length: usize) -> Result<Self, String> {
+        if length < 1 {
+            return Err(format!(
+                "setext_underline must be at least 1, got {}.",
+                length
+            ));
+        }
+        Ok(Self::Fixed(length))
+    }
+
+    /// Returns the number of underline characters for a heading whose text
+    /// is `text_width` wide and starts at `column`.
+    pub(crate) fn length(self, text_width: usize, column: usize, line_width: usize) -> usize {
+        match self {
+            Self::Match => text_width,
+            Self::FullWidth => line_width.saturating_sub(column).max(text_width),
+            Self::Fixed(length) => length,
+        }
+    }
+}
+
+/// How the heading text is measured for the length of setext underlines.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnderlineMeasure {
+    /// By display width, so that wide characters, e.g., CJK ones, count as
+    /// two columns (default).
+    #[default]
+    DisplayWidth,
+    /// By the number of characters.
+    Characters,
+}
+
+impl UnderlineMeasure {
+    /// Get the string representation of this measure, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DisplayWidth => "display-width",
+            Self::Characters => "characters",
+        }
+    }
+
+    /// Returns the width of `text` under this measure.
+    pub(crate) fn measure(self, text: &str) -> usize {
+        match self {
+            Self::DisplayWidth => crate::serializer::wrap::display_width(text),
+            Self::Characters => text.chars().count(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SetextUnderline {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::{self, Visitor};
+
+        struct SetextUnderlineVisitor;
+
+        impl<'de> Visitor<'de> for SetextUnderlineVisitor {
+            type Value = SetextUnderline;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("\"match\", \"full-width\", or a number")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<SetextUnderline, E>
+            where
+                E: de::Error,
+            {
+                SetextUnderline::from_name(value).map_err(de::Error::custom)
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<SetextUnderline, E>
+            where
+                E: de::Error,
+            {
+                let length = usize::try_from(value).map_err(|_| {
+                    de::Error::custom(format!(
+                        "setext_underline must be at least 1, got {}.",
+                        value
+                    ))
+                })?;
+                SetextUnderline::fixed(length).map_err(de::Error::custom)
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<SetextUnderline, E>
+            where
+                E: de::Error,
+            {
+                SetextUnderline::fixed(value as usize).map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(SetextUnderlineVisitor)
+    }
+}
+
 /// Heading formatting options.
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 #[serde(default)]
@@ -82,18 +775,129 @@ pub struct HeadingConfig {
     /// Use `---` underline for h2 (default: true).
     pub setext_h2: bool,
 
+    /// Length of setext underlines: `match` the heading text, `full-width`
+    /// up to the line width, or a fixed number (default: `match`).
+    pub setext_underline: SetextUnderline,
+
+    /// How the heading text is measured for `setext_underline`:
+    /// `display-width` or `characters` (default: `display-width`).
+    pub setext_underline_measure: UnderlineMeasure,
+
     /// Convert headings to sentence case (default: false).
     pub sentence_case: bool,
 
+    /// Convert headings to title case in the given style: `none`, `simple`,
+    /// `ap`, or `chicago` (default: `none`).  Ignored when `sentence_case`
+    /// is enabled.
+    pub title_case: TitleCase,
+
     /// Additional proper nouns to preserve (case-sensitive).
     /// These are merged with built-in proper nouns.
     pub proper_nouns: Vec<String>,
 
+    /// A file of additional proper nouns, one per line, relative to
+    /// the configuration file.  Blank lines and lines starting with `#` are
+    /// ignored.  The nouns are added to `proper_nouns` when the configuration
+    /// is loaded with [`Config::from_file`].
+    pub proper_nouns_file: Option<PathBuf>,
+
+    /// Dictionaries of additional proper nouns: names of bundled
+    /// dictionaries (see [`BUNDLED_DICTIONARIES`]) or paths of files like
+    /// `proper_nouns_file`.  Nouns of later dictionaries override those of
+    /// earlier ones with the same letters, and are overridden in turn by
+    /// `proper_nouns_file` and `proper_nouns`.  They are added to
+    /// `proper_nouns` when the configuration is loaded with
+    /// [`Config::from_file`].
+    pub dictionaries: Vec<String>,
+
     /// Words to treat as common nouns (case-sensitive).
     /// These are excluded from built-in proper nouns.
     /// Useful for words like "Go" which can be either a programming language
     /// or a common verb depending on context.
     pub common_nouns: Vec<String>,
+
+    /// Blank lines before top-level headings of each level, from h1 to h6
+    /// (default: `[1, 2, 1, 1, 1, 1]`).
+    pub blank_lines_before: HeadingBlankLines,
+
+    /// Write a single blank line between a heading and a heading right
+    /// after it, regardless of `blank_lines_before` (default: true).
+    pub collapse_consecutive: bool,
+
+    /// Blank lines between a top-level heading and the block after it,
+    /// unless that is a heading as well (default: 1).
+    pub blank_lines_after: usize,
+
+    /// What to do with headings wider than the line width: `keep`, `warn`,
+    /// `atx`, or `wrap`, either for all levels or as an array of six, one
+    /// for each level (default: `keep`).
+    pub overlong: OverlongHeadings,
+
+    /// How links inside headings are written: `reference`, `inline`, or
+    /// `text` (default: `reference`).
+    pub links: HeadingLinks,
+}
+
+/// Dictionaries of proper nouns bundled with Hongdown, by name.
+pub const BUNDLED_DICTIONARIES: &[(&str, &str)] = &[
+    (
+        "fediverse",
+        include_str!("../data/dictionaries/fediverse.txt"),
+    ),
+    ("rust", include_str!("../data/dictionaries/rust.txt")),
+    ("tech", include_str!("../data/dictionaries/tech.txt")),
+];
+
+impl HeadingConfig {
+    /// Merge the nouns of [`Self::dictionaries`] and
+    /// [`Self::proper_nouns_file`], with files resolved against `base_dir`,
+    /// into [`Self::proper_nouns`].
+    pub fn load_dictionaries(&mut self, base_dir: &Path) -> Result<(), ConfigError> {
+        let read = |file: &Path| {
+            let path = base_dir.join(file);
+            std::fs::read_to_string(&path).map_err(|e| ConfigError::Io(path, e))
+        };
+        let mut nouns = Vec::new();
+        for source in &self.dictionaries {
+            let bundled = BUNDLED_DICTIONARIES
+                .iter()
+                .find(|(name, _)| name == source)
+                .map(|(_, content)| *content);
+            let list = match bundled {
+                Some(content) => parse_noun_list(content),
+                None => parse_noun_list(&read(Path::new(source))?),
+            };
+            merge_nouns(&mut nouns, list);
+        }
+        if let Some(file) = &self.proper_nouns_file {
+            merge_nouns(&mut nouns, parse_noun_list(&read(file)?));
+        }
+        merge_nouns(&mut nouns, std::mem::take(&mut self.proper_nouns));
+        self.proper_nouns = nouns;
+        Ok(())
+    }
+}
+
+/// Add `additions` to `nouns`, replacing nouns with the same letters
+/// regardless of case.
+fn merge_nouns(nouns: &mut Vec<String>, additions: Vec<String>) {
+    for noun in additions {
+        let key = noun.to_lowercase();
+        nouns.retain(|existing| existing.to_lowercase() != key);
+        nouns.push(noun);
+    }
+}
+
+/// Parse a list of nouns, one per line.  Blank lines and lines starting with
+/// `#` are skipped.  Comments cannot follow a noun on the same line, since
+/// nouns such as `C#` may contain `#`.
+fn parse_noun_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
 }
 
 impl Default for HeadingConfig {
@@ -101,9 +905,210 @@ impl Default for HeadingConfig {
         Self {
             setext_h1: true,
             setext_h2: true,
+            setext_underline: SetextUnderline::default(),
+            setext_underline_measure: UnderlineMeasure::default(),
             sentence_case: false,
+            title_case: TitleCase::default(),
             proper_nouns: Vec::new(),
+            proper_nouns_file: None,
+            dictionaries: Vec::new(),
             common_nouns: Vec::new(),
+            blank_lines_before: HeadingBlankLines::default(),
+            collapse_consecutive: true,
+            blank_lines_after: 1,
+            overlong: OverlongHeadings::default(),
+            links: HeadingLinks::default(),
+        }
+    }
+}
+
+/// Numbers of blank lines before headings, one for each level from h1 to h6
+/// (each must be at least 1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadingBlankLines([usize; 6]);
+
+impl HeadingBlankLines {
+    /// Minimum allowed number of blank lines.
+    pub const MIN: usize = 1;
+
+    /// Create a new HeadingBlankLines.
+    ///
+    /// Returns an error if any value is less than 1.
+    pub fn new(values: [usize; 6]) -> Result<Self, String> {
+        match values.iter().find(|&&value| value < Self::MIN) {
+            Some(value) => Err(format!(
+                "blank_lines_before must be at least {}, got {}.",
+                Self::MIN,
+                value
+            )),
+            None => Ok(Self(values)),
+        }
+    }
+
+    /// Get the number of blank lines before headings of the given level.
+    pub fn get(self, level: u8) -> usize {
+        self.0[usize::from(level.clamp(1, 6)) - 1]
+    }
+
+    /// Get the inner values.
+    pub fn values(self) -> [usize; 6] {
+        self.0
+    }
+}
+
+impl Default for HeadingBlankLines {
+    fn default() -> Self {
+        Self([1, 2, 1, 1, 1, 1])
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for HeadingBlankLines {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let values = <[usize; 6]>::deserialize(deserializer)?;
+        Self::new(values).map_err(serde::de::Error::custom)
+    }
+}
+
+/// How links inside headings are written.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadingLinks {
+    /// Convert external links to reference links, like in paragraphs
+    /// (default).
+    #[default]
+    Reference,
+    /// Keep inline links inline.
+    Inline,
+    /// Replace links with their text.
+    Text,
+}
+
+impl HeadingLinks {
+    /// Get the string representation of this policy, as used in
+    /// the configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reference => "reference",
+            Self::Inline => "inline",
+            Self::Text => "text",
+        }
+    }
+}
+
+/// Which convention to follow when converting headings to title case.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TitleCase {
+    /// Keep the case of headings as it is (default).
+    #[default]
+    None,
+    /// Capitalize every word.
+    Simple,
+    /// Associated Press style: lowercase articles, conjunctions, and
+    /// prepositions of up to three letters.
+    Ap,
+    /// Chicago style: lowercase articles, coordinating conjunctions, and
+    /// all prepositions, including those after a hyphen.
+    Chicago,
+}
+
+impl TitleCase {
+    /// Get the string representation of this style, as used in
+    /// the configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Simple => "simple",
+            Self::Ap => "ap",
+            Self::Chicago => "chicago",
+        }
+    }
+}
+
+/// What to do with a heading that does not fit within the line width.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlongHeading {
+    /// Keep the heading on a single line (default).
+    #[default]
+    Keep,
+    /// Keep the heading on a single line, but report a warning.
+    Warn,
+    /// Write a setext heading as an ATX heading instead, which avoids
+    /// an equally long underline.
+    Atx,
+    /// Wrap a setext heading over multiple lines.  ATX headings cannot span
+    /// lines, so they are kept as is.
+    Wrap,
+}
+
+impl OverlongHeading {
+    /// Get the string representation of this policy, as used in
+    /// the configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Keep => "keep",
+            Self::Warn => "warn",
+            Self::Atx => "atx",
+            Self::Wrap => "wrap",
+        }
+    }
+}
+
+/// Policies for overlong headings, one for each level from h1 to h6.
+///
+/// In the configuration file, either a single policy for all levels or
+/// an array of six policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OverlongHeadings([OverlongHeading; 6]);
+
+impl OverlongHeadings {
+    /// Create a new OverlongHeadings from a policy for each level.
+    pub fn new(values: [OverlongHeading; 6]) -> Self {
+        Self(values)
+    }
+
+    /// Get the policy for headings of the given level.
+    pub fn get(self, level: u8) -> OverlongHeading {
+        self.0[usize::from(level.clamp(1, 6)) - 1]
+    }
+
+    /// Get the inner values.
+    pub fn values(self) -> [OverlongHeading; 6] {
+        self.0
+    }
+}
+
+impl From<OverlongHeading> for OverlongHeadings {
+    /// Use the same policy for all levels.
+    fn from(policy: OverlongHeading) -> Self {
+        Self([policy; 6])
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for OverlongHeadings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            All(OverlongHeading),
+            PerLevel([OverlongHeading; 6]),
+        }
+
+        match Value::deserialize(deserializer).map_err(|_| {
+            serde::de::Error::custom(
+                "overlong must be \"keep\", \"warn\", \"atx\", or \"wrap\", \
+                 or an array of six of them.",
+            )
+        })? {
+            Value::All(policy) => Ok(policy.into()),
+            Value::PerLevel(values) => Ok(Self(values)),
         }
     }
 }
@@ -322,6 +1327,97 @@ impl<'de> serde::Deserialize<'de> for LineWidth {
     }
 }
 
+/// Width of a tab stop in columns (must be at least 1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TabWidth(usize);
+
+impl TabWidth {
+    /// Minimum allowed tab width.
+    pub const MIN: usize = 1;
+
+    /// Create a new TabWidth.
+    ///
+    /// Returns an error if the value is less than 1.
+    pub fn new(value: usize) -> Result<Self, String> {
+        if value < Self::MIN {
+            Err(format!(
+                "tab_width must be at least {}, got {}.",
+                Self::MIN,
+                value
+            ))
+        } else {
+            Ok(Self(value))
+        }
+    }
+
+    /// Get the inner value.
+    pub fn get(self) -> usize {
+        self.0
+    }
+}
+
+impl Default for TabWidth {
+    fn default() -> Self {
+        Self(4)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TabWidth {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = usize::deserialize(deserializer)?;
+        Self::new(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Maximum number of consecutive blank lines kept between top-level blocks
+/// (must be at least 1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxBlankLines(usize);
+
+impl MaxBlankLines {
+    /// Minimum allowed number of blank lines.
+    pub const MIN: usize = 1;
+
+    /// Create a new MaxBlankLines.
+    ///
+    /// Returns an error if the value is less than 1.
+    pub fn new(value: usize) -> Result<Self, String> {
+        if value < Self::MIN {
+            Err(format!(
+                "max_blank_lines must be at least {}, got {}.",
+                Self::MIN,
+                value
+            ))
+        } else {
+            Ok(Self(value))
+        }
+    }
+
+    /// Get the inner value.
+    pub fn get(self) -> usize {
+        self.0
+    }
+}
+
+impl Default for MaxBlankLines {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for MaxBlankLines {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = usize::deserialize(deserializer)?;
+        Self::new(value).map_err(serde::de::Error::custom)
+    }
+}
+
 /// Unordered list formatting options.
 #[derive(Debug, Clone, Deserialize, PartialEq, Default)]
 #[serde(default)]
@@ -400,8 +1496,249 @@ impl Default for OrderedListConfig {
     }
 }
 
+/// Spaces after the `:` marker of a definition (1-3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefinitionMarkerSpaces(usize);
+
+impl DefinitionMarkerSpaces {
+    /// Minimum allowed spaces, without which the marker is not a marker.
+    pub const MIN: usize = 1;
+
+    /// Maximum allowed spaces.
+    pub const MAX: usize = 3;
+
+    /// Create a new DefinitionMarkerSpaces.
+    ///
+    /// Returns an error if the value is not between 1 and 3.
+    pub fn new(value: usize) -> Result<Self, String> {
+        if (Self::MIN..=Self::MAX).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(format!(
+                "marker_spaces must be between {} and {}, got {}.",
+                Self::MIN,
+                Self::MAX,
+                value
+            ))
+        }
+    }
+
+    /// Get the inner value.
+    pub fn get(self) -> usize {
+        self.0
+    }
+}
+
+impl Default for DefinitionMarkerSpaces {
+    fn default() -> Self {
+        Self(3)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for DefinitionMarkerSpaces {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = usize::deserialize(deserializer)?;
+        Self::new(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Definition list formatting options.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DefinitionListConfig {
+    /// Spaces after the `:` marker of a definition (default: 3).
+    pub marker_spaces: DefinitionMarkerSpaces,
+
+    /// Indentation width for the content of a definition (default: 4).
+    pub indent_width: IndentWidth,
+}
+
+/// Where to place abbreviation definitions, e.g., `*[HTML]: HyperText Markup
+/// Language`.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AbbreviationPlacement {
+    /// Keep definitions where they are (default).
+    #[default]
+    Preserve,
+    /// Group the definitions of each section, sorted alphabetically, at the
+    /// end of the section, after its link reference definitions.
+    Section,
+    /// Group all definitions, sorted alphabetically, at the end of the
+    /// document.
+    Document,
+}
+
+impl AbbreviationPlacement {
+    /// Get the string representation of this placement, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Preserve => "preserve",
+            Self::Section => "section",
+            Self::Document => "document",
+        }
+    }
+}
+
+/// Abbreviation definition formatting options.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AbbreviationConfig {
+    /// Where to place abbreviation definitions: `preserve`, `section`, or
+    /// `document` (default: `preserve`).
+    pub placement: AbbreviationPlacement,
+}
+
+/// Front matter formatting options.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FrontMatterConfig {
+    /// Canonical order of the top-level keys of YAML front matter.  Listed
+    /// keys are moved to the front in this order, and the others follow in
+    /// their original order (default: `[]`, which keeps the order).
+    pub key_order: Vec<String>,
+
+    /// Whether to normalize the `date` and `updated` fields to
+    /// `date_format` or `datetime_format` (default: false).
+    pub normalize_dates: bool,
+
+    /// Format of normalized dates without a time, using `%Y`, `%m`, and `%d`
+    /// (default: `"%Y-%m-%d"`).
+    pub date_format: String,
+
+    /// Format of normalized dates with a time, which may also use `%H`, `%M`,
+    /// `%S`, and `%z` (default: `"%Y-%m-%dT%H:%M:%S%z"`).
+    pub datetime_format: String,
+}
+
+impl Default for FrontMatterConfig {
+    fn default() -> Self {
+        Self {
+            key_order: Vec::new(),
+            normalize_dates: false,
+            date_format: "%Y-%m-%d".to_string(),
+            datetime_format: "%Y-%m-%dT%H:%M:%S%z".to_string(),
+        }
+    }
+}
+
+/// How a paragraph of badges, i.e., links around images like
+/// `[![CI][CI badge]][CI]`, is laid out.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BadgeLayout {
+    /// Format badges like any other links (default).
+    #[default]
+    Preserve,
+    /// Put all badges of a paragraph on one line.
+    Line,
+    /// Put each badge on its own line.
+    Stack,
+}
+
+impl BadgeLayout {
+    /// Get the string representation of this layout, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Preserve => "preserve",
+            Self::Line => "line",
+            Self::Stack => "stack",
+        }
+    }
+}
+
+/// Badge formatting options.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BadgeConfig {
+    /// How paragraphs of badges are laid out: `preserve`, `line`, or
+    /// `stack` (default: `preserve`).  Unless `preserve`, badges also get
+    /// reference labels named after their alt text, e.g.,
+    /// `[![CI][CI badge]][CI]`.
+    pub layout: BadgeLayout,
+}
+
+/// What to do with HTML `<img>` elements that have attributes other than
+/// `src`, `alt`, and `title`, e.g., `width`, when converting them to
+/// Markdown images.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HtmlImageAttributes {
+    /// Leave such elements as they are (default).
+    #[default]
+    Skip,
+    /// Convert them, and keep the other attributes in an attribute block,
+    /// e.g., `![alt](src){width="100"}`.
+    Preserve,
+}
+
+impl HtmlImageAttributes {
+    /// Get the string representation of this policy, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Preserve => "preserve",
+        }
+    }
+}
+
+/// Image formatting options.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ImageConfig {
+    /// Convert HTML `<img>` elements into Markdown images, e.g.,
+    /// `<img src="a.png" alt="A">` into `![A](a.png)` (default: false).
+    pub convert_html: bool,
+
+    /// What to do with `<img>` elements that have other attributes, e.g.,
+    /// `width`: `skip` or `preserve` (default: `skip`).
+    pub html_attributes: HtmlImageAttributes,
+}
+
+/// How figures, i.e., images with a caption, are written.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FigureStyle {
+    /// Keep figures in the syntax they are written in (default).
+    #[default]
+    Preserve,
+    /// Write simple HTML `<figure>` elements as an image followed by an
+    /// emphasized caption on the next line.
+    Markdown,
+    /// Write images followed by an emphasized caption on the next line as
+    /// HTML `<figure>` elements.
+    Html,
+}
+
+impl FigureStyle {
+    /// Get the string representation of this style, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Preserve => "preserve",
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// Figure formatting options.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FigureConfig {
+    /// How figures are written: `preserve`, `markdown`, or `html` (default:
+    /// `preserve`).
+    pub style: FigureStyle,
+}
+
 /// Default timeout for external formatters in seconds.
-fn default_formatter_timeout() -> u64 {
+pub(crate) fn default_formatter_timeout() -> u64 {
     5
 }
 
@@ -538,11 +1875,35 @@ pub struct CodeBlockConfig {
     /// Set to e.g. "text" to add a default language identifier.
     pub default_language: String,
 
+    /// Warn about lines of code blocks wider than `line_width` (default:
+    /// false).
+    pub warn_long_lines: bool,
+
+    /// Line width for `warn_long_lines` (default: the top-level
+    /// `line_width`).
+    pub line_width: Option<LineWidth>,
+
+    /// Tab widths to expand leading tabs in code blocks with, by language
+    /// (default: none).
+    ///
+    /// Key: language identifier (exact match only).
+    /// Value: tab width.
+    pub expand_tabs: BTreeMap<String, TabWidth>,
+
     /// External formatters for code blocks by language.
     ///
     /// Key: language identifier (exact match only).
     /// Value: formatter configuration.
     pub formatters: HashMap<String, FormatterConfig>,
+
+    /// Run formatters in a sandbox (default: false): with a scrubbed
+    /// environment, in an empty temporary directory, and, on Linux, without
+    /// network access.
+    pub sandbox: bool,
+
+    /// Commands formatters may run (default: any).  A formatter is run only
+    /// if its command starts with one of these, e.g., `["deno", "fmt"]`.
+    pub allowed_formatters: Option<Vec<Vec<String>>>,
 }
 
 impl Default for CodeBlockConfig {
@@ -552,7 +1913,12 @@ impl Default for CodeBlockConfig {
             min_fence_length: MinFenceLength::default(),
             space_after_fence: true,
             default_language: String::new(),
+            warn_long_lines: false,
+            line_width: None,
+            expand_tabs: BTreeMap::new(),
             formatters: HashMap::new(),
+            sandbox: false,
+            allowed_formatters: None,
         }
     }
 }
@@ -684,6 +2050,181 @@ impl Default for ThematicBreakConfig {
     }
 }
 
+/// How `<br>` tags in table cells are written.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+pub enum BrStyle {
+    /// Keep each tag as written (default).
+    #[default]
+    #[serde(rename = "preserve")]
+    Preserve,
+    /// `<br>`
+    #[serde(rename = "<br>")]
+    Void,
+    /// `<br/>`
+    #[serde(rename = "<br/>")]
+    SelfClosing,
+    /// `<br />`
+    #[serde(rename = "<br />")]
+    SpacedSelfClosing,
+}
+
+impl BrStyle {
+    /// Get the string representation of this style, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Preserve => "preserve",
+            Self::Void => "<br>",
+            Self::SelfClosing => "<br/>",
+            Self::SpacedSelfClosing => "<br />",
+        }
+    }
+}
+
+/// How to handle table rows with a different number of cells than the
+/// header row.
+///
+/// Rows with fewer cells are always padded with empty cells.  Excess cells
+/// are ignored by GFM renderers, so they are either dropped or merged.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RaggedRows {
+    /// Warn about such rows, and drop excess cells (default).
+    #[default]
+    Warn,
+    /// Drop excess cells without warnings.
+    Drop,
+    /// Merge excess cells into the last cell, separated by escaped pipes,
+    /// so that no content is lost.
+    Merge,
+}
+
+impl RaggedRows {
+    /// Get the string representation of this mode, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warn => "warn",
+            Self::Drop => "drop",
+            Self::Merge => "merge",
+        }
+    }
+}
+
+/// Style of the delimiter row of tables.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DelimiterStyle {
+    /// Dashes padded to the column width, e.g., `| ------ |` (default).
+    #[default]
+    Padded,
+    /// Three characters per column without spaces, e.g., `|---|`.
+    Minimal,
+    /// Three characters per column regardless of its width, e.g.,
+    /// `| --- |`, so that changing a cell does not change the delimiter row.
+    Fixed,
+}
+
+impl DelimiterStyle {
+    /// Get the string representation of this style, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Padded => "padded",
+            Self::Minimal => "minimal",
+            Self::Fixed => "fixed",
+        }
+    }
+}
+
+/// Table formatting options.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TableConfig {
+    /// Escape raw HTML in table cells so that it is rendered as literal text
+    /// (default: false).
+    pub escape_html: bool,
+
+    /// How to write `<br>` tags in table cells (default: `preserve`).
+    pub br_style: BrStyle,
+
+    /// How to handle rows with more or fewer cells than the header row
+    /// (default: `warn`).
+    pub ragged_rows: RaggedRows,
+
+    /// Style of the delimiter row (default: `padded`).
+    pub delimiter_style: DelimiterStyle,
+
+    /// Keep the colons of tables whose columns are all left-aligned, which
+    /// renders the same as no alignment (default: true).
+    pub preserve_left_alignment: bool,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        Self {
+            escape_html: false,
+            br_style: BrStyle::default(),
+            ragged_rows: RaggedRows::default(),
+            delimiter_style: DelimiterStyle::default(),
+            preserve_left_alignment: true,
+        }
+    }
+}
+
+/// The name of an alert type, e.g., `NOTE` in `> [!NOTE]`.
+/// Must be a non-empty string of ASCII letters, digits, `-`, and `_`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlertName(String);
+
+impl AlertName {
+    /// Create a new AlertName.
+    ///
+    /// Returns an error if the name is empty or contains invalid characters.
+    pub fn new(name: String) -> Result<Self, String> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "alert type must only contain ASCII letters, digits, \"-\", and \"_\", got {:?}.",
+                name
+            ));
+        }
+        Ok(Self(name))
+    }
+
+    /// Get the inner value.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for AlertName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Self::new(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Alert (callout) formatting options.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Alert types to replace with another type, matched case-insensitively
+    /// (default: none).  For example, `{ info = "NOTE" }` rewrites
+    /// `> [!info]` as `> [!NOTE]`.
+    pub synonyms: BTreeMap<String, AlertName>,
+
+    /// Rewrite block quotes and paragraphs that start with a bold label,
+    /// e.g., `**Note:**`, as alerts of the same type (default: false).
+    pub convert_bold_labels: bool,
+}
+
 /// Dash pattern for en-dash or em-dash transformation.
 /// Must be a non-empty string of valid characters.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -817,6 +2358,37 @@ pub struct PunctuationConfig {
     /// Set to `false` to disable, or a string like `"---"` for a different pattern.
     /// The pattern is replaced with `—` (U+2014).
     pub em_dash: DashSetting,
+
+    /// Convert straight quotes around mostly CJK text to `corner` brackets,
+    /// i.e., `「」` and `『』`, or `full-width` quotation marks, i.e., `＂`
+    /// and `＇` (default: `none`).
+    pub cjk_quotes: CjkQuotes,
+}
+
+/// Which quotation marks straight quotes around CJK text become.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CjkQuotes {
+    /// Treat them like other quotes (default).
+    #[default]
+    None,
+    /// Corner brackets: `「」` for double quotes and `『』` for single ones.
+    Corner,
+    /// Full-width quotation marks: `＂` for double quotes and `＇` for single
+    /// ones.
+    FullWidth,
+}
+
+impl CjkQuotes {
+    /// Get the string representation of this style, as used in the
+    /// configuration file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Corner => "corner",
+            Self::FullWidth => "full-width",
+        }
+    }
 }
 
 impl Default for PunctuationConfig {
@@ -828,6 +2400,7 @@ impl Default for PunctuationConfig {
             ellipsis: true,
             en_dash: DashSetting::Disabled,
             em_dash: DashSetting::Pattern(DashPattern::new("--".to_string()).unwrap()),
+            cjk_quotes: CjkQuotes::default(),
         }
     }
 }
@@ -838,25 +2411,66 @@ impl Config {
         toml::from_str(toml_str)
     }
 
+    /// Parse a configuration from a JSON string.  The schema is the same as
+    /// that of TOML configurations, e.g., `{"heading": {"setext_h1": false}}`.
+    pub fn from_json(json_str: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json_str)
+    }
+
+    /// Parse a configuration from a YAML string.  The schema is the same as
+    /// that of TOML configurations, e.g., `heading: {setext_h1: false}`.
+    pub fn from_yaml(yaml_str: &str) -> Result<Self, serde_yaml_ng::Error> {
+        // An empty document is null in YAML, but means all defaults here
+        if yaml_str.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_yaml_ng::from_str(yaml_str)
+    }
+
     /// Load configuration from a file.
+    ///
+    /// Files with the `.json` extension are parsed as JSON, those with
+    /// the `.yaml` or `.yml` extension as YAML, and all others as TOML.
+    /// Files the configuration refers to, such as
+    /// [`HeadingConfig::dictionaries`], are loaded as well, relative to
+    /// the directory of `path`.
     pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
         let content =
             std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
-        Self::from_toml(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        let mut config = match extension.as_deref() {
+            Some("json") => Self::from_json(&content)
+                .map_err(|e| ConfigError::ParseJson(path.to_path_buf(), e))?,
+            Some("yaml" | "yml") => Self::from_yaml(&content)
+                .map_err(|e| ConfigError::ParseYaml(path.to_path_buf(), e))?,
+            _ => {
+                Self::from_toml(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?
+            }
+        };
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        config.heading.load_dictionaries(base_dir)?;
+        Ok(config)
     }
 
     /// Discover and load configuration by searching up the directory tree.
     ///
-    /// Starting from `start_dir`, searches for `.hongdown.toml` in each parent
-    /// directory until the filesystem root is reached. Returns `None` if no
-    /// configuration file is found.
+    /// Starting from `start_dir`, searches for `.hongdown.toml`,
+    /// `.hongdown.json`, `hongdown.config.json`, `.hongdown.yaml`, or
+    /// `.hongdown.yml`, in that order, in each parent directory until
+    /// the filesystem root is reached. Returns `None` if no configuration
+    /// file is found.
     pub fn discover(start_dir: &Path) -> Result<Option<(PathBuf, Self)>, ConfigError> {
         let mut current = start_dir.to_path_buf();
         loop {
-            let config_path = current.join(CONFIG_FILE_NAME);
-            if config_path.exists() {
-                let config = Self::from_file(&config_path)?;
-                return Ok(Some((config_path, config)));
+            for name in CONFIG_FILE_NAMES {
+                let config_path = current.join(name);
+                if config_path.exists() {
+                    let config = Self::from_file(&config_path)?;
+                    return Ok(Some((config_path, config)));
+                }
             }
             if !current.pop() {
                 break;
@@ -865,6 +2479,122 @@ impl Config {
         Ok(None)
     }
 
+    /// Build formatting options from this configuration.
+    ///
+    /// Returns an error message if an external code formatter or the dash
+    /// settings are invalid.
+    pub fn to_options(&self) -> Result<Options, String> {
+        for (lang, cfg) in &self.code_block.formatters {
+            cfg.validate()
+                .map_err(|msg| format!("formatter for '{}': {}", lang, msg))?;
+        }
+        let options = Options {
+            line_width: self.line_width,
+            unicode_normalization: self.unicode_normalization,
+            bom: self.bom,
+            line_ending: self.line_ending,
+            strip_trailing_whitespace: self.strip_trailing_whitespace,
+            final_newline: self.final_newline,
+            tab_width: self.tab_width,
+            expand_tabs: self.expand_tabs,
+            max_blank_lines: self.max_blank_lines,
+            escape_underscores: self.escape_underscores,
+            renumber_references: self.renumber_references,
+            autolink_host: self.autolink_host,
+            mentions: self.mentions,
+            profile: self.profile,
+            link_title_style: self.link_title_style,
+            link_reference_style: self.link_reference_style,
+            reference_label_case: self.reference_label_case,
+            normalize_urls: self.normalize_urls,
+            link_base: self.link_base.clone(),
+            verify_escaping: self.verify_escaping,
+            verify_stability: self.verify_stability,
+            setext_h1: self.heading.setext_h1,
+            setext_h2: self.heading.setext_h2,
+            setext_underline: self.heading.setext_underline,
+            setext_underline_measure: self.heading.setext_underline_measure,
+            heading_sentence_case: self.heading.sentence_case,
+            heading_proper_nouns: self.heading.proper_nouns.clone(),
+            heading_common_nouns: self.heading.common_nouns.clone(),
+            heading_blank_lines_before: self.heading.blank_lines_before,
+            heading_collapse_consecutive: self.heading.collapse_consecutive,
+            heading_blank_lines_after: self.heading.blank_lines_after,
+            heading_overlong: self.heading.overlong,
+            heading_links: self.heading.links,
+            heading_title_case: self.heading.title_case,
+            unordered_marker: self.unordered_list.unordered_marker,
+            leading_spaces: self.unordered_list.leading_spaces,
+            trailing_spaces: self.unordered_list.trailing_spaces,
+            indent_width: self.unordered_list.indent_width,
+            odd_level_marker: self.ordered_list.odd_level_marker,
+            even_level_marker: self.ordered_list.even_level_marker,
+            ordered_list_pad: self.ordered_list.pad,
+            ordered_list_indent_width: self.ordered_list.indent_width,
+            definition_marker_spaces: self.definition_list.marker_spaces,
+            definition_indent_width: self.definition_list.indent_width,
+            abbreviation_placement: self.abbreviation.placement,
+            front_matter_key_order: self.front_matter.key_order.clone(),
+            front_matter_normalize_dates: self.front_matter.normalize_dates,
+            front_matter_date_format: self.front_matter.date_format.clone(),
+            front_matter_datetime_format: self.front_matter.datetime_format.clone(),
+            badge_layout: self.badge.layout,
+            image_convert_html: self.image.convert_html,
+            image_html_attributes: self.image.html_attributes,
+            figure_style: self.figure.style,
+            fence_char: self.code_block.fence_char,
+            min_fence_length: self.code_block.min_fence_length,
+            space_after_fence: self.code_block.space_after_fence,
+            default_language: self.code_block.default_language.clone(),
+            warn_long_code_lines: self.code_block.warn_long_lines,
+            code_line_width: self.code_block.line_width,
+            code_expand_tabs: self.code_block.expand_tabs.clone(),
+            thematic_break_style: self.thematic_break.style.clone(),
+            thematic_break_leading_spaces: self.thematic_break.leading_spaces,
+            table_escape_html: self.table.escape_html,
+            table_br_style: self.table.br_style,
+            table_ragged_rows: self.table.ragged_rows,
+            table_delimiter_style: self.table.delimiter_style,
+            table_preserve_left_alignment: self.table.preserve_left_alignment,
+            alert_synonyms: self.alert.synonyms.clone(),
+            alert_convert_bold_labels: self.alert.convert_bold_labels,
+            curly_double_quotes: self.punctuation.curly_double_quotes,
+            curly_single_quotes: self.punctuation.curly_single_quotes,
+            curly_apostrophes: self.punctuation.curly_apostrophes,
+            ellipsis: self.punctuation.ellipsis,
+            en_dash: self.punctuation.en_dash.clone(),
+            em_dash: self.punctuation.em_dash.clone(),
+            cjk_quotes: self.punctuation.cjk_quotes,
+            code_formatters: self
+                .code_block
+                .formatters
+                .iter()
+                .map(|(lang, cfg)| {
+                    (
+                        lang.clone(),
+                        CodeFormatter {
+                            command: cfg.command().to_vec(),
+                            timeout_secs: cfg.timeout(),
+                        },
+                    )
+                })
+                .collect(),
+            code_formatter_dir: None,
+            code_formatter_sandbox: self.code_block.sandbox,
+            allowed_code_formatters: self.code_block.allowed_formatters.clone(),
+            transforms: Vec::new(),
+            inline_renderers: HashMap::new(),
+            url_rewriter: None,
+            directives: HashMap::new(),
+            parallel_sections: false,
+            limits: self.limits,
+            extensions: self.extensions,
+            trace: false,
+        };
+        crate::validate_dash_settings(&options).map_err(|e| e.to_string())?;
+        Ok(options)
+    }
+
     /// Collect files matching the include patterns, excluding those matching
     /// exclude patterns.
     ///
@@ -930,6 +2660,10 @@ pub enum ConfigError {
     Io(PathBuf, std::io::Error),
     /// Error parsing the TOML configuration.
     Parse(PathBuf, toml::de::Error),
+    /// Error parsing the JSON configuration.
+    ParseJson(PathBuf, serde_json::Error),
+    /// Error parsing the YAML configuration.
+    ParseYaml(PathBuf, serde_yaml_ng::Error),
     /// Error parsing a glob pattern.
     Glob(String, glob::PatternError),
     /// I/O error during glob iteration.
@@ -947,6 +2681,12 @@ impl std::fmt::Display for ConfigError {
             ConfigError::Parse(path, err) => {
                 write!(f, "failed to parse {}: {}", path.display(), err)
             }
+            ConfigError::ParseJson(path, err) => {
+                write!(f, "failed to parse {}: {}", path.display(), err)
+            }
+            ConfigError::ParseYaml(path, err) => {
+                write!(f, "failed to parse {}: {}", path.display(), err)
+            }
             ConfigError::Glob(pattern, err) => {
                 write!(f, "invalid glob pattern '{}': {}", pattern, err)
             }
@@ -965,6 +2705,8 @@ impl std::error::Error for ConfigError {
         match self {
             ConfigError::Io(_, err) => Some(err),
             ConfigError::Parse(_, err) => Some(err),
+            ConfigError::ParseJson(_, err) => Some(err),
+            ConfigError::ParseYaml(_, err) => Some(err),
             ConfigError::Glob(_, err) => Some(err),
             ConfigError::GlobIo(err) => Some(err),
             ConfigError::Ignore(err) => Some(err),
@@ -997,6 +2739,12 @@ mod tests {
         );
         assert_eq!(config.ordered_list.pad, OrderedListPad::Start);
         assert_eq!(config.ordered_list.indent_width.get(), 4);
+        assert_eq!(config.definition_list.marker_spaces.get(), 3);
+        assert_eq!(config.definition_list.indent_width.get(), 4);
+        assert_eq!(
+            config.abbreviation.placement,
+            AbbreviationPlacement::Preserve
+        );
         assert_eq!(config.code_block.fence_char, FenceChar::Tilde);
         assert_eq!(config.code_block.min_fence_length.get(), 4);
         assert!(config.code_block.space_after_fence);
@@ -1006,6 +2754,11 @@ mod tests {
             "- - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -"
         );
         assert_eq!(config.thematic_break.leading_spaces.get(), 3);
+        assert!(!config.table.escape_html);
+        assert_eq!(config.table.br_style, BrStyle::Preserve);
+        assert_eq!(config.table.ragged_rows, RaggedRows::Warn);
+        assert_eq!(config.table.delimiter_style, DelimiterStyle::Padded);
+        assert!(config.table.preserve_left_alignment);
     }
 
     #[test]
@@ -1241,6 +2994,60 @@ style = "---"
         assert_eq!(config.thematic_break.style.as_str(), "---");
     }
 
+    #[test]
+    fn test_parse_table_config() {
+        let config = Config::from_toml(
+            r#"
+[table]
+escape_html = true
+br_style = "<br />"
+ragged_rows = "merge"
+delimiter_style = "minimal"
+preserve_left_alignment = false
+"#,
+        )
+        .unwrap();
+        assert!(config.table.escape_html);
+        assert_eq!(config.table.br_style, BrStyle::SpacedSelfClosing);
+        assert_eq!(config.table.ragged_rows, RaggedRows::Merge);
+        assert_eq!(config.table.delimiter_style, DelimiterStyle::Minimal);
+        assert!(!config.table.preserve_left_alignment);
+    }
+
+    #[test]
+    fn test_parse_alert_config() {
+        let config = Config::from_toml(
+            r#"
+[alert]
+convert_bold_labels = true
+
+[alert.synonyms]
+info = "NOTE"
+danger = "CAUTION"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.alert.synonyms["info"].as_str(), "NOTE");
+        assert_eq!(config.alert.synonyms["danger"].as_str(), "CAUTION");
+        assert!(config.alert.convert_bold_labels);
+        assert!(Config::default().alert.synonyms.is_empty());
+        assert!(!Config::default().alert.convert_bold_labels);
+    }
+
+    #[test]
+    fn test_parse_invalid_alert_synonym() {
+        let result = Config::from_toml("[alert.synonyms]\ninfo = \"NO TE\"\n");
+        assert!(result.is_err());
+        let result = Config::from_toml("[alert.synonyms]\ninfo = \"\"\n");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_br_style() {
+        let result = Config::from_toml("[table]\nbr_style = \"<BR>\"\n");
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_parse_invalid_toml() {
         let result = Config::from_toml("line_width = \"not a number\"");
@@ -1265,6 +3072,216 @@ style = "---"
         let _ = std::fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_parse_json() {
+        let config = Config::from_json(
+            r#"{
+                "line_width": 100,
+                "warnings_as_errors": ["HD001"],
+                "heading": { "setext_h1": false },
+                "code_block": { "formatters": { "python": ["black", "-"] } }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.line_width.get(), 100);
+        assert!(!config.heading.setext_h1);
+        assert!(
+            config
+                .warnings_as_errors
+                .contains(WarningCode::UndefinedReference)
+        );
+        assert_eq!(
+            config.code_block.formatters["python"].command(),
+            ["black", "-"]
+        );
+        assert_eq!(Config::from_json("{}").unwrap(), Config::default());
+        assert!(Config::from_json(r#"{"line_width": "wide"}"#).is_err());
+    }
+
+    #[test]
+    fn test_discover_json_config() {
+        let temp_dir = std::env::temp_dir().join("hongdown_test_discover_json");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let sub_dir = temp_dir.join("subdir");
+        std::fs::create_dir_all(&sub_dir).unwrap();
+
+        let config_path = temp_dir.join("hongdown.config.json");
+        std::fs::write(&config_path, r#"{"line_width": 70}"#).unwrap();
+        let (path, config) = Config::discover(&sub_dir).unwrap().unwrap();
+        assert_eq!(path, config_path);
+        assert_eq!(config.line_width.get(), 70);
+
+        let config_path = temp_dir.join(".hongdown.json");
+        std::fs::write(&config_path, r#"{"line_width": 80}"#).unwrap();
+        let (path, config) = Config::discover(&sub_dir).unwrap().unwrap();
+        assert_eq!(path, config_path);
+        assert_eq!(config.line_width.get(), 80);
+
+        // TOML takes precedence over JSON in the same directory
+        let config_path = temp_dir.join(CONFIG_FILE_NAME);
+        std::fs::write(&config_path, "line_width = 90").unwrap();
+        let (path, config) = Config::discover(&sub_dir).unwrap().unwrap();
+        assert_eq!(path, config_path);
+        assert_eq!(config.line_width.get(), 90);
+
+        // A nearer JSON file takes precedence over a farther TOML file
+        let config_path = sub_dir.join(".hongdown.json");
+        std::fs::write(&config_path, "{\"line_width\": 100").unwrap();
+        let err = Config::discover(&sub_dir).unwrap_err();
+        assert!(matches!(err, ConfigError::ParseJson(..)), "{}", err);
+        std::fs::write(&config_path, r#"{"line_width": 100}"#).unwrap();
+        let (path, config) = Config::discover(&sub_dir).unwrap().unwrap();
+        assert_eq!(path, config_path);
+        assert_eq!(config.line_width.get(), 100);
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        let config = Config::from_yaml(
+            "line_width: 100\n\
+             warnings_as_errors: [HD001]\n\
+             heading:\n  setext_h1: false\n\
+             code_block:\n  formatters:\n    python: [black, \"-\"]\n\
+             punctuation:\n  em_dash: false\n",
+        )
+        .unwrap();
+        assert_eq!(config.line_width.get(), 100);
+        assert!(!config.heading.setext_h1);
+        assert!(
+            config
+                .warnings_as_errors
+                .contains(WarningCode::UndefinedReference)
+        );
+        assert_eq!(
+            config.code_block.formatters["python"].command(),
+            ["black", "-"]
+        );
+        assert_eq!(config.punctuation.em_dash, DashSetting::Disabled);
+        assert_eq!(Config::from_yaml("").unwrap(), Config::default());
+        assert_eq!(Config::from_yaml("{}").unwrap(), Config::default());
+        assert!(Config::from_yaml("line_width: wide").is_err());
+    }
+
+    #[test]
+    fn test_discover_yaml_config() {
+        let temp_dir = std::env::temp_dir().join("hongdown_test_discover_yaml");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let sub_dir = temp_dir.join("subdir");
+        std::fs::create_dir_all(&sub_dir).unwrap();
+
+        let config_path = temp_dir.join(".hongdown.yml");
+        std::fs::write(&config_path, "line_width: 70\n").unwrap();
+        let (path, config) = Config::discover(&sub_dir).unwrap().unwrap();
+        assert_eq!(path, config_path);
+        assert_eq!(config.line_width.get(), 70);
+
+        let config_path = temp_dir.join(".hongdown.yaml");
+        std::fs::write(&config_path, "line_width: 80\n").unwrap();
+        let (path, config) = Config::discover(&sub_dir).unwrap().unwrap();
+        assert_eq!(path, config_path);
+        assert_eq!(config.line_width.get(), 80);
+
+        // JSON takes precedence over YAML in the same directory
+        let config_path = temp_dir.join(".hongdown.json");
+        std::fs::write(&config_path, r#"{"line_width": 90}"#).unwrap();
+        let (path, config) = Config::discover(&sub_dir).unwrap().unwrap();
+        assert_eq!(path, config_path);
+        assert_eq!(config.line_width.get(), 90);
+
+        // A nearer YAML file takes precedence over a farther JSON file
+        let config_path = sub_dir.join(".hongdown.yaml");
+        std::fs::write(&config_path, "line_width: [100\n").unwrap();
+        let err = Config::discover(&sub_dir).unwrap_err();
+        assert!(matches!(err, ConfigError::ParseYaml(..)), "{}", err);
+        std::fs::write(&config_path, "line_width: 100\n").unwrap();
+        let (path, config) = Config::discover(&sub_dir).unwrap().unwrap();
+        assert_eq!(path, config_path);
+        assert_eq!(config.line_width.get(), 100);
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_proper_nouns_file() {
+        let temp_dir = std::env::temp_dir().join("hongdown_test_proper_nouns_file");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(temp_dir.join("pm")).unwrap();
+        std::fs::write(
+            temp_dir.join("pm").join("nouns.txt"),
+            "# Products\nHongdown\n\n  Fedify  \nC#\n",
+        )
+        .unwrap();
+        let config_path = temp_dir.join(CONFIG_FILE_NAME);
+        std::fs::write(
+            &config_path,
+            "[heading]\nproper_nouns = [\"Hollo\"]\nproper_nouns_file = \"pm/nouns.txt\"",
+        )
+        .unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        assert_eq!(
+            config.heading.proper_nouns,
+            ["Hongdown", "Fedify", "C#", "Hollo"]
+        );
+
+        std::fs::write(
+            &config_path,
+            "[heading]\nproper_nouns_file = \"missing.txt\"",
+        )
+        .unwrap();
+        let err = Config::from_file(&config_path).unwrap_err();
+        assert!(err.to_string().contains("missing.txt"));
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_dictionaries() {
+        let temp_dir = std::env::temp_dir().join("hongdown_test_dictionaries");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("team.txt"), "TOKIO\nHongdown\n").unwrap();
+        std::fs::write(temp_dir.join("nouns.txt"), "hongdown\n").unwrap();
+        let config_path = temp_dir.join(CONFIG_FILE_NAME);
+        std::fs::write(
+            &config_path,
+            "[heading]\ndictionaries = [\"rust\", \"fediverse\", \"team.txt\"]\n\
+             proper_nouns_file = \"nouns.txt\"\nproper_nouns = [\"HONGDOWN\"]",
+        )
+        .unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        let nouns = &config.heading.proper_nouns;
+        assert!(nouns.contains(&"Serde".to_string()));
+        assert!(nouns.contains(&"Mastodon".to_string()));
+        // Later sources override earlier ones
+        assert!(nouns.contains(&"TOKIO".to_string()));
+        assert!(!nouns.contains(&"Tokio".to_string()));
+        assert_eq!(nouns.last().unwrap(), "HONGDOWN");
+        assert_eq!(
+            nouns
+                .iter()
+                .filter(|n| n.eq_ignore_ascii_case("hongdown"))
+                .count(),
+            1
+        );
+
+        std::fs::write(&config_path, "[heading]\ndictionaries = [\"go\"]").unwrap();
+        assert!(Config::from_file(&config_path).is_err());
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_bundled_dictionaries() {
+        for (name, content) in BUNDLED_DICTIONARIES {
+            let nouns = parse_noun_list(content);
+            assert!(!nouns.is_empty(), "{} is empty", name);
+        }
+    }
+
     #[test]
     fn test_default_include_exclude() {
         let config = Config::default();
@@ -1294,6 +3311,48 @@ exclude = ["node_modules/**", "target/**"]
         assert_eq!(config.exclude, vec!["node_modules/**", "target/**"]);
     }
 
+    #[test]
+    fn test_parse_warnings_as_errors() {
+        let config = Config::from_toml("").unwrap();
+        assert_eq!(config.warnings_as_errors, WarningsAsErrors::None);
+        assert!(
+            !config
+                .warnings_as_errors
+                .contains(WarningCode::UndefinedReference)
+        );
+
+        let config = Config::from_toml("warnings_as_errors = true").unwrap();
+        assert_eq!(config.warnings_as_errors, WarningsAsErrors::All);
+        assert!(
+            config
+                .warnings_as_errors
+                .contains(WarningCode::UndefinedReference)
+        );
+
+        let config = Config::from_toml("warnings_as_errors = false").unwrap();
+        assert_eq!(config.warnings_as_errors, WarningsAsErrors::None);
+
+        let config = Config::from_toml(r#"warnings_as_errors = ["hd001", "tables"]"#).unwrap();
+        let rules = &config.warnings_as_errors;
+        assert!(rules.contains(WarningCode::UndefinedReference));
+        assert!(rules.contains(WarningCode::TablePipeCount));
+        assert!(!rules.contains(WarningCode::HeadingTooLong));
+
+        let config = Config::from_toml(r#"warnings_as_errors = ["heading-too-long"]"#).unwrap();
+        assert!(
+            config
+                .warnings_as_errors
+                .contains(WarningCode::HeadingTooLong)
+        );
+    }
+
+    #[test]
+    fn test_parse_warnings_as_errors_unknown_rule() {
+        let error = Config::from_toml(r#"warnings_as_errors = ["HD999"]"#).unwrap_err();
+        assert!(error.to_string().contains("HD999"), "{}", error);
+        assert!(Config::from_toml(r#"warnings_as_errors = "all""#).is_err());
+    }
+
     #[test]
     fn test_parse_include_and_exclude() {
         let config = Config::from_toml(
@@ -1407,6 +3466,16 @@ em_dash = "---"
         );
     }
 
+    #[test]
+    fn test_parse_cjk_quotes() {
+        assert_eq!(Config::default().punctuation.cjk_quotes, CjkQuotes::None);
+        let config = Config::from_toml("[punctuation]\ncjk_quotes = \"corner\"").unwrap();
+        assert_eq!(config.punctuation.cjk_quotes, CjkQuotes::Corner);
+        let config = Config::from_toml("[punctuation]\ncjk_quotes = \"full-width\"").unwrap();
+        assert_eq!(config.punctuation.cjk_quotes, CjkQuotes::FullWidth);
+        assert!(Config::from_toml("[punctuation]\ncjk_quotes = \"guillemets\"").is_err());
+    }
+
     #[test]
     fn test_parse_dash_setting_disabled() {
         let config = Config::from_toml(
@@ -1454,6 +3523,417 @@ em_dash = "--"
         );
     }
 
+    #[test]
+    fn test_limits_config() {
+        assert_eq!(Config::default().limits, Limits::default());
+        let config = Config::from_toml(
+            r#"
+[limits]
+max_input_bytes = 1048576
+max_table_columns = 64
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.limits.max_input_bytes, Some(1048576));
+        assert_eq!(config.limits.max_nesting_depth, None);
+        assert_eq!(config.limits.max_table_columns, Some(64));
+    }
+
+    #[test]
+    fn test_unicode_normalization_config() {
+        assert_eq!(
+            Config::default().unicode_normalization,
+            UnicodeNormalization::None
+        );
+        let config = Config::from_toml(r#"unicode_normalization = "nfc""#).unwrap();
+        assert_eq!(config.unicode_normalization, UnicodeNormalization::Nfc);
+        assert!(Config::from_toml(r#"unicode_normalization = "nfd""#).is_err());
+    }
+
+    #[test]
+    fn test_unicode_normalization_apply() {
+        let decomposed = "Cafe\u{301}";
+        assert_eq!(UnicodeNormalization::None.apply(decomposed), decomposed);
+        assert_eq!(UnicodeNormalization::Nfc.apply(decomposed), "Caf\u{e9}");
+        assert_eq!(UnicodeNormalization::Nfc.apply("\u{fb01}le"), "\u{fb01}le");
+        assert_eq!(UnicodeNormalization::Nfkc.apply("\u{fb01}le"), "file");
+    }
+
+    #[test]
+    fn test_bom_config() {
+        assert_eq!(Config::default().bom, ByteOrderMark::Strip);
+        let config = Config::from_toml(r#"bom = "preserve""#).unwrap();
+        assert_eq!(config.bom, ByteOrderMark::Preserve);
+        assert!(Config::from_toml(r#"bom = "add""#).is_err());
+    }
+
+    #[test]
+    fn test_line_ending_config() {
+        assert_eq!(Config::default().line_ending, LineEnding::Lf);
+        let config = Config::from_toml(r#"line_ending = "crlf""#).unwrap();
+        assert_eq!(config.line_ending, LineEnding::Crlf);
+        assert!(Config::from_toml(r#"line_ending = "cr""#).is_err());
+    }
+
+    #[test]
+    fn test_strip_trailing_whitespace_config() {
+        assert!(!Config::default().strip_trailing_whitespace);
+        let config = Config::from_toml("strip_trailing_whitespace = true").unwrap();
+        assert!(config.strip_trailing_whitespace);
+    }
+
+    #[test]
+    fn test_final_newline_config() {
+        assert_eq!(Config::default().final_newline, FinalNewline::Always);
+        let config = Config::from_toml(r#"final_newline = "preserve""#).unwrap();
+        assert_eq!(config.final_newline, FinalNewline::Preserve);
+        assert!(Config::from_toml(r#"final_newline = "true""#).is_err());
+    }
+
+    #[test]
+    fn test_tabs_config() {
+        let config = Config::default();
+        assert_eq!(config.tab_width.get(), 4);
+        assert!(!config.expand_tabs);
+        let config = Config::from_toml("tab_width = 8\nexpand_tabs = true").unwrap();
+        assert_eq!(config.tab_width.get(), 8);
+        assert!(config.expand_tabs);
+        let err = Config::from_toml("tab_width = 0").unwrap_err();
+        assert!(err.to_string().contains("tab_width must be at least 1"));
+    }
+
+    #[test]
+    fn test_max_blank_lines_config() {
+        assert_eq!(Config::default().max_blank_lines.get(), 1);
+        let config = Config::from_toml("max_blank_lines = 2").unwrap();
+        assert_eq!(config.max_blank_lines.get(), 2);
+        let err = Config::from_toml("max_blank_lines = 0").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("max_blank_lines must be at least 1")
+        );
+    }
+
+    #[test]
+    fn test_escape_underscores_config() {
+        assert!(Config::default().escape_underscores);
+        let config = Config::from_toml("escape_underscores = false").unwrap();
+        assert!(!config.escape_underscores);
+    }
+
+    #[test]
+    fn test_renumber_references_config() {
+        assert!(!Config::default().renumber_references);
+        let config = Config::from_toml("renumber_references = true").unwrap();
+        assert!(config.renumber_references);
+    }
+
+    #[test]
+    fn test_autolink_host_config() {
+        assert_eq!(Config::default().autolink_host, AutolinkHost::GitHub);
+        let config = Config::from_toml("autolink_host = \"gitlab\"").unwrap();
+        assert_eq!(config.autolink_host, AutolinkHost::GitLab);
+        assert!(Config::from_toml("autolink_host = \"bitbucket\"").is_err());
+    }
+
+    #[test]
+    fn test_mentions_config() {
+        assert_eq!(Config::default().mentions, MentionStyle::Keep);
+        let config = Config::from_toml("mentions = \"code\"").unwrap();
+        assert_eq!(config.mentions, MentionStyle::Code);
+        assert!(Config::from_toml("mentions = \"strip\"").is_err());
+    }
+
+    #[test]
+    fn test_profile_config() {
+        assert_eq!(Config::default().profile, Profile::Default);
+        let config = Config::from_toml("profile = \"changelog\"").unwrap();
+        assert_eq!(config.profile, Profile::Changelog);
+        assert!(Config::from_toml("profile = \"readme\"").is_err());
+    }
+
+    #[test]
+    fn test_badge_config() {
+        assert_eq!(Config::default().badge.layout, BadgeLayout::Preserve);
+        let config = Config::from_toml("[badge]\nlayout = \"stack\"").unwrap();
+        assert_eq!(config.badge.layout, BadgeLayout::Stack);
+        assert!(Config::from_toml("[badge]\nlayout = \"grid\"").is_err());
+    }
+
+    #[test]
+    fn test_link_title_style_config() {
+        assert_eq!(Config::default().link_title_style, LinkTitleStyle::Double);
+        let config = Config::from_toml("link_title_style = \"paren\"").unwrap();
+        assert_eq!(config.link_title_style, LinkTitleStyle::Paren);
+        assert!(Config::from_toml("link_title_style = \"backtick\"").is_err());
+    }
+
+    #[test]
+    fn test_link_reference_style_config() {
+        assert_eq!(
+            Config::default().link_reference_style,
+            LinkReferenceStyle::Shortcut
+        );
+        let config = Config::from_toml("link_reference_style = \"collapsed\"").unwrap();
+        assert_eq!(config.link_reference_style, LinkReferenceStyle::Collapsed);
+        assert!(Config::from_toml("link_reference_style = \"full\"").is_err());
+    }
+
+    #[test]
+    fn test_reference_label_case_config() {
+        assert_eq!(
+            Config::default().reference_label_case,
+            ReferenceLabelCase::Preserve
+        );
+        let config = Config::from_toml("reference_label_case = \"lower\"").unwrap();
+        assert_eq!(config.reference_label_case, ReferenceLabelCase::Lower);
+        assert!(Config::from_toml("reference_label_case = \"upper\"").is_err());
+    }
+
+    #[test]
+    fn test_link_title_style_quote() {
+        assert_eq!(
+            LinkTitleStyle::Double.quote("a \"b\" c"),
+            "\"a \\\"b\\\" c\""
+        );
+        assert_eq!(
+            LinkTitleStyle::Single.quote("it's \"ok\""),
+            "'it\\'s \"ok\"'"
+        );
+        assert_eq!(LinkTitleStyle::Paren.quote("a (b)"), "(a \\(b\\))");
+        assert_eq!(LinkTitleStyle::Double.quote("C:\\dir\\"), "\"C:\\dir\\\\\"");
+    }
+
+    #[test]
+    fn test_normalize_urls_config() {
+        assert!(!Config::default().normalize_urls);
+        let config = Config::from_toml("normalize_urls = true").unwrap();
+        assert!(config.normalize_urls);
+    }
+
+    #[test]
+    fn test_link_base_config() {
+        assert_eq!(Config::default().link_base, "");
+        let config = Config::from_toml("link_base = \"docs\"").unwrap();
+        assert_eq!(config.link_base, "docs");
+    }
+
+    #[test]
+    fn test_parse_image_config() {
+        let config = Config::from_toml(
+            r#"
+[image]
+convert_html = true
+html_attributes = "preserve"
+"#,
+        )
+        .unwrap();
+        assert!(config.image.convert_html);
+        assert_eq!(config.image.html_attributes, HtmlImageAttributes::Preserve);
+        assert!(!Config::default().image.convert_html);
+        assert_eq!(
+            Config::default().image.html_attributes,
+            HtmlImageAttributes::Skip
+        );
+    }
+
+    #[test]
+    fn test_parse_figure_config() {
+        let config = Config::from_toml("[figure]\nstyle = \"html\"").unwrap();
+        assert_eq!(config.figure.style, FigureStyle::Html);
+        assert_eq!(Config::default().figure.style, FigureStyle::Preserve);
+        assert!(Config::from_toml("[figure]\nstyle = \"latex\"").is_err());
+    }
+
+    #[test]
+    fn test_verify_config() {
+        let config = Config::default();
+        assert!(!config.verify_escaping);
+        assert!(!config.verify_stability);
+        let config = Config::from_toml("verify_escaping = true\nverify_stability = true").unwrap();
+        assert!(config.verify_escaping);
+        assert!(config.verify_stability);
+    }
+
+    #[test]
+    fn test_heading_blank_lines_config() {
+        let config = Config::default();
+        assert_eq!(
+            config.heading.blank_lines_before.values(),
+            [1, 2, 1, 1, 1, 1]
+        );
+        assert!(config.heading.collapse_consecutive);
+        let config = Config::from_toml(
+            "[heading]\nblank_lines_before = [1, 1, 1, 1, 1, 1]\ncollapse_consecutive = false",
+        )
+        .unwrap();
+        assert_eq!(config.heading.blank_lines_before.get(2), 1);
+        assert!(!config.heading.collapse_consecutive);
+        let err =
+            Config::from_toml("[heading]\nblank_lines_before = [1, 0, 1, 1, 1, 1]").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("blank_lines_before must be at least 1")
+        );
+        assert!(Config::from_toml("[heading]\nblank_lines_before = [1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_setext_underline_config() {
+        assert_eq!(
+            Config::default().heading.setext_underline,
+            SetextUnderline::Match
+        );
+        let config = Config::from_toml("[heading]\nsetext_underline = \"full-width\"").unwrap();
+        assert_eq!(config.heading.setext_underline, SetextUnderline::FullWidth);
+        let config = Config::from_toml("[heading]\nsetext_underline = 10").unwrap();
+        assert_eq!(config.heading.setext_underline, SetextUnderline::Fixed(10));
+        assert!(Config::from_toml("[heading]\nsetext_underline = 0").is_err());
+        assert!(Config::from_toml("[heading]\nsetext_underline = \"wide\"").is_err());
+        assert_eq!(
+            Config::default().heading.setext_underline_measure,
+            UnderlineMeasure::DisplayWidth
+        );
+        let config =
+            Config::from_toml("[heading]\nsetext_underline_measure = \"characters\"").unwrap();
+        assert_eq!(
+            config.heading.setext_underline_measure,
+            UnderlineMeasure::Characters
+        );
+    }
+
+    #[test]
+    fn test_heading_blank_lines_after_config() {
+        assert_eq!(Config::default().heading.blank_lines_after, 1);
+        let config = Config::from_toml("[heading]\nblank_lines_after = 0").unwrap();
+        assert_eq!(config.heading.blank_lines_after, 0);
+    }
+
+    #[test]
+    fn test_heading_overlong_config() {
+        assert_eq!(
+            Config::default().heading.overlong.get(1),
+            OverlongHeading::Keep
+        );
+        let config = Config::from_toml("[heading]\noverlong = \"atx\"").unwrap();
+        assert_eq!(config.heading.overlong.get(2), OverlongHeading::Atx);
+        let config = Config::from_toml(
+            "[heading]\noverlong = [\"wrap\", \"atx\", \"warn\", \"keep\", \"keep\", \"keep\"]",
+        )
+        .unwrap();
+        assert_eq!(config.heading.overlong.get(1), OverlongHeading::Wrap);
+        assert_eq!(config.heading.overlong.get(3), OverlongHeading::Warn);
+        assert!(Config::from_toml("[heading]\noverlong = \"split\"").is_err());
+        assert!(Config::from_toml("[heading]\noverlong = [\"atx\"]").is_err());
+    }
+
+    #[test]
+    fn test_heading_links_config() {
+        assert_eq!(Config::default().heading.links, HeadingLinks::Reference);
+        let config = Config::from_toml("[heading]\nlinks = \"text\"").unwrap();
+        assert_eq!(config.heading.links, HeadingLinks::Text);
+        assert!(Config::from_toml("[heading]\nlinks = \"strip\"").is_err());
+    }
+
+    #[test]
+    fn test_heading_title_case_config() {
+        assert_eq!(Config::default().heading.title_case, TitleCase::None);
+        let config = Config::from_toml("[heading]\ntitle_case = \"chicago\"").unwrap();
+        assert_eq!(config.heading.title_case, TitleCase::Chicago);
+        assert!(Config::from_toml("[heading]\ntitle_case = \"apa\"").is_err());
+    }
+
+    #[test]
+    fn test_final_newline_apply() {
+        let output = || "Text.\n\n".to_string();
+        assert_eq!(FinalNewline::Always.apply("", output()), "Text.\n");
+        assert_eq!(FinalNewline::Never.apply("", output()), "Text.");
+        assert_eq!(FinalNewline::Preserve.apply("Text.", output()), "Text.");
+        assert_eq!(
+            FinalNewline::Preserve.apply("Text.\r\n", output()),
+            "Text.\n"
+        );
+        assert_eq!(FinalNewline::Always.apply("", String::new()), "");
+    }
+
+    #[test]
+    fn test_line_ending_apply() {
+        let output = "a\n<div>\r\nb\r\n</div>\n".to_string();
+        assert_eq!(
+            LineEnding::Lf.apply("", output.clone()),
+            "a\n<div>\nb\n</div>\n"
+        );
+        assert_eq!(
+            LineEnding::Crlf.apply("", output.clone()),
+            "a\r\n<div>\r\nb\r\n</div>\r\n"
+        );
+        assert_eq!(
+            LineEnding::Preserve.apply("a\r\nb\n", "a\nb\n".to_string()),
+            "a\r\nb\r\n"
+        );
+        assert_eq!(
+            LineEnding::Preserve.apply("a\nb\r\n", "a\nb\n".to_string()),
+            "a\nb\n"
+        );
+        assert_eq!(LineEnding::Preserve.apply("a", "a\n".to_string()), "a\n");
+    }
+
+    #[test]
+    fn test_front_matter_config() {
+        assert!(Config::default().front_matter.key_order.is_empty());
+        let config =
+            Config::from_toml("[front_matter]\nkey_order = [\"title\", \"date\"]").unwrap();
+        assert_eq!(config.front_matter.key_order, vec!["title", "date"]);
+        assert!(!config.front_matter.normalize_dates);
+        assert_eq!(config.front_matter.date_format, "%Y-%m-%d");
+        let config =
+            Config::from_toml("[front_matter]\nnormalize_dates = true\ndate_format = \"%d.%m.%Y\"")
+                .unwrap();
+        assert!(config.front_matter.normalize_dates);
+        assert_eq!(config.front_matter.date_format, "%d.%m.%Y");
+        assert_eq!(config.front_matter.datetime_format, "%Y-%m-%dT%H:%M:%S%z");
+    }
+
+    #[test]
+    fn test_abbreviation_config() {
+        let config = Config::from_toml(
+            r#"
+[abbreviation]
+placement = "section"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.abbreviation.placement,
+            AbbreviationPlacement::Section
+        );
+        let result = Config::from_toml(
+            r#"
+[abbreviation]
+placement = "top"
+"#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_extensions_config() {
+        assert!(Config::default().extensions.description_lists);
+        let config = Config::from_toml(
+            r#"
+[extensions]
+description_lists = false
+"#,
+        )
+        .unwrap();
+        assert!(!config.extensions.description_lists);
+        assert!(!config.extensions.wikilinks);
+
+        let config = Config::from_toml("[extensions]\nwikilinks = true\n").unwrap();
+        assert!(config.extensions.description_lists);
+        assert!(config.extensions.wikilinks);
+    }
+
     #[test]
     fn test_default_code_block_formatters() {
         let config = Config::default();
@@ -1523,6 +4003,59 @@ timeout = 10
         assert!(config.code_block.formatters.contains_key("python"));
     }
 
+    #[test]
+    fn test_parse_code_block_long_lines() {
+        let config = Config::from_toml(
+            r#"
+[code_block]
+warn_long_lines = true
+line_width = 100
+"#,
+        )
+        .unwrap();
+        assert!(config.code_block.warn_long_lines);
+        assert_eq!(config.code_block.line_width.map(LineWidth::get), Some(100));
+        assert!(!Config::default().code_block.warn_long_lines);
+        assert!(Config::from_toml("[code_block]\nline_width = 4").is_err());
+    }
+
+    #[test]
+    fn test_parse_code_block_expand_tabs() {
+        let config = Config::from_toml(
+            r#"
+[code_block.expand_tabs]
+yaml = 2
+python = 4
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.code_block.expand_tabs["yaml"].get(), 2);
+        assert_eq!(config.code_block.expand_tabs["python"].get(), 4);
+        assert!(Config::default().code_block.expand_tabs.is_empty());
+        assert!(Config::from_toml("[code_block.expand_tabs]\nyaml = 0").is_err());
+    }
+
+    #[test]
+    fn test_parse_formatter_sandbox() {
+        let config = Config::from_toml(
+            r#"
+[code_block]
+sandbox = true
+allowed_formatters = [["deno", "fmt"], ["rustfmt"]]
+"#,
+        )
+        .unwrap();
+        assert!(config.code_block.sandbox);
+        assert_eq!(
+            config.code_block.allowed_formatters,
+            Some(vec![
+                vec!["deno".to_string(), "fmt".to_string()],
+                vec!["rustfmt".to_string()],
+            ])
+        );
+        assert_eq!(Config::default().code_block.allowed_formatters, None);
+    }
+
     #[test]
     fn test_formatter_empty_command_validation() {
         let config = Config::from_toml(
@@ -1562,6 +4095,39 @@ javascript = ["deno", "fmt", "-"]
                 .is_ok()
         );
     }
+
+    #[test]
+    fn test_to_options() {
+        let config = Config::from_toml(
+            r#"
+line_width = 100
+
+[heading]
+setext_h1 = false
+
+[code_block.formatters]
+javascript = ["deno", "fmt", "-"]
+"#,
+        )
+        .unwrap();
+        let options = config.to_options().unwrap();
+        assert_eq!(options.line_width.get(), 100);
+        assert!(!options.setext_h1);
+        assert_eq!(
+            options.code_formatters["javascript"].command,
+            ["deno", "fmt", "-"]
+        );
+    }
+
+    #[test]
+    fn test_to_options_invalid() {
+        let config = Config::from_toml("[code_block.formatters]\njavascript = []\n").unwrap();
+        let error = config.to_options().unwrap_err();
+        assert!(error.starts_with("formatter for 'javascript':"));
+        let config =
+            Config::from_toml("[punctuation]\nen_dash = \"--\"\nem_dash = \"--\"\n").unwrap();
+        assert!(config.to_options().is_err());
+    }
 }
 
 #[cfg(test)]
@@ -2245,3 +4811,37 @@ indent_width = 0
         assert!(err.contains("indent_width must be at least 1"));
     }
 }
+
+#[cfg(test)]
+mod definition_marker_spaces_tests {
+    use super::*;
+
+    #[test]
+    fn test_definition_marker_spaces_valid() {
+        assert_eq!(DefinitionMarkerSpaces::new(1).unwrap().get(), 1);
+        assert_eq!(DefinitionMarkerSpaces::new(3).unwrap().get(), 3);
+    }
+
+    #[test]
+    fn test_definition_marker_spaces_invalid() {
+        assert_eq!(
+            DefinitionMarkerSpaces::new(0).unwrap_err(),
+            "marker_spaces must be between 1 and 3, got 0."
+        );
+        assert!(DefinitionMarkerSpaces::new(4).is_err());
+    }
+
+    #[test]
+    fn test_definition_list_parse() {
+        let config = Config::from_toml(
+            r#"
+[definition_list]
+marker_spaces = 1
+indent_width = 2
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.definition_list.marker_spaces.get(), 1);
+        assert_eq!(config.definition_list.indent_width.get(), 2);
+    }
+}
@@ -0,0 +1,260 @@
+//! Structured diffs between a document and its formatted output.
+//!
+//! [`diff`] formats a document and returns the changes as unified diff
+//! [`Hunk`]s, so that editors and other integrations do not have to run their
+//! own diff over the formatted output.  [`check`] only reports which lines
+//! would change, for `--check`-style integrations.
+
+use std::ops::Range;
+
+use similar::{ChangeTag, DiffTag, TextDiff};
+
+use crate::{FormatError, Options, format};
+
+/// The kind of a line in a [`Hunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiffLineKind {
+    /// A line present in both the original and the formatted text.
+    Context,
+    /// A line removed from the original text.
+    Removed,
+    /// A line added by formatting.
+    Added,
+}
+
+impl DiffLineKind {
+    /// Returns the unified diff prefix of this kind: `' '`, `'-'`, or `'+'`.
+    pub fn sign(self) -> char {
+        match self {
+            DiffLineKind::Context => ' ',
+            DiffLineKind::Removed => '-',
+            DiffLineKind::Added => '+',
+        }
+    }
+}
+
+/// A single line in a [`Hunk`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    /// Whether the line is kept, removed, or added.
+    pub kind: DiffLineKind,
+    /// The line text without its trailing newline.
+    pub text: String,
+}
+
+/// A unified diff hunk: a group of nearby changes with surrounding context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The 0-indexed range of lines covered in the original text.
+    pub old_range: Range<usize>,
+    /// The 0-indexed range of lines covered in the formatted text.
+    pub new_range: Range<usize>,
+    /// The lines of the hunk, including context lines, in order.
+    pub lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    /// Returns the unified diff header of this hunk, e.g., `@@ -1,3 +1,4 @@`.
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{} +{} @@",
+            HunkRange(&self.old_range),
+            HunkRange(&self.new_range)
+        )
+    }
+}
+
+impl std::fmt::Display for Hunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.header())?;
+        for line in &self.lines {
+            writeln!(f, "{}{}", line.kind.sign(), line.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// A line range formatted as in unified diff hunk headers.
+struct HunkRange<'a>(&'a Range<usize>);
+
+impl std::fmt::Display for HunkRange<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.len() {
+            1 => write!(f, "{}", self.0.start + 1),
+            // Empty ranges refer to the line just before them
+            0 => write!(f, "{},0", self.0.start),
+            len => write!(f, "{},{}", self.0.start + 1, len),
+        }
+    }
+}
+
+/// Formats the input and returns the changes formatting would make.
+///
+/// Returns an empty vector if the input is already formatted.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{DiffLineKind, Options, diff};
+///
+/// let hunks = diff("Title\n===\n", &Options::default()).unwrap();
+/// assert_eq!(hunks.len(), 1);
+/// assert_eq!(hunks[0].header(), "@@ -1,2 +1,2 @@");
+/// assert_eq!(hunks[0].lines[1].kind, DiffLineKind::Removed);
+/// assert_eq!(hunks[0].lines[1].text, "===");
+/// ```
+pub fn diff(input: &str, options: &Options) -> Result<Vec<Hunk>, FormatError> {
+    let output = format(input, options)?;
+    Ok(diff_text(input, &output))
+}
+
+/// Returns the line-level changes between two texts as unified diff hunks
+/// with three lines of context.
+///
+/// This is useful when the formatted output is already at hand, e.g., from
+/// [`format_with_warnings`](crate::format_with_warnings).
+pub fn diff_text(old: &str, new: &str) -> Vec<Hunk> {
+    if old == new {
+        return Vec::new();
+    }
+    let diff = TextDiff::from_lines(old, new);
+    diff.unified_diff()
+        .iter_hunks()
+        .map(|hunk| {
+            let ops = hunk.ops();
+            let (first, last) = (ops[0], ops[ops.len() - 1]);
+            let lines = hunk
+                .iter_changes()
+                .map(|change| DiffLine {
+                    kind: match change.tag() {
+                        ChangeTag::Equal => DiffLineKind::Context,
+                        ChangeTag::Delete => DiffLineKind::Removed,
+                        ChangeTag::Insert => DiffLineKind::Added,
+                    },
+                    text: change.value().trim_end_matches('\n').to_string(),
+                })
+                .collect();
+            Hunk {
+                old_range: first.old_range().start..last.old_range().end,
+                new_range: first.new_range().start..last.new_range().end,
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// The result of [`check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    /// Whether the input is already formatted.
+    pub formatted: bool,
+    /// The 0-indexed ranges of lines in the input that formatting would
+    /// change, in document order.  An empty range means lines would be
+    /// inserted before that line.  Empty if the input is formatted.
+    pub changed_lines: Vec<Range<usize>>,
+}
+
+/// Checks whether the input is already formatted.
+///
+/// Unlike [`diff`], only the minimal ranges of differing input lines are
+/// reported, without context lines or the text of the changes.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{Options, check};
+///
+/// let result = check("Title\n=====\n\n* Item\n", &Options::default()).unwrap();
+/// assert!(!result.formatted);
+/// assert_eq!(result.changed_lines, vec![3..4]);
+/// ```
+pub fn check(input: &str, options: &Options) -> Result<CheckResult, FormatError> {
+    let output = format(input, options)?;
+    if input == output {
+        return Ok(CheckResult {
+            formatted: true,
+            changed_lines: Vec::new(),
+        });
+    }
+    let diff = TextDiff::from_lines(input, &output);
+    let mut changed_lines: Vec<Range<usize>> = Vec::new();
+    for op in diff.ops().iter().filter(|op| op.tag() != DiffTag::Equal) {
+        let range = op.old_range();
+        // Adjacent operations, e.g., a deletion followed by an insertion,
+        // belong to the same change
+        match changed_lines.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => changed_lines.push(range),
+        }
+    }
+    Ok(CheckResult {
+        formatted: false,
+        changed_lines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_text_identical() {
+        assert!(diff_text("a\nb\n", "a\nb\n").is_empty());
+    }
+
+    #[test]
+    fn test_diff_text_replace() {
+        let hunks = diff_text("a\nb\nc\n", "a\nB\nc\n");
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].old_range, 0..3);
+        assert_eq!(hunks[0].new_range, 0..3);
+        assert_eq!(hunks[0].to_string(), "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+    }
+
+    #[test]
+    fn test_diff_text_separate_hunks() {
+        let lines: Vec<String> = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let old = lines.concat();
+        let mut changed = lines.clone();
+        changed[1] = "two\n".to_string();
+        changed[18] = "nineteen\n".to_string();
+        let new = changed.concat();
+        let hunks = diff_text(&old, &new);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[1].header(), "@@ -16,5 +16,5 @@");
+    }
+
+    #[test]
+    fn test_hunk_range_header() {
+        let hunk = Hunk {
+            old_range: 4..4,
+            new_range: 4..5,
+            lines: Vec::new(),
+        };
+        assert_eq!(hunk.header(), "@@ -4,0 +5 @@");
+    }
+
+    #[test]
+    fn test_diff_formatted_input() {
+        let options = Options::default();
+        let formatted = format("Some *text*.", &options).unwrap();
+        assert!(diff(&formatted, &options).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_formatted() {
+        let options = Options::default();
+        let formatted = format("Some *text*.", &options).unwrap();
+        let result = check(&formatted, &options).unwrap();
+        assert!(result.formatted);
+        assert!(result.changed_lines.is_empty());
+    }
+
+    #[test]
+    fn test_check_changed_lines() {
+        let input = "Title\n===\n\nText.\n\n\n* a\n";
+        let result = check(input, &Options::default()).unwrap();
+        assert!(!result.formatted);
+        assert_eq!(result.changed_lines, vec![1..2, 4..5, 6..7]);
+    }
+}
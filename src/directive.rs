@@ -0,0 +1,230 @@
+//! User-defined comment directives.
+//!
+//! Besides its built-in `<!-- hongdown-... -->` comments, Hongdown can
+//! recognize directives registered by library users in
+//! [`Options::directives`].  A handler is called for each matching top-level
+//! HTML comment and can toggle formatting or inject content through the
+//! [`DirectiveContext`] it receives.
+
+#[cfg(doc)]
+use crate::Options;
+
+/// A handler for a user-defined comment directive.
+///
+/// A directive registered under the name `mytool-note` matches comments like
+/// `<!-- mytool-note -->`, `<!-- mytool-note: some args -->`, and
+/// `<!-- mytool-note some args -->`.  The handler receives the trimmed text
+/// after the name (without the colon) as `args`.  The comment itself is
+/// always kept in the output.
+///
+/// Closures with the matching signature implement this trait as well.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use hongdown::{DirectiveContext, Options, format};
+///
+/// let mut options = Options::default();
+/// options.directives.insert(
+///     "mytool-verbatim".to_string(),
+///     Arc::new(|_args: &str, context: &mut DirectiveContext| {
+///         context.disable_next_block();
+///     }),
+/// );
+/// let input = "<!-- mytool-verbatim -->\n\n*   Kept.\n\nText.\n\n*   Fixed.\n";
+/// let output = format(input, &options).unwrap();
+/// assert_eq!(
+///     output,
+///     "<!-- mytool-verbatim -->\n\n*   Kept.\n\nText.\n\n -  Fixed.\n"
+/// );
+/// ```
+pub trait DirectiveHandler: Send + Sync {
+    /// Handles an occurrence of the directive.
+    fn handle(&self, args: &str, context: &mut DirectiveContext);
+
+    /// Returns a name identifying this handler in debug output.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<F> DirectiveHandler for F
+where
+    F: Fn(&str, &mut DirectiveContext) + Send + Sync,
+{
+    fn handle(&self, args: &str, context: &mut DirectiveContext) {
+        self(args, context)
+    }
+}
+
+impl std::fmt::Debug for dyn DirectiveHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("DirectiveHandler")
+            .field(&self.name())
+            .finish()
+    }
+}
+
+/// A change of the formatting state requested by a directive handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SkipRequest {
+    NextBlock,
+    NextSection,
+    Disable,
+    Enable,
+}
+
+/// The serializer state exposed to a [`DirectiveHandler`].
+///
+/// Requested changes are applied after the handler returns.
+#[derive(Debug, Default)]
+pub struct DirectiveContext {
+    line: usize,
+    pub(crate) skip: Option<SkipRequest>,
+    pub(crate) proper_nouns: Vec<String>,
+    pub(crate) common_nouns: Vec<String>,
+    pub(crate) inserted: String,
+}
+
+impl DirectiveContext {
+    pub(crate) fn new(line: usize) -> Self {
+        DirectiveContext {
+            line,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the 1-indexed line number of the directive comment.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Leaves the next block unformatted, like
+    /// `<!-- hongdown-disable-next-line -->`.
+    pub fn disable_next_block(&mut self) {
+        self.skip = Some(SkipRequest::NextBlock);
+    }
+
+    /// Leaves everything up to the next section heading unformatted, like
+    /// `<!-- hongdown-disable-next-section -->`.
+    pub fn disable_next_section(&mut self) {
+        self.skip = Some(SkipRequest::NextSection);
+    }
+
+    /// Disables formatting from this point, like `<!-- hongdown-disable -->`.
+    pub fn disable(&mut self) {
+        self.skip = Some(SkipRequest::Disable);
+    }
+
+    /// Re-enables formatting, like `<!-- hongdown-enable -->`.
+    pub fn enable(&mut self) {
+        self.skip = Some(SkipRequest::Enable);
+    }
+
+    /// Adds proper nouns for sentence case, like
+    /// `<!-- hongdown-proper-nouns: ... -->`.
+    pub fn add_proper_nouns<I, S>(&mut self, nouns: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.proper_nouns.extend(nouns.into_iter().map(Into::into));
+    }
+
+    /// Adds common nouns for sentence case, like
+    /// `<!-- hongdown-common-nouns: ... -->`.
+    pub fn add_common_nouns<I, S>(&mut self, nouns: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.common_nouns.extend(nouns.into_iter().map(Into::into));
+    }
+
+    /// Inserts Markdown as a block right after the directive comment.
+    ///
+    /// The content is written as-is, without formatting.  Calling this more
+    /// than once appends to the inserted content.
+    pub fn insert(&mut self, markdown: &str) {
+        self.inserted.push_str(markdown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{Options, format};
+
+    fn options_with(name: &str, handler: Arc<dyn DirectiveHandler>) -> Options {
+        let mut options = Options::default();
+        options.directives.insert(name.to_string(), handler);
+        options
+    }
+
+    #[test]
+    fn test_custom_directive_insert() {
+        let options = options_with(
+            "mytool-toc",
+            Arc::new(|args: &str, context: &mut DirectiveContext| {
+                context.insert(&format!("TOC for {} (line {})", args, context.line()));
+            }),
+        );
+        let input = "# Title\n\n<!-- mytool-toc: docs -->\n\nText.\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(
+            output,
+            "Title\n=====\n\n<!-- mytool-toc: docs -->\n\nTOC for docs (line 3)\n\nText.\n"
+        );
+    }
+
+    #[test]
+    fn test_custom_directive_disable_and_enable() {
+        let mut options = Options::default();
+        options.directives.insert(
+            "mytool-off".to_string(),
+            Arc::new(|_: &str, context: &mut DirectiveContext| context.disable()),
+        );
+        options.directives.insert(
+            "mytool-on".to_string(),
+            Arc::new(|_: &str, context: &mut DirectiveContext| context.enable()),
+        );
+        let input = "<!-- mytool-off -->\n\n*   a\n\n<!-- mytool-on -->\n\n*   b\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(
+            output,
+            "<!-- mytool-off -->\n\n*   a\n\n<!-- mytool-on -->\n\n -  b\n"
+        );
+    }
+
+    #[test]
+    fn test_custom_directive_name_must_match_exactly() {
+        let options = options_with(
+            "mytool",
+            Arc::new(|_: &str, context: &mut DirectiveContext| context.insert("Inserted")),
+        );
+        let output = format("<!-- mytoolbox -->\n\nText.\n", &options).unwrap();
+        assert!(!output.contains("Inserted"), "{}", output);
+        let output = format("<!-- mytool with args -->\n\nText.\n", &options).unwrap();
+        assert!(output.contains("Inserted"), "{}", output);
+    }
+
+    #[test]
+    fn test_custom_directive_proper_nouns() {
+        let options = Options {
+            heading_sentence_case: true,
+            ..options_with(
+                "mytool-nouns",
+                Arc::new(|args: &str, context: &mut DirectiveContext| {
+                    context.add_proper_nouns(args.split(',').map(str::trim));
+                }),
+            )
+        };
+        let input =
+            "<!-- mytool-nouns: Hongdown -->\n\nUsing Hongdown Today\n--------------------\n";
+        let output = format(input, &options).unwrap();
+        assert!(output.contains("Using Hongdown today"), "{}", output);
+    }
+}
@@ -0,0 +1,800 @@
+//! Semantic equivalence verification for the formatter.
+//!
+//! Formatting should only change how a document is written, never what it
+//! says.  [`verify_equivalent`] parses the original input and the formatted
+//! output and compares their ASTs, ignoring differences that are a matter of
+//! style: heading and list marker styles, code fences, line wrapping,
+//! reference versus inline links, and the typographic punctuation and
+//! sentence case conversions Hongdown applies on purpose.  Anything else,
+//! such as lost text, merged list items, or dropped HTML, is reported.
+
+use comrak::Arena;
+use comrak::nodes::{AstNode, ListType, NodeValue, TableAlignment};
+use similar::{Algorithm, DiffTag, capture_diff_slices};
+
+use crate::config::{
+    AbbreviationPlacement, CjkQuotes, DashSetting, HeadingLinks, MentionStyle, TitleCase,
+};
+use crate::serializer::{
+    Warning, format_front_matter, is_br_tag, is_mention, normalize_url, rebase_url,
+};
+use crate::{BOM, Options, WarningCode, parse_markdown, transform};
+
+/// A single semantic change made by formatting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticChange {
+    /// The 1-indexed line number in the original input where the change is.
+    pub line: usize,
+    /// Nodes of the original document that were removed or altered, as
+    /// indented descriptions such as `paragraph` or `text "Hello"`.
+    pub removed: Vec<String>,
+    /// Nodes of the formatted document that were added in their place.
+    pub added: Vec<String>,
+}
+
+/// The semantic difference between a document and its formatted output.
+///
+/// Returned by [`verify_equivalent`] when formatting changed the meaning of
+/// the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticDiff {
+    /// The changes, in document order.
+    pub changes: Vec<SemanticChange>,
+}
+
+impl std::fmt::Display for SemanticDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Formatting changed the document; found {} semantic change(s):",
+            self.changes.len()
+        )?;
+        for change in &self.changes {
+            writeln!(f, "@@ line {} @@", change.line)?;
+            for node in &change.removed {
+                writeln!(f, "-{}", node)?;
+            }
+            for node in &change.added {
+                writeln!(f, "+{}", node)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SemanticDiff {}
+
+/// Verifies that `output`, the formatted form of `input`, means the same as
+/// `input`.
+///
+/// Both documents are parsed, and their ASTs are compared modulo style.
+/// [`Options::transforms`], as well as conversions enabled by options such as
+/// [`Options::alert_convert_bold_labels`], are applied to the input before
+/// the comparison, since their changes are intended.  Custom inline renderers and directive
+/// handlers may change the document on purpose as well; such changes are
+/// reported like any other.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{Options, format, verify_equivalent};
+///
+/// let options = Options::default();
+/// let input = "# Hello\n\n* Some *text*...\n* More\n";
+/// let output = format(input, &options).unwrap();
+/// assert!(verify_equivalent(input, &output, &options).is_ok());
+/// assert!(verify_equivalent(input, "Hello\n=====\n", &options).is_err());
+/// ```
+pub fn verify_equivalent(input: &str, output: &str, options: &Options) -> Result<(), SemanticDiff> {
+    // Line endings and byte order marks are a matter of style as well
+    let input = input.trim_start_matches(BOM).replace("\r\n", "\n");
+    let output = output.trim_start_matches(BOM).replace("\r\n", "\n");
+
+    let input_arena = Arena::new();
+    let input_root = parse_markdown(&input_arena, &input, &options.extensions);
+    transform::apply_transforms(input_root, &input_arena, options);
+    let expected = describe_document(input_root, options);
+
+    let output_arena = Arena::new();
+    let output_root = parse_markdown(&output_arena, &output, &options.extensions);
+    let actual = describe_document(output_root, options);
+
+    let expected_descriptions: Vec<&str> = expected.iter().map(|e| e.text.as_str()).collect();
+    let actual_descriptions: Vec<&str> = actual.iter().map(|e| e.text.as_str()).collect();
+    let changes: Vec<SemanticChange> = capture_diff_slices(
+        Algorithm::Myers,
+        &expected_descriptions,
+        &actual_descriptions,
+    )
+    .iter()
+    .filter(|op| op.tag() != DiffTag::Equal)
+    .map(|op| {
+        let old_range = op.old_range();
+        let line = expected
+            .get(old_range.start)
+            .or_else(|| expected.last())
+            .map_or(1, |event| event.line);
+        SemanticChange {
+            line,
+            removed: expected_descriptions[old_range]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            added: actual_descriptions[op.new_range()]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    })
+    .collect();
+
+    if changes.is_empty() {
+        Ok(())
+    } else {
+        Err(SemanticDiff { changes })
+    }
+}
+
+/// Descriptions of the inline nodes, whose changes come from escaping.
+const INLINE_DESCRIPTIONS: &[&str] = &[
+    "text ",
+    "soft break",
+    "line break",
+    "emph",
+    "strong",
+    "strikethrough",
+    "superscript",
+    "subscript",
+    "highlight",
+    "code \"",
+    "html \"",
+    "link <",
+    "image <",
+    "footnote reference ",
+    "math",
+];
+
+/// Re-parses `output`, the formatted form of `input`, and returns an
+/// `escaping` warning for each change of the inline content of a block,
+/// e.g., text that became emphasis or a link, or an escape that became
+/// visible.  Changes to blocks are left to [`verify_equivalent`].
+pub(crate) fn escaping_warnings(input: &str, output: &str, options: &Options) -> Vec<Warning> {
+    let Err(diff) = verify_equivalent(input, output, options) else {
+        return Vec::new();
+    };
+    let is_inline = |description: &String| {
+        let description = description.trim_start();
+        INLINE_DESCRIPTIONS
+            .iter()
+            .any(|prefix| description.starts_with(prefix))
+    };
+    let describe = |descriptions: &[String]| {
+        if descriptions.is_empty() {
+            "nothing".to_string()
+        } else {
+            descriptions
+                .iter()
+                .map(|description| description.trim_start())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+    diff.changes
+        .iter()
+        .filter(|change| change.removed.iter().chain(&change.added).all(is_inline))
+        .map(|change| {
+            Warning::new(
+                WarningCode::Escaping,
+                change.line,
+                0,
+                format!(
+                    "re-parsing the output gives {} instead of {}, which suggests \
+                     missing or excessive escaping",
+                    describe(&change.added),
+                    describe(&change.removed)
+                ),
+            )
+        })
+        .collect()
+}
+
+/// A node of a document, described in a style-independent way.
+struct Event {
+    /// The description, indented by the node's depth.
+    text: String,
+    /// The 1-indexed source line of the node.
+    line: usize,
+}
+
+/// An entry of the traversal stack of [`describe_document`].
+enum Item<'a> {
+    Node {
+        node: &'a AstNode<'a>,
+        depth: usize,
+        in_heading: bool,
+    },
+    /// A run of adjacent sibling nodes merged into a single event.
+    Run { run: Run, depth: usize },
+}
+
+/// The kinds of sibling nodes that are merged when adjacent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RunKind {
+    /// Text and soft line breaks, so that rewrapping does not count as a
+    /// change.
+    Text,
+    /// HTML blocks, which render the same with or without blank lines
+    /// between them.
+    HtmlBlock,
+}
+
+/// Adjacent sibling nodes of the same [`RunKind`].
+struct Run {
+    kind: RunKind,
+    content: String,
+    line: usize,
+}
+
+/// Describe every node of the document in pre-order.
+///
+/// The tree is walked iteratively so that deeply nested documents cannot
+/// overflow the stack.  Abbreviation definitions that the formatter groups
+/// and sorts are described at the end, sorted.
+fn describe_document<'a>(root: &'a AstNode<'a>, options: &Options) -> Vec<Event> {
+    let abbreviations = if options.abbreviation_placement == AbbreviationPlacement::Preserve {
+        Vec::new()
+    } else {
+        take_abbreviation_definitions(root)
+    };
+    let mut events = Vec::new();
+    let mut stack: Vec<Item<'a>> = Vec::new();
+    push_children(&mut stack, root, 0, false, options);
+    while let Some(item) = stack.pop() {
+        match item {
+            Item::Run { run, depth } => {
+                let description = match run.kind {
+                    RunKind::Text => format!("text {:?}", run.content),
+                    RunKind::HtmlBlock => format!("html block {:?}", normalize_html(&run.content)),
+                };
+                events.push(Event {
+                    text: format!("{}{}", "  ".repeat(depth), description),
+                    line: run.line,
+                });
+            }
+            Item::Node {
+                node,
+                depth,
+                in_heading,
+            } => {
+                let ast = node.data.borrow();
+                let description = describe_node(&ast.value, options);
+                let in_heading = in_heading || matches!(ast.value, NodeValue::Heading(_));
+                events.push(Event {
+                    text: format!("{}{}", "  ".repeat(depth), description),
+                    line: ast.sourcepos.start.line,
+                });
+                drop(ast);
+                push_children(&mut stack, node, depth + 1, in_heading, options);
+            }
+        }
+    }
+    events.extend(abbreviations.into_iter().map(|(definition, line)| Event {
+        text: format!("abbreviation {:?}", definition),
+        line,
+    }));
+    events
+}
+
+/// Detach the top-level paragraphs of the document that consist only of
+/// abbreviation definitions, and return the definitions sorted, without
+/// duplicates, along with their lines.
+fn take_abbreviation_definitions<'a>(root: &'a AstNode<'a>) -> Vec<(String, usize)> {
+    let mut definitions = Vec::new();
+    let paragraphs: Vec<_> = root
+        .children()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))
+        .collect();
+    for paragraph in paragraphs {
+        let mut text = String::new();
+        for node in paragraph.descendants() {
+            match &node.data.borrow().value {
+                NodeValue::Text(t) => text.push_str(t),
+                NodeValue::SoftBreak | NodeValue::LineBreak => text.push('\n'),
+                _ => {}
+            }
+        }
+        let lines: Vec<&str> = text.lines().map(str::trim).collect();
+        if lines
+            .iter()
+            .all(|line| line.starts_with("*[") && line.contains("]:"))
+        {
+            let line = paragraph.data.borrow().sourcepos.start.line;
+            definitions.extend(lines.into_iter().map(|def| (def.to_string(), line)));
+            paragraph.detach();
+        }
+    }
+    definitions.sort();
+    definitions.dedup_by(|a, b| a.0 == b.0);
+    definitions
+}
+
+/// Push the children of `node` onto the stack in reverse order, so that they
+/// are popped in document order.
+fn push_children<'a>(
+    stack: &mut Vec<Item<'a>>,
+    node: &'a AstNode<'a>,
+    depth: usize,
+    in_heading: bool,
+    options: &Options,
+) {
+    let mut items: Vec<Item<'a>> = Vec::new();
+    let mut current: Option<Run> = None;
+    // Raw HTML in table cells is escaped into text on request
+    let html_is_text = options.table_escape_html
+        && node
+            .ancestors()
+            .any(|ancestor| matches!(ancestor.data.borrow().value, NodeValue::TableCell));
+    let finish = |run: Run| {
+        let content = match run.kind {
+            RunKind::Text => normalize_text(&run.content, in_heading, options),
+            RunKind::HtmlBlock => run.content,
+        };
+        Item::Run {
+            run: Run { content, ..run },
+            depth,
+        }
+    };
+    for child in children_unwrapping_links(node, in_heading, options) {
+        let ast = child.data.borrow();
+        let piece = match &ast.value {
+            NodeValue::Text(text) => Some((RunKind::Text, text.as_ref())),
+            NodeValue::SoftBreak => Some((RunKind::Text, " ")),
+            NodeValue::HtmlInline(html) if html_is_text => Some((RunKind::Text, html.as_str())),
+            // Mentions are wrapped in code spans on request
+            NodeValue::Code(code)
+                if options.mentions == MentionStyle::Code && is_mention(&code.literal) =>
+            {
+                Some((RunKind::Text, code.literal.as_str()))
+            }
+            NodeValue::HtmlBlock(html) => Some((RunKind::HtmlBlock, html.literal.as_str())),
+            _ => None,
+        };
+        if current
+            .as_ref()
+            .is_some_and(|run| Some(run.kind) != piece.map(|(kind, _)| kind))
+            && let Some(run) = current.take()
+        {
+            items.push(finish(run));
+        }
+        match piece {
+            Some((kind, content)) => current
+                .get_or_insert_with(|| Run {
+                    kind,
+                    content: String::new(),
+                    line: ast.sourcepos.start.line,
+                })
+                .content
+                .push_str(content),
+            None => items.push(Item::Node {
+                node: child,
+                depth,
+                in_heading,
+            }),
+        }
+    }
+    if let Some(run) = current {
+        items.push(finish(run));
+    }
+    stack.extend(items.into_iter().rev());
+}
+
+/// Returns the children of `node`, with links replaced by their own children
+/// if links in headings are written as text, since that is intended.
+/// Autolinks are kept, as their text is the URL anyway.
+fn children_unwrapping_links<'a>(
+    node: &'a AstNode<'a>,
+    in_heading: bool,
+    options: &Options,
+) -> Vec<&'a AstNode<'a>> {
+    let unwrap = in_heading && options.heading_links == HeadingLinks::Text;
+    let mut children = Vec::new();
+    for child in node.children() {
+        let is_link = match &child.data.borrow().value {
+            NodeValue::Link(link) => {
+                let text: String = child
+                    .children()
+                    .filter_map(|grandchild| match &grandchild.data.borrow().value {
+                        NodeValue::Text(text) => Some(text.to_string()),
+                        _ => None,
+                    })
+                    .collect();
+                !(link.title.is_empty() && text == link.url)
+            }
+            _ => false,
+        };
+        if unwrap && is_link {
+            children.extend(children_unwrapping_links(child, in_heading, options));
+        } else {
+            children.push(child);
+        }
+    }
+    children
+}
+
+/// The destination of a link or image, rebased and normalized if the
+/// formatter does.  The output is within the link base already, so that
+/// rebasing leaves it as it is.
+fn url(url: &str, options: &Options) -> String {
+    let url = match rebase_url(url, &options.link_base) {
+        Ok(Some(rebased)) => rebased,
+        _ => url.to_string(),
+    };
+    match normalize_url(&url) {
+        Ok(Some(normalized)) if options.normalize_urls => normalized,
+        _ => url,
+    }
+}
+
+/// Describe a node without its children.
+fn describe_node(value: &NodeValue, options: &Options) -> String {
+    match value {
+        NodeValue::FrontMatter(literal) => {
+            format!("front matter {:?}", format_front_matter(literal, options))
+        }
+        NodeValue::List(list) => match list.list_type {
+            ListType::Bullet => format!("bullet list{}", tightness(list.tight)),
+            ListType::Ordered => {
+                format!("ordered list from {}{}", list.start, tightness(list.tight))
+            }
+        },
+        NodeValue::CodeBlock(code) => {
+            let language = code.info.split_whitespace().next().unwrap_or("");
+            let language = if language.is_empty() {
+                options.default_language.as_str()
+            } else {
+                language
+            };
+            let skip_format = code
+                .info
+                .split_whitespace()
+                .any(|word| word == "hongdown-no-format");
+            // External code formatters change the code on purpose
+            if !skip_format && options.code_formatters.contains_key(language) {
+                format!("code block {:?}", language)
+            } else if options.strip_trailing_whitespace {
+                let literal: Vec<&str> = code.literal.lines().map(str::trim_end).collect();
+                format!("code block {:?} {:?}", language, literal.join("\n"))
+            } else {
+                format!("code block {:?} {:?}", language, code.literal)
+            }
+        }
+        NodeValue::Heading(heading) => format!("heading {}", heading.level),
+        NodeValue::FootnoteDefinition(footnote) => {
+            format!("footnote definition {:?}", footnote.name)
+        }
+        NodeValue::Table(table) => {
+            // The colons of all-left-aligned tables are dropped on request
+            let all_left = !options.table_preserve_left_alignment
+                && table.alignments.iter().all(|a| *a == TableAlignment::Left);
+            let alignments: Vec<&str> = table
+                .alignments
+                .iter()
+                .map(|alignment| match alignment {
+                    TableAlignment::None => "none",
+                    TableAlignment::Left if all_left => "none",
+                    TableAlignment::Left => "left",
+                    TableAlignment::Center => "center",
+                    TableAlignment::Right => "right",
+                })
+                .collect();
+            format!("table [{}]", alignments.join(", "))
+        }
+        NodeValue::TableRow(header) => {
+            if *header {
+                "table header".to_string()
+            } else {
+                "table row".to_string()
+            }
+        }
+        NodeValue::TaskItem(task) => {
+            if task.symbol.is_some() {
+                "task item [x]".to_string()
+            } else {
+                "task item [ ]".to_string()
+            }
+        }
+        NodeValue::Code(code) => format!("code {:?}", code.literal),
+        // The style of line break tags is normalized on request
+        NodeValue::HtmlInline(html) if is_br_tag(html) => "html \"<br>\"".to_string(),
+        NodeValue::HtmlInline(html) => {
+            // Whitespace within a tag is insignificant, and inline HTML is
+            // rewrapped like text
+            let html: Vec<&str> = html.split_whitespace().collect();
+            format!("html {:?}", html.join(" "))
+        }
+        NodeValue::Link(link) => format!("link <{}> {:?}", url(&link.url, options), link.title),
+        NodeValue::Image(link) => format!("image <{}> {:?}", url(&link.url, options), link.title),
+        NodeValue::FootnoteReference(footnote) => {
+            format!("footnote reference {:?}", footnote.name)
+        }
+        NodeValue::Raw(header) => format!("raw {:?}", header),
+        NodeValue::Alert(alert) => format!(
+            "alert {:?} {:?}",
+            alert.alert_type,
+            alert.title.as_deref().unwrap_or("")
+        ),
+        // Style-only details of the remaining nodes, such as setext versus
+        // ATX headings or `*` versus `_` emphasis, are not part of the AST
+        // variant name
+        value => {
+            let name = format!("{:?}", value);
+            let name = name.split(['(', ' ', '{']).next().unwrap_or_default();
+            let mut description = String::new();
+            for (i, ch) in name.chars().enumerate() {
+                if ch.is_uppercase() && i > 0 {
+                    description.push(' ');
+                }
+                description.push(ch.to_ascii_lowercase());
+            }
+            description
+        }
+    }
+}
+
+fn tightness(tight: bool) -> &'static str {
+    if tight { " (tight)" } else { " (loose)" }
+}
+
+/// Normalize text so that the conversions Hongdown makes on purpose compare
+/// equal: whitespace runs, Unicode normalization, typographic quotes,
+/// ellipses, and dashes, as well as the letter case of headings when
+/// sentence case is enabled.
+fn normalize_text(text: &str, in_heading: bool, options: &Options) -> String {
+    let text = options.unicode_normalization.apply(text);
+    let mut normalized = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            if !in_whitespace {
+                normalized.push(' ');
+            }
+            in_whitespace = true;
+            continue;
+        }
+        in_whitespace = false;
+        match ch {
+            '\u{201C}' | '\u{201D}' => normalized.push('"'),
+            '\u{2018}' | '\u{2019}' => normalized.push('\''),
+            '\u{300C}' | '\u{300D}' | '\u{FF02}' if options.cjk_quotes != CjkQuotes::None => {
+                normalized.push('"')
+            }
+            '\u{300E}' | '\u{300F}' | '\u{FF07}' if options.cjk_quotes != CjkQuotes::None => {
+                normalized.push('\'')
+            }
+            '\u{2026}' => normalized.push_str("..."),
+            '\u{2013}' => push_dash(&mut normalized, &options.en_dash, ch),
+            '\u{2014}' => push_dash(&mut normalized, &options.em_dash, ch),
+            _ => normalized.push(ch),
+        }
+    }
+    if in_heading
+        && (options.heading_sentence_case || options.heading_title_case != TitleCase::None)
+    {
+        normalized.to_lowercase()
+    } else {
+        normalized
+    }
+}
+
+/// Write a dash as the pattern it is converted from, if any.
+fn push_dash(normalized: &mut String, setting: &DashSetting, dash: char) {
+    match setting {
+        DashSetting::Pattern(pattern) => normalized.push_str(pattern.as_str()),
+        DashSetting::Disabled => normalized.push(dash),
+    }
+}
+
+/// Normalize HTML blocks by ignoring trailing whitespace and blank lines.
+fn normalize_html(html: &str) -> String {
+    let lines: Vec<&str> = html
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format;
+
+    fn verify(input: &str, output: &str) -> Result<(), SemanticDiff> {
+        verify_equivalent(input, output, &Options::default())
+    }
+
+    #[test]
+    fn test_style_changes_are_equivalent() {
+        let input = "Title\n===\n\n* One\n* Two *emphasis* and \"quotes\"...\n\n\
+                     ```\ncode\n```\n\nSee [link][1] -- really.\n\n[1]: https://example.com/\n";
+        let output = format(input, &Options::default()).unwrap();
+        assert_ne!(input, output);
+        assert_eq!(verify(input, &output), Ok(()));
+    }
+
+    #[test]
+    fn test_rewrapping_is_equivalent() {
+        let input = "A paragraph that\nis wrapped at odd\nplaces.\n";
+        assert_eq!(
+            verify(input, "A paragraph that is wrapped at odd places.\n"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_lost_text() {
+        let diff = verify("One two three.\n", "One two.\n").unwrap_err();
+        assert_eq!(
+            diff.changes,
+            vec![SemanticChange {
+                line: 1,
+                removed: vec!["  text \"One two three.\"".to_string()],
+                added: vec!["  text \"One two.\"".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_merged_list_items() {
+        let diff = verify("- One\n- Two\n", "- One\n  Two\n").unwrap_err();
+        assert_eq!(diff.changes.len(), 1);
+        assert!(diff.changes[0].removed.contains(&"  item".to_string()));
+        assert_eq!(diff.changes[0].added, vec!["      text \"One Two\""]);
+    }
+
+    #[test]
+    fn test_dropped_html() {
+        let diff = verify("<div>\nHi\n</div>\n\nText.\n", "Text.\n").unwrap_err();
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(
+            diff.changes[0].removed,
+            vec!["html block \"<div>\\nHi\\n</div>\""]
+        );
+        assert!(diff.changes[0].added.is_empty());
+    }
+
+    #[test]
+    fn test_sentence_case_is_equivalent() {
+        let options = Options {
+            heading_sentence_case: true,
+            ..Options::default()
+        };
+        let input = "# Some Title Here\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(verify_equivalent(input, &output, &options), Ok(()));
+        assert!(verify(input, &output).is_err());
+    }
+
+    #[test]
+    fn test_heading_links_as_text_are_equivalent() {
+        let options = Options {
+            heading_links: crate::HeadingLinks::Text,
+            ..Options::default()
+        };
+        let input = "# Using [*Hongdown*](https://example.com) with <https://x.org>\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(verify_equivalent(input, &output, &options), Ok(()));
+        assert!(verify(input, &output).is_err());
+    }
+
+    #[test]
+    fn test_mentions_as_code_are_equivalent() {
+        let options = Options {
+            mentions: MentionStyle::Code,
+            ..Options::default()
+        };
+        let input = "Thanks @octocat and @my-org/team!\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(verify_equivalent(input, &output, &options), Ok(()));
+        assert!(verify(input, &output).is_err());
+    }
+
+    #[test]
+    fn test_front_matter_key_order_is_equivalent() {
+        let options = Options {
+            front_matter_key_order: vec!["title".to_string()],
+            ..Options::default()
+        };
+        let input = "---\ndate: 2025-01-01\ntitle: Hello\n---\n\nText\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(verify_equivalent(input, &output, &options), Ok(()));
+        assert!(verify(input, &output).is_err());
+    }
+
+    #[test]
+    fn test_grouped_abbreviations_are_equivalent() {
+        let options = Options {
+            abbreviation_placement: AbbreviationPlacement::Document,
+            ..Options::default()
+        };
+        let input = "# T\n\n*[ZZ]: Zed\n\nText ZZ and AA.\n\n*[AA]: Aye\n*[ZZ]: Zed\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(verify_equivalent(input, &output, &options), Ok(()));
+        assert!(verify(input, &output).is_err());
+        assert!(
+            verify_equivalent(input, "T\n=\n\nText ZZ and AA.\n\n*[AA]: Aye\n", &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_escaped_table_html_is_equivalent() {
+        let options = Options {
+            table_escape_html: true,
+            ..Options::default()
+        };
+        let input = "| a | b |\n|---|---|\n| <b>open | close</b> |\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(verify_equivalent(input, &output, &options), Ok(()));
+        assert!(verify(input, &output).is_err());
+    }
+
+    #[test]
+    fn test_strip_trailing_whitespace_is_equivalent() {
+        let options = Options {
+            strip_trailing_whitespace: true,
+            ..Options::default()
+        };
+        let input = "```\ncode  \n```\n";
+        let output = format(input, &options).unwrap();
+        assert!(verify_equivalent(input, &output, &options).is_ok());
+        assert!(verify(input, &output).is_err());
+    }
+
+    #[test]
+    fn test_unicode_normalization_is_equivalent() {
+        let options = Options {
+            unicode_normalization: crate::UnicodeNormalization::Nfc,
+            ..Options::default()
+        };
+        let input = "Cafe\u{301} au lait.\n";
+        let output = format(input, &options).unwrap();
+        assert_eq!(verify_equivalent(input, &output, &options), Ok(()));
+        assert!(verify(input, &output).is_err());
+    }
+
+    #[test]
+    fn test_admonition_body_is_compared_as_markdown() {
+        let input = "!!! note\n\n    * Some *text*...\n";
+        let output = format(input, &Options::default()).unwrap();
+        assert_eq!(verify(input, &output), Ok(()));
+        assert!(verify(input, "!!! note\n\n    * Other text\n").is_err());
+    }
+
+    #[test]
+    fn test_escaping_warnings() {
+        let options = Options::default();
+        let input = "Some \\*stars\\* and a [link](https://example.com).\n\nMore.\n";
+        let output = format(input, &options).unwrap();
+        assert!(escaping_warnings(input, &output, &options).is_empty());
+
+        let warnings = escaping_warnings(input, "Some *stars* and a link.\n\nMore.\n", &options);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            (warnings[0].code, warnings[0].line),
+            (WarningCode::Escaping, 1)
+        );
+        assert!(warnings[0].message.contains("emph"));
+
+        // Changes to blocks are not a matter of escaping
+        let output = "Some \\*stars\\* and a [link](https://example.com).\n";
+        assert!(escaping_warnings(input, output, &options).is_empty());
+    }
+
+    #[test]
+    fn test_semantic_diff_display() {
+        let diff = verify("# Title\n\nText.\n", "# Title\n").unwrap_err();
+        let display = diff.to_string();
+        assert!(display.contains("1 semantic change(s)"));
+        assert!(display.contains("@@ line 3 @@\n-paragraph\n-  text \"Text.\"\n"));
+    }
+}
@@ -0,0 +1,32 @@
+//! Markdown extensions recognized by the parser.
+//!
+//! Hongdown parses GitHub Flavored Markdown along with a few other
+//! extensions.  Documents that do not use an extension may still contain
+//! text that looks like its syntax, e.g., prose lines starting with `: `
+//! that comrak would read as a definition list.  [`Extensions`] turns such
+//! extensions off, so that the text is formatted as ordinary paragraphs.
+
+use serde::Deserialize;
+
+/// Markdown extensions to enable when parsing.  Every extension but
+/// [`wikilinks`](Self::wikilinks) is enabled by default.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Extensions {
+    /// Definition lists, i.e., terms followed by lines starting with `: `.
+    pub description_lists: bool,
+    /// Obsidian-style wikilinks and embeds, e.g., `[[note|alias]]` and
+    /// `![[note#section]]`, which are kept verbatim instead of being escaped
+    /// and reported as undefined references.  Wikilinks whose text contains
+    /// inline markup, e.g., `[[note|*alias*]]`, are formatted as usual.
+    pub wikilinks: bool,
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Self {
+            description_lists: true,
+            wikilinks: false,
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Formatting of files.
+//!
+//! Build tools and editor integrations format files rather than strings,
+//! and need the same handling the CLI gives them.  [`format_file`] reads
+//! a file, formats it, and tells whether formatting changed it, without
+//! writing anything back.
+
+use std::fs;
+use std::path::Path;
+
+use crate::{FormatError, Options, TraceEvent, Warning, format_with_warnings};
+
+/// The result of formatting a file with [`format_file`].
+#[derive(Debug)]
+pub struct FileOutcome {
+    /// The formatted Markdown output.
+    pub output: String,
+    /// Warnings generated during formatting.
+    pub warnings: Vec<Warning>,
+    /// Formatting decisions, if [`Options::trace`] is enabled.
+    pub trace: Vec<TraceEvent>,
+    /// Whether the output differs from the contents of the file.
+    pub changed: bool,
+}
+
+/// Reads and formats the file at `path`, without writing it back.
+///
+/// The file must be UTF-8.  Its byte order mark and line endings are kept
+/// or changed as [`Options::bom`] and [`Options::line_ending`] say, as with
+/// [`format`](crate::format).  External code formatters run in the
+/// directory of the file, unless [`Options::code_formatter_dir`] is set, so
+/// that they pick up the configuration files next to it.
+///
+/// # Errors
+///
+/// Returns [`FormatError::Io`] if the file cannot be read or is not valid
+/// UTF-8, and the errors of [`format`](crate::format) otherwise.
+///
+/// # Example
+///
+/// ```no_run
+/// use hongdown::{Options, format_file};
+///
+/// let outcome = format_file("README.md", &Options::default()).unwrap();
+/// if outcome.changed {
+///     std::fs::write("README.md", &outcome.output).unwrap();
+/// }
+/// ```
+pub fn format_file(path: impl AsRef<Path>, options: &Options) -> Result<FileOutcome, FormatError> {
+    let path = path.as_ref();
+    let input = fs::read_to_string(path).map_err(FormatError::Io)?;
+    let result = match path.parent() {
+        Some(dir)
+            if options.code_formatter_dir.is_none()
+                && (!options.code_formatters.is_empty()
+                    || options.allowed_code_formatters.is_some()) =>
+        {
+            let options = Options {
+                code_formatter_dir: Some(if dir.as_os_str().is_empty() {
+                    Path::new(".").to_path_buf()
+                } else {
+                    dir.to_path_buf()
+                }),
+                ..options.clone()
+            };
+            format_with_warnings(&input, &options)?
+        }
+        _ => format_with_warnings(&input, options)?,
+    };
+    Ok(FileOutcome {
+        changed: result.output != input,
+        output: result.output,
+        warnings: result.warnings,
+        trace: result.trace,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{CodeFormatter, LineEnding, WarningCode};
+
+    #[test]
+    fn test_format_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        fs::write(&path, "# Title\r\n\r\nSee [a][b].\r\n").unwrap();
+        let options = Options {
+            line_ending: LineEnding::Preserve,
+            ..Options::default()
+        };
+        let outcome = format_file(&path, &options).unwrap();
+        assert!(outcome.changed);
+        assert_eq!(outcome.output, "Title\r\n=====\r\n\r\nSee [a][b].\r\n");
+        assert_eq!(outcome.warnings[0].code, WarningCode::UndefinedReference);
+
+        fs::write(&path, &outcome.output).unwrap();
+        assert!(!format_file(&path, &options).unwrap().changed);
+    }
+
+    #[test]
+    fn test_format_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.md");
+        assert!(matches!(
+            format_file(&missing, &Options::default()),
+            Err(FormatError::Io(_))
+        ));
+        let binary = dir.path().join("binary.md");
+        fs::write(&binary, [0xff, 0xfe, 0x00]).unwrap();
+        assert!(matches!(
+            format_file(&binary, &Options::default()),
+            Err(FormatError::Io(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_format_file_runs_code_formatters_in_its_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        fs::write(&path, "~~~~ text\nplaceholder\n~~~~\n").unwrap();
+        let options = Options {
+            code_formatters: HashMap::from([(
+                "text".to_string(),
+                CodeFormatter {
+                    command: vec!["pwd".to_string()],
+                    timeout_secs: 5,
+                },
+            )]),
+            ..Options::default()
+        };
+        let outcome = format_file(&path, &options).unwrap();
+        let expected = dir.path().canonicalize().unwrap();
+        let ran_in = outcome.output.lines().nth(1).unwrap();
+        assert_eq!(Path::new(ran_in).canonicalize().unwrap(), expected);
+    }
+}
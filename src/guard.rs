@@ -0,0 +1,119 @@
+//! Conversion of internal panics into errors.
+//!
+//! A bug in the formatter, or in a user-supplied transform, renderer, or
+//! directive handler, should not abort the process of an editor or language
+//! server that formats documents through the library.  [`catch`] runs
+//! formatting under [`std::panic::catch_unwind`] and turns a panic into
+//! [`FormatError::Internal`], carrying the panic message and the source
+//! location it was raised at.
+//!
+//! The location is recorded by a panic hook, installed once, that also keeps
+//! caught panics from being printed to stderr.  Panics outside of [`catch`]
+//! are passed on to the previously installed hook.  Panics cannot be caught
+//! when the binary is built with `panic = "abort"`, which includes WASM.
+
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use crate::FormatError;
+
+thread_local! {
+    /// How many calls of [`catch`] are active on this thread.
+    static CATCHING: Cell<usize> = const { Cell::new(0) };
+    /// The location of the last panic caught on this thread.
+    static LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.with(Cell::get) > 0 {
+                let location = info.location().map(|location| location.to_string());
+                LOCATION.with(|cell| *cell.borrow_mut() = location);
+            } else {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// Run `f`, converting a panic into [`FormatError::Internal`].
+pub(crate) fn catch<T>(f: impl FnOnce() -> Result<T, FormatError>) -> Result<T, FormatError> {
+    install_hook();
+    LOCATION.with(|cell| cell.borrow_mut().take());
+    CATCHING.with(|count| count.set(count.get() + 1));
+    // Nothing observes the state touched by `f` after a panic, since all of
+    // it is created within the formatting call
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|count| count.set(count.get() - 1));
+    result.unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        // Panics raised on other threads, e.g., by parallel section
+        // serialization, are resumed here without a recorded location
+        let location = LOCATION.with(|cell| cell.borrow_mut().take());
+        Err(FormatError::Internal { message, location })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use comrak::Arena;
+    use comrak::nodes::AstNode;
+
+    use super::*;
+    use crate::{AstTransform, Options, format, format_with_warnings};
+
+    struct PanickingTransform;
+
+    impl AstTransform for PanickingTransform {
+        fn transform<'a>(&self, _root: &'a AstNode<'a>, _arena: &'a Arena<'a>) {
+            panic!("transform failed on purpose");
+        }
+    }
+
+    #[test]
+    fn test_catch_returns_value() {
+        assert_eq!(catch(|| Ok(42)).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_catch_panic_with_location() {
+        let error = catch::<()>(|| panic!("index {} out of range", 3)).unwrap_err();
+        match error {
+            FormatError::Internal { message, location } => {
+                assert_eq!(message, "index 3 out of range");
+                assert!(location.unwrap().starts_with("src/guard.rs:"));
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_format_catches_panics() {
+        let options = Options {
+            transforms: vec![Arc::new(PanickingTransform)],
+            ..Options::default()
+        };
+        let error = format("Text.\n", &options).unwrap_err();
+        assert!(matches!(error, FormatError::Internal { .. }));
+        assert!(
+            error
+                .to_string()
+                .starts_with("Internal error at src/guard.rs:")
+        );
+        assert!(error.to_string().ends_with(": transform failed on purpose"));
+        assert!(format_with_warnings("Text.\n", &options).is_err());
+        // The formatter keeps working after a caught panic
+        assert_eq!(format("Text.\n", &Options::default()).unwrap(), "Text.\n");
+    }
+}
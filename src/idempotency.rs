@@ -0,0 +1,160 @@
+//! Idempotency verification for the formatter.
+//!
+//! Formatting an already formatted document must not change it.  This module
+//! provides [`verify_idempotent`], which runs the formatter twice and reports
+//! the changes made by the second pass, if any.
+
+use similar::{DiffTag, TextDiff};
+
+use crate::{Options, format};
+
+/// A single change made by the second formatting pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyChange {
+    /// The 1-indexed line number in the first pass output where the change
+    /// starts.
+    pub line: usize,
+    /// Lines from the first pass output that were removed or replaced.
+    pub removed: Vec<String>,
+    /// Lines in the second pass output that were added in their place.
+    pub added: Vec<String>,
+}
+
+/// The difference between two formatting passes of the same input.
+///
+/// Returned by [`verify_idempotent`] when formatting is not stable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyDiff {
+    /// Output of formatting the original input.
+    pub first_pass: String,
+    /// Output of formatting the first pass output again.
+    pub second_pass: String,
+    /// The changes made by the second pass, in document order.
+    pub changes: Vec<IdempotencyChange>,
+}
+
+impl std::fmt::Display for IdempotencyDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Formatting is not idempotent; the second pass made {} change(s):",
+            self.changes.len()
+        )?;
+        for change in &self.changes {
+            writeln!(f, "@@ line {} @@", change.line)?;
+            for line in &change.removed {
+                writeln!(f, "-{}", line)?;
+            }
+            for line in &change.added {
+                writeln!(f, "+{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for IdempotencyDiff {}
+
+/// Verifies that formatting the given input is idempotent.
+///
+/// The input is formatted once, and the result is formatted again.  If the
+/// second pass changes anything, an [`IdempotencyDiff`] describing the
+/// stabilizing changes is returned.
+///
+/// Inputs that fail to format are not considered unstable; use [`format`] to
+/// surface formatting errors.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{Options, verify_idempotent};
+///
+/// let input = "# Hello\n\nSome *text*.";
+/// assert!(verify_idempotent(input, &Options::default()).is_ok());
+/// ```
+pub fn verify_idempotent(input: &str, options: &Options) -> Result<(), IdempotencyDiff> {
+    let Ok(first_pass) = format(input, options) else {
+        return Ok(());
+    };
+    let Ok(second_pass) = format(&first_pass, options) else {
+        return Ok(());
+    };
+    if first_pass == second_pass {
+        return Ok(());
+    }
+
+    let changes = diff_changes(&first_pass, &second_pass);
+    Err(IdempotencyDiff {
+        first_pass,
+        second_pass,
+        changes,
+    })
+}
+
+/// Collect the line-level changes between two strings.
+fn diff_changes(old: &str, new: &str) -> Vec<IdempotencyChange> {
+    let diff = TextDiff::from_lines(old, new);
+    let old_lines: Vec<&str> = diff.old_slices().to_vec();
+    let new_lines: Vec<&str> = diff.new_slices().to_vec();
+    let strip = |line: &&str| line.trim_end_matches('\n').to_string();
+
+    diff.ops()
+        .iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .map(|op| IdempotencyChange {
+            line: op.old_range().start + 1,
+            removed: old_lines[op.old_range()].iter().map(strip).collect(),
+            added: new_lines[op.new_range()].iter().map(strip).collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_changes_replace() {
+        let changes = diff_changes("a\nb\nc\n", "a\nB\nc\n");
+        assert_eq!(
+            changes,
+            vec![IdempotencyChange {
+                line: 2,
+                removed: vec!["b".to_string()],
+                added: vec!["B".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_changes_insert_and_delete() {
+        let changes = diff_changes("a\nb\n", "a\n\nb\n");
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].line, 2);
+        assert!(changes[0].removed.is_empty());
+        assert_eq!(changes[0].added, vec![String::new()]);
+
+        let changes = diff_changes("a\n\nb\n", "a\nb\n");
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].removed, vec![String::new()]);
+        assert!(changes[0].added.is_empty());
+    }
+
+    #[test]
+    fn test_verify_idempotent_stable() {
+        let input = "# Title\n\n * One\n * Two\n\nA [link](https://example.com).";
+        assert_eq!(verify_idempotent(input, &Options::default()), Ok(()));
+    }
+
+    #[test]
+    fn test_idempotency_diff_display() {
+        let diff = IdempotencyDiff {
+            first_pass: "a\nb\n".to_string(),
+            second_pass: "a\nB\n".to_string(),
+            changes: diff_changes("a\nb\n", "a\nB\n"),
+        };
+        let display = diff.to_string();
+        assert!(display.contains("1 change(s)"));
+        assert!(display.contains("@@ line 2 @@\n-b\n+B\n"));
+    }
+}
@@ -0,0 +1,1529 @@
+//! Options shared by the JavaScript bindings (WASM and N-API).
+//!
+//! Both bindings accept the same camelCase options object, so it is defined
+//! here once and converted to [`Options`] for formatting.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Options;
+use crate::config::{
+    AbbreviationPlacement, AlertName, AutolinkHost, BadgeLayout, BrStyle, ByteOrderMark, CjkQuotes,
+    Config, DashPattern, DashSetting, DefinitionMarkerSpaces, DelimiterStyle, FenceChar,
+    FigureStyle, FinalNewline, HeadingBlankLines, HeadingLinks, HtmlImageAttributes, IndentWidth,
+    LeadingSpaces, LineEnding, LineWidth, LinkReferenceStyle, LinkTitleStyle, MaxBlankLines,
+    MentionStyle, MinFenceLength, OrderedListPad, OrderedMarker, OverlongHeading, OverlongHeadings,
+    Profile, RaggedRows, ReferenceLabelCase, SetextUnderline, TabWidth, ThematicBreakStyle,
+    TitleCase, TrailingSpaces, UnderlineMeasure, UnicodeNormalization, UnorderedMarker,
+};
+
+/// JavaScript-friendly options struct.
+///
+/// All fields are optional and use camelCase naming for JavaScript conventions.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JsOptions {
+    /// Line width for wrapping (default: 80).
+    pub line_width: Option<usize>,
+
+    /// Unicode normalization of prose text: "none", "nfc", or "nfkc"
+    /// (default: "none").
+    pub unicode_normalization: Option<String>,
+
+    /// What to do with a byte order mark at the start of the input: "strip"
+    /// or "preserve" (default: "strip").
+    pub bom: Option<String>,
+
+    /// Line ending of the output: "preserve", "lf", or "crlf" (default:
+    /// "lf").
+    pub line_ending: Option<String>,
+
+    /// Strip trailing whitespace from every line except hard line breaks
+    /// (default: false).
+    pub strip_trailing_whitespace: Option<bool>,
+
+    /// Whether the output ends with a newline: "always", "never", or
+    /// "preserve" (default: "always").
+    pub final_newline: Option<String>,
+
+    /// Width of a tab stop in columns (default: 4).
+    pub tab_width: Option<usize>,
+
+    /// Expand tabs in prose to spaces (default: false).
+    pub expand_tabs: Option<bool>,
+
+    /// Maximum number of consecutive blank lines kept between top-level
+    /// blocks (default: 1).
+    pub max_blank_lines: Option<usize>,
+
+    /// Escape underscores in the middle of words (default: true).
+    pub escape_underscores: Option<bool>,
+
+    /// Renumber numeric reference labels by first use (default: false).
+    pub renumber_references: Option<bool>,
+
+    /// Code hosting service whose shorthand references are kept intact:
+    /// "github", "gitlab", or "none" (default: "github").
+    pub autolink_host: Option<String>,
+
+    /// How `@username` mentions in prose are written: "keep", "escape", or
+    /// "code" (default: "keep").
+    pub mentions: Option<String>,
+
+    /// A set of conventions for a kind of document: "default" or
+    /// "changelog" (default: "default").
+    pub profile: Option<String>,
+
+    /// How link titles are quoted: "double", "single", or "paren"
+    /// (default: "double").
+    pub link_title_style: Option<String>,
+
+    /// How text-equals-label references are written: "shortcut" or
+    /// "collapsed" (default: "shortcut").
+    pub link_reference_style: Option<String>,
+
+    /// How reference labels are cased: "preserve" or "lower" (default:
+    /// "preserve").
+    pub reference_label_case: Option<String>,
+
+    /// Normalize link and image destinations (default: false).
+    pub normalize_urls: Option<bool>,
+
+    /// Base path to join relative link destinations against (default: "").
+    pub link_base: Option<String>,
+
+    /// Warn where re-parsing the output changes its inline content
+    /// (default: false).
+    pub verify_escaping: Option<bool>,
+
+    /// Warn if formatting the output again changes it (default: false).
+    pub verify_stability: Option<bool>,
+
+    /// Use setext-style for h1 headings (default: true).
+    pub setext_h1: Option<bool>,
+
+    /// Use setext-style for h2 headings (default: true).
+    pub setext_h2: Option<bool>,
+
+    /// Length of setext underlines: "match", "full-width", or a number
+    /// (default: "match").
+    pub setext_underline: Option<JsSetextUnderline>,
+
+    /// How the heading text is measured for `setextUnderline`:
+    /// "display-width" or "characters" (default: "display-width").
+    pub setext_underline_measure: Option<String>,
+
+    /// Convert headings to sentence case (default: false).
+    pub heading_sentence_case: Option<bool>,
+
+    /// Additional proper nouns to preserve in sentence case.
+    /// These are merged with built-in proper nouns.
+    pub heading_proper_nouns: Option<Vec<String>>,
+
+    /// Words to treat as common nouns in sentence case.
+    /// These are excluded from built-in proper nouns.
+    pub heading_common_nouns: Option<Vec<String>>,
+
+    /// Blank lines before top-level headings of each level from h1 to h6
+    /// (default: [1, 2, 1, 1, 1, 1]).
+    pub heading_blank_lines_before: Option<[usize; 6]>,
+
+    /// Write a single blank line between consecutive headings (default: true).
+    pub heading_collapse_consecutive: Option<bool>,
+
+    /// Blank lines between a top-level heading and the block after it
+    /// (default: 1).
+    pub heading_blank_lines_after: Option<usize>,
+
+    /// What to do with headings wider than the line width: "keep", "warn",
+    /// "atx", or "wrap", or an array of six of them (default: "keep").
+    pub heading_overlong: Option<JsOverlongHeadings>,
+
+    /// How links inside headings are written: "reference", "inline", or
+    /// "text" (default: "reference").
+    pub heading_links: Option<String>,
+
+    /// Title case style for headings: "none", "simple", "ap", or "chicago"
+    /// (default: "none").
+    pub heading_title_case: Option<String>,
+
+    /// Marker for unordered lists: "-", "*", or "+" (default: "-").
+    pub unordered_marker: Option<String>,
+
+    /// Leading spaces before list marker (default: 1).
+    pub leading_spaces: Option<usize>,
+
+    /// Trailing spaces after list marker (default: 2).
+    pub trailing_spaces: Option<usize>,
+
+    /// Indent width for nested items (default: 4).
+    pub indent_width: Option<usize>,
+
+    /// Marker for odd-level ordered lists (default: ".").
+    pub odd_level_marker: Option<String>,
+
+    /// Marker for even-level ordered lists (default: ")").
+    pub even_level_marker: Option<String>,
+
+    /// Padding style for ordered list numbers: "start" or "end" (default: "start").
+    pub ordered_list_pad: Option<String>,
+
+    /// Indent width for nested ordered lists (default: 4).
+    pub ordered_list_indent_width: Option<usize>,
+
+    /// Spaces after the `:` marker of a definition (default: 3).
+    pub definition_marker_spaces: Option<usize>,
+
+    /// Indent width for the content of a definition (default: 4).
+    pub definition_indent_width: Option<usize>,
+
+    /// Abbreviation placement: "preserve", "section", or "document"
+    /// (default: "preserve").
+    pub abbreviation_placement: Option<String>,
+
+    /// Canonical order of the top-level keys of YAML front matter
+    /// (default: []).
+    pub front_matter_key_order: Option<Vec<String>>,
+
+    /// Whether to normalize front matter dates (default: false).
+    pub front_matter_normalize_dates: Option<bool>,
+
+    /// Format of normalized front matter dates (default: "%Y-%m-%d").
+    pub front_matter_date_format: Option<String>,
+
+    /// Format of normalized front matter dates with a time
+    /// (default: "%Y-%m-%dT%H:%M:%S%z").
+    pub front_matter_datetime_format: Option<String>,
+
+    /// Badge layout: "preserve", "line", or "stack" (default: "preserve").
+    pub badge_layout: Option<String>,
+
+    /// Convert HTML `<img>` elements into Markdown images (default: false).
+    pub image_convert_html: Option<bool>,
+
+    /// What to do with `<img>` elements that have other attributes: "skip"
+    /// or "preserve" (default: "skip").
+    pub image_html_attributes: Option<String>,
+
+    /// Figure style: "preserve", "markdown", or "html" (default: "preserve").
+    pub figure_style: Option<String>,
+
+    /// Fence character: "~" or "`" (default: "~").
+    pub fence_char: Option<String>,
+
+    /// Minimum fence length (default: 4).
+    pub min_fence_length: Option<usize>,
+
+    /// Space after fence character (default: true).
+    pub space_after_fence: Option<bool>,
+
+    /// Default language for code blocks (default: "").
+    pub default_language: Option<String>,
+
+    /// Warn about lines of code blocks wider than `codeLineWidth`
+    /// (default: false).
+    pub warn_long_code_lines: Option<bool>,
+
+    /// Line width for `warnLongCodeLines` (default: `lineWidth`).
+    pub code_line_width: Option<usize>,
+
+    /// Tab widths to expand leading tabs in code blocks with, by language
+    /// (default: {}).
+    pub code_expand_tabs: Option<HashMap<String, usize>>,
+
+    /// Thematic break style (default: spaced dashes).
+    pub thematic_break_style: Option<String>,
+
+    /// Leading spaces for thematic breaks (default: 3).
+    pub thematic_break_leading_spaces: Option<usize>,
+
+    /// Escape raw HTML in table cells (default: false).
+    pub table_escape_html: Option<bool>,
+
+    /// How to write `<br>` tags in table cells: "preserve", "<br>", "<br/>",
+    /// or "<br />" (default: "preserve").
+    pub table_br_style: Option<String>,
+
+    /// How to handle table rows with more or fewer cells than the header
+    /// row: "warn", "drop", or "merge" (default: "warn").
+    pub table_ragged_rows: Option<String>,
+
+    /// Delimiter row style: "padded", "minimal", or "fixed" (default:
+    /// "padded").
+    pub table_delimiter_style: Option<String>,
+
+    /// Keep the colons of all-left-aligned tables (default: true).
+    pub table_preserve_left_alignment: Option<bool>,
+
+    /// Alert types to replace with another type, matched case-insensitively
+    /// (default: none).
+    pub alert_synonyms: Option<HashMap<String, String>>,
+
+    /// Rewrite bold labels such as `**Note:**` as alerts (default: false).
+    pub alert_convert_bold_labels: Option<bool>,
+
+    /// Convert straight double quotes to curly (default: true).
+    pub curly_double_quotes: Option<bool>,
+
+    /// Convert straight single quotes to curly (default: true).
+    pub curly_single_quotes: Option<bool>,
+
+    /// Convert apostrophes to curly (default: false).
+    pub curly_apostrophes: Option<bool>,
+
+    /// Convert ... to ellipsis (default: true).
+    pub ellipsis: Option<bool>,
+
+    /// En-dash setting: false to disable, or a string pattern (default: false).
+    pub en_dash: Option<JsDashSetting>,
+
+    /// Em-dash setting: false to disable, or a string pattern (default: "--").
+    pub em_dash: Option<JsDashSetting>,
+
+    /// Quotation marks for straight quotes around mostly CJK text: "none",
+    /// "corner", or "full-width" (default: "none").
+    pub cjk_quotes: Option<String>,
+
+    /// Maximum input size in bytes (default: no limit).
+    pub max_input_bytes: Option<usize>,
+
+    /// Maximum nesting depth of the document (default: no limit).
+    pub max_nesting_depth: Option<usize>,
+
+    /// Maximum number of columns in a table (default: no limit).
+    pub max_table_columns: Option<usize>,
+
+    /// Recognize definition lists (default: true).
+    pub description_lists: Option<bool>,
+
+    /// Keep Obsidian wikilinks and embeds verbatim (default: false).
+    pub wikilinks: Option<bool>,
+}
+
+/// JavaScript-friendly setext underline length.
+///
+/// Can be either a mode name or a fixed number of characters.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum JsSetextUnderline {
+    /// Fixed number of underline characters.
+    Fixed(usize),
+    /// `"match"` or `"full-width"`.
+    Mode(String),
+}
+
+impl JsSetextUnderline {
+    pub(crate) fn to_setext_underline(&self) -> Result<SetextUnderline, String> {
+        match self {
+            JsSetextUnderline::Fixed(length) => SetextUnderline::fixed(*length),
+            JsSetextUnderline::Mode(name) => SetextUnderline::from_name(name),
+        }
+    }
+}
+
+impl From<SetextUnderline> for JsSetextUnderline {
+    fn from(underline: SetextUnderline) -> Self {
+        match underline {
+            SetextUnderline::Match => JsSetextUnderline::Mode("match".to_string()),
+            SetextUnderline::FullWidth => JsSetextUnderline::Mode("full-width".to_string()),
+            SetextUnderline::Fixed(length) => JsSetextUnderline::Fixed(length),
+        }
+    }
+}
+
+/// JavaScript-friendly overlong heading policies.
+///
+/// Can be either a single policy for all levels or an array of six.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum JsOverlongHeadings {
+    /// Same policy for all levels.
+    All(String),
+    /// One policy for each level from h1 to h6.
+    PerLevel(Vec<String>),
+}
+
+impl JsOverlongHeadings {
+    pub(crate) fn to_overlong_headings(&self) -> Result<OverlongHeadings, String> {
+        match self {
+            JsOverlongHeadings::All(value) => parse_overlong_heading(value).map(Into::into),
+            JsOverlongHeadings::PerLevel(values) => {
+                let policies = values
+                    .iter()
+                    .map(|value| parse_overlong_heading(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let policies: [OverlongHeading; 6] = policies.try_into().map_err(|_| {
+                    format!("headingOverlong must have 6 entries, got {}.", values.len())
+                })?;
+                Ok(OverlongHeadings::new(policies))
+            }
+        }
+    }
+}
+
+/// JavaScript-friendly dash setting.
+///
+/// Can be either `false` (disabled) or a string pattern.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum JsDashSetting {
+    /// Disabled when false.
+    Disabled(bool),
+    /// Pattern to transform to dash.
+    Pattern(String),
+}
+
+impl JsDashSetting {
+    pub(crate) fn to_dash_setting(&self) -> Result<DashSetting, String> {
+        match self {
+            JsDashSetting::Disabled(false) => Ok(DashSetting::Disabled),
+            JsDashSetting::Disabled(true) => Ok(DashSetting::Disabled),
+            JsDashSetting::Pattern(s) => DashPattern::new(s.clone()).map(DashSetting::Pattern),
+        }
+    }
+}
+
+impl JsOptions {
+    /// Parse options from a JSON string.
+    pub(crate) fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("invalid options JSON: {}.", e))
+    }
+
+    /// Convert JavaScript options to Rust Options.
+    ///
+    /// Returns an error message if any option has an invalid value.
+    pub(crate) fn to_options(&self) -> Result<Options, String> {
+        let mut opts = Options::default();
+
+        if let Some(v) = self.line_width {
+            opts.line_width = LineWidth::new(v)?;
+        }
+        if let Some(ref v) = self.unicode_normalization {
+            opts.unicode_normalization = match v.as_str() {
+                "none" => UnicodeNormalization::None,
+                "nfc" => UnicodeNormalization::Nfc,
+                "nfkc" => UnicodeNormalization::Nfkc,
+                _ => {
+                    return Err(format!(
+                        "unicodeNormalization must be \"none\", \"nfc\", or \"nfkc\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.bom {
+            opts.bom = match v.as_str() {
+                "strip" => ByteOrderMark::Strip,
+                "preserve" => ByteOrderMark::Preserve,
+                _ => {
+                    return Err(format!(
+                        "bom must be \"strip\" or \"preserve\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.line_ending {
+            opts.line_ending = match v.as_str() {
+                "preserve" => LineEnding::Preserve,
+                "lf" => LineEnding::Lf,
+                "crlf" => LineEnding::Crlf,
+                _ => {
+                    return Err(format!(
+                        "lineEnding must be \"preserve\", \"lf\", or \"crlf\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(v) = self.strip_trailing_whitespace {
+            opts.strip_trailing_whitespace = v;
+        }
+        if let Some(ref v) = self.final_newline {
+            opts.final_newline = match v.as_str() {
+                "always" => FinalNewline::Always,
+                "never" => FinalNewline::Never,
+                "preserve" => FinalNewline::Preserve,
+                _ => {
+                    return Err(format!(
+                        "finalNewline must be \"always\", \"never\", or \"preserve\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(v) = self.tab_width {
+            opts.tab_width = TabWidth::new(v)?;
+        }
+        if let Some(v) = self.expand_tabs {
+            opts.expand_tabs = v;
+        }
+        if let Some(v) = self.max_blank_lines {
+            opts.max_blank_lines = MaxBlankLines::new(v)?;
+        }
+        if let Some(v) = self.escape_underscores {
+            opts.escape_underscores = v;
+        }
+        if let Some(v) = self.renumber_references {
+            opts.renumber_references = v;
+        }
+        if let Some(ref v) = self.autolink_host {
+            opts.autolink_host = match v.as_str() {
+                "github" => AutolinkHost::GitHub,
+                "gitlab" => AutolinkHost::GitLab,
+                "none" => AutolinkHost::None,
+                _ => {
+                    return Err(format!(
+                        "autolinkHost must be \"github\", \"gitlab\", or \"none\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.mentions {
+            opts.mentions = match v.as_str() {
+                "keep" => MentionStyle::Keep,
+                "escape" => MentionStyle::Escape,
+                "code" => MentionStyle::Code,
+                _ => {
+                    return Err(format!(
+                        "mentions must be \"keep\", \"escape\", or \"code\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.profile {
+            opts.profile = match v.as_str() {
+                "default" => Profile::Default,
+                "changelog" => Profile::Changelog,
+                _ => {
+                    return Err(format!(
+                        "profile must be \"default\" or \"changelog\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.link_title_style {
+            opts.link_title_style = match v.as_str() {
+                "double" => LinkTitleStyle::Double,
+                "single" => LinkTitleStyle::Single,
+                "paren" => LinkTitleStyle::Paren,
+                _ => {
+                    return Err(format!(
+                        "linkTitleStyle must be \"double\", \"single\", or \"paren\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.link_reference_style {
+            opts.link_reference_style = match v.as_str() {
+                "shortcut" => LinkReferenceStyle::Shortcut,
+                "collapsed" => LinkReferenceStyle::Collapsed,
+                _ => {
+                    return Err(format!(
+                        "linkReferenceStyle must be \"shortcut\" or \"collapsed\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.reference_label_case {
+            opts.reference_label_case = match v.as_str() {
+                "preserve" => ReferenceLabelCase::Preserve,
+                "lower" => ReferenceLabelCase::Lower,
+                _ => {
+                    return Err(format!(
+                        "referenceLabelCase must be \"preserve\" or \"lower\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(v) = self.normalize_urls {
+            opts.normalize_urls = v;
+        }
+        if let Some(ref v) = self.link_base {
+            opts.link_base = v.clone();
+        }
+        if let Some(v) = self.verify_escaping {
+            opts.verify_escaping = v;
+        }
+        if let Some(v) = self.verify_stability {
+            opts.verify_stability = v;
+        }
+        if let Some(v) = self.setext_h1 {
+            opts.setext_h1 = v;
+        }
+        if let Some(v) = self.setext_h2 {
+            opts.setext_h2 = v;
+        }
+        if let Some(ref v) = self.setext_underline {
+            opts.setext_underline = v.to_setext_underline()?;
+        }
+        if let Some(ref v) = self.setext_underline_measure {
+            opts.setext_underline_measure = match v.as_str() {
+                "display-width" => UnderlineMeasure::DisplayWidth,
+                "characters" => UnderlineMeasure::Characters,
+                _ => {
+                    return Err(format!(
+                        "setextUnderlineMeasure must be \"display-width\" or \"characters\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(v) = self.heading_sentence_case {
+            opts.heading_sentence_case = v;
+        }
+        if let Some(ref v) = self.heading_proper_nouns {
+            opts.heading_proper_nouns = v.clone();
+        }
+        if let Some(ref v) = self.heading_common_nouns {
+            opts.heading_common_nouns = v.clone();
+        }
+        if let Some(v) = self.heading_blank_lines_before {
+            opts.heading_blank_lines_before = HeadingBlankLines::new(v)?;
+        }
+        if let Some(v) = self.heading_collapse_consecutive {
+            opts.heading_collapse_consecutive = v;
+        }
+        if let Some(v) = self.heading_blank_lines_after {
+            opts.heading_blank_lines_after = v;
+        }
+        if let Some(ref v) = self.heading_overlong {
+            opts.heading_overlong = v.to_overlong_headings()?;
+        }
+        if let Some(ref v) = self.heading_links {
+            opts.heading_links = match v.as_str() {
+                "reference" => HeadingLinks::Reference,
+                "inline" => HeadingLinks::Inline,
+                "text" => HeadingLinks::Text,
+                _ => {
+                    return Err(format!(
+                        "headingLinks must be \"reference\", \"inline\", or \"text\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.heading_title_case {
+            opts.heading_title_case = match v.as_str() {
+                "none" => TitleCase::None,
+                "simple" => TitleCase::Simple,
+                "ap" => TitleCase::Ap,
+                "chicago" => TitleCase::Chicago,
+                _ => {
+                    return Err(format!(
+                        "headingTitleCase must be \"none\", \"simple\", \"ap\", or \"chicago\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.unordered_marker {
+            opts.unordered_marker = match v.as_str() {
+                "-" => UnorderedMarker::Hyphen,
+                "*" => UnorderedMarker::Asterisk,
+                "+" => UnorderedMarker::Plus,
+                _ => {
+                    return Err(format!(
+                        "unorderedMarker must be \"-\", \"*\", or \"+\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(v) = self.leading_spaces {
+            opts.leading_spaces = LeadingSpaces::new(v)?;
+        }
+        if let Some(v) = self.trailing_spaces {
+            opts.trailing_spaces = TrailingSpaces::new(v)?;
+        }
+        if let Some(v) = self.indent_width {
+            opts.indent_width = IndentWidth::new(v)?;
+        }
+        if let Some(ref v) = self.odd_level_marker {
+            opts.odd_level_marker = parse_ordered_marker("oddLevelMarker", v)?;
+        }
+        if let Some(ref v) = self.even_level_marker {
+            opts.even_level_marker = parse_ordered_marker("evenLevelMarker", v)?;
+        }
+        if let Some(ref v) = self.ordered_list_pad {
+            opts.ordered_list_pad = match v.as_str() {
+                "start" => OrderedListPad::Start,
+                "end" => OrderedListPad::End,
+                _ => {
+                    return Err(format!(
+                        "orderedListPad must be \"start\" or \"end\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(v) = self.ordered_list_indent_width {
+            opts.ordered_list_indent_width = IndentWidth::new(v)?;
+        }
+        if let Some(v) = self.definition_marker_spaces {
+            opts.definition_marker_spaces = DefinitionMarkerSpaces::new(v)?;
+        }
+        if let Some(v) = self.definition_indent_width {
+            opts.definition_indent_width = IndentWidth::new(v)?;
+        }
+        if let Some(ref v) = self.abbreviation_placement {
+            opts.abbreviation_placement = match v.as_str() {
+                "preserve" => AbbreviationPlacement::Preserve,
+                "section" => AbbreviationPlacement::Section,
+                "document" => AbbreviationPlacement::Document,
+                _ => {
+                    return Err(format!(
+                        "abbreviationPlacement must be \"preserve\", \"section\", or \"document\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.front_matter_key_order {
+            opts.front_matter_key_order = v.clone();
+        }
+        if let Some(v) = self.front_matter_normalize_dates {
+            opts.front_matter_normalize_dates = v;
+        }
+        if let Some(ref v) = self.front_matter_date_format {
+            opts.front_matter_date_format = v.clone();
+        }
+        if let Some(ref v) = self.front_matter_datetime_format {
+            opts.front_matter_datetime_format = v.clone();
+        }
+        if let Some(ref v) = self.badge_layout {
+            opts.badge_layout = match v.as_str() {
+                "preserve" => BadgeLayout::Preserve,
+                "line" => BadgeLayout::Line,
+                "stack" => BadgeLayout::Stack,
+                _ => {
+                    return Err(format!(
+                        "badgeLayout must be \"preserve\", \"line\", or \"stack\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(v) = self.image_convert_html {
+            opts.image_convert_html = v;
+        }
+        if let Some(ref v) = self.image_html_attributes {
+            opts.image_html_attributes = match v.as_str() {
+                "skip" => HtmlImageAttributes::Skip,
+                "preserve" => HtmlImageAttributes::Preserve,
+                _ => {
+                    return Err(format!(
+                        "imageHtmlAttributes must be \"skip\" or \"preserve\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.figure_style {
+            opts.figure_style = match v.as_str() {
+                "preserve" => FigureStyle::Preserve,
+                "markdown" => FigureStyle::Markdown,
+                "html" => FigureStyle::Html,
+                _ => {
+                    return Err(format!(
+                        "figureStyle must be \"preserve\", \"markdown\", or \"html\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.fence_char {
+            opts.fence_char = match v.as_str() {
+                "~" => FenceChar::Tilde,
+                "`" => FenceChar::Backtick,
+                _ => {
+                    return Err(format!("fenceChar must be \"~\" or \"`\", got {:?}.", v));
+                }
+            };
+        }
+        if let Some(v) = self.min_fence_length {
+            opts.min_fence_length = MinFenceLength::new(v)?;
+        }
+        if let Some(v) = self.space_after_fence {
+            opts.space_after_fence = v;
+        }
+        if let Some(ref v) = self.default_language {
+            opts.default_language = v.clone();
+        }
+        if let Some(v) = self.warn_long_code_lines {
+            opts.warn_long_code_lines = v;
+        }
+        if let Some(v) = self.code_line_width {
+            opts.code_line_width = Some(LineWidth::new(v)?);
+        }
+        if let Some(ref v) = self.code_expand_tabs {
+            for (language, &width) in v {
+                let width = TabWidth::new(width)
+                    .map_err(|e| format!("codeExpandTabs[{:?}]: {}", language, e))?;
+                opts.code_expand_tabs.insert(language.clone(), width);
+            }
+        }
+        if let Some(ref v) = self.thematic_break_style {
+            opts.thematic_break_style = ThematicBreakStyle::new(v.clone())?;
+        }
+        if let Some(v) = self.thematic_break_leading_spaces {
+            opts.thematic_break_leading_spaces = LeadingSpaces::new(v)?;
+        }
+        if let Some(v) = self.table_escape_html {
+            opts.table_escape_html = v;
+        }
+        if let Some(ref v) = self.table_br_style {
+            opts.table_br_style = match v.as_str() {
+                "preserve" => BrStyle::Preserve,
+                "<br>" => BrStyle::Void,
+                "<br/>" => BrStyle::SelfClosing,
+                "<br />" => BrStyle::SpacedSelfClosing,
+                _ => {
+                    return Err(format!(
+                        "tableBrStyle must be \"preserve\", \"<br>\", \"<br/>\", or \"<br />\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.table_ragged_rows {
+            opts.table_ragged_rows = match v.as_str() {
+                "warn" => RaggedRows::Warn,
+                "drop" => RaggedRows::Drop,
+                "merge" => RaggedRows::Merge,
+                _ => {
+                    return Err(format!(
+                        "tableRaggedRows must be \"warn\", \"drop\", or \"merge\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(ref v) = self.table_delimiter_style {
+            opts.table_delimiter_style = match v.as_str() {
+                "padded" => DelimiterStyle::Padded,
+                "minimal" => DelimiterStyle::Minimal,
+                "fixed" => DelimiterStyle::Fixed,
+                _ => {
+                    return Err(format!(
+                        "tableDelimiterStyle must be \"padded\", \"minimal\", or \"fixed\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        if let Some(v) = self.table_preserve_left_alignment {
+            opts.table_preserve_left_alignment = v;
+        }
+        if let Some(ref v) = self.alert_synonyms {
+            for (from, to) in v {
+                let to = AlertName::new(to.clone())
+                    .map_err(|e| format!("alertSynonyms[{:?}]: {}", from, e))?;
+                opts.alert_synonyms.insert(from.clone(), to);
+            }
+        }
+        if let Some(v) = self.alert_convert_bold_labels {
+            opts.alert_convert_bold_labels = v;
+        }
+        if let Some(v) = self.curly_double_quotes {
+            opts.curly_double_quotes = v;
+        }
+        if let Some(v) = self.curly_single_quotes {
+            opts.curly_single_quotes = v;
+        }
+        if let Some(v) = self.curly_apostrophes {
+            opts.curly_apostrophes = v;
+        }
+        if let Some(v) = self.ellipsis {
+            opts.ellipsis = v;
+        }
+        if let Some(ref v) = self.en_dash {
+            opts.en_dash = v.to_dash_setting()?;
+        }
+        if let Some(ref v) = self.em_dash {
+            opts.em_dash = v.to_dash_setting()?;
+        }
+        if let Some(ref v) = self.cjk_quotes {
+            opts.cjk_quotes = match v.as_str() {
+                "none" => CjkQuotes::None,
+                "corner" => CjkQuotes::Corner,
+                "full-width" => CjkQuotes::FullWidth,
+                _ => {
+                    return Err(format!(
+                        "cjkQuotes must be \"none\", \"corner\", or \"full-width\", got {:?}.",
+                        v
+                    ));
+                }
+            };
+        }
+        opts.limits.max_input_bytes = self.max_input_bytes;
+        opts.limits.max_nesting_depth = self.max_nesting_depth;
+        opts.limits.max_table_columns = self.max_table_columns;
+        if let Some(v) = self.description_lists {
+            opts.extensions.description_lists = v;
+        }
+        if let Some(v) = self.wikilinks {
+            opts.extensions.wikilinks = v;
+        }
+
+        crate::validate_dash_settings(&opts).map_err(|e| e.to_string())?;
+
+        Ok(opts)
+    }
+}
+
+/// Parse an ordered list marker option value.
+fn parse_ordered_marker(name: &str, value: &str) -> Result<OrderedMarker, String> {
+    match value {
+        "." => Ok(OrderedMarker::Period),
+        ")" => Ok(OrderedMarker::Parenthesis),
+        _ => Err(format!("{} must be \".\" or \")\", got {:?}.", name, value)),
+    }
+}
+
+/// Parse an overlong heading policy option value.
+fn parse_overlong_heading(value: &str) -> Result<OverlongHeading, String> {
+    match value {
+        "keep" => Ok(OverlongHeading::Keep),
+        "warn" => Ok(OverlongHeading::Warn),
+        "atx" => Ok(OverlongHeading::Atx),
+        "wrap" => Ok(OverlongHeading::Wrap),
+        _ => Err(format!(
+            "headingOverlong must be \"keep\", \"warn\", \"atx\", or \"wrap\", got {:?}.",
+            value
+        )),
+    }
+}
+
+impl From<&DashSetting> for JsDashSetting {
+    fn from(setting: &DashSetting) -> Self {
+        match setting {
+            DashSetting::Disabled => JsDashSetting::Disabled(false),
+            DashSetting::Pattern(p) => JsDashSetting::Pattern(p.as_str().to_string()),
+        }
+    }
+}
+
+impl From<&Config> for JsOptions {
+    /// Convert a configuration file into the equivalent options object.
+    ///
+    /// File collection settings and external code formatters have no
+    /// counterpart in the options object and are therefore dropped.
+    fn from(config: &Config) -> Self {
+        JsOptions {
+            line_width: Some(config.line_width.get()),
+            unicode_normalization: Some(config.unicode_normalization.as_str().to_string()),
+            bom: Some(config.bom.as_str().to_string()),
+            line_ending: Some(config.line_ending.as_str().to_string()),
+            strip_trailing_whitespace: Some(config.strip_trailing_whitespace),
+            final_newline: Some(config.final_newline.as_str().to_string()),
+            tab_width: Some(config.tab_width.get()),
+            expand_tabs: Some(config.expand_tabs),
+            max_blank_lines: Some(config.max_blank_lines.get()),
+            escape_underscores: Some(config.escape_underscores),
+            renumber_references: Some(config.renumber_references),
+            autolink_host: Some(config.autolink_host.as_str().to_string()),
+            mentions: Some(config.mentions.as_str().to_string()),
+            profile: Some(config.profile.as_str().to_string()),
+            link_title_style: Some(config.link_title_style.as_str().to_string()),
+            link_reference_style: Some(config.link_reference_style.as_str().to_string()),
+            reference_label_case: Some(config.reference_label_case.as_str().to_string()),
+            normalize_urls: Some(config.normalize_urls),
+            link_base: Some(config.link_base.clone()),
+            verify_escaping: Some(config.verify_escaping),
+            verify_stability: Some(config.verify_stability),
+            setext_h1: Some(config.heading.setext_h1),
+            setext_h2: Some(config.heading.setext_h2),
+            setext_underline: Some(config.heading.setext_underline.into()),
+            setext_underline_measure: Some(
+                config.heading.setext_underline_measure.as_str().to_string(),
+            ),
+            heading_sentence_case: Some(config.heading.sentence_case),
+            heading_proper_nouns: Some(config.heading.proper_nouns.clone()),
+            heading_common_nouns: Some(config.heading.common_nouns.clone()),
+            heading_blank_lines_before: Some(config.heading.blank_lines_before.values()),
+            heading_collapse_consecutive: Some(config.heading.collapse_consecutive),
+            heading_blank_lines_after: Some(config.heading.blank_lines_after),
+            heading_links: Some(config.heading.links.as_str().to_string()),
+            heading_title_case: Some(config.heading.title_case.as_str().to_string()),
+            heading_overlong: Some(JsOverlongHeadings::PerLevel(
+                config
+                    .heading
+                    .overlong
+                    .values()
+                    .iter()
+                    .map(|policy| policy.as_str().to_string())
+                    .collect(),
+            )),
+            unordered_marker: Some(config.unordered_list.unordered_marker.as_char().to_string()),
+            leading_spaces: Some(config.unordered_list.leading_spaces.get()),
+            trailing_spaces: Some(config.unordered_list.trailing_spaces.get()),
+            indent_width: Some(config.unordered_list.indent_width.get()),
+            odd_level_marker: Some(config.ordered_list.odd_level_marker.as_char().to_string()),
+            even_level_marker: Some(config.ordered_list.even_level_marker.as_char().to_string()),
+            ordered_list_pad: Some(
+                match config.ordered_list.pad {
+                    OrderedListPad::Start => "start",
+                    OrderedListPad::End => "end",
+                }
+                .to_string(),
+            ),
+            ordered_list_indent_width: Some(config.ordered_list.indent_width.get()),
+            definition_marker_spaces: Some(config.definition_list.marker_spaces.get()),
+            definition_indent_width: Some(config.definition_list.indent_width.get()),
+            abbreviation_placement: Some(config.abbreviation.placement.as_str().to_string()),
+            front_matter_key_order: Some(config.front_matter.key_order.clone()),
+            front_matter_normalize_dates: Some(config.front_matter.normalize_dates),
+            front_matter_date_format: Some(config.front_matter.date_format.clone()),
+            front_matter_datetime_format: Some(config.front_matter.datetime_format.clone()),
+            badge_layout: Some(config.badge.layout.as_str().to_string()),
+            image_convert_html: Some(config.image.convert_html),
+            image_html_attributes: Some(config.image.html_attributes.as_str().to_string()),
+            figure_style: Some(config.figure.style.as_str().to_string()),
+            fence_char: Some(config.code_block.fence_char.as_char().to_string()),
+            min_fence_length: Some(config.code_block.min_fence_length.get()),
+            space_after_fence: Some(config.code_block.space_after_fence),
+            default_language: Some(config.code_block.default_language.clone()),
+            warn_long_code_lines: Some(config.code_block.warn_long_lines),
+            code_line_width: config.code_block.line_width.map(LineWidth::get),
+            code_expand_tabs: Some(
+                config
+                    .code_block
+                    .expand_tabs
+                    .iter()
+                    .map(|(language, width)| (language.clone(), width.get()))
+                    .collect(),
+            ),
+            thematic_break_style: Some(config.thematic_break.style.as_str().to_string()),
+            thematic_break_leading_spaces: Some(config.thematic_break.leading_spaces.get()),
+            table_escape_html: Some(config.table.escape_html),
+            table_br_style: Some(config.table.br_style.as_str().to_string()),
+            table_ragged_rows: Some(config.table.ragged_rows.as_str().to_string()),
+            table_delimiter_style: Some(config.table.delimiter_style.as_str().to_string()),
+            table_preserve_left_alignment: Some(config.table.preserve_left_alignment),
+            alert_synonyms: Some(
+                config
+                    .alert
+                    .synonyms
+                    .iter()
+                    .map(|(from, to)| (from.clone(), to.as_str().to_string()))
+                    .collect(),
+            ),
+            alert_convert_bold_labels: Some(config.alert.convert_bold_labels),
+            curly_double_quotes: Some(config.punctuation.curly_double_quotes),
+            curly_single_quotes: Some(config.punctuation.curly_single_quotes),
+            curly_apostrophes: Some(config.punctuation.curly_apostrophes),
+            ellipsis: Some(config.punctuation.ellipsis),
+            en_dash: Some(JsDashSetting::from(&config.punctuation.en_dash)),
+            em_dash: Some(JsDashSetting::from(&config.punctuation.em_dash)),
+            cjk_quotes: Some(config.punctuation.cjk_quotes.as_str().to_string()),
+            max_input_bytes: config.limits.max_input_bytes,
+            max_nesting_depth: config.limits.max_nesting_depth,
+            max_table_columns: config.limits.max_table_columns,
+            description_lists: Some(config.extensions.description_lists),
+            wikilinks: Some(config.extensions.wikilinks),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_js_options_default() {
+        let js_opts = JsOptions::default();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.line_width.get(), 80);
+        assert!(opts.setext_h1);
+        assert!(opts.setext_h2);
+    }
+
+    #[test]
+    fn test_js_options_partial() {
+        let js_opts = JsOptions {
+            line_width: Some(100),
+            setext_h1: Some(false),
+            ..Default::default()
+        };
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.line_width.get(), 100);
+        assert!(!opts.setext_h1);
+        assert!(opts.setext_h2); // default
+    }
+
+    #[test]
+    fn test_js_options_invalid_values() {
+        let js_opts = JsOptions {
+            line_width: Some(5),
+            ..Default::default()
+        };
+        assert!(js_opts.to_options().unwrap_err().contains("line_width"));
+
+        let js_opts = JsOptions {
+            fence_char: Some("#".to_string()),
+            ..Default::default()
+        };
+        assert!(js_opts.to_options().unwrap_err().contains("fenceChar"));
+
+        let js_opts = JsOptions {
+            table_br_style: Some("<BR>".to_string()),
+            ..Default::default()
+        };
+        assert!(js_opts.to_options().unwrap_err().contains("tableBrStyle"));
+
+        let js_opts = JsOptions {
+            table_ragged_rows: Some("pad".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            js_opts
+                .to_options()
+                .unwrap_err()
+                .contains("tableRaggedRows")
+        );
+
+        let js_opts = JsOptions {
+            table_delimiter_style: Some("compact".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            js_opts
+                .to_options()
+                .unwrap_err()
+                .contains("tableDelimiterStyle")
+        );
+
+        let js_opts = JsOptions::from_json(r#"{"alertSynonyms": {"info": "NO TE"}}"#).unwrap();
+        assert!(js_opts.to_options().unwrap_err().contains("alertSynonyms"));
+
+        let js_opts = JsOptions {
+            em_dash: Some(JsDashSetting::Pattern("--".to_string())),
+            en_dash: Some(JsDashSetting::Pattern("--".to_string())),
+            ..Default::default()
+        };
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_from_json() {
+        let js_opts = JsOptions::from_json(
+            r#"{
+                "lineWidth": 100,
+                "headingSentenceCase": true,
+                "unorderedMarker": "*",
+                "fenceChar": "`",
+                "minFenceLength": 3,
+                "curlyDoubleQuotes": false,
+                "emDash": false,
+                "enDash": "--"
+            }"#,
+        )
+        .unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.line_width.get(), 100);
+        assert!(opts.heading_sentence_case);
+        assert_eq!(opts.unordered_marker, UnorderedMarker::Asterisk);
+        assert_eq!(opts.fence_char, FenceChar::Backtick);
+        assert_eq!(opts.min_fence_length.get(), 3);
+        assert!(!opts.curly_double_quotes);
+        assert_eq!(opts.em_dash, DashSetting::Disabled);
+        assert!(matches!(opts.en_dash, DashSetting::Pattern(_)));
+    }
+
+    #[test]
+    fn test_js_options_from_invalid_json() {
+        let err = JsOptions::from_json("{ lineWidth: 100 }").unwrap_err();
+        assert!(err.starts_with("invalid options JSON"));
+    }
+
+    #[test]
+    fn test_js_dash_setting_disabled() {
+        let setting = JsDashSetting::Disabled(false);
+        assert!(matches!(
+            setting.to_dash_setting(),
+            Ok(DashSetting::Disabled)
+        ));
+    }
+
+    #[test]
+    fn test_js_dash_setting_pattern() {
+        let setting = JsDashSetting::Pattern("--".to_string());
+        match setting.to_dash_setting().unwrap() {
+            DashSetting::Pattern(p) => assert_eq!(p.as_str(), "--"),
+            _ => panic!("Expected Pattern"),
+        }
+    }
+
+    #[test]
+    fn test_js_options_heading_sentence_case() {
+        let js_opts = JsOptions {
+            heading_sentence_case: Some(true),
+            ..Default::default()
+        };
+        let opts = js_opts.to_options().unwrap();
+        assert!(opts.heading_sentence_case);
+    }
+
+    #[test]
+    fn test_js_options_heading_proper_nouns() {
+        let js_opts = JsOptions {
+            heading_proper_nouns: Some(vec!["MyApp".to_string(), "OpenAI".to_string()]),
+            ..Default::default()
+        };
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.heading_proper_nouns, vec!["MyApp", "OpenAI"]);
+    }
+
+    #[test]
+    fn test_js_options_heading_common_nouns() {
+        let js_opts = JsOptions {
+            heading_common_nouns: Some(vec!["react".to_string()]),
+            ..Default::default()
+        };
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.heading_common_nouns, vec!["react"]);
+    }
+
+    #[test]
+    fn test_js_options_heading_all() {
+        let js_opts = JsOptions {
+            heading_sentence_case: Some(true),
+            heading_proper_nouns: Some(vec!["Fedify".to_string()]),
+            heading_common_nouns: Some(vec!["api".to_string()]),
+            ..Default::default()
+        };
+        let opts = js_opts.to_options().unwrap();
+        assert!(opts.heading_sentence_case);
+        assert_eq!(opts.heading_proper_nouns, vec!["Fedify"]);
+        assert_eq!(opts.heading_common_nouns, vec!["api"]);
+    }
+
+    #[test]
+    fn test_js_options_from_config_round_trip() {
+        let config = Config::from_toml(
+            r#"
+line_width = 100
+
+[unordered_list]
+unordered_marker = "*"
+
+[ordered_list]
+pad = "end"
+
+[code_block]
+fence_char = "`"
+
+[alert]
+convert_bold_labels = true
+
+[alert.synonyms]
+info = "NOTE"
+
+[punctuation]
+em_dash = false
+en_dash = "--"
+"#,
+        )
+        .unwrap();
+        let opts = JsOptions::from(&config).to_options().unwrap();
+        assert_eq!(opts.line_width.get(), 100);
+        assert_eq!(opts.unordered_marker, UnorderedMarker::Asterisk);
+        assert_eq!(opts.ordered_list_pad, OrderedListPad::End);
+        assert_eq!(opts.fence_char, FenceChar::Backtick);
+        assert_eq!(opts.alert_synonyms["info"].as_str(), "NOTE");
+        assert!(opts.alert_convert_bold_labels);
+        assert_eq!(opts.em_dash, DashSetting::Disabled);
+        assert_eq!(
+            opts.en_dash,
+            DashSetting::Pattern(DashPattern::new("--".to_string()).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_js_options_limits() {
+        let js_opts =
+            JsOptions::from_json(r#"{"maxInputBytes": 1024, "maxTableColumns": 8}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.limits.max_input_bytes, Some(1024));
+        assert_eq!(opts.limits.max_nesting_depth, None);
+        assert_eq!(opts.limits.max_table_columns, Some(8));
+    }
+
+    #[test]
+    fn test_js_options_description_lists() {
+        let opts = JsOptions::from_json("{}").unwrap().to_options().unwrap();
+        assert!(opts.extensions.description_lists);
+        let js_opts = JsOptions::from_json(r#"{"descriptionLists": false}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert!(!opts.extensions.description_lists);
+    }
+
+    #[test]
+    fn test_js_options_wikilinks() {
+        let opts = JsOptions::from_json("{}").unwrap().to_options().unwrap();
+        assert!(!opts.extensions.wikilinks);
+        let js_opts = JsOptions::from_json(r#"{"wikilinks": true}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert!(opts.extensions.wikilinks);
+    }
+
+    #[test]
+    fn test_js_options_bom() {
+        let opts = JsOptions::from_json("{}").unwrap().to_options().unwrap();
+        assert_eq!(opts.bom, ByteOrderMark::Strip);
+        let js_opts = JsOptions::from_json(r#"{"bom": "preserve"}"#).unwrap();
+        assert_eq!(js_opts.to_options().unwrap().bom, ByteOrderMark::Preserve);
+        let js_opts = JsOptions::from_json(r#"{"bom": "keep"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_line_ending() {
+        let opts = JsOptions::from_json("{}").unwrap().to_options().unwrap();
+        assert_eq!(opts.line_ending, LineEnding::Lf);
+        let js_opts = JsOptions::from_json(r#"{"lineEnding": "crlf"}"#).unwrap();
+        assert_eq!(js_opts.to_options().unwrap().line_ending, LineEnding::Crlf);
+        let js_opts = JsOptions::from_json(r#"{"lineEnding": "CRLF"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_final_newline() {
+        let opts = JsOptions::from_json("{}").unwrap().to_options().unwrap();
+        assert_eq!(opts.final_newline, FinalNewline::Always);
+        let js_opts = JsOptions::from_json(r#"{"finalNewline": "never"}"#).unwrap();
+        assert_eq!(
+            js_opts.to_options().unwrap().final_newline,
+            FinalNewline::Never
+        );
+        let js_opts = JsOptions::from_json(r#"{"finalNewline": "keep"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_tabs() {
+        let js_opts = JsOptions::from_json(r#"{"tabWidth": 2, "expandTabs": true}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.tab_width.get(), 2);
+        assert!(opts.expand_tabs);
+        let js_opts = JsOptions::from_json(r#"{"tabWidth": 0}"#).unwrap();
+        assert!(js_opts.to_options().unwrap_err().contains("tab_width"));
+    }
+
+    #[test]
+    fn test_js_options_heading_blank_lines() {
+        let js_opts = JsOptions::from_json(
+            r#"{"headingBlankLinesBefore": [2, 2, 1, 1, 1, 1], "headingCollapseConsecutive": false}"#,
+        )
+        .unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.heading_blank_lines_before.get(1), 2);
+        assert!(!opts.heading_collapse_consecutive);
+        let js_opts =
+            JsOptions::from_json(r#"{"headingBlankLinesBefore": [0, 2, 1, 1, 1, 1]}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_setext_underline() {
+        let js_opts = JsOptions::from_json(r#"{"setextUnderline": "full-width"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.setext_underline, SetextUnderline::FullWidth);
+        let js_opts = JsOptions::from_json(r#"{"setextUnderline": 8}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.setext_underline, SetextUnderline::Fixed(8));
+        let js_opts = JsOptions::from_json(r#"{"setextUnderline": 0}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+        let js_opts = JsOptions::from_json(r#"{"setextUnderline": "wide"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_heading_overlong() {
+        let js_opts = JsOptions::from_json(r#"{"headingOverlong": "warn"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.heading_overlong.get(6), OverlongHeading::Warn);
+        let js_opts = JsOptions::from_json(
+            r#"{"headingOverlong": ["atx", "wrap", "keep", "keep", "keep", "keep"]}"#,
+        )
+        .unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.heading_overlong.get(1), OverlongHeading::Atx);
+        assert_eq!(opts.heading_overlong.get(2), OverlongHeading::Wrap);
+        let js_opts = JsOptions::from_json(r#"{"headingOverlong": ["atx"]}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+        let js_opts = JsOptions::from_json(r#"{"headingOverlong": "split"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_heading_links() {
+        let js_opts = JsOptions::from_json(r#"{"headingLinks": "inline"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.heading_links, HeadingLinks::Inline);
+        let js_opts = JsOptions::from_json(r#"{"headingLinks": "strip"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_heading_title_case() {
+        let js_opts = JsOptions::from_json(r#"{"headingTitleCase": "chicago"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.heading_title_case, TitleCase::Chicago);
+        let js_opts = JsOptions::from_json(r#"{"headingTitleCase": "apa"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_renumber_references() {
+        let js_opts = JsOptions::from_json(r#"{"renumberReferences": true}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert!(opts.renumber_references);
+    }
+
+    #[test]
+    fn test_js_options_autolink_host() {
+        let js_opts = JsOptions::from_json(r#"{"autolinkHost": "gitlab"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.autolink_host, AutolinkHost::GitLab);
+        let js_opts = JsOptions::from_json(r#"{"autolinkHost": "bitbucket"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_mentions() {
+        let js_opts = JsOptions::from_json(r#"{"mentions": "escape"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.mentions, MentionStyle::Escape);
+        let js_opts = JsOptions::from_json(r#"{"mentions": "strip"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_front_matter_key_order() {
+        let js_opts =
+            JsOptions::from_json(r#"{"frontMatterKeyOrder": ["title", "date"]}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.front_matter_key_order, vec!["title", "date"]);
+    }
+
+    #[test]
+    fn test_js_options_front_matter_dates() {
+        let js_opts = JsOptions::from_json(
+            r#"{"frontMatterNormalizeDates": true, "frontMatterDateFormat": "%d.%m.%Y"}"#,
+        )
+        .unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert!(opts.front_matter_normalize_dates);
+        assert_eq!(opts.front_matter_date_format, "%d.%m.%Y");
+        assert_eq!(opts.front_matter_datetime_format, "%Y-%m-%dT%H:%M:%S%z");
+    }
+
+    #[test]
+    fn test_js_options_profile() {
+        let js_opts = JsOptions::from_json(r#"{"profile": "changelog"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.profile, Profile::Changelog);
+        let js_opts = JsOptions::from_json(r#"{"profile": "readme"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_badge_layout() {
+        let js_opts = JsOptions::from_json(r#"{"badgeLayout": "line"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.badge_layout, BadgeLayout::Line);
+        let js_opts = JsOptions::from_json(r#"{"badgeLayout": "grid"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_link_title_style() {
+        let js_opts = JsOptions::from_json(r#"{"linkTitleStyle": "single"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.link_title_style, LinkTitleStyle::Single);
+        let js_opts = JsOptions::from_json(r#"{"linkTitleStyle": "backtick"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_link_reference_style() {
+        let js_opts = JsOptions::from_json(r#"{"linkReferenceStyle": "collapsed"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.link_reference_style, LinkReferenceStyle::Collapsed);
+        let js_opts = JsOptions::from_json(r#"{"linkReferenceStyle": "full"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_reference_label_case() {
+        let js_opts = JsOptions::from_json(r#"{"referenceLabelCase": "lower"}"#).unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert_eq!(opts.reference_label_case, ReferenceLabelCase::Lower);
+        let js_opts = JsOptions::from_json(r#"{"referenceLabelCase": "upper"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_normalize_urls() {
+        let js_opts = JsOptions::from_json(r#"{"normalizeUrls": true}"#).unwrap();
+        assert!(js_opts.to_options().unwrap().normalize_urls);
+    }
+
+    #[test]
+    fn test_js_options_link_base() {
+        let js_opts = JsOptions::from_json(r#"{"linkBase": "docs"}"#).unwrap();
+        assert_eq!(js_opts.to_options().unwrap().link_base, "docs");
+    }
+
+    #[test]
+    fn test_js_options_image_html() {
+        let js_opts = JsOptions::from_json(
+            r#"{"imageConvertHtml": true, "imageHtmlAttributes": "preserve"}"#,
+        )
+        .unwrap();
+        let opts = js_opts.to_options().unwrap();
+        assert!(opts.image_convert_html);
+        assert_eq!(opts.image_html_attributes, HtmlImageAttributes::Preserve);
+        let js_opts = JsOptions::from_json(r#"{"imageHtmlAttributes": "drop"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_setext_underline_measure() {
+        let js_opts = JsOptions::from_json(r#"{"setextUnderlineMeasure": "characters"}"#).unwrap();
+        assert_eq!(
+            js_opts.to_options().unwrap().setext_underline_measure,
+            UnderlineMeasure::Characters
+        );
+        let js_opts = JsOptions::from_json(r#"{"setextUnderlineMeasure": "bytes"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_cjk_quotes() {
+        let js_opts = JsOptions::from_json(r#"{"cjkQuotes": "corner"}"#).unwrap();
+        assert_eq!(js_opts.to_options().unwrap().cjk_quotes, CjkQuotes::Corner);
+        let js_opts = JsOptions::from_json(r#"{"cjkQuotes": "guillemets"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+
+    #[test]
+    fn test_js_options_figure_style() {
+        let js_opts = JsOptions::from_json(r#"{"figureStyle": "html"}"#).unwrap();
+        assert_eq!(
+            js_opts.to_options().unwrap().figure_style,
+            FigureStyle::Html
+        );
+        let js_opts = JsOptions::from_json(r#"{"figureStyle": "latex"}"#).unwrap();
+        assert!(js_opts.to_options().is_err());
+    }
+}
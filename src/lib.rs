@@ -11,22 +11,84 @@
 //! let output = format(input, &options).unwrap();
 //! ```
 
-use std::collections::HashMap;
-
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "bench")]
+mod bench;
+#[cfg(feature = "capi")]
+mod capi;
+mod code_blocks;
 pub mod config;
+mod diff;
+mod directive;
+mod equivalence;
+mod extensions;
+mod file;
+mod guard;
+mod idempotency;
+mod limits;
+mod links;
+mod outline;
+mod renderer;
+mod rewriter;
+mod rules;
+#[cfg(feature = "parallel")]
+mod sections;
 mod serializer;
-
+mod syntax;
+mod toc;
+mod transform;
+
+#[cfg(any(feature = "wasm", feature = "napi"))]
+mod js_options;
+#[cfg(feature = "napi")]
+// The exports are only registered with Node.js outside of test builds
+#[cfg_attr(test, allow(dead_code))]
+mod napi;
 #[cfg(feature = "wasm")]
 mod wasm;
 
+#[cfg(feature = "async")]
+pub use asynchronous::format_async;
+#[cfg(feature = "bench")]
+pub use bench::{Timings, measure};
+pub use code_blocks::{CodeBlockInfo, code_blocks};
 pub use config::{
-    DashPattern, DashSetting, FenceChar, IndentWidth, LeadingSpaces, LineWidth, MinFenceLength,
-    OrderedListPad, OrderedMarker, ThematicBreakStyle, TrailingSpaces, UnorderedMarker,
+    AbbreviationPlacement, AlertName, AutolinkHost, BadgeLayout, BrStyle, ByteOrderMark, CjkQuotes,
+    DashPattern, DashSetting, DefinitionMarkerSpaces, DelimiterStyle, FenceChar, FigureStyle,
+    FinalNewline, HeadingBlankLines, HeadingLinks, HtmlImageAttributes, IndentWidth, LeadingSpaces,
+    LineEnding, LineWidth, LinkReferenceStyle, LinkTitleStyle, MaxBlankLines, MentionStyle,
+    MinFenceLength, OrderedListPad, OrderedMarker, OverlongHeading, OverlongHeadings, Profile,
+    RaggedRows, ReferenceLabelCase, SetextUnderline, TabWidth, ThematicBreakStyle, TitleCase,
+    TrailingSpaces, UnderlineMeasure, UnicodeNormalization, UnorderedMarker,
 };
-pub use serializer::Warning;
+pub use diff::{CheckResult, DiffLine, DiffLineKind, Hunk, check, diff, diff_text};
+pub use directive::{DirectiveContext, DirectiveHandler};
+pub use equivalence::{SemanticChange, SemanticDiff, verify_equivalent};
+pub use extensions::Extensions;
+pub use file::{FileOutcome, format_file};
+pub use idempotency::{IdempotencyChange, IdempotencyDiff, verify_idempotent};
+pub use limits::{Limit, Limits};
+pub use links::{LinkInfo, LinkStyle, links};
+pub use outline::{HeadingInfo, outline};
+pub use renderer::{InlineKind, InlineRenderer};
+pub use rewriter::{LinkKind, UrlRewriter};
+pub use rules::{RULES, Rule, WarningCode, WarningKind, rule};
 pub use serializer::punctuation::{PunctuationError, validate_dash_settings};
+pub use serializer::{TraceEvent, TraceKind, Warning};
+pub use toc::{SlugStyle, slugify};
+pub use transform::AstTransform;
+
+/// Re-export of the comrak crate, whose AST is passed to [`AstTransform`]s.
+pub use comrak;
 
 use comrak::{Arena, Options as ComrakOptions, parse_document};
+use serializer::wrap;
 
 /// External code formatter configuration.
 #[derive(Debug, Clone)]
@@ -43,12 +105,129 @@ pub struct Options {
     /// Line width for wrapping. Default: 80.
     pub line_width: LineWidth,
 
+    /// Unicode normalization form applied to prose text, but not to code
+    /// spans and code blocks. Default: `None`.
+    pub unicode_normalization: UnicodeNormalization,
+
+    /// What to do with a byte order mark at the start of the input.
+    /// Default: `Strip`.
+    pub bom: ByteOrderMark,
+
+    /// Line ending of the output.  Default: `Lf`.
+    pub line_ending: LineEnding,
+
+    /// Strip trailing whitespace from every line, including those of code
+    /// and HTML blocks, except the two spaces of hard line breaks and
+    /// regions where formatting is disabled.  Default: false.
+    pub strip_trailing_whitespace: bool,
+
+    /// Whether the output ends with a newline.  Default: `Always`.
+    pub final_newline: FinalNewline,
+
+    /// Width of a tab stop in columns.  Default: 4.
+    pub tab_width: TabWidth,
+
+    /// Expand tabs in prose, including table cells, to spaces, so that
+    /// they do not throw off the widths of wrapped lines and table columns.
+    /// Code spans and blocks and HTML keep their tabs.  Default: false.
+    pub expand_tabs: bool,
+
+    /// Maximum number of consecutive blank lines kept between top-level
+    /// blocks.  Runs of blank lines in the source are kept up to this
+    /// number, and blocks are always separated by at least one.  Two blank
+    /// lines before h2 headings are written regardless.  Default: 1.
+    pub max_blank_lines: MaxBlankLines,
+
+    /// Escape underscores in the middle of words, e.g., `snake\_case`.
+    /// If disabled, underscores between letters or digits, where they
+    /// cannot open or close emphasis, are written as is, even if they were
+    /// escaped in the source.  Default: true.
+    pub escape_underscores: bool,
+
+    /// Renumber purely numeric reference labels, e.g., `[1]`, `[2]`, in the
+    /// order of their first use, rewriting both the links and their
+    /// definitions, so that the numbering stays dense and ordered as the
+    /// document is edited.  Shortcut and collapsed references become full
+    /// references, e.g., `[222]` becomes `[222][1]`, to keep their text.
+    /// Default: false.
+    pub renumber_references: bool,
+
+    /// The code hosting service whose shorthand references are kept intact:
+    /// underscores in the repository of `owner/repo#123` are not escaped,
+    /// and `#123` is never wrapped to the start of a line, where git would
+    /// take it for a comment in a commit message.  Default: GitHub.
+    pub autolink_host: AutolinkHost,
+
+    /// How `@username` mentions in prose, outside of code and links, are
+    /// written: as they are, with the `@` escaped so that pasting the text
+    /// into an issue notifies no one, or in code spans.  Default: as they
+    /// are.
+    pub mentions: MentionStyle,
+
+    /// A set of conventions for a kind of document.  With
+    /// [`Profile::Changelog`], headings of version sections, e.g.,
+    /// `[1.0.0] - 2017-06-20` or `[Unreleased]`, are kept as they are, version
+    /// strings are kept by sentence case and title case, and the reference
+    /// definitions of version sections are kept at the bottom of the
+    /// document, newest first.  Default: [`Profile::Default`].
+    pub profile: Profile,
+
+    /// How the titles of links and images are quoted: `"Title"`,
+    /// `'Title'`, or `(Title)`.  Characters that would end a title early
+    /// are escaped.  Default: `Double`.
+    pub link_title_style: LinkTitleStyle,
+
+    /// How reference links and images whose text is their label are
+    /// written: `[text]` or `[text][]`.  Default: `Shortcut`.
+    pub link_reference_style: LinkReferenceStyle,
+
+    /// How reference labels are cased.  Labels differing only in case share
+    /// a single definition either way.  Default: `Preserve`.
+    pub reference_label_case: ReferenceLabelCase,
+
+    /// Percent-encode spaces and other unsafe characters in link and image
+    /// destinations, lowercase their scheme and host, and remove default
+    /// ports, so that links survive strict renderers.  Destinations whose
+    /// meaning this could change, e.g., with a `%` that does not start
+    /// a percent-encoded sequence, are left alone with a `url-normalization`
+    /// warning.  Default: false.
+    pub normalize_urls: bool,
+
+    /// A base path that relative link and image destinations are joined
+    /// against, e.g., `docs` turns `../images/a.png` in `guide/` into
+    /// `docs/images/a.png`.  Destinations already within the base are left
+    /// as they are, so that formatting again does not rebase them twice, and
+    /// ones pointing outside of it are left alone with a `link-base` warning.
+    /// Default: empty, meaning no rebasing.
+    pub link_base: String,
+
+    /// Re-parse the output and report an `escaping` warning wherever its
+    /// inline content differs from the input's, e.g., where text became
+    /// emphasis or a link, or an escape became visible.  Only
+    /// [`format_with_warnings`] reports warnings.  Default: false.
+    pub verify_escaping: bool,
+
+    /// Format the output once more and report an `unstable` warning, with
+    /// the first hunk of the changes, if that changes it.  This is a cheap
+    /// form of the idempotency check.  Only [`format_with_warnings`] reports
+    /// warnings.  Default: false.
+    pub verify_stability: bool,
+
     /// Use setext-style (underlined) for h1 headings. Default: true.
     pub setext_h1: bool,
 
     /// Use setext-style (underlined) for h2 headings. Default: true.
     pub setext_h2: bool,
 
+    /// Length of the underlines of setext headings.  Default: as long as
+    /// the heading text.
+    pub setext_underline: SetextUnderline,
+
+    /// How the heading text is measured for
+    /// [`setext_underline`](Self::setext_underline).  Default: by display
+    /// width, so that a wide character gets two underline characters.
+    pub setext_underline_measure: UnderlineMeasure,
+
     /// Convert headings to sentence case. Default: false.
     pub heading_sentence_case: bool,
 
@@ -60,6 +239,31 @@ pub struct Options {
     /// These are excluded from built-in proper nouns.
     pub heading_common_nouns: Vec<String>,
 
+    /// Blank lines before top-level headings of each level.  Default: two
+    /// before h2 and one before the others.
+    pub heading_blank_lines_before: HeadingBlankLines,
+
+    /// Write a single blank line between a heading and a heading right after
+    /// it, regardless of [`Options::heading_blank_lines_before`].
+    /// Default: true.
+    pub heading_collapse_consecutive: bool,
+
+    /// Blank lines between a top-level heading and the block after it,
+    /// unless that is a heading as well.  Default: 1.
+    pub heading_blank_lines_after: usize,
+
+    /// What to do with headings of each level that are wider than the line
+    /// width.  Default: keep them as they are.
+    pub heading_overlong: OverlongHeadings,
+
+    /// How links inside headings are written.  Default: external links are
+    /// converted to reference links, like in paragraphs.
+    pub heading_links: HeadingLinks,
+
+    /// Title case style for headings, used unless sentence case is
+    /// enabled.  Default: headings keep their case.
+    pub heading_title_case: TitleCase,
+
     /// Marker character for unordered lists: `-`, `*`, or `+`. Default: `-`.
     pub unordered_marker: UnorderedMarker,
 
@@ -86,6 +290,57 @@ pub struct Options {
     /// Indentation width for nested ordered list items. Default: 4.
     pub ordered_list_indent_width: IndentWidth,
 
+    /// Number of spaces after the `:` marker of a definition. Default: 3.
+    pub definition_marker_spaces: DefinitionMarkerSpaces,
+
+    /// Indentation width for the content of a definition. Default: 4.
+    pub definition_indent_width: IndentWidth,
+
+    /// Where to place abbreviation definitions. Default: `Preserve`.
+    pub abbreviation_placement: AbbreviationPlacement,
+
+    /// Canonical order of the top-level keys of YAML front matter.  Listed
+    /// keys are moved to the front in this order, along with the comments
+    /// directly above them, and the others follow in their original order.
+    /// Default: empty, which keeps the order.
+    pub front_matter_key_order: Vec<String>,
+
+    /// Whether to normalize the `date` and `updated` fields of YAML front
+    /// matter.  Values that cannot be parsed as dates are kept and reported
+    /// as warnings.  Default: false.
+    pub front_matter_normalize_dates: bool,
+
+    /// Format of normalized front matter dates without a time.
+    /// Default: `%Y-%m-%d`.
+    pub front_matter_date_format: String,
+
+    /// Format of normalized front matter dates with a time.
+    /// Default: `%Y-%m-%dT%H:%M:%S%z`.
+    pub front_matter_datetime_format: String,
+
+    /// How paragraphs that consist only of badges, i.e., links around
+    /// images, are laid out: as any other paragraph, all on one line, or one
+    /// per line.  Unless preserved, badges also get reference labels named
+    /// after their alt text, e.g., `[![CI][CI badge]][CI]`, so that their
+    /// definitions are kept together.  Default: `Preserve`.
+    pub badge_layout: BadgeLayout,
+
+    /// Convert HTML `<img>` elements into Markdown images, e.g.,
+    /// `<img src="a.png" alt="A">` into `![A](a.png)`.  Default: `false`.
+    pub image_convert_html: bool,
+
+    /// What to do with `<img>` elements that have attributes other than
+    /// `src`, `alt`, and `title`, e.g., `width`, when converting them: leave
+    /// them as they are, or keep the attributes in an attribute block like
+    /// `{width="100"}`.  Default: `Skip`.
+    pub image_html_attributes: HtmlImageAttributes,
+
+    /// How figures, i.e., images followed by an emphasized caption on the
+    /// next line, are written: as they are, converting simple HTML
+    /// `<figure>` elements into Markdown, or the other way around.
+    /// Default: `Preserve`.
+    pub figure_style: FigureStyle,
+
     /// Fence character for code blocks: `~` or `` ` ``. Default: `~`.
     pub fence_char: FenceChar,
 
@@ -100,6 +355,22 @@ pub struct Options {
     /// Set to e.g. "text" to add a default language identifier.
     pub default_language: String,
 
+    /// Warn about lines of code blocks wider than
+    /// [`code_line_width`](Self::code_line_width).  Formatters cannot
+    /// rewrap code, so these lines are only reported.  Default: `false`.
+    pub warn_long_code_lines: bool,
+
+    /// Line width for [`warn_long_code_lines`](Self::warn_long_code_lines).
+    /// Default: `None`, i.e., [`line_width`](Self::line_width).
+    pub code_line_width: Option<LineWidth>,
+
+    /// Tab widths to expand the leading tabs of code blocks to spaces with,
+    /// by language.  Default: empty, i.e., tabs are kept.
+    ///
+    /// The tabs are expanded before the code is passed to an external code
+    /// formatter.  Code blocks marked with `hongdown-no-format` keep them.
+    pub code_expand_tabs: BTreeMap<String, TabWidth>,
+
     /// The style string for thematic breaks. Default: 37 spaced dashes.
     pub thematic_break_style: ThematicBreakStyle,
 
@@ -107,6 +378,44 @@ pub struct Options {
     /// CommonMark allows 0-3 leading spaces for thematic breaks.
     pub thematic_break_leading_spaces: LeadingSpaces,
 
+    /// Escape raw HTML in table cells, so that it is rendered as literal
+    /// text.  Default: false.
+    ///
+    /// Inline HTML that is opened in one cell and closed in another, or not
+    /// at all, breaks the table when it is rendered.  When this is disabled,
+    /// such HTML is kept and reported with a warning instead.
+    pub table_escape_html: bool,
+
+    /// How to write `<br>` tags in table cells.  Default: `Preserve`.
+    pub table_br_style: BrStyle,
+
+    /// How to handle table rows with more or fewer cells than the header
+    /// row.  Default: `Warn`.
+    pub table_ragged_rows: RaggedRows,
+
+    /// Style of the delimiter row of tables.  Default: `Padded`.
+    pub table_delimiter_style: DelimiterStyle,
+
+    /// Keep the colons of tables whose columns are all left-aligned.
+    /// Default: true.
+    ///
+    /// Left is the default alignment of most renderers, so such colons can
+    /// be dropped without changing how the table looks.
+    pub table_preserve_left_alignment: bool,
+
+    /// Alert types to replace with another type, matched case-insensitively.
+    /// Default: none.
+    ///
+    /// Alert types other than GitHub's five are kept as they are, so this
+    /// can normalize synonyms used by other renderers, e.g., `info` to
+    /// `NOTE`.
+    pub alert_synonyms: BTreeMap<String, AlertName>,
+
+    /// Rewrite block quotes and paragraphs that start with a bold label of
+    /// an alert type, e.g., `**Note:**` or `**Warning**:`, as GitHub alerts
+    /// of that type.  Default: false.
+    pub alert_convert_bold_labels: bool,
+
     /// Convert straight double quotes to curly quotes. Default: true.
     /// `"text"` becomes `"text"` (U+201C and U+201D).
     pub curly_double_quotes: bool,
@@ -133,6 +442,11 @@ pub struct Options {
     /// The pattern is replaced with `—` (U+2014).
     pub em_dash: DashSetting,
 
+    /// Quotation marks that straight quotes around mostly CJK text, e.g.,
+    /// `"你好"`, become instead of curly quotes.  Default: `None`, i.e.,
+    /// they are treated like other quotes.
+    pub cjk_quotes: CjkQuotes,
+
     /// External code formatters by language.
     ///
     /// Key: language identifier (exact match only).
@@ -145,17 +459,119 @@ pub struct Options {
     /// If the formatter fails (non-zero exit, timeout, etc.), the original code
     /// is preserved and a warning is emitted.
     pub code_formatters: HashMap<String, CodeFormatter>,
+
+    /// Directory to run external code formatters in.  Default: `None`, i.e.,
+    /// the current directory.  [`format_file`] runs them in the directory of
+    /// the file unless this is set.
+    pub code_formatter_dir: Option<PathBuf>,
+
+    /// Run external code formatters in a sandbox.  Default: `false`.
+    ///
+    /// Sandboxed formatters get only the environment variables they need to
+    /// start, e.g., `PATH` and `HOME`, and run in an empty temporary
+    /// directory instead of [`code_formatter_dir`](Self::code_formatter_dir).
+    /// On Linux, they also have no network access.  This needs unprivileged
+    /// user namespaces; where the system disables them, sandboxed formatters
+    /// fail with a warning instead of running.  On other operating systems,
+    /// they still have network access.
+    pub code_formatter_sandbox: bool,
+
+    /// Commands external code formatters may run.  Default: `None`, i.e.,
+    /// any command.
+    ///
+    /// A formatter is run only if its command starts with one of these,
+    /// e.g., `["deno", "fmt"]` allows `deno fmt --ext=ts -` but not
+    /// `deno run script.ts`.  Other formatters fail with a warning.
+    pub allowed_code_formatters: Option<Vec<Vec<String>>>,
+
+    /// Transforms applied to the parsed document before serialization, in
+    /// order.  Default: none.
+    ///
+    /// See [`AstTransform`] for details.
+    pub transforms: Vec<Arc<dyn AstTransform>>,
+
+    /// Custom renderers for inline nodes, by node kind.  Default: none.
+    ///
+    /// See [`InlineRenderer`] for details.
+    pub inline_renderers: HashMap<InlineKind, Arc<dyn InlineRenderer>>,
+
+    /// A function that rewrites link and image destinations before
+    /// serialization.  Default: none.
+    ///
+    /// See [`UrlRewriter`] for details.
+    pub url_rewriter: Option<Arc<dyn UrlRewriter>>,
+
+    /// User-defined comment directives, by name (e.g., `"mytool-toc"` for
+    /// `<!-- mytool-toc -->`).  Default: none.
+    ///
+    /// Built-in `hongdown-*` directives take precedence.  See
+    /// [`DirectiveHandler`] for details.
+    pub directives: HashMap<String, Arc<dyn DirectiveHandler>>,
+
+    /// Serialize the sections between top-level h2 headings in parallel.
+    /// Default: `false`.
+    ///
+    /// This speeds up formatting of single large documents, and produces the
+    /// same output as sequential formatting.  Documents whose sections are
+    /// not independent, e.g., because of footnotes, directives, or
+    /// transforms, are formatted sequentially.  Has no effect unless the
+    /// `parallel` feature is enabled.
+    pub parallel_sections: bool,
+
+    /// Limits on the input, for formatting untrusted documents.  Default:
+    /// no limits.
+    ///
+    /// Input that exceeds a limit is rejected with
+    /// [`FormatError::LimitExceeded`] before it is serialized.
+    pub limits: Limits,
+
+    /// Markdown extensions to recognize when parsing.  Default: all
+    /// extensions enabled.
+    pub extensions: Extensions,
+
+    /// Record which formatting decisions were made for each block, e.g.,
+    /// which paragraphs were wrapped and which links were converted to
+    /// references, in [`FormatResult::trace`].  Default: `false`.
+    pub trace: bool,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
             line_width: LineWidth::default(),
+            unicode_normalization: UnicodeNormalization::default(),
+            bom: ByteOrderMark::default(),
+            line_ending: LineEnding::default(),
+            strip_trailing_whitespace: false,
+            final_newline: FinalNewline::default(),
+            tab_width: TabWidth::default(),
+            expand_tabs: false,
+            max_blank_lines: MaxBlankLines::default(),
+            escape_underscores: true,
+            renumber_references: false,
+            autolink_host: AutolinkHost::default(),
+            mentions: MentionStyle::default(),
+            profile: Profile::default(),
+            link_title_style: LinkTitleStyle::default(),
+            link_reference_style: LinkReferenceStyle::default(),
+            reference_label_case: ReferenceLabelCase::default(),
+            normalize_urls: false,
+            link_base: String::new(),
+            verify_escaping: false,
+            verify_stability: false,
             setext_h1: true,
             setext_h2: true,
+            setext_underline: SetextUnderline::default(),
+            setext_underline_measure: UnderlineMeasure::default(),
             heading_sentence_case: false,
             heading_proper_nouns: Vec::new(),
             heading_common_nouns: Vec::new(),
+            heading_blank_lines_before: HeadingBlankLines::default(),
+            heading_collapse_consecutive: true,
+            heading_blank_lines_after: 1,
+            heading_overlong: OverlongHeadings::default(),
+            heading_links: HeadingLinks::default(),
+            heading_title_case: TitleCase::default(),
             unordered_marker: UnorderedMarker::default(),
             leading_spaces: LeadingSpaces::default(),
             trailing_spaces: TrailingSpaces::default(),
@@ -164,19 +580,52 @@ impl Default for Options {
             even_level_marker: OrderedMarker::Parenthesis,
             ordered_list_pad: OrderedListPad::Start,
             ordered_list_indent_width: IndentWidth::default(),
+            definition_marker_spaces: DefinitionMarkerSpaces::default(),
+            definition_indent_width: IndentWidth::default(),
+            abbreviation_placement: AbbreviationPlacement::default(),
+            front_matter_key_order: Vec::new(),
+            front_matter_normalize_dates: false,
+            front_matter_date_format: "%Y-%m-%d".to_string(),
+            front_matter_datetime_format: "%Y-%m-%dT%H:%M:%S%z".to_string(),
+            badge_layout: BadgeLayout::default(),
+            image_convert_html: false,
+            image_html_attributes: HtmlImageAttributes::default(),
+            figure_style: FigureStyle::default(),
             fence_char: FenceChar::default(),
             min_fence_length: MinFenceLength::default(),
             space_after_fence: true,
             default_language: String::new(),
+            warn_long_code_lines: false,
+            code_line_width: None,
+            code_expand_tabs: BTreeMap::new(),
             thematic_break_style: ThematicBreakStyle::default(),
             thematic_break_leading_spaces: LeadingSpaces::new(3).unwrap(),
+            table_escape_html: false,
+            table_br_style: BrStyle::Preserve,
+            table_ragged_rows: RaggedRows::Warn,
+            table_delimiter_style: DelimiterStyle::Padded,
+            table_preserve_left_alignment: true,
+            alert_synonyms: BTreeMap::new(),
+            alert_convert_bold_labels: false,
             curly_double_quotes: true,
             curly_single_quotes: true,
             curly_apostrophes: false,
             ellipsis: true,
             en_dash: DashSetting::Disabled,
             em_dash: DashSetting::Pattern(DashPattern::new("--".to_string()).unwrap()),
+            cjk_quotes: CjkQuotes::default(),
             code_formatters: HashMap::new(),
+            code_formatter_dir: None,
+            code_formatter_sandbox: false,
+            allowed_code_formatters: None,
+            transforms: Vec::new(),
+            inline_renderers: HashMap::new(),
+            url_rewriter: None,
+            directives: HashMap::new(),
+            parallel_sections: false,
+            limits: Limits::default(),
+            extensions: Extensions::default(),
+            trace: false,
         }
     }
 }
@@ -191,6 +640,12 @@ impl Default for Options {
 ///   section heading.
 /// - `<!-- hongdown-disable -->` - Disable formatting from this point.
 /// - `<!-- hongdown-enable -->` - Re-enable formatting.
+/// - `<!-- hongdown-disable wrap reference-links -->` - Disable only the given
+///   rules (`wrap`, `reference-links`, `punctuation`, `sentence-case`,
+///   `title-case`) from this point.
+/// - `<!-- hongdown-enable wrap -->` - Re-enable the given rules.
+/// - `<!-- hongdown-off -->...<!-- hongdown-on -->` - Keep an inline span
+///   within a paragraph as-is.
 ///
 /// # Arguments
 ///
@@ -203,25 +658,24 @@ impl Default for Options {
 ///
 /// # Errors
 ///
-/// Returns an error if the input cannot be parsed or formatted.
+/// Returns an error if the input cannot be parsed or formatted, or exceeds
+/// [`Options::limits`].  This function does not panic: an internal panic is
+/// returned as [`FormatError::Internal`].
 pub fn format(input: &str, options: &Options) -> Result<String, FormatError> {
+    let (bom, input) = split_bom(input, options);
     if input.is_empty() {
-        return Ok(String::new());
+        return Ok(bom.to_string());
     }
 
-    let arena = Arena::new();
-    let mut comrak_options = ComrakOptions::default();
-    comrak_options.extension.front_matter_delimiter = Some("---".to_string());
-    comrak_options.extension.table = true;
-    comrak_options.extension.description_lists = true;
-    comrak_options.extension.alerts = true;
-    comrak_options.extension.footnotes = true;
-    comrak_options.extension.tasklist = true;
-
-    let root = parse_document(&arena, input, &comrak_options);
-    let output = serializer::serialize_with_source(root, options, Some(input));
-
-    Ok(output)
+    guard::catch(|| {
+        let normalized = normalize_line_endings(input);
+        let (escaped, substitutes) = wrap::escape_placeholders(&normalized);
+        let arena = Arena::new();
+        let root = parse(&arena, &escaped, options)?;
+        let output = serialize_document(root, &escaped, options).output;
+        let output = wrap::restore_placeholders(output, &substitutes);
+        Ok(finish_output(input, bom, output, options))
+    })
 }
 
 /// Result of formatting with warnings.
@@ -231,6 +685,8 @@ pub struct FormatResult {
     pub output: String,
     /// Warnings generated during formatting.
     pub warnings: Vec<Warning>,
+    /// Formatting decisions, if [`Options::trace`] is enabled.
+    pub trace: Vec<TraceEvent>,
 }
 
 /// Formats a Markdown document and returns both output and warnings.
@@ -247,29 +703,164 @@ pub struct FormatResult {
 ///
 /// A [`FormatResult`] containing the formatted output and any warnings.
 pub fn format_with_warnings(input: &str, options: &Options) -> Result<FormatResult, FormatError> {
+    format_with_serializer(input, options, |root, input| {
+        serialize_document(root, input, options)
+    })
+}
+
+/// Format `input` like [`format_with_warnings`], serializing the parsed
+/// document with `serialize`.
+pub(crate) fn format_with_serializer(
+    input: &str,
+    options: &Options,
+    serialize: impl for<'a> FnOnce(&'a comrak::nodes::AstNode<'a>, &str) -> serializer::SerializeResult,
+) -> Result<FormatResult, FormatError> {
+    let (bom, input) = split_bom(input, options);
     if input.is_empty() {
         return Ok(FormatResult {
-            output: String::new(),
+            output: bom.to_string(),
             warnings: Vec::new(),
+            trace: Vec::new(),
         });
     }
 
-    let arena = Arena::new();
+    guard::catch(|| {
+        let normalized = normalize_line_endings(input);
+        let (escaped, substitutes) = wrap::escape_placeholders(&normalized);
+        let arena = Arena::new();
+        let root = parse(&arena, &escaped, options)?;
+        let mut result = serialize(root, &escaped);
+        result.output = wrap::restore_placeholders(result.output, &substitutes);
+        result.warnings.extend(toc::stale_toc_warnings(root));
+        if options.verify_escaping {
+            let warnings = equivalence::escaping_warnings(input, &result.output, options);
+            result.warnings.extend(warnings);
+        }
+
+        let output = finish_output(input, bom, result.output, options);
+        if options.verify_stability {
+            result.warnings.extend(stability_warning(&output, options));
+        }
+
+        Ok(FormatResult {
+            output,
+            warnings: result.warnings,
+            trace: result.trace,
+        })
+    })
+}
+
+/// Format `output` once more, and return an `unstable` warning with the
+/// first hunk of the changes if that changes it.
+pub(crate) fn stability_warning(output: &str, options: &Options) -> Option<Warning> {
+    let again = format(output, options).ok()?;
+    changed_output_warning(output, &again)
+}
+
+/// Return an `unstable` warning with the first hunk of the changes from
+/// `output` to `again`, the result of formatting it once more, if any.
+pub(crate) fn changed_output_warning(output: &str, again: &str) -> Option<Warning> {
+    let hunk = diff_text(output, again).into_iter().next()?;
+    Some(Warning::new(
+        WarningCode::Unstable,
+        hunk.old_range.start + 1,
+        0,
+        format!(
+            "formatting the output again changes it from line {} of the output on:\n{}",
+            hunk.old_range.start + 1,
+            hunk.to_string().trim_end()
+        ),
+    ))
+}
+
+/// The UTF-8 byte order mark.
+pub(crate) const BOM: &str = "\u{feff}";
+
+/// Set aside the byte order marks at the start of `input`, so that they do
+/// not become part of the first block.  Returns the byte order mark to put
+/// back at the start of the output according to [`Options::bom`], and the
+/// rest of the input.
+pub(crate) fn split_bom<'i>(input: &'i str, options: &Options) -> (&'static str, &'i str) {
+    let rest = input.trim_start_matches(BOM);
+    if rest.len() < input.len() && options.bom == ByteOrderMark::Preserve {
+        (BOM, rest)
+    } else {
+        ("", rest)
+    }
+}
+
+/// Convert the CRLF line endings of `input` to LF, so that no carriage
+/// return becomes part of the text that is measured and wrapped.  The line
+/// ending of the output is set by [`finish_output`] afterwards.
+pub(crate) fn normalize_line_endings(input: &str) -> Cow<'_, str> {
+    if input.contains('\r') {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Apply the options that concern the file as a whole to `output`,
+/// formatted from `input`: the final newline, the byte order mark `bom`
+/// returned by [`split_bom`], and the line ending.
+pub(crate) fn finish_output(input: &str, bom: &str, output: String, options: &Options) -> String {
+    let mut output = options.final_newline.apply(input, output);
+    output.insert_str(0, bom);
+    options.line_ending.apply(input, output)
+}
+
+/// Parse a document within [`Options::limits`] and apply
+/// [`Options::transforms`] to it.
+pub(crate) fn parse<'a>(
+    arena: &'a Arena<'a>,
+    input: &str,
+    options: &Options,
+) -> Result<&'a comrak::nodes::AstNode<'a>, FormatError> {
+    limits::check_input(input, &options.limits)?;
+    let root = parse_markdown(arena, input, &options.extensions);
+    limits::check_document(root, &options.limits)?;
+    transform::apply_transforms(root, arena, options);
+    Ok(root)
+}
+
+/// Parse a document with [`comrak_options`], and recognize the block
+/// containers in it that comrak does not know, such as MkDocs admonitions.
+pub(crate) fn parse_markdown<'a>(
+    arena: &'a Arena<'a>,
+    input: &str,
+    extensions: &Extensions,
+) -> &'a comrak::nodes::AstNode<'a> {
+    let root = parse_document(arena, input, &comrak_options(extensions));
+    syntax::expand(root, arena, input, extensions);
+    root
+}
+
+/// Serialize a parsed document, section by section in parallel if enabled
+/// and possible.
+fn serialize_document<'a>(
+    root: &'a comrak::nodes::AstNode<'a>,
+    input: &str,
+    options: &Options,
+) -> serializer::SerializeResult {
+    #[cfg(feature = "parallel")]
+    if options.parallel_sections
+        && let Some(result) = sections::serialize(root, input, options)
+    {
+        return result;
+    }
+    serializer::serialize_with_source_and_warnings(root, options, Some(input))
+}
+
+/// Returns the comrak parser options used by the formatter.
+pub(crate) fn comrak_options(extensions: &Extensions) -> ComrakOptions<'static> {
     let mut comrak_options = ComrakOptions::default();
     comrak_options.extension.front_matter_delimiter = Some("---".to_string());
     comrak_options.extension.table = true;
-    comrak_options.extension.description_lists = true;
+    comrak_options.extension.description_lists = extensions.description_lists;
     comrak_options.extension.alerts = true;
     comrak_options.extension.footnotes = true;
     comrak_options.extension.tasklist = true;
-
-    let root = parse_document(&arena, input, &comrak_options);
-    let result = serializer::serialize_with_source_and_warnings(root, options, Some(input));
-
-    Ok(FormatResult {
-        output: result.output,
-        warnings: result.warnings,
-    })
+    comrak_options
 }
 
 /// Errors that can occur during formatting.
@@ -277,12 +868,45 @@ pub fn format_with_warnings(input: &str, options: &Options) -> Result<FormatResu
 pub enum FormatError {
     /// An error occurred during parsing.
     ParseError(String),
+    /// The input exceeded one of [`Options::limits`].
+    LimitExceeded {
+        /// The limit that was exceeded.
+        limit: Limit,
+        /// The configured maximum.
+        max: usize,
+        /// The actual value found in the input.
+        actual: usize,
+    },
+    /// The file could not be read, or is not valid UTF-8.
+    Io(std::io::Error),
+    /// The formatter panicked because of a bug, either in Hongdown or in
+    /// a user-supplied extension such as an [`AstTransform`].
+    Internal {
+        /// The panic message.
+        message: String,
+        /// The source location of the panic as `file:line:column`, if known.
+        location: Option<String>,
+    },
 }
 
 impl std::fmt::Display for FormatError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FormatError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            FormatError::LimitExceeded { limit, max, actual } => write!(
+                f,
+                "Limit exceeded: {} is {}, but the maximum is {}",
+                limit, actual, max
+            ),
+            FormatError::Io(e) => write!(f, "I/O error: {}", e),
+            FormatError::Internal {
+                message,
+                location: Some(location),
+            } => write!(f, "Internal error at {}: {}", location, message),
+            FormatError::Internal {
+                message,
+                location: None,
+            } => write!(f, "Internal error: {}", message),
         }
     }
 }
@@ -307,6 +931,19 @@ mod tests {
         assert_eq!(result, "Hello, world!\n");
     }
 
+    #[test]
+    fn test_stability_warning() {
+        let options = Options::default();
+        assert!(stability_warning("Title\n=====\n", &options).is_none());
+        let warning = stability_warning("Title\n===\n", &options).unwrap();
+        assert_eq!((warning.code, warning.line), (WarningCode::Unstable, 1));
+        assert!(
+            warning
+                .message
+                .ends_with("@@ -1,2 +1,2 @@\n Title\n-===\n+=====")
+        );
+    }
+
     #[test]
     fn test_options_default_punctuation() {
         let options = Options::default();
@@ -0,0 +1,169 @@
+//! Resource limits for untrusted input.
+//!
+//! Pathological documents, e.g., thousands of nested block quotes or tables
+//! with thousands of columns, can make formatting take a lot of time and
+//! memory, or overflow the stack of the recursive serializer.  When Hongdown
+//! formats input it does not control, such as in a browser through the WASM
+//! bindings, [`Limits`] rejects such documents with
+//! [`FormatError::LimitExceeded`] before they are serialized.
+
+use comrak::nodes::{AstNode, NodeValue};
+use serde::Deserialize;
+
+use crate::FormatError;
+
+/// Limits on the input accepted by the formatter.  Every limit is disabled
+/// (`None`) by default.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Limits {
+    /// Maximum size of the input in bytes.
+    pub max_input_bytes: Option<usize>,
+
+    /// Maximum nesting depth of the parsed document, counting every block
+    /// and inline node below the document root.
+    pub max_nesting_depth: Option<usize>,
+
+    /// Maximum number of columns in a table.
+    pub max_table_columns: Option<usize>,
+}
+
+/// A limit in [`Limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// [`Limits::max_input_bytes`].
+    InputBytes,
+    /// [`Limits::max_nesting_depth`].
+    NestingDepth,
+    /// [`Limits::max_table_columns`].
+    TableColumns,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Limit::InputBytes => "input size in bytes",
+            Limit::NestingDepth => "nesting depth",
+            Limit::TableColumns => "number of table columns",
+        })
+    }
+}
+
+/// Check the limits that apply to the raw input, before parsing.
+pub(crate) fn check_input(input: &str, limits: &Limits) -> Result<(), FormatError> {
+    check(Limit::InputBytes, limits.max_input_bytes, input.len())
+}
+
+/// Check the limits that apply to the parsed document.
+pub(crate) fn check_document<'a>(
+    root: &'a AstNode<'a>,
+    limits: &Limits,
+) -> Result<(), FormatError> {
+    if limits.max_nesting_depth.is_none() && limits.max_table_columns.is_none() {
+        return Ok(());
+    }
+    // Walk the tree iteratively, since a deeply nested document is exactly
+    // what would overflow the stack of a recursive walk
+    let mut stack = vec![(root, 0)];
+    while let Some((node, depth)) = stack.pop() {
+        check(Limit::NestingDepth, limits.max_nesting_depth, depth)?;
+        if let NodeValue::Table(table) = &node.data.borrow().value {
+            check(
+                Limit::TableColumns,
+                limits.max_table_columns,
+                table.num_columns,
+            )?;
+        }
+        stack.extend(node.children().map(|child| (child, depth + 1)));
+    }
+    Ok(())
+}
+
+fn check(limit: Limit, max: Option<usize>, actual: usize) -> Result<(), FormatError> {
+    match max {
+        Some(max) if actual > max => Err(FormatError::LimitExceeded { limit, max, actual }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Options, format};
+
+    fn format_with_limits(input: &str, limits: Limits) -> Result<String, FormatError> {
+        let options = Options {
+            limits,
+            ..Options::default()
+        };
+        format(input, &options)
+    }
+
+    fn exceeded(result: Result<String, FormatError>) -> Option<(Limit, usize, usize)> {
+        match result {
+            Err(FormatError::LimitExceeded { limit, max, actual }) => Some((limit, max, actual)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_no_limits_by_default() {
+        let input = format!("{}Deep.\n", "> ".repeat(200));
+        assert!(format(&input, &Options::default()).is_ok());
+    }
+
+    #[test]
+    fn test_max_input_bytes() {
+        let limits = Limits {
+            max_input_bytes: Some(8),
+            ..Limits::default()
+        };
+        assert!(format_with_limits("Short.\n", limits).is_ok());
+        assert_eq!(
+            exceeded(format_with_limits("Too long.\n", limits)),
+            Some((Limit::InputBytes, 8, 10))
+        );
+    }
+
+    #[test]
+    fn test_max_nesting_depth() {
+        let limits = Limits {
+            max_nesting_depth: Some(10),
+            ..Limits::default()
+        };
+        assert!(format_with_limits("> > Quoted *text*.\n", limits).is_ok());
+        let input = format!("{}Deep.\n", "> ".repeat(20));
+        let (limit, max, actual) = exceeded(format_with_limits(&input, limits)).unwrap();
+        assert_eq!((limit, max), (Limit::NestingDepth, 10));
+        assert!(actual > 10);
+    }
+
+    #[test]
+    fn test_max_table_columns() {
+        let limits = Limits {
+            max_table_columns: Some(2),
+            ..Limits::default()
+        };
+        assert!(format_with_limits("| a | b |\n|---|---|\n| 1 | 2 |\n", limits).is_ok());
+        assert_eq!(
+            exceeded(format_with_limits(
+                "| a | b | c |\n|---|---|---|\n| 1 | 2 | 3 |\n",
+                limits
+            )),
+            Some((Limit::TableColumns, 2, 3))
+        );
+    }
+
+    #[test]
+    fn test_limit_exceeded_message() {
+        let error = FormatError::LimitExceeded {
+            limit: Limit::TableColumns,
+            max: 2,
+            actual: 3,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Limit exceeded: number of table columns is 3, but the maximum is 2"
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! Inventory of the links of a document.
+//!
+//! Link checkers and migration scripts need every link, image, and
+//! reference definition of a document along with where it is.  [`links`]
+//! returns them from the same parse the formatter uses.
+//!
+//! Comrak resolves reference links while parsing and keeps no trace of the
+//! definitions, so those are found in the source lines outside of code and
+//! HTML blocks, the way the check for undefined references does.
+
+use std::ops::RangeInclusive;
+
+use comrak::Arena;
+use comrak::nodes::{AstNode, NodeValue};
+use regex::Regex;
+
+use crate::serializer::Serializer;
+use crate::{
+    FormatError, LinkKind, Options, guard, limits, parse_markdown, rewriter, split_bom, toc,
+};
+
+/// How a link or image is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkStyle {
+    /// Inline, e.g., `[text](url)`, or an autolink, e.g., `<url>`.
+    Inline,
+    /// Full reference, e.g., `[text][label]`.
+    Full,
+    /// Collapsed reference, e.g., `[text][]`.
+    Collapsed,
+    /// Shortcut reference, e.g., `[text]`.
+    Shortcut,
+    /// Reference definition, e.g., `[label]: url`.
+    Definition,
+}
+
+/// A link, image, autolink, or reference definition of a document, as
+/// returned by [`links`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkInfo {
+    /// What the destination is for.  Reference definitions are
+    /// [`LinkKind::Link`].
+    pub kind: LinkKind,
+    /// How the link is written.
+    pub style: LinkStyle,
+    /// The plain text of the link or the alt text of the image.  For
+    /// reference definitions, the label.
+    pub text: String,
+    /// The destination.
+    pub url: String,
+    /// The title, or an empty string if there is none.
+    pub title: String,
+    /// The reference label, as written, unless the link is inline.
+    pub label: Option<String>,
+    /// The 1-indexed line number where the link starts.
+    pub line: usize,
+}
+
+/// Returns the links, images, autolinks, and reference definitions of
+/// a document in order.
+///
+/// Links using references have the destination and title of their
+/// definitions.  Definitions are listed even if no link uses them.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{LinkKind, LinkStyle, Options, links};
+///
+/// let input = "See [the guide][guide] and ![logo](logo.png).\n\n[guide]: /guide\n";
+/// let links = links(input, &Options::default()).unwrap();
+/// assert_eq!(links.len(), 3);
+/// assert_eq!(links[0].style, LinkStyle::Full);
+/// assert_eq!(links[0].url, "/guide");
+/// assert_eq!(links[1].kind, LinkKind::Image);
+/// assert_eq!(links[2].style, LinkStyle::Definition);
+/// assert_eq!(links[2].line, 3);
+/// ```
+pub fn links(input: &str, options: &Options) -> Result<Vec<LinkInfo>, FormatError> {
+    let (_, input) = split_bom(input, options);
+    guard::catch(|| {
+        limits::check_input(input, &options.limits)?;
+        let arena = Arena::new();
+        let root = parse_markdown(&arena, input, &options.extensions);
+        limits::check_document(root, &options.limits)?;
+
+        let source_lines: Vec<&str> = input.lines().collect();
+        let mut links = collect_links(root, options, &source_lines);
+        links.extend(collect_definitions(root, &source_lines));
+        links.sort_by_key(|link| link.line);
+        Ok(links)
+    })
+}
+
+/// Collect the links and images under `root`.
+fn collect_links<'a>(
+    root: &'a AstNode<'a>,
+    options: &Options,
+    source_lines: &[&str],
+) -> Vec<LinkInfo> {
+    let serializer = Serializer::new(options, source_lines.to_vec(), false);
+    let mut links = Vec::new();
+    for node in root.descendants() {
+        let (link, kind) = match &node.data.borrow().value {
+            NodeValue::Link(link) if rewriter::is_autolink(node, &link.url, &link.title) => {
+                (link.clone(), LinkKind::Autolink)
+            }
+            NodeValue::Link(link) => (link.clone(), LinkKind::Link),
+            NodeValue::Image(image) => (image.clone(), LinkKind::Image),
+            _ => continue,
+        };
+        let reference = match kind {
+            LinkKind::Autolink => None,
+            _ => serializer.source_reference_style_info(node),
+        };
+        // A collapsed reference's label is its text marked with \x01
+        let (style, label) = match reference {
+            None => (LinkStyle::Inline, None),
+            Some((text, label)) if label.starts_with('\x01') => (LinkStyle::Collapsed, Some(text)),
+            Some((text, label)) if text == label => (LinkStyle::Shortcut, Some(label)),
+            Some((_, label)) => (LinkStyle::Full, Some(label)),
+        };
+        let mut text = String::new();
+        toc::collect_text(node, &mut text);
+        links.push(LinkInfo {
+            kind,
+            style,
+            text,
+            url: link.url,
+            title: link.title,
+            label,
+            line: node.data.borrow().sourcepos.start.line,
+        });
+    }
+    links
+}
+
+/// Collect the reference definitions in the source lines.  A line is
+/// a definition if it is outside of code and HTML blocks, and it does not
+/// continue a paragraph: comrak counts the definitions at the start of
+/// a paragraph as part of it, but a definition cannot interrupt one.
+fn collect_definitions<'a>(root: &'a AstNode<'a>, source_lines: &[&str]) -> Vec<LinkInfo> {
+    // A label, a destination, optionally in angle brackets, and an optional
+    // title in double quotes, single quotes, or parentheses
+    let definition = Regex::new(concat!(
+        r#"^\[((?:[^\\\[\]]|\\.)+)\]:[ \t]*(<(?:[^\\<>\n]|\\.)*>|\S+)"#,
+        r#"(?:[ \t]+("(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'|\((?:[^()\\]|\\.)*\)))?[ \t]*$"#,
+    ))
+    .unwrap();
+    let mut excluded: Vec<RangeInclusive<usize>> = Vec::new();
+    let mut paragraphs: Vec<RangeInclusive<usize>> = Vec::new();
+    for node in root.descendants() {
+        let sourcepos = node.data.borrow().sourcepos;
+        let lines = sourcepos.start.line..=sourcepos.end.line;
+        match node.data.borrow().value {
+            NodeValue::CodeBlock(_) | NodeValue::HtmlBlock(_) => excluded.push(lines),
+            NodeValue::Paragraph => paragraphs.push(lines),
+            _ => {}
+        }
+    }
+
+    let mut definitions = Vec::new();
+    let mut previous_is_definition = false;
+    for (i, line) in source_lines.iter().enumerate() {
+        let line_number = i + 1;
+        let content = strip_container_prefixes(line);
+        let continues_paragraph = paragraphs
+            .iter()
+            .any(|range| range.contains(&line_number) && *range.start() != line_number);
+        let caps = definition.captures(content).filter(|_| {
+            !excluded.iter().any(|range| range.contains(&line_number))
+                && (previous_is_definition || !continues_paragraph)
+        });
+        previous_is_definition = caps.is_some();
+        let Some(caps) = caps else {
+            continue;
+        };
+        let url = &caps[2];
+        let url = url
+            .strip_prefix('<')
+            .and_then(|url| url.strip_suffix('>'))
+            .unwrap_or(url);
+        let title = caps.get(3).map_or("", |title| {
+            let title = title.as_str();
+            &title[1..title.len() - 1]
+        });
+        definitions.push(LinkInfo {
+            kind: LinkKind::Link,
+            style: LinkStyle::Definition,
+            text: caps[1].to_string(),
+            url: url.to_string(),
+            title: title.to_string(),
+            label: Some(caps[1].to_string()),
+            line: line_number,
+        });
+    }
+    definitions
+}
+
+/// Strip the indentation and block quote markers in front of the content of
+/// `line`.
+fn strip_container_prefixes(line: &str) -> &str {
+    let mut rest = line.trim_start();
+    while let Some(quoted) = rest.strip_prefix('>') {
+        rest = quoted.trim_start();
+    }
+    rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(input: &str) -> Vec<(LinkKind, LinkStyle, String, String, usize)> {
+        links(input, &Options::default())
+            .unwrap()
+            .into_iter()
+            .map(|link| (link.kind, link.style, link.text, link.url, link.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_links() {
+        let input = "# Links
+
+An [inline](https://example.com/ \"Title\") link, a [full][ref], a [ref][],
+a [ref], an ![image](a.png), and <https://example.com/auto>.
+
+[![Badge](https://example.com/badge.svg)][ref]
+
+[ref]: /ref 'Reference'
+";
+        assert_eq!(
+            summary(input),
+            vec![
+                (
+                    LinkKind::Link,
+                    LinkStyle::Inline,
+                    "inline".to_string(),
+                    "https://example.com/".to_string(),
+                    3
+                ),
+                (
+                    LinkKind::Link,
+                    LinkStyle::Full,
+                    "full".to_string(),
+                    "/ref".to_string(),
+                    3
+                ),
+                (
+                    LinkKind::Link,
+                    LinkStyle::Collapsed,
+                    "ref".to_string(),
+                    "/ref".to_string(),
+                    3
+                ),
+                (
+                    LinkKind::Link,
+                    LinkStyle::Shortcut,
+                    "ref".to_string(),
+                    "/ref".to_string(),
+                    4
+                ),
+                (
+                    LinkKind::Image,
+                    LinkStyle::Inline,
+                    "image".to_string(),
+                    "a.png".to_string(),
+                    4
+                ),
+                (
+                    LinkKind::Autolink,
+                    LinkStyle::Inline,
+                    "https://example.com/auto".to_string(),
+                    "https://example.com/auto".to_string(),
+                    4
+                ),
+                (
+                    LinkKind::Link,
+                    LinkStyle::Full,
+                    "Badge".to_string(),
+                    "/ref".to_string(),
+                    6
+                ),
+                (
+                    LinkKind::Image,
+                    LinkStyle::Inline,
+                    "Badge".to_string(),
+                    "https://example.com/badge.svg".to_string(),
+                    6
+                ),
+                (
+                    LinkKind::Link,
+                    LinkStyle::Definition,
+                    "ref".to_string(),
+                    "/ref".to_string(),
+                    8
+                ),
+            ]
+        );
+        let links = links(input, &Options::default()).unwrap();
+        assert_eq!(links[0].title, "Title");
+        assert_eq!(links[1].label.as_deref(), Some("ref"));
+        assert_eq!(links[8].title, "Reference");
+    }
+
+    #[test]
+    fn test_definitions() {
+        let input = "[a]: <a b.md>
+
+> [b]: /b \"B\"
+> [c]: /c
+
+Text
+[d]: /d
+
+~~~~ markdown
+[e]: /e
+~~~~
+";
+        let definitions: Vec<(String, String, String, usize)> = links(input, &Options::default())
+            .unwrap()
+            .into_iter()
+            .map(|link| (link.text, link.url, link.title, link.line))
+            .collect();
+        assert_eq!(
+            definitions,
+            vec![
+                ("a".to_string(), "a b.md".to_string(), String::new(), 1),
+                ("b".to_string(), "/b".to_string(), "B".to_string(), 3),
+                ("c".to_string(), "/c".to_string(), String::new(), 4),
+            ]
+        );
+    }
+}
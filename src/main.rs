@@ -1,124 +1,248 @@
 //! Hongdown CLI - A Markdown formatter for Hong Minhee's style conventions.
 
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use std::fs;
-use std::io::{self, Read};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use clap::Parser;
-use hongdown::config::Config;
-use hongdown::{CodeFormatter, LineWidth, Options, format_with_warnings, validate_dash_settings};
+use clap::{Parser, Subcommand, ValueEnum};
+use hongdown::config::{Config, WarningsAsErrors};
+use hongdown::{
+    FormatError, LineWidth, Options, RULES, TraceEvent, Warning, diff_text, format,
+    format_with_warnings, verify_equivalent,
+};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use similar::{ChangeTag, TextDiff};
 use walkdir::WalkDir;
 
 /// A Markdown formatter that enforces Hong Minhee's Markdown style conventions.
 #[derive(Parser, Debug)]
 #[command(name = "hongdown")]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
     /// Input file(s) to format. Use `-` to read from stdin.
     #[arg(value_name = "FILE")]
     files: Vec<PathBuf>,
 
     /// Write formatted output back to the input file(s).
-    #[arg(short, long, conflicts_with_all = ["check", "diff"])]
+    #[arg(short, long, conflicts_with_all = ["check", "diff", "check_idempotent", "verify"])]
     write: bool,
 
     /// Check if files are already formatted (exit 1 if not).
-    #[arg(short, long, conflicts_with_all = ["write", "diff"])]
+    #[arg(short, long, conflicts_with_all = ["write", "diff", "check_idempotent", "verify"])]
     check: bool,
 
     /// Show a diff of formatting changes.
-    #[arg(short, long, conflicts_with_all = ["write", "check"])]
+    #[arg(short, long, conflicts_with_all = ["write", "check", "check_idempotent", "verify"])]
     diff: bool,
 
+    /// Check that formatting is idempotent by formatting each file twice
+    /// (exit 1 and show a diff if the second pass changes the output).
+    #[arg(long, conflicts_with_all = ["write", "check", "diff", "verify"])]
+    check_idempotent: bool,
+
+    /// Check that formatting preserves the meaning of each file by parsing
+    /// the output again and comparing it with the input (exit 1 and show the
+    /// changed elements if formatting lost or restructured content).
+    #[arg(long, conflicts_with_all = ["write", "check", "diff", "check_idempotent"])]
+    verify: bool,
+
     /// Read input from stdin (alternative to using `-` as filename).
     #[arg(long)]
     stdin: bool,
 
     /// Line width for wrapping (overrides config file).
-    #[arg(long)]
+    #[arg(long, global = true)]
     line_width: Option<usize>,
 
-    /// Path to configuration file.
-    #[arg(long, value_name = "FILE")]
+    /// Join relative link and image destinations against PREFIX (overrides
+    /// config file).
+    #[arg(long, global = true, value_name = "PREFIX")]
+    link_base: Option<String>,
+
+    /// Path to configuration file, in TOML, or in JSON or YAML if its
+    /// extension is `.json`, or `.yaml` or `.yml`.
+    #[arg(long, global = true, value_name = "FILE")]
     config: Option<PathBuf>,
+
+    /// Fail if the total number of warnings exceeds this threshold.
+    #[arg(long, value_name = "N")]
+    max_warnings: Option<usize>,
+
+    /// Fail if any warning is reported, even if all files are formatted.
+    /// Overrides `warnings_as_errors` in the configuration file, which can
+    /// instead select rules or kinds of warnings.
+    #[arg(long)]
+    warnings_as_errors: bool,
+
+    /// Remember files that are already formatted in FILE, and skip
+    /// formatting them again on later `--check` and `--write` runs as long as
+    /// their contents and the options are unchanged.
+    #[arg(long, value_name = "FILE")]
+    cache: Option<PathBuf>,
+
+    /// Format the sections between h2 headings of each file in parallel,
+    /// which speeds up very large documents.
+    #[arg(long, global = true)]
+    parallel_sections: bool,
+
+    /// Print the formatting decisions made for each block, e.g., which
+    /// paragraphs were wrapped and which links were converted to references,
+    /// to stderr.  Files are formatted again even if `--cache` says they are
+    /// already formatted.
+    #[arg(long, conflicts_with_all = ["diff", "check_idempotent", "verify"])]
+    trace: bool,
+
+    /// When to report progress while processing multiple files.
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    progress: ProgressMode,
+
+    /// When to color diffs and warnings.
+    #[arg(long, global = true, value_name = "WHEN", default_value = "auto")]
+    color: ColorMode,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Subcommands other than formatting.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Describe a warning: what triggers it, an example, and how to configure
+    /// or suppress it.  Lists all warnings if none is given.
+    Explain {
+        /// Identifier, e.g., `HD004`, or code, e.g., `table-cell-html`, of
+        /// the warning.
+        #[arg(value_name = "RULE")]
+        rule: Option<String>,
+    },
+    /// Format each file once and report how long parsing, serialization, and
+    /// code formatters took, instead of printing the formatted output.
+    #[cfg(feature = "bench")]
+    Bench {
+        /// Input file(s) to measure. Use `-` to read from stdin.
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+    },
 }
 
+/// The outcome of processing a single file in parallel: its warnings and a
+/// mode-specific payload, or an error message to print.
+type FileResult<T> = Result<(Vec<Warning>, T), String>;
+
+/// When to report progress for `--write`, `--check`, `--diff`,
+/// `--check-idempotent`, and `--verify` runs.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ProgressMode {
+    /// Show a progress line only when stderr is a terminal.
+    Auto,
+    /// Always report progress; prints one line per file when not on a terminal.
+    Always,
+    /// Never report progress.
+    Never,
+}
+
+/// When to color diffs and warnings.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ColorMode {
+    /// Color output written to a terminal, unless `NO_COLOR` is set; color
+    /// any output if `CLICOLOR_FORCE` is set.
+    Auto,
+    /// Always color output.
+    Always,
+    /// Never color output.
+    Never,
+}
+
+impl ColorMode {
+    /// Whether to color output written to a stream that is or is not
+    /// a `terminal`.
+    fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto if env_flag("NO_COLOR") => false,
+            ColorMode::Auto if env_flag("CLICOLOR_FORCE") => true,
+            ColorMode::Auto => terminal,
+        }
+    }
+}
+
+/// Whether the environment variable `name` is set to a non-empty value
+/// other than `0`.
+fn env_flag(name: &str) -> bool {
+    std::env::var_os(name).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Which of the standard streams to color.
+#[derive(Clone, Copy, Debug)]
+struct Colors {
+    /// Color diffs and reports printed to stdout.
+    stdout: bool,
+    /// Color warnings printed to stderr.
+    stderr: bool,
+}
+
+impl Colors {
+    fn new(mode: ColorMode) -> Self {
+        Colors {
+            stdout: mode.enabled(io::stdout().is_terminal()),
+            stderr: mode.enabled(io::stderr().is_terminal()),
+        }
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
 fn main() -> ExitCode {
     let args = Args::parse();
+    if let Some(Command::Explain { rule }) = &args.command {
+        return explain(rule.as_deref());
+    }
+    let colors = Colors::new(args.color);
 
     // Load configuration
     let (config, config_dir) = load_config(&args);
 
     // Build options, with CLI args overriding config file
-    let options = Options {
-        line_width: args
-            .line_width
-            .map(|w| LineWidth::new(w).expect("Invalid line width"))
-            .unwrap_or(config.line_width),
-        setext_h1: config.heading.setext_h1,
-        setext_h2: config.heading.setext_h2,
-        heading_sentence_case: config.heading.sentence_case,
-        heading_proper_nouns: config.heading.proper_nouns.clone(),
-        heading_common_nouns: config.heading.common_nouns.clone(),
-        unordered_marker: config.unordered_list.unordered_marker,
-        leading_spaces: config.unordered_list.leading_spaces,
-        trailing_spaces: config.unordered_list.trailing_spaces,
-        indent_width: config.unordered_list.indent_width,
-        odd_level_marker: config.ordered_list.odd_level_marker,
-        even_level_marker: config.ordered_list.even_level_marker,
-        ordered_list_pad: config.ordered_list.pad,
-        ordered_list_indent_width: config.ordered_list.indent_width,
-        fence_char: config.code_block.fence_char,
-        min_fence_length: config.code_block.min_fence_length,
-        space_after_fence: config.code_block.space_after_fence,
-        default_language: config.code_block.default_language.clone(),
-        thematic_break_style: config.thematic_break.style.clone(),
-        thematic_break_leading_spaces: config.thematic_break.leading_spaces,
-        curly_double_quotes: config.punctuation.curly_double_quotes,
-        curly_single_quotes: config.punctuation.curly_single_quotes,
-        curly_apostrophes: config.punctuation.curly_apostrophes,
-        ellipsis: config.punctuation.ellipsis,
-        en_dash: config.punctuation.en_dash.clone(),
-        em_dash: config.punctuation.em_dash.clone(),
-        code_formatters: config
-            .code_block
-            .formatters
-            .iter()
-            .map(|(lang, cfg)| {
-                (
-                    lang.clone(),
-                    CodeFormatter {
-                        command: cfg.command().to_vec(),
-                        timeout_secs: cfg.timeout(),
-                    },
-                )
-            })
-            .collect(),
-    };
-
-    // Validate formatter configurations
-    for (lang, cfg) in &config.code_block.formatters {
-        if let Err(msg) = cfg.validate() {
-            eprintln!("Error: formatter for '{}': {}", lang, msg);
+    let mut options = match config.to_options() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Error: {}", e);
             return ExitCode::FAILURE;
         }
+    };
+    if let Some(width) = args.line_width {
+        options.line_width = LineWidth::new(width).expect("Invalid line width");
     }
-
-    // Validate punctuation settings
-    if let Err(e) = validate_dash_settings(&options) {
-        eprintln!("Error: {}", e);
-        return ExitCode::FAILURE;
+    if let Some(link_base) = &args.link_base {
+        options.link_base = link_base.clone();
     }
+    options.parallel_sections = args.parallel_sections;
+    options.trace = args.trace;
+
+    let (file_args, stdin) = match &args.command {
+        #[cfg(feature = "bench")]
+        Some(Command::Bench { files }) => (files, false),
+        _ => (&args.files, args.stdin),
+    };
 
     // Check if stdin is explicitly requested via --stdin or `-` as filename
-    let stdin_requested = args.stdin || args.files.iter().any(|f| f.to_str() == Some("-"));
+    let stdin_requested = stdin || file_args.iter().any(|f| f.to_str() == Some("-"));
 
     // Determine files to process
-    let files: Vec<PathBuf> = if args.files.is_empty() && !stdin_requested {
+    let files: Vec<PathBuf> = if file_args.is_empty() && !stdin_requested {
         // No files specified and stdin not requested, try to use include patterns from config
         if !config.include.is_empty() {
             match config.collect_files(&config_dir) {
@@ -137,18 +261,29 @@ fn main() -> ExitCode {
         }
     } else if stdin_requested {
         // Filter out `-` from files list since we'll handle stdin separately
-        let filtered: Vec<PathBuf> = args
-            .files
+        let filtered: Vec<PathBuf> = file_args
             .iter()
             .filter(|f| f.to_str() != Some("-"))
             .cloned()
             .collect();
         expand_paths(&filtered)
     } else {
-        expand_paths(&args.files)
+        expand_paths(file_args)
     };
 
-    if stdin_requested {
+    #[cfg(feature = "bench")]
+    if let Some(Command::Bench { .. }) = &args.command {
+        return bench_files(&files, stdin_requested, &options);
+    }
+
+    let warnings_as_errors = if args.warnings_as_errors {
+        WarningsAsErrors::All
+    } else {
+        config.warnings_as_errors.clone()
+    };
+    let tally = WarningTally::new(&warnings_as_errors);
+
+    let exit_code = if stdin_requested {
         // Read from stdin
         let mut input = String::new();
         if let Err(e) = io::stdin().read_to_string(&mut input) {
@@ -159,11 +294,33 @@ fn main() -> ExitCode {
         match format_with_warnings(&input, &options) {
             Ok(result) => {
                 // Print warnings to stderr
-                for warning in &result.warnings {
-                    eprintln!("<stdin>:{}: warning: {}", warning.line, warning.message);
-                }
-                if args.diff {
-                    print_diff("<stdin>", &input, &result.output);
+                print_warnings("<stdin>", &result.warnings, colors);
+                print_trace("<stdin>", &result.trace);
+                tally.record(&result.warnings);
+                if args.check_idempotent {
+                    return match idempotency_diff("<stdin>", &result.output, &options) {
+                        Ok(None) => ExitCode::SUCCESS,
+                        Ok(Some(diff)) => {
+                            eprintln!("<stdin>: formatting is not idempotent");
+                            print_report(&diff, colors);
+                            ExitCode::FAILURE
+                        }
+                        Err(e) => {
+                            eprintln!("Error formatting: {}", e);
+                            ExitCode::FAILURE
+                        }
+                    };
+                } else if args.verify {
+                    return match verify_equivalent(&input, &result.output, &options) {
+                        Ok(()) => ExitCode::SUCCESS,
+                        Err(diff) => {
+                            eprintln!("<stdin>: formatting changed the document");
+                            print_report(&diff.to_string(), colors);
+                            ExitCode::FAILURE
+                        }
+                    };
+                } else if args.diff {
+                    print_report(&render_diff("<stdin>", &input, &result.output), colors);
                 } else {
                     print!("{}", result.output);
                 }
@@ -176,71 +333,222 @@ fn main() -> ExitCode {
         }
     } else if args.write || args.check {
         // Parallel processing for --write and --check modes
-        process_files_parallel(&files, &options, args.write, args.check)
+        // Traced files are formatted again so that their decisions are printed
+        let cache = args
+            .cache
+            .as_deref()
+            .filter(|_| !args.trace)
+            .map(|path| FormattedCache::load(path, &options));
+        let progress = Progress::new(args.progress, files.len());
+        let exit_code = process_files_parallel(
+            &files,
+            &options,
+            args.write,
+            args.check,
+            cache.as_ref(),
+            &tally,
+            &progress,
+            colors,
+        );
+        if let (Some(cache), Some(path)) = (&cache, &args.cache)
+            && let Err(e) = cache.save(path)
+        {
+            eprintln!("Error writing cache {}: {}", path.display(), e);
+        }
+        exit_code
     } else if args.diff {
         // Diff mode for files
-        process_files_diff(&files, &options)
+        let progress = Progress::new(args.progress, files.len());
+        process_files_diff(&files, &options, &tally, &progress, colors)
+    } else if args.check_idempotent {
+        // Idempotency check mode for files
+        let progress = Progress::new(args.progress, files.len());
+        process_files_checked(
+            &files,
+            &options,
+            check_idempotent_file,
+            "formatting is not idempotent",
+            &tally,
+            &progress,
+            colors,
+        )
+    } else if args.verify {
+        // Semantic equivalence check mode for files
+        let progress = Progress::new(args.progress, files.len());
+        process_files_checked(
+            &files,
+            &options,
+            verify_file,
+            "formatting changed the document",
+            &tally,
+            &progress,
+            colors,
+        )
     } else {
         // Sequential processing for stdout mode (order matters)
-        process_files_sequential(&files, &options)
+        process_files_sequential(&files, &options, &tally, colors)
+    };
+
+    // Enforce the warning threshold, if any
+    if let Some(max_warnings) = args.max_warnings {
+        let count = tally.total.load(Ordering::Relaxed);
+        if count > max_warnings {
+            eprintln!(
+                "Error: found {} warning(s), which exceeds the maximum of {}.",
+                count, max_warnings
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // Fail on warnings treated as errors, if any
+    let errors = tally.errors.load(Ordering::Relaxed);
+    if errors > 0 {
+        eprintln!("Error: found {} warning(s) treated as errors.", errors);
+        return ExitCode::FAILURE;
+    }
+
+    exit_code
+}
+
+/// Measure and print the formatting phase timings of each file (for the
+/// `bench` subcommand).  Files are measured one at a time so that they do not compete for
+/// CPU time.
+#[cfg(feature = "bench")]
+fn bench_files(files: &[PathBuf], stdin_requested: bool, options: &Options) -> ExitCode {
+    let mut inputs: Vec<(String, String)> = Vec::new();
+    if stdin_requested {
+        let mut input = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut input) {
+            eprintln!("Error reading stdin: {}", e);
+            return ExitCode::FAILURE;
+        }
+        inputs.push(("<stdin>".to_string(), input));
+    }
+    let mut exit_code = ExitCode::SUCCESS;
+    for file in files {
+        match fs::read_to_string(file) {
+            Ok(input) => inputs.push((file.display().to_string(), input)),
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                exit_code = ExitCode::FAILURE;
+            }
+        }
     }
+
+    let millis = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+    for (name, input) in &inputs {
+        match hongdown::measure(input, options) {
+            Ok(timings) => println!(
+                "{}: parse {:.3} ms, transform {:.3} ms, serialize {:.3} ms, \
+                 code formatters {:.3} ms, total {:.3} ms",
+                name,
+                millis(timings.parse),
+                millis(timings.transform),
+                millis(timings.serialize),
+                millis(timings.code_formatter),
+                millis(timings.total()),
+            ),
+            Err(e) => {
+                eprintln!("Error formatting {}: {}", name, e);
+                exit_code = ExitCode::FAILURE;
+            }
+        }
+    }
+    exit_code
+}
+
+/// Iterate over files in parallel, or sequentially when the `parallel`
+/// feature is disabled (e.g., on WASI targets without thread support).
+#[cfg(feature = "parallel")]
+fn file_iter(files: &[PathBuf]) -> rayon::slice::Iter<'_, PathBuf> {
+    files.par_iter()
+}
+
+/// Iterate over files in parallel, or sequentially when the `parallel`
+/// feature is disabled (e.g., on WASI targets without thread support).
+#[cfg(not(feature = "parallel"))]
+fn file_iter(files: &[PathBuf]) -> std::slice::Iter<'_, PathBuf> {
+    files.iter()
 }
 
 /// Process files in parallel (for --write and --check modes).
+#[allow(clippy::too_many_arguments)]
 fn process_files_parallel(
     files: &[PathBuf],
     options: &Options,
     write: bool,
     check: bool,
+    cache: Option<&FormattedCache>,
+    tally: &WarningTally,
+    progress: &Progress,
+    colors: Colors,
 ) -> ExitCode {
     let has_error = AtomicBool::new(false);
     let all_formatted = AtomicBool::new(true);
 
-    files.par_iter().for_each(|file| {
+    file_iter(files).for_each(|file| {
         let input = match fs::read_to_string(file) {
             Ok(content) => content,
             Err(e) => {
-                eprintln!("Error reading {}: {}", file.display(), e);
+                progress.suspend(|| eprintln!("Error reading {}: {}", file.display(), e));
                 has_error.store(true, Ordering::Relaxed);
+                progress.tick(file);
                 return;
             }
         };
 
+        // Skip files that were already formatted on a previous run
+        if cache.is_some_and(|cache| cache.is_formatted(file, &input)) {
+            progress.tick(file);
+            return;
+        }
+
         match format_with_warnings(&input, options) {
             Ok(result) => {
+                // Files with warnings are not cached so that the warnings are
+                // reported again on the next run
+                let cacheable = result.warnings.is_empty();
                 // Print warnings to stderr
-                for warning in &result.warnings {
-                    eprintln!(
-                        "{}:{}: warning: {}",
-                        file.display(),
-                        warning.line,
-                        warning.message
-                    );
-                }
+                progress.suspend(|| {
+                    print_warnings(&file.display().to_string(), &result.warnings, colors);
+                    print_trace(&file.display().to_string(), &result.trace);
+                });
+                tally.record(&result.warnings);
 
                 if check {
                     if input != result.output {
-                        eprintln!("{}: not formatted", file.display());
+                        progress.suspend(|| eprintln!("{}: not formatted", file.display()));
                         all_formatted.store(false, Ordering::Relaxed);
+                    } else if let Some(cache) = cache.filter(|_| cacheable) {
+                        cache.record(file, &input);
                     }
                 } else if write && input != result.output {
                     match fs::write(file, &result.output) {
                         Ok(()) => {
-                            println!("{}", file.display());
+                            progress.suspend(|| println!("{}", file.display()));
+                            if let Some(cache) = cache.filter(|_| cacheable) {
+                                cache.record(file, &result.output);
+                            }
                         }
                         Err(e) => {
-                            eprintln!("Error writing {}: {}", file.display(), e);
+                            progress
+                                .suspend(|| eprintln!("Error writing {}: {}", file.display(), e));
                             has_error.store(true, Ordering::Relaxed);
                         }
                     }
+                } else if let Some(cache) = cache.filter(|_| cacheable) {
+                    cache.record(file, &input);
                 }
             }
             Err(e) => {
-                eprintln!("Error formatting {}: {}", file.display(), e);
+                progress.suspend(|| eprintln!("Error formatting {}: {}", file.display(), e));
                 has_error.store(true, Ordering::Relaxed);
             }
         }
+        progress.tick(file);
     });
+    progress.finish();
 
     if has_error.load(Ordering::Relaxed) || (check && !all_formatted.load(Ordering::Relaxed)) {
         ExitCode::FAILURE
@@ -250,7 +558,12 @@ fn process_files_parallel(
 }
 
 /// Process files sequentially (for stdout mode where order matters).
-fn process_files_sequential(files: &[PathBuf], options: &Options) -> ExitCode {
+fn process_files_sequential(
+    files: &[PathBuf],
+    options: &Options,
+    tally: &WarningTally,
+    colors: Colors,
+) -> ExitCode {
     for file in files {
         let input = match fs::read_to_string(file) {
             Ok(content) => content,
@@ -263,14 +576,9 @@ fn process_files_sequential(files: &[PathBuf], options: &Options) -> ExitCode {
         match format_with_warnings(&input, options) {
             Ok(result) => {
                 // Print warnings to stderr
-                for warning in &result.warnings {
-                    eprintln!(
-                        "{}:{}: warning: {}",
-                        file.display(),
-                        warning.line,
-                        warning.message
-                    );
-                }
+                print_warnings(&file.display().to_string(), &result.warnings, colors);
+                print_trace(&file.display().to_string(), &result.trace);
+                tally.record(&result.warnings);
                 print!("{}", result.output);
             }
             Err(e) => {
@@ -283,64 +591,441 @@ fn process_files_sequential(files: &[PathBuf], options: &Options) -> ExitCode {
     ExitCode::SUCCESS
 }
 
+/// Print the description of `rule`, or list all rules if it is `None`.
+fn explain(rule: Option<&str>) -> ExitCode {
+    let Some(name) = rule else {
+        for rule in RULES {
+            println!("{}  {:<22}  {}", rule.id, rule.code, rule.summary);
+        }
+        return ExitCode::SUCCESS;
+    };
+    match hongdown::rule(name) {
+        Some(rule) => {
+            print!("{}", rule.explain());
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!(
+                "Unknown rule: {}; run `hongdown explain` to list all rules",
+                name
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Counts of the warnings reported across all files.
+struct WarningTally<'a> {
+    /// Number of all warnings.
+    total: AtomicUsize,
+    /// Number of warnings treated as errors.
+    errors: AtomicUsize,
+    warnings_as_errors: &'a WarningsAsErrors,
+}
+
+impl<'a> WarningTally<'a> {
+    fn new(warnings_as_errors: &'a WarningsAsErrors) -> Self {
+        WarningTally {
+            total: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+            warnings_as_errors,
+        }
+    }
+
+    /// Count the warnings reported for a file.
+    fn record(&self, warnings: &[Warning]) {
+        let errors = warnings
+            .iter()
+            .filter(|warning| self.warnings_as_errors.contains(warning.code))
+            .count();
+        self.total.fetch_add(warnings.len(), Ordering::Relaxed);
+        self.errors.fetch_add(errors, Ordering::Relaxed);
+    }
+}
+
+/// Print warnings to stderr, along with the identifiers of their rules,
+/// which `hongdown explain` describes.
+fn print_warnings(filename: &str, warnings: &[Warning], colors: Colors) {
+    for warning in warnings {
+        if colors.stderr {
+            eprintln!(
+                "{BOLD}{}:{}:{RESET} {BOLD}{YELLOW}warning:{RESET} {} [{}]",
+                filename,
+                warning.line,
+                warning.message,
+                warning.code.rule().id
+            );
+        } else {
+            eprintln!(
+                "{}:{}: warning: {} [{}]",
+                filename,
+                warning.line,
+                warning.message,
+                warning.code.rule().id
+            );
+        }
+    }
+}
+
+/// Print the formatting decisions recorded with `--trace` to stderr.
+fn print_trace(filename: &str, trace: &[TraceEvent]) {
+    for event in trace {
+        eprintln!(
+            "{}:{}: trace: {}: {}",
+            filename,
+            event.line,
+            event.kind.as_str(),
+            event.detail
+        );
+    }
+}
+
 /// Process files in diff mode.
-fn process_files_diff(files: &[PathBuf], options: &Options) -> ExitCode {
-    for file in files {
-        let input = match fs::read_to_string(file) {
-            Ok(content) => content,
-            Err(e) => {
-                eprintln!("Error reading {}: {}", file.display(), e);
+///
+/// Files are read, formatted, and diffed in parallel; only printing happens
+/// sequentially so that the output order matches the input order.
+fn process_files_diff(
+    files: &[PathBuf],
+    options: &Options,
+    tally: &WarningTally,
+    progress: &Progress,
+    colors: Colors,
+) -> ExitCode {
+    let results: Vec<FileResult<String>> = file_iter(files)
+        .map(|file| {
+            let result = diff_file(file, options);
+            progress.tick(file);
+            result
+        })
+        .collect();
+    progress.finish();
+
+    for (file, result) in files.iter().zip(results) {
+        match result {
+            Ok((warnings, diff)) => {
+                // Print warnings to stderr
+                print_warnings(&file.display().to_string(), &warnings, colors);
+                tally.record(&warnings);
+                print_report(&diff, colors);
+            }
+            Err(message) => {
+                eprintln!("{}", message);
                 return ExitCode::FAILURE;
             }
-        };
+        }
+    }
 
-        match format_with_warnings(&input, options) {
-            Ok(result) => {
+    ExitCode::SUCCESS
+}
+
+/// Format a single file and render its diff, returning its warnings as well.
+fn diff_file(file: &Path, options: &Options) -> FileResult<String> {
+    let input =
+        fs::read_to_string(file).map_err(|e| format!("Error reading {}: {}", file.display(), e))?;
+    let result = format_with_warnings(&input, options)
+        .map_err(|e| format!("Error formatting {}: {}", file.display(), e))?;
+    let diff = render_diff(&file.display().to_string(), &input, &result.output);
+    Ok((result.warnings, diff))
+}
+
+/// Process files in idempotency or semantic equivalence check mode.
+///
+/// Each file is checked in parallel with `check`; any file for which it
+/// returns a report is printed along with `failure` and the report.
+fn process_files_checked(
+    files: &[PathBuf],
+    options: &Options,
+    check: fn(&Path, &Options) -> FileResult<Option<String>>,
+    failure: &str,
+    tally: &WarningTally,
+    progress: &Progress,
+    colors: Colors,
+) -> ExitCode {
+    let results: Vec<FileResult<Option<String>>> = file_iter(files)
+        .map(|file| {
+            let result = check(file, options);
+            progress.tick(file);
+            result
+        })
+        .collect();
+    progress.finish();
+
+    let mut exit_code = ExitCode::SUCCESS;
+    for (file, result) in files.iter().zip(results) {
+        match result {
+            Ok((warnings, report)) => {
                 // Print warnings to stderr
-                for warning in &result.warnings {
-                    eprintln!(
-                        "{}:{}: warning: {}",
-                        file.display(),
-                        warning.line,
-                        warning.message
-                    );
+                print_warnings(&file.display().to_string(), &warnings, colors);
+                tally.record(&warnings);
+                if let Some(report) = report {
+                    eprintln!("{}: {}", file.display(), failure);
+                    print_report(&report, colors);
+                    exit_code = ExitCode::FAILURE;
                 }
-                print_diff(&file.display().to_string(), &input, &result.output);
             }
-            Err(e) => {
-                eprintln!("Error formatting {}: {}", file.display(), e);
-                return ExitCode::FAILURE;
+            Err(message) => {
+                eprintln!("{}", message);
+                exit_code = ExitCode::FAILURE;
             }
         }
     }
 
-    ExitCode::SUCCESS
+    exit_code
+}
+
+/// Format a single file twice, returning the first pass's warnings and the
+/// diff between both passes if they differ.
+fn check_idempotent_file(file: &Path, options: &Options) -> FileResult<Option<String>> {
+    let input =
+        fs::read_to_string(file).map_err(|e| format!("Error reading {}: {}", file.display(), e))?;
+    let result = format_with_warnings(&input, options)
+        .map_err(|e| format!("Error formatting {}: {}", file.display(), e))?;
+    let diff = idempotency_diff(&file.display().to_string(), &result.output, options)
+        .map_err(|e| format!("Error formatting {}: {}", file.display(), e))?;
+    Ok((result.warnings, diff))
 }
 
-/// Print a unified diff between original and formatted content.
-fn print_diff(filename: &str, original: &str, formatted: &str) {
+/// Format a single file and parse the output again, returning the warnings
+/// and the semantic changes formatting made, if any.
+fn verify_file(file: &Path, options: &Options) -> FileResult<Option<String>> {
+    let input =
+        fs::read_to_string(file).map_err(|e| format!("Error reading {}: {}", file.display(), e))?;
+    let result = format_with_warnings(&input, options)
+        .map_err(|e| format!("Error formatting {}: {}", file.display(), e))?;
+    let report = verify_equivalent(&input, &result.output, options)
+        .err()
+        .map(|diff| diff.to_string());
+    Ok((result.warnings, report))
+}
+
+/// Format already formatted content again and render a diff between the
+/// first and second passes, or return `None` if formatting is stable.
+fn idempotency_diff(
+    filename: &str,
+    first_pass: &str,
+    options: &Options,
+) -> Result<Option<String>, FormatError> {
+    let second_pass = format(first_pass, options)?;
+    if first_pass == second_pass {
+        return Ok(None);
+    }
+    Ok(Some(render_diff(filename, first_pass, &second_pass)))
+}
+
+/// Print a diff rendered by [`render_diff`] or a semantic change report to
+/// stdout, coloring removed and added lines if stdout is to be colored.
+fn print_report(report: &str, colors: Colors) {
+    if colors.stdout {
+        print!("{}", colorize_report(report));
+    } else {
+        print!("{}", report);
+    }
+}
+
+/// Color the file headers, hunk headers, and removed and added lines of
+/// a diff or semantic change report.
+fn colorize_report(report: &str) -> String {
+    let mut output = String::with_capacity(report.len());
+    let mut in_hunk = false;
+    for line in report.split_inclusive('\n') {
+        let text = line.strip_suffix('\n').unwrap_or(line);
+        let color = if text.starts_with("@@") {
+            in_hunk = true;
+            CYAN
+        } else if !in_hunk && (text.starts_with("--- ") || text.starts_with("+++ ")) {
+            BOLD
+        } else if in_hunk && text.starts_with('-') {
+            RED
+        } else if in_hunk && text.starts_with('+') {
+            GREEN
+        } else {
+            output.push_str(line);
+            continue;
+        };
+        output.push_str(color);
+        output.push_str(text);
+        output.push_str(RESET);
+        output.push_str(&line[text.len()..]);
+    }
+    output
+}
+
+/// Render a unified diff between original and formatted content.
+///
+/// Returns an empty string if the contents are identical.
+fn render_diff(filename: &str, original: &str, formatted: &str) -> String {
+    let mut output = String::new();
     if original == formatted {
-        return;
+        return output;
     }
 
-    let diff = TextDiff::from_lines(original, formatted);
+    let _ = writeln!(output, "--- {}", filename);
+    let _ = writeln!(output, "+++ {}", filename);
+    for hunk in diff_text(original, formatted) {
+        let _ = write!(output, "{}", hunk);
+    }
+
+    output
+}
 
-    println!("--- {}", filename);
-    println!("+++ {}", filename);
+/// The header of cache files written by [`FormattedCache::save`].
+const CACHE_HEADER: &str = "hongdown-cache 1";
 
-    for hunk in diff.unified_diff().iter_hunks() {
-        println!("{}", hunk.header());
-        for change in hunk.iter_changes() {
-            let sign = match change.tag() {
-                ChangeTag::Delete => '-',
-                ChangeTag::Insert => '+',
-                ChangeTag::Equal => ' ',
-            };
-            print!("{}{}", sign, change.value());
-            if !change.value().ends_with('\n') {
-                println!();
+/// Files known to be formatted, persisted between runs with `--cache`.
+///
+/// Each entry maps a file path to a hash of its formatted contents.  The whole
+/// cache is tied to a fingerprint of the Hongdown version and the options, so
+/// changing either invalidates it.  Whole files are hashed rather than
+/// individual blocks, because reference definitions, footnotes, and
+/// directives make the output of a block depend on the rest of the document.
+struct FormattedCache {
+    fingerprint: u64,
+    entries: Mutex<BTreeMap<PathBuf, u64>>,
+}
+
+impl FormattedCache {
+    /// Load the cache at `path`, or start an empty one if it does not exist,
+    /// cannot be read, or was written for other options.
+    fn load(path: &Path, options: &Options) -> Self {
+        let fingerprint = options_fingerprint(options);
+        let mut entries = BTreeMap::new();
+        if let Ok(content) = fs::read_to_string(path) {
+            let mut lines = content.lines();
+            let expected = format!("{} {:016x}", CACHE_HEADER, fingerprint);
+            if lines.next() == Some(expected.as_str()) {
+                for line in lines {
+                    if let Some((hash, file)) = line.split_once('\t')
+                        && let Ok(hash) = u64::from_str_radix(hash, 16)
+                    {
+                        entries.insert(PathBuf::from(file), hash);
+                    }
+                }
             }
         }
+        FormattedCache {
+            fingerprint,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Whether `file` was formatted with these contents on a previous run.
+    fn is_formatted(&self, file: &Path, content: &str) -> bool {
+        let entries = self.entries.lock().unwrap();
+        entries.get(file) == Some(&content_hash(content))
+    }
+
+    /// Remember that `file` is formatted with the given contents.
+    fn record(&self, file: &Path, content: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(file.to_path_buf(), content_hash(content));
+    }
+
+    /// Write the cache to `path`.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let entries = self.entries.lock().unwrap();
+        let mut content = format!("{} {:016x}\n", CACHE_HEADER, self.fingerprint);
+        for (file, hash) in entries.iter() {
+            let _ = writeln!(content, "{:016x}\t{}", hash, file.display());
+        }
+        fs::write(path, content)
+    }
+}
+
+/// Hash the Hongdown version and the options that affect the output.
+fn options_fingerprint(options: &Options) -> u64 {
+    let mut options = options.clone();
+    // Sort the formatters so that the fingerprint does not depend on the
+    // iteration order of the hash map
+    let formatters: BTreeMap<_, _> = std::mem::take(&mut options.code_formatters)
+        .into_iter()
+        .map(|(language, formatter)| (language, format!("{:?}", formatter)))
+        .collect();
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    format!("{:?}", options).hash(&mut hasher);
+    formatters.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash the contents of a file for the cache.
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Progress reporter shared across the rayon workers.
+///
+/// On a terminal, a single status line on stderr is redrawn in place;
+/// otherwise, one line is printed per processed file.
+struct Progress {
+    enabled: bool,
+    interactive: bool,
+    total: usize,
+    done: AtomicUsize,
+    lock: Mutex<()>,
+}
+
+impl Progress {
+    /// Create a progress reporter for `total` files.
+    fn new(mode: ProgressMode, total: usize) -> Self {
+        let interactive = io::stderr().is_terminal();
+        let enabled = match mode {
+            ProgressMode::Auto => interactive && total > 1,
+            ProgressMode::Always => true,
+            ProgressMode::Never => false,
+        };
+        Progress {
+            enabled,
+            interactive,
+            total,
+            done: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Record that a file has been processed.
+    fn tick(&self, file: &Path) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if !self.enabled {
+            return;
+        }
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stderr = io::stderr().lock();
+        if self.interactive {
+            let _ = write!(
+                stderr,
+                "\r\x1b[2K[{}/{}] {}",
+                done,
+                self.total,
+                file.display()
+            );
+        } else {
+            let _ = writeln!(stderr, "[{}/{}] {}", done, self.total, file.display());
+        }
+        let _ = stderr.flush();
+    }
+
+    /// Clear the status line, run `f`, and let the next tick redraw it.
+    ///
+    /// Use this around any output printed while progress is shown so that
+    /// messages do not get appended to the status line.
+    fn suspend<F: FnOnce()>(&self, f: F) {
+        if !(self.enabled && self.interactive) {
+            f();
+            return;
+        }
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        eprint!("\r\x1b[2K");
+        f();
+    }
+
+    /// Clear the status line once all files have been processed.
+    fn finish(&self) {
+        if self.enabled && self.interactive {
+            let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+            eprint!("\r\x1b[2K");
+        }
     }
 }
 
@@ -385,7 +1070,9 @@ fn collect_md_files(dir: &Path) -> Vec<PathBuf> {
 ///
 /// Priority:
 /// 1. Explicit `--config` path
-/// 2. Auto-discovered `.hongdown.toml` in current or parent directories
+/// 2. Auto-discovered `.hongdown.toml`, `.hongdown.json`,
+///    `hongdown.config.json`, `.hongdown.yaml`, or `.hongdown.yml` in
+///    current or parent directories
 /// 3. Default configuration
 fn load_config(args: &Args) -> (Config, PathBuf) {
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
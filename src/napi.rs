@@ -0,0 +1,125 @@
+//! Node.js N-API bindings for Hongdown.
+//!
+//! This module provides a native Node.js addon exposing the formatter and
+//! configuration loading without subprocess or WASM marshaling overhead.
+
+use std::path::Path;
+
+use napi::{Error, Result};
+use napi_derive::napi;
+
+use crate::Options;
+use crate::config::Config;
+use crate::js_options::JsOptions;
+
+/// Format result with warnings.
+#[napi(object)]
+pub struct NodeFormatResult {
+    /// The formatted Markdown output.
+    pub output: String,
+    /// Warnings generated during formatting.
+    pub warnings: Vec<NodeWarning>,
+}
+
+/// A warning generated during formatting.
+#[napi(object)]
+pub struct NodeWarning {
+    /// Line number where the warning was generated (1-indexed).
+    pub line: u32,
+    /// Column number where the warning was generated (1-indexed), or 0 if
+    /// unknown.
+    pub column: u32,
+    /// Machine-readable warning code (e.g., `"undefined-reference"`).
+    pub code: String,
+    /// Category of the warning (e.g., `"tables"`).
+    pub kind: String,
+    /// Warning message.
+    pub message: String,
+}
+
+impl From<crate::Warning> for NodeWarning {
+    fn from(warning: crate::Warning) -> Self {
+        NodeWarning {
+            line: warning.line as u32,
+            column: warning.column as u32,
+            code: warning.code.to_string(),
+            kind: warning.kind.as_str().to_string(),
+            message: warning.message,
+        }
+    }
+}
+
+/// A configuration file found by [`discover_config`].
+#[napi(object)]
+pub struct NodeDiscoveredConfig {
+    /// Path to the discovered *.hongdown.toml*, *.hongdown.json*, or
+    /// *.hongdown.yaml* file.
+    pub path: String,
+    /// Formatting options from the configuration file.
+    pub options: serde_json::Value,
+}
+
+/// Convert a JSON-like options value to Rust [`Options`].
+fn parse_options(options: Option<serde_json::Value>) -> Result<Options> {
+    let js_opts: JsOptions = match options {
+        None | Some(serde_json::Value::Null) => JsOptions::default(),
+        Some(serde_json::Value::String(json)) => {
+            JsOptions::from_json(&json).map_err(Error::from_reason)?
+        }
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| Error::from_reason(format!("invalid options: {}.", e)))?,
+    };
+    js_opts.to_options().map_err(Error::from_reason)
+}
+
+/// Convert a configuration to a JSON-like options value.
+fn config_to_options(config: &Config) -> Result<serde_json::Value> {
+    serde_json::to_value(JsOptions::from(config)).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// Format Markdown according to Hong Minhee's style conventions.
+#[napi]
+pub fn format(input: String, options: Option<serde_json::Value>) -> Result<String> {
+    let opts = parse_options(options)?;
+    crate::format(&input, &opts).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// Format Markdown and return both output and warnings.
+#[napi(js_name = "formatWithWarnings")]
+pub fn format_with_warnings(
+    input: String,
+    options: Option<serde_json::Value>,
+) -> Result<NodeFormatResult> {
+    let opts = parse_options(options)?;
+    let result = crate::format_with_warnings(&input, &opts)
+        .map_err(|e| Error::from_reason(e.to_string()))?;
+    Ok(NodeFormatResult {
+        output: result.output,
+        warnings: result.warnings.into_iter().map(NodeWarning::from).collect(),
+    })
+}
+
+/// Load formatting options from a TOML, JSON, or YAML configuration file,
+/// e.g., *.hongdown.toml*.
+#[napi(js_name = "loadConfig")]
+pub fn load_config(path: String) -> Result<serde_json::Value> {
+    let config =
+        Config::from_file(Path::new(&path)).map_err(|e| Error::from_reason(e.to_string()))?;
+    config_to_options(&config)
+}
+
+/// Find a *.hongdown.toml*, *.hongdown.json*, *hongdown.config.json*,
+/// *.hongdown.yaml*, or *.hongdown.yml* file in the given directory or its
+/// ancestors and load formatting options from it.  Returns `null` if none
+/// is found.
+#[napi(js_name = "discoverConfig")]
+pub fn discover_config(start_dir: String) -> Result<Option<NodeDiscoveredConfig>> {
+    match Config::discover(Path::new(&start_dir)) {
+        Ok(Some((path, config))) => Ok(Some(NodeDiscoveredConfig {
+            path: path.display().to_string(),
+            options: config_to_options(&config)?,
+        })),
+        Ok(None) => Ok(None),
+        Err(e) => Err(Error::from_reason(e.to_string())),
+    }
+}
@@ -0,0 +1,112 @@
+//! Extraction of the outline of a document.
+//!
+//! Tools such as sidebars and table of contents generators only need the
+//! headings of a document.  [`outline`] returns them from the same parse
+//! the formatter uses, so that they need no Markdown parser of their own.
+
+use comrak::Arena;
+
+use crate::{FormatError, Options, guard, limits, parse_markdown, split_bom, toc};
+
+/// A heading of a document, as returned by [`outline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadingInfo {
+    /// The level of the heading, from 1 to 6.
+    pub level: u8,
+    /// The plain text of the heading, including code spans and image alt
+    /// text, but without any other formatting.
+    pub text: String,
+    /// The anchor GitHub generates for the heading, without the leading `#`.
+    /// A heading whose anchor an earlier heading already has gets a numeric
+    /// suffix, e.g., `usage-1`.  See also [`slugify`](crate::slugify).
+    pub slug: String,
+    /// The 1-indexed line number where the heading starts.
+    pub line: usize,
+}
+
+/// Returns the headings of a document in order.
+///
+/// The document is parsed with [`Options::extensions`] within
+/// [`Options::limits`], but [`Options::transforms`] are not applied, so
+/// the headings are those of the input as written.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{Options, outline};
+///
+/// let headings = outline("Title\n=====\n\n## What's new?\n", &Options::default()).unwrap();
+/// assert_eq!(headings.len(), 2);
+/// assert_eq!(headings[1].level, 2);
+/// assert_eq!(headings[1].text, "What's new?");
+/// assert_eq!(headings[1].slug, "whats-new");
+/// assert_eq!(headings[1].line, 4);
+/// ```
+pub fn outline(input: &str, options: &Options) -> Result<Vec<HeadingInfo>, FormatError> {
+    let (_, input) = split_bom(input, options);
+    guard::catch(|| {
+        limits::check_input(input, &options.limits)?;
+        let arena = Arena::new();
+        let root = parse_markdown(&arena, input, &options.extensions);
+        limits::check_document(root, &options.limits)?;
+        Ok(toc::headings(root)
+            .into_iter()
+            .map(|heading| HeadingInfo {
+                level: heading.level,
+                text: heading.text.trim().to_string(),
+                slug: heading.slug,
+                line: heading.node.data.borrow().sourcepos.start.line,
+            })
+            .collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline() {
+        let input = "---
+title: Example
+---
+
+# Usage
+
+Text.
+
+## Options `--foo`
+
+Usage
+-----
+
+ -  Not a heading
+
+> ### Quoted
+";
+        let headings = outline(input, &Options::default()).unwrap();
+        let summary: Vec<(u8, &str, &str, usize)> = headings
+            .iter()
+            .map(|h| (h.level, h.text.as_str(), h.slug.as_str(), h.line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "Usage", "usage", 5),
+                (2, "Options --foo", "options---foo", 9),
+                (2, "Usage", "usage-1", 11),
+                (3, "Quoted", "quoted", 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outline_empty() {
+        assert!(outline("", &Options::default()).unwrap().is_empty());
+        assert!(
+            outline("Just a paragraph.\n", &Options::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
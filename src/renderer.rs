@@ -0,0 +1,166 @@
+//! Custom rendering of inline nodes.
+//!
+//! Library users can register an [`InlineRenderer`] for a kind of inline node
+//! in [`Options::inline_renderers`].  The serializer consults the table before
+//! its built-in rendering, so downstream tools can change how, for example,
+//! footnote references or inline HTML are written without patching it.
+
+use comrak::nodes::{AstNode, NodeValue};
+
+#[cfg(doc)]
+use crate::Options;
+
+/// Kinds of inline nodes that can have a custom [`InlineRenderer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InlineKind {
+    /// Plain text.
+    Text,
+    /// Code span, e.g., `` `code` ``.
+    Code,
+    /// Inline HTML, e.g., `<kbd>`.
+    HtmlInline,
+    /// Emphasis, e.g., `*text*`.
+    Emph,
+    /// Strong emphasis, e.g., `**text**`.
+    Strong,
+    /// Link, e.g., `[text](url)`.
+    Link,
+    /// Image, e.g., `![alt](url)`.
+    Image,
+    /// Footnote reference, e.g., `[^1]`.
+    FootnoteReference,
+}
+
+impl InlineKind {
+    /// Returns the kind of the given node value, or `None` if it is not an
+    /// inline node that supports custom rendering.
+    pub fn of(value: &NodeValue) -> Option<Self> {
+        match value {
+            NodeValue::Text(_) => Some(InlineKind::Text),
+            NodeValue::Code(_) => Some(InlineKind::Code),
+            NodeValue::HtmlInline(_) => Some(InlineKind::HtmlInline),
+            NodeValue::Emph => Some(InlineKind::Emph),
+            NodeValue::Strong => Some(InlineKind::Strong),
+            NodeValue::Link(_) => Some(InlineKind::Link),
+            NodeValue::Image(_) => Some(InlineKind::Image),
+            NodeValue::FootnoteReference(_) => Some(InlineKind::FootnoteReference),
+            _ => None,
+        }
+    }
+}
+
+/// A custom renderer for one kind of inline node.
+///
+/// Returning `Some` replaces the built-in rendering of the node, including
+/// its children, with the returned Markdown as-is.  Returning `None` falls
+/// back to the built-in rendering.
+///
+/// Functions with the matching signature implement this trait as well.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use hongdown::comrak::nodes::{AstNode, NodeValue};
+/// use hongdown::{InlineKind, Options, format};
+///
+/// /// Writes `<kbd>` elements in uppercase.
+/// fn uppercase_html<'a>(node: &'a AstNode<'a>) -> Option<String> {
+///     match &node.data.borrow().value {
+///         NodeValue::HtmlInline(html) => Some(html.to_uppercase()),
+///         _ => None,
+///     }
+/// }
+///
+/// let mut options = Options::default();
+/// options
+///     .inline_renderers
+///     .insert(InlineKind::HtmlInline, Arc::new(uppercase_html));
+/// let output = format("Press <kbd>Enter</kbd>.", &options).unwrap();
+/// assert_eq!(output, "Press <KBD>Enter</KBD>.\n");
+/// ```
+pub trait InlineRenderer: Send + Sync {
+    /// Renders the given node as Markdown, or returns `None` to use the
+    /// built-in rendering.
+    fn render<'a>(&self, node: &'a AstNode<'a>) -> Option<String>;
+
+    /// Returns a name identifying this renderer in debug output.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<F> InlineRenderer for F
+where
+    F: for<'a> Fn(&'a AstNode<'a>) -> Option<String> + Send + Sync,
+{
+    fn render<'a>(&self, node: &'a AstNode<'a>) -> Option<String> {
+        self(node)
+    }
+}
+
+impl std::fmt::Debug for dyn InlineRenderer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("InlineRenderer").field(&self.name()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{Options, format};
+
+    fn footnote_as_superscript<'a>(node: &'a AstNode<'a>) -> Option<String> {
+        match &node.data.borrow().value {
+            NodeValue::FootnoteReference(footnote) => Some(format!("<sup>{}</sup>", footnote.name)),
+            _ => None,
+        }
+    }
+
+    fn options_with(kind: InlineKind, renderer: Arc<dyn InlineRenderer>) -> Options {
+        let mut options = Options::default();
+        options.inline_renderers.insert(kind, renderer);
+        options
+    }
+
+    #[test]
+    fn test_inline_kind_of() {
+        assert_eq!(InlineKind::of(&NodeValue::Emph), Some(InlineKind::Emph));
+        assert_eq!(InlineKind::of(&NodeValue::Paragraph), None);
+    }
+
+    #[test]
+    fn test_custom_footnote_reference_renderer() {
+        let options = options_with(
+            InlineKind::FootnoteReference,
+            Arc::new(footnote_as_superscript),
+        );
+        let output = format("See this.[^1]\n\n[^1]: A note.", &options).unwrap();
+        assert!(output.starts_with("See this.<sup>1</sup>\n"), "{}", output);
+    }
+
+    #[test]
+    fn test_renderer_falls_back_on_none() {
+        fn only_foo<'a>(node: &'a AstNode<'a>) -> Option<String> {
+            match &node.data.borrow().value {
+                NodeValue::Code(code) if code.literal == "foo" => Some("`FOO`".to_string()),
+                _ => None,
+            }
+        }
+        let options = options_with(InlineKind::Code, Arc::new(only_foo));
+        let output = format("Use `foo` or `bar`.", &options).unwrap();
+        assert_eq!(output, "Use `FOO` or `bar`.\n");
+    }
+
+    #[test]
+    fn test_renderer_replaces_children() {
+        fn plain_strong<'a>(_node: &'a AstNode<'a>) -> Option<String> {
+            Some("STRONG".to_string())
+        }
+        let options = options_with(InlineKind::Strong, Arc::new(plain_strong));
+        let output = format("Some **bold *text***.", &options).unwrap();
+        assert_eq!(output, "Some STRONG.\n");
+    }
+}
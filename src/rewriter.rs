@@ -0,0 +1,158 @@
+//! Rewriting of link and image destinations.
+//!
+//! Library users can set an [`Options::url_rewriter`] to map destinations,
+//! e.g., from an old documentation host to a new one or without tracking
+//! parameters, without writing a whole [`AstTransform`].
+//!
+//! [`AstTransform`]: crate::AstTransform
+
+use comrak::nodes::{AstNode, NodeValue};
+
+#[cfg(doc)]
+use crate::Options;
+
+/// Kinds of destinations passed to a [`UrlRewriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    /// Link, e.g., `[text](url)`.
+    Link,
+    /// Image, e.g., `![alt](url)`.
+    Image,
+    /// Autolink, e.g., `<https://example.com/>`.  Its text is rewritten along
+    /// with its destination.
+    Autolink,
+}
+
+/// A function that rewrites link and image destinations before
+/// serialization.
+///
+/// It is called for every link and image in the document, and returns
+/// the new destination, or `None` to leave it as it is.  Links using
+/// references are rewritten as well, and so are their definitions.
+///
+/// Functions with the matching signature implement this trait as well.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use hongdown::{LinkKind, Options, format};
+///
+/// let options = Options {
+///     url_rewriter: Some(Arc::new(|url: &str, _kind: LinkKind| {
+///         url.strip_prefix("https://old.example/")
+///             .map(|path| format!("https://docs.example/{}", path))
+///     })),
+///     ..Options::default()
+/// };
+/// let output = format("See the [guide](/guide) and <https://old.example/faq>.", &options)
+///     .unwrap();
+/// assert_eq!(output, "See the [guide](/guide) and <https://docs.example/faq>.\n");
+/// ```
+pub trait UrlRewriter: Send + Sync {
+    /// Returns the new destination for `url`, or `None` to keep it.
+    fn rewrite(&self, url: &str, kind: LinkKind) -> Option<String>;
+
+    /// Returns a name identifying this rewriter in debug output.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<F> UrlRewriter for F
+where
+    F: Fn(&str, LinkKind) -> Option<String> + Send + Sync,
+{
+    fn rewrite(&self, url: &str, kind: LinkKind) -> Option<String> {
+        self(url, kind)
+    }
+}
+
+impl std::fmt::Debug for dyn UrlRewriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("UrlRewriter").field(&self.name()).finish()
+    }
+}
+
+/// Rewrite the destinations of the links and images under `root`.
+pub(crate) fn rewrite_urls<'a>(root: &'a AstNode<'a>, rewriter: &dyn UrlRewriter) {
+    for node in root.descendants() {
+        let (url, kind) = match &node.data.borrow().value {
+            NodeValue::Link(link) if is_autolink(node, &link.url, &link.title) => {
+                (link.url.clone(), LinkKind::Autolink)
+            }
+            NodeValue::Link(link) => (link.url.clone(), LinkKind::Link),
+            NodeValue::Image(image) => (image.url.clone(), LinkKind::Image),
+            _ => continue,
+        };
+        let Some(rewritten) = rewriter.rewrite(&url, kind) else {
+            continue;
+        };
+        if kind == LinkKind::Autolink
+            && let Some(text) = node.first_child()
+            && let NodeValue::Text(ref mut text) = text.data.borrow_mut().value
+        {
+            *text = rewritten.clone().into();
+        }
+        if let NodeValue::Link(link) | NodeValue::Image(link) = &mut node.data.borrow_mut().value {
+            link.url = rewritten;
+        }
+    }
+}
+
+/// Check if `node` is an autolink, i.e., a link whose only text is its
+/// destination.
+pub(crate) fn is_autolink<'a>(node: &'a AstNode<'a>, url: &str, title: &str) -> bool {
+    title.is_empty()
+        && node.first_child().is_some_and(|child| {
+            child.next_sibling().is_none()
+                && matches!(&child.data.borrow().value, NodeValue::Text(text) if text == url)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{Options, format};
+
+    #[test]
+    fn test_url_rewriter_kinds() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&seen);
+        let options = Options {
+            url_rewriter: Some(Arc::new(move |url: &str, kind: LinkKind| {
+                recorded.lock().unwrap().push((url.to_string(), kind));
+                None
+            })),
+            ..Options::default()
+        };
+        let input = "[a](/a) ![b](/b.png) <https://c.example/> [d][]\n\n[d]: /d\n";
+        assert_eq!(format(input, &options).unwrap(), input);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ("/a".to_string(), LinkKind::Link),
+                ("/b.png".to_string(), LinkKind::Image),
+                ("https://c.example/".to_string(), LinkKind::Autolink),
+                ("/d".to_string(), LinkKind::Link),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_url_rewriter_references() {
+        let options = Options {
+            url_rewriter: Some(Arc::new(|url: &str, kind: LinkKind| {
+                (kind == LinkKind::Link).then(|| url.replace("?utm_source=x", ""))
+            })),
+            ..Options::default()
+        };
+        let input = "[a] and ![b](/b.png?utm_source=x)\n\n[a]: /a?utm_source=x\n";
+        assert_eq!(
+            format(input, &options).unwrap(),
+            "[a] and ![b](/b.png?utm_source=x)\n\n[a]: /a\n"
+        );
+    }
+}
@@ -0,0 +1,477 @@
+//! Registry of the warnings Hongdown reports.
+//!
+//! Each warning has a stable identifier, e.g., `HD001`, along with its
+//! machine-readable code, e.g., `undefined-reference`, and a description of
+//! what triggers it and how to configure or suppress it.  `hongdown explain`
+//! prints these, and the tests check that every example triggers its
+//! warning, so that the descriptions do not drift from the behavior.
+
+#[cfg(doc)]
+use crate::Warning;
+use crate::serializer::wrap;
+
+/// Machine-readable codes of warnings, in the order of the identifiers of
+/// their rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningCode {
+    /// A reference link or image uses an undefined label.
+    UndefinedReference,
+    /// A table row has more or fewer cells than the delimiter row.
+    TablePipeCount,
+    /// A table cell contains a line break.
+    TableCellLineBreak,
+    /// Inline HTML in a table cell is not closed within the cell.
+    TableCellHtml,
+    /// A heading is wider than the line width.
+    HeadingTooLong,
+    /// A date in the front matter cannot be normalized.
+    FrontMatterDate,
+    /// An external code formatter failed.
+    CodeFormatter,
+    /// A relative URL cannot be joined against the link base.
+    LinkBase,
+    /// A URL cannot be normalized without possibly changing it.
+    UrlNormalization,
+    /// A table of contents links to a heading that does not exist.
+    StaleToc,
+    /// Re-parsing the output changes its inline content.
+    Escaping,
+    /// Formatting the output again changes it.
+    Unstable,
+    /// An external code formatter timed out and was killed.
+    CodeFormatterTimeout,
+    /// A line of a code block is wider than the code line width.
+    CodeLineTooLong,
+    /// A directive names a formatting rule that does not exist.
+    UnknownRule,
+}
+
+impl WarningCode {
+    /// Get the string form of this code, e.g., `"undefined-reference"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UndefinedReference => "undefined-reference",
+            Self::TablePipeCount => "table-pipe-count",
+            Self::TableCellLineBreak => "table-cell-line-break",
+            Self::TableCellHtml => "table-cell-html",
+            Self::HeadingTooLong => "heading-too-long",
+            Self::FrontMatterDate => "front-matter-date",
+            Self::CodeFormatter => "code-formatter",
+            Self::LinkBase => "link-base",
+            Self::UrlNormalization => "url-normalization",
+            Self::StaleToc => "stale-toc",
+            Self::Escaping => "escaping",
+            Self::Unstable => "unstable",
+            Self::CodeFormatterTimeout => "code-formatter-timeout",
+            Self::CodeLineTooLong => "code-line-too-long",
+            Self::UnknownRule => "unknown-rule",
+        }
+    }
+
+    /// Get the category of this code.
+    pub fn kind(self) -> WarningKind {
+        match self {
+            Self::UndefinedReference | Self::LinkBase | Self::UrlNormalization => {
+                WarningKind::References
+            }
+            Self::TablePipeCount | Self::TableCellLineBreak | Self::TableCellHtml => {
+                WarningKind::Tables
+            }
+            Self::HeadingTooLong => WarningKind::Typography,
+            Self::FrontMatterDate | Self::StaleToc | Self::UnknownRule => WarningKind::Structure,
+            Self::CodeFormatter | Self::CodeFormatterTimeout | Self::CodeLineTooLong => {
+                WarningKind::Code
+            }
+            Self::Escaping | Self::Unstable => WarningKind::Verification,
+        }
+    }
+
+    /// Get the rule describing this code.
+    pub fn rule(self) -> &'static Rule {
+        &RULES[self as usize]
+    }
+}
+
+impl std::fmt::Display for WarningCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Categories of warnings, for filtering them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// Tables, e.g., rows with too many cells.
+    Tables,
+    /// Links, images, and their references, e.g., undefined labels.
+    References,
+    /// Layout of text, e.g., headings wider than the line width.
+    Typography,
+    /// Structure of the document, e.g., front matter and tables of contents.
+    Structure,
+    /// Code blocks, e.g., failing code formatters.
+    Code,
+    /// Checks of the output, which report bugs in Hongdown.
+    Verification,
+}
+
+impl WarningKind {
+    /// Get the string form of this kind, e.g., `"tables"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Tables => "tables",
+            Self::References => "references",
+            Self::Typography => "typography",
+            Self::Structure => "structure",
+            Self::Code => "code",
+            Self::Verification => "verification",
+        }
+    }
+}
+
+/// A warning Hongdown reports, as listed in [`RULES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    /// Stable identifier, e.g., `"HD001"`.
+    pub id: &'static str,
+    /// Machine-readable code, as in [`Warning::code`].
+    pub code: WarningCode,
+    /// One-line summary.
+    pub summary: &'static str,
+    /// What triggers the warning.
+    pub description: &'static str,
+    /// Markdown that triggers the warning, or an empty string if it has no
+    /// reliable trigger.
+    pub example: &'static str,
+    /// How to configure or suppress the warning.
+    pub configuration: &'static str,
+}
+
+impl Rule {
+    /// Render the full description of this rule as plain text wrapped at 80
+    /// columns, as printed by `hongdown explain`.
+    pub fn explain(&self) -> String {
+        let mut text = format!(
+            "{}: {}\n\n{}\n\n{}\n",
+            self.id,
+            self.code,
+            wrap(self.summary),
+            wrap(self.description)
+        );
+        if !self.example.is_empty() {
+            text.push_str("\nExample:\n\n");
+            for line in self.example.lines() {
+                if !line.is_empty() {
+                    text.push_str("    ");
+                    text.push_str(line);
+                }
+                text.push('\n');
+            }
+        }
+        text.push_str("\nConfiguration:\n\n");
+        text.push_str(&wrap(self.configuration));
+        text.push('\n');
+        text
+    }
+}
+
+/// Wrap a paragraph of plain text at 80 columns.
+fn wrap(text: &str) -> String {
+    wrap::wrap_text_first_line(text, "", "", 80)
+}
+
+/// All warnings Hongdown reports, in the order of their identifiers.
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "HD001",
+        code: WarningCode::UndefinedReference,
+        summary: "A reference link or image uses a label that has no definition.",
+        description: "Text in square brackets that looks like a reference link, \
+            e.g., `[text][label]` or `[label]`, is rendered as plain text if no \
+            `[label]: url` definition exists.  Usually the definition is \
+            missing or its label is misspelled.",
+        example: "See the [guide][gude].\n\n[guide]: https://example.com/guide\n",
+        configuration: "Add the missing definition, or escape the brackets, e.g., \
+            `\\[label\\]`, if they are meant literally.  With the `changelog` \
+            profile, version headings such as `## [Unreleased]` are not \
+            reported.  The warning is not reported within regions disabled by \
+            `<!-- hongdown-disable -->` directives.",
+    },
+    Rule {
+        id: "HD002",
+        code: WarningCode::TablePipeCount,
+        summary: "A table row has more or fewer cells than the delimiter row.",
+        description: "Each row of a pipe table should have as many cells as its \
+            delimiter row.  Excess cells are usually caused by an unescaped `|` \
+            in cell content, which renderers read as a column separator.",
+        example: "| A | B |\n|---|---|\n| 1 | 2 | 3 |\n",
+        configuration: "Escape `|` in cell content as `\\|`.  Set `ragged_rows` \
+            in the `[table]` section of _.hongdown.toml_ to `\"drop\"` or \
+            `\"merge\"` to drop or merge the excess cells instead of \
+            reporting them.",
+    },
+    Rule {
+        id: "HD003",
+        code: WarningCode::TableCellLineBreak,
+        summary: "A table cell contains a line break.",
+        description: "A row of a pipe table must fit on a single line, so a line \
+            break in a cell, e.g., one produced by a custom inline renderer, \
+            cannot be represented.  It is replaced with a space.",
+        example: "",
+        configuration: "Use `<br>` for line breaks within table cells, or change \
+            the inline renderer so that it does not produce line breaks.",
+    },
+    Rule {
+        id: "HD004",
+        code: WarningCode::TableCellHtml,
+        summary: "Inline HTML in a table cell is not closed within the cell.",
+        description: "An HTML tag opened in a table cell but not closed in it, \
+            e.g., `<details>`, may swallow the rest of the table when \
+            rendered.",
+        example: "| A |\n|---|\n| <details> |\n",
+        configuration: "Close the tag within the cell, or set `escape_html = true` \
+            in the `[table]` section of _.hongdown.toml_ to escape raw HTML in \
+            cells.",
+    },
+    Rule {
+        id: "HD005",
+        code: WarningCode::HeadingTooLong,
+        summary: "A heading is wider than the line width.",
+        description: "Headings are not wrapped by default, so one wider than the \
+            line width overflows it.  This is reported only if the `overlong` \
+            policy of the heading's level is `\"warn\"`.",
+        example: "# A heading that is so long that it does not fit within the \
+            default line width of eighty columns\n",
+        configuration: "Shorten the heading, or set `overlong` in the `[heading]` \
+            section of _.hongdown.toml_ to `\"keep\"`, `\"atx\"`, or `\"wrap\"`, \
+            or to a list with one policy per level.",
+    },
+    Rule {
+        id: "HD006",
+        code: WarningCode::FrontMatterDate,
+        summary: "A date in the front matter cannot be normalized.",
+        description: "With `normalize_dates` enabled, the `date` and `updated` \
+            fields of the YAML front matter are rewritten in the configured \
+            format.  A value that is not a recognizable date is left as it is.",
+        example: "---\ntitle: Post\ndate: someday\n---\n\nText.\n",
+        configuration: "Fix the date, or set `normalize_dates = false` in the \
+            `[front_matter]` section of _.hongdown.toml_.",
+    },
+    Rule {
+        id: "HD007",
+        code: WarningCode::CodeFormatter,
+        summary: "An external code formatter failed.",
+        description: "A formatter configured for the language of a code block \
+            could not be run, is not allowed by `allowed_formatters`, or exited \
+            with a non-zero status.  The code block is left as it is.",
+        example: "``` python\nprint( 1 )\n```\n",
+        configuration: "Check the command in the `[code_block.formatters]` \
+            section of _.hongdown.toml_, and whether it starts with one of the \
+            commands of `allowed_formatters` in the `[code_block]` section.",
+    },
+    Rule {
+        id: "HD008",
+        code: WarningCode::LinkBase,
+        summary: "A relative URL cannot be joined against the link base.",
+        description: "With `link_base` set, relative link and image destinations \
+            are joined against it.  A destination that points outside of the \
+            link base through `..` segments is left as it is.",
+        example: "See the [readme](../README.md).\n",
+        configuration: "Fix the destination, or change `link_base` in \
+            _.hongdown.toml_ or `--link-base` on the command line.",
+    },
+    Rule {
+        id: "HD009",
+        code: WarningCode::UrlNormalization,
+        summary: "A URL cannot be normalized without possibly changing it.",
+        description: "With `normalize_urls` enabled, unsafe characters in link \
+            and image destinations are percent-encoded.  A destination whose \
+            meaning this could change, e.g., because it contains a backslash \
+            or a `%` that does not start a percent-encoded sequence, is left \
+            as it is.",
+        example: "A [discount](https://example.com/100%).\n",
+        configuration: "Percent-encode the destination by hand, e.g., `%` as \
+            `%25`, or set `normalize_urls = false` in _.hongdown.toml_.",
+    },
+    Rule {
+        id: "HD010",
+        code: WarningCode::StaleToc,
+        summary: "A table of contents links to a heading that does not exist.",
+        description: "A list near the top of the document whose items all \
+            start with links to anchors, at least two, is taken as a table of \
+            contents.  One of its links points to an anchor that no heading \
+            has, usually because the heading was renamed or removed.  Anchors \
+            are computed the way GitHub does.",
+        example: "Title\n=====\n\n -  [Installation](#installation)\n\
+            \x20-  [Usage](#usage)\n\nInstallation\n------------\n",
+        configuration: "Update the links of the table of contents to match the \
+            headings.",
+    },
+    Rule {
+        id: "HD011",
+        code: WarningCode::Escaping,
+        summary: "Re-parsing the output changes its inline content.",
+        description: "With `verify_escaping` enabled, the output is parsed again \
+            and compared with the input.  Inline content that differs suggests \
+            missing or excessive escaping, which is a bug in Hongdown.",
+        example: "",
+        configuration: "Please report the document to the Hongdown issue tracker.  \
+            Set `verify_escaping = false` in _.hongdown.toml_ to skip the check.",
+    },
+    Rule {
+        id: "HD012",
+        code: WarningCode::Unstable,
+        summary: "Formatting the output again changes it.",
+        description: "With `verify_stability` enabled, the output is formatted \
+            once more.  If that changes it, formatting is not idempotent for \
+            the document, which is a bug in Hongdown.",
+        example: "",
+        configuration: "Please report the document to the Hongdown issue tracker.  \
+            Set `verify_stability = false` in _.hongdown.toml_ to skip the check.",
+    },
+    Rule {
+        id: "HD013",
+        code: WarningCode::CodeFormatterTimeout,
+        summary: "An external code formatter timed out and was killed.",
+        description: "A formatter configured for the language of a code block \
+            did not finish within its timeout, five seconds by default.  It is \
+            killed along with the processes it started, and the code block is \
+            left as it is.",
+        example: "``` python\nprint( 1 )\n```\n",
+        configuration: "Increase the `timeout` of the formatter in the \
+            `[code_block.formatters]` section of _.hongdown.toml_, or check \
+            whether the command waits for input it never gets, e.g., because \
+            it does not read the code from stdin.",
+    },
+    Rule {
+        id: "HD014",
+        code: WarningCode::CodeLineTooLong,
+        summary: "A line of a code block is wider than the code line width.",
+        description: "Hongdown does not rewrap code, and neither do most code \
+            formatters, so a line of a code block wider than the line width \
+            overflows it.  This is reported only if `warn_long_lines` is \
+            enabled in the `[code_block]` section.  Tabs count up to the next \
+            tab stop.",
+        example: "``` sh\ncurl --fail --silent --show-error --location \
+            https://example.com/releases/latest/install.sh | sh\n```\n",
+        configuration: "Break the line, e.g., with a line continuation, or set \
+            `line_width` in the `[code_block]` section of _.hongdown.toml_ to \
+            allow wider code.  Set `warn_long_lines = false` there to skip \
+            the check.",
+    },
+    Rule {
+        id: "HD015",
+        code: WarningCode::UnknownRule,
+        summary: "A directive names a formatting rule that does not exist.",
+        description: "The `hongdown-disable` and `hongdown-enable` directives \
+            take the names of the rules to switch off or on: `wrap`, \
+            `reference-links`, `punctuation`, `sentence-case`, and \
+            `title-case`.  A name that is none of these, usually a typo, is \
+            ignored, while the other names of the directive still apply.",
+        example: "<!-- hongdown-disable wrpa -->\n\nText.\n",
+        configuration: "Correct the name of the rule, or remove it from the \
+            directive.",
+    },
+];
+
+/// Look up a rule by its identifier, e.g., `HD004`, or by its code, e.g.,
+/// `table-cell-html`, ignoring case.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{WarningCode, rule};
+///
+/// let hd001 = rule("hd001").unwrap();
+/// assert_eq!(hd001.code, WarningCode::UndefinedReference);
+/// assert_eq!(rule("undefined-reference"), Some(hd001));
+/// ```
+pub fn rule(name: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| {
+        rule.id.eq_ignore_ascii_case(name) || rule.code.as_str().eq_ignore_ascii_case(name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{CodeFormatter, Options, OverlongHeading, OverlongHeadings, format_with_warnings};
+
+    /// Options under which the example of the rule with `code` triggers it.
+    fn example_options(code: WarningCode) -> Options {
+        let mut options = Options::default();
+        match code {
+            WarningCode::HeadingTooLong => {
+                options.heading_overlong = OverlongHeadings::new([OverlongHeading::Warn; 6]);
+            }
+            WarningCode::FrontMatterDate => options.front_matter_normalize_dates = true,
+            WarningCode::CodeFormatter => {
+                options.code_formatters = HashMap::from([(
+                    "python".to_string(),
+                    CodeFormatter {
+                        command: vec!["hongdown-nonexistent-formatter".to_string()],
+                        timeout_secs: 5,
+                    },
+                )]);
+            }
+            WarningCode::CodeFormatterTimeout => {
+                options.code_formatters = HashMap::from([(
+                    "python".to_string(),
+                    CodeFormatter {
+                        command: vec!["sleep".to_string(), "10".to_string()],
+                        timeout_secs: 0,
+                    },
+                )]);
+            }
+            WarningCode::CodeLineTooLong => options.warn_long_code_lines = true,
+            WarningCode::LinkBase => options.link_base = "docs".to_string(),
+            WarningCode::UrlNormalization => options.normalize_urls = true,
+            _ => {}
+        }
+        options
+    }
+
+    #[test]
+    fn test_examples_trigger_their_rules() {
+        for rule in RULES.iter().filter(|rule| !rule.example.is_empty()) {
+            let result = format_with_warnings(rule.example, &example_options(rule.code)).unwrap();
+            let codes: Vec<WarningCode> = result.warnings.iter().map(|w| w.code).collect();
+            assert!(
+                codes.contains(&rule.code),
+                "example of {} gives {:?}",
+                rule.id,
+                codes
+            );
+        }
+    }
+
+    #[test]
+    fn test_rules_are_in_order() {
+        for (i, rule) in RULES.iter().enumerate() {
+            assert_eq!(rule.id, format!("HD{:03}", i + 1));
+            assert_eq!(rule.code as usize, i);
+            assert_eq!(rule.code.rule(), rule);
+        }
+    }
+
+    #[test]
+    fn test_rule_lookup() {
+        assert_eq!(
+            rule("HD004").map(|rule| rule.code),
+            Some(WarningCode::TableCellHtml)
+        );
+        assert_eq!(WarningCode::TableCellHtml.kind(), WarningKind::Tables);
+        assert_eq!(rule("Stale-TOC").map(|rule| rule.id), Some("HD010"));
+        assert_eq!(rule("HD999"), None);
+    }
+
+    #[test]
+    fn test_explain() {
+        let text = rule("HD002").unwrap().explain();
+        assert!(text.starts_with("HD002: table-pipe-count\n\n"));
+        assert!(text.contains("\nExample:\n\n    | A | B |\n"));
+        assert!(text.contains("\nConfiguration:\n\n"));
+    }
+}
@@ -0,0 +1,437 @@
+//! Parallel serialization of top-level sections.
+//!
+//! With [`Options::parallel_sections`] enabled, a document is split at its
+//! top-level h2 headings, and each section is parsed and serialized on the
+//! rayon pool.  comrak's AST cannot be shared between threads, so every
+//! section is parsed again from its own slice of the source, followed by all
+//! link reference definitions of the document so that its links resolve as
+//! they do in the whole document.
+//!
+//! Sections are only independent when no state crosses their boundaries.
+//! Whenever that might not hold (footnotes, directives, transforms, duplicate
+//! reference definitions, or links that resolve differently), [`serialize`]
+//! returns `None` and the caller falls back to sequential serialization.
+
+use std::collections::HashSet;
+
+use comrak::Arena;
+use comrak::nodes::{AstNode, NodeValue};
+use rayon::prelude::*;
+
+use crate::serializer::{self, SerializeResult, TraceEvent, Warning};
+use crate::{AbbreviationPlacement, Options, Profile, parse_markdown, transform};
+
+/// A section of the document: a range of 1-indexed source lines.
+struct Section {
+    start_line: usize,
+    end_line: usize,
+    /// The separator between the previous section's output and this one.
+    separator: String,
+    /// The number of links and images in this section in the whole document.
+    links: usize,
+}
+
+/// The serialized output of a single section.
+struct SectionOutput {
+    result: SerializeResult,
+    links: usize,
+}
+
+/// Serialize `root`, parsed from `input`, one section per thread.
+///
+/// Returns `None` if the document has fewer than two sections or if its
+/// sections may depend on each other.
+pub(crate) fn serialize<'a>(
+    root: &'a AstNode<'a>,
+    input: &str,
+    options: &Options,
+) -> Option<SerializeResult> {
+    // Abbreviation definitions grouped at the end of the document cross
+    // section boundaries, and so do blank lines kept from the source and
+    // reference labels renumbered by first use and reference definitions of
+    // changelog versions kept for the bottom
+    if !options.transforms.is_empty()
+        || (options.abbreviation_placement == AbbreviationPlacement::Document
+            && input.contains("*["))
+        || options.max_blank_lines.get() > 1
+        || options.renumber_references
+        || options.profile == Profile::Changelog
+    {
+        return None;
+    }
+    let lines: Vec<&str> = input.split_inclusive('\n').collect();
+    let sections = split_sections(root, options, lines.len())?;
+    let definitions = collect_definitions(root, &lines)?;
+
+    let outputs: Vec<SectionOutput> = sections
+        .par_iter()
+        .map(|section| serialize_section(section, &lines, &definitions, options))
+        .collect();
+
+    let mut output = String::new();
+    let mut warnings: Vec<Warning> = Vec::new();
+    let mut trace: Vec<TraceEvent> = Vec::new();
+    let mut emitted: HashSet<String> = HashSet::new();
+    #[cfg(feature = "bench")]
+    let mut code_formatter_time = std::time::Duration::ZERO;
+    for (section, section_output) in sections.iter().zip(outputs) {
+        // A link that resolved differently means the section did not parse
+        // as it does in the whole document
+        if section_output.links != section.links {
+            return None;
+        }
+        let result = section_output.result;
+        // A reference definition is emitted only once per document, so
+        // sections that share one are not independent
+        for label in &result.emitted_references {
+            if !emitted.insert(label.clone()) {
+                return None;
+            }
+        }
+        output.push_str(&section.separator);
+        output.push_str(&result.output);
+        #[cfg(feature = "bench")]
+        {
+            code_formatter_time += result.code_formatter_time;
+        }
+        warnings.extend(result.warnings.into_iter().map(|mut warning| {
+            if warning.line > 0 {
+                warning.line += section.start_line - 1;
+            }
+            warning
+        }));
+        trace.extend(result.trace.into_iter().map(|mut event| {
+            if event.line > 0 {
+                event.line += section.start_line - 1;
+            }
+            event
+        }));
+    }
+    Some(SerializeResult {
+        output,
+        warnings,
+        trace,
+        emitted_references: emitted,
+        #[cfg(feature = "bench")]
+        code_formatter_time,
+    })
+}
+
+/// Split the document into sections at top-level h2 headings.
+fn split_sections<'a>(
+    root: &'a AstNode<'a>,
+    options: &Options,
+    line_count: usize,
+) -> Option<Vec<Section>> {
+    let children: Vec<_> = root.children().collect();
+    let mut sections = vec![Section {
+        start_line: 1,
+        end_line: line_count,
+        separator: String::new(),
+        links: 0,
+    }];
+    for (i, child) in children.iter().enumerate() {
+        let ast = child.data.borrow();
+        match &ast.value {
+            NodeValue::FootnoteDefinition(_) => return None,
+            NodeValue::HtmlBlock(html) if serializer::is_directive(&html.literal, options) => {
+                return None;
+            }
+            NodeValue::Heading(heading) if heading.level == 2 && i > 0 => {
+                let previous = children[i - 1].data.borrow();
+                let after_heading = match previous.value {
+                    // Trailing HTML blocks are moved after the reference
+                    // definitions, which only applies at the end of the document
+                    NodeValue::HtmlBlock(_) | NodeValue::FrontMatter(_) => return None,
+                    NodeValue::Heading(_) => true,
+                    _ => false,
+                };
+                let separator = "\n".repeat(serializer::blank_lines_before_heading(
+                    options,
+                    2,
+                    after_heading,
+                ));
+                let start_line = ast.sourcepos.start.line;
+                if let Some(last) = sections.last_mut() {
+                    last.end_line = start_line - 1;
+                }
+                sections.push(Section {
+                    start_line,
+                    end_line: line_count,
+                    separator,
+                    links: 0,
+                });
+            }
+            _ => {}
+        }
+    }
+    if sections.len() < 2 {
+        return None;
+    }
+
+    // Count links per section to verify that each section resolves them the
+    // same way as the whole document
+    for node in root.descendants() {
+        let ast = node.data.borrow();
+        if matches!(ast.value, NodeValue::Link(_) | NodeValue::Image(_)) {
+            let line = ast.sourcepos.start.line;
+            if let Some(section) = sections
+                .iter_mut()
+                .rev()
+                .find(|section| section.start_line <= line)
+            {
+                section.links += 1;
+            }
+        }
+    }
+    Some(sections)
+}
+
+/// Collect the source lines of link reference definitions.
+///
+/// comrak does not keep definitions in the AST, so these are the non-blank
+/// lines not covered by any top-level block.  Returns `None` if the same
+/// label is defined more than once, since the first definition in the whole
+/// document might then differ from the first one in a section, or if any of
+/// these lines is not part of a definition, e.g., a lazy continuation line
+/// that comrak's source positions leave out.
+fn collect_definitions<'a>(root: &'a AstNode<'a>, lines: &[&str]) -> Option<String> {
+    let mut covered = vec![false; lines.len()];
+    for child in root.children() {
+        let sourcepos = child.data.borrow().sourcepos;
+        let start = sourcepos.start.line.max(1) - 1;
+        let end = sourcepos.end.line.min(lines.len());
+        for line in covered.iter_mut().take(end).skip(start) {
+            *line = true;
+        }
+    }
+
+    let mut definitions = String::new();
+    let mut labels = HashSet::new();
+    // Whether the previous line is part of a definition, which may continue
+    // with its destination or title on the following lines
+    let mut in_definition = false;
+    for (line, is_covered) in lines.iter().zip(covered) {
+        if is_covered || line.trim().is_empty() {
+            in_definition = false;
+            continue;
+        }
+        match definition_label(line) {
+            Some(label) => {
+                if !labels.insert(label) {
+                    return None;
+                }
+                in_definition = true;
+            }
+            None if !in_definition => return None,
+            None => {}
+        }
+        definitions.push_str(line);
+        if !line.ends_with('\n') {
+            definitions.push('\n');
+        }
+    }
+    Some(definitions)
+}
+
+/// Extract the normalized label of a line starting a reference definition.
+fn definition_label(line: &str) -> Option<String> {
+    let rest = line.trim_start().strip_prefix('[')?;
+    let end = rest.find("]:")?;
+    let label = rest[..end].split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(label.to_lowercase())
+}
+
+/// Parse and serialize a single section.
+fn serialize_section(
+    section: &Section,
+    lines: &[&str],
+    definitions: &str,
+    options: &Options,
+) -> SectionOutput {
+    let mut source: String = lines[section.start_line - 1..section.end_line].concat();
+    if !source.ends_with('\n') {
+        source.push('\n');
+    }
+    // Definitions are appended so that line numbers stay the same
+    source.push('\n');
+    source.push_str(definitions);
+
+    let arena = Arena::new();
+    let root = parse_markdown(&arena, &source, &options.extensions);
+    // Only conversions built into the options are left to apply, since
+    // custom transforms are not run section by section
+    transform::apply_transforms(root, &arena, options);
+    let links = root
+        .descendants()
+        .filter(|node| {
+            matches!(
+                node.data.borrow().value,
+                NodeValue::Link(_) | NodeValue::Image(_)
+            )
+        })
+        .count();
+    let result = serializer::serialize_with_source_and_warnings(root, options, Some(&source));
+    SectionOutput { result, links }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serialize `input` sequentially and, if possible, section by section.
+    fn serialize_both(input: &str) -> (SerializeResult, Option<SerializeResult>) {
+        serialize_both_with_options(input, &Options::default())
+    }
+
+    fn serialize_both_with_options(
+        input: &str,
+        options: &Options,
+    ) -> (SerializeResult, Option<SerializeResult>) {
+        let arena = Arena::new();
+        let root = parse_markdown(&arena, input, &options.extensions);
+        transform::apply_transforms(root, &arena, options);
+        let sequential = serializer::serialize_with_source_and_warnings(root, options, Some(input));
+        (sequential, serialize(root, input, options))
+    }
+
+    #[test]
+    fn test_sections_match_sequential_output() {
+        let input = "Title\n=====\n\nIntro with [a link][a].\n\n[a]: https://example.com/a\n\n\
+                     First\n-----\n\n * Item with [another](https://example.com/b).\n\n\
+                     ## Second\n\nSee [c].\n\n[c]: https://example.com/c \"C\"\n";
+        let (sequential, parallel) = serialize_both(input);
+        let parallel = parallel.expect("Sections should be independent");
+        assert_eq!(parallel.output, sequential.output);
+    }
+
+    #[test]
+    fn test_sections_convert_bold_labels() {
+        let options = Options {
+            alert_convert_bold_labels: true,
+            ..Options::default()
+        };
+        let input = "# Title\n\n## One\n\n**Note:** First.\n\n## Two\n\n**Tip:** Second.\n";
+        let (sequential, parallel) = serialize_both_with_options(input, &options);
+        let parallel = parallel.expect("Sections should be independent");
+        assert_eq!(parallel.output, sequential.output);
+        assert!(parallel.output.contains("> [!TIP]\n> Second.\n"));
+    }
+
+    #[test]
+    fn test_sections_abbreviation_placement() {
+        let input = "# Title\n\n## One\n\nHTML.\n\n*[HTML]: HyperText Markup Language\n\n## Two\n\nCSS.\n\n*[CSS]: Cascading Style Sheets\n";
+        let options = Options {
+            abbreviation_placement: AbbreviationPlacement::Section,
+            ..Options::default()
+        };
+        let (sequential, parallel) = serialize_both_with_options(input, &options);
+        let parallel = parallel.expect("Sections should be independent");
+        assert_eq!(parallel.output, sequential.output);
+
+        let options = Options {
+            abbreviation_placement: AbbreviationPlacement::Document,
+            ..Options::default()
+        };
+        let (_, parallel) = serialize_both_with_options(input, &options);
+        assert!(parallel.is_none());
+    }
+
+    #[test]
+    fn test_sections_heading_blank_lines() {
+        let input = "# Title\n\n## Empty\n\n## One\n\nText.\n\n## Two\n\nText.\n";
+        let options = Options {
+            heading_blank_lines_before: crate::HeadingBlankLines::new([1, 3, 1, 1, 1, 1]).unwrap(),
+            heading_collapse_consecutive: false,
+            ..Options::default()
+        };
+        let (sequential, parallel) = serialize_both_with_options(input, &options);
+        let parallel = parallel.expect("Sections should be independent");
+        assert_eq!(parallel.output, sequential.output);
+        assert!(parallel.output.contains("Empty\n-----\n\n\n\nOne"));
+    }
+
+    #[test]
+    fn test_sections_max_blank_lines_fall_back() {
+        let input = "# Title\n\nText.\n\n\n\n## Section\n\nText.\n";
+        let options = Options {
+            max_blank_lines: crate::MaxBlankLines::new(3).unwrap(),
+            ..Options::default()
+        };
+        let (_, parallel) = serialize_both_with_options(input, &options);
+        assert!(parallel.is_none());
+    }
+
+    #[test]
+    fn test_sections_offset_warning_lines() {
+        let input = "# Title\n\nText.\n\n## Section\n\nSee [undefined][nope].\n";
+        let (sequential, parallel) = serialize_both(input);
+        let parallel = parallel.expect("Sections should be independent");
+        let lines = |result: &SerializeResult| -> Vec<usize> {
+            result.warnings.iter().map(|warning| warning.line).collect()
+        };
+        assert_eq!(lines(&parallel), lines(&sequential));
+        assert_eq!(lines(&parallel), vec![7]);
+    }
+
+    #[test]
+    fn test_sections_sharing_a_reference_fall_back() {
+        let input = "# Title\n\n## One\n\nSee [Foo].\n\n## Two\n\nSee [Foo] again.\n\n\
+                     [Foo]: https://example.com/\n";
+        let (_, parallel) = serialize_both(input);
+        assert!(parallel.is_none());
+    }
+
+    #[test]
+    fn test_sections_with_footnotes_fall_back() {
+        let input = "# Title\n\n## One\n\nText.[^1]\n\n## Two\n\nMore.\n\n[^1]: A note.\n";
+        let (_, parallel) = serialize_both(input);
+        assert!(parallel.is_none());
+    }
+
+    #[test]
+    fn test_sections_with_directives_fall_back() {
+        let input = "# Title\n\n## One\n\n<!-- hongdown-disable -->\n\n## Two\n\nText.\n";
+        let (_, parallel) = serialize_both(input);
+        assert!(parallel.is_none());
+    }
+
+    #[test]
+    fn test_sections_with_uncovered_lines_fall_back() {
+        let input = "Intro\n\n## A\n\n1. Add the attribute.\n  - The field should\n    have a reason.\n2. Do it.\n\n## B\n\nText.\n";
+        let (_, parallel) = serialize_both(input);
+        assert!(parallel.is_none());
+        let options = Options {
+            parallel_sections: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            crate::format(input, &options).unwrap(),
+            crate::format(input, &Options::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sections_definition_spanning_lines() {
+        let input = "# Title\n\n## One\n\nSee [a].\n\n[a]:\n  https://example.com/a\n  \"A\"\n\n\
+                     ## Two\n\nText.\n";
+        let (sequential, parallel) = serialize_both(input);
+        let parallel = parallel.expect("Sections should be independent");
+        assert_eq!(parallel.output, sequential.output);
+    }
+
+    #[test]
+    fn test_single_section_falls_back() {
+        let (_, parallel) = serialize_both("# Title\n\nText.\n");
+        assert!(parallel.is_none());
+    }
+
+    #[test]
+    fn test_duplicate_definition_label() {
+        assert_eq!(
+            definition_label("[Foo  Bar]: https://example.com/"),
+            Some("foo bar".to_string())
+        );
+        assert_eq!(definition_label("Not a definition"), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Badge block serialization logic.
+//!
+//! READMEs often start with a paragraph of badges, i.e., links around
+//! images like `[![CI][CI badge]][CI]`.  Unless the layout is preserved,
+//! such a paragraph is written all on one line or one badge per line, and
+//! each badge gets reference labels named after its alt text, so that the
+//! definitions of its image and link are kept together.
+
+use comrak::nodes::{AstNode, NodeValue};
+
+use super::Serializer;
+use super::state::reference_key;
+use crate::BadgeLayout;
+
+impl<'a> Serializer<'a> {
+    /// Returns the badges that make up `node`, if it is a paragraph
+    /// consisting only of links around images and the whitespace between
+    /// them.
+    pub(super) fn badge_links<'b>(&self, node: &'b AstNode<'b>) -> Option<Vec<&'b AstNode<'b>>> {
+        if self.options.badge_layout == BadgeLayout::Preserve
+            || !matches!(node.data.borrow().value, NodeValue::Paragraph)
+        {
+            return None;
+        }
+        let mut badges = Vec::new();
+        for child in node.children() {
+            match &child.data.borrow().value {
+                NodeValue::Link(_) if Self::badge_image(child).is_some() => badges.push(child),
+                NodeValue::Text(text) if text.trim().is_empty() => {}
+                NodeValue::SoftBreak | NodeValue::LineBreak => {}
+                _ => return None,
+            }
+        }
+        (!badges.is_empty()).then_some(badges)
+    }
+
+    /// Returns the image of a badge, if `link` contains nothing else.
+    fn badge_image<'b>(link: &'b AstNode<'b>) -> Option<&'b AstNode<'b>> {
+        let image = link.first_child()?;
+        (image.next_sibling().is_none() && matches!(image.data.borrow().value, NodeValue::Image(_)))
+            .then_some(image)
+    }
+
+    /// Write a paragraph of badges in the configured layout.
+    pub(super) fn serialize_badges<'b>(&mut self, badges: &[&'b AstNode<'b>]) {
+        let mut labels: Vec<String> = Vec::new();
+        for (i, badge) in badges.iter().enumerate() {
+            if i > 0 {
+                match self.options.badge_layout {
+                    BadgeLayout::Stack => self.output.push('\n'),
+                    _ => self.output.push(' '),
+                }
+            }
+            let mut content = String::new();
+            if !self.collect_badge(badge, &mut labels, &mut content) {
+                self.collect_inline_node(badge, &mut content);
+            }
+            self.output.push_str(&content);
+        }
+        self.output.push('\n');
+    }
+
+    /// Write `badge` with reference labels named after its alt text, e.g.,
+    /// `[![CI][CI badge]][CI]`.  Returns false without writing anything if
+    /// the alt text cannot be a label, or if the labels are already taken by
+    /// other links.
+    fn collect_badge<'b>(
+        &mut self,
+        badge: &'b AstNode<'b>,
+        labels: &mut Vec<String>,
+        content: &mut String,
+    ) -> bool {
+        let Some(image) = Self::badge_image(badge) else {
+            return false;
+        };
+        let (link_url, link_title) = match &badge.data.borrow().value {
+            NodeValue::Link(link) => (link.url.clone(), link.title.clone()),
+            _ => return false,
+        };
+        let (image_url, image_title) = match &image.data.borrow().value {
+            NodeValue::Image(image) => (image.url.clone(), image.title.clone()),
+            _ => return false,
+        };
+        let alt = self.collect_raw_text(image);
+        let alt = alt.split_whitespace().collect::<Vec<_>>().join(" ");
+        if alt.is_empty() || alt.contains(['[', ']', '\\', '*', '_', '`', '<', '&']) {
+            return false;
+        }
+
+        let image_label = self.canonical_label(&format!("{} badge", alt));
+        let link_label = self.canonical_label(&alt);
+        let image_reference = self.converts_to_reference(&image_url);
+        let link_reference = self.converts_to_reference(&link_url);
+        let taken = |label: &str, url: &str| {
+            labels
+                .iter()
+                .any(|l| reference_key(l) == reference_key(label))
+                || self.is_label_taken(label, url)
+        };
+        if (image_reference && taken(&image_label, &image_url))
+            || (link_reference && taken(&link_label, &link_url))
+        {
+            return false;
+        }
+
+        content.push('[');
+        if image_reference {
+            content.push_str("![");
+            content.push_str(&alt);
+            content.push_str("][");
+            content.push_str(&image_label);
+            content.push(']');
+            self.add_reference(image_label.clone(), image_url, image_title);
+            labels.push(image_label);
+        } else {
+            Self::format_inline_image(
+                content,
+                &alt,
+                &image_url,
+                &image_title,
+                self.options.link_title_style,
+            );
+        }
+        if link_reference {
+            content.push_str("][");
+            content.push_str(&link_label);
+            content.push(']');
+            self.add_reference(link_label.clone(), link_url, link_title);
+            labels.push(link_label);
+        } else {
+            content.push_str("](");
+            Self::push_destination(content, &link_url);
+            Self::push_title(content, &link_title, self.options.link_title_style);
+            content.push(')');
+        }
+        true
+    }
+}
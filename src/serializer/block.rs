@@ -1,92 +1,109 @@
-//! Block quote and alert serialization logic.
+//! Block quote, alert, admonition, and fenced div serialization logic.
 
-use comrak::nodes::{AlertType, AstNode};
+use comrak::nodes::{AlertType, AstNode, NodeValue};
 
 use super::Serializer;
-
-/// Saved state for blockquote context, used for restoration after processing.
-struct BlockquoteState {
-    was_in_block_quote: bool,
-    old_blockquote_prefix: String,
-    old_blockquote_outer_indent: String,
-    old_blockquote_entry_list_depth: usize,
-    old_list_item_indent: String,
-    old_list_type: Option<comrak::nodes::ListType>,
-    old_list_depth: usize,
-    /// The list item indent before entering blockquote context (for separator lines).
-    indent: String,
-}
+use super::output::ContainerKind;
 
 impl<'a> Serializer<'a> {
-    /// Enter blockquote context: save current state and set up for blockquote processing.
-    fn enter_blockquote_context(&mut self) -> BlockquoteState {
-        let was_in_block_quote = self.in_block_quote;
-        self.in_block_quote = true;
-
-        let old_blockquote_prefix = self.blockquote_prefix.clone();
-        self.blockquote_prefix.push_str("> ");
-
-        let old_blockquote_outer_indent = std::mem::replace(
-            &mut self.blockquote_outer_indent,
-            self.list_item_indent.clone(),
+    /// Serialize children within blockquote context, adding blank quote lines between them.
+    fn serialize_blockquote_children<'b>(&mut self, children: &[&'b AstNode<'b>]) {
+        self.output.push_container(
+            ContainerKind::BlockQuote,
+            "> ".to_string(),
+            "> ".to_string(),
         );
+        for (i, child) in children.iter().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            self.serialize_node(child);
+        }
+        self.output.pop_container();
+    }
 
-        let old_blockquote_entry_list_depth =
-            std::mem::replace(&mut self.blockquote_entry_list_depth, self.list_depth);
-
-        let indent = self.list_item_indent.clone();
-
-        let old_list_item_indent = std::mem::take(&mut self.list_item_indent);
-        let old_list_type = self.list_type.take();
-        let old_list_depth = std::mem::replace(&mut self.list_depth, 0);
+    pub(super) fn serialize_block_quote<'b>(&mut self, node: &'b AstNode<'b>) {
+        let children: Vec<_> = node.children().collect();
+        if let Some(&first) = children.first()
+            && let Some((alert_type, rest)) = self.custom_alert_header(first)
+        {
+            self.serialize_custom_alert(&children, &alert_type, &rest);
+            return;
+        }
+        self.serialize_blockquote_children(&children);
+    }
 
-        BlockquoteState {
-            was_in_block_quote,
-            old_blockquote_prefix,
-            old_blockquote_outer_indent,
-            old_blockquote_entry_list_depth,
-            old_list_item_indent,
-            old_list_type,
-            old_list_depth,
-            indent,
+    /// Returns the type and the rest of the header line, e.g., a title, if
+    /// the paragraph starts with the header of an alert type that is not
+    /// one of GitHub's, e.g., `[!INFO]` or Obsidian's foldable `[!faq]-`.
+    fn custom_alert_header<'b>(&self, paragraph: &'b AstNode<'b>) -> Option<(String, String)> {
+        let ast = paragraph.data.borrow();
+        if !matches!(ast.value, NodeValue::Paragraph) {
+            return None;
         }
+        let start = ast.sourcepos.start;
+        let line = self.source_lines.get(start.line.checked_sub(1)?)?;
+        let header = line
+            .get(start.column.checked_sub(1)?..)?
+            .strip_prefix("[!")?;
+        let (alert_type, rest) = header.split_once(']')?;
+        let is_name = !alert_type.is_empty()
+            && alert_type
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        is_name.then(|| (alert_type.to_string(), rest.trim_end().to_string()))
     }
 
-    /// Exit blockquote context: restore the saved state.
-    fn exit_blockquote_context(&mut self, state: BlockquoteState) {
-        self.list_depth = state.old_list_depth;
-        self.list_type = state.old_list_type;
-        self.list_item_indent = state.old_list_item_indent;
-        self.blockquote_outer_indent = state.old_blockquote_outer_indent;
-        self.blockquote_entry_list_depth = state.old_blockquote_entry_list_depth;
-        self.blockquote_prefix = state.old_blockquote_prefix;
-        self.in_block_quote = state.was_in_block_quote;
+    /// Returns the type that replaces the given alert type according to
+    /// [`Options::alert_synonyms`](crate::Options::alert_synonyms).
+    fn alert_synonym<'s>(&'s self, alert_type: &'s str) -> &'s str {
+        self.options
+            .alert_synonyms
+            .iter()
+            .find(|(from, _)| from.eq_ignore_ascii_case(alert_type))
+            .map_or(alert_type, |(_, to)| to.as_str())
     }
 
-    /// Serialize children within blockquote context, adding blank quote lines between them.
-    fn serialize_blockquote_children<'b>(
+    /// Serialize a block quote that starts with the header of a custom alert
+    /// type.  The header is kept verbatim, except for its type, which may
+    /// have a synonym.
+    fn serialize_custom_alert<'b>(
         &mut self,
         children: &[&'b AstNode<'b>],
-        state: &BlockquoteState,
+        alert_type: &str,
+        rest: &str,
     ) {
-        for (i, child) in children.iter().enumerate() {
-            if i > 0 {
-                self.output.push_str(&state.indent);
-                self.output.push_str(&state.old_blockquote_prefix);
-                self.output.push_str(">\n");
-            }
+        // The header is the first line of the first paragraph
+        let mut content = String::new();
+        self.collect_inline_content(children[0], &mut content);
+        let body = content
+            .split_once('\x00')
+            .map_or("", |(_, body)| body.trim());
+
+        let header = format!("[!{}]{}\n", self.alert_synonym(alert_type), rest);
+        self.output.push_container(
+            ContainerKind::BlockQuote,
+            "> ".to_string(),
+            "> ".to_string(),
+        );
+        self.output.push_str(&header);
+        if !body.is_empty() {
+            self.write_wrapped(body);
+            self.output.push('\n');
+        }
+        for child in &children[1..] {
+            self.output.push('\n');
             self.serialize_node(child);
         }
+        self.output.pop_container();
     }
 
-    pub(super) fn serialize_block_quote<'b>(&mut self, node: &'b AstNode<'b>) {
-        let state = self.enter_blockquote_context();
-        let children: Vec<_> = node.children().collect();
-        self.serialize_blockquote_children(&children, &state);
-        self.exit_blockquote_context(state);
-    }
-
-    pub(super) fn serialize_alert<'b>(&mut self, node: &'b AstNode<'b>, alert_type: AlertType) {
+    pub(super) fn serialize_alert<'b>(
+        &mut self,
+        node: &'b AstNode<'b>,
+        alert_type: AlertType,
+        title: Option<&str>,
+    ) {
         // Output the alert header
         let type_str = match alert_type {
             AlertType::Note => "NOTE",
@@ -95,11 +112,22 @@ impl<'a> Serializer<'a> {
             AlertType::Warning => "WARNING",
             AlertType::Caution => "CAUTION",
         };
-        self.output.push_str(&self.list_item_indent);
-        self.output.push_str(&self.blockquote_prefix);
-        self.output.push_str("> [!");
-        self.output.push_str(type_str);
-        self.output.push_str("]\n");
+        // Keep what follows the type verbatim, e.g., a title or Obsidian's
+        // fold marker, which comrak folds into the title.  Alerts converted
+        // from bold labels have no header in the source.
+        let start = node.data.borrow().sourcepos.start;
+        let rest = start
+            .line
+            .checked_sub(1)
+            .and_then(|index| self.source_lines.get(index))
+            .and_then(|line| line.get(start.column.checked_sub(1)?..))
+            .and_then(|line| line.strip_prefix('>')?.trim_start().strip_prefix("[!"))
+            .and_then(|header| header.split_once(']'))
+            .map(|(_, rest)| rest.trim_end().to_string())
+            .or_else(|| title.map(|title| format!(" {}", title.trim())))
+            .unwrap_or_default();
+        let header = format!("> [!{}]{}\n", self.alert_synonym(type_str), rest);
+        self.output.push_str(&header);
 
         // Check if original source has a blank line after the alert header
         let children: Vec<_> = node.children().collect();
@@ -112,13 +140,76 @@ impl<'a> Serializer<'a> {
         };
 
         if has_blank_after_header {
-            self.output.push_str(&self.list_item_indent);
-            self.output.push_str(&self.blockquote_prefix);
             self.output.push_str(">\n");
         }
 
-        let state = self.enter_blockquote_context();
-        self.serialize_blockquote_children(&children, &state);
-        self.exit_blockquote_context(state);
+        self.serialize_blockquote_children(&children);
+    }
+
+    /// Serialize a MkDocs admonition: its header as is, followed by its body
+    /// indented by four spaces.
+    pub(super) fn serialize_admonition<'b>(&mut self, node: &'b AstNode<'b>, header: &str) {
+        self.output.push_str(header);
+        self.output.push('\n');
+
+        let children: Vec<_> = node.children().collect();
+        // Keep the blank line between the header and the body if there is one
+        if let Some(first_child) = children.first() {
+            let header_line = node.data.borrow().sourcepos.start.line;
+            if first_child.data.borrow().sourcepos.start.line > header_line + 1 {
+                self.output.push('\n');
+            }
+        }
+
+        self.output.push_container(
+            ContainerKind::Admonition,
+            "    ".to_string(),
+            "    ".to_string(),
+        );
+        for (i, child) in children.iter().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            self.serialize_node(child);
+        }
+        self.output.pop_container();
+    }
+
+    /// Serialize a Pandoc fenced div: its fences as they are, and its
+    /// content in between.
+    pub(super) fn serialize_fenced_div<'b>(&mut self, node: &'b AstNode<'b>, opening: &str) {
+        let sourcepos = node.data.borrow().sourcepos;
+        let source_line = |line: usize| {
+            line.checked_sub(1)
+                .and_then(|index| self.source_lines.get(index))
+                .map(|line| line.trim())
+        };
+        // Keep the closing fence, which may be longer or shorter than the
+        // opening one
+        let closing = source_line(sourcepos.end.line)
+            .filter(|line| line.starts_with(":::"))
+            .unwrap_or_else(|| opening.split(|c| c != ':').next().unwrap_or(":::"))
+            .to_string();
+        let blank_after_opening = source_line(sourcepos.start.line + 1) == Some("");
+        let blank_before_closing = sourcepos.end.line > sourcepos.start.line + 1
+            && source_line(sourcepos.end.line - 1) == Some("");
+
+        self.output.push_str(opening);
+        self.output.push('\n');
+        let children: Vec<_> = node.children().collect();
+        if blank_after_opening && !children.is_empty() {
+            self.output.push('\n');
+        }
+        for (i, child) in children.iter().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            self.serialize_node(child);
+        }
+        if blank_before_closing && !children.is_empty() {
+            self.output.push('\n');
+        }
+        self.output.push_str(&closing);
+        self.output.push('\n');
     }
 }
@@ -0,0 +1,106 @@
+//! Conventions of the `changelog` profile.
+//!
+//! Changelogs following [Keep a Changelog] have a section per version whose
+//! heading links to a comparison of the version with the previous one, e.g.,
+//! `## [1.0.0] - 2017-06-20`, plus an `[Unreleased]` section at the top.
+//! The link reference definitions of these headings are kept together at the
+//! bottom of the document, newest first.
+//!
+//! [Keep a Changelog]: https://keepachangelog.com/
+
+use std::cmp::Reverse;
+
+/// Check if `text` is a version string, e.g., `1.0.0`, `v2.1`, or
+/// `1.0.0-rc.1`.
+pub(crate) fn is_version(text: &str) -> bool {
+    let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+    let core = text.split(['-', '+']).next().unwrap_or("");
+    let mut parts = core.split('.');
+    parts.clone().count() >= 2
+        && parts.all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+        && text
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '-' | '+'))
+}
+
+/// Check if a reference label is one of a version section: a version or
+/// `Unreleased`.
+pub(crate) fn is_version_label(label: &str) -> bool {
+    label.eq_ignore_ascii_case("unreleased") || is_version(label)
+}
+
+/// Check if the heading `text` is the heading of a version section, e.g.,
+/// `[1.0.0] - 2017-06-20`, `v2.1 (2024-01-01)`, or `[Unreleased]`.
+pub(crate) fn is_version_heading(text: &str) -> bool {
+    let text = text.trim_start();
+    let first = match text.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(""),
+        None => text.split_whitespace().next().unwrap_or(""),
+    };
+    is_version_label(first)
+}
+
+/// Get the version strings among the words of `text`, so that sentence case
+/// and title case can keep them as they are, e.g., `1.0.0-RC.1`.
+pub(crate) fn version_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|ch: char| !ch.is_ascii_alphanumeric()))
+        .filter(|word| is_version(word))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Sort reference labels of version sections: `Unreleased` first, then
+/// versions from the newest to the oldest.
+pub(crate) fn version_sort_key(label: &str) -> (bool, Reverse<(Vec<u64>, bool)>) {
+    if label.eq_ignore_ascii_case("unreleased") {
+        return (false, Reverse((Vec::new(), true)));
+    }
+    let version = label.strip_prefix(['v', 'V']).unwrap_or(label);
+    let version = version.split('+').next().unwrap_or("");
+    let (core, prerelease) = match version.split_once('-') {
+        Some((core, _)) => (core, true),
+        None => (version, false),
+    };
+    let numbers = core
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    // A release is newer than its prereleases
+    (true, Reverse((numbers, !prerelease)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_version() {
+        assert!(is_version("1.0.0"));
+        assert!(is_version("v2.1"));
+        assert!(is_version("1.0.0-rc.1"));
+        assert!(!is_version("1"));
+        assert!(!is_version("Added"));
+        assert!(!is_version("1.x"));
+        assert!(!is_version("1..0"));
+    }
+
+    #[test]
+    fn test_is_version_heading() {
+        assert!(is_version_heading("[1.0.0] - 2017-06-20"));
+        assert!(is_version_heading("[Unreleased]"));
+        assert!(is_version_heading("v2.1 (2024-01-01)"));
+        assert!(!is_version_heading("Added"));
+        assert!(!is_version_heading("Changes in 1.0.0"));
+    }
+
+    #[test]
+    fn test_version_sort_key() {
+        let mut labels = vec!["0.3.0", "1.0.0", "unreleased", "1.0.0-rc.1", "0.10.0"];
+        labels.sort_by_key(|label| version_sort_key(label));
+        assert_eq!(
+            labels,
+            vec!["unreleased", "1.0.0", "1.0.0-rc.1", "0.10.0", "0.3.0"]
+        );
+    }
+}
@@ -1,8 +1,13 @@
 //! Code block serialization logic.
 
-use comrak::nodes::NodeCodeBlock;
+use comrak::nodes::{AstNode, NodeValue};
 
 use super::Serializer;
+use super::state::Directive;
+#[cfg(any(not(target_arch = "wasm32"), feature = "wasm", feature = "async"))]
+use super::state::TraceKind;
+use super::wrap;
+use crate::{Options, WarningCode};
 
 /// The keyword to skip code formatting for a code block.
 const NO_FORMAT_KEYWORD: &str = "hongdown-no-format";
@@ -34,32 +39,82 @@ fn parse_code_info(info: &str) -> (&str, &str, bool) {
     (language, trimmed, has_no_format)
 }
 
+/// Expand the tabs in the indentation of each line of `code` to spaces, with
+/// a tab stop every `tab_width` columns.
+fn expand_leading_tabs(code: &str, tab_width: usize) -> String {
+    let mut result = String::with_capacity(code.len());
+    for line in code.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        result.push_str(&wrap::expand_tabs(&line[..indent], 0, tab_width));
+        result.push_str(&line[indent..]);
+    }
+    result
+}
+
+/// Returns the language of a code block with the info string `info`, as
+/// code formatters are looked up by: its first word, or
+/// [`Options::default_language`] if it has none.
+pub(crate) fn code_language<'i>(info: &'i str, options: &'i Options) -> &'i str {
+    match parse_code_info(info) {
+        ("", _, _) => &options.default_language,
+        (language, _, _) => language,
+    }
+}
+
 impl<'a> Serializer<'a> {
     /// Try to format code using an external formatter.
     ///
     /// Returns `Some(formatted_code)` if a formatter is configured for the language
     /// and succeeds. Returns `None` if no formatter is configured or if the formatter
-    /// fails (in which case a warning is added).
+    /// fails (in which case a warning is added at `line`, where the code
+    /// block starts).  A `command` chosen by a `hongdown-format-with`
+    /// directive is run instead of the formatter of the language.
     #[cfg(not(target_arch = "wasm32"))]
-    fn try_format_code(&mut self, language: &str, code: &str) -> Option<String> {
-        use super::formatter::run_formatter;
-
-        let formatter = self.options.code_formatters.get(language)?;
-
-        match run_formatter(&formatter.command, code, formatter.timeout_secs) {
-            Ok(formatted) => Some(formatted),
-            Err(e) => {
-                // Add warning with line 0 for now (we don't have source position here)
-                // This will be improved when we have access to the node's source position
-                self.add_warning(
-                    0,
+    fn try_format_code(
+        &mut self,
+        language: &str,
+        command: Option<&[String]>,
+        code: &str,
+        line: usize,
+    ) -> Option<String> {
+        use super::formatter::{block_formatter, check_allowed, failure_warning, run_formatter};
+
+        #[cfg(any(feature = "wasm", feature = "async"))]
+        if let Some(formatted) = self.format_code_with_callback(language, command, code, line) {
+            return formatted;
+        }
+        let formatter = block_formatter(self.options, language, command)?;
+
+        #[cfg(feature = "bench")]
+        let started = std::time::Instant::now();
+        let result =
+            check_allowed(&formatter.command, command.is_some(), self.options).and_then(|()| {
+                run_formatter(
+                    &formatter.command,
+                    code,
+                    formatter.timeout_secs,
+                    self.options.code_formatter_dir.as_deref(),
+                    self.options.code_formatter_sandbox,
+                )
+            });
+        #[cfg(feature = "bench")]
+        {
+            self.code_formatter_time += started.elapsed();
+        }
+        match result {
+            Ok(formatted) => {
+                self.trace(TraceKind::CodeFormatter, || {
                     format!(
-                        "code formatter '{}' failed for language '{}': {}",
+                        "code formatter '{}' ran for language '{}'",
                         formatter.command.join(" "),
-                        language,
-                        e
-                    ),
-                );
+                        language
+                    )
+                });
+                Some(formatted)
+            }
+            Err(e) => {
+                let (code, message) = failure_warning(&formatter, language, &e);
+                self.add_warning(code, line, 0, message);
                 None
             }
         }
@@ -67,90 +122,55 @@ impl<'a> Serializer<'a> {
 
     /// WASM: use the callback if provided.
     #[cfg(target_arch = "wasm32")]
-    fn try_format_code(&mut self, language: &str, code: &str) -> Option<String> {
-        #[cfg(feature = "wasm")]
-        if let Some(ref callback) = self.code_formatter_callback {
-            return callback(language, code);
+    fn try_format_code(
+        &mut self,
+        language: &str,
+        command: Option<&[String]>,
+        code: &str,
+        line: usize,
+    ) -> Option<String> {
+        #[cfg(any(feature = "wasm", feature = "async"))]
+        if let Some(formatted) = self.format_code_with_callback(language, command, code, line) {
+            return formatted;
         }
+        #[cfg(not(any(feature = "wasm", feature = "async")))]
+        let _ = (language, command, code, line);
         None
     }
 
-    /// Serialize a code block with indent for description list details.
-    pub(super) fn serialize_code_block_with_indent(&mut self, code: &NodeCodeBlock, indent: &str) {
-        let fence_char = self.options.fence_char.as_char();
-        let min_len = self.options.min_fence_length.get();
-        let base_fence: String = std::iter::repeat_n(fence_char, min_len).collect();
-        let long_fence: String = std::iter::repeat_n(fence_char, min_len + 1).collect();
-
-        // Parse info to get language and check for no-format flag
-        let (parsed_lang, info_output, skip_format) = parse_code_info(&code.info);
-
-        // Determine language for formatter lookup (use default if empty)
-        let language = if parsed_lang.is_empty() {
-            &self.options.default_language
-        } else {
-            parsed_lang
-        };
-
-        // Determine the info string to output
-        let output_info = if info_output.is_empty() && !self.options.default_language.is_empty() {
-            self.options.default_language.as_str()
-        } else {
-            info_output
-        };
-
-        // Try to format the code if a formatter is configured and not skipped
-        let formatted_literal = if !language.is_empty() && !skip_format {
-            self.try_format_code(language, &code.literal)
-        } else {
-            None
-        };
-        let literal = formatted_literal.as_deref().unwrap_or(&code.literal);
-
-        let fence = if literal.contains(&base_fence) {
-            &long_fence
-        } else {
-            &base_fence
-        };
-        self.output.push_str(fence);
-        if !output_info.is_empty() {
-            if self.options.space_after_fence {
-                self.output.push(' ');
-            }
-            self.output.push_str(output_info);
-        }
-        self.output.push('\n');
-        // Add indent to each line of code (skip indent for empty lines)
-        for line in literal.lines() {
-            if !line.is_empty() {
-                self.output.push_str(indent);
-                self.output.push_str(line);
-            }
-            self.output.push('\n');
-        }
-        // Handle trailing newline in literal
-        if !literal.ends_with('\n') && !literal.is_empty() {
-            self.output.push('\n');
+    /// Format code with the code formatter callback.  Returns `None` if no
+    /// callback is set, or what the callback returned otherwise.
+    #[cfg(any(feature = "wasm", feature = "async"))]
+    fn format_code_with_callback(
+        &mut self,
+        language: &str,
+        command: Option<&[String]>,
+        code: &str,
+        line: usize,
+    ) -> Option<Option<String>> {
+        let formatted = self.code_formatter_callback.as_ref()?(language, command, code, line);
+        if formatted.is_some() {
+            self.trace(TraceKind::CodeFormatter, || {
+                format!("code formatter ran for language '{}'", language)
+            });
         }
-        self.output.push_str(indent);
-        self.output.push_str(fence);
-        self.output.push('\n');
+        Some(formatted)
     }
 
-    pub(super) fn serialize_code_block(&mut self, info: &str, literal: &str) {
+    pub(super) fn serialize_code_block<'b>(
+        &mut self,
+        node: &'b AstNode<'b>,
+        info: &str,
+        literal: &str,
+    ) {
         // Determine the minimum fence length from options
         let min_fence_length = self.options.min_fence_length.get();
-        let fence_char = self.options.fence_char.as_char();
 
         // Parse info to get language and check for no-format flag
-        let (parsed_lang, info_output, skip_format) = parse_code_info(info);
+        let (_, info_output, skip_format) = parse_code_info(info);
 
         // Use default_language if no language specified (empty string means no language)
-        let language = if parsed_lang.is_empty() {
-            &self.options.default_language
-        } else {
-            parsed_lang
-        };
+        let language = code_language(info, self.options);
 
         // Determine the info string to output
         let output_info = if info_output.is_empty() && !self.options.default_language.is_empty() {
@@ -159,13 +179,45 @@ impl<'a> Serializer<'a> {
             info_output
         };
 
+        // The info string of a backtick fence cannot contain backticks, so
+        // such a code block is fenced with tildes instead
+        let fence_char = match self.options.fence_char.as_char() {
+            '`' if output_info.contains('`') => '~',
+            fence_char => fence_char,
+        };
+
+        // A directive right before the code block can choose or suppress
+        // its formatter
+        let directive =
+            node.previous_sibling()
+                .and_then(|previous| match &previous.data.borrow().value {
+                    NodeValue::HtmlBlock(html_block) => Directive::parse(&html_block.literal),
+                    _ => None,
+                });
+
+        let expanded = match self.options.code_expand_tabs.get(language) {
+            Some(tab_width) if !skip_format && literal.contains('\t') => {
+                Some(expand_leading_tabs(literal, tab_width.get()))
+            }
+            _ => None,
+        };
+        let literal = expanded.as_deref().unwrap_or(literal);
+
         // Try to format the code if a formatter is configured and not skipped
-        let formatted_literal = if !language.is_empty() && !skip_format {
-            self.try_format_code(language, literal)
-        } else {
-            None
+        let line = node.data.borrow().sourcepos.start.line;
+        let formatted_literal = match directive {
+            _ if skip_format => None,
+            Some(Directive::NoFormatCode) => None,
+            Some(Directive::FormatCodeWith(command)) => {
+                self.try_format_code(language, Some(&command), literal, line)
+            }
+            _ if !language.is_empty() => self.try_format_code(language, None, literal, line),
+            _ => None,
         };
         let content = formatted_literal.as_deref().unwrap_or(literal);
+        if self.options.warn_long_code_lines {
+            self.check_code_line_widths(node, language, content);
+        }
 
         // Find the longest sequence of fence characters in the content
         let max_fence_in_content = content
@@ -186,9 +238,6 @@ impl<'a> Serializer<'a> {
         let fence: String = std::iter::repeat_n(fence_char, fence_length).collect();
 
         // Opening fence
-        if self.in_block_quote {
-            self.output.push_str("> ");
-        }
         self.output.push_str(&fence);
         if !output_info.is_empty() {
             if self.options.space_after_fence {
@@ -200,108 +249,50 @@ impl<'a> Serializer<'a> {
 
         // Content lines
         for line in content.lines() {
-            if self.in_block_quote {
-                if line.is_empty() {
-                    self.output.push('>');
-                } else {
-                    self.output.push_str("> ");
-                }
-            }
             self.output.push_str(line);
             self.output.push('\n');
         }
 
         // Closing fence
-        if self.in_block_quote {
-            self.output.push_str("> ");
-        }
         self.output.push_str(&fence);
         self.output.push('\n');
     }
 
-    /// Serialize a code block with indentation prefix on each line.
-    /// Used for code blocks inside list items.
-    pub(super) fn serialize_code_block_indented(
-        &mut self,
-        info: &str,
-        literal: &str,
-        indent: &str,
-    ) {
-        // Determine the minimum fence length from options
-        let min_fence_length = self.options.min_fence_length.get();
-        let fence_char = self.options.fence_char.as_char();
-
-        // Parse info to get language and check for no-format flag
-        let (parsed_lang, info_output, skip_format) = parse_code_info(info);
-
-        // Use default_language if no language specified
-        let language = if parsed_lang.is_empty() {
-            &self.options.default_language
-        } else {
-            parsed_lang
-        };
-
-        // Determine the info string to output
-        let output_info = if info_output.is_empty() && !self.options.default_language.is_empty() {
-            self.options.default_language.as_str()
-        } else {
-            info_output
+    /// Warn about the lines of `content`, the code of the code block `node`,
+    /// that are wider than the code line width.
+    fn check_code_line_widths<'b>(&mut self, node: &'b AstNode<'b>, language: &str, content: &str) {
+        let max_width = self
+            .options
+            .code_line_width
+            .unwrap_or(self.options.line_width)
+            .get();
+        let (start, fenced) = match &node.data.borrow().value {
+            NodeValue::CodeBlock(code_block) => {
+                (node.data.borrow().sourcepos.start.line, code_block.fenced)
+            }
+            _ => return,
         };
-
-        // Try to format the code if a formatter is configured and not skipped
-        let formatted_literal = if !language.is_empty() && !skip_format {
-            self.try_format_code(language, literal)
+        // The code of a fenced code block starts after the opening fence
+        let first_line = if fenced { start + 1 } else { start };
+        let block = if language.is_empty() {
+            "code block".to_string()
         } else {
-            None
+            format!("{} code block", language)
         };
-        let content = formatted_literal.as_deref().unwrap_or(literal);
-
-        // Find the longest sequence of fence characters in the content
-        let max_fence_in_content = content
-            .lines()
-            .filter_map(|line| {
-                let trimmed = line.trim_start();
-                if trimmed.starts_with(fence_char) {
-                    Some(trimmed.chars().take_while(|&c| c == fence_char).count())
-                } else {
-                    None
-                }
-            })
-            .max()
-            .unwrap_or(0);
-
-        // Fence length must be greater than any fence sequence in content
-        let fence_length = std::cmp::max(min_fence_length, max_fence_in_content + 1);
-        let fence: String = std::iter::repeat_n(fence_char, fence_length).collect();
-
-        // Output opening fence with optional language
-        self.output.push_str(&fence);
-        if !output_info.is_empty() {
-            if self.options.space_after_fence {
-                self.output.push(' ');
-            }
-            self.output.push_str(output_info);
-        }
-        self.output.push('\n');
-
-        // Output content with indentation (skip indent for empty lines)
-        for line in content.lines() {
-            if self.in_block_quote {
-                self.output.push_str("> ");
-            }
-            if !line.is_empty() {
-                self.output.push_str(indent);
-                self.output.push_str(line);
+        let tab_width = self.options.tab_width.get();
+        for (i, line) in content.lines().enumerate() {
+            let width = wrap::display_width(&wrap::expand_tabs(line, 0, tab_width));
+            if width > max_width {
+                self.add_warning(
+                    WarningCode::CodeLineTooLong,
+                    first_line + i,
+                    1,
+                    format!(
+                        "line of {} is {} columns wide, which exceeds the code line width of {}",
+                        block, width, max_width
+                    ),
+                );
             }
-            self.output.push('\n');
         }
-
-        // Output closing fence with indentation
-        if self.in_block_quote {
-            self.output.push_str("> ");
-        }
-        self.output.push_str(indent);
-        self.output.push_str(&fence);
-        self.output.push('\n');
     }
 }
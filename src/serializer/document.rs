@@ -2,11 +2,14 @@
 
 use comrak::nodes::{AstNode, NodeValue};
 use regex::Regex;
-use unicode_width::UnicodeWidthStr;
 
-use super::Serializer;
-use super::state::{Directive, FormatSkipMode};
+use super::inline::attribute_block_len;
+use super::output::{ContainerKind, SegmentKind};
+use super::state::{Directive, FormatRule, FormatSkipMode, RuleSet, TraceKind, reference_key};
 use super::wrap;
+use super::{Serializer, blank_lines_before_heading};
+use crate::directive::{DirectiveContext, SkipRequest};
+use crate::{AbbreviationPlacement, OverlongHeading, Profile, TitleCase, WarningCode};
 
 impl<'a> Serializer<'a> {
     pub(super) fn serialize_document<'b>(&mut self, node: &'b AstNode<'b>) {
@@ -33,6 +36,8 @@ impl<'a> Serializer<'a> {
         let trailing_html_start = self.find_trailing_html_blocks(&children);
 
         for (i, child) in children.iter().enumerate() {
+            self.report_progress(i, children.len());
+
             // Skip trailing HTML blocks for now - they'll be output after references
             if i >= trailing_html_start
                 && let NodeValue::HtmlBlock(_) = &child.data.borrow().value
@@ -43,9 +48,21 @@ impl<'a> Serializer<'a> {
             if let NodeValue::FootnoteDefinition(_) = &child.data.borrow().value {
                 continue;
             }
+            // Collect abbreviation definitions to output them as a group
+            if self.options.abbreviation_placement != AbbreviationPlacement::Preserve
+                && !self.should_skip_formatting()
+                && let Some(definitions) = self.abbreviation_definitions(child)
+            {
+                self.pending_abbreviations.extend(definitions);
+                continue;
+            }
+            // Nothing has been written yet if the blocks before were all
+            // abbreviation definitions
+            let is_first = self.output.is_empty();
             // Check for directives in HTML blocks
             if let NodeValue::HtmlBlock(html_block) = &child.data.borrow().value
-                && let Some(directive) = Directive::parse(&html_block.literal)
+                && let Some(directive) =
+                    Directive::parse_with_options(&html_block.literal, self.options)
             {
                 match directive {
                     Directive::DisableFile => {
@@ -54,7 +71,9 @@ impl<'a> Serializer<'a> {
                         let directive_line = child.data.borrow().sourcepos.start.line;
                         self.flush_footnotes_before(Some(directive_line));
                         self.flush_references();
+                        self.flush_version_references();
                         self.flush_footnote_references_before(Some(directive_line));
+                        self.flush_abbreviations(true);
 
                         // Output the directive comment, then output remaining content as-is
                         self.output.push_str(html_block.literal.trim_end());
@@ -65,6 +84,7 @@ impl<'a> Serializer<'a> {
                             self.extract_source_from_line(directive_end_line + 1)
                         {
                             self.output.push('\n');
+                            self.output.begin(SegmentKind::Verbatim);
                             self.output.push_str(&remaining);
                         }
                         return;
@@ -76,10 +96,11 @@ impl<'a> Serializer<'a> {
                         self.flush_footnotes_before(Some(directive_line));
                         self.flush_references();
                         self.flush_footnote_references_before(Some(directive_line));
+                        self.flush_abbreviations(false);
 
                         self.skip_mode = FormatSkipMode::NextBlock;
                         // Output the directive comment
-                        if i > 0 {
+                        if !is_first {
                             self.output.push('\n');
                         }
                         self.output.push_str(&html_block.literal);
@@ -92,10 +113,11 @@ impl<'a> Serializer<'a> {
                         self.flush_footnotes_before(Some(directive_line));
                         self.flush_references();
                         self.flush_footnote_references_before(Some(directive_line));
+                        self.flush_abbreviations(false);
 
                         self.skip_mode = FormatSkipMode::UntilSection;
                         // Output the directive comment
-                        if i > 0 {
+                        if !is_first {
                             self.output.push('\n');
                         }
                         self.output.push_str(&html_block.literal);
@@ -108,10 +130,11 @@ impl<'a> Serializer<'a> {
                         self.flush_footnotes_before(Some(directive_line));
                         self.flush_references();
                         self.flush_footnote_references_before(Some(directive_line));
+                        self.flush_abbreviations(false);
 
                         self.skip_mode = FormatSkipMode::Disabled;
                         // Output the directive comment
-                        if i > 0 {
+                        if !is_first {
                             self.output.push('\n');
                         }
                         self.output.push_str(&html_block.literal);
@@ -119,8 +142,29 @@ impl<'a> Serializer<'a> {
                     }
                     Directive::Enable => {
                         self.skip_mode = FormatSkipMode::None;
+                        self.disabled_rules = RuleSet::default();
+                        // Output the directive comment
+                        if !is_first {
+                            self.output.push('\n');
+                        }
+                        self.output.push_str(&html_block.literal);
+                        continue;
+                    }
+                    Directive::DisableRules { rules, unknown } => {
+                        self.warn_unknown_rules(child, &unknown);
+                        self.disabled_rules.insert_all(rules);
                         // Output the directive comment
-                        if i > 0 {
+                        if !is_first {
+                            self.output.push('\n');
+                        }
+                        self.output.push_str(&html_block.literal);
+                        continue;
+                    }
+                    Directive::EnableRules { rules, unknown } => {
+                        self.warn_unknown_rules(child, &unknown);
+                        self.disabled_rules.remove_all(rules);
+                        // Output the directive comment
+                        if !is_first {
                             self.output.push('\n');
                         }
                         self.output.push_str(&html_block.literal);
@@ -130,7 +174,7 @@ impl<'a> Serializer<'a> {
                         // Add to directive proper nouns list
                         self.directive_proper_nouns.extend(nouns);
                         // Output the directive comment
-                        if i > 0 {
+                        if !is_first {
                             self.output.push('\n');
                         }
                         self.output.push_str(&html_block.literal);
@@ -140,12 +184,40 @@ impl<'a> Serializer<'a> {
                         // Add to directive common nouns list
                         self.directive_common_nouns.extend(nouns);
                         // Output the directive comment
-                        if i > 0 {
+                        if !is_first {
                             self.output.push('\n');
                         }
                         self.output.push_str(&html_block.literal);
                         continue;
                     }
+                    Directive::FormatCodeWith(_) | Directive::NoFormatCode => {
+                        // Applied by the code block right after
+                        if !is_first {
+                            self.output.push('\n');
+                        }
+                        self.output.push_str(&html_block.literal);
+                        continue;
+                    }
+                    Directive::Custom { name, args } => {
+                        let directive_line = child.data.borrow().sourcepos.start.line;
+                        let mut context = DirectiveContext::new(directive_line);
+                        if let Some(handler) = self.options.directives.get(&name) {
+                            handler.handle(&args, &mut context);
+                        }
+                        self.apply_directive_context(&context, directive_line);
+                        // Output the directive comment, then any inserted content
+                        if !is_first {
+                            self.output.push('\n');
+                        }
+                        self.output.push_str(&html_block.literal);
+                        let inserted = context.inserted.trim_end();
+                        if !inserted.is_empty() {
+                            self.output.push('\n');
+                            self.output.push_str(inserted);
+                            self.output.push('\n');
+                        }
+                        continue;
+                    }
                 }
             }
 
@@ -155,39 +227,39 @@ impl<'a> Serializer<'a> {
                 NodeValue::Heading(h) => Some(h.level),
                 _ => None,
             };
-            let is_h2 = heading_level == Some(2);
             let is_h2_or_h3 = matches!(heading_level, Some(2) | Some(3));
 
-            if is_h2_or_h3 && i > 0 {
+            if is_h2_or_h3 && !is_first {
                 // Get the source line of the heading to flush only earlier footnotes
                 let heading_line = child.data.borrow().sourcepos.start.line;
                 // Footnotes come before link reference definitions
                 self.flush_footnotes_before(Some(heading_line));
                 self.flush_references();
                 self.flush_footnote_references_before(Some(heading_line));
+                self.flush_abbreviations(false);
             }
 
             // Add blank line between block elements (except after front matter)
-            if i > 0 {
+            if !is_first {
                 let prev_is_front_matter = matches!(
                     &children[i - 1].data.borrow().value,
                     NodeValue::FrontMatter(_)
                 );
+                let blank_lines = self.source_blank_lines_before(child);
+                let prev_is_heading =
+                    matches!(&children[i - 1].data.borrow().value, NodeValue::Heading(_));
                 if prev_is_front_matter {
                     // No extra blank line needed after front matter
-                } else if is_h2 {
-                    // Check if previous element was a heading (empty section)
-                    let prev_is_heading =
-                        matches!(&children[i - 1].data.borrow().value, NodeValue::Heading(_));
-                    if prev_is_heading {
-                        // Just one blank line between consecutive headings
-                        self.output.push('\n');
-                    } else {
-                        // Two blank lines before h2 sections (one after content + one extra)
-                        self.output.push_str("\n\n");
-                    }
+                } else if let Some(level) = heading_level {
+                    // Two blank lines before h2 sections by default, but one
+                    // between consecutive headings (empty sections)
+                    let minimum = blank_lines_before_heading(self.options, level, prev_is_heading);
+                    self.push_blank_lines(blank_lines.max(minimum));
+                } else if prev_is_heading {
+                    let minimum = self.options.heading_blank_lines_after;
+                    self.push_blank_lines(blank_lines.max(minimum));
                 } else {
-                    self.output.push('\n');
+                    self.push_blank_lines(blank_lines.max(1));
                 }
             }
 
@@ -212,8 +284,10 @@ impl<'a> Serializer<'a> {
 
                 // Output the original source
                 if let Some(source) = self.extract_source(child) {
+                    self.output.begin(SegmentKind::Verbatim);
                     self.output.push_str(&source);
                     self.output.push('\n');
+                    self.output.begin(SegmentKind::Content);
                 } else {
                     self.serialize_node(child);
                 }
@@ -226,10 +300,54 @@ impl<'a> Serializer<'a> {
         // Footnotes come before link reference definitions
         self.flush_footnotes();
         self.flush_references();
+        self.flush_version_references();
         self.flush_footnote_references();
+        self.flush_abbreviations(true);
 
         // Output trailing HTML blocks after references and footnotes
         self.output_trailing_html_blocks(&children, trailing_html_start);
+        self.report_progress(children.len(), children.len());
+    }
+
+    /// Warn about the `names` given to the rule directive `node` that are
+    /// not rules.
+    fn warn_unknown_rules<'b>(&mut self, node: &'b AstNode<'b>, names: &[String]) {
+        let (line, column) = {
+            let data = node.data.borrow();
+            (data.sourcepos.start.line, data.sourcepos.start.column)
+        };
+        for name in names {
+            self.add_warning(
+                WarningCode::UnknownRule,
+                line,
+                column,
+                format!("unknown rule '{}' in directive is ignored", name),
+            );
+        }
+    }
+
+    /// Apply the changes requested by a user-defined directive handler.
+    fn apply_directive_context(&mut self, context: &DirectiveContext, directive_line: usize) {
+        self.directive_proper_nouns
+            .extend(context.proper_nouns.iter().cloned());
+        self.directive_common_nouns
+            .extend(context.common_nouns.iter().cloned());
+        let skip_mode = match context.skip {
+            None => return,
+            Some(SkipRequest::Enable) => {
+                self.skip_mode = FormatSkipMode::None;
+                return;
+            }
+            Some(SkipRequest::NextBlock) => FormatSkipMode::NextBlock,
+            Some(SkipRequest::NextSection) => FormatSkipMode::UntilSection,
+            Some(SkipRequest::Disable) => FormatSkipMode::Disabled,
+        };
+        // Definitions that appear before the directive should stay before it.
+        self.flush_footnotes_before(Some(directive_line));
+        self.flush_references();
+        self.flush_footnote_references_before(Some(directive_line));
+        self.flush_abbreviations(false);
+        self.skip_mode = skip_mode;
     }
 
     /// Find the index where trailing HTML blocks start.
@@ -243,7 +361,7 @@ impl<'a> Serializer<'a> {
             match &child.data.borrow().value {
                 NodeValue::HtmlBlock(html_block) => {
                     // Skip formatting directives - they should stay where they are
-                    if Directive::parse(&html_block.literal).is_some() {
+                    if Directive::parse_with_options(&html_block.literal, self.options).is_some() {
                         break;
                     }
                     // This is a regular HTML block (e.g., comment) - mark as trailing
@@ -269,24 +387,25 @@ impl<'a> Serializer<'a> {
         children: &[&'b AstNode<'b>],
         start_index: usize,
     ) {
-        let mut is_first = true;
+        let mut previous_end_line = None;
         for (i, child) in children.iter().enumerate() {
             if i < start_index {
                 continue;
             }
 
             if let NodeValue::HtmlBlock(html_block) = &child.data.borrow().value {
-                // Add a blank line before the first trailing HTML block
-                if is_first {
-                    if !self.output.ends_with("\n\n") {
-                        if self.output.ends_with('\n') {
-                            self.output.push('\n');
-                        } else {
-                            self.output.push_str("\n\n");
-                        }
-                    }
-                    is_first = false;
+                // Add a blank line before the first trailing HTML block, and
+                // between blocks that a blank line separates in the source,
+                // lest they merge into one
+                let sourcepos = child.data.borrow().sourcepos;
+                let separated = match previous_end_line {
+                    None => !self.output.is_empty(),
+                    Some(end_line) => sourcepos.start.line > end_line + 1,
+                };
+                if separated {
+                    self.output.ensure_blank_line();
                 }
+                previous_end_line = Some(sourcepos.end.line);
                 self.output.push_str(&html_block.literal);
             }
         }
@@ -295,159 +414,98 @@ impl<'a> Serializer<'a> {
     pub(super) fn serialize_description_details<'b>(&mut self, node: &'b AstNode<'b>) {
         let children: Vec<_> = node.children().collect();
 
-        // Set flag so nested lists know to add extra indentation
-        let was_in_description_details = self.in_description_details;
-        self.in_description_details = true;
-
-        // Determine the prefix for blockquote context
-        let blockquote_prefix = if self.in_block_quote {
-            format!("{}{}", self.blockquote_outer_indent, self.blockquote_prefix)
-        } else {
-            String::new()
-        };
-
+        // Details start with a `:` marker followed by spaces, and their
+        // content, including nested blocks, is indented below it
+        let marker = format!(
+            ":{}",
+            " ".repeat(self.options.definition_marker_spaces.get())
+        );
+        let indent = " ".repeat(self.options.definition_indent_width.get());
+        self.output
+            .push_container(ContainerKind::Details, marker, indent);
         for (i, child) in children.iter().enumerate() {
-            let child_value = &child.data.borrow().value;
-
-            if i == 0 {
-                // First child: start with `:   ` marker
-                match child_value {
-                    NodeValue::Paragraph => {
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str(":   ");
-                        let mut content = String::new();
-                        self.collect_inline_content(child, &mut content);
-                        let continuation = format!("{}    ", blockquote_prefix);
-                        let wrapped = wrap::wrap_text_first_line(
-                            content.trim(),
-                            "",
-                            &continuation,
-                            self.options.line_width.get(),
-                        );
-                        self.output.push_str(&wrapped);
-                        self.output.push('\n');
-                    }
-                    NodeValue::CodeBlock(code) => {
-                        // Code block as first child (unusual but possible)
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str(":   ");
-                        self.output.push('\n');
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str("    ");
-                        self.serialize_code_block_with_indent(
-                            code,
-                            &format!("{}    ", blockquote_prefix),
-                        );
-                    }
-                    NodeValue::List(_) => {
-                        // List as first child: output marker with 4 spaces, then list on same line
-                        // This ensures idempotent formatting - the list stays inside the definition
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str(":    ");
-                        // Set flag so list knows first item shouldn't have base indentation
-                        self.description_details_first_list = true;
-                        self.serialize_node(child);
-                        self.description_details_first_list = false;
-                    }
-                    NodeValue::BlockQuote | NodeValue::Alert(_) => {
-                        // Block quotes and alerts as first child: output marker, newline,
-                        // then serialize with proper list_item_indent for continuation lines
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str(":\n");
-                        let old_list_item_indent =
-                            std::mem::replace(&mut self.list_item_indent, "    ".to_string());
-                        self.serialize_node(child);
-                        self.list_item_indent = old_list_item_indent;
-                    }
-                    _ => {
-                        // Other block types: serialize normally with indent
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str(":   ");
-                        self.serialize_node(child);
-                    }
-                }
-            } else {
-                // Subsequent children: need blank line and 4-space indent
+            if i > 0 {
+                // Subsequent children are separated by a blank line
                 self.output.push('\n');
-                match child_value {
-                    NodeValue::Paragraph => {
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str("    ");
-                        let mut content = String::new();
-                        self.collect_inline_content(child, &mut content);
-                        let continuation = format!("{}    ", blockquote_prefix);
-                        let wrapped = wrap::wrap_text_first_line(
-                            content.trim(),
-                            "",
-                            &continuation,
-                            self.options.line_width.get(),
-                        );
-                        self.output.push_str(&wrapped);
-                        self.output.push('\n');
-                    }
-                    NodeValue::CodeBlock(code) => {
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str("    ");
-                        self.serialize_code_block_with_indent(
-                            code,
-                            &format!("{}    ", blockquote_prefix),
-                        );
-                    }
-                    NodeValue::List(_) => {
-                        // Lists handle their own indentation via in_description_details flag
-                        self.serialize_node(child);
-                    }
-                    NodeValue::BlockQuote | NodeValue::Alert(_) => {
-                        // Block quotes and alerts need list_item_indent to be set
-                        // so that their continuation lines are properly indented
-                        let old_list_item_indent =
-                            std::mem::replace(&mut self.list_item_indent, "    ".to_string());
-                        self.serialize_node(child);
-                        self.list_item_indent = old_list_item_indent;
-                    }
-                    _ => {
-                        // Other block types
-                        self.output.push_str(&blockquote_prefix);
-                        self.output.push_str("    ");
-                        self.serialize_node(child);
-                    }
-                }
             }
+            self.serialize_node(child);
         }
-
-        self.in_description_details = was_in_description_details;
+        if children.is_empty() {
+            self.output.push('\n');
+        }
+        self.output.pop_container();
     }
 
     pub(super) fn serialize_heading<'b>(&mut self, node: &'b AstNode<'b>, level: u8) {
-        // Collect heading text first
-        let mut heading_text = self.collect_text(node);
-
-        // Apply sentence case if enabled
-        if self.options.heading_sentence_case {
+        // Collect heading text first, with the parts that case conversion
+        // leaves alone marked
+        let mut heading_text = self.collect_heading_text(node);
+        let plain_text = super::heading::strip_verbatim_markers(&heading_text);
+
+        // Apply sentence case or title case if enabled
+        // The changelog profile keeps headings of version sections as they are
+        let changelog = self.options.profile == Profile::Changelog;
+        let version_heading = changelog && super::changelog::is_version_heading(&plain_text);
+        let sentence_case = self.options.heading_sentence_case
+            && !version_heading
+            && !self.disabled_rules.contains(FormatRule::SentenceCase);
+        let title_case = !sentence_case
+            && !version_heading
+            && self.options.heading_title_case != TitleCase::None
+            && !self.disabled_rules.contains(FormatRule::TitleCase);
+        if sentence_case || title_case {
             // Merge config proper nouns with directive proper nouns
             let mut proper_nouns = self.options.heading_proper_nouns.clone();
             proper_nouns.extend(self.directive_proper_nouns.clone());
+            if changelog {
+                proper_nouns.extend(super::changelog::version_words(&plain_text));
+            }
 
             // Merge config common nouns with directive common nouns
             let mut common_nouns = self.options.heading_common_nouns.clone();
             common_nouns.extend(self.directive_common_nouns.clone());
 
-            heading_text =
-                super::heading::to_sentence_case(&heading_text, &proper_nouns, &common_nouns);
+            heading_text = if sentence_case {
+                super::heading::to_sentence_case(&heading_text, &proper_nouns, &common_nouns)
+            } else {
+                super::heading::to_title_case(
+                    &heading_text,
+                    self.options.heading_title_case,
+                    &proper_nouns,
+                    &common_nouns,
+                )
+            };
+        }
+        let heading_text = super::heading::strip_verbatim_markers(&heading_text);
+
+        let setext =
+            (level == 1 && self.options.setext_h1) || (level == 2 && self.options.setext_h2);
+        let prefix_width = if setext { 0 } else { usize::from(level) + 1 };
+        let width = self.output.column() + prefix_width + wrap::display_width(&heading_text);
+        let overlong = width > self.wrap_width();
+        let policy = self.options.heading_overlong.get(level);
+        if overlong && policy == OverlongHeading::Warn {
+            let start = node.data.borrow().sourcepos.start;
+            self.add_warning(
+                WarningCode::HeadingTooLong,
+                start.line,
+                start.column,
+                format!(
+                    "heading is {} columns wide, which exceeds the line width of {}",
+                    width,
+                    self.wrap_width()
+                ),
+            );
         }
 
-        if level == 1 && self.options.setext_h1 {
-            // Setext-style with '='
-            self.output.push_str(&heading_text);
-            self.output.push('\n');
-            self.output.push_str(&"=".repeat(heading_text.width()));
-            self.output.push('\n');
-        } else if level == 2 && self.options.setext_h2 {
-            // Setext-style with '-'
-            self.output.push_str(&heading_text);
-            self.output.push('\n');
-            self.output.push_str(&"-".repeat(heading_text.width()));
-            self.output.push('\n');
+        if setext && !(overlong && policy == OverlongHeading::Atx) {
+            // Setext-style with '=' for h1 and '-' for h2
+            let underline = if level == 1 { '=' } else { '-' };
+            if overlong && policy == OverlongHeading::Wrap {
+                self.write_wrapped_setext_heading(&heading_text, underline);
+            } else {
+                self.write_setext_heading(&heading_text, underline);
+            }
         } else {
             // ATX-style for level 3+ or when setext is disabled
             self.output.push_str(&"#".repeat(level as usize));
@@ -457,6 +515,74 @@ impl<'a> Serializer<'a> {
         }
     }
 
+    /// Write a setext heading, with an underline of `underline` characters
+    /// as long as [`Options::setext_underline`](crate::Options::setext_underline)
+    /// asks for.
+    fn write_setext_heading(&mut self, heading_text: &str, underline: char) {
+        let length = self.options.setext_underline.length(
+            self.options.setext_underline_measure.measure(heading_text),
+            self.output.column(),
+            self.options.line_width.get(),
+        );
+        self.output.push_str(heading_text);
+        self.output.push('\n');
+        self.output.push_str(&underline.to_string().repeat(length));
+        self.output.push('\n');
+    }
+
+    /// Write a setext heading whose text is wrapped over as many lines as
+    /// needed, with an underline as long as its widest line.
+    fn write_wrapped_setext_heading(&mut self, heading_text: &str, underline: char) {
+        let column = self.output.column();
+        let first = " ".repeat(column);
+        let rest = " ".repeat(self.output.indent_width());
+        let heading_text = wrap::protect_block_markers(heading_text);
+        let wrapped = wrap::wrap_text_first_line(&heading_text, &first, &rest, self.wrap_width());
+        self.trace_wrapped(&heading_text, &wrapped);
+        let mut text_width = 0;
+        for (i, line) in wrapped.split('\n').enumerate() {
+            let placeholder = if i == 0 {
+                first.as_str()
+            } else {
+                self.output.push('\n');
+                rest.as_str()
+            };
+            let line = line.strip_prefix(placeholder).unwrap_or(line);
+            text_width = text_width.max(self.options.setext_underline_measure.measure(line));
+            self.output.push_str(line);
+        }
+        self.output.push('\n');
+        let length = self.options.setext_underline.length(
+            text_width,
+            self.output.column(),
+            self.options.line_width.get(),
+        );
+        self.output.push_str(&underline.to_string().repeat(length));
+        self.output.push('\n');
+    }
+
+    /// Record in the trace that `text` was broken into more lines when
+    /// wrapped.
+    fn trace_wrapped(&mut self, text: &str, wrapped: &str) {
+        let breaks = wrapped.matches('\n').count();
+        if breaks > text.matches('\n').count() {
+            let width = self.options.line_width.get();
+            self.trace(TraceKind::Wrapped, || {
+                format!("wrapped into {} lines at {} columns", breaks + 1, width)
+            });
+        }
+    }
+
+    /// The line width to wrap at, or `usize::MAX` if wrapping is disabled by
+    /// a `hongdown-disable wrap` directive.
+    pub(super) fn wrap_width(&self) -> usize {
+        if self.disabled_rules.contains(FormatRule::Wrap) {
+            usize::MAX
+        } else {
+            self.options.line_width.get()
+        }
+    }
+
     pub(super) fn serialize_paragraph<'b>(&mut self, node: &'b AstNode<'b>) {
         // Check if this is a PHP Markdown Extra abbreviation definition (*[abbr]: ...)
         // These are not parsed by comrak, so we preserve them as-is
@@ -469,72 +595,107 @@ impl<'a> Serializer<'a> {
             }
         }
 
+        if let Some(badges) = self.badge_links(node) {
+            self.serialize_badges(&badges);
+            return;
+        }
+
+        if let Some((image, caption)) = super::figure_parts(node) {
+            self.serialize_figure(image, caption);
+            return;
+        }
+
         // Collect all inline content first
         let mut inline_content = String::new();
         self.collect_inline_content(node, &mut inline_content);
+        self.write_wrapped(inline_content.trim());
+        self.output.push('\n');
+    }
 
-        if self.list_type.is_some() {
-            // Inside a list item, wrap with proper continuation indent
-            // First line has no prefix (marker already output)
-            // Continuation lines need appropriate indent
-            //
-            // There are two cases:
-            // 1. List inside blockquote: list_depth > blockquote_entry_list_depth
-            //    - Use base_indent for the inner list's continuation
-            // 2. Blockquote inside list: list_depth == blockquote_entry_list_depth
-            //    - Don't add base_indent, just use the outer prefix
-            let inner_list_depth = self
-                .list_depth
-                .saturating_sub(self.blockquote_entry_list_depth);
-            let base_indent = if self.in_description_details && inner_list_depth > 0 {
-                // Inside description details, add extra 5-space indent for `:    ` prefix.
-                // For unordered lists at top level, the marker is `-  ` (3 chars, no leading space).
-                // For nested lists, use the standard 4-char indent.
-                let first_level_marker_width = 1 + self.options.trailing_spaces.get(); // `-` + trailing
-                let nested_indent = "    ".repeat(inner_list_depth.saturating_sub(1));
-                format!(
-                    "     {}{}",
-                    " ".repeat(first_level_marker_width),
-                    nested_indent
-                )
-            } else {
-                "    ".repeat(inner_list_depth)
-            };
-            let continuation = if self.in_block_quote {
-                // Inside a blockquote, continuation lines need > prefix + indent
-                // Use blockquote_outer_indent (the outer list's indent, if any)
-                // rather than list_item_indent (which is for the list inside the blockquote)
-                format!(
-                    "{}{}{}",
-                    self.blockquote_outer_indent, self.blockquote_prefix, base_indent
-                )
-            } else {
-                base_indent
-            };
-            let wrapped = wrap::wrap_text_first_line(
-                inline_content.trim(),
-                "",
-                &continuation,
-                self.options.line_width.get(),
-            );
-            self.output.push_str(&wrapped);
+    /// Returns the abbreviation definitions that make up `node`, if it is a
+    /// paragraph consisting only of them, one per line.
+    fn abbreviation_definitions<'b>(&self, node: &'b AstNode<'b>) -> Option<Vec<String>> {
+        if !matches!(node.data.borrow().value, NodeValue::Paragraph) {
+            return None;
+        }
+        let source = self.extract_source(node)?;
+        source
+            .lines()
+            .map(|line| {
+                let line = line.trim();
+                (line.starts_with("*[") && line.contains("]:")).then(|| line.to_string())
+            })
+            .collect()
+    }
+
+    /// Write text wrapped at the line width, accounting for the prefixes
+    /// that the enclosing containers add to each line.
+    pub(super) fn write_wrapped(&mut self, text: &str) {
+        // Wrap with placeholders as wide as the prefixes, which the output
+        // replaces with the actual prefixes.  The first line of a list item's
+        // paragraph does not count the prefixes toward the line width.
+        let first = if self.output.innermost() == Some(ContainerKind::ListItem) {
+            String::new()
         } else {
-            // Not in a list - wrap the paragraph at line_width
-            let prefix = if self.in_block_quote {
-                format!("{}{}", self.blockquote_outer_indent, self.blockquote_prefix)
+            " ".repeat(self.output.column())
+        };
+        let rest = " ".repeat(self.output.indent_width());
+        // Issue references are protected only now, after link labels and
+        // reference definitions have been resolved from the text
+        let text = wrap::protect_issue_references(text, self.options.autolink_host);
+        let text = wrap::protect_block_markers(&text);
+        let wrapped = wrap::wrap_text_first_line(&text, &first, &rest, self.wrap_width());
+        self.trace_wrapped(&text, &wrapped);
+        for (i, line) in wrapped.split('\n').enumerate() {
+            let placeholder = if i == 0 {
+                first.as_str()
             } else {
-                String::new()
+                self.output.push('\n');
+                rest.as_str()
             };
-            let wrapped = wrap::wrap_text(&inline_content, &prefix, self.options.line_width.get());
-            self.output.push_str(&wrapped);
+            self.output
+                .push_str(line.strip_prefix(placeholder).unwrap_or(line));
+        }
+    }
+
+    /// Returns the number of blank lines right before `node` in the source,
+    /// limited to [`Options::max_blank_lines`](crate::Options::max_blank_lines),
+    /// or zero if only one is kept anyway.
+    fn source_blank_lines_before<'b>(&self, node: &'b AstNode<'b>) -> usize {
+        let max = self.options.max_blank_lines.get();
+        if max <= 1 {
+            return 0;
+        }
+        let start_line = node.data.borrow().sourcepos.start.line;
+        let lines = self
+            .source_lines
+            .get(..start_line.saturating_sub(1))
+            .unwrap_or_default();
+        let blank = lines
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        blank.min(max)
+    }
+
+    /// Write `count` blank lines, assuming the output ends with a newline.
+    fn push_blank_lines(&mut self, count: usize) {
+        for _ in 0..count {
             self.output.push('\n');
         }
     }
 
     pub(super) fn serialize_front_matter(&mut self, content: &str) {
-        // Front matter content from comrak includes the delimiters,
-        // so we preserve it verbatim and add a trailing blank line
-        self.output.push_str(content.trim());
+        // Front matter content from comrak includes the delimiters, so we
+        // preserve it apart from the requested changes and add a trailing
+        // blank line
+        let (formatted, warnings) =
+            super::front_matter::format_front_matter_with_warnings(content, self.options);
+        for (line, message) in warnings {
+            self.add_warning(WarningCode::FrontMatterDate, line, 1, message);
+        }
+        self.output.push_str(&formatted);
         self.output.push_str("\n\n");
     }
 
@@ -576,17 +737,23 @@ impl<'a> Serializer<'a> {
         let disabled_ranges = Self::collect_disabled_line_ranges(node);
 
         // Collect warnings first to avoid borrow issues
+        // Headings of changelog versions often have no link yet, e.g.,
+        // `## [Unreleased]`
+        let skip_version_labels = self.options.profile == Profile::Changelog;
+
         let warnings = Self::find_undefined_references_in_ast(
             node,
             &self.source_lines,
             &abbreviations,
             &source_ref_defs,
+            self.options.extensions.wikilinks,
+            skip_version_labels,
         );
 
         // Filter out warnings that fall within disabled regions
-        for (line, msg) in warnings {
+        for (line, column, msg) in warnings {
             if !Self::is_line_in_disabled_ranges(line, &disabled_ranges) {
-                self.add_warning(line, msg);
+                self.add_warning(WarningCode::UndefinedReference, line, column, msg);
             }
         }
     }
@@ -660,9 +827,17 @@ impl<'a> Serializer<'a> {
                     Directive::Enable => {
                         // Enable doesn't start a new range, it ends one
                     }
-                    Directive::ProperNouns(_) | Directive::CommonNouns(_) => {
+                    Directive::DisableRules { .. }
+                    | Directive::EnableRules { .. }
+                    | Directive::ProperNouns(_)
+                    | Directive::CommonNouns(_)
+                    | Directive::FormatCodeWith(_)
+                    | Directive::NoFormatCode => {
                         // These directives don't affect warning ranges
                     }
+                    Directive::Custom { .. } => {
+                        // Only built-in directives are parsed here
+                    }
                 }
             }
         }
@@ -697,7 +872,7 @@ impl<'a> Serializer<'a> {
     /// Collect reference definitions from source that comrak may not have parsed.
     /// This happens when a reference definition follows an abbreviation definition
     /// without a blank line in between.
-    /// Returns a set of (label, line_number) tuples.
+    /// Returns a set of their [`reference_key`]s.
     fn collect_source_reference_definitions(
         source_lines: &[&str],
     ) -> std::collections::HashSet<String> {
@@ -709,7 +884,7 @@ impl<'a> Serializer<'a> {
             if let Some(caps) = ref_def_pattern.captures(line)
                 && let Some(label) = caps.get(1)
             {
-                definitions.insert(label.as_str().to_string());
+                definitions.insert(reference_key(label.as_str()));
             }
         }
 
@@ -717,13 +892,15 @@ impl<'a> Serializer<'a> {
     }
 
     /// Find undefined references by walking the AST.
-    /// Returns a vector of (line_number, warning_message) tuples.
+    /// Returns a vector of (line_number, column_number, warning_message) tuples.
     fn find_undefined_references_in_ast<'b>(
         node: &'b AstNode<'b>,
         source_lines: &[&str],
         abbreviations: &std::collections::HashSet<String>,
         source_ref_defs: &std::collections::HashSet<String>,
-    ) -> Vec<(usize, String)> {
+        wikilinks: bool,
+        skip_version_labels: bool,
+    ) -> Vec<(usize, usize, String)> {
         let mut warnings = Vec::new();
 
         // Pattern to find [label] or [text][label] in text nodes
@@ -737,6 +914,8 @@ impl<'a> Serializer<'a> {
             &ref_pattern,
             abbreviations,
             source_ref_defs,
+            wikilinks,
+            skip_version_labels,
             &mut warnings,
         );
 
@@ -744,13 +923,16 @@ impl<'a> Serializer<'a> {
     }
 
     /// Recursively walk the AST looking for undefined references in Text nodes.
+    #[allow(clippy::too_many_arguments)]
     fn walk_ast_for_undefined_refs<'b>(
         node: &'b AstNode<'b>,
         source_lines: &[&str],
         ref_pattern: &Regex,
         abbreviations: &std::collections::HashSet<String>,
         source_ref_defs: &std::collections::HashSet<String>,
-        warnings: &mut Vec<(usize, String)>,
+        wikilinks: bool,
+        skip_version_labels: bool,
+        warnings: &mut Vec<(usize, usize, String)>,
     ) {
         let data = node.data.borrow();
 
@@ -790,6 +972,21 @@ impl<'a> Serializer<'a> {
                         continue;
                     }
 
+                    // Skip Obsidian wikilinks [[note]] and embeds ![[note]]
+                    if wikilinks
+                        && text[..full_match.start()].ends_with('[')
+                        && text[full_match.end()..].starts_with(']')
+                    {
+                        continue;
+                    }
+
+                    // Skip bracketed spans with attributes, e.g., [text]{.class}
+                    if caps.get(2).is_none()
+                        && attribute_block_len(&text[full_match.end()..]).is_some()
+                    {
+                        continue;
+                    }
+
                     // Skip PHP Markdown Extra abbreviations
                     if abbreviations.contains(label) {
                         continue;
@@ -797,7 +994,11 @@ impl<'a> Serializer<'a> {
 
                     // Skip reference definitions that exist in source but comrak didn't parse
                     // (e.g., when they follow abbreviation definitions without a blank line)
-                    if source_ref_defs.contains(label) {
+                    if source_ref_defs.contains(&reference_key(label)) {
+                        continue;
+                    }
+
+                    if skip_version_labels && super::changelog::is_version_label(label) {
                         continue;
                     }
 
@@ -806,7 +1007,14 @@ impl<'a> Serializer<'a> {
                         continue;
                     }
 
-                    warnings.push((line_num, format!("undefined reference link: [{}]", label)));
+                    let column =
+                        Self::find_column_in_source(source_lines, line_num, full_match.as_str())
+                            .unwrap_or(data.sourcepos.start.column);
+                    warnings.push((
+                        line_num,
+                        column,
+                        format!("undefined reference link: [{}]", label),
+                    ));
                 }
             }
             // Skip code blocks and inline code - they don't contain reference links
@@ -830,6 +1038,8 @@ impl<'a> Serializer<'a> {
                 ref_pattern,
                 abbreviations,
                 source_ref_defs,
+                wikilinks,
+                skip_version_labels,
                 warnings,
             );
         }
@@ -858,16 +1068,26 @@ impl<'a> Serializer<'a> {
         false
     }
 
+    /// Find the 1-indexed character column of a pattern in the original source
+    /// line, if it appears there.
+    fn find_column_in_source(
+        source_lines: &[&str],
+        line_num: usize,
+        pattern: &str,
+    ) -> Option<usize> {
+        if line_num == 0 || line_num > source_lines.len() {
+            return None;
+        }
+
+        let line = source_lines[line_num - 1];
+        line.find(pattern)
+            .map(|pos| line[..pos].chars().count() + 1)
+    }
+
     pub(super) fn serialize_thematic_break(&mut self) {
         let style = self.options.thematic_break_style.as_str();
         let leading_spaces = self.options.thematic_break_leading_spaces.get();
 
-        // Determine the prefix based on blockquote context
-        if self.in_block_quote {
-            let prefix = format!("{}> ", self.blockquote_outer_indent);
-            self.output.push_str(&prefix);
-        }
-
         // Add leading spaces
         for _ in 0..leading_spaces {
             self.output.push(' ');
@@ -1,5 +1,7 @@
 //! Text escaping and formatting utilities for Markdown serialization.
 
+use crate::{AutolinkHost, MentionStyle};
+
 /// Normalize whitespace in text: convert newlines and multiple spaces to single space.
 pub fn normalize_whitespace(text: &str) -> String {
     text.split_whitespace().collect::<Vec<_>>().join(" ")
@@ -7,7 +9,11 @@ pub fn normalize_whitespace(text: &str) -> String {
 
 /// Escape special Markdown characters in text content.
 /// Characters that could be misinterpreted as Markdown syntax need escaping.
-pub fn escape_text(text: &str) -> String {
+///
+/// Underscores in the middle of words are left alone unless
+/// `escape_underscores` is set, or always in the repository of a shorthand
+/// reference that `host` links automatically, e.g., `my_org/my_repo#123`.
+pub fn escape_text(text: &str, escape_underscores: bool, host: AutolinkHost) -> String {
     let mut result = String::with_capacity(text.len());
     let chars: Vec<char> = text.chars().collect();
 
@@ -18,11 +24,16 @@ pub fn escape_text(text: &str) -> String {
                 result.push('\\');
                 result.push(ch);
             }
-            // Underscore always needs escaping for safety
-            // While CommonMark doesn't create emphasis for intraword underscores,
-            // escaping ensures consistent behavior across all Markdown parsers
+            // Underscore needs escaping for safety unless it is in the middle
+            // of a word.  While CommonMark doesn't create emphasis for
+            // intraword underscores, escaping them ensures consistent
+            // behavior across all Markdown parsers
             '_' => {
-                result.push('\\');
+                if !is_intraword_underscore(&chars, i)
+                    || (escape_underscores && !is_in_repository_reference(&chars, i, host))
+                {
+                    result.push('\\');
+                }
                 result.push(ch);
             }
             // Square brackets - only escape if they could form a link
@@ -70,6 +81,135 @@ pub fn escape_text(text: &str) -> String {
     result
 }
 
+/// Returns whether the underscore at `index` of `chars` belongs to a run of
+/// underscores between letters or digits, e.g., `snake_case`, which cannot
+/// open or close emphasis.
+pub fn is_intraword_underscore(chars: &[char], index: usize) -> bool {
+    let before = chars[..index].iter().rev().find(|&&ch| ch != '_');
+    let after = chars[index + 1..].iter().find(|&&ch| ch != '_');
+    matches!(
+        (before, after),
+        (Some(before), Some(after)) if before.is_alphanumeric() && after.is_alphanumeric()
+    )
+}
+
+/// Returns whether the character at `index` of `chars` belongs to the
+/// repository of a cross-repository reference that `host` links
+/// automatically, e.g., `owner/repo` in `owner/repo#123` or
+/// `owner/repo@a1b2c3d`.
+pub fn is_in_repository_reference(chars: &[char], index: usize, host: AutolinkHost) -> bool {
+    let is_path_char = |ch: char| ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.' | '/');
+    if host == AutolinkHost::None || !is_path_char(chars[index]) {
+        return false;
+    }
+    let start = chars[..index]
+        .iter()
+        .rposition(|&ch| !is_path_char(ch))
+        .map_or(0, |i| i + 1);
+    let end = chars[index..]
+        .iter()
+        .position(|&ch| !is_path_char(ch))
+        .map_or(chars.len(), |i| index + i);
+    let path: String = chars[start..end].iter().collect();
+    let segments = path.split('/').count();
+    let valid_path = path.split('/').all(|segment| !segment.is_empty())
+        && match host {
+            AutolinkHost::GitLab => segments >= 2,
+            _ => segments == 2,
+        };
+    if !valid_path {
+        return false;
+    }
+    let reference: String = chars[(end + 1).min(chars.len())..]
+        .iter()
+        .take_while(|ch| ch.is_ascii_alphanumeric())
+        .collect();
+    match chars.get(end) {
+        Some('#') => !reference.is_empty() && reference.bytes().all(|b| b.is_ascii_digit()),
+        Some('!') => {
+            host == AutolinkHost::GitLab
+                && !reference.is_empty()
+                && reference.bytes().all(|b| b.is_ascii_digit())
+        }
+        Some('@') => {
+            (7..=40).contains(&reference.len()) && reference.bytes().all(|b| b.is_ascii_hexdigit())
+        }
+        _ => false,
+    }
+}
+
+/// Returns the length in characters of the `@mention` that starts at `index`
+/// of `chars`, e.g., `@octocat` or the team mention `@org/team`, if any.
+/// An `@` in the middle of a word, as in an email address, or after a
+/// backslash does not start a mention.
+pub fn mention_len(chars: &[char], index: usize) -> Option<usize> {
+    if chars.get(index) != Some(&'@')
+        || index > 0 && (chars[index - 1].is_alphanumeric() || "\\`_-/.".contains(chars[index - 1]))
+    {
+        return None;
+    }
+    // Usernames consist of letters, digits, and hyphens, but cannot start or
+    // end with a hyphen
+    let name_len = chars[index + 1..]
+        .iter()
+        .take_while(|ch| ch.is_ascii_alphanumeric() || **ch == '-')
+        .count();
+    let name = &chars[index + 1..index + 1 + name_len];
+    let name_len = name.len() - name.iter().rev().take_while(|ch| **ch == '-').count();
+    if name_len == 0 || name[0] == '-' {
+        return None;
+    }
+    let mut len = 1 + name_len;
+    if name_len == name.len() && chars.get(index + len) == Some(&'/') {
+        let team_len = chars[index + len + 1..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+            .count();
+        if team_len > 0 {
+            len += 1 + team_len;
+        }
+    }
+    Some(len)
+}
+
+/// Check whether `text` consists of a single `@mention`.
+pub(crate) fn is_mention(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    mention_len(&chars, 0) == Some(chars.len())
+}
+
+/// Write the `@mentions` in escaped text in the given style.
+pub fn style_mentions(text: &str, style: MentionStyle) -> String {
+    if style == MentionStyle::Keep || !text.contains('@') {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let Some(len) = mention_len(&chars, i) else {
+            result.push(chars[i]);
+            i += 1;
+            continue;
+        };
+        let mention: String = chars[i..i + len].iter().collect();
+        match style {
+            MentionStyle::Keep => result.push_str(&mention),
+            MentionStyle::Escape => {
+                result.push('\\');
+                result.push_str(&mention);
+            }
+            MentionStyle::Code => {
+                result.push('`');
+                result.push_str(&mention);
+                result.push('`');
+            }
+        }
+        i += len;
+    }
+    result
+}
+
 /// Format a code span with the appropriate number of backticks.
 /// According to CommonMark spec, if the content contains N consecutive backticks,
 /// the delimiter must use at least N+1 backticks. Spaces are added if the content
@@ -136,14 +276,74 @@ pub fn is_valid_code_span(source: &str) -> bool {
     leading_backticks == trailing_backticks && leading_backticks <= source.len() / 2
 }
 
+/// Check whether the `<` at `chars[i]` would start raw HTML when parsed: an
+/// opening or closing tag, a comment, a declaration, or a processing
+/// instruction.  Autolinks such as `<https://example.com>` do not count.
+fn starts_html(chars: &[char], i: usize) -> bool {
+    match chars.get(i + 1) {
+        Some('!' | '?') => true,
+        Some('/') => chars.get(i + 2).is_some_and(|c| c.is_ascii_alphabetic()),
+        Some(c) if c.is_ascii_alphabetic() => {
+            // A tag name must be followed by whitespace, `/`, or `>`, whereas
+            // the scheme of an autolink is followed by `:`
+            match chars[i + 1..]
+                .iter()
+                .find(|c| !c.is_ascii_alphanumeric() && **c != '-')
+            {
+                Some(&c) => c == '>' || c == '/' || c.is_whitespace(),
+                None => true,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Find the byte range of the first tag in `text` that would be raw HTML if
+/// its `<` were not escaped, from the `<` through the `>` closing it.
+pub fn find_html_tag(text: &str) -> Option<std::ops::Range<usize>> {
+    let chars: Vec<char> = text.chars().collect();
+    let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+    let start = (0..chars.len()).find(|&i| chars[i] == '<' && starts_html(&chars, i))?;
+    let end = (start..chars.len()).find(|&i| chars[i] == '>')?;
+    Some(offsets[start]..offsets[end] + 1)
+}
+
+/// Escape `<` characters in escaped text that would start raw HTML when
+/// parsed.  Characters that are already escaped are left alone.
+pub fn escape_html_starts(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+            result.push(chars[i]);
+            result.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if chars[i] == '<' && starts_html(&chars, i) {
+            result.push('\\');
+        }
+        result.push(chars[i]);
+        i += 1;
+    }
+    result
+}
+
 /// Escape pipe characters in table cell content.
 /// Pipes must be escaped to prevent being interpreted as cell boundaries.
-pub fn escape_table_cell(content: &str) -> String {
+///
+/// If `escape_html` is set, `<` characters that start raw HTML are escaped
+/// too, so that inline HTML in the cell is rendered as literal text and
+/// cannot break the table.  Code spans and link destinations are left alone.
+pub fn escape_table_cell(content: &str, escape_html: bool) -> String {
     // Escape unescaped pipe characters
     // We need to be careful not to double-escape already escaped pipes
     let mut result = String::with_capacity(content.len());
     let chars: Vec<char> = content.chars().collect();
     let mut i = 0;
+    // The end of the code span being copied, if any
+    let mut code_span_end = 0;
     while i < chars.len() {
         if chars[i] == '\\' && i + 1 < chars.len() {
             // Already escaped character - preserve both
@@ -151,10 +351,25 @@ pub fn escape_table_cell(content: &str) -> String {
             result.push(chars[i + 1]);
             i += 2;
         } else if chars[i] == '|' {
-            // Unescaped pipe - escape it
+            // Unescaped pipe - escape it, even inside code spans as GFM
+            // requires
             result.push('\\');
             result.push('|');
             i += 1;
+        } else if escape_html && i >= code_span_end && chars[i] == '`' {
+            let run = chars[i..].iter().take_while(|&&c| c == '`').count();
+            code_span_end = find_code_span_end(&chars, i + run, run).unwrap_or(i + run);
+            result.extend(&chars[i..i + run]);
+            i += run;
+        } else if escape_html
+            && i >= code_span_end
+            && chars[i] == '<'
+            && !result.ends_with("](")
+            && starts_html(&chars, i)
+        {
+            result.push('\\');
+            result.push('<');
+            i += 1;
         } else {
             result.push(chars[i]);
             i += 1;
@@ -163,10 +378,96 @@ pub fn escape_table_cell(content: &str) -> String {
     result
 }
 
+/// Find the end of a code span opened by a backtick run of length `run`
+/// just before `start`, i.e., the index after its closing backtick run.
+fn find_code_span_end(chars: &[char], start: usize, run: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '`' {
+            let length = chars[i..].iter().take_while(|&&c| c == '`').count();
+            if length == run {
+                return Some(i + length);
+            }
+            i += length;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_escape_text_underscores() {
+        assert_eq!(
+            escape_text("snake_case", true, AutolinkHost::GitHub),
+            "snake\\_case"
+        );
+        assert_eq!(
+            escape_text("snake_case", false, AutolinkHost::GitHub),
+            "snake_case"
+        );
+        assert_eq!(
+            escape_text("A__B 1_2", false, AutolinkHost::GitHub),
+            "A__B 1_2"
+        );
+        assert_eq!(
+            escape_text("_private __init__ x_", false, AutolinkHost::GitHub),
+            "\\_private \\_\\_init\\_\\_ x\\_"
+        );
+        assert_eq!(escape_text("한_글", false, AutolinkHost::GitHub), "한_글");
+    }
+
+    #[test]
+    fn test_escape_text_repository_references() {
+        let github = AutolinkHost::GitHub;
+        assert_eq!(
+            escape_text("my_org/my_repo#12", true, github),
+            "my_org/my_repo#12"
+        );
+        assert_eq!(
+            escape_text("my_org/my_repo@a1b2c3d", true, github),
+            "my_org/my_repo@a1b2c3d"
+        );
+        // Only intraword underscores are kept, and only in references
+        assert_eq!(
+            escape_text("_org/my_repo#12 my_org/my_repo", true, github),
+            "\\_org/my_repo#12 my\\_org/my\\_repo"
+        );
+        assert_eq!(
+            escape_text("my_org/my_repo#12", true, AutolinkHost::None),
+            "my\\_org/my\\_repo#12"
+        );
+        // Nested groups and merge requests are GitLab's
+        assert_eq!(escape_text("a_b/c/d_e!3", true, github), "a\\_b/c/d\\_e!3");
+        assert_eq!(
+            escape_text("a_b/c/d_e!3", true, AutolinkHost::GitLab),
+            "a_b/c/d_e!3"
+        );
+    }
+
+    #[test]
+    fn test_style_mentions() {
+        let text = "Thanks @octocat and @my-org/core-team, mail me@example.com.";
+        assert_eq!(style_mentions(text, MentionStyle::Keep), text);
+        assert_eq!(
+            style_mentions(text, MentionStyle::Escape),
+            "Thanks \\@octocat and \\@my-org/core-team, mail me@example.com."
+        );
+        assert_eq!(
+            style_mentions(text, MentionStyle::Code),
+            "Thanks `@octocat` and `@my-org/core-team`, mail me@example.com."
+        );
+        // Already escaped mentions and trailing hyphens are left alone
+        assert_eq!(
+            style_mentions("\\@octocat @octo- @-x", MentionStyle::Code),
+            "\\@octocat `@octo`- @-x"
+        );
+    }
+
     #[test]
     fn test_is_valid_code_span() {
         // Valid code spans
@@ -185,4 +486,57 @@ mod tests {
         assert!(!is_valid_code_span("foo"));
         assert!(!is_valid_code_span("foo`"));
     }
+
+    #[test]
+    fn test_escape_html_starts() {
+        assert_eq!(escape_html_starts("<span> x"), "\\<span> x");
+        assert_eq!(
+            escape_html_starts("</b> <!-- c --> <?x?>"),
+            "\\</b> \\<!-- c --> \\<?x?>"
+        );
+        assert_eq!(escape_html_starts("\\<span>"), "\\<span>");
+        assert_eq!(escape_html_starts("\\\\<br>"), "\\\\\\<br>");
+        // Not HTML
+        assert_eq!(escape_html_starts("1 < 2 <3 <-"), "1 < 2 <3 <-");
+        assert_eq!(
+            escape_html_starts("<https://example.com>"),
+            "<https://example.com>"
+        );
+        assert_eq!(
+            escape_html_starts("<user@example.com>"),
+            "<user@example.com>"
+        );
+    }
+
+    #[test]
+    fn test_escape_table_cell() {
+        assert_eq!(escape_table_cell("a | b", false), "a \\| b");
+        assert_eq!(escape_table_cell("a \\| b", false), "a \\| b");
+        assert_eq!(escape_table_cell("<b>x</b>", false), "<b>x</b>");
+    }
+
+    #[test]
+    fn test_escape_table_cell_html() {
+        assert_eq!(escape_table_cell("<b>x</b>", true), "\\<b>x\\</b>");
+        assert_eq!(escape_table_cell("a<br>b", true), "a\\<br>b");
+        assert_eq!(escape_table_cell("\\<b>", true), "\\<b>");
+        // Code spans, autolinks, and link destinations are kept
+        assert_eq!(escape_table_cell("`<b>` <b>", true), "`<b>` \\<b>");
+        assert_eq!(escape_table_cell("`` `<b>` ``", true), "`` `<b>` ``");
+        assert_eq!(escape_table_cell("`a | <b>`", true), "`a \\| <b>`");
+        assert_eq!(
+            escape_table_cell("<https://example.com>", true),
+            "<https://example.com>"
+        );
+        assert_eq!(escape_table_cell("[a](<b c>)", true), "[a](<b c>)");
+    }
+
+    #[test]
+    fn test_find_html_tag() {
+        assert_eq!(find_html_tag("a <img src=\"x\"> b"), Some(2..15));
+        assert_eq!(find_html_tag("é</b>"), Some(2..6));
+        assert_eq!(find_html_tag("<https://example.com>"), None);
+        assert_eq!(find_html_tag("a < b > c"), None);
+        assert_eq!(find_html_tag("<b unclosed"), None);
+    }
 }
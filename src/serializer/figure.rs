@@ -0,0 +1,113 @@
+//! Figure serialization logic.
+//!
+//! A common way to write a figure in Markdown is an image followed by an
+//! emphasized caption on the next line:
+//!
+//! ~~~~ markdown
+//! ![Architecture](architecture.png)
+//! *Figure 1: How the parser and the serializer interact.*
+//! ~~~~
+//!
+//! Such a paragraph is written with the caption on its own line, however
+//! long the image line is, so that wrapping never glues the two together.
+
+use comrak::nodes::{AstNode, NodeValue};
+
+use super::{Serializer, wrap};
+
+/// Returns the image and the caption of `node`, if it is a paragraph
+/// consisting only of an image, possibly within a link, and an emphasized
+/// caption on the next line.
+pub(crate) fn figure_parts<'a>(
+    node: &'a AstNode<'a>,
+) -> Option<(&'a AstNode<'a>, &'a AstNode<'a>)> {
+    if !matches!(node.data.borrow().value, NodeValue::Paragraph) {
+        return None;
+    }
+    let mut children = node.children().filter(|child| {
+        !matches!(&child.data.borrow().value, NodeValue::Text(text) if text.trim().is_empty())
+    });
+    let image = children.next()?;
+    let line_break = children.next()?;
+    let caption = children.next()?;
+    let is_image = match &image.data.borrow().value {
+        NodeValue::Image(_) => true,
+        NodeValue::Link(_) => image.first_child().is_some_and(|child| {
+            child.next_sibling().is_none()
+                && matches!(child.data.borrow().value, NodeValue::Image(_))
+        }),
+        _ => false,
+    };
+    (is_image
+        && matches!(
+            line_break.data.borrow().value,
+            NodeValue::SoftBreak | NodeValue::LineBreak
+        )
+        && matches!(caption.data.borrow().value, NodeValue::Emph)
+        && children.next().is_none())
+    .then_some((image, caption))
+}
+
+impl<'a> Serializer<'a> {
+    /// Write a figure paragraph: the image on one line, and the caption,
+    /// wrapped if necessary, from the next line on.
+    pub(super) fn serialize_figure<'b>(
+        &mut self,
+        image: &'b AstNode<'b>,
+        caption: &'b AstNode<'b>,
+    ) {
+        let mut image_content = String::new();
+        self.collect_inline_node(image, &mut image_content);
+        // The image line is never wrapped, so alt text spanning lines is
+        // joined
+        self.output
+            .push_str(image_content.trim().replace('\x00', " ").as_str());
+        let hard_break = image
+            .next_sibling()
+            .is_some_and(|next| matches!(next.data.borrow().value, NodeValue::LineBreak));
+        if hard_break {
+            self.output.push(wrap::HARD_BREAK);
+        }
+        self.output.push('\n');
+        let mut caption_content = String::new();
+        self.collect_inline_node(caption, &mut caption_content);
+        self.write_wrapped(caption_content.trim());
+        self.output.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Options, format};
+
+    #[test]
+    fn test_figure_caption_stays_on_its_own_line() {
+        let input = "![Diagram](diagram.png \"The architecture of the system, as of version 2\")
+*Figure 1: The architecture of the system, showing how the parser and the serializer interact.*
+";
+        assert_eq!(
+            format(input, &Options::default()).unwrap(),
+            "![Diagram](diagram.png \"The architecture of the system, as of version 2\")
+*Figure 1: The architecture of the system, showing how the parser and the
+serializer interact.*
+"
+        );
+    }
+
+    #[test]
+    fn test_figure_in_list_item() {
+        let input = " -  [![Diagram](diagram.png)](https://example.com/diagram)
+    *Figure 1: The diagram.*
+";
+        assert_eq!(format(input, &Options::default()).unwrap(), input);
+    }
+
+    #[test]
+    fn test_figure_with_hard_break() {
+        let input = "![Diagram](diagram.png)\\\n*Figure 1: The diagram.*\n";
+        assert_eq!(
+            format(input, &Options::default()).unwrap(),
+            "![Diagram](diagram.png)  \n*Figure 1: The diagram.*\n"
+        );
+    }
+}
@@ -3,22 +3,35 @@
 //! This module provides functionality for running external code formatters
 //! on code block contents. It handles process spawning, timeout management,
 //! and error handling.
+//!
+//! Formatters are often wrapper scripts, e.g., `npx prettier`, whose
+//! children would keep running and hold the pipes open if only the wrapper
+//! were killed.  So each formatter runs in a process tree of its own, a new
+//! process group on Unix and a job object on Windows, and the whole tree is
+//! killed when it times out.
 
-use std::io::Write;
+use std::io::{self, Read, Write};
+use std::path::Path;
 use std::process::{Command, Stdio};
-use std::thread;
+use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
+use super::sandbox::Sandbox;
+use crate::config::default_formatter_timeout;
+use crate::{CodeFormatter, Options, WarningCode};
+
 /// Error types for formatter execution.
 #[derive(Debug)]
 pub enum FormatterError {
     /// Command array is empty.
     EmptyCommand,
+    /// Command is not allowed by [`Options::allowed_code_formatters`].
+    NotAllowed,
     /// Failed to spawn the process.
     Spawn(std::io::Error),
     /// Failed to write to stdin.
     Stdin(std::io::Error),
-    /// Process timed out.
+    /// Process timed out and was killed along with its children.
     Timeout,
     /// Process exited with non-zero status.
     NonZeroExit {
@@ -35,6 +48,9 @@ impl std::fmt::Display for FormatterError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FormatterError::EmptyCommand => write!(f, "command is empty"),
+            FormatterError::NotAllowed => {
+                write!(f, "command is not among the allowed code formatters")
+            }
             FormatterError::Spawn(e) => write!(f, "failed to spawn process: {}", e),
             FormatterError::Stdin(e) => write!(f, "failed to write to stdin: {}", e),
             FormatterError::Timeout => write!(f, "process timed out"),
@@ -65,13 +81,255 @@ impl std::error::Error for FormatterError {
     }
 }
 
+/// Get the code and message of the warning for `error`, which `formatter`
+/// failed with for `language`.
+pub(crate) fn failure_warning(
+    formatter: &CodeFormatter,
+    language: &str,
+    error: &FormatterError,
+) -> (WarningCode, String) {
+    let command = formatter.command.join(" ");
+    match error {
+        FormatterError::Timeout => (
+            WarningCode::CodeFormatterTimeout,
+            format!(
+                "code formatter '{}' for language '{}' timed out after {} second{} and was killed",
+                command,
+                language,
+                formatter.timeout_secs,
+                if formatter.timeout_secs == 1 { "" } else { "s" }
+            ),
+        ),
+        _ => (
+            WarningCode::CodeFormatter,
+            format!(
+                "code formatter '{}' failed for language '{}': {}",
+                command, language, error
+            ),
+        ),
+    }
+}
+
+/// Get the formatter for a code block in `language`: the one configured
+/// for the language, or the `command` chosen by a `hongdown-format-with`
+/// directive, which has the timeout of the former, if any.
+pub(crate) fn block_formatter(
+    options: &Options,
+    language: &str,
+    command: Option<&[String]>,
+) -> Option<CodeFormatter> {
+    let configured = options.code_formatters.get(language);
+    match command {
+        Some(command) => Some(CodeFormatter {
+            command: command.to_vec(),
+            timeout_secs: configured.map_or_else(default_formatter_timeout, |formatter| {
+                formatter.timeout_secs
+            }),
+        }),
+        None => configured.cloned(),
+    }
+}
+
+/// Check that `command` starts with one of
+/// [`Options::allowed_code_formatters`], if any.
+///
+/// Commands chosen by a `hongdown-format-with` directive come from the
+/// document rather than the configuration, so without the allowed list they
+/// must be the command of one of [`Options::code_formatters`].
+pub(crate) fn check_allowed(
+    command: &[String],
+    from_directive: bool,
+    options: &Options,
+) -> Result<(), FormatterError> {
+    let allowed = match &options.allowed_code_formatters {
+        Some(allowed) => allowed
+            .iter()
+            .any(|prefix| !prefix.is_empty() && command.starts_with(prefix)),
+        None if from_directive => options
+            .code_formatters
+            .values()
+            .any(|formatter| formatter.command == command),
+        None => true,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(FormatterError::NotAllowed)
+    }
+}
+
+/// Make `command` start a process tree of its own when spawned, so that
+/// [`ProcessTree::kill`] can kill it along with its children.
+pub(crate) fn isolate(command: &mut Command) {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// The processes of a formatter spawned from a command passed to
+/// [`isolate`].  Dropping it kills them, unless it is released.
+pub(crate) struct ProcessTree {
+    /// The process group, whose ID is the PID of the formatter.
+    #[cfg(unix)]
+    group: Option<libc::pid_t>,
+    /// The job object the formatter is assigned to.
+    #[cfg(windows)]
+    job: Option<windows_sys::Win32::Foundation::HANDLE>,
+    /// Whether to kill the processes when dropped.
+    armed: bool,
+}
+
+impl ProcessTree {
+    /// Track the processes of `child`.
+    pub(crate) fn new(child: &std::process::Child) -> Self {
+        #[cfg(windows)]
+        {
+            use std::os::windows::io::AsRawHandle;
+            Self::track(Some(child.as_raw_handle()))
+        }
+        #[cfg(not(windows))]
+        {
+            Self::track(Some(child.id()))
+        }
+    }
+
+    /// Track the processes of `child`, spawned by Tokio.
+    #[cfg(feature = "async")]
+    pub(crate) fn new_async(child: &tokio::process::Child) -> Self {
+        #[cfg(windows)]
+        {
+            Self::track(child.raw_handle())
+        }
+        #[cfg(not(windows))]
+        {
+            Self::track(child.id())
+        }
+    }
+
+    #[cfg(unix)]
+    fn track(pid: Option<u32>) -> Self {
+        Self {
+            group: pid.and_then(|pid| libc::pid_t::try_from(pid).ok()),
+            armed: true,
+        }
+    }
+
+    /// Assign the process to a new job object.  Its children join the job
+    /// as well, unless they break away from it.
+    #[cfg(windows)]
+    fn track(process: Option<std::os::windows::io::RawHandle>) -> Self {
+        use windows_sys::Win32::Foundation::CloseHandle;
+        use windows_sys::Win32::System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW};
+
+        let job = process.and_then(|process| {
+            // SAFETY: the job object is created unnamed with default security,
+            // and `process` is a valid handle owned by the child.
+            unsafe {
+                let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+                if job.is_null() {
+                    return None;
+                }
+                if AssignProcessToJobObject(job, process) == 0 {
+                    CloseHandle(job);
+                    return None;
+                }
+                Some(job)
+            }
+        });
+        Self { job, armed: true }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn track(_pid: Option<u32>) -> Self {
+        Self { armed: true }
+    }
+
+    /// Kill all processes of the tree.  This must be done before the
+    /// formatter is waited for, so that its process group cannot have been
+    /// reused by then.
+    pub(crate) fn kill(&mut self) {
+        self.armed = false;
+        #[cfg(unix)]
+        if let Some(group) = self.group {
+            // SAFETY: `kill` has no memory safety requirements.
+            unsafe {
+                libc::kill(-group, libc::SIGKILL);
+            }
+        }
+        #[cfg(windows)]
+        if let Some(job) = self.job {
+            // SAFETY: `job` is a job object handle owned by this tree.
+            unsafe {
+                windows_sys::Win32::System::JobObjects::TerminateJobObject(job, 1);
+            }
+        }
+    }
+
+    /// Leave the processes running when dropped, e.g., because the
+    /// formatter finished.
+    pub(crate) fn release(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        if self.armed {
+            self.kill();
+        }
+        #[cfg(windows)]
+        if let Some(job) = self.job.take() {
+            // SAFETY: `job` is a job object handle owned by this tree.
+            unsafe {
+                windows_sys::Win32::Foundation::CloseHandle(job);
+            }
+        }
+    }
+}
+
+/// Read `pipe` to its end on a thread of its own.
+fn read_on_thread(mut pipe: impl Read + Send + 'static) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        pipe.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    })
+}
+
+/// Wait for the thread `handle`, if any, assuming that it has finished.
+fn join<T: Default>(handle: Option<JoinHandle<io::Result<T>>>) -> io::Result<T> {
+    match handle {
+        Some(handle) => handle
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("thread panicked"))),
+        None => Ok(T::default()),
+    }
+}
+
+/// Ignore the error of writing to a pipe that the formatter closed, as it
+/// may exit successfully without reading all of its input.
+pub(crate) fn ignore_broken_pipe(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
 /// Run an external formatter command with the given code as stdin.
 ///
+/// If the process does not finish within the timeout, it is killed along
+/// with its children, and [`FormatterError::Timeout`] is returned.
+///
 /// # Arguments
 ///
 /// * `command` - Command and arguments as a slice of strings.
 /// * `code` - Code to format, passed via stdin.
 /// * `timeout_secs` - Maximum time to wait for the process in seconds.
+/// * `dir` - Directory to run the process in, or `None` for the current
+///   directory.
+/// * `sandbox` - Whether to run the process in a [`Sandbox`], which ignores
+///   `dir`.
 ///
 /// # Returns
 ///
@@ -80,6 +338,8 @@ pub fn run_formatter(
     command: &[String],
     code: &str,
     timeout_secs: u64,
+    dir: Option<&Path>,
+    sandbox: bool,
 ) -> Result<String, FormatterError> {
     if command.is_empty() {
         return Err(FormatterError::EmptyCommand);
@@ -87,52 +347,66 @@ pub fn run_formatter(
 
     let (program, args) = command.split_first().unwrap();
 
-    let mut child = Command::new(program)
+    let mut command = Command::new(program);
+    command
         .args(args)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(FormatterError::Spawn)?;
+        .stderr(Stdio::piped());
+    // Kept until the process exits
+    let _sandbox = if sandbox {
+        Some(Sandbox::apply(&mut command).map_err(FormatterError::Spawn)?)
+    } else {
+        if let Some(dir) = dir {
+            command.current_dir(dir);
+        }
+        None
+    };
+    isolate(&mut command);
+    let mut child = command.spawn().map_err(FormatterError::Spawn)?;
+    let mut tree = ProcessTree::new(&child);
 
-    // Write code to stdin
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(code.as_bytes())
-            .map_err(FormatterError::Stdin)?;
-        // stdin is dropped here, closing it
-    }
+    // Write stdin and read stdout and stderr on threads of their own, so
+    // that a formatter blocked on a full pipe still times out
+    let stdin = child.stdin.take().map(|mut stdin| {
+        let code = code.to_string();
+        thread::spawn(move || stdin.write_all(code.as_bytes()))
+    });
+    let stdout = child.stdout.take().map(read_on_thread);
+    let stderr = child.stderr.take().map(read_on_thread);
 
-    // Wait with timeout using polling
-    let timeout = Duration::from_secs(timeout_secs);
-    let start = Instant::now();
+    // Wait with timeout using polling.  The pipes may outlive the process
+    // if its children inherited them, so they are waited for as well.
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
     let poll_interval = Duration::from_millis(50);
-
-    loop {
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                // Process finished
-                let output = child.wait_with_output().map_err(FormatterError::Spawn)?;
-                if status.success() {
-                    return String::from_utf8(output.stdout).map_err(FormatterError::InvalidUtf8);
-                } else {
-                    return Err(FormatterError::NonZeroExit {
-                        code: status.code(),
-                        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                    });
-                }
-            }
-            Ok(None) => {
-                // Still running
-                if start.elapsed() >= timeout {
-                    let _ = child.kill();
-                    return Err(FormatterError::Timeout);
-                }
-                thread::sleep(poll_interval);
+    let status = loop {
+        let status = child.try_wait().map_err(FormatterError::Spawn)?;
+        let pipes_closed = stdin.as_ref().is_none_or(JoinHandle::is_finished)
+            && stdout.as_ref().is_none_or(JoinHandle::is_finished)
+            && stderr.as_ref().is_none_or(JoinHandle::is_finished);
+        match status {
+            Some(status) if pipes_closed => break status,
+            _ if Instant::now() >= deadline => {
+                tree.kill();
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(FormatterError::Timeout);
             }
-            Err(e) => return Err(FormatterError::Spawn(e)),
+            _ => thread::sleep(poll_interval),
         }
+    };
+    tree.release();
+
+    let stdout = join(stdout).map_err(FormatterError::Spawn)?;
+    let stderr = join(stderr).map_err(FormatterError::Spawn)?;
+    if !status.success() {
+        return Err(FormatterError::NonZeroExit {
+            code: status.code(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+        });
     }
+    ignore_broken_pipe(join(stdin)).map_err(FormatterError::Stdin)?;
+    String::from_utf8(stdout).map_err(FormatterError::InvalidUtf8)
 }
 
 #[cfg(test)]
@@ -141,14 +415,14 @@ mod tests {
 
     #[test]
     fn test_run_formatter_empty_command() {
-        let result = run_formatter(&[], "code", 5);
+        let result = run_formatter(&[], "code", 5, None, false);
         assert!(matches!(result, Err(FormatterError::EmptyCommand)));
     }
 
     #[test]
     fn test_run_formatter_cat() {
         // cat simply outputs its input unchanged
-        let result = run_formatter(&["cat".to_string()], "hello world", 5);
+        let result = run_formatter(&["cat".to_string()], "hello world", 5, None, false);
         assert_eq!(result.unwrap(), "hello world");
     }
 
@@ -159,6 +433,8 @@ mod tests {
             &["tr".to_string(), "a-z".to_string(), "A-Z".to_string()],
             "hello",
             5,
+            None,
+            false,
         );
         assert_eq!(result.unwrap(), "HELLO");
     }
@@ -166,34 +442,152 @@ mod tests {
     #[test]
     fn test_run_formatter_nonzero_exit() {
         // false always exits with code 1
-        let result = run_formatter(&["false".to_string()], "code", 5);
+        let result = run_formatter(&["false".to_string()], "code", 5, None, false);
         assert!(matches!(result, Err(FormatterError::NonZeroExit { .. })));
     }
 
     #[test]
     fn test_run_formatter_command_not_found() {
-        let result = run_formatter(&["nonexistent_command_12345".to_string()], "code", 5);
+        let result = run_formatter(
+            &["nonexistent_command_12345".to_string()],
+            "code",
+            5,
+            None,
+            false,
+        );
         assert!(matches!(result, Err(FormatterError::Spawn(_))));
     }
 
     #[test]
     fn test_run_formatter_timeout() {
         // sleep for longer than timeout
-        let result = run_formatter(&["sleep".to_string(), "10".to_string()], "", 1);
+        let result = run_formatter(&["sleep".to_string(), "10".to_string()], "", 1, None, false);
+        assert!(matches!(result, Err(FormatterError::Timeout)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_formatter_timeout_kills_children() {
+        // The shell exits at once, but its child keeps stdout open
+        let dir = tempfile::tempdir().unwrap();
+        let command = ["sh", "-c", "sleep 30 & echo $! > pid"].map(String::from);
+        let started = Instant::now();
+        let result = run_formatter(&command, "", 1, Some(dir.path()), false);
         assert!(matches!(result, Err(FormatterError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let pid = std::fs::read_to_string(dir.path().join("pid")).unwrap();
+        let stat = format!("/proc/{}/stat", pid.trim());
+        // A killed orphan may linger as a zombie until it is reaped
+        let killed = || {
+            std::fs::read_to_string(&stat).map_or(true, |stat| {
+                stat.rsplit(") ").next().unwrap().starts_with('Z')
+            })
+        };
+        while !killed() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_formatter_sandbox() {
+        let dir = tempfile::tempdir().unwrap();
+        let command = ["sh", "-c", "echo \"${CARGO_MANIFEST_DIR-unset}\"; pwd"].map(String::from);
+        let output = run_formatter(&command, "", 5, Some(dir.path()), false).unwrap();
+        assert_eq!(
+            output,
+            format!("{}\n{}\n", env!("CARGO_MANIFEST_DIR"), dir.path().display())
+        );
+
+        let output = match run_formatter(&command, "", 5, Some(dir.path()), true) {
+            Ok(output) => output,
+            // Without unprivileged user namespaces, the formatter is not run
+            Err(FormatterError::Spawn(_)) if cfg!(target_os = "linux") => return,
+            Err(e) => panic!("{}", e),
+        };
+        let (variable, ran_in) = output.trim_end().split_once('\n').unwrap();
+        assert_eq!(variable, "unset");
+        assert_ne!(Path::new(ran_in), dir.path());
+        assert!(!Path::new(ran_in).exists());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_formatter_sandbox_network() {
+        let command = ["cat", "/proc/net/dev"].map(String::from);
+        match run_formatter(&command, "", 5, None, true) {
+            Ok(output) => {
+                let interfaces: Vec<&str> = output
+                    .lines()
+                    .skip(2)
+                    .map(|line| line.split(':').next().unwrap().trim())
+                    .collect();
+                assert_eq!(interfaces, ["lo"]);
+            }
+            // Without unprivileged user namespaces, the formatter is not run
+            // instead of running with network access
+            Err(FormatterError::Spawn(e)) => {
+                assert!(e.to_string().contains("network"), "{}", e);
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn test_check_allowed() {
+        let command = ["deno", "fmt", "-"].map(String::from);
+        let mut options = Options::default();
+        assert!(check_allowed(&command, false, &options).is_ok());
+        assert!(check_allowed(&command, true, &options).is_err());
+        options.code_formatters.insert(
+            "javascript".to_string(),
+            CodeFormatter {
+                command: command.to_vec(),
+                timeout_secs: 5,
+            },
+        );
+        assert!(check_allowed(&command, true, &options).is_ok());
+
+        options.allowed_code_formatters = Some(vec![vec!["deno".to_string(), "fmt".to_string()]]);
+        assert!(check_allowed(&command, true, &options).is_ok());
+        assert!(matches!(
+            check_allowed(&["deno", "run", "x.ts"].map(String::from), false, &options),
+            Err(FormatterError::NotAllowed)
+        ));
+        options.allowed_code_formatters = Some(vec![Vec::new()]);
+        assert!(check_allowed(&command, false, &options).is_err());
+    }
+
+    #[test]
+    fn test_run_formatter_large_output() {
+        // More than the pipes can buffer, so stdout must be read while
+        // stdin is being written
+        let input = "x".repeat(1 << 20);
+        let result = run_formatter(&["cat".to_string()], &input, 5, None, false);
+        assert_eq!(result.unwrap(), input);
+    }
+
+    #[test]
+    fn test_run_formatter_ignoring_stdin() {
+        // The formatter exits before the input is written
+        let input = "x".repeat(1 << 20);
+        let result = run_formatter(&["true".to_string()], &input, 5, None, false);
+        assert_eq!(result.unwrap(), "");
     }
 
     #[test]
     fn test_run_formatter_multiline() {
         let input = "line1\nline2\nline3";
-        let result = run_formatter(&["cat".to_string()], input, 5);
+        let result = run_formatter(&["cat".to_string()], input, 5, None, false);
         assert_eq!(result.unwrap(), input);
     }
 
     #[test]
     fn test_run_formatter_unicode() {
         let input = "Hello, \u{4e16}\u{754c}! \u{1f600}";
-        let result = run_formatter(&["cat".to_string()], input, 5);
+        let result = run_formatter(&["cat".to_string()], input, 5, None, false);
         assert_eq!(result.unwrap(), input);
     }
 }
@@ -0,0 +1,563 @@
+//! Front matter formatting.
+//!
+//! YAML front matter is kept as it is, except for the changes requested by
+//! the options.  Rather than parsing the YAML, its top-level keys are
+//! recognized line by line, so that comments and the formatting of values
+//! are preserved.  Front matter that is not a plain mapping is left alone.
+
+use crate::Options;
+
+/// A part of the front matter body.
+enum Item<'a> {
+    /// A top-level key with the lines of its value and the comment lines
+    /// directly above it.
+    Entry { key: String, lines: &'a [&'a str] },
+    /// A blank line or a comment that does not belong to any key.
+    Other(&'a str),
+}
+
+/// Keys whose values are normalized as dates.
+const DATE_KEYS: &[&str] = &["date", "updated"];
+
+/// English month names, which dates may also be written with.
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Format front matter `content`, delimiters included.
+pub(crate) fn format_front_matter(content: &str, options: &Options) -> String {
+    format_front_matter_with_warnings(content, options).0
+}
+
+/// Format front matter `content`, delimiters included, along with warnings
+/// about it as pairs of a 1-indexed line number in `content` and a message.
+pub(crate) fn format_front_matter_with_warnings(
+    content: &str,
+    options: &Options,
+) -> (String, Vec<(usize, String)>) {
+    let content = content.trim();
+    let mut warnings = Vec::new();
+    if options.front_matter_key_order.is_empty() && !options.front_matter_normalize_dates {
+        return (content.to_string(), warnings);
+    }
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    if lines.len() < 2
+        || lines[0].trim_end() != "---"
+        || !matches!(lines[lines.len() - 1].trim_end(), "---" | "...")
+    {
+        return (content.to_string(), warnings);
+    }
+    let last = lines.len() - 1;
+    {
+        let body: Vec<&str> = lines[1..last].iter().map(String::as_str).collect();
+        if parse_items(&body).is_none() {
+            return (content.to_string(), warnings);
+        }
+    }
+
+    if options.front_matter_normalize_dates {
+        for (index, line) in lines.iter_mut().enumerate().take(last).skip(1) {
+            match normalize_date_line(line, options) {
+                Ok(Some(normalized)) => *line = normalized,
+                Ok(None) => {}
+                Err(message) => warnings.push((index + 1, message)),
+            }
+        }
+    }
+    if options.front_matter_key_order.is_empty() {
+        return (lines.join("\n"), warnings);
+    }
+
+    let body: Vec<&str> = lines[1..last].iter().map(String::as_str).collect();
+    let Some(items) = parse_items(&body) else {
+        return (lines.join("\n"), warnings);
+    };
+
+    // Listed keys first, in the given order, then the rest as they were
+    let mut entries: Vec<(&String, &[&str])> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Entry { key, lines } => Some((key, *lines)),
+            Item::Other(_) => None,
+        })
+        .collect();
+    entries.sort_by_key(|(key, _)| {
+        options
+            .front_matter_key_order
+            .iter()
+            .position(|k| k == *key)
+            .unwrap_or(usize::MAX)
+    });
+
+    // Put the entries back into the slots of the original ones, so that
+    // blank lines and detached comments stay where they are
+    let mut result = vec![lines[0].as_str()];
+    let mut entries = entries.into_iter();
+    for item in &items {
+        match item {
+            Item::Entry { .. } => {
+                if let Some((_, lines)) = entries.next() {
+                    result.extend_from_slice(lines);
+                }
+            }
+            Item::Other(line) => result.push(line),
+        }
+    }
+    result.push(lines[last].as_str());
+    (result.join("\n"), warnings)
+}
+
+/// Normalize the value of a top-level date key on `line`.  Returns
+/// `Ok(None)` if the line has nothing to normalize, or an error message if
+/// its value cannot be parsed as a date.
+fn normalize_date_line(line: &str, options: &Options) -> Result<Option<String>, String> {
+    let Some(key) = top_level_key(line) else {
+        return Ok(None);
+    };
+    if !DATE_KEYS.contains(&key.as_str()) {
+        return Ok(None);
+    }
+    // The value follows the first colon after the key, and may be followed
+    // by a comment
+    let key_end = line.find(&key).map_or(0, |i| i + key.len());
+    let Some(colon) = line[key_end..].find(':').map(|i| key_end + i + 1) else {
+        return Ok(None);
+    };
+    let rest = &line[colon..];
+    let value_start = colon + (rest.len() - rest.trim_start().len());
+    let value_end = line[value_start..]
+        .find(" #")
+        .map_or(line.trim_end().len(), |i| value_start + i);
+    let raw = line[value_start..value_end].trim_end();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    let (quote, value) = match raw.chars().next() {
+        Some(q @ ('"' | '\'')) if raw.len() >= 2 && raw.ends_with(q) => {
+            (Some(q), &raw[1..raw.len() - 1])
+        }
+        _ => (None, raw),
+    };
+    let Some(date) = parse_date(value) else {
+        return Err(format!("cannot parse {:?} in `{}` as a date", value, key));
+    };
+    let pattern = if date.time.is_some() {
+        &options.front_matter_datetime_format
+    } else {
+        &options.front_matter_date_format
+    };
+    let formatted = date.format(pattern);
+    let quote = quote.map(String::from).unwrap_or_default();
+    Ok(Some(format!(
+        "{}{}{}{}{}",
+        &line[..value_start],
+        quote,
+        formatted,
+        quote,
+        &line[value_start + raw.len()..]
+    )))
+}
+
+/// A date read from front matter.
+struct Date {
+    year: u32,
+    month: u32,
+    day: u32,
+    time: Option<Time>,
+    /// The UTC offset: `Z` or `+HH:MM`.
+    offset: Option<String>,
+}
+
+/// A time of day read from front matter.
+struct Time {
+    hour: u32,
+    minute: u32,
+    second: u32,
+    /// Fractional seconds, including the leading dot, if any.
+    fraction: String,
+}
+
+impl Date {
+    /// Format the date with a pattern of `%Y`, `%m`, `%d`, `%H`, `%M`, `%S`
+    /// (with fractional seconds, if any), `%z` (the UTC offset, if any), and
+    /// `%%`.
+    fn format(&self, pattern: &str) -> String {
+        let mut result = String::new();
+        let mut chars = pattern.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                result.push(ch);
+                continue;
+            }
+            let time = self.time.as_ref();
+            match chars.next() {
+                Some('Y') => result.push_str(&format!("{:04}", self.year)),
+                Some('m') => result.push_str(&format!("{:02}", self.month)),
+                Some('d') => result.push_str(&format!("{:02}", self.day)),
+                Some('H') => result.push_str(&format!("{:02}", time.map_or(0, |t| t.hour))),
+                Some('M') => result.push_str(&format!("{:02}", time.map_or(0, |t| t.minute))),
+                Some('S') => {
+                    result.push_str(&format!("{:02}", time.map_or(0, |t| t.second)));
+                    result.push_str(time.map_or("", |t| &t.fraction));
+                }
+                Some('z') => result.push_str(self.offset.as_deref().unwrap_or("")),
+                Some('%') => result.push('%'),
+                Some(other) => {
+                    result.push('%');
+                    result.push(other);
+                }
+                None => result.push('%'),
+            }
+        }
+        result
+    }
+}
+
+/// Parse a date like `2025-01-31`, `2025/1/31 09:30`, `2025-01-31T09:30:00Z`,
+/// `January 31, 2025`, or `31 Jan 2025`.
+fn parse_date(value: &str) -> Option<Date> {
+    parse_numeric_date(value).or_else(|| parse_written_date(value))
+}
+
+fn parse_numeric_date(value: &str) -> Option<Date> {
+    let (date, time) = match value.find(['T', 't', ' ']) {
+        Some(i) => (&value[..i], Some(value[i + 1..].trim_start())),
+        None => (value, None),
+    };
+    let separator = date.chars().find(|ch| !ch.is_ascii_digit())?;
+    if !matches!(separator, '-' | '/' | '.') {
+        return None;
+    }
+    let parts: Vec<&str> = date.split(separator).collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() > 2 || parts[2].len() > 2 {
+        return None;
+    }
+    let (time, offset) = match time {
+        Some(time) => {
+            let (time, offset) = parse_time(time)?;
+            (Some(time), offset)
+        }
+        None => (None, None),
+    };
+    new_date(
+        parse_number(parts[0])?,
+        parse_number(parts[1])?,
+        parse_number(parts[2])?,
+        time,
+        offset,
+    )
+}
+
+fn parse_time(text: &str) -> Option<(Time, Option<String>)> {
+    let end = text
+        .find(|ch: char| !(ch.is_ascii_digit() || ch == ':' || ch == '.'))
+        .unwrap_or(text.len());
+    let (clock, zone) = (&text[..end], text[end..].trim_start());
+    let mut parts = clock.split(':');
+    let hour = parts.next().filter(|part| part.len() <= 2)?;
+    let minute = parts.next().filter(|part| part.len() == 2)?;
+    let (second, fraction) = match parts.next() {
+        Some(second) => match second.split_once('.') {
+            Some((second, fraction)) => (second, format!(".{}", fraction)),
+            None => (second, String::new()),
+        },
+        None => ("0", String::new()),
+    };
+    if parts.next().is_some() || second.len() > 2 || fraction.len() == 1 {
+        return None;
+    }
+    let time = Time {
+        hour: parse_number(hour).filter(|&hour| hour < 24)?,
+        minute: parse_number(minute).filter(|&minute| minute < 60)?,
+        second: parse_number(second).filter(|&second| second <= 60)?,
+        fraction,
+    };
+    if !fraction_is_digits(&time.fraction) {
+        return None;
+    }
+    let offset = match zone {
+        "" => None,
+        "Z" | "z" => Some("Z".to_string()),
+        _ => {
+            let sign = zone.chars().next().filter(|ch| matches!(ch, '+' | '-'))?;
+            let digits: String = zone[1..].chars().filter(|&ch| ch != ':').collect();
+            if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let minutes = digits.get(2..).unwrap_or("00");
+            Some(format!("{}{}:{}", sign, &digits[..2], minutes))
+        }
+    };
+    Some((time, offset))
+}
+
+fn parse_written_date(value: &str) -> Option<Date> {
+    let words: Vec<&str> = value
+        .split([' ', ','])
+        .filter(|word| !word.is_empty())
+        .collect();
+    let [first, second, year] = words[..] else {
+        return None;
+    };
+    let (month, day) = match parse_month(first) {
+        Some(month) => (month, second),
+        None => (parse_month(second)?, first),
+    };
+    let day = day.trim_end_matches(|ch: char| ch.is_ascii_alphabetic());
+    if year.len() != 4 {
+        return None;
+    }
+    new_date(parse_number(year)?, month, parse_number(day)?, None, None)
+}
+
+/// Parse an English month name or its abbreviation, e.g., `Jan` or `Sept.`.
+fn parse_month(word: &str) -> Option<u32> {
+    let word = word.trim_end_matches('.').to_lowercase();
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(&word))
+        .map(|index| index as u32 + 1)
+}
+
+fn parse_number(text: &str) -> Option<u32> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn fraction_is_digits(fraction: &str) -> bool {
+    fraction
+        .strip_prefix('.')
+        .is_none_or(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Make a date, if the day exists.
+fn new_date(
+    year: u32,
+    month: u32,
+    day: u32,
+    time: Option<Time>,
+    offset: Option<String>,
+) -> Option<Date> {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    (1..=days).contains(&day).then_some(Date {
+        year,
+        month,
+        day,
+        time,
+        offset,
+    })
+}
+
+/// Split the front matter body into top-level entries and other lines, or
+/// return `None` if it is not a mapping.
+fn parse_items<'a>(lines: &'a [&'a str]) -> Option<Vec<Item<'a>>> {
+    let mut items = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        // Comments directly above a key belong to it
+        let mut key_line = i;
+        while key_line < lines.len() && lines[key_line].starts_with('#') {
+            key_line += 1;
+        }
+        let Some(key) = lines.get(key_line).and_then(|line| top_level_key(line)) else {
+            let line = lines[i];
+            if !line.trim().is_empty() && !line.starts_with('#') {
+                return None;
+            }
+            items.push(Item::Other(line));
+            i += 1;
+            continue;
+        };
+        // The value goes on over indented lines and sequence items, and over
+        // blank lines followed by more of them
+        let mut end = key_line + 1;
+        loop {
+            let mut next = end;
+            while next < lines.len() && lines[next].trim().is_empty() {
+                next += 1;
+            }
+            if next < lines.len() && is_continuation(lines[next]) {
+                end = next + 1;
+            } else {
+                break;
+            }
+        }
+        items.push(Item::Entry {
+            key,
+            lines: &lines[i..end],
+        });
+        i = end;
+    }
+    Some(items)
+}
+
+/// Return the key of a line that starts a top-level mapping entry.
+fn top_level_key(line: &str) -> Option<String> {
+    let first = line.chars().next()?;
+    if first.is_whitespace() || matches!(first, '#' | '-' | '?' | '[' | '{') {
+        return None;
+    }
+    if first == '"' || first == '\'' {
+        let end = line[1..].find(first)? + 1;
+        let rest = &line[end + 1..];
+        return (rest.starts_with(": ") || rest.trim_end() == ":")
+            .then(|| line[1..end].to_string());
+    }
+    let line = line.trim_end();
+    let end = line
+        .find(": ")
+        .or_else(|| line.ends_with(':').then(|| line.len() - 1))?;
+    Some(line[..end].to_string())
+}
+
+/// Check whether a line continues the value of the entry above it.
+fn is_continuation(line: &str) -> bool {
+    line.starts_with([' ', '\t']) || line == "-" || line.starts_with("- ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reorder(content: &str, key_order: &[&str]) -> String {
+        let options = Options {
+            front_matter_key_order: key_order.iter().map(|key| key.to_string()).collect(),
+            ..Options::default()
+        };
+        format_front_matter(content, &options)
+    }
+
+    #[test]
+    fn test_key_order() {
+        let content = "---\ntags:\n- a\n- b\ndate: 2025-01-01\nauthor: Hong\ntitle: Hello\n---";
+        assert_eq!(
+            reorder(content, &["title", "description", "date", "tags"]),
+            "---\ntitle: Hello\ndate: 2025-01-01\ntags:\n- a\n- b\nauthor: Hong\n---"
+        );
+        assert_eq!(reorder(content, &[]), content);
+    }
+
+    #[test]
+    fn test_key_order_keeps_comments() {
+        let content = "---\n# Generated\n\n# When\ndate: 2025-01-01\ntitle: |\n  Hello\n\n  World\n# trailing\n---";
+        assert_eq!(
+            reorder(content, &["title", "date"]),
+            "---\n# Generated\n\ntitle: |\n  Hello\n\n  World\n# When\ndate: 2025-01-01\n# trailing\n---"
+        );
+    }
+
+    #[test]
+    fn test_key_order_quoted_keys() {
+        let content = "---\n\"date\": 2025-01-01\n'title': \"a: b\"\n---";
+        assert_eq!(
+            reorder(content, &["title", "date"]),
+            "---\n'title': \"a: b\"\n\"date\": 2025-01-01\n---"
+        );
+    }
+
+    fn normalize(content: &str) -> (String, Vec<(usize, String)>) {
+        let options = Options {
+            front_matter_normalize_dates: true,
+            ..Options::default()
+        };
+        format_front_matter_with_warnings(content, &options)
+    }
+
+    #[test]
+    fn test_normalize_dates() {
+        let content =
+            "---\ndate: 2025/1/5\nupdated: \"January 31, 2025\" # edited\ntitle: 2025/1/5\n---";
+        assert_eq!(
+            normalize(content),
+            (
+                "---\ndate: 2025-01-05\nupdated: \"2025-01-31\" # edited\ntitle: 2025/1/5\n---"
+                    .to_string(),
+                vec![]
+            )
+        );
+        assert_eq!(
+            normalize("---\ndate: 5 Sept 2025\n---").0,
+            "---\ndate: 2025-09-05\n---"
+        );
+    }
+
+    #[test]
+    fn test_normalize_datetimes() {
+        assert_eq!(
+            normalize("---\ndate: 2025-01-05 9:30\n---").0,
+            "---\ndate: 2025-01-05T09:30:00\n---"
+        );
+        assert_eq!(
+            normalize("---\ndate: 2025-01-05T09:30:15.5+0900\n---").0,
+            "---\ndate: 2025-01-05T09:30:15.5+09:00\n---"
+        );
+        assert_eq!(
+            normalize("---\ndate: 2025-01-05 09:30:00 Z\n---").0,
+            "---\ndate: 2025-01-05T09:30:00Z\n---"
+        );
+    }
+
+    #[test]
+    fn test_normalize_dates_with_format() {
+        let options = Options {
+            front_matter_normalize_dates: true,
+            front_matter_date_format: "%d.%m.%Y".to_string(),
+            ..Options::default()
+        };
+        assert_eq!(
+            format_front_matter("---\ndate: 2025-01-05\n---", &options),
+            "---\ndate: 05.01.2025\n---"
+        );
+    }
+
+    #[test]
+    fn test_normalize_dates_warnings() {
+        let content = "---\ntitle: Hello\ndate: next Tuesday\nupdated: 2025-02-30\n---";
+        let (output, warnings) = normalize(content);
+        assert_eq!(output, content);
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    3,
+                    "cannot parse \"next Tuesday\" in `date` as a date".to_string()
+                ),
+                (
+                    4,
+                    "cannot parse \"2025-02-30\" in `updated` as a date".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_order_not_a_mapping() {
+        let content = "---\n- title\n- date\n---";
+        assert_eq!(reorder(content, &["date"]), content);
+        let content = "---\njust text\ntitle: x\n---";
+        assert_eq!(reorder(content, &["title"]), content);
+    }
+}
@@ -1,16 +1,46 @@
 // SPDX-FileCopyrightText: 2025 Hong Minhee <https://hongminhee.org/>
 // SPDX-License-Identifier: GPL-3.0-or-later
-//! Heading sentence case conversion.
+//! Heading sentence case and title case conversion.
+
+use crate::TitleCase;
 
 // Include generated proper nouns constants
 include!(concat!(env!("OUT_DIR"), "/proper_nouns_generated.rs"));
 
+/// Marks the start of a part of heading text that is not prose, e.g., a link
+/// destination or inline HTML, which sentence case and title case leave as
+/// is.
+pub const VERBATIM_START: char = '\u{E002}';
+
+/// Marks the end of a part of heading text started with [`VERBATIM_START`].
+pub const VERBATIM_END: char = '\u{E003}';
+
+/// Remove the [`VERBATIM_START`] and [`VERBATIM_END`] markers from `text`.
+pub fn strip_verbatim_markers(text: &str) -> String {
+    text.replace([VERBATIM_START, VERBATIM_END], "")
+}
+
+/// Take the characters of a verbatim part from `chars` up to and including
+/// its [`VERBATIM_END`], and return them after the [`VERBATIM_START`].
+fn take_verbatim(chars: &mut impl Iterator<Item = char>) -> String {
+    let mut verbatim = String::from(VERBATIM_START);
+    for ch in chars {
+        verbatim.push(ch);
+        if ch == VERBATIM_END {
+            break;
+        }
+    }
+    verbatim
+}
+
 /// Convert heading text to sentence case.
 ///
 /// This function applies intelligent heuristics to convert heading text:
 /// - Capitalizes only the first word
-/// - Preserves code spans (backticks)
+/// - Preserves code spans (backticks) and parts between [`VERBATIM_START`]
+///   and [`VERBATIM_END`]
 /// - Preserves acronyms (2+ consecutive uppercase letters)
+/// - Preserves camelCase and PascalCase identifiers (e.g., `useState`)
 /// - Preserves proper nouns (built-in + user-configured, minus common_nouns)
 /// - Handles compound words (hyphenated)
 /// - Handles quoted text based on original capitalization
@@ -38,6 +68,9 @@ pub fn to_sentence_case(
                 result.push_str(&content);
                 is_first_word = false;
             }
+            Token::Verbatim(content) => {
+                result.push_str(&content);
+            }
             Token::Quote(content, is_double) => {
                 let processed =
                     process_quoted_text(&content, is_double, user_proper_nouns, common_nouns);
@@ -58,11 +91,228 @@ pub fn to_sentence_case(
     result
 }
 
+/// Words that AP style keeps lowercase in titles: articles, conjunctions,
+/// and prepositions of up to three letters.
+const AP_MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "nor", "of", "on", "or", "per", "so",
+    "the", "to", "via", "yet",
+];
+
+/// Words that Chicago style keeps lowercase in titles: articles, coordinating
+/// conjunctions, `to`, `as`, and prepositions regardless of their length.
+/// Words like `up` or `over` are left out, as they are more often adverbs
+/// in phrasal verbs, which are capitalized.
+const CHICAGO_MINOR_WORDS: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "across",
+    "after",
+    "against",
+    "along",
+    "among",
+    "an",
+    "and",
+    "around",
+    "as",
+    "at",
+    "before",
+    "behind",
+    "below",
+    "beneath",
+    "beside",
+    "between",
+    "beyond",
+    "but",
+    "by",
+    "despite",
+    "during",
+    "except",
+    "for",
+    "from",
+    "in",
+    "inside",
+    "into",
+    "like",
+    "near",
+    "nor",
+    "of",
+    "on",
+    "onto",
+    "or",
+    "outside",
+    "past",
+    "per",
+    "since",
+    "the",
+    "through",
+    "throughout",
+    "to",
+    "toward",
+    "towards",
+    "under",
+    "underneath",
+    "until",
+    "upon",
+    "via",
+    "with",
+    "within",
+    "without",
+];
+
+/// Convert heading text to title case in the given style.
+///
+/// - Capitalizes every word except the minor words of the style, which are
+///   lowercased unless they are the first or last word or follow a colon
+/// - Preserves code spans (backticks) and parts between [`VERBATIM_START`]
+///   and [`VERBATIM_END`]
+/// - Preserves words with capitals after their first letter, such as
+///   acronyms and `iOS`
+/// - Preserves proper nouns (built-in + user-configured, minus common_nouns)
+/// - Capitalizes every part of hyphenated compounds, except that Chicago
+///   style lowercases minor words after the first part
+pub fn to_title_case(
+    text: &str,
+    style: TitleCase,
+    user_proper_nouns: &[String],
+    common_nouns: &[String],
+) -> String {
+    if style == TitleCase::None {
+        return text.to_string();
+    }
+    let minor_words = match style {
+        TitleCase::None | TitleCase::Simple => &[][..],
+        TitleCase::Ap => AP_MINOR_WORDS,
+        TitleCase::Chicago => CHICAGO_MINOR_WORDS,
+    };
+
+    // Split into code spans, whitespace, and words; `true` marks a word
+    let mut pieces: Vec<(String, bool)> = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '`' {
+            let mut code_span = String::from('`');
+            for ch in chars.by_ref() {
+                code_span.push(ch);
+                if ch == '`' {
+                    break;
+                }
+            }
+            pieces.push((code_span, false));
+        } else if ch == VERBATIM_START {
+            pieces.push((take_verbatim(&mut chars), false));
+        } else if ch.is_whitespace() {
+            pieces.push((ch.to_string(), false));
+        } else {
+            let mut word = String::from(ch);
+            while let Some(&next) = chars.peek() {
+                if next == '`' || next == VERBATIM_START || next.is_whitespace() {
+                    break;
+                }
+                word.push(next);
+                chars.next();
+            }
+            pieces.push((word, true));
+        }
+    }
+
+    // Code spans and verbatim parts other than HTML tags, such as reference
+    // links, take the place of a word
+    let takes_word_place = |piece: &str| {
+        piece.starts_with('`')
+            || piece
+                .strip_prefix(VERBATIM_START)
+                .is_some_and(|rest| !rest.starts_with('<'))
+    };
+
+    // The last word is capitalized, unless the heading ends in a code span
+    let last = pieces
+        .iter()
+        .rposition(|(piece, is_word)| *is_word || takes_word_place(piece));
+    let mut result = String::new();
+    let mut capitalize_next = true;
+    for (index, (piece, is_word)) in pieces.iter().enumerate() {
+        if !is_word {
+            if takes_word_place(piece) {
+                capitalize_next = false;
+            }
+            result.push_str(piece);
+            continue;
+        }
+        let is_edge = capitalize_next || Some(index) == last;
+        let parts: Vec<&str> = piece.split('-').collect();
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                result.push('-');
+            }
+            let part_is_edge = if i == 0 {
+                is_edge || parts.len() > 1
+            } else {
+                style != TitleCase::Chicago
+            };
+            result.push_str(&title_case_word(
+                part,
+                minor_words,
+                part_is_edge,
+                user_proper_nouns,
+                common_nouns,
+            ));
+        }
+        capitalize_next = piece.ends_with([':', '\u{2014}', '?', '!']) || piece == "\u{2013}";
+    }
+    result
+}
+
+/// Convert a single word (without hyphens) to title case.
+fn title_case_word(
+    word: &str,
+    minor_words: &[&str],
+    is_edge: bool,
+    user_proper_nouns: &[String],
+    common_nouns: &[String],
+) -> String {
+    if let Some(pronoun) = is_first_person_pronoun_i(word) {
+        return pronoun;
+    }
+    // Acronyms and words like `iOS` or `JavaScript` are kept as they are
+    if word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .skip(1)
+        .any(char::is_uppercase)
+    {
+        return word.to_string();
+    }
+    if let Some(proper) = find_proper_noun(word, user_proper_nouns, common_nouns) {
+        return proper;
+    }
+    let core = word
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    if !is_edge && minor_words.contains(&core.as_str()) {
+        return word.to_lowercase();
+    }
+    // Capitalize the first letter, unless the word starts with a digit
+    match word.char_indices().find(|(_, c)| c.is_alphanumeric()) {
+        Some((i, c)) if c.is_alphabetic() => {
+            format!(
+                "{}{}{}",
+                &word[..i],
+                c.to_uppercase(),
+                &word[i + c.len_utf8()..]
+            )
+        }
+        _ => word.to_string(),
+    }
+}
+
 /// Token types for parsing heading text.
 #[derive(Debug, PartialEq)]
 enum Token {
     /// Code span with backticks (preserved as-is)
     CodeSpan(String),
+    /// Part between [`VERBATIM_START`] and [`VERBATIM_END`] (preserved as-is)
+    Verbatim(String),
     /// Quoted text (content, is_double_quote)
     Quote(String, bool),
     /// Regular text
@@ -210,6 +460,13 @@ fn tokenize_with_code_spans(text: &str) -> Vec<Token> {
                 }
             }
             tokens.push(Token::CodeSpan(code_span));
+        } else if ch == VERBATIM_START {
+            if !current.is_empty() {
+                let normalized = normalize_quotes(&current);
+                tokens.extend(tokenize_quotes(&normalized));
+                current.clear();
+            }
+            tokens.push(Token::Verbatim(take_verbatim(&mut chars)));
         } else {
             current.push(ch);
         }
@@ -325,7 +582,7 @@ fn process_quoted_text(
 
             for token in tokens {
                 match token {
-                    Token::CodeSpan(c) => result.push_str(&c),
+                    Token::CodeSpan(c) | Token::Verbatim(c) => result.push_str(&c),
                     Token::Quote(c, is_dbl) => {
                         let processed =
                             process_quoted_text(&c, is_dbl, user_proper_nouns, common_nouns);
@@ -383,7 +640,7 @@ fn collect_multiword_proper_nouns(
     }
 
     // Sort by length (longest first) to handle overlapping matches correctly
-    multiword_nouns.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    multiword_nouns.sort_by_key(|b| std::cmp::Reverse(b.0.len()));
 
     multiword_nouns
 }
@@ -722,11 +979,21 @@ fn process_word_simple(
         return word.to_string();
     }
 
-    // Check if it's an acronym (2+ consecutive uppercase letters at start)
+    // Check if it's an acronym (2+ consecutive uppercase letters)
     if is_acronym(word) {
         return word.to_string();
     }
 
+    // Check if it's a camelCase or PascalCase identifier (e.g., "useState",
+    // "iPhone", "MyClass"), unless it's listed as a common noun
+    if is_camel_case(word)
+        && !common_nouns
+            .iter()
+            .any(|noun| noun.to_lowercase() == word.to_lowercase())
+    {
+        return word.to_string();
+    }
+
     // Check if it's the English first-person pronoun "I" (always capitalized)
     if let Some(canonical) = is_first_person_pronoun_i(word) {
         return canonical;
@@ -747,18 +1014,18 @@ fn process_word_simple(
     }
 }
 
-/// Check if a word is an acronym (2+ consecutive uppercase letters at the start).
+/// Check if a word is an acronym (2+ consecutive uppercase letters anywhere,
+/// e.g., "API", "(HTTP)", or "gRPC").
 fn is_acronym(word: &str) -> bool {
     let chars: Vec<char> = word.chars().collect();
     if chars.len() < 2 {
         return false;
     }
 
-    // Check if first two characters are uppercase letters
-    if chars[0].is_uppercase()
-        && chars[0].is_alphabetic()
-        && chars[1].is_uppercase()
-        && chars[1].is_alphabetic()
+    // Check for two consecutive uppercase letters
+    if chars
+        .windows(2)
+        .any(|pair| pair[0].is_uppercase() && pair[1].is_uppercase())
     {
         return true;
     }
@@ -796,6 +1063,15 @@ fn is_acronym(word: &str) -> bool {
     has_period && letters.len() >= 2 && uppercase_count >= 1
 }
 
+/// Check if a word looks like a camelCase or PascalCase identifier, i.e.,
+/// has a lowercase letter directly followed by an uppercase letter.
+fn is_camel_case(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    chars
+        .windows(2)
+        .any(|pair| pair[0].is_lowercase() && pair[1].is_uppercase())
+}
+
 /// Find a proper noun match (case-insensitive search).
 /// Returns None if the word is in the common_nouns list.
 /// Handles words with leading punctuation (e.g., "(France" matches "France").
@@ -1147,6 +1423,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_preserve_acronyms_inside_words() {
+        assert_eq!(
+            to_sentence_case("Calling gRPC Services", &[], &[]),
+            "Calling gRPC services"
+        );
+        assert_eq!(
+            to_sentence_case("Serving Pages (HTTP Only)", &[], &[]),
+            "Serving pages (HTTP only)"
+        );
+    }
+
+    #[test]
+    fn test_preserve_camel_case_identifiers() {
+        assert_eq!(
+            to_sentence_case("Using useState With MyComponent", &[], &[]),
+            "Using useState with MyComponent"
+        );
+        assert_eq!(
+            to_sentence_case("getElementById Returns Null", &[], &[]),
+            "getElementById returns null"
+        );
+        assert_eq!(
+            to_sentence_case("The fooBar-Based Approach", &[], &[]),
+            "The fooBar-based approach"
+        );
+    }
+
+    #[test]
+    fn test_camel_case_common_noun_is_lowercased() {
+        assert_eq!(
+            to_sentence_case("Using MyWidget Here", &[], &["MyWidget".to_string()]),
+            "Using mywidget here"
+        );
+    }
+
     #[test]
     fn test_mixed_case_not_acronym() {
         // "Api" is not an acronym (only first letter uppercase)
@@ -1355,10 +1667,11 @@ mod tests {
 
     #[test]
     fn test_multiword_proper_noun_partial_match_not_replaced() {
-        // Partial match should not be replaced (word boundaries)
+        // Partial match should not be replaced (word boundaries); the
+        // PascalCase word itself is kept as an identifier
         assert_eq!(
             to_sentence_case("MyGitHub Actions Service", &[], &[]),
-            "Mygithub actions service"
+            "MyGitHub actions service"
         );
     }
 
@@ -1712,4 +2025,95 @@ mod tests {
             "I think therefore I am"
         );
     }
+
+    #[test]
+    fn test_title_case_none() {
+        assert_eq!(
+            to_title_case("a guide to the galaxy", TitleCase::None, &[], &[]),
+            "a guide to the galaxy"
+        );
+    }
+
+    #[test]
+    fn test_title_case_simple() {
+        assert_eq!(
+            to_title_case("a guide to the galaxy", TitleCase::Simple, &[], &[]),
+            "A Guide To The Galaxy"
+        );
+        assert_eq!(
+            to_title_case("step-by-step setup", TitleCase::Simple, &[], &[]),
+            "Step-By-Step Setup"
+        );
+    }
+
+    #[test]
+    fn test_title_case_ap() {
+        assert_eq!(
+            to_title_case("a guide to the galaxy", TitleCase::Ap, &[], &[]),
+            "A Guide to the Galaxy"
+        );
+        // AP capitalizes prepositions of four or more letters
+        assert_eq!(
+            to_title_case("life without a map", TitleCase::Ap, &[], &[]),
+            "Life Without a Map"
+        );
+        assert_eq!(
+            to_title_case("step-by-step setup", TitleCase::Ap, &[], &[]),
+            "Step-By-Step Setup"
+        );
+    }
+
+    #[test]
+    fn test_title_case_chicago() {
+        assert_eq!(
+            to_title_case("a guide to the galaxy", TitleCase::Chicago, &[], &[]),
+            "A Guide to the Galaxy"
+        );
+        // Chicago lowercases prepositions regardless of their length
+        assert_eq!(
+            to_title_case("life without a map", TitleCase::Chicago, &[], &[]),
+            "Life without a Map"
+        );
+        assert_eq!(
+            to_title_case("step-by-step setup", TitleCase::Chicago, &[], &[]),
+            "Step-by-Step Setup"
+        );
+    }
+
+    #[test]
+    fn test_title_case_first_and_last_words() {
+        assert_eq!(
+            to_title_case("the things we believe in", TitleCase::Chicago, &[], &[]),
+            "The Things We Believe In"
+        );
+        assert_eq!(
+            to_title_case("hongdown: a guide for the curious", TitleCase::Ap, &[], &[]),
+            "Hongdown: A Guide for the Curious"
+        );
+    }
+
+    #[test]
+    fn test_title_case_preserves_words() {
+        assert_eq!(
+            to_title_case("using the API on iOS with github", TitleCase::Ap, &[], &[]),
+            "Using the API on iOS With GitHub"
+        );
+        assert_eq!(
+            to_title_case("calling `to_string` in rust", TitleCase::Ap, &[], &[]),
+            "Calling `to_string` in Rust"
+        );
+        assert_eq!(
+            to_title_case("what i learned in 2nd year", TitleCase::Simple, &[], &[]),
+            "What I Learned In 2nd Year"
+        );
+        assert_eq!(
+            to_title_case(
+                "working with hongdown",
+                TitleCase::Ap,
+                &["Hongdown".to_string()],
+                &[]
+            ),
+            "Working With Hongdown"
+        );
+    }
 }
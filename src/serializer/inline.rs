@@ -4,10 +4,123 @@ use comrak::nodes::{AstNode, NodeValue};
 
 use super::Serializer;
 use super::escape;
+use super::heading;
+use super::output::ContainerKind;
 use super::punctuation;
+use super::state::{FormatRule, TraceKind};
+use super::table;
+use super::wrap;
+use crate::{AutolinkHost, BrStyle, HeadingLinks, InlineKind, MentionStyle};
+
+/// Marks the start of an inline span that is left as-is.
+const SPAN_OFF_MARKER: &str = "<!-- hongdown-off -->";
+
+/// Marks the end of an inline span that is left as-is.
+const SPAN_ON_MARKER: &str = "<!-- hongdown-on -->";
+
+/// Replace spaces with [`wrap::PROTECTED_SPACE`] so that the wrapper keeps
+/// the text on a single line.
+fn protect_spaces(text: &str) -> String {
+    text.replace(' ', &wrap::PROTECTED_SPACE.to_string())
+}
+
+/// Returns the length of the attribute block, e.g., `{.class #id}`, that
+/// `text` starts with, if any.
+pub(super) fn attribute_block_len(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('{')?;
+    let end = rest.find(['{', '}', '\n'])?;
+    (end > 0 && rest[end..].starts_with('}')).then_some(end + 2)
+}
+
+/// Returns the length of the Obsidian wikilink, e.g., `[[note|alias]]`, or
+/// embed, e.g., `![[note#section]]`, that `text` starts with, if any.
+fn wikilink_len(text: &str) -> Option<usize> {
+    let prefix = if text.starts_with('!') { 3 } else { 2 };
+    let rest = text
+        .get(..prefix)?
+        .ends_with("[[")
+        .then(|| &text[prefix..])?;
+    let end = rest.find(['[', ']', '\n'])?;
+    (end > 0 && rest[end..].starts_with("]]")).then_some(prefix + end + 2)
+}
+
+/// Split `text` into runs, each paired with whether it is kept verbatim:
+/// an attribute block in the markdown-it and Pandoc syntax, or, if
+/// `wikilinks` is true, an Obsidian wikilink or embed.  Attribute blocks
+/// follow a bracketed span, e.g., `[text]{.class}`, or, if `follows_link` is
+/// true, may start `text`, as they belong to the link or image before it.
+fn split_verbatim_runs(text: &str, follows_link: bool, wikilinks: bool) -> Vec<(&str, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let bytes = text.as_bytes();
+        let is_candidate = bytes[i] == b'{'
+            && if i == 0 {
+                follows_link
+            } else {
+                bytes[i - 1] == b']'
+            };
+        let len = if wikilinks && matches!(bytes[i], b'!' | b'[') {
+            wikilink_len(&text[i..])
+        } else {
+            is_candidate
+                .then(|| attribute_block_len(&text[i..]))
+                .flatten()
+        };
+        if let Some(len) = len {
+            if start < i {
+                runs.push((&text[start..i], false));
+            }
+            runs.push((&text[i..i + len], true));
+            i += len;
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    if start < text.len() || runs.is_empty() {
+        runs.push((&text[start..], false));
+    }
+    runs
+}
+
+/// Write `part` of heading text that is not prose to `text`, between
+/// [`heading::VERBATIM_START`] and [`heading::VERBATIM_END`].
+fn push_verbatim(text: &mut String, part: &str) {
+    text.push(heading::VERBATIM_START);
+    text.push_str(part);
+    text.push(heading::VERBATIM_END);
+}
+
+/// Write `link`, a link or image whose text is `link_text`, to `text`, with
+/// everything but its text, e.g., its destination or label, marked as
+/// verbatim with [`push_verbatim`].
+fn push_marked_link(text: &mut String, link: &str, link_text: &str) {
+    let opening = if link.starts_with('!') { "![" } else { "[" };
+    match link
+        .strip_prefix(opening)
+        .and_then(|rest| rest.strip_prefix(link_text))
+    {
+        Some(rest) => {
+            text.push_str(opening);
+            text.push_str(link_text);
+            push_verbatim(text, rest);
+        }
+        None => push_verbatim(text, link),
+    }
+}
 
 impl<'a> Serializer<'a> {
     pub(super) fn collect_text<'b>(&mut self, node: &'b AstNode<'b>) -> String {
+        heading::strip_verbatim_markers(&self.collect_heading_text(node))
+    }
+
+    /// Collect the text of a heading like [`collect_text`](Self::collect_text),
+    /// but with the parts that are not prose, such as link destinations and
+    /// inline HTML, between [`heading::VERBATIM_START`] and
+    /// [`heading::VERBATIM_END`], so that case conversion leaves them alone.
+    pub(super) fn collect_heading_text<'b>(&mut self, node: &'b AstNode<'b>) -> String {
         let mut text = String::new();
         self.collect_text_recursive(node, &mut text);
         text
@@ -39,15 +152,27 @@ impl<'a> Serializer<'a> {
     fn collect_text_recursive<'b>(&mut self, node: &'b AstNode<'b>, text: &mut String) {
         match &node.data.borrow().value {
             NodeValue::Text(t) => {
-                // Apply punctuation transformation first
-                let transformed = punctuation::transform_punctuation(t, self.options);
+                // Apply Unicode normalization and punctuation transformation first
+                let t = self.options.unicode_normalization.apply(t);
+                let transformed = self.transform_punctuation(&t);
 
                 // Try to preserve escapes from the original source
-                if let Some(source) = self.extract_source(node) {
-                    text.push_str(&Self::escape_text_preserving_source(&transformed, &source));
+                let escaped = if let Some(source) = self.extract_source(node) {
+                    let source = self.options.unicode_normalization.apply(&source);
+                    Self::escape_text_preserving_source(
+                        &transformed,
+                        &source,
+                        self.options.escape_underscores,
+                        self.options.autolink_host,
+                    )
                 } else {
-                    text.push_str(&escape::escape_text(&transformed));
-                }
+                    escape::escape_text(
+                        &transformed,
+                        self.options.escape_underscores,
+                        self.options.autolink_host,
+                    )
+                };
+                text.push_str(&self.expand_tabs(node, escaped));
             }
             NodeValue::Code(code) => {
                 // Try to use original source to preserve spacing, but validate it first.
@@ -85,31 +210,75 @@ impl<'a> Serializer<'a> {
                 text.push(' ');
             }
             NodeValue::Link(link) => {
-                // Handle reference-style links in headings
-                if let Some((link_text, label)) = self.get_reference_style_info(node) {
-                    self.format_reference_link(text, &link_text, &label, &link.url, &link.title);
+                // Links only occur here inside headings, since link text
+                // cannot contain links
+                let is_autolink = link.title.is_empty() && self.collect_raw_text(node) == link.url;
+                let mut rendered = String::new();
+                if is_autolink {
+                    Self::format_autolink(&mut rendered, &link.url);
+                    push_verbatim(text, &rendered);
+                } else if self.options.heading_links == HeadingLinks::Text {
+                    for child in node.children() {
+                        self.collect_text_recursive(child, text);
+                    }
+                } else if let Some((link_text, label)) = self.get_reference_style_info(node) {
+                    // The text of a reference link can be its label too, so
+                    // it is kept as it is
+                    self.format_reference_link(
+                        &mut rendered,
+                        &link_text,
+                        &label,
+                        &link.url,
+                        &link.title,
+                    );
+                    push_verbatim(text, &rendered);
                 } else {
-                    // For inline links, just output plain text (or format as inline?)
-                    // In headings, we typically want reference style for external links
-                    let link_text = self.collect_raw_text(node);
-                    if Self::is_external_url(&link.url) {
+                    let mut link_text = String::new();
+                    for child in node.children() {
+                        self.collect_text_recursive(child, &mut link_text);
+                    }
+                    // Links whose text has images or inline HTML are kept
+                    // inline, since their text cannot be a reference label
+                    if self.options.heading_links == HeadingLinks::Reference
+                        && !link_text.contains(heading::VERBATIM_START)
+                        && self.converts_to_reference(&link.url)
+                    {
                         // Headings don't have footnote references as siblings, so no need for collapsed style
                         self.format_external_link_as_reference(
-                            text,
+                            &mut rendered,
                             &link_text,
                             &link.url,
                             &link.title,
                             false,
                         );
+                        push_verbatim(text, &rendered);
                     } else {
-                        Self::format_inline_link(text, &link_text, &link.url, &link.title);
+                        Self::format_inline_link(
+                            &mut rendered,
+                            &link_text,
+                            &link.url,
+                            &link.title,
+                            self.options.link_title_style,
+                        );
+                        push_marked_link(text, &rendered, &link_text);
                     }
                 }
             }
             NodeValue::Image(image) => {
                 // Preserve images in headings using inline syntax
                 let alt_text = self.collect_raw_text(node);
-                Self::format_inline_image(text, &alt_text, &image.url, &image.title);
+                let mut rendered = String::new();
+                Self::format_inline_image(
+                    &mut rendered,
+                    &alt_text,
+                    &image.url,
+                    &image.title,
+                    self.options.link_title_style,
+                );
+                push_marked_link(text, &rendered, &alt_text);
+            }
+            NodeValue::HtmlInline(html) => {
+                push_verbatim(text, html);
             }
             _ => {
                 for child in node.children() {
@@ -124,27 +293,170 @@ impl<'a> Serializer<'a> {
         node: &'b AstNode<'b>,
         content: &mut String,
     ) {
-        for child in node.children() {
+        let mut children = node.children();
+        while let Some(child) = children.next() {
+            if let Some(end) = Self::find_protected_span_end(child)
+                && let Some(span) = self.extract_protected_span(child, end)
+            {
+                // A line starting with `<!--` would begin an HTML block, so
+                // keep the marker on the same line as the preceding word
+                if content.ends_with([' ', '\x00']) {
+                    content.pop();
+                    content.push(wrap::PROTECTED_SPACE);
+                }
+                content.push_str(&protect_spaces(SPAN_OFF_MARKER));
+                content.push_str(&span);
+                content.push_str(&protect_spaces(SPAN_ON_MARKER));
+                // Skip the nodes inside the span, including the end marker
+                for skipped in children.by_ref() {
+                    if std::ptr::eq(skipped, end) {
+                        break;
+                    }
+                }
+                continue;
+            }
             self.collect_inline_node(child, content);
         }
     }
 
+    /// If `node` is a `<!-- hongdown-off -->` marker, find the matching
+    /// `<!-- hongdown-on -->` marker among its following siblings.
+    fn find_protected_span_end<'b>(node: &'b AstNode<'b>) -> Option<&'b AstNode<'b>> {
+        let is_marker = |node: &'b AstNode<'b>, marker: &str| matches!(&node.data.borrow().value, NodeValue::HtmlInline(html) if html == marker);
+        if !is_marker(node, SPAN_OFF_MARKER) {
+            return None;
+        }
+        node.following_siblings()
+            .skip(1)
+            .find(|sibling| is_marker(sibling, SPAN_ON_MARKER))
+    }
+
+    /// Extract the original source between a `<!-- hongdown-off -->` marker
+    /// and its `<!-- hongdown-on -->` marker, protecting its spaces from
+    /// wrapping.  Line breaks inside the span are collapsed into spaces.
+    ///
+    /// Returns `None` if the original source is not available.
+    fn extract_protected_span<'b>(
+        &self,
+        start: &'b AstNode<'b>,
+        end: &'b AstNode<'b>,
+    ) -> Option<String> {
+        let from = start.data.borrow().sourcepos.end;
+        let to = end.data.borrow().sourcepos.start;
+        if from.line == 0 || to.line < from.line {
+            return None;
+        }
+        let mut raw = String::new();
+        for line_no in from.line..=to.line {
+            let line = *self.source_lines.get(line_no - 1)?;
+            // Columns are 1-indexed byte offsets; `from` is the last byte of
+            // the start marker and `to` is the first byte of the end marker.
+            let begin = if line_no == from.line { from.column } else { 0 };
+            let finish = if line_no == to.line {
+                to.column.checked_sub(1)?
+            } else {
+                line.len()
+            };
+            let segment = line.get(begin..finish)?;
+            if line_no == from.line {
+                raw.push_str(segment);
+            } else {
+                // Drop the indentation and blockquote markers of continuation lines
+                let segment = if self.output.is_inside(ContainerKind::BlockQuote) {
+                    segment.trim_start_matches(|c: char| c == '>' || c.is_whitespace())
+                } else {
+                    segment.trim_start()
+                };
+                raw.push(' ');
+                raw.push_str(segment);
+            }
+        }
+        Some(protect_spaces(&raw))
+    }
+
     pub(super) fn collect_inline_node<'b>(&mut self, node: &'b AstNode<'b>, content: &mut String) {
+        if let Some(rendered) = self.render_custom_inline(node) {
+            content.push_str(&rendered);
+            return;
+        }
         match &node.data.borrow().value {
             NodeValue::Text(text) => {
-                // Apply punctuation transformation first
-                let transformed = punctuation::transform_punctuation(text, self.options);
-
-                // Try to preserve escapes from the original source
-                if let Some(source) = self.extract_source(node) {
-                    content.push_str(&Self::escape_text_preserving_source(&transformed, &source));
+                let follows_link = node.previous_sibling().is_some_and(|sibling| {
+                    matches!(
+                        sibling.data.borrow().value,
+                        NodeValue::Link(_) | NodeValue::Image(_)
+                    )
+                });
+                // Normalize the source as well so that the two still match
+                let normalization = self.options.unicode_normalization;
+                let text = normalization.apply(text);
+                let wikilinks = self.options.extensions.wikilinks;
+                let runs = split_verbatim_runs(&text, follows_link, wikilinks);
+                // Pair each run with its source, as long as the source has
+                // the same attribute blocks
+                let source = self
+                    .extract_source(node)
+                    .map(|source| normalization.apply(&source).into_owned());
+                let source_runs = source
+                    .as_deref()
+                    .map(|source| split_verbatim_runs(source, follows_link, wikilinks))
+                    .filter(|source_runs| {
+                        source_runs.len() == runs.len()
+                            && source_runs.iter().zip(&runs).all(|(a, b)| a.1 == b.1)
+                    });
+                // Mentions in link text are part of the link, not prose
+                let mentions = if node.ancestors().any(|ancestor| {
+                    matches!(
+                        ancestor.data.borrow().value,
+                        NodeValue::Link(_) | NodeValue::Image(_)
+                    )
+                }) {
+                    MentionStyle::Keep
                 } else {
-                    content.push_str(&escape::escape_text(&transformed));
+                    self.options.mentions
+                };
+                let mut node_content = String::new();
+                for (i, &(run, is_verbatim)) in runs.iter().enumerate() {
+                    if is_verbatim {
+                        // Attribute blocks and wikilinks are kept verbatim
+                        // and on one line; wikilinks even keep their escapes
+                        let run = match &source_runs {
+                            Some(source_runs) if wikilink_len(run).is_some() => source_runs[i].0,
+                            _ => run,
+                        };
+                        node_content.push_str(&protect_spaces(run));
+                        continue;
+                    }
+                    // Apply punctuation transformation first
+                    let transformed = self.transform_punctuation(run);
+
+                    // Try to preserve escapes from the original source
+                    let escaped = if let Some(source_runs) = &source_runs {
+                        Self::escape_text_preserving_source(
+                            &transformed,
+                            source_runs[i].0,
+                            self.options.escape_underscores,
+                            self.options.autolink_host,
+                        )
+                    } else {
+                        escape::escape_text(
+                            &transformed,
+                            self.options.escape_underscores,
+                            self.options.autolink_host,
+                        )
+                    };
+                    // Text that looks like HTML was escaped in the source, but
+                    // the escape is lost when the source positions are off,
+                    // e.g., in table cells
+                    let escaped =
+                        escape::style_mentions(&escape::escape_html_starts(&escaped), mentions);
+                    node_content.push_str(&escaped);
                 }
+                content.push_str(&self.expand_tabs(node, node_content));
             }
             NodeValue::SoftBreak => {
                 // Use a special marker to preserve original line breaks
-                // This will be processed by wrap_text to decide whether to keep them
+                // This will be processed by the wrapper to decide whether to keep them
                 content.push('\x00');
             }
             NodeValue::LineBreak => {
@@ -203,7 +515,7 @@ impl<'a> Serializer<'a> {
                             self.collect_inline_node(child, content);
                         }
                         content.push_str("][");
-                        content.push_str(actual_label);
+                        content.push_str(&self.canonical_label(actual_label));
                         content.push(']');
 
                         self.add_reference(
@@ -223,16 +535,14 @@ impl<'a> Serializer<'a> {
                         self.collect_inline_node(child, content);
                     }
                     content.push_str("](");
-                    content.push_str(&link.url);
-                    if !link.title.is_empty() {
-                        content.push_str(" \"");
-                        content.push_str(&link.title);
-                        content.push('"');
-                    }
+                    Self::push_destination(content, &link.url);
+                    Self::push_title(content, &link.title, self.options.link_title_style);
                     content.push(')');
                 } else if is_autolink {
                     Self::format_autolink(content, &link.url);
-                } else if Self::is_external_url(&link.url) {
+                } else if self.converts_to_reference(&link.url)
+                    && !Self::next_sibling_is_attribute_block(node)
+                {
                     // External URL: collect link text first
                     let mut link_text = String::new();
                     for child in node.children() {
@@ -253,7 +563,13 @@ impl<'a> Serializer<'a> {
                     for child in node.children() {
                         self.collect_inline_node(child, &mut link_text);
                     }
-                    Self::format_inline_link(content, &link_text, &link.url, &link.title);
+                    Self::format_inline_link(
+                        content,
+                        &link_text,
+                        &link.url,
+                        &link.title,
+                        self.options.link_title_style,
+                    );
                 }
             }
             NodeValue::Image(image) => {
@@ -266,9 +582,29 @@ impl<'a> Serializer<'a> {
                     for child in node.children() {
                         self.collect_inline_node(child, &mut alt_text);
                     }
-                    Self::format_inline_image(content, &alt_text, &image.url, &image.title);
+                    Self::format_inline_image(
+                        content,
+                        &alt_text,
+                        &image.url,
+                        &image.title,
+                        self.options.link_title_style,
+                    );
                 }
             }
+            NodeValue::HtmlInline(html)
+                if self.in_table_cell
+                    && !self.options.table_escape_html
+                    && table::is_br_tag(html) =>
+            {
+                // Mark the line break so that table column widths can be
+                // computed per line
+                let tag = match self.options.table_br_style {
+                    BrStyle::Preserve => html.as_str(),
+                    style => style.as_str(),
+                };
+                content.push_str(tag);
+                content.push(table::LINE_BREAK_MARKER);
+            }
             NodeValue::HtmlInline(html) => {
                 // Preserve inline HTML as-is
                 content.push_str(html);
@@ -286,6 +622,66 @@ impl<'a> Serializer<'a> {
         }
     }
 
+    /// Apply punctuation transformations unless disabled by a
+    /// `hongdown-disable punctuation` directive.  Tags written as literal
+    /// text, e.g., `\<img src="x.png">`, are left alone, since they are
+    /// what inline HTML in table cells becomes if
+    /// [`Options::table_escape_html`](crate::Options::table_escape_html) is
+    /// set.
+    fn transform_punctuation(&mut self, text: &str) -> String {
+        if self.disabled_rules.contains(FormatRule::Punctuation) {
+            return text.to_string();
+        }
+        let mut transformed = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(tag) = escape::find_html_tag(rest) {
+            transformed.push_str(&punctuation::transform_punctuation(
+                &rest[..tag.start],
+                self.options,
+            ));
+            transformed.push_str(&rest[tag.clone()]);
+            rest = &rest[tag.end..];
+        }
+        transformed.push_str(&punctuation::transform_punctuation(rest, self.options));
+        if transformed != text {
+            self.trace(TraceKind::Punctuation, || {
+                format!("{:?} became {:?}", text, transformed)
+            });
+        }
+        transformed
+    }
+
+    /// Render an inline node with a custom renderer registered in
+    /// [`Options::inline_renderers`](crate::Options::inline_renderers), if any.
+    pub(super) fn render_custom_inline<'b>(&self, node: &'b AstNode<'b>) -> Option<String> {
+        if self.options.inline_renderers.is_empty() {
+            return None;
+        }
+        let kind = InlineKind::of(&node.data.borrow().value)?;
+        self.options.inline_renderers.get(&kind)?.render(node)
+    }
+
+    /// Expand the tabs in `text`, written for the text node `node`, to
+    /// spaces if [`Options::expand_tabs`](crate::Options::expand_tabs) is
+    /// set.  Tab stops are counted from the start of the node's line in the
+    /// source, so that the text lines up as it did there.
+    fn expand_tabs<'b>(&self, node: &'b AstNode<'b>, text: String) -> String {
+        if !self.options.expand_tabs || !text.contains('\t') {
+            return text;
+        }
+        let tab_width = self.options.tab_width.get();
+        let start = node.data.borrow().sourcepos.start;
+        let column = start
+            .line
+            .checked_sub(1)
+            .and_then(|index| self.source_lines.get(index))
+            .and_then(|line| line.get(..start.column.checked_sub(1)?))
+            .map_or(0, |prefix| {
+                wrap::display_width(&wrap::expand_tabs(prefix, 0, tab_width))
+            });
+        wrap::expand_tabs(&text, column, tab_width)
+    }
+
     /// Escape text while preserving escapes from the original source.
     ///
     /// When comrak parses text like `node\_modules`, it stores `node_modules` in the AST.
@@ -293,10 +689,33 @@ impl<'a> Serializer<'a> {
     /// characters were escaped, and preserves those escapes in the output.
     ///
     /// Also preserves HTML entities (e.g., `&lt;`, `&amp;`, `&#60;`) from the source.
-    fn escape_text_preserving_source(text: &str, source: &str) -> String {
+    ///
+    /// Unless `escape_underscores` is set, underscores in the middle of words
+    /// are written without escapes, even if they were escaped in the source.
+    fn escape_text_preserving_source(
+        text: &str,
+        source: &str,
+        escape_underscores: bool,
+        host: AutolinkHost,
+    ) -> String {
         let mut result = String::with_capacity(source.len());
         let text_chars: Vec<char> = text.chars().collect();
         let source_chars: Vec<char> = source.chars().collect();
+        let is_bare_underscore = |index: usize| {
+            text_chars[index] == '_'
+                && escape::is_intraword_underscore(&text_chars, index)
+                && (!escape_underscores
+                    || escape::is_in_repository_reference(&text_chars, index, host))
+        };
+        // Escape a single character, which loses the context that tells
+        // whether an underscore is in the middle of a word
+        let escape_char = |index: usize| {
+            if is_bare_underscore(index) {
+                "_".to_string()
+            } else {
+                escape::escape_text(&text_chars[index].to_string(), true, host)
+            }
+        };
 
         let mut text_idx = 0;
         let mut source_idx = 0;
@@ -311,13 +730,15 @@ impl<'a> Serializer<'a> {
                 if escaped_char == text_char {
                     // The escape in source corresponds to this character in text
                     // Preserve the escape
-                    result.push('\\');
+                    if !is_bare_underscore(text_idx) {
+                        result.push('\\');
+                    }
                     result.push(escaped_char);
                     text_idx += 1;
                     source_idx += 2;
                 } else {
                     // Escape doesn't match - use normal escaping
-                    result.push_str(&escape::escape_text(&text_char.to_string()));
+                    result.push_str(&escape_char(text_idx));
                     text_idx += 1;
                     // Don't advance source_idx - the escape might be for something else
                 }
@@ -333,12 +754,12 @@ impl<'a> Serializer<'a> {
                         source_idx += entity.len();
                     } else {
                         // Entity doesn't match the text character - use normal escaping
-                        result.push_str(&escape::escape_text(&text_char.to_string()));
+                        result.push_str(&escape_char(text_idx));
                         text_idx += 1;
                     }
                 } else if source_char == text_char {
                     // Not an entity, just a regular '&'
-                    result.push_str(&escape::escape_text(&text_char.to_string()));
+                    result.push_str(&escape_char(text_idx));
                     text_idx += 1;
                     source_idx += 1;
                 } else {
@@ -347,7 +768,7 @@ impl<'a> Serializer<'a> {
                 }
             } else if source_char == text_char {
                 // Characters match - apply normal escaping rules
-                result.push_str(&escape::escape_text(&text_char.to_string()));
+                result.push_str(&escape_char(text_idx));
                 text_idx += 1;
                 source_idx += 1;
             } else {
@@ -358,8 +779,8 @@ impl<'a> Serializer<'a> {
         }
 
         // Handle any remaining text characters that weren't matched
-        for ch in text_chars.iter().skip(text_idx) {
-            result.push_str(&escape::escape_text(&ch.to_string()));
+        for index in text_idx..text_chars.len() {
+            result.push_str(&escape_char(index));
         }
 
         result
@@ -3,6 +3,9 @@
 use comrak::nodes::{AstNode, NodeValue};
 
 use super::Serializer;
+use super::inline::attribute_block_len;
+use super::state::TraceKind;
+use crate::{LinkReferenceStyle, LinkTitleStyle};
 
 impl<'a> Serializer<'a> {
     /// Format a reference-style link and write to output buffer.
@@ -23,10 +26,14 @@ impl<'a> Serializer<'a> {
 
             self.add_reference(actual_label.to_string(), url.to_string(), title.to_string());
         } else if text == label {
-            // Shortcut reference: [text]
+            // Shortcut reference: [text], or [text][] if collapsed ones are
+            // preferred
             output.push('[');
             output.push_str(text);
             output.push(']');
+            if self.options.link_reference_style == LinkReferenceStyle::Collapsed {
+                output.push_str("[]");
+            }
 
             self.add_reference(label.to_string(), url.to_string(), title.to_string());
         } else {
@@ -34,24 +41,77 @@ impl<'a> Serializer<'a> {
             output.push('[');
             output.push_str(text);
             output.push_str("][");
-            output.push_str(label);
+            output.push_str(&self.canonical_label(label));
             output.push(']');
 
             self.add_reference(label.to_string(), url.to_string(), title.to_string());
         }
     }
 
+    /// Write the title of a link or image, if any, after a space, quoted as
+    /// `style` asks for.
+    pub(super) fn push_title(output: &mut String, title: &str, style: LinkTitleStyle) {
+        if !title.is_empty() {
+            output.push(' ');
+            output.push_str(&style.quote(title));
+        }
+    }
+
+    /// Write a link destination, wrapping it in angle brackets if it would
+    /// not be read back as is otherwise, e.g., if it contains spaces or
+    /// unbalanced parentheses.
+    pub(super) fn push_destination(output: &mut String, url: &str) {
+        if !Self::needs_angle_brackets(url) {
+            output.push_str(url);
+            return;
+        }
+        output.push('<');
+        for ch in url.chars() {
+            if matches!(ch, '<' | '>') {
+                output.push('\\');
+            }
+            output.push(ch);
+        }
+        output.push('>');
+    }
+
+    /// Check if a link destination has to be written in angle brackets: it
+    /// contains spaces or control characters, its parentheses are unbalanced,
+    /// or it starts with `<` itself.
+    fn needs_angle_brackets(url: &str) -> bool {
+        if url.starts_with('<') {
+            return true;
+        }
+        let mut depth = 0usize;
+        let mut escaped = false;
+        for ch in url.chars() {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                ' ' => return true,
+                _ if ch.is_ascii_control() => return true,
+                '(' => depth += 1,
+                ')' if depth == 0 => return true,
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+        depth > 0
+    }
+
     /// Format an inline-style link and write to output buffer.
-    pub(super) fn format_inline_link(output: &mut String, text: &str, url: &str, title: &str) {
+    pub(super) fn format_inline_link(
+        output: &mut String,
+        text: &str,
+        url: &str,
+        title: &str,
+        title_style: LinkTitleStyle,
+    ) {
         output.push('[');
         output.push_str(text);
         output.push_str("](");
-        output.push_str(url);
-        if !title.is_empty() {
-            output.push_str(" \"");
-            output.push_str(title);
-            output.push('"');
-        }
+        Self::push_destination(output, url);
+        Self::push_title(output, title, title_style);
         output.push(')');
     }
 
@@ -67,6 +127,10 @@ impl<'a> Serializer<'a> {
     /// If `use_collapsed` is true, outputs `[text][]` (collapsed reference) instead of
     /// `[text]` (shortcut reference). This is needed to disambiguate when the link is
     /// immediately followed by something that starts with `[`, like a footnote reference.
+    /// Collapsed references are always used if [`LinkReferenceStyle::Collapsed`] is
+    /// configured.
+    /// If the text is already the label of another destination, the link is kept
+    /// inline instead.
     pub(super) fn format_external_link_as_reference(
         &mut self,
         output: &mut String,
@@ -77,14 +141,40 @@ impl<'a> Serializer<'a> {
     ) {
         // Normalize: replace SoftBreak markers with spaces for shortcut refs
         let normalized_text = text.replace('\x00', " ");
+        // A link whose text is already the label of another destination is
+        // kept inline, since its reference would point elsewhere
+        if self.is_label_taken(&normalized_text, url) {
+            Self::format_inline_link(output, text, url, title, self.options.link_title_style);
+            return;
+        }
+        self.trace(TraceKind::LinkToReference, || {
+            format!(
+                "link to {} converted to reference [{}]",
+                url, normalized_text
+            )
+        });
         output.push('[');
         output.push_str(&normalized_text);
         output.push(']');
-        if use_collapsed {
-            output.push_str("[]");
-        }
+        // Numeric text is renumbered like the numeric labels of references
+        let label = match self.reference_numbers.get(&normalized_text) {
+            Some(number) if *number != normalized_text => {
+                output.push('[');
+                output.push_str(number);
+                output.push(']');
+                number.clone()
+            }
+            _ => {
+                if use_collapsed
+                    || self.options.link_reference_style == LinkReferenceStyle::Collapsed
+                {
+                    output.push_str("[]");
+                }
+                normalized_text
+            }
+        };
 
-        self.add_reference(normalized_text, url.to_string(), title.to_string());
+        self.add_reference(label, url.to_string(), title.to_string());
     }
 
     /// Check if the next sibling of a node starts with `[`.
@@ -101,6 +191,15 @@ impl<'a> Serializer<'a> {
         }
     }
 
+    /// Check if the next sibling of a node starts with an attribute block,
+    /// e.g., `{target="_blank"}`.  Such a link is kept inline, since the
+    /// attributes belong to its inline syntax.
+    pub(super) fn next_sibling_is_attribute_block<'b>(node: &'b AstNode<'b>) -> bool {
+        node.next_sibling().is_some_and(|next| {
+            matches!(&next.data.borrow().value, NodeValue::Text(text) if attribute_block_len(text).is_some())
+        })
+    }
+
     /// Format a reference-style image and write to output buffer.
     pub(super) fn format_reference_image(
         &mut self,
@@ -119,10 +218,14 @@ impl<'a> Serializer<'a> {
 
             self.add_reference(actual_label.to_string(), url.to_string(), title.to_string());
         } else if text == label {
-            // Shortcut reference: ![alt]
+            // Shortcut reference: ![alt], or ![alt][] if collapsed ones are
+            // preferred
             output.push_str("![");
             output.push_str(text);
             output.push(']');
+            if self.options.link_reference_style == LinkReferenceStyle::Collapsed {
+                output.push_str("[]");
+            }
 
             self.add_reference(label.to_string(), url.to_string(), title.to_string());
         } else {
@@ -130,7 +233,7 @@ impl<'a> Serializer<'a> {
             output.push_str("![");
             output.push_str(text);
             output.push_str("][");
-            output.push_str(label);
+            output.push_str(&self.canonical_label(label));
             output.push(']');
 
             self.add_reference(label.to_string(), url.to_string(), title.to_string());
@@ -138,16 +241,18 @@ impl<'a> Serializer<'a> {
     }
 
     /// Format an inline-style image and write to output buffer.
-    pub(super) fn format_inline_image(output: &mut String, alt_text: &str, url: &str, title: &str) {
+    pub(super) fn format_inline_image(
+        output: &mut String,
+        alt_text: &str,
+        url: &str,
+        title: &str,
+        title_style: LinkTitleStyle,
+    ) {
         output.push_str("![");
         output.push_str(alt_text);
         output.push_str("](");
-        output.push_str(url);
-        if !title.is_empty() {
-            output.push_str(" \"");
-            output.push_str(title);
-            output.push('"');
-        }
+        Self::push_destination(output, url);
+        Self::push_title(output, title, title_style);
         output.push(')');
     }
 
@@ -172,7 +277,7 @@ impl<'a> Serializer<'a> {
                 }
                 self.output.push_str("][");
                 let actual_label = label.strip_prefix('\x01').unwrap_or(&label);
-                self.output.push_str(actual_label);
+                self.output.push_str(&self.canonical_label(actual_label));
                 self.output.push(']');
                 self.add_reference(actual_label.to_string(), url.to_string(), title.to_string());
             } else {
@@ -188,16 +293,16 @@ impl<'a> Serializer<'a> {
                 self.serialize_node(child);
             }
             self.output.push_str("](");
-            self.output.push_str(url);
-            if !title.is_empty() {
-                self.output.push_str(" \"");
-                self.output.push_str(title);
-                self.output.push('"');
-            }
+            Self::push_destination(self.output.buffer_mut(), url);
+            Self::push_title(
+                self.output.buffer_mut(),
+                title,
+                self.options.link_title_style,
+            );
             self.output.push(')');
         } else if is_autolink {
-            Self::format_autolink(&mut self.output, url);
-        } else if Self::is_external_url(url) {
+            Self::format_autolink(self.output.buffer_mut(), url);
+        } else if self.converts_to_reference(url) && !Self::next_sibling_is_attribute_block(node) {
             let link_text = self.collect_text(node);
             let mut output = String::new();
             let use_collapsed = Self::next_sibling_starts_with_bracket(node);
@@ -212,7 +317,13 @@ impl<'a> Serializer<'a> {
         } else {
             // Relative/local URL: keep as inline link
             let link_text = self.collect_text(node);
-            Self::format_inline_link(&mut self.output, &link_text, url, title);
+            Self::format_inline_link(
+                self.output.buffer_mut(),
+                &link_text,
+                url,
+                title,
+                self.options.link_title_style,
+            );
         }
     }
 
@@ -230,6 +341,12 @@ impl<'a> Serializer<'a> {
         }
 
         // Inline style: ![alt](url)
-        Self::format_inline_image(&mut self.output, &alt_text, url, title);
+        Self::format_inline_image(
+            self.output.buffer_mut(),
+            &alt_text,
+            url,
+            title,
+            self.options.link_title_style,
+        );
     }
 }
@@ -1,28 +1,48 @@
 //! Serializer for converting comrak AST to formatted Markdown.
 
+mod badge;
 mod block;
+mod changelog;
 mod code;
 mod document;
 mod escape;
+mod figure;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod formatter;
+mod front_matter;
 pub mod heading;
 mod inline;
 mod link;
 mod list;
+mod output;
 pub mod punctuation;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod sandbox;
 mod state;
 mod table;
-mod wrap;
-
-#[cfg(feature = "wasm")]
+mod url;
+pub(crate) mod wrap;
+
+pub(crate) use code::code_language;
+pub(crate) use escape::is_mention;
+pub(crate) use figure::figure_parts;
+pub(crate) use front_matter::format_front_matter;
+#[cfg(any(feature = "wasm", feature = "async"))]
 pub use state::CodeFormatterCallback;
-pub use state::{ReferenceLink, Serializer, Warning};
+#[cfg(feature = "wasm")]
+pub use state::ProgressCallback;
+pub use state::{ReferenceLink, Serializer, TraceEvent, TraceKind, Warning};
+pub(crate) use table::is_br_tag;
+pub(crate) use url::{normalize_url, rebase_url};
+
+use std::collections::HashSet;
 
 use comrak::nodes::{AstNode, NodeValue};
-use unicode_width::UnicodeWidthStr;
 
-use crate::Options;
+use crate::syntax::{self, ContainerKind as SyntaxContainer};
+use crate::{AbbreviationPlacement, HeadingLinks, LinkTitleStyle, Options, WarningCode};
+use output::{ContainerKind, SegmentKind};
+use state::reference_key;
 
 /// Result of serialization including output and any warnings.
 pub struct SerializeResult {
@@ -30,10 +50,39 @@ pub struct SerializeResult {
     pub output: String,
     /// Warnings generated during formatting.
     pub warnings: Vec<Warning>,
+    /// Formatting decisions, if [`Options::trace`] is enabled.
+    pub trace: Vec<TraceEvent>,
+    /// Labels of the reference definitions written to the output.
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    pub emitted_references: HashSet<String>,
+    /// Time spent in external code formatters.
+    #[cfg(feature = "bench")]
+    pub code_formatter_time: std::time::Duration,
+}
+
+/// Check whether an HTML block is a Hongdown or user-defined directive.
+#[cfg(feature = "parallel")]
+pub(crate) fn is_directive(html: &str, options: &Options) -> bool {
+    state::Directive::parse_with_options(html, options).is_some()
+}
+
+/// Returns the number of blank lines before a top-level heading of the given
+/// level, which follows another heading if `after_heading` is set.
+pub(crate) fn blank_lines_before_heading(
+    options: &Options,
+    level: u8,
+    after_heading: bool,
+) -> usize {
+    if after_heading && options.heading_collapse_consecutive {
+        1
+    } else {
+        options.heading_blank_lines_before.get(level)
+    }
 }
 
 /// Serializes a comrak AST node to a formatted Markdown string,
 /// with access to the original source for directive handling.
+#[cfg(test)]
 pub fn serialize_with_source<'a>(
     node: &'a AstNode<'a>,
     options: &Options,
@@ -51,21 +100,37 @@ pub fn serialize_with_source_and_warnings<'a>(
 ) -> SerializeResult {
     let source_lines: Vec<&str> = source.map(|s| s.lines().collect()).unwrap_or_default();
     let source_ends_with_newline = source.is_some_and(|s| s.ends_with('\n'));
-    let mut serializer = Serializer::new(options, source_lines, source_ends_with_newline);
-    serializer.serialize_node(node);
-    SerializeResult {
-        output: serializer.output,
-        warnings: serializer.warnings,
-    }
+    Serializer::new(options, source_lines, source_ends_with_newline).serialize_root(node)
+}
+
+/// Serializes with a code formatter callback instead of running external
+/// code formatters (async API).
+#[cfg(feature = "async")]
+pub(crate) fn serialize_with_code_formatter_callback<'a>(
+    node: &'a AstNode<'a>,
+    options: &Options,
+    source: Option<&str>,
+    code_formatter: CodeFormatterCallback,
+) -> SerializeResult {
+    let source_lines: Vec<&str> = source.map(|s| s.lines().collect()).unwrap_or_default();
+    let source_ends_with_newline = source.is_some_and(|s| s.ends_with('\n'));
+    Serializer::with_code_formatter_callback(
+        options,
+        source_lines,
+        source_ends_with_newline,
+        code_formatter,
+    )
+    .serialize_root(node)
 }
 
-/// Serializes with a code formatter callback (WASM only).
+/// Serializes with code formatter and progress callbacks (WASM only).
 #[cfg(feature = "wasm")]
 pub fn serialize_with_code_formatter<'a>(
     node: &'a AstNode<'a>,
     options: &Options,
     source: Option<&str>,
     code_formatter: CodeFormatterCallback,
+    progress: ProgressCallback,
 ) -> SerializeResult {
     let source_lines: Vec<&str> = source.map(|s| s.lines().collect()).unwrap_or_default();
     let source_ends_with_newline = source.is_some_and(|s| s.ends_with('\n'));
@@ -75,19 +140,176 @@ pub fn serialize_with_code_formatter<'a>(
         source_ends_with_newline,
         code_formatter,
     );
-    serializer.serialize_node(node);
-    SerializeResult {
-        output: serializer.output,
-        warnings: serializer.warnings,
-    }
+    serializer.progress_callback = progress;
+    serializer.serialize_root(node)
 }
 
 impl<'a> Serializer<'a> {
+    /// Serialize the document `root`, consuming the serializer.
+    fn serialize_root<'b>(mut self, root: &'b AstNode<'b>) -> SerializeResult {
+        self.number_references(root);
+        self.rebase_links(root);
+        self.normalize_urls(root);
+        self.serialize_node(root);
+        SerializeResult {
+            output: self.output.into_string(),
+            warnings: self.warnings,
+            trace: self.trace,
+            emitted_references: self.emitted_references,
+            #[cfg(feature = "bench")]
+            code_formatter_time: self.code_formatter_time,
+        }
+    }
+
+    /// Assign new labels to the purely numeric reference labels of the
+    /// document in the order of their first use, if enabled.  Inline links
+    /// with numeric text that are converted to references are numbered as
+    /// well, since their text becomes their label.
+    fn number_references<'b>(&mut self, root: &'b AstNode<'b>) {
+        if !self.options.renumber_references {
+            return;
+        }
+        for node in root.descendants() {
+            if !matches!(
+                node.data.borrow().value,
+                NodeValue::Link(_) | NodeValue::Image(_)
+            ) {
+                continue;
+            }
+            let Some(label) = self
+                .source_reference_style_info(node)
+                .map(|(_, label)| label)
+                .or_else(|| self.converted_reference_label(node))
+            else {
+                continue;
+            };
+            // Collapsed references carry their label with a `\x01` prefix
+            let label = label.trim_start_matches('\x01').to_string();
+            if !label.is_empty()
+                && label.bytes().all(|b| b.is_ascii_digit())
+                && !self.reference_numbers.contains_key(&label)
+            {
+                let number = (self.reference_numbers.len() + 1).to_string();
+                self.reference_numbers.insert(label, number);
+            }
+        }
+    }
+
+    /// Find the label an inline link gets when it is converted to a
+    /// reference, i.e., its text, if it is.
+    fn converted_reference_label<'b>(&self, node: &'b AstNode<'b>) -> Option<String> {
+        let NodeValue::Link(link) = &node.data.borrow().value else {
+            return None;
+        };
+        let in_heading = node
+            .ancestors()
+            .any(|ancestor| matches!(ancestor.data.borrow().value, NodeValue::Heading(_)));
+        if (in_heading && self.options.heading_links != HeadingLinks::Reference)
+            || !Self::is_external_url(&link.url)
+            || Self::next_sibling_is_attribute_block(node)
+            || node
+                .children()
+                .any(|child| !matches!(child.data.borrow().value, NodeValue::Text(_)))
+        {
+            return None;
+        }
+        let text = self.collect_raw_text(node);
+        // Autolinks are kept as they are
+        if link.title.is_empty() && text == link.url {
+            return None;
+        }
+        Some(text)
+    }
+
+    /// Join relative destinations of links and images against the link
+    /// base, if any.  Those pointing outside of the base are left alone with
+    /// a warning.
+    fn rebase_links<'b>(&mut self, root: &'b AstNode<'b>) {
+        if self.options.link_base.is_empty() {
+            return;
+        }
+        for node in root.descendants() {
+            let url = match &node.data.borrow().value {
+                NodeValue::Link(link) | NodeValue::Image(link) => link.url.clone(),
+                _ => continue,
+            };
+            match url::rebase_url(&url, &self.options.link_base) {
+                Ok(Some(rebased)) => match &mut node.data.borrow_mut().value {
+                    NodeValue::Link(link) | NodeValue::Image(link) => link.url = rebased,
+                    _ => {}
+                },
+                Ok(None) => {}
+                Err(reason) => {
+                    let start = node.data.borrow().sourcepos.start;
+                    self.add_warning(
+                        WarningCode::LinkBase,
+                        start.line,
+                        start.column,
+                        format!("URL {:?} is left as it is, since {}", url, reason),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Normalize the destinations of links and images, if enabled.  Those
+    /// that normalization could change the meaning of are left alone with
+    /// a warning.  Autolinks are left alone as well, since their text is
+    /// their destination.
+    fn normalize_urls<'b>(&mut self, root: &'b AstNode<'b>) {
+        if !self.options.normalize_urls {
+            return;
+        }
+        for node in root.descendants() {
+            let url = match &node.data.borrow().value {
+                NodeValue::Link(link)
+                    if !(link.title.is_empty() && self.collect_raw_text(node) == link.url) =>
+                {
+                    link.url.clone()
+                }
+                NodeValue::Image(image) => image.url.clone(),
+                _ => continue,
+            };
+            match url::normalize_url(&url) {
+                Ok(Some(normalized)) => match &mut node.data.borrow_mut().value {
+                    NodeValue::Link(link) | NodeValue::Image(link) => link.url = normalized,
+                    _ => {}
+                },
+                Ok(None) => {}
+                Err(reason) => {
+                    let start = node.data.borrow().sourcepos.start;
+                    self.add_warning(
+                        WarningCode::UrlNormalization,
+                        start.line,
+                        start.column,
+                        format!("URL {:?} is left as it is, since {}", url, reason),
+                    );
+                }
+            }
+        }
+    }
+
     /// Check if a link/image was originally in reference style by examining the source.
     /// Returns Some((text, label)) if reference style, None if inline style.
     /// The returned text has newlines normalized to spaces for consistent output.
+    /// Numeric labels are renumbered if enabled; shortcut and collapsed
+    /// references then become full references, so that their visible text
+    /// stays the same.
     fn get_reference_style_info<'b>(&self, node: &'b AstNode<'b>) -> Option<(String, String)> {
-        let source = self.extract_source(node)?;
+        let (text, label) = self.source_reference_style_info(node)?;
+        match self.reference_numbers.get(label.trim_start_matches('\x01')) {
+            Some(number) => Some((text, number.clone())),
+            None => Some((text, label)),
+        }
+    }
+
+    /// Find the text and label of a link/image written in reference style
+    /// in the source, as they are written there.
+    pub(crate) fn source_reference_style_info<'b>(
+        &self,
+        node: &'b AstNode<'b>,
+    ) -> Option<(String, String)> {
+        let source = self.source_span(node)?;
 
         // Reference style patterns:
         // [text][label] or ![text][label] - full reference
@@ -103,17 +325,16 @@ impl<'a> Serializer<'a> {
         // Remove leading ! for images
         let source = source.strip_prefix('!').unwrap_or(&source);
 
-        // Find the position of the first '[' and track brackets to find the matching ']'
+        // Find the position of the first '[' and track brackets to find the
+        // matching ']'.  Brackets are ASCII, so scanning bytes is safe and the
+        // positions found are always on char boundaries.
         let first_bracket = source.find('[')?;
-        let chars: Vec<char> = source.chars().collect();
-
-        // Find the closing bracket at depth 0 (the one that closes the text/content part)
         let mut depth = 0;
         let mut text_end_pos = None;
-        for (i, &ch) in chars.iter().enumerate().skip(first_bracket) {
-            match ch {
-                '[' => depth += 1,
-                ']' => {
+        for (i, byte) in source.bytes().enumerate().skip(first_bracket) {
+            match byte {
+                b'[' => depth += 1,
+                b']' => {
                     depth -= 1;
                     if depth == 0 {
                         text_end_pos = Some(i);
@@ -125,13 +346,10 @@ impl<'a> Serializer<'a> {
         }
 
         let text_end_pos = text_end_pos?;
-
-        // Convert char indices to strings for safe UTF-8 handling
-        let after_close: String = chars[text_end_pos + 1..].iter().collect();
-        let text: String = chars[first_bracket + 1..text_end_pos].iter().collect();
+        let after_close = &source[text_end_pos + 1..];
 
         // Normalize newlines to spaces in the text (for idempotency when text spans lines)
-        let text = escape::normalize_whitespace(&text);
+        let text = escape::normalize_whitespace(&source[first_bracket + 1..text_end_pos]);
 
         // If followed by "(", it's inline style
         if after_close.starts_with('(') {
@@ -142,9 +360,8 @@ impl<'a> Serializer<'a> {
         if let Some(label_content) = after_close.strip_prefix('[') {
             // Find the label between [ and ]
             if let Some(label_end) = label_content.find(']') {
-                let label = label_content[..label_end].to_string();
                 // Normalize label too
-                let label = escape::normalize_whitespace(&label);
+                let label = escape::normalize_whitespace(&label_content[..label_end]);
 
                 // If label is empty, it's collapsed reference - mark with special prefix
                 // to distinguish from shortcut reference
@@ -163,18 +380,6 @@ impl<'a> Serializer<'a> {
         Some((text.clone(), text))
     }
 
-    /// Ensure output ends with a blank line (two newlines).
-    /// Used before emitting reference definitions and footnotes.
-    fn ensure_blank_line(&mut self) {
-        if !self.output.ends_with("\n\n") {
-            if self.output.ends_with('\n') {
-                self.output.push('\n');
-            } else {
-                self.output.push_str("\n\n");
-            }
-        }
-    }
-
     /// Output pending reference definitions and clear them
     fn flush_references(&mut self) {
         if self.pending_references.is_empty() {
@@ -186,7 +391,7 @@ impl<'a> Serializer<'a> {
         let refs: Vec<ReferenceLink> = self
             .pending_references
             .values()
-            .filter(|r| !self.emitted_references.contains(&r.label))
+            .filter(|r| !self.emitted_references.contains(&reference_key(&r.label)))
             .cloned()
             .collect();
         self.pending_references.clear();
@@ -201,13 +406,19 @@ impl<'a> Serializer<'a> {
             .filter(|r| Self::extract_numeric_label(&r.label).is_some())
             .count();
 
-        self.ensure_blank_line();
+        self.output.begin(SegmentKind::References);
+        self.output.ensure_blank_line();
 
         if numeric_count < 2 {
             // Less than 2 numeric refs: output all in insertion order
             for reference in &refs {
-                Self::write_reference(&mut self.output, reference);
-                self.emitted_references.insert(reference.label.clone());
+                Self::write_reference(
+                    self.output.buffer_mut(),
+                    reference,
+                    self.options.link_title_style,
+                );
+                self.emitted_references
+                    .insert(reference_key(&reference.label));
             }
         } else {
             // 2+ numeric refs: separate, sort numeric ones, output regular first
@@ -227,16 +438,85 @@ impl<'a> Serializer<'a> {
 
             // Output regular references first (in insertion order)
             for reference in regular_refs {
-                Self::write_reference(&mut self.output, reference);
-                self.emitted_references.insert(reference.label.clone());
+                Self::write_reference(
+                    self.output.buffer_mut(),
+                    reference,
+                    self.options.link_title_style,
+                );
+                self.emitted_references
+                    .insert(reference_key(&reference.label));
             }
 
             // Output numeric references (sorted by number)
             for (_, reference) in numeric_refs {
-                Self::write_reference(&mut self.output, reference);
-                self.emitted_references.insert(reference.label.clone());
+                Self::write_reference(
+                    self.output.buffer_mut(),
+                    reference,
+                    self.options.link_title_style,
+                );
+                self.emitted_references
+                    .insert(reference_key(&reference.label));
             }
         }
+        self.output.begin(SegmentKind::Content);
+    }
+
+    /// Output the reference definitions of version sections, which the
+    /// `changelog` profile keeps at the bottom of the document, newest first.
+    fn flush_version_references(&mut self) {
+        if self.pending_version_references.is_empty() {
+            return;
+        }
+        let mut refs: Vec<ReferenceLink> = std::mem::take(&mut self.pending_version_references)
+            .into_values()
+            .filter(|r| !self.emitted_references.contains(&reference_key(&r.label)))
+            .collect();
+        if refs.is_empty() {
+            return;
+        }
+        refs.sort_by_cached_key(|r| changelog::version_sort_key(&r.label));
+
+        self.output.begin(SegmentKind::References);
+        self.output.ensure_blank_line();
+        for reference in &refs {
+            Self::write_reference(
+                self.output.buffer_mut(),
+                reference,
+                self.options.link_title_style,
+            );
+            self.emitted_references
+                .insert(reference_key(&reference.label));
+        }
+        self.output.begin(SegmentKind::Content);
+    }
+
+    /// Output pending abbreviation definitions, sorted by abbreviation, and
+    /// clear them.  Unless `end_of_document` is true, definitions placed at
+    /// the end of the document are kept for later.
+    fn flush_abbreviations(&mut self, end_of_document: bool) {
+        if self.pending_abbreviations.is_empty()
+            || (!end_of_document
+                && self.options.abbreviation_placement == AbbreviationPlacement::Document)
+        {
+            return;
+        }
+        let mut definitions = std::mem::take(&mut self.pending_abbreviations);
+        definitions.sort_by_cached_key(|definition| {
+            let abbreviation = definition
+                .strip_prefix("*[")
+                .and_then(|rest| rest.split_once("]:"))
+                .map_or("", |(abbreviation, _)| abbreviation);
+            (abbreviation.to_lowercase(), definition.clone())
+        });
+        definitions.dedup();
+
+        self.output.begin(SegmentKind::Abbreviations);
+        self.output.ensure_blank_line();
+        for definition in &definitions {
+            self.output.push_str(definition);
+            self.output.push('\n');
+        }
+        self.output.begin(SegmentKind::Content);
     }
 
     /// Extract numeric value from a reference label like "123" or "#123"
@@ -246,18 +526,18 @@ impl<'a> Serializer<'a> {
     }
 
     /// Write a single reference definition to output
-    fn write_reference(output: &mut String, reference: &ReferenceLink) {
+    fn write_reference(
+        output: &mut String,
+        reference: &ReferenceLink,
+        title_style: LinkTitleStyle,
+    ) {
         output.push('[');
         // Replace SoftBreak marker with space for reference labels
         // (comrak normalizes whitespace in labels, so this ensures idempotency)
         output.push_str(&reference.label.replace('\x00', " "));
         output.push_str("]: ");
-        output.push_str(&reference.url);
-        if !reference.title.is_empty() {
-            output.push_str(" \"");
-            output.push_str(&reference.title);
-            output.push('"');
-        }
+        Self::push_destination(output, &reference.url);
+        Self::push_title(output, &reference.title, title_style);
         output.push('\n');
     }
 
@@ -296,7 +576,8 @@ impl<'a> Serializer<'a> {
             return;
         }
 
-        self.ensure_blank_line();
+        self.output.begin(SegmentKind::Footnotes);
+        self.output.ensure_blank_line();
 
         // Count numeric footnotes to decide sorting strategy
         let numeric_count = to_emit
@@ -338,6 +619,7 @@ impl<'a> Serializer<'a> {
                 self.footnotes.emitted.insert(footnote.name.clone());
             }
         }
+        self.output.begin(SegmentKind::Content);
     }
 
     /// Extract numeric value from a footnote name like "1" or "123"
@@ -385,13 +667,20 @@ impl<'a> Serializer<'a> {
             return;
         }
 
-        self.ensure_blank_line();
+        self.output.begin(SegmentKind::References);
+        self.output.ensure_blank_line();
 
         // Output references in insertion order
         for reference in &to_emit {
-            Self::write_reference(&mut self.output, reference);
-            self.emitted_references.insert(reference.label.clone());
+            Self::write_reference(
+                self.output.buffer_mut(),
+                reference,
+                self.options.link_title_style,
+            );
+            self.emitted_references
+                .insert(reference_key(&reference.label));
         }
+        self.output.begin(SegmentKind::Content);
     }
 
     /// Output all pending footnote reference definitions.
@@ -399,15 +688,39 @@ impl<'a> Serializer<'a> {
         self.flush_footnote_references_before(None);
     }
 
+    /// Render a footnote definition that has more than a single paragraph,
+    /// with its blocks indented by four spaces below the label.
+    fn render_footnote_blocks<'b>(&mut self, node: &'b AstNode<'b>, name: &str) -> String {
+        let output = std::mem::take(&mut self.output);
+        self.output.push_container(
+            ContainerKind::Footnote,
+            format!("[^{}]: ", name),
+            "    ".to_string(),
+        );
+        for (i, child) in node.children().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            self.serialize_node(child);
+        }
+        self.output.pop_container();
+        std::mem::replace(&mut self.output, output).into_string()
+    }
+
     /// Write a single footnote definition to output, wrapping at 80 characters
     fn write_footnote(&mut self, footnote: &state::FootnoteDefinition) {
+        if footnote.rendered {
+            self.output.push_str(&footnote.content);
+            return;
+        }
         let prefix = format!("[^{}]: ", footnote.name);
-        // Continuation indent matches prefix length for alignment
-        let continuation_indent = " ".repeat(prefix.len());
+        // Continuation indent matches prefix width for alignment
+        let prefix_width = wrap::display_width(&prefix);
+        let continuation_indent = " ".repeat(prefix_width);
 
         // Wrap content at 80 chars, accounting for prefix on first line
-        let first_line_width = 80 - prefix.width();
-        let continuation_width = 80 - continuation_indent.len();
+        let first_line_width = 80usize.saturating_sub(prefix_width);
+        let continuation_width = first_line_width;
 
         // Replace SoftBreak marker (\x00) with space before processing
         let content = footnote.content.replace('\x00', " ");
@@ -431,7 +744,9 @@ impl<'a> Serializer<'a> {
 
             if current_line.is_empty() {
                 current_line.push_str(word);
-            } else if current_line.width() + 1 + word.width() <= max_width {
+            } else if wrap::display_width(&current_line) + 1 + wrap::display_width(word)
+                <= max_width
+            {
                 current_line.push(' ');
                 current_line.push_str(word);
             } else {
@@ -460,9 +775,24 @@ impl<'a> Serializer<'a> {
     }
 
     pub fn serialize_node<'b>(&mut self, node: &'b AstNode<'b>) {
+        if let Some(rendered) = self.render_custom_inline(node) {
+            self.output.push_str(&rendered);
+            return;
+        }
+        if self.options.trace {
+            let data = node.data.borrow();
+            if data.value.block() && data.sourcepos.start.line > 0 {
+                self.trace_line = data.sourcepos.start.line;
+            }
+        }
         match &node.data.borrow().value {
             NodeValue::Document => {
                 self.serialize_document(node);
+                if self.options.strip_trailing_whitespace {
+                    self.output.trim_line_ends();
+                }
+                self.output.replace(wrap::HARD_BREAK, "  ");
+                self.output.replace(wrap::PROTECTED_SPACE, " ");
             }
             NodeValue::Heading(heading) => {
                 self.serialize_heading(node, heading.level);
@@ -471,11 +801,16 @@ impl<'a> Serializer<'a> {
                 self.serialize_list(node, list.list_type, list.tight);
             }
             NodeValue::CodeBlock(code_block) => {
-                self.serialize_code_block(&code_block.info, &code_block.literal);
+                self.serialize_code_block(node, &code_block.info, &code_block.literal);
             }
             NodeValue::BlockQuote => {
                 self.serialize_block_quote(node);
             }
+            NodeValue::Raw(opening) => match syntax::container_kind(node) {
+                Some(SyntaxContainer::Admonition) => self.serialize_admonition(node, opening),
+                Some(SyntaxContainer::FencedDiv) => self.serialize_fenced_div(node, opening),
+                None => self.serialize_children(node),
+            },
             NodeValue::HtmlBlock(html_block) => {
                 // Preserve HTML blocks (like comments) as-is
                 self.output.push_str(&html_block.literal);
@@ -502,16 +837,7 @@ impl<'a> Serializer<'a> {
                 for (i, child) in children.iter().enumerate() {
                     if i > 0 {
                         // Add blank line between description items
-                        // If inside a blockquote, we need to add the > prefix on the blank line
-                        // to keep items within the same blockquote
-                        if self.in_block_quote {
-                            self.output.push_str(&self.list_item_indent);
-                            // Use blockquote_prefix without trailing space for blank lines
-                            self.output.push_str(self.blockquote_prefix.trim_end());
-                            self.output.push('\n');
-                        } else {
-                            self.output.push('\n');
-                        }
+                        self.output.push('\n');
                     }
                     self.serialize_node(child);
                 }
@@ -528,7 +854,7 @@ impl<'a> Serializer<'a> {
                 self.serialize_description_details(node);
             }
             NodeValue::Alert(alert) => {
-                self.serialize_alert(node, alert.alert_type);
+                self.serialize_alert(node, alert.alert_type, alert.title.as_deref());
             }
             NodeValue::Item(_) => {
                 self.serialize_list_item(node, None);
@@ -543,7 +869,11 @@ impl<'a> Serializer<'a> {
                 self.serialize_thematic_break();
             }
             NodeValue::Text(text) => {
-                self.output.push_str(&escape::escape_text(text));
+                self.output.push_str(&escape::escape_text(
+                    text,
+                    self.options.escape_underscores,
+                    self.options.autolink_host,
+                ));
             }
             NodeValue::SoftBreak => {
                 self.output.push(' ');
@@ -595,15 +925,25 @@ impl<'a> Serializer<'a> {
                 // Set flag so references within footnotes go to pending_footnote_references
                 // Also set the current footnote's reference line for proper flush timing
                 self.footnotes.start_collecting(reference_line);
-                let mut content = String::new();
-                for child in node.children() {
-                    self.collect_inline_node(child, &mut content);
-                }
+                let is_paragraph = node.children().count() <= 1
+                    && node
+                        .children()
+                        .all(|child| matches!(child.data.borrow().value, NodeValue::Paragraph));
+                let content = if is_paragraph {
+                    let mut content = String::new();
+                    for child in node.children() {
+                        self.collect_inline_node(child, &mut content);
+                    }
+                    content.trim().to_string()
+                } else {
+                    self.render_footnote_blocks(node, &footnote_def.name)
+                };
                 self.footnotes.stop_collecting();
                 // Add to pending footnotes (will be flushed at section end)
                 self.footnotes.add(
                     footnote_def.name.clone(),
-                    content.trim().to_string(),
+                    content,
+                    !is_paragraph,
                     reference_line,
                 );
             }
@@ -622,4 +962,5 @@ impl<'a> Serializer<'a> {
 }
 
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
 mod tests;
@@ -0,0 +1,420 @@
+//! Segmented output buffer for the serializer.
+//!
+//! Instead of one growing `String`, the serializer writes into a list of typed
+//! segments: regular content, flushed reference definitions, footnote
+//! definitions, and source copied verbatim.  The segments are joined only when
+//! serialization is finished.  Checks that used to inspect the tail of the
+//! whole output, such as the blank line policy, work across segment
+//! boundaries, and passes that emit definitions open their own segments
+//! instead of appending to whatever was written last.
+//!
+//! The buffer also keeps the stack of block containers, such as block quotes,
+//! list items, and definition details, that the serializer is inside of.
+//! Each container contributes a prefix to every line written within it, and
+//! the buffer writes the prefixes of all open containers whenever a line is
+//! started, so block serializers never emit indentation or `>` markers
+//! themselves and can be nested arbitrarily.
+
+use super::wrap::display_width;
+
+/// The kind of content held by a [`Segment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// Formatted blocks.
+    Content,
+    /// Link reference definitions flushed at a section boundary.
+    References,
+    /// Footnote definitions flushed at a section boundary.
+    Footnotes,
+    /// Abbreviation definitions grouped at a section boundary or at the end
+    /// of the document.
+    Abbreviations,
+    /// Original source copied without formatting.
+    Verbatim,
+}
+
+/// A run of output text of a single kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub text: String,
+}
+
+/// The kind of a [`Container`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    /// A block quote or an alert.
+    BlockQuote,
+    /// A list item.
+    ListItem,
+    /// The details of a definition list item.
+    Details,
+    /// A footnote definition.
+    Footnote,
+    /// The body of a MkDocs admonition.
+    Admonition,
+}
+
+/// A block container whose lines all start with the same prefix.
+#[derive(Debug, Clone)]
+struct Container {
+    kind: ContainerKind,
+    /// The prefix of the first line, e.g., a list marker.
+    first: String,
+    /// The prefix of every following line, e.g., the indentation of a list
+    /// item's content.
+    rest: String,
+    /// Whether the first line has been started.
+    started: bool,
+}
+
+/// The serializer's output, built from typed segments.
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// Never empty; the last segment receives all writes.
+    segments: Vec<Segment>,
+    /// The block containers being written, outermost first.
+    containers: Vec<Container>,
+    /// Whether nothing has been written to the current line yet, not even
+    /// the container prefixes.
+    at_line_start: bool,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Output {
+            segments: vec![Segment {
+                kind: SegmentKind::Content,
+                text: String::new(),
+            }],
+            containers: Vec::new(),
+            at_line_start: true,
+        }
+    }
+}
+
+impl Output {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start writing segments of the given kind.  Consecutive segments of the
+    /// same kind are merged, and an empty segment is reused.
+    pub fn begin(&mut self, kind: SegmentKind) {
+        let last = self.last_mut();
+        if last.kind == kind {
+            return;
+        }
+        if last.text.is_empty() {
+            last.kind = kind;
+            return;
+        }
+        self.segments.push(Segment {
+            kind,
+            text: String::new(),
+        });
+    }
+
+    /// Returns the text of the current segment for helpers that write into a
+    /// `String` directly.  The helpers must not write line breaks.
+    pub fn buffer_mut(&mut self) -> &mut String {
+        if self.at_line_start {
+            self.start_line(false);
+        }
+        &mut self.last_mut().text
+    }
+
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Write `text`, starting each of its lines with the prefixes of the open
+    /// containers.  Blank lines get the prefixes without trailing spaces.
+    pub fn push_str(&mut self, text: &str) {
+        if self.containers.is_empty() {
+            if !text.is_empty() {
+                self.last_mut().text.push_str(text);
+                self.at_line_start = text.ends_with('\n');
+            }
+            return;
+        }
+        for line in text.split_inclusive('\n') {
+            if self.at_line_start {
+                self.start_line(line == "\n");
+            }
+            self.last_mut().text.push_str(line);
+            self.at_line_start = line.ends_with('\n');
+        }
+    }
+
+    /// Write a line consisting only of the prefixes of the open containers,
+    /// keeping their trailing spaces, e.g., for an empty list item.
+    pub fn push_prefix_line(&mut self) {
+        if self.at_line_start {
+            self.start_line(false);
+        }
+        self.last_mut().text.push('\n');
+        self.at_line_start = true;
+    }
+
+    /// Write the prefixes of the open containers to start a new line.
+    fn start_line(&mut self, blank: bool) {
+        self.at_line_start = false;
+        if self.containers.is_empty() {
+            return;
+        }
+        let mut prefix = String::new();
+        for container in &mut self.containers {
+            if container.started {
+                prefix.push_str(&container.rest);
+            } else {
+                prefix.push_str(&container.first);
+                container.started = true;
+            }
+        }
+        let prefix = if blank { prefix.trim_end() } else { &prefix };
+        self.last_mut().text.push_str(prefix);
+    }
+
+    /// Open a container whose first line starts with `first` and whose other
+    /// lines start with `rest`.  The container must be opened at the start
+    /// of a line.
+    pub fn push_container(&mut self, kind: ContainerKind, first: String, rest: String) {
+        self.containers.push(Container {
+            kind,
+            first,
+            rest,
+            started: false,
+        });
+    }
+
+    /// Close the innermost container.
+    pub fn pop_container(&mut self) {
+        self.containers.pop();
+    }
+
+    /// Replace the prefix that the innermost container writes on the lines
+    /// after its first one, returning the previous prefix.
+    pub fn replace_indent(&mut self, rest: String) -> Option<String> {
+        let container = self.containers.last_mut()?;
+        Some(std::mem::replace(&mut container.rest, rest))
+    }
+
+    /// Returns the kind of the innermost container.
+    pub fn innermost(&self) -> Option<ContainerKind> {
+        self.containers.last().map(|container| container.kind)
+    }
+
+    /// Returns whether any open container is of the given kind.
+    pub fn is_inside(&self, kind: ContainerKind) -> bool {
+        self.containers
+            .iter()
+            .any(|container| container.kind == kind)
+    }
+
+    /// Returns the number of list items nested directly in each other at the
+    /// innermost position, which is the nesting level of a list opened there.
+    pub fn list_depth(&self) -> usize {
+        self.containers
+            .iter()
+            .rev()
+            .take_while(|container| container.kind == ContainerKind::ListItem)
+            .count()
+    }
+
+    /// Returns the display width of the current line so far, counting the
+    /// container prefixes a new line would start with.
+    pub fn column(&self) -> usize {
+        if self.at_line_start {
+            return self
+                .containers
+                .iter()
+                .map(|container| {
+                    if container.started {
+                        display_width(&container.rest)
+                    } else {
+                        display_width(&container.first)
+                    }
+                })
+                .sum();
+        }
+        let mut width = 0;
+        for segment in self.segments.iter().rev() {
+            match segment.text.rfind('\n') {
+                Some(index) => return width + display_width(&segment.text[index + 1..]),
+                None => width += display_width(&segment.text),
+            }
+        }
+        width
+    }
+
+    /// Returns the display width of the prefixes that the lines after the
+    /// current one start with.
+    pub fn indent_width(&self) -> usize {
+        self.containers
+            .iter()
+            .map(|container| display_width(&container.rest))
+            .sum()
+    }
+
+    /// Returns whether the output written so far ends with `suffix`, looking
+    /// across segment boundaries.
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        let mut rest = suffix;
+        for segment in self.segments.iter().rev() {
+            let text = segment.text.as_str();
+            if text.len() >= rest.len() {
+                return text.ends_with(rest);
+            }
+            if !rest.ends_with(text) {
+                return false;
+            }
+            rest = &rest[..rest.len() - text.len()];
+        }
+        rest.is_empty()
+    }
+
+    /// Returns whether nothing has been written yet.
+    pub fn is_empty(&self) -> bool {
+        self.segments.iter().all(|segment| segment.text.is_empty())
+    }
+
+    /// Ensure the output ends with a blank line (two newlines).
+    pub fn ensure_blank_line(&mut self) {
+        if self.ends_with("\n\n") {
+            return;
+        }
+        if self.ends_with("\n") {
+            self.push('\n');
+        } else {
+            self.push_str("\n\n");
+        }
+    }
+
+    /// Replace every occurrence of `from` in all segments.
+    pub fn replace(&mut self, from: char, to: &str) {
+        for segment in &mut self.segments {
+            if segment.text.contains(from) {
+                segment.text = segment.text.replace(from, to);
+            }
+        }
+    }
+
+    /// Remove the whitespace at the end of every line, except in source
+    /// copied verbatim.
+    pub fn trim_line_ends(&mut self) {
+        for segment in &mut self.segments {
+            if segment.kind == SegmentKind::Verbatim {
+                continue;
+            }
+            let mut text = String::with_capacity(segment.text.len());
+            for line in segment.text.split_inclusive('\n') {
+                match line.strip_suffix('\n') {
+                    Some(line) => {
+                        text.push_str(line.trim_end_matches([' ', '\t', '\r']));
+                        text.push('\n');
+                    }
+                    None => text.push_str(line),
+                }
+            }
+            segment.text = text;
+        }
+    }
+
+    /// Returns the segments written so far, in order.
+    #[cfg(test)]
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Join all segments into the final output.
+    pub fn into_string(self) -> String {
+        let len = self.segments.iter().map(|segment| segment.text.len()).sum();
+        let mut output = String::with_capacity(len);
+        for segment in &self.segments {
+            output.push_str(&segment.text);
+        }
+        output
+    }
+
+    fn last_mut(&mut self) -> &mut Segment {
+        let last = self.segments.len() - 1;
+        &mut self.segments[last]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_begin_merges_and_reuses_segments() {
+        let mut output = Output::new();
+        output.begin(SegmentKind::References);
+        output.push_str("[a]: /a\n");
+        output.begin(SegmentKind::References);
+        output.push_str("[b]: /b\n");
+        output.begin(SegmentKind::Content);
+        output.push_str("Text.\n");
+        let kinds: Vec<SegmentKind> = output.segments().iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![SegmentKind::References, SegmentKind::Content]);
+        assert_eq!(output.into_string(), "[a]: /a\n[b]: /b\nText.\n");
+    }
+
+    #[test]
+    fn test_ends_with_across_segments() {
+        let mut output = Output::new();
+        output.push_str("Text.\n");
+        output.begin(SegmentKind::Footnotes);
+        output.push('\n');
+        output.begin(SegmentKind::Content);
+        assert!(output.ends_with("\n\n"));
+        assert!(output.ends_with(".\n\n"));
+        assert!(!output.ends_with("x\n\n"));
+        assert!(!output.ends_with("Longer than everything. Text.\n\n"));
+    }
+
+    #[test]
+    fn test_ensure_blank_line() {
+        let mut output = Output::new();
+        output.push_str("Text.");
+        output.ensure_blank_line();
+        assert_eq!(output.clone().into_string(), "Text.\n\n");
+        output.begin(SegmentKind::References);
+        output.ensure_blank_line();
+        assert_eq!(output.into_string(), "Text.\n\n");
+    }
+
+    #[test]
+    fn test_container_prefixes() {
+        let mut output = Output::new();
+        output.push_container(ContainerKind::BlockQuote, "> ".into(), "> ".into());
+        output.push_str("Quote.\n\n");
+        output.push_container(ContainerKind::ListItem, " -  ".into(), "    ".into());
+        assert_eq!(output.column(), 6);
+        output.push_str("Item\ncontinued.\n\n");
+        assert_eq!(output.column(), 6);
+        assert_eq!(output.indent_width(), 6);
+        output.push_str("Para");
+        assert_eq!(output.column(), 10);
+        output.pop_container();
+        output.pop_container();
+        output.push_str(".\n");
+        assert_eq!(
+            output.into_string(),
+            "> Quote.\n>\n>  -  Item\n>     continued.\n>\n>     Para.\n"
+        );
+    }
+
+    #[test]
+    fn test_trim_line_ends_skips_verbatim() {
+        let mut output = Output::new();
+        output.push_str("Text.  \n\t\ncode \t\r\n");
+        output.begin(SegmentKind::Verbatim);
+        output.push_str("Source.  \n");
+        output.begin(SegmentKind::Content);
+        output.push_str("End. ");
+        output.trim_line_ends();
+        assert_eq!(output.into_string(), "Text.\n\ncode\nSource.  \nEnd. ");
+    }
+}
@@ -3,7 +3,7 @@
 //! This module provides functions to transform straight quotes, apostrophes,
 //! and other punctuation into their typographic equivalents.
 
-use crate::{DashSetting, Options};
+use crate::{CjkQuotes, DashSetting, Options};
 
 #[cfg(test)]
 use crate::DashPattern;
@@ -25,6 +25,19 @@ pub const LEFT_SINGLE_QUOTE: char = '\u{2018}';
 /// Right single quotation mark (U+2019) - also used as curly apostrophe
 pub const RIGHT_SINGLE_QUOTE: char = '\u{2019}';
 
+/// Left corner bracket (U+300C)
+pub const LEFT_CORNER_BRACKET: char = '\u{300C}';
+/// Right corner bracket (U+300D)
+pub const RIGHT_CORNER_BRACKET: char = '\u{300D}';
+/// Left white corner bracket (U+300E)
+pub const LEFT_WHITE_CORNER_BRACKET: char = '\u{300E}';
+/// Right white corner bracket (U+300F)
+pub const RIGHT_WHITE_CORNER_BRACKET: char = '\u{300F}';
+/// Fullwidth quotation mark (U+FF02)
+pub const FULLWIDTH_QUOTATION_MARK: char = '\u{FF02}';
+/// Fullwidth apostrophe (U+FF07)
+pub const FULLWIDTH_APOSTROPHE: char = '\u{FF07}';
+
 /// Horizontal ellipsis (U+2026)
 pub const ELLIPSIS: char = '\u{2026}';
 /// En dash (U+2013)
@@ -71,6 +84,8 @@ pub fn validate_dash_settings(options: &Options) -> Result<(), PunctuationError>
 /// Transform punctuation in text according to options.
 ///
 /// This applies SmartyPants-style transformations:
+/// - Straight quotes around mostly CJK text to CJK quotation marks (if
+///   enabled)
 /// - Straight double quotes to curly double quotes
 /// - Straight single quotes to curly single quotes
 /// - Straight apostrophes to curly apostrophes (if enabled)
@@ -96,17 +111,22 @@ pub fn transform_punctuation(text: &str, options: &Options) -> String {
         result = transform_ellipsis(&result);
     }
 
-    // 4. Double quotes
+    // 4. CJK quotes, before curly quotes take the remaining straight ones
+    if options.cjk_quotes != CjkQuotes::None {
+        result = transform_cjk_quotes(&result, options.cjk_quotes);
+    }
+
+    // 5. Double quotes
     if options.curly_double_quotes {
         result = transform_double_quotes(&result);
     }
 
-    // 5. Single quotes (before apostrophes since they share the same character)
+    // 6. Single quotes (before apostrophes since they share the same character)
     if options.curly_single_quotes {
         result = transform_single_quotes(&result);
     }
 
-    // 6. Apostrophes (only if enabled, processed after single quotes)
+    // 7. Apostrophes (only if enabled, processed after single quotes)
     if options.curly_apostrophes {
         result = transform_apostrophes(&result);
     }
@@ -143,6 +163,86 @@ fn transform_ellipsis(text: &str) -> String {
     result
 }
 
+/// Transform pairs of straight quotes around mostly CJK text to the CJK
+/// quotation marks of `style`.  Other pairs are left for the curly quote
+/// transformations, though the quotes nested in them are transformed.
+fn transform_cjk_quotes(text: &str, style: CjkQuotes) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        // A single quote right after a letter or digit is an apostrophe
+        let opens = ch == STRAIGHT_DOUBLE_QUOTE
+            || (ch == STRAIGHT_SINGLE_QUOTE && (i == 0 || !chars[i - 1].is_ascii_alphanumeric()));
+        let end = chars[i + 1..]
+            .iter()
+            .position(|&c| c == ch)
+            .map(|offset| i + 1 + offset);
+        let (true, Some(end)) = (opens, end) else {
+            result.push(ch);
+            i += 1;
+            continue;
+        };
+        let content: String = chars[i + 1..end].iter().collect();
+        let (open, close) = if is_mostly_cjk(&content) {
+            cjk_quotation_marks(style, ch == STRAIGHT_DOUBLE_QUOTE)
+        } else {
+            (ch, ch)
+        };
+        result.push(open);
+        result.push_str(&transform_cjk_quotes(&content, style));
+        result.push(close);
+        i = end + 1;
+    }
+    result
+}
+
+/// Returns the opening and closing quotation marks of `style` for double
+/// or single quotes.
+fn cjk_quotation_marks(style: CjkQuotes, double: bool) -> (char, char) {
+    match (style, double) {
+        (CjkQuotes::Corner, true) => (LEFT_CORNER_BRACKET, RIGHT_CORNER_BRACKET),
+        (CjkQuotes::Corner, false) => (LEFT_WHITE_CORNER_BRACKET, RIGHT_WHITE_CORNER_BRACKET),
+        (CjkQuotes::FullWidth, true) => (FULLWIDTH_QUOTATION_MARK, FULLWIDTH_QUOTATION_MARK),
+        (CjkQuotes::FullWidth, false) => (FULLWIDTH_APOSTROPHE, FULLWIDTH_APOSTROPHE),
+        (CjkQuotes::None, true) => (STRAIGHT_DOUBLE_QUOTE, STRAIGHT_DOUBLE_QUOTE),
+        (CjkQuotes::None, false) => (STRAIGHT_SINGLE_QUOTE, STRAIGHT_SINGLE_QUOTE),
+    }
+}
+
+/// Check whether most of the letters of `text` are Chinese, Japanese, or
+/// Korean ones.
+fn is_mostly_cjk(text: &str) -> bool {
+    let (cjk, other) =
+        text.chars()
+            .filter(|ch| ch.is_alphabetic())
+            .fold((0, 0), |(cjk, other), ch| {
+                if is_cjk(ch) {
+                    (cjk + 1, other)
+                } else {
+                    (cjk, other + 1)
+                }
+            });
+    cjk > other
+}
+
+/// Check whether `ch` is a Han character, kana, or hangul.
+fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{1100}'..='\u{11FF}'     // Hangul Jamo
+            | '\u{3040}'..='\u{30FF}' // Hiragana and Katakana
+            | '\u{3130}'..='\u{318F}' // Hangul Compatibility Jamo
+            | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+            | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+            | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+            | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+            | '\u{FF66}'..='\u{FF9F}' // Halfwidth Katakana
+            | '\u{20000}'..='\u{3134F}' // CJK Unified Ideographs Extensions B to G
+    )
+}
+
 /// Transform straight double quotes to curly double quotes.
 fn transform_double_quotes(text: &str) -> String {
     let mut result = String::with_capacity(text.len());
@@ -487,6 +587,7 @@ fn should_be_opening_quote(chars: &[char], i: usize, expecting_open: bool) -> bo
 }
 
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
 mod tests {
     use super::*;
 
@@ -1086,6 +1187,76 @@ mod tests {
         assert_eq!(result, format!("em{}dash and en{}dash", EM_DASH, EN_DASH));
     }
 
+    // ========== CJK quote tests ==========
+
+    fn cjk_options(cjk_quotes: CjkQuotes) -> Options {
+        Options {
+            cjk_quotes,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_cjk_quotes_disabled_by_default() {
+        let result = transform_punctuation("他说\"你好\"。", &default_options());
+        assert_eq!(
+            result,
+            format!("他说{}你好{}。", LEFT_DOUBLE_QUOTE, RIGHT_DOUBLE_QUOTE)
+        );
+    }
+
+    #[test]
+    fn test_cjk_quotes_corner() {
+        let options = cjk_options(CjkQuotes::Corner);
+        assert_eq!(
+            transform_punctuation("他说\"你好，'世界'\"。", &options),
+            "他说「你好，『世界』」。"
+        );
+        assert_eq!(
+            transform_punctuation("\"こんにちは\" and \"안녕하세요\"", &options),
+            "「こんにちは」 and 「안녕하세요」"
+        );
+    }
+
+    #[test]
+    fn test_cjk_quotes_full_width() {
+        let options = cjk_options(CjkQuotes::FullWidth);
+        assert_eq!(
+            transform_punctuation("他说\"你好\"，'世界'。", &options),
+            "他说＂你好＂，＇世界＇。"
+        );
+    }
+
+    #[test]
+    fn test_cjk_quotes_only_around_mostly_cjk_text() {
+        let options = cjk_options(CjkQuotes::Corner);
+        // Latin text keeps curly quotes, even in a CJK sentence
+        assert_eq!(
+            transform_punctuation("他说\"Hello 世界\"。", &options),
+            format!(
+                "他说{}Hello 世界{}。",
+                LEFT_DOUBLE_QUOTE, RIGHT_DOUBLE_QUOTE
+            )
+        );
+        assert_eq!(
+            transform_punctuation("\"He said '你好'\"", &options),
+            format!(
+                "{}He said 『你好』{}",
+                LEFT_DOUBLE_QUOTE, RIGHT_DOUBLE_QUOTE
+            )
+        );
+        // Apostrophes do not open quotes
+        assert_eq!(
+            transform_punctuation("It's '中文' now", &options),
+            "It's 『中文』 now"
+        );
+        // An unpaired quote is left to the curly quote transformations
+        assert_eq!(
+            transform_punctuation("\"你好", &options),
+            format!("{}你好", LEFT_DOUBLE_QUOTE)
+        );
+    }
+
     // ========== Validation tests ==========
 
     #[test]
@@ -0,0 +1,151 @@
+//! Sandboxing of external code formatters.
+//!
+//! Code formatters are configured by the repository being formatted, so
+//! formatting the documents of a pull request may run whatever commands it
+//! configures.  With [`Options::code_formatter_sandbox`], formatters run
+//! with only the environment variables they need to start, in an empty
+//! temporary directory, and, on Linux, in network and user namespaces of
+//! their own, which have no network access.
+//!
+//! Creating the namespaces needs unprivileged user namespaces, which some
+//! systems and containers disable.  There, sandboxed formatters are not run
+//! at all, and fail with a warning instead of running with network access.
+//! On other operating systems, they run with network access.
+//!
+//! [`Options::code_formatter_sandbox`]: crate::Options::code_formatter_sandbox
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Environment variables that sandboxed formatters keep, because programs
+/// commonly need them to start or to find their own files.
+const KEPT_VARIABLES: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "PATHEXT",
+    "SYSTEMROOT",
+    "WINDIR",
+    "COMSPEC",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+];
+
+/// Distinguishes the directories of formatters running at the same time.
+static DIRECTORY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The empty temporary directory a sandboxed formatter runs in, which is
+/// removed when dropped.
+pub(crate) struct Sandbox {
+    dir: PathBuf,
+}
+
+impl Sandbox {
+    /// Restrict the process `command` spawns.  The returned sandbox must be
+    /// kept until the process exits.
+    pub(crate) fn apply(command: &mut Command) -> io::Result<Self> {
+        command.env_clear();
+        for name in KEPT_VARIABLES {
+            if let Some(value) = env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+        #[cfg(target_os = "linux")]
+        if !namespaces_available() {
+            return Err(io::Error::other(
+                "the network cannot be isolated, since unprivileged user \
+                 namespaces are unavailable",
+            ));
+        }
+        let dir = env::temp_dir().join(format!(
+            "hongdown-formatter-{}-{}",
+            std::process::id(),
+            DIRECTORY_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&dir)?;
+        command.current_dir(&dir);
+        #[cfg(target_os = "linux")]
+        isolate_network(command);
+        Ok(Self { dir })
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Check once whether processes can enter new user and network namespaces,
+/// by trying it in a child process.
+#[cfg(target_os = "linux")]
+fn namespaces_available() -> bool {
+    static AVAILABLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    // SAFETY: the child only makes system calls, which are
+    // async-signal-safe, before exiting.
+    *AVAILABLE.get_or_init(|| unsafe {
+        match libc::fork() {
+            -1 => false,
+            0 => libc::_exit(libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET)),
+            pid => {
+                let mut status = 0;
+                libc::waitpid(pid, &mut status, 0) == pid
+                    && libc::WIFEXITED(status)
+                    && libc::WEXITSTATUS(status) == 0
+            }
+        }
+    })
+}
+
+/// Make the process `command` spawns enter new user and network namespaces.
+/// The user namespace maps the user to itself, so that the process can still
+/// access its files.  If the namespaces cannot be created, the process fails
+/// to spawn.
+#[cfg(target_os = "linux")]
+fn isolate_network(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: `getuid` and `getgid` always succeed.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let uid_map = format!("{} {} 1", uid, uid);
+    let gid_map = format!("{} {} 1", gid, gid);
+    // SAFETY: the closure only makes system calls, which are
+    // async-signal-safe, with memory allocated before forking.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            write_proc_file(c"/proc/self/setgroups", b"deny");
+            write_proc_file(c"/proc/self/uid_map", uid_map.as_bytes());
+            write_proc_file(c"/proc/self/gid_map", gid_map.as_bytes());
+            Ok(())
+        });
+    }
+}
+
+/// Write `contents` to the file at `path` with system calls only, ignoring
+/// errors.
+#[cfg(target_os = "linux")]
+fn write_proc_file(path: &std::ffi::CStr, contents: &[u8]) {
+    // SAFETY: `path` is NUL-terminated, and `contents` is valid for its
+    // length.
+    unsafe {
+        let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd >= 0 {
+            libc::write(fd, contents.as_ptr().cast(), contents.len());
+            libc::close(fd);
+        }
+    }
+}
@@ -1,10 +1,13 @@
 //! Serializer state and common types.
 
+use std::borrow::Cow;
+
 use indexmap::IndexMap;
 
 use comrak::nodes::{AstNode, ListType, NodeValue};
 
-use crate::Options;
+use super::output::Output;
+use crate::{Options, Profile, ReferenceLabelCase, WarningCode, WarningKind};
 
 /// The current formatting skip mode.
 ///
@@ -26,6 +29,87 @@ pub enum FormatSkipMode {
     Disabled,
 }
 
+/// A formatting rule that can be switched off for a region with
+/// `<!-- hongdown-disable RULE... -->`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatRule {
+    /// Line wrapping of paragraphs and list items (`wrap`).
+    Wrap,
+    /// Conversion of external inline links to reference style
+    /// (`reference-links`).
+    ReferenceLinks,
+    /// Punctuation transformations such as curly quotes (`punctuation`).
+    Punctuation,
+    /// Sentence case conversion of headings (`sentence-case`).
+    SentenceCase,
+    /// Title case conversion of headings (`title-case`).
+    TitleCase,
+}
+
+impl FormatRule {
+    /// Parse a rule from its directive name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wrap" => Some(FormatRule::Wrap),
+            "reference-links" => Some(FormatRule::ReferenceLinks),
+            "punctuation" => Some(FormatRule::Punctuation),
+            "sentence-case" => Some(FormatRule::SentenceCase),
+            "title-case" => Some(FormatRule::TitleCase),
+            _ => None,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << (self as u8)
+    }
+}
+
+/// A set of [`FormatRule`]s, stored as a bit mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RuleSet(u8);
+
+impl RuleSet {
+    /// Parse a set of rules from whitespace- or comma-separated names.
+    /// Returns the set along with the names that are not rules, or `None` if
+    /// there are no names.
+    pub fn parse(names: &str) -> Option<(Self, Vec<String>)> {
+        let mut set = RuleSet::default();
+        let mut unknown = Vec::new();
+        let mut any = false;
+        for name in names
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|name| !name.is_empty())
+        {
+            any = true;
+            match FormatRule::from_name(name) {
+                Some(rule) => set.insert(rule),
+                None => unknown.push(name.to_string()),
+            }
+        }
+        any.then_some((set, unknown))
+    }
+
+    /// Whether the set contains the given rule.
+    pub fn contains(self, rule: FormatRule) -> bool {
+        self.0 & rule.bit() != 0
+    }
+
+    /// Add a rule to the set.
+    pub fn insert(&mut self, rule: FormatRule) {
+        self.0 |= rule.bit();
+    }
+
+    /// Add all rules of another set to this set.
+    pub fn insert_all(&mut self, other: RuleSet) {
+        self.0 |= other.0;
+    }
+
+    /// Remove all rules of another set from this set.
+    pub fn remove_all(&mut self, other: RuleSet) {
+        self.0 &= !other.0;
+    }
+}
+
 /// Formatting directives that can be embedded in HTML comments.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Directive {
@@ -37,12 +121,40 @@ pub enum Directive {
     DisableNextSection,
     /// Disable formatting from this point until `Enable`.
     Disable,
-    /// Re-enable formatting after `Disable`.
+    /// Re-enable formatting after `Disable`, and re-enable all rules
+    /// disabled by `DisableRules`.
     Enable,
+    /// Disable specific formatting rules from this point until
+    /// `EnableRules` or `Enable`.
+    DisableRules {
+        /// The rules to disable.
+        rules: RuleSet,
+        /// The given names that are not rules, to be reported.
+        unknown: Vec<String>,
+    },
+    /// Re-enable specific formatting rules after `DisableRules`.
+    EnableRules {
+        /// The rules to re-enable.
+        rules: RuleSet,
+        /// The given names that are not rules, to be reported.
+        unknown: Vec<String>,
+    },
     /// Define proper nouns for sentence case (case-sensitive).
     ProperNouns(Vec<String>),
     /// Define common nouns for sentence case (case-sensitive).
     CommonNouns(Vec<String>),
+    /// Format the code block right after with this command instead of the
+    /// formatter of its language.
+    FormatCodeWith(Vec<String>),
+    /// Do not format the code block right after with an external formatter.
+    NoFormatCode,
+    /// A user-defined directive registered in [`Options::directives`].
+    Custom {
+        /// The registered directive name.
+        name: String,
+        /// The text after the name, with a leading colon removed.
+        args: String,
+    },
 }
 
 impl Directive {
@@ -64,10 +176,25 @@ impl Directive {
             "hongdown-disable-next-section" => return Some(Directive::DisableNextSection),
             "hongdown-disable" => return Some(Directive::Disable),
             "hongdown-enable" => return Some(Directive::Enable),
+            "hongdown-no-format-code" => return Some(Directive::NoFormatCode),
             _ => {}
         }
 
         // Check for directives with arguments
+        if let Some(rules) = content.strip_prefix("hongdown-disable")
+            && rules.starts_with(char::is_whitespace)
+        {
+            return RuleSet::parse(rules)
+                .map(|(rules, unknown)| Directive::DisableRules { rules, unknown });
+        }
+
+        if let Some(rules) = content.strip_prefix("hongdown-enable")
+            && rules.starts_with(char::is_whitespace)
+        {
+            return RuleSet::parse(rules)
+                .map(|(rules, unknown)| Directive::EnableRules { rules, unknown });
+        }
+
         if let Some(args) = content.strip_prefix("hongdown-proper-nouns:") {
             let nouns = args
                 .split(',')
@@ -86,8 +213,43 @@ impl Directive {
             return Some(Directive::CommonNouns(nouns));
         }
 
+        if let Some(args) = content.strip_prefix("hongdown-format-with:") {
+            let command: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+            return (!command.is_empty()).then_some(Directive::FormatCodeWith(command));
+        }
+
         None
     }
+
+    /// Parse a directive from an HTML comment, also recognizing the
+    /// user-defined directives registered in [`Options::directives`].
+    pub fn parse_with_options(html: &str, options: &Options) -> Option<Self> {
+        if let Some(directive) = Self::parse(html) {
+            return Some(directive);
+        }
+        if options.directives.is_empty() {
+            return None;
+        }
+        let content = html
+            .trim()
+            .strip_prefix("<!--")?
+            .strip_suffix("-->")?
+            .trim();
+        options.directives.keys().find_map(|name| {
+            let rest = content.strip_prefix(name.as_str())?;
+            let args = if let Some(args) = rest.strip_prefix(':') {
+                args
+            } else if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+                rest
+            } else {
+                return None;
+            };
+            Some(Directive::Custom {
+                name: name.clone(),
+                args: args.trim().to_string(),
+            })
+        })
+    }
 }
 
 /// A reference link definition: label -> (url, title)
@@ -98,11 +260,25 @@ pub struct ReferenceLink {
     pub title: String,
 }
 
+/// Returns the key under which a reference `label` is matched: CommonMark
+/// matches labels case-insensitively and with their whitespace collapsed,
+/// so `[Docs]` and `[docs]` are the same reference.
+pub fn reference_key(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
 /// A footnote definition: name -> content
 #[derive(Debug, Clone)]
 pub struct FootnoteDefinition {
     pub name: String,
     pub content: String,
+    /// Whether `content` is the whole definition rendered as blocks, label
+    /// included, rather than the text of a single paragraph
+    pub rendered: bool,
     /// Line number where the footnote was referenced (1-indexed)
     pub reference_line: usize,
 }
@@ -142,12 +318,13 @@ impl FootnoteSet {
     }
 
     /// Add a footnote definition.
-    pub fn add(&mut self, name: String, content: String, reference_line: usize) {
+    pub fn add(&mut self, name: String, content: String, rendered: bool, reference_line: usize) {
         self.pending.insert(
             name.clone(),
             FootnoteDefinition {
                 name,
                 content,
+                rendered,
                 reference_line,
             },
         );
@@ -175,10 +352,12 @@ impl FootnoteSet {
         self.current_reference_line = 0;
     }
 
-    /// Add a reference link found within footnote content.
-    pub fn add_reference(&mut self, label: String, reference: ReferenceLink) {
+    /// Add a reference link found within footnote content, unless one with
+    /// the same key is already pending.
+    pub fn add_reference(&mut self, key: String, reference: ReferenceLink) {
         self.pending_references
-            .insert(label, (reference, self.current_reference_line));
+            .entry(key)
+            .or_insert((reference, self.current_reference_line));
     }
 }
 
@@ -187,10 +366,67 @@ impl FootnoteSet {
 pub struct Warning {
     /// Line number where the issue was detected (1-indexed)
     pub line: usize,
+    /// Column number where the issue was detected (1-indexed, in characters),
+    /// or 0 if unknown
+    pub column: usize,
+    /// Machine-readable code of the warning
+    pub code: WarningCode,
+    /// Category of the warning, for filtering
+    pub kind: WarningKind,
     /// Warning message
     pub message: String,
 }
 
+impl Warning {
+    /// Create a warning, categorized by its code.
+    pub fn new(code: WarningCode, line: usize, column: usize, message: String) -> Self {
+        Self {
+            line,
+            column,
+            code,
+            kind: code.kind(),
+            message,
+        }
+    }
+}
+
+/// Kinds of formatting decisions recorded in a trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TraceKind {
+    /// Text was wrapped to fit the line width.
+    Wrapped,
+    /// An inline link was converted to a reference link.
+    LinkToReference,
+    /// Punctuation was replaced, e.g., straight quotes with curly ones.
+    Punctuation,
+    /// An external code formatter formatted a code block.
+    CodeFormatter,
+}
+
+impl TraceKind {
+    /// Get the short machine-readable identifier of this kind.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Wrapped => "wrapped",
+            Self::LinkToReference => "link-to-reference",
+            Self::Punctuation => "punctuation",
+            Self::CodeFormatter => "code-formatter",
+        }
+    }
+}
+
+/// A formatting decision, recorded if [`Options::trace`] is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// Line number of the block the decision was made for (1-indexed), or 0
+    /// if unknown
+    pub line: usize,
+    /// What was decided
+    pub kind: TraceKind,
+    /// Description of the decision, e.g., which link was converted
+    pub detail: String,
+}
+
 /// Safely slice a string, ensuring the indices are valid UTF-8 boundaries.
 /// If the indices are not valid boundaries, adjusts to the nearest valid boundary.
 fn safe_str_slice(s: &str, start: usize, end: usize) -> &str {
@@ -220,16 +456,26 @@ fn safe_str_slice(s: &str, start: usize, end: usize) -> &str {
     &s[safe_start..safe_end]
 }
 
-/// Code formatter callback type for WASM builds.
+/// Code formatter callback type for WASM builds and the async API.
 ///
-/// The callback receives the language identifier and code content,
-/// and should return the formatted code (or `None` to keep original).
+/// The callback receives the language identifier, the command chosen by
+/// a `hongdown-format-with` directive, if any, code content, and the line
+/// the code block starts at, and should return the formatted code (or
+/// `None` to keep original).
+#[cfg(any(feature = "wasm", feature = "async"))]
+pub type CodeFormatterCallback =
+    Option<Box<dyn Fn(&str, Option<&[String]>, &str, usize) -> Option<String>>>;
+
+/// Progress callback type for WASM builds.
+///
+/// The callback receives the number of top-level blocks processed so far and
+/// the total number of top-level blocks in the document.
 #[cfg(feature = "wasm")]
-pub type CodeFormatterCallback = Option<Box<dyn Fn(&str, &str) -> Option<String>>>;
+pub type ProgressCallback = Option<Box<dyn FnMut(usize, usize)>>;
 
 /// The main serializer state for converting comrak AST to formatted Markdown.
 pub struct Serializer<'a> {
-    pub output: String,
+    pub output: Output,
     pub options: &'a Options,
     /// Original source lines for extracting unformatted content
     pub source_lines: Vec<&'a str>,
@@ -239,48 +485,53 @@ pub struct Serializer<'a> {
     pub list_type: Option<ListType>,
     /// Whether the current list is tight (no blank lines between items)
     pub list_tight: bool,
-    /// Whether we're inside a block quote
-    pub in_block_quote: bool,
-    /// Accumulated blockquote prefix for nested blockquotes (e.g., "> " or "> > ")
-    pub blockquote_prefix: String,
+    /// Whether we're collecting the content of a table cell
+    pub in_table_cell: bool,
     /// Reference links collected for the current section
-    /// Key: label, Value: ReferenceLink (insertion order preserved)
+    /// Key: [`reference_key`] of the label, Value: ReferenceLink (insertion
+    /// order preserved)
     pub pending_references: IndexMap<String, ReferenceLink>,
-    /// Reference labels that have already been emitted (to avoid duplicates)
+    /// Keys of reference labels that have already been emitted (to avoid
+    /// duplicates)
     pub emitted_references: std::collections::HashSet<String>,
+    /// New labels of numeric references when they are renumbered by first
+    /// use.  Key: label in the source, Value: new label
+    pub reference_numbers: std::collections::HashMap<String, String>,
+    /// Reference links of version sections, kept for the bottom of the
+    /// document by the `changelog` profile
+    pub pending_version_references: IndexMap<String, ReferenceLink>,
+    /// Abbreviation definition lines collected to be output as a group
+    pub pending_abbreviations: Vec<String>,
     /// Footnote definitions and their reference tracking
     pub footnotes: FootnoteSet,
-    /// Current list nesting depth (0 = not in list, 1 = top-level, 2+ = nested)
-    pub list_depth: usize,
     /// Current formatting skip mode
     pub skip_mode: FormatSkipMode,
-    /// Whether we're inside a description details block (for indentation)
-    pub in_description_details: bool,
-    /// Whether we're serializing the first list inside description details on the same line as `:`.
-    /// When true, the first list item should not have base indentation (only marker).
-    pub description_details_first_list: bool,
+    /// Formatting rules disabled by `hongdown-disable RULE...` directives
+    pub disabled_rules: RuleSet,
     /// Warnings generated during formatting
     pub warnings: Vec<Warning>,
+    /// Formatting decisions recorded if [`Options::trace`] is enabled
+    pub trace: Vec<TraceEvent>,
+    /// Line number of the block being serialized, for the trace
+    pub trace_line: usize,
     /// Maximum number of items in the current ordered list (for padding calculation)
     pub ordered_list_max_items: usize,
     /// Whether the original source ends with a newline
     pub source_ends_with_newline: bool,
-    /// Current indentation prefix for list item content (e.g., "     " for ` 1.  `)
-    /// Used by blockquotes and other block elements inside list items.
-    pub list_item_indent: String,
-    /// Indentation prefix for content inside a blockquote that's nested inside a list.
-    /// This is the outer list's indent that should appear before each `>` in the blockquote.
-    pub blockquote_outer_indent: String,
-    /// The list depth when entering the current blockquote.
-    /// Used to determine if a list exists inside vs outside the blockquote.
-    pub blockquote_entry_list_depth: usize,
     /// Proper nouns defined via directives for sentence case (merged with config)
     pub directive_proper_nouns: Vec<String>,
     /// Common nouns defined via directives for sentence case (merged with config)
     pub directive_common_nouns: Vec<String>,
-    /// Code formatter callback for WASM builds.
-    #[cfg(feature = "wasm")]
+    /// Code formatter callback for WASM builds and the async API, used
+    /// instead of running external code formatters.
+    #[cfg(any(feature = "wasm", feature = "async"))]
     pub code_formatter_callback: CodeFormatterCallback,
+    /// Progress callback for WASM builds.
+    #[cfg(feature = "wasm")]
+    pub progress_callback: ProgressCallback,
+    /// Time spent in external code formatters.
+    #[cfg(feature = "bench")]
+    pub code_formatter_time: std::time::Duration,
 }
 
 impl<'a> Serializer<'a> {
@@ -290,36 +541,40 @@ impl<'a> Serializer<'a> {
         source_ends_with_newline: bool,
     ) -> Self {
         Self {
-            output: String::new(),
+            output: Output::new(),
             options,
             source_lines,
             list_item_index: 0,
             list_type: None,
             list_tight: true,
-            in_block_quote: false,
-            blockquote_prefix: String::new(),
+            in_table_cell: false,
             pending_references: IndexMap::new(),
             emitted_references: std::collections::HashSet::new(),
+            reference_numbers: std::collections::HashMap::new(),
+            pending_version_references: IndexMap::new(),
+            pending_abbreviations: Vec::new(),
             footnotes: FootnoteSet::new(),
-            list_depth: 0,
             skip_mode: FormatSkipMode::None,
-            in_description_details: false,
-            description_details_first_list: false,
+            disabled_rules: RuleSet::default(),
             warnings: Vec::new(),
+            trace: Vec::new(),
+            trace_line: 0,
             ordered_list_max_items: 0,
             source_ends_with_newline,
-            list_item_indent: String::new(),
-            blockquote_outer_indent: String::new(),
-            blockquote_entry_list_depth: 0,
             directive_proper_nouns: Vec::new(),
             directive_common_nouns: Vec::new(),
-            #[cfg(feature = "wasm")]
+            #[cfg(any(feature = "wasm", feature = "async"))]
             code_formatter_callback: None,
+            #[cfg(feature = "wasm")]
+            progress_callback: None,
+            #[cfg(feature = "bench")]
+            code_formatter_time: std::time::Duration::ZERO,
         }
     }
 
-    /// Create a new serializer with a code formatter callback (WASM only).
-    #[cfg(feature = "wasm")]
+    /// Create a new serializer with a code formatter callback (WASM builds
+    /// and the async API).
+    #[cfg(any(feature = "wasm", feature = "async"))]
     pub fn with_code_formatter_callback(
         options: &'a Options,
         source_lines: Vec<&'a str>,
@@ -327,36 +582,82 @@ impl<'a> Serializer<'a> {
         callback: CodeFormatterCallback,
     ) -> Self {
         Self {
-            output: String::new(),
+            output: Output::new(),
             options,
             source_lines,
             list_item_index: 0,
             list_type: None,
             list_tight: true,
-            in_block_quote: false,
-            blockquote_prefix: String::new(),
+            in_table_cell: false,
             pending_references: IndexMap::new(),
             emitted_references: std::collections::HashSet::new(),
+            reference_numbers: std::collections::HashMap::new(),
+            pending_version_references: IndexMap::new(),
+            pending_abbreviations: Vec::new(),
             footnotes: FootnoteSet::new(),
-            list_depth: 0,
             skip_mode: FormatSkipMode::None,
-            in_description_details: false,
-            description_details_first_list: false,
+            disabled_rules: RuleSet::default(),
             warnings: Vec::new(),
+            trace: Vec::new(),
+            trace_line: 0,
             ordered_list_max_items: 0,
             source_ends_with_newline,
-            list_item_indent: String::new(),
-            blockquote_outer_indent: String::new(),
-            blockquote_entry_list_depth: 0,
             directive_proper_nouns: Vec::new(),
             directive_common_nouns: Vec::new(),
             code_formatter_callback: callback,
+            #[cfg(feature = "wasm")]
+            progress_callback: None,
+            #[cfg(feature = "bench")]
+            code_formatter_time: std::time::Duration::ZERO,
+        }
+    }
+
+    /// Report progress through the document's top-level blocks.
+    ///
+    /// This is a no-op unless a progress callback is set (WASM only).
+    pub fn report_progress(&mut self, done: usize, total: usize) {
+        #[cfg(feature = "wasm")]
+        if let Some(callback) = self.progress_callback.as_mut() {
+            callback(done, total);
         }
+        #[cfg(not(feature = "wasm"))]
+        let _ = (done, total);
     }
 
     /// Add a warning.
-    pub fn add_warning(&mut self, line: usize, message: String) {
-        self.warnings.push(Warning { line, message });
+    pub fn add_warning(&mut self, code: WarningCode, line: usize, column: usize, message: String) {
+        self.warnings
+            .push(Warning::new(code, line, column, message));
+    }
+
+    /// Record a formatting decision for the current block, if tracing is
+    /// enabled.  The `detail` is only computed then.
+    pub fn trace(&mut self, kind: TraceKind, detail: impl FnOnce() -> String) {
+        if self.options.trace {
+            self.trace.push(TraceEvent {
+                line: self.trace_line,
+                kind,
+                detail: detail(),
+            });
+        }
+    }
+
+    /// Like [`Self::extract_source`], but borrows the source of nodes that
+    /// span a single line instead of copying it.
+    ///
+    /// Inline nodes such as links are usually on a single line, so this
+    /// avoids an allocation for each of them.
+    pub fn source_span<'b>(&self, node: &'b AstNode<'b>) -> Option<Cow<'a, str>> {
+        let sourcepos = node.data.borrow().sourcepos;
+        if sourcepos.start.line == 0 || sourcepos.start.line != sourcepos.end.line {
+            return self.extract_source(node).map(Cow::Owned);
+        }
+        let line = self.source_lines.get(sourcepos.start.line - 1)?;
+        Some(Cow::Borrowed(safe_str_slice(
+            line,
+            sourcepos.start.column.saturating_sub(1),
+            sourcepos.end.column,
+        )))
     }
 
     /// Extract original source text for a node using its sourcepos.
@@ -441,24 +742,73 @@ impl<'a> Serializer<'a> {
     /// Add a reference link to the pending references.
     /// If collecting_footnote_content is true, adds to pending_footnote_references instead,
     /// along with the current footnote's reference line for proper flush timing.
+    /// Labels differing only in case or whitespace share a single definition,
+    /// labeled as the first of them is.
     pub fn add_reference(&mut self, label: String, url: String, title: String) {
-        let reference = ReferenceLink {
-            label: label.clone(),
-            url,
-            title,
-        };
+        let label = self.canonical_label(&label);
+        let key = reference_key(&label);
+        let reference = ReferenceLink { label, url, title };
         if self.footnotes.collecting_content {
-            self.footnotes.add_reference(label, reference);
+            self.footnotes.add_reference(key, reference);
+        } else if self.options.profile == Profile::Changelog
+            && super::changelog::is_version_label(&reference.label)
+        {
+            self.pending_version_references
+                .entry(key)
+                .or_insert(reference);
         } else {
-            self.pending_references.insert(label, reference);
+            self.pending_references.entry(key).or_insert(reference);
+        }
+    }
+
+    /// Returns `label` in the configured casing of reference labels.
+    pub fn canonical_label(&self, label: &str) -> String {
+        match self.options.reference_label_case {
+            ReferenceLabelCase::Preserve => label.to_string(),
+            ReferenceLabelCase::Lower => label.to_lowercase(),
         }
     }
 
+    /// Check if a reference `label` is already defined for a URL other than
+    /// `url`, either in the source or by an earlier link.  Labels are
+    /// compared as CommonMark matches them, i.e., case-insensitively.
+    pub fn is_label_taken(&self, label: &str, url: &str) -> bool {
+        let key = reference_key(label);
+        let pending = self
+            .pending_references
+            .values()
+            .chain(self.pending_version_references.values())
+            .chain(self.footnotes.pending_references.values().map(|(r, _)| r))
+            .any(|r| reference_key(&r.label) == key && r.url != url);
+        pending
+            || self.source_lines.iter().any(|line| {
+                let Some(rest) = line.trim_start().strip_prefix('[') else {
+                    return false;
+                };
+                let Some((defined, target)) = rest.split_once("]:") else {
+                    return false;
+                };
+                let target = target.split_whitespace().next().unwrap_or("");
+                let target = target
+                    .strip_prefix('<')
+                    .and_then(|t| t.strip_suffix('>'))
+                    .unwrap_or(target);
+                reference_key(defined) == key && target != url
+            })
+    }
+
     /// Check if a URL is external (starts with http:// or https://).
     pub fn is_external_url(url: &str) -> bool {
         url.starts_with("http://") || url.starts_with("https://")
     }
 
+    /// Check if an inline link to the URL should be converted to reference
+    /// style: it is external and `hongdown-disable reference-links` is not
+    /// in effect.
+    pub fn converts_to_reference(&self, url: &str) -> bool {
+        !self.disabled_rules.contains(FormatRule::ReferenceLinks) && Self::is_external_url(url)
+    }
+
     /// Get the emphasis delimiter character.
     /// Uses '_' if the content contains '*' (to avoid escaping).
     /// Otherwise, preserves the original delimiter from source, defaulting to '*'.
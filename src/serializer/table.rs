@@ -1,10 +1,15 @@
 //! Table serialization logic.
 
-use comrak::nodes::{AstNode, NodeTable, TableAlignment};
-use unicode_width::UnicodeWidthStr;
+use comrak::nodes::{AstNode, NodeTable, NodeValue, TableAlignment};
 
 use super::Serializer;
 use super::escape;
+use super::wrap;
+use crate::{DelimiterStyle, RaggedRows, WarningCode};
+
+/// Marks the end of a line of a multi-line table cell, right after its
+/// `<br>` tag.  Removed before the cell is written.
+pub(super) const LINE_BREAK_MARKER: char = '\u{E001}';
 
 impl<'a> Serializer<'a> {
     pub(super) fn serialize_table<'b>(&mut self, node: &'b AstNode<'b>, table: &NodeTable) {
@@ -21,7 +26,9 @@ impl<'a> Serializer<'a> {
 
         // Check source lines for column count mismatches
         // comrak normalizes column counts, so we need to check the source directly
-        self.check_table_source_columns(node, expected_cols, table_line);
+        if self.options.table_ragged_rows == RaggedRows::Warn {
+            self.check_table_source_columns(node, expected_cols, table_line);
+        }
 
         // Collect cell contents (with full inline formatting) and calculate max widths
         let mut all_cells: Vec<Vec<String>> = Vec::new();
@@ -33,13 +40,28 @@ impl<'a> Serializer<'a> {
             for (i, cell) in row.children().enumerate() {
                 // Use collect_inline_content to preserve links and formatting
                 let mut content = String::new();
+                self.in_table_cell = true;
                 self.collect_inline_content(cell, &mut content);
+                self.in_table_cell = false;
+                self.check_table_cell(cell, &mut content);
                 // Escape pipe characters in table cells to prevent cell boundary confusion
-                let content = escape::escape_table_cell(&content);
+                let content = escape::escape_table_cell(&content, self.options.table_escape_html);
+                // A cell with `<br>` tags is rendered as multiple lines, so
+                // only its longest line counts toward the column width; the
+                // cell itself may then overflow the column
+                let width = content
+                    .split(LINE_BREAK_MARKER)
+                    .map(wrap::display_width)
+                    .max()
+                    .unwrap_or(0);
                 if i < col_widths.len() {
-                    col_widths[i] = col_widths[i].max(content.width());
+                    col_widths[i] = col_widths[i].max(width);
                 }
-                row_cells.push(content);
+                row_cells.push(content.replace(LINE_BREAK_MARKER, ""));
+            }
+
+            if self.options.table_ragged_rows == RaggedRows::Merge {
+                self.merge_excess_cells(row, expected_cols, &mut row_cells, &mut col_widths);
             }
 
             all_cells.push(row_cells);
@@ -52,9 +74,6 @@ impl<'a> Serializer<'a> {
 
         // Output header row
         if let Some(header_cells) = all_cells.first() {
-            if self.in_block_quote {
-                self.output.push_str("> ");
-            }
             self.output.push('|');
             for (i, cell) in header_cells.iter().enumerate() {
                 self.output.push(' ');
@@ -68,13 +87,25 @@ impl<'a> Serializer<'a> {
         }
 
         // Output separator row with alignment
-        if self.in_block_quote {
-            self.output.push_str("> ");
-        }
+        // Left is the default alignment of most renderers, so the colons of
+        // a table aligned entirely to the left can be dropped
+        let drop_colons = !self.options.table_preserve_left_alignment
+            && alignments.iter().all(|a| *a == TableAlignment::Left);
+        let style = self.options.table_delimiter_style;
         self.output.push('|');
         for (i, alignment) in alignments.iter().enumerate() {
-            self.output.push(' ');
-            let width = col_widths.get(i).copied().unwrap_or(3);
+            if style != DelimiterStyle::Minimal {
+                self.output.push(' ');
+            }
+            let width = match style {
+                DelimiterStyle::Padded => col_widths.get(i).copied().unwrap_or(3),
+                DelimiterStyle::Minimal | DelimiterStyle::Fixed => 3,
+            };
+            let alignment = if drop_colons {
+                TableAlignment::None
+            } else {
+                *alignment
+            };
             match alignment {
                 TableAlignment::Left => {
                     self.output.push(':');
@@ -93,15 +124,16 @@ impl<'a> Serializer<'a> {
                     self.output.push_str(&"-".repeat(width));
                 }
             }
-            self.output.push_str(" |");
+            if style == DelimiterStyle::Minimal {
+                self.output.push('|');
+            } else {
+                self.output.push_str(" |");
+            }
         }
         self.output.push('\n');
 
         // Output data rows (skip header)
         for row_cells in all_cells.iter().skip(1) {
-            if self.in_block_quote {
-                self.output.push_str("> ");
-            }
             self.output.push('|');
             for (i, cell) in row_cells.iter().enumerate() {
                 self.output.push(' ');
@@ -119,6 +151,75 @@ impl<'a> Serializer<'a> {
         // Table rows are handled by serialize_table
     }
 
+    /// Merge the cells of a row beyond the header's column count, which
+    /// comrak drops, into the last cell.  The excess cells are recovered from
+    /// the source as they are written.
+    fn merge_excess_cells<'b>(
+        &self,
+        row: &'b AstNode<'b>,
+        expected_cols: usize,
+        row_cells: &mut [String],
+        col_widths: &mut [usize],
+    ) {
+        let sourcepos = row.data.borrow().sourcepos;
+        let Some(line) = sourcepos
+            .start
+            .line
+            .checked_sub(1)
+            .and_then(|index| self.source_lines.get(index))
+            .and_then(|line| line.get(sourcepos.start.column.saturating_sub(1)..))
+        else {
+            return;
+        };
+        let raw_cells = split_row(line);
+        if raw_cells.len() <= expected_cols {
+            return;
+        }
+        let (Some(last), Some(width)) = (row_cells.last_mut(), col_widths.last_mut()) else {
+            return;
+        };
+        for excess in raw_cells[expected_cols..].iter().filter(|c| !c.is_empty()) {
+            if !last.is_empty() {
+                last.push_str(" \\| ");
+            }
+            last.push_str(excess);
+        }
+        *width = (*width).max(wrap::display_width(last));
+    }
+
+    /// Warn about cell content that cannot be represented safely in a pipe
+    /// table.  Line breaks, e.g., from custom inline renderers, are replaced
+    /// with spaces, since a row must fit on a single line.
+    fn check_table_cell<'b>(&mut self, cell: &'b AstNode<'b>, content: &mut String) {
+        let sourcepos = cell.data.borrow().sourcepos;
+        if content.contains('\n') {
+            *content = content.replace('\n', " ");
+            self.add_warning(
+                WarningCode::TableCellLineBreak,
+                sourcepos.start.line,
+                sourcepos.start.column,
+                "table cell contains a line break, which cannot be represented in a pipe \
+                 table; replaced it with a space"
+                    .to_string(),
+            );
+        }
+        if !self.options.table_escape_html
+            && let Some(tag) = find_unclosed_html(cell)
+        {
+            self.add_warning(
+                WarningCode::TableCellHtml,
+                sourcepos.start.line,
+                sourcepos.start.column,
+                format!(
+                    "inline HTML `{}` is not closed within its table cell, which may break \
+                     the table when rendered; enable `escape_html` in the `[table]` \
+                     configuration to escape it",
+                    tag
+                ),
+            );
+        }
+    }
+
     /// Check source lines for table column count mismatches.
     /// This detects issues that comrak normalizes away, like unescaped pipes in cells.
     fn check_table_source_columns<'b>(
@@ -162,7 +263,9 @@ impl<'a> Serializer<'a> {
 
             if pipe_count > expected_pipes_full {
                 self.add_warning(
+                    WarningCode::TablePipeCount,
                     line_num,
+                    1,
                     format!(
                         "table row has {} pipe characters, expected {} for {} columns; \
                          unescaped `|` in cell content? (table starts at line {})",
@@ -171,7 +274,9 @@ impl<'a> Serializer<'a> {
                 );
             } else if pipe_count < expected_pipes_min {
                 self.add_warning(
+                    WarningCode::TablePipeCount,
                     line_num,
+                    1,
                     format!(
                         "table row has {} pipe characters, expected at least {} for {} columns \
                          (table starts at line {})",
@@ -188,7 +293,7 @@ impl<'a> Serializer<'a> {
 /// The `width` parameter is the target display width (in terminal columns).
 /// Full-width characters (CJK, emoji, etc.) take 2 display columns each.
 fn format_cell_aligned(content: &str, width: usize, alignment: TableAlignment) -> String {
-    let content_width = content.width();
+    let content_width = wrap::display_width(content);
     let padding = width.saturating_sub(content_width);
 
     match alignment {
@@ -226,3 +331,90 @@ fn count_unescaped_pipes(line: &str) -> usize {
 
     count
 }
+
+/// Split a table row into the trimmed source of its cells, without the
+/// optional leading and trailing pipes.  Escaped pipes do not split cells.
+fn split_row(line: &str) -> Vec<&str> {
+    let line = line.trim();
+    let mut cells = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '|' {
+            cells.push(line[start..index].trim());
+            start = index + 1;
+        }
+    }
+    let rest = line[start..].trim();
+    if !rest.is_empty() {
+        cells.push(rest);
+    }
+    if line.starts_with('|') && !cells.is_empty() {
+        cells.remove(0);
+    }
+    cells
+}
+
+/// Check whether an inline HTML tag is a line break: `<br>`, `<br/>`, or
+/// `<br />`, in any case.
+pub(crate) fn is_br_tag(html: &str) -> bool {
+    let Some(rest) = html.strip_prefix('<') else {
+        return false;
+    };
+    if !rest
+        .get(..2)
+        .is_some_and(|name| name.eq_ignore_ascii_case("br"))
+    {
+        return false;
+    }
+    let rest = rest[2..].trim_start();
+    let rest = rest.strip_prefix('/').unwrap_or(rest).trim_start();
+    rest == ">"
+}
+
+/// HTML elements that have no closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Find the first inline HTML tag in a table cell that is not balanced within
+/// the cell: an opening tag without a closing tag, or the other way around.
+fn find_unclosed_html<'b>(cell: &'b AstNode<'b>) -> Option<String> {
+    let mut open: Vec<(String, String)> = Vec::new();
+    for node in cell.descendants() {
+        let NodeValue::HtmlInline(html) = &node.data.borrow().value else {
+            continue;
+        };
+        let (closing, rest) = match html.strip_prefix("</") {
+            Some(rest) => (true, rest),
+            None => match html.strip_prefix('<') {
+                Some(rest) => (false, rest),
+                None => continue,
+            },
+        };
+        let name: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect::<String>()
+            .to_ascii_lowercase();
+        // Comments, declarations, and processing instructions are complete
+        // within a single node
+        if name.is_empty() {
+            continue;
+        }
+        if closing {
+            match open.iter().rposition(|(open_name, _)| *open_name == name) {
+                Some(index) => open.truncate(index),
+                None => return Some(html.clone()),
+            }
+        } else if !html.ends_with("/>") && !VOID_ELEMENTS.contains(&name.as_str()) {
+            open.push((name, html.clone()));
+        }
+    }
+    open.into_iter().next().map(|(_, html)| html)
+}
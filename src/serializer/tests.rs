@@ -1,5 +1,8 @@
 use super::*;
-use crate::{LineWidth, ThematicBreakStyle};
+use crate::{
+    AbbreviationPlacement, AutolinkHost, ByteOrderMark, Extensions, FinalNewline, LineEnding,
+    LineWidth, MentionStyle, ThematicBreakStyle, TitleCase, UnicodeNormalization, WarningCode,
+};
 use comrak::{Arena, Options as ComrakOptions, parse_document};
 
 fn comrak_options() -> ComrakOptions<'static> {
@@ -315,6 +318,222 @@ fn test_hash_numeric_references_sorted() {
     );
 }
 
+#[test]
+fn test_renumber_references() {
+    let input = "See [this][3] and [that][1], then [3] again and [foo][bar].
+
+Also ![img][10].
+
+[1]: https://one.example/
+[3]: https://three.example/
+[10]: https://ten.example/img.png
+[bar]: https://bar.example/
+";
+    let options = Options {
+        renumber_references: true,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "See [this][1] and [that][2], then [3][1] again and [foo][bar].
+
+Also ![img][3].
+
+[bar]: https://bar.example/
+[1]: https://three.example/
+[2]: https://one.example/
+[3]: https://ten.example/img.png
+"
+    );
+
+    // Labels are kept as they are unless enabled
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert!(result.starts_with("See [this][3] and [that][1], then [3] again"));
+}
+
+#[test]
+fn test_renumber_converted_references() {
+    let input = "See [127](https://x.example/127) and [128](https://x.example/128), then [5].
+
+[5]: https://five.example/
+";
+    let options = Options {
+        renumber_references: true,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "See [127][1] and [128][2], then [5][3].
+
+[1]: https://x.example/127
+[2]: https://x.example/128
+[3]: https://five.example/
+"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+}
+
+#[test]
+fn test_changelog_profile() {
+    use crate::format_with_warnings;
+
+    let input = "# Changelog
+
+## [Unreleased]
+
+### Fixed in 1.0.0-RC.2 Release
+
+- See [docs](https://example.com/docs).
+
+## [1.0.0] - 2017-06-20
+
+### Added
+
+- Initial release.
+
+[1.0.0]: https://example.com/compare/v0.3.0...v1.0.0
+[unreleased]: https://example.com/compare/v1.0.0...HEAD
+";
+    let options = Options {
+        profile: crate::Profile::Changelog,
+        heading_sentence_case: true,
+        ..Options::default()
+    };
+    let result = format_with_warnings(input, &options).unwrap();
+    assert_eq!(
+        result.output,
+        "Changelog
+=========
+
+[Unreleased]
+------------
+
+### Fixed in 1.0.0-RC.2 release
+
+ -  See [docs].
+
+[docs]: https://example.com/docs
+
+
+[1.0.0] - 2017-06-20
+--------------------
+
+### Added
+
+ -  Initial release.
+
+[Unreleased]: https://example.com/compare/v1.0.0...HEAD
+[1.0.0]: https://example.com/compare/v0.3.0...v1.0.0
+"
+    );
+    assert!(result.warnings.is_empty());
+    assert_eq!(
+        crate::format(&result.output, &options).unwrap(),
+        result.output
+    );
+
+    // Headings of versions without links are not undefined references
+    let result = format_with_warnings("## [Unreleased]\n\n## [1.0.0]\n", &options).unwrap();
+    assert!(result.warnings.is_empty());
+}
+
+#[test]
+fn test_issue_reference_does_not_start_line() {
+    let input = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do fixes #123 now.";
+    assert_eq!(
+        parse_and_serialize_with_width(input, 70),
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do\nfixes #123 now.\n"
+    );
+    let options = Options {
+        line_width: LineWidth::new(70).unwrap(),
+        autolink_host: AutolinkHost::None,
+        ..Options::default()
+    };
+    assert_eq!(
+        parse_and_serialize_with_options(input, &options),
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do fixes\n#123 now.\n"
+    );
+}
+
+#[test]
+fn test_issue_reference_in_link_label() {
+    // The label keeps its plain spaces, so the conflicting link is not
+    // mistaken for a new reference
+    let input = "See [A #1] and [A #1](https://e.com/2).\n\n[A #1]: https://e.com/1";
+    let expected = "See [A #1] and [A #1](https://e.com/2).\n\n[A #1]: https://e.com/1\n";
+    let options = Options::default();
+    assert_eq!(crate::format(input, &options).unwrap(), expected);
+    assert_eq!(crate::format(expected, &options).unwrap(), expected);
+}
+
+#[test]
+fn test_repository_reference_underscores_not_escaped() {
+    let input = "See my_org/my_repo#12 and my_org/my_repo@a1b2c3d, but not my\\_var.";
+    assert_eq!(
+        crate::format(input, &Options::default()).unwrap(),
+        "See my_org/my_repo#12 and my_org/my_repo@a1b2c3d, but not my\\_var.\n"
+    );
+}
+
+#[test]
+fn test_issue_reference_labels_keep_hash() {
+    let input = "See [#123] and [#45][].\n\n[#123]: https://example.com/123\n[#45]: https://example.com/45\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert!(
+        result.starts_with("See [#123] and [#45][].\n"),
+        "Issue reference labels should keep their #, got:\n{}",
+        result
+    );
+}
+
+#[test]
+fn test_mentions() {
+    let input = "Thanks @octocat for [@link](https://example.com/), `@code`, and me@example.com.";
+    assert_eq!(
+        crate::format(input, &Options::default()).unwrap(),
+        "Thanks @octocat for [@link], `@code`, and me@example.com.\n\n\
+         [@link]: https://example.com/\n"
+    );
+    let options = Options {
+        mentions: MentionStyle::Escape,
+        ..Options::default()
+    };
+    assert_eq!(
+        crate::format(input, &options).unwrap(),
+        "Thanks \\@octocat for [@link], `@code`, and me@example.com.\n\n\
+         [@link]: https://example.com/\n"
+    );
+    let options = Options {
+        mentions: MentionStyle::Code,
+        ..Options::default()
+    };
+    assert_eq!(
+        crate::format(input, &options).unwrap(),
+        "Thanks `@octocat` for [@link], `@code`, and me@example.com.\n\n\
+         [@link]: https://example.com/\n"
+    );
+}
+
+#[test]
+fn test_renumber_references_keeps_visible_text() {
+    // Shortcut and collapsed references become full references instead of
+    // having their text renumbered
+    let input =
+        "See PR [222] and [222][] and [x][5].\n\n[222]: https://e.com/222\n[5]: https://e.com/5\n";
+    let options = Options {
+        renumber_references: true,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "See PR [222][1] and [222][1] and [x][2].\n\n[1]: https://e.com/222\n[2]: https://e.com/5\n"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+}
+
 fn parse_and_serialize_with_frontmatter(input: &str) -> String {
     let arena = Arena::new();
     let mut options = ComrakOptions::default();
@@ -324,6 +543,19 @@ fn parse_and_serialize_with_frontmatter(input: &str) -> String {
     serialize_with_source(root, &format_options, None)
 }
 
+#[test]
+fn test_front_matter_key_order() {
+    let options = Options {
+        front_matter_key_order: vec!["title".to_string(), "date".to_string()],
+        ..Options::default()
+    };
+    let input = "---\ndate: 2025-01-01\ntitle: Hello\n---\n\nText\n";
+    assert_eq!(
+        crate::format(input, &options).unwrap(),
+        "---\ntitle: Hello\ndate: 2025-01-01\n---\n\nText\n"
+    );
+}
+
 #[test]
 fn test_serialize_yaml_front_matter() {
     let input = "---\ntitle: Hello\nauthor: World\n---\n\n# Heading";
@@ -341,6 +573,24 @@ fn test_serialize_yaml_front_matter_only() {
     assert_eq!(result, "---\ntitle: Test\n---\n\nSome content.\n");
 }
 
+#[test]
+fn test_serialize_front_matter_normalize_dates() {
+    use crate::format_with_warnings;
+
+    let options = Options {
+        front_matter_normalize_dates: true,
+        ..Options::default()
+    };
+    let input = "---\ntitle: Test\ndate: March 4, 2025\nupdated: soon\n---\n\nSome content.";
+    let result = format_with_warnings(input, &options).unwrap();
+    assert_eq!(
+        result.output,
+        "---\ntitle: Test\ndate: 2025-03-04\nupdated: soon\n---\n\nSome content.\n"
+    );
+    let codes: Vec<_> = result.warnings.iter().map(|w| (w.code, w.line)).collect();
+    assert_eq!(codes, [(WarningCode::FrontMatterDate, 4)]);
+}
+
 #[test]
 fn test_serialize_two_blank_lines_before_h2() {
     let input = "# Title\n\nParagraph.\n\n## Section";
@@ -672,6 +922,65 @@ fn test_serialize_definition_list_multiple_definitions() {
     assert!(result.contains(":   Second definition"));
 }
 
+#[test]
+fn test_definition_list_extension_disabled() {
+    let input = "Term\n: not a definition\n";
+    let options = Options {
+        extensions: Extensions {
+            description_lists: false,
+            ..Extensions::default()
+        },
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(result, "Term\n: not a definition\n");
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(result, "Term\n:   not a definition\n");
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_wikilinks_extension() {
+    let input = "See ![[my_note#Some section|the alias]] and [[snake_case note]].\n\n\
+                 ![[diagram_v2.png|300]]\n";
+    let options = Options {
+        line_width: LineWidth::new(30).unwrap(),
+        extensions: Extensions {
+            wikilinks: true,
+            ..Extensions::default()
+        },
+        ..Options::default()
+    };
+    let result = crate::format_with_warnings(input, &options).unwrap();
+    assert_eq!(
+        result.output,
+        "See\n![[my_note#Some section|the alias]]\nand [[snake_case note]].\n\n\
+         ![[diagram_v2.png|300]]\n"
+    );
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+    // Without the extension, they are ordinary text with undefined references
+    let result = crate::format_with_warnings(input, &Options::default()).unwrap();
+    assert!(result.output.contains("[[snake\\_case note]]"));
+    assert!(!result.warnings.is_empty());
+}
+
+#[test]
+fn test_definition_marker_and_indent_configurable() {
+    let input = "Term\n:   First paragraph.\n\n    Second paragraph.\n\n     -  Item\n";
+    let options = Options {
+        definition_marker_spaces: DefinitionMarkerSpaces::new(1).unwrap(),
+        definition_indent_width: IndentWidth::new(2).unwrap(),
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Term\n: First paragraph.\n\n  Second paragraph.\n\n   -  Item\n"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+}
+
 fn parse_and_serialize_with_alerts(input: &str) -> String {
     let arena = Arena::new();
     let mut options = ComrakOptions::default();
@@ -705,6 +1014,123 @@ fn test_serialize_github_caution_alert() {
     assert!(result.contains("> Be careful!"));
 }
 
+#[test]
+fn test_alert_title_preserved() {
+    let input = "> [!note] Custom title\n> Body.";
+    let result = parse_and_serialize_with_source(input);
+    assert_eq!(result, "> [!NOTE] Custom title\n> Body.\n");
+}
+
+#[test]
+fn test_custom_alert_type_preserved() {
+    // Types other than GitHub's five are kept verbatim, including
+    // Obsidian's fold markers and titles
+    let input = "> [!faq]- Why *not*?\n> Because the body is long enough.\n>\n> More.";
+    let result = parse_and_serialize_with_source(input);
+    assert_eq!(
+        result,
+        "> [!faq]- Why *not*?\n> Because the body is long enough.\n>\n> More.\n"
+    );
+}
+
+#[test]
+fn test_alert_synonyms() {
+    let options = Options {
+        alert_synonyms: [
+            (
+                "info".to_string(),
+                AlertName::new("NOTE".to_string()).unwrap(),
+            ),
+            (
+                "Tip".to_string(),
+                AlertName::new("HINT".to_string()).unwrap(),
+            ),
+        ]
+        .into(),
+        ..Options::default()
+    };
+    let input = "> [!INFO] Heads up\n> Info text.\n\n> [!TIP]\n> Tip text.";
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "> [!NOTE] Heads up\n> Info text.\n\n> [!HINT]\n> Tip text.\n"
+    );
+}
+
+#[test]
+fn test_admonition_body_formatted() {
+    let input =
+        "!!! warning \"Watch out\"\n\n    Some *body* text...\n\n    * one\n    * two\n\nAfter.\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(
+        result,
+        "!!! warning \"Watch out\"\n\n    Some *body* text…\n\n     -  one\n     -  two\n\nAfter.\n"
+    );
+}
+
+#[test]
+fn test_admonition_body_wrapped() {
+    let input = "!!! note\n    This body follows the header directly and is long enough to be wrapped at the line width.\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(
+        result,
+        "!!! note\n    This body follows the header directly and is long enough to be wrapped at\n    the line width.\n"
+    );
+}
+
+#[test]
+fn test_admonition_nested() {
+    let input = " -  Item\n\n    !!! tip\n        Tip in a list.\n\n        !!! note inline end\n            Nested.\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(result, input);
+}
+
+#[test]
+fn test_admonition_header_only() {
+    // The title is not curled, since it is part of the syntax
+    let input = "!!! danger \"Only a title\"\n\nNot \"indented\".\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(result, "!!! danger \"Only a title\"\n\nNot “indented”.\n");
+}
+
+#[test]
+fn test_admonition_body_link_definition() {
+    let input = "!!! note\n    See [the guide].\n\n[the guide]: https://example.com/\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(result, input);
+}
+
+#[test]
+fn test_fenced_div_content_formatted() {
+    let input = "::: {.note #intro}\nA paragraph glued to the fences, which is long enough to be wrapped here.\n* item\n:::\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(
+        result,
+        "::: {.note #intro}\nA paragraph glued to the fences, which is long enough to be wrapped here.\n\n -  item\n:::\n"
+    );
+}
+
+#[test]
+fn test_fenced_div_nested() {
+    let input = ":::: outer\n\n::: inner\nInner.\n:::\n\n~~~~\n:::\n~~~~\n\n::::\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(result, input);
+}
+
+#[test]
+fn test_fenced_div_in_list_item() {
+    let input = " -  Item\n\n    ::: aside\n    Aside in a list.\n    :::\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(result, input);
+}
+
+#[test]
+fn test_fenced_div_unclosed() {
+    let input = "::: note\nText \"here\".\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(result, "::: note\nText “here”.\n");
+}
+
 fn parse_and_serialize_with_footnotes(input: &str) -> String {
     let arena = Arena::new();
     let mut options = ComrakOptions::default();
@@ -1033,40 +1459,308 @@ fn test_serialize_image_inside_link_badge_style() {
 }
 
 #[test]
-fn test_serialize_underscore_always_escaped() {
-    // Underscores are always escaped for safety and consistency across parsers
-    let input = "Use ALL_CAPS for constants.";
-    let result = parse_and_serialize(input);
-    assert_eq!(result, "Use ALL\\_CAPS for constants.\n");
-}
-
-#[test]
-fn test_serialize_underscore_at_boundary_escaped() {
-    // Underscores at word boundaries should be escaped
-    let input = r"\_start and end\_";
-    let result = parse_and_serialize(input);
-    assert_eq!(result, "\\_start and end\\_\n");
-}
+fn test_badge_layout() {
+    let input = "[![npm](https://img.shields.io/npm/v/foo.svg)](https://npmjs.com/foo)
+[![CI][ci-badge]][ci] [![License](https://img.shields.io/badge/MIT.svg)](LICENSE)
 
-#[test]
-fn test_serialize_autolink_preserved() {
-    // Autolinks <url> should be preserved as autolink format, not reference style
-    let input = "Visit <https://example.com/> for more info.";
-    let result = parse_and_serialize(input);
-    assert_eq!(result, "Visit <https://example.com/> for more info.\n");
-}
+[ci-badge]: https://ci.example/badge.svg
+[ci]: https://ci.example/
+";
+    let options = Options {
+        badge_layout: crate::BadgeLayout::Stack,
+        ..Options::default()
+    };
+    let output = crate::format(input, &options).unwrap();
+    assert_eq!(
+        output,
+        "[![npm][npm badge]][npm]
+[![CI][CI badge]][CI]
+[![License][License badge]](LICENSE)
+
+[npm badge]: https://img.shields.io/npm/v/foo.svg
+[npm]: https://npmjs.com/foo
+[CI badge]: https://ci.example/badge.svg
+[CI]: https://ci.example/
+[License badge]: https://img.shields.io/badge/MIT.svg
+"
+    );
+    assert_eq!(crate::format(&output, &options).unwrap(), output);
 
-#[test]
-fn test_serialize_nested_list_wrap_continuation() {
-    // Nested list items should wrap with proper continuation indent
-    // accounting for the parent list's indentation
-    let input = " 1. First\n     -  This is a very long nested item that should wrap with proper eight-space continuation.";
-    let result = parse_and_serialize_with_width(input, 80);
-    // Continuation should have 8 spaces (4 for parent + 4 for list item content)
+    let options = Options {
+        badge_layout: crate::BadgeLayout::Line,
+        ..Options::default()
+    };
     assert!(
-        result.contains("\n        "),
-        "Nested list continuation should have 8-space indent, got:\n{}",
-        result
+        crate::format(input, &options)
+            .unwrap()
+            .starts_with("[![npm][npm badge]][npm] [![CI][CI badge]][CI] [![License]")
+    );
+}
+
+#[test]
+fn test_badge_layout_keeps_taken_labels() {
+    // The label `CI` already belongs to another link, so the badge keeps its
+    // own labels
+    let input = "[![CI][ci-badge]][ci-link]
+
+See [CI] for details.
+
+[ci-badge]: https://ci.example/badge.svg
+[ci-link]: https://ci.example/
+[CI]: https://other.example/
+";
+    let options = Options {
+        badge_layout: crate::BadgeLayout::Stack,
+        ..Options::default()
+    };
+    let output = crate::format(input, &options).unwrap();
+    assert!(output.starts_with("[![CI][ci-badge]][ci-link]\n"));
+    assert!(output.contains("[CI]: https://other.example/"));
+
+    // Paragraphs with anything but badges are left alone
+    let input = "Built with [![Rust](https://rust.example/badge.svg)](https://rust.example/).";
+    assert_eq!(
+        crate::format(input, &options).unwrap(),
+        crate::format(input, &Options::default()).unwrap()
+    );
+}
+
+#[test]
+fn test_link_title_style() {
+    use crate::LinkTitleStyle;
+
+    let input =
+        "[a](/a \"it's \\\"quoted\\\" (here)\") ![b](/b.png 'b') [c](https://c.example/ (c))";
+    let format = |style| {
+        let options = Options {
+            link_title_style: style,
+            ..Options::default()
+        };
+        crate::format(input, &options).unwrap()
+    };
+    assert_eq!(
+        format(LinkTitleStyle::Double),
+        "[a](/a \"it's \\\"quoted\\\" (here)\") ![b](/b.png \"b\") [c]\n\n[c]: https://c.example/ \"c\"\n"
+    );
+    assert_eq!(
+        format(LinkTitleStyle::Single),
+        "[a](/a 'it\\'s \"quoted\" (here)') ![b](/b.png 'b') [c]\n\n[c]: https://c.example/ 'c'\n"
+    );
+    assert_eq!(
+        format(LinkTitleStyle::Paren),
+        "[a](/a (it's \"quoted\" \\(here\\))) ![b](/b.png (b)) [c]\n\n[c]: https://c.example/ (c)\n"
+    );
+}
+
+#[test]
+fn test_reference_labels_differing_in_case_share_definition() {
+    let input = "[Docs] and [docs] and [see][DOCS].\n\n[docs]: /x\n";
+    assert_eq!(
+        crate::format(input, &Options::default()).unwrap(),
+        "[Docs] and [docs] and [see][DOCS].\n\n[Docs]: /x\n"
+    );
+    let input = "See [API](https://a.example/) and [api](https://a.example/).\n";
+    assert_eq!(
+        crate::format(input, &Options::default()).unwrap(),
+        "See [API] and [api].\n\n[API]: https://a.example/\n"
+    );
+}
+
+#[test]
+fn test_conflicting_external_link_stays_inline() {
+    let input = "[Docs](https://a.example/) and [docs](https://b.example/)\n";
+    assert_eq!(
+        crate::format(input, &Options::default()).unwrap(),
+        "[Docs] and [docs](https://b.example/)\n\n[Docs]: https://a.example/\n"
+    );
+    let input = "[docs](https://a.example/) and [DOCS]\n\n[docs]: https://b.example/\n";
+    assert_eq!(
+        crate::format(input, &Options::default()).unwrap(),
+        "[docs](https://a.example/) and [DOCS]\n\n[DOCS]: https://b.example/\n"
+    );
+}
+
+#[test]
+fn test_reference_label_case_lower() {
+    use crate::ReferenceLabelCase;
+
+    let input =
+        "[Docs] and [see][API] and [Spec](https://spec.example/).\n\n[docs]: /x\n[API]: /api\n";
+    let options = Options {
+        reference_label_case: ReferenceLabelCase::Lower,
+        ..Options::default()
+    };
+    assert_eq!(
+        crate::format(input, &options).unwrap(),
+        "[Docs] and [see][api] and [Spec].\n\n[docs]: /x\n[api]: /api\n[spec]: https://spec.example/\n"
+    );
+}
+
+#[test]
+fn test_link_reference_style_collapsed() {
+    use crate::LinkReferenceStyle;
+
+    let input =
+        "[a] ![b] [c][] [d][x] [e](https://e.example/)\n\n[a]: /a\n[b]: /b.png\n[c]: /c\n[x]: /d\n";
+    let options = Options {
+        link_reference_style: LinkReferenceStyle::Collapsed,
+        ..Options::default()
+    };
+    assert_eq!(
+        crate::format(input, &options).unwrap(),
+        "[a][] ![b][] [c][] [d][x] [e][]\n\n[a]: /a\n[b]: /b.png\n[c]: /c\n[x]: /d\n[e]: https://e.example/\n"
+    );
+    assert_eq!(
+        crate::format(input, &Options::default()).unwrap(),
+        "[a] ![b] [c][] [d][x] [e]\n\n[a]: /a\n[b]: /b.png\n[c]: /c\n[x]: /d\n[e]: https://e.example/\n"
+    );
+}
+
+#[test]
+fn test_normalize_urls() {
+    use crate::format_with_warnings;
+
+    let input = "[a](<docs/my file.md>) [b](HTTPS://Example.COM:443/x) \
+<HTTPS://Example.COM/> [c](https://example.com/100%)";
+    let options = Options {
+        normalize_urls: true,
+        ..Options::default()
+    };
+    let result = format_with_warnings(input, &options).unwrap();
+    assert_eq!(
+        result.output,
+        "[a](docs/my%20file.md) [b] <HTTPS://Example.COM/> [c]
+
+[b]: https://example.com/x
+[c]: https://example.com/100%
+"
+    );
+    let codes: Vec<_> = result.warnings.iter().map(|w| (w.code, w.column)).collect();
+    assert_eq!(codes, [(WarningCode::UrlNormalization, 78)]);
+    assert!(crate::verify_equivalent(input, &result.output, &options).is_ok());
+}
+
+#[test]
+fn test_serialize_angle_bracket_destinations() {
+    let input = "See [a](<my file.md>), [b](<foo(bar>), and ![c](<x y.png>).
+
+Read [d].
+
+[d]: <docs/a b.md>
+";
+    let expected = "See [a](<my file.md>), [b](<foo(bar>), and ![c](<x y.png>).
+
+Read [d].
+
+[d]: <docs/a b.md>
+";
+    assert_eq!(crate::format(input, &Options::default()).unwrap(), expected);
+}
+
+#[test]
+fn test_serialize_balanced_parentheses_destination() {
+    let input = "See [Rust](/wiki/Rust_(language)).\n";
+    assert_eq!(crate::format(input, &Options::default()).unwrap(), input);
+}
+
+#[test]
+fn test_angle_bracket_destination_is_not_wrapped() {
+    let input = "Lorem ipsum dolor sit amet consectetur adipiscing elit sed do eiusmod \
+see [the guide](<docs/my long file name.md>) for more.
+";
+    let options = Options {
+        line_width: LineWidth::new(60).unwrap(),
+        ..Options::default()
+    };
+    let output = crate::format(input, &options).unwrap();
+    assert!(output.contains("[the guide](<docs/my long file name.md>)"));
+    assert_eq!(crate::format(&output, &options).unwrap(), output);
+}
+
+#[test]
+fn test_link_base() {
+    let input = "See [a](a.md), [b](docs/b.md), [c](/c.md), and ![d](./img/d.png).
+
+Read [e] and [f](../f.md).
+
+[e]: guide/e.md#usage
+";
+    let options = Options {
+        link_base: "docs".to_string(),
+        ..Options::default()
+    };
+    let result = crate::format_with_warnings(input, &options).unwrap();
+    assert_eq!(
+        result.output,
+        "See [a](docs/a.md), [b](docs/b.md), [c](/c.md), and ![d](docs/img/d.png).
+
+Read [e] and [f](../f.md).
+
+[e]: docs/guide/e.md#usage
+"
+    );
+    let warnings: Vec<_> = result
+        .warnings
+        .iter()
+        .map(|w| (w.code, w.line, w.message.as_str()))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![(
+            WarningCode::LinkBase,
+            3,
+            "URL \"../f.md\" is left as it is, since it points outside of the link base \"docs\""
+        )]
+    );
+    assert_eq!(
+        crate::format(&result.output, &options).unwrap(),
+        result.output
+    );
+}
+
+#[test]
+fn test_trailing_html_blocks_stay_separate() {
+    let input = "<div>\na\n</div>\n\n<div>\nb\n</div>\n";
+    assert_eq!(crate::format(input, &Options::default()).unwrap(), input);
+    let input = "Text.\n\n<!-- a -->\n<!-- b -->\n";
+    assert_eq!(crate::format(input, &Options::default()).unwrap(), input);
+}
+
+#[test]
+fn test_serialize_underscore_always_escaped() {
+    // Underscores are always escaped for safety and consistency across parsers
+    let input = "Use ALL_CAPS for constants.";
+    let result = parse_and_serialize(input);
+    assert_eq!(result, "Use ALL\\_CAPS for constants.\n");
+}
+
+#[test]
+fn test_serialize_underscore_at_boundary_escaped() {
+    // Underscores at word boundaries should be escaped
+    let input = r"\_start and end\_";
+    let result = parse_and_serialize(input);
+    assert_eq!(result, "\\_start and end\\_\n");
+}
+
+#[test]
+fn test_serialize_autolink_preserved() {
+    // Autolinks <url> should be preserved as autolink format, not reference style
+    let input = "Visit <https://example.com/> for more info.";
+    let result = parse_and_serialize(input);
+    assert_eq!(result, "Visit <https://example.com/> for more info.\n");
+}
+
+#[test]
+fn test_serialize_nested_list_wrap_continuation() {
+    // Nested list items should wrap with proper continuation indent
+    // accounting for the parent list's indentation
+    let input = " 1. First\n     -  This is a very long nested item that should wrap with proper eight-space continuation.";
+    let result = parse_and_serialize_with_width(input, 80);
+    // Continuation should have 8 spaces (4 for parent + 4 for list item content)
+    assert!(
+        result.contains("\n        "),
+        "Nested list continuation should have 8-space indent, got:\n{}",
+        result
     );
 }
 
@@ -1150,6 +1844,150 @@ fn test_directive_disable_enable() {
     );
 }
 
+#[test]
+fn test_directive_disable_wrap_rule() {
+    // Only wrapping is disabled; other formatting still applies
+    let long_line = "This is a deliberately long line that would normally be wrapped at eighty columns by hongdown.";
+    let input = format!(
+        "<!-- hongdown-disable wrap -->\n\n{}\n\n*   item\n\n<!-- hongdown-enable wrap -->\n\n{}\n",
+        long_line, long_line
+    );
+    let result = parse_and_serialize_with_source(&input);
+    assert!(
+        result.contains(&format!("{}\n\n -  item", long_line)),
+        "wrap should be disabled but lists formatted, got:\n{}",
+        result
+    );
+    assert!(
+        result.ends_with("normally be wrapped at eighty\ncolumns by hongdown.\n"),
+        "wrap should be re-enabled, got:\n{}",
+        result
+    );
+}
+
+#[test]
+fn test_directive_disable_reference_links_rule() {
+    let input = "<!-- hongdown-disable reference-links punctuation -->\n\nSee [\"docs\"](https://example.com/docs).\n\n<!-- hongdown-enable -->\n\nSee [site](https://example.com/).\n";
+    let result = parse_and_serialize_with_source(input);
+    assert!(
+        result.contains("See [\"docs\"](https://example.com/docs)."),
+        "inline link and straight quotes should be kept, got:\n{}",
+        result
+    );
+    assert!(
+        result.contains("See [site].") && result.contains("[site]: https://example.com/"),
+        "hongdown-enable should re-enable all rules, got:\n{}",
+        result
+    );
+}
+
+#[test]
+fn test_directive_disable_unknown_rule_keeps_known_rules() {
+    assert!(matches!(
+        state::Directive::parse("<!-- hongdown-disable wrap bogus -->"),
+        Some(state::Directive::DisableRules { rules, unknown })
+            if rules.contains(state::FormatRule::Wrap) && unknown == ["bogus"]
+    ));
+    assert!(matches!(
+        state::Directive::parse("<!-- hongdown-disable wrap, sentence-case -->"),
+        Some(state::Directive::DisableRules { rules, unknown })
+            if rules.contains(state::FormatRule::Wrap)
+                && rules.contains(state::FormatRule::SentenceCase)
+                && !rules.contains(state::FormatRule::Punctuation)
+                && unknown.is_empty()
+    ));
+}
+
+#[test]
+fn test_directive_unknown_rule_warning() {
+    let input = "<!-- hongdown-disable wrap wrpa -->\n\nA \"quoted\" paragraph that is long enough to go past the line width if wrapped.\n\n<!-- hongdown-enable foo -->\n";
+    let result = crate::format_with_warnings(input, &Options::default()).unwrap();
+    assert_eq!(
+        result.output,
+        "<!-- hongdown-disable wrap wrpa -->\n\nA \u{201c}quoted\u{201d} paragraph that is long enough to go past the line width if wrapped.\n\n<!-- hongdown-enable foo -->\n"
+    );
+    let warnings: Vec<_> = result
+        .warnings
+        .iter()
+        .map(|warning| (warning.code, warning.line, warning.message.as_str()))
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            (
+                crate::WarningCode::UnknownRule,
+                1,
+                "unknown rule 'wrpa' in directive is ignored"
+            ),
+            (
+                crate::WarningCode::UnknownRule,
+                5,
+                "unknown rule 'foo' in directive is ignored"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_inline_span_markers_preserve_text() {
+    let input =
+        "He said \"hi\" and <!-- hongdown-off -->\"kept\" -- as   is<!-- hongdown-on --> ok.";
+    let result = parse_and_serialize_with_source(input);
+    assert_eq!(
+        result,
+        "He said \u{201c}hi\u{201d} and <!-- hongdown-off -->\"kept\" -- as   is<!-- hongdown-on --> ok.\n",
+        "Text between hongdown-off and hongdown-on should be kept as-is, got:\n{}",
+        result
+    );
+}
+
+#[test]
+fn test_inline_span_markers_are_not_wrapped() {
+    let input = "A very long paragraph that is going to be wrapped somewhere around here <!-- hongdown-off -->this protected span must never be broken<!-- hongdown-on --> and more.";
+    let result = parse_and_serialize_with_source(input);
+    assert_eq!(
+        result,
+        "A very long paragraph that is going to be wrapped somewhere around\nhere <!-- hongdown-off -->this protected span must never be broken<!-- hongdown-on -->\nand more.\n",
+        "Protected span should not be broken across lines, got:\n{}",
+        result
+    );
+    assert_eq!(parse_and_serialize_with_source(&result), result);
+}
+
+#[test]
+fn test_placeholder_characters_in_input_are_kept() {
+    // Private use characters that the serializer uses as placeholders are
+    // not replaced when the document itself contains them
+    let input = "A\u{E000}B and C\u{E001}D <!-- hongdown-off -->x y<!-- hongdown-on -->\n\n```\nx\u{E000}y\u{E001}\n```\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(
+        result,
+        "A\u{E000}B and C\u{E001}D <!-- hongdown-off -->x y<!-- hongdown-on -->\n\n~~~~\nx\u{E000}y\u{E001}\n~~~~\n"
+    );
+}
+
+#[test]
+fn test_inline_span_marker_without_end_is_ignored() {
+    let input = "Text <!-- hongdown-off -->\"quoted\" here.";
+    let result = parse_and_serialize_with_source(input);
+    assert_eq!(
+        result, "Text <!-- hongdown-off -->\u{201c}quoted\u{201d} here.\n",
+        "Unmatched hongdown-off should not protect anything, got:\n{}",
+        result
+    );
+}
+
+#[test]
+fn test_preserve_reference_style_with_multibyte_text() {
+    let input = "![한국어 그림][그림] and [日本語のリンク][]\n\n[그림]: https://example.com/a.png\n[日本語のリンク]: https://example.com/";
+    let result = parse_and_serialize_with_source(input);
+    assert!(
+        result.starts_with("![한국어 그림][그림] and [日本語のリンク][]\n"),
+        "Reference style with multibyte text should be preserved, got:\n{}",
+        result
+    );
+}
+
 #[test]
 fn test_preserve_reference_style_badge() {
     // Reference-style badge links should be preserved as reference style
@@ -1665,6 +2503,34 @@ fn test_abbreviation_definition_multiple() {
     assert!(result.contains("*[CSS]: Cascading Style Sheets"));
 }
 
+#[test]
+fn test_abbreviation_definitions_grouped_per_section() {
+    let input = "*[W3C]: World Wide Web Consortium\n\nHTML and W3C.\n\n*[HTML]: HyperText Markup Language\n*[CSS]: Cascading Style Sheets\n\nSection\n-------\n\nAMQP [here][1].\n\n*[AMQP]: Advanced Message Queuing Protocol\n\n[1]: https://example.com/\n";
+    let options = Options {
+        abbreviation_placement: AbbreviationPlacement::Section,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "HTML and W3C.\n\n*[CSS]: Cascading Style Sheets\n*[HTML]: HyperText Markup Language\n*[W3C]: World Wide Web Consortium\n\n\nSection\n-------\n\nAMQP [here][1].\n\n[1]: https://example.com/\n\n*[AMQP]: Advanced Message Queuing Protocol\n"
+    );
+}
+
+#[test]
+fn test_abbreviation_definitions_grouped_at_end() {
+    let input = "*[W3C]: World Wide Web Consortium\n\nHTML and W3C.\n\n*[HTML]: HyperText Markup Language\n\nSection\n-------\n\nText.\n";
+    let options = Options {
+        abbreviation_placement: AbbreviationPlacement::Document,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "HTML and W3C.\n\n\nSection\n-------\n\nText.\n\n*[HTML]: HyperText Markup Language\n*[W3C]: World Wide Web Consortium\n"
+    );
+}
+
 #[test]
 fn test_definition_list_with_list_as_first_child() {
     let input = "Pros\n:    -  First item\n     -  Second item";
@@ -1785,6 +2651,29 @@ fn test_footnote_continuation_indent_matches_prefix() {
     );
 }
 
+#[test]
+fn test_footnote_continuation_indent_wide_label() {
+    let input = "Text[^주석].\n\n[^주석]: This is a long footnote with a label of wide characters, \
+                 which should wrap.\n";
+    let result = parse_and_serialize_with_source(input);
+    // "[^주석]: " is 9 columns wide, though 11 bytes long
+    assert_eq!(
+        result,
+        "Text[^주석].\n\n[^주석]: This is a long footnote with a label of wide characters, \
+         which should\n         wrap.\n"
+    );
+}
+
+#[test]
+fn test_footnote_with_multiple_blocks() {
+    // Blocks after the first paragraph are indented by four spaces
+    let input = "Text[^1].\n\n[^1]: Footnote.\n\n    > Quote.\n\n    - Item";
+    let result = parse_and_serialize(input);
+    let expected = "Text[^1].\n\n[^1]: Footnote.\n\n    > Quote.\n\n     -  Item\n";
+    assert_eq!(result, expected);
+    assert_eq!(parse_and_serialize(&result), expected);
+}
+
 #[test]
 fn test_table_warns_on_unescaped_pipe_in_cell() {
     use crate::format_with_warnings;
@@ -1799,13 +2688,271 @@ fn test_table_warns_on_unescaped_pipe_in_cell() {
 }
 
 #[test]
-fn test_heading_setext_h1_disabled() {
-    let options = Options {
-        setext_h1: false,
-        ..Options::default()
-    };
-    let result = parse_and_serialize_with_options("# Document Title", &options);
-    assert_eq!(result, "# Document Title\n");
+fn test_table_keeps_escaped_html_in_cells() {
+    let input = "| a | b |\n|---|---|\n| \\<span> x | `<i>` <br> |\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(
+        result,
+        "| a         | b          |\n| --------- | ---------- |\n| \\<span> x | `<i>` <br> |\n"
+    );
+}
+
+#[test]
+fn test_table_warns_on_unclosed_html_in_cell() {
+    use crate::format_with_warnings;
+
+    let input = "| a | b |\n|---|---|\n| <b>open | close</b> |\n| <i>x</i> | <br> |\n";
+    let result = format_with_warnings(input, &Options::default()).unwrap();
+    let codes: Vec<_> = result.warnings.iter().map(|w| (w.code, w.line)).collect();
+    assert_eq!(
+        codes,
+        [
+            (WarningCode::TableCellHtml, 3),
+            (WarningCode::TableCellHtml, 3)
+        ]
+    );
+    assert!(result.warnings[0].message.contains("`<b>`"));
+    assert!(result.warnings[1].message.contains("`</b>`"));
+}
+
+#[test]
+fn test_table_escape_html() {
+    use crate::format_with_warnings;
+
+    let options = Options {
+        table_escape_html: true,
+        ..Options::default()
+    };
+    let input =
+        "| a | b |\n|---|---|\n| <b>open | close</b> |\n| `<i>` | <https://example.com> |\n";
+    let result = format_with_warnings(input, &options).unwrap();
+    assert!(result.warnings.is_empty());
+    assert_eq!(
+        result.output,
+        "| a        | b                     |\n\
+         | -------- | --------------------- |\n\
+         | \\<b>open | close\\</b>            |\n\
+         | `<i>`    | <https://example.com> |\n"
+    );
+}
+
+#[test]
+fn test_table_escape_html_idempotent() {
+    let options = Options {
+        table_escape_html: true,
+        ..Options::default()
+    };
+    let input = "| a | b |\n|---|---|\n| <img src=\"x.png\" alt='x'> | \"quoted\" |\n";
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "| a                          | b        |\n\
+         | -------------------------- | -------- |\n\
+         | \\<img src=\"x.png\" alt='x'> | \u{201C}quoted\u{201D} |\n"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+}
+
+#[test]
+fn test_table_delimiter_style() {
+    use crate::DelimiterStyle;
+
+    let input = "| Name | Value | Center |\n|:-|-:|:-:|\n| alpha | 1 | x |\n";
+    let format_with_style = |style| {
+        let options = Options {
+            table_delimiter_style: style,
+            ..Options::default()
+        };
+        crate::format(input, &options).unwrap()
+    };
+    assert_eq!(
+        format_with_style(DelimiterStyle::Padded),
+        "| Name  | Value | Center |\n| :---- | ----: | :----: |\n| alpha |     1 |   x    |\n"
+    );
+    assert_eq!(
+        format_with_style(DelimiterStyle::Minimal),
+        "| Name  | Value | Center |\n|:--|--:|:-:|\n| alpha |     1 |   x    |\n"
+    );
+    assert_eq!(
+        format_with_style(DelimiterStyle::Fixed),
+        "| Name  | Value | Center |\n| :-- | --: | :-: |\n| alpha |     1 |   x    |\n"
+    );
+}
+
+#[test]
+fn test_table_preserve_left_alignment() {
+    let options = Options {
+        table_preserve_left_alignment: false,
+        ..Options::default()
+    };
+    let input = "| a | b |\n|:--|:--|\n| 1 | 2 |\n";
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(result, "| a   | b   |\n| --- | --- |\n| 1   | 2   |\n");
+    assert_eq!(crate::verify_equivalent(input, &result, &options), Ok(()));
+    // Colons are kept unless every column is left-aligned
+    let input = "| a | b |\n|:--|--:|\n| 1 | 2 |\n";
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(result, "| a   |   b |\n| :-- | --: |\n| 1   |   2 |\n");
+}
+
+#[test]
+fn test_table_ragged_rows_drop() {
+    use crate::{RaggedRows, format_with_warnings};
+
+    let options = Options {
+        table_ragged_rows: RaggedRows::Drop,
+        ..Options::default()
+    };
+    let input = "| a | b | c |\n|---|---|---|\n| 1 |\n| 1 | 2 | 3 | 4 |\n";
+    let result = format_with_warnings(input, &options).unwrap();
+    assert!(result.warnings.is_empty());
+    assert_eq!(
+        result.output,
+        "| a   | b   | c   |\n\
+         | --- | --- | --- |\n\
+         | 1   |     |     |\n\
+         | 1   | 2   | 3   |\n"
+    );
+}
+
+#[test]
+fn test_table_ragged_rows_merge() {
+    use crate::{RaggedRows, format_with_warnings};
+
+    let options = Options {
+        table_ragged_rows: RaggedRows::Merge,
+        ..Options::default()
+    };
+    let input = "| a | b |\n|---|---|\n| 1 |\n| 1 | 2 | 3 \\| 4 | 5 |\n| x | | y |\n\n\
+                 > | a | b |\n> |---|---|\n> | 1 | 2 | 3 |\n";
+    let result = format_with_warnings(input, &options).unwrap();
+    assert!(result.warnings.is_empty());
+    assert_eq!(
+        result.output,
+        "| a   | b                |\n\
+         | --- | ---------------- |\n\
+         | 1   |                  |\n\
+         | 1   | 2 \\| 3 \\| 4 \\| 5 |\n\
+         | x   | y                |\n\
+         \n\
+         > | a   | b      |\n\
+         > | --- | ------ |\n\
+         > | 1   | 2 \\| 3 |\n"
+    );
+}
+
+#[test]
+fn test_table_br_lines_determine_column_width() {
+    let input =
+        "| Name | Description |\n|---|---|\n| a | line one<br>line two<br>three |\n| b | short |\n";
+    let result = parse_and_serialize_with_source(input);
+    assert_eq!(
+        result,
+        "| Name | Description  |\n\
+         | ---- | ------------ |\n\
+         | a    | line one<br>line two<br>three |\n\
+         | b    | short        |\n"
+    );
+    assert_eq!(parse_and_serialize_with_source(&result), result);
+}
+
+#[test]
+fn test_table_br_style() {
+    let input = "| a |\n|---|\n| x<br>y<BR/>z<br >w |\n\nText<br>here.\n";
+    let options = Options {
+        table_br_style: crate::BrStyle::SpacedSelfClosing,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "| a       |\n| ------- |\n| x<br />y<br />z<br />w |\n\nText<br>here.\n"
+    );
+    assert_eq!(crate::verify_equivalent(input, &result, &options), Ok(()));
+    // Escaped tags and code spans are not line breaks
+    let input = "| a |\n|---|\n| `<br>` x \\<br> y |\n";
+    let result = crate::format(input, &options).unwrap();
+    assert!(result.contains("| `<br>` x \\<br> y |"), "{}", result);
+}
+
+#[test]
+fn test_table_warns_on_line_break_in_cell() {
+    use crate::{InlineKind, InlineRenderer, format_with_warnings};
+    use std::sync::Arc;
+
+    let renderer: Arc<dyn InlineRenderer> =
+        Arc::new(|_: &comrak::nodes::AstNode<'_>| Some("two\nlines".to_string()));
+    let options = Options {
+        inline_renderers: [(InlineKind::Code, renderer)].into_iter().collect(),
+        ..Options::default()
+    };
+    let input = "| a |\n|---|\n| `x` |\n";
+    let result = format_with_warnings(input, &options).unwrap();
+    assert_eq!(
+        result.output,
+        "| a         |\n| --------- |\n| two lines |\n"
+    );
+    assert_eq!(result.warnings.len(), 1);
+    assert_eq!(result.warnings[0].code, WarningCode::TableCellLineBreak);
+    assert_eq!(result.warnings[0].line, 3);
+}
+
+#[test]
+fn test_table_in_list_item() {
+    let input = "- Item with a table:
+
+  | A | B |
+  |---|:-:|
+  | 1 | 2 |
+- Next item
+  - Nested:
+    | Long header | X |
+    |---|---|
+    | a | b |
+";
+    let expected = " -  Item with a table:
+
+    | A   |  B  |
+    | --- | :-: |
+    | 1   |  2  |
+
+ -  Next item
+     -  Nested:
+
+        | Long header | X   |
+        | ----------- | --- |
+        | a           | b   |
+";
+    let output = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(output, expected);
+    assert_eq!(crate::format(&output, &Options::default()).unwrap(), output);
+}
+
+#[test]
+fn test_table_in_ordered_list_item_in_block_quote() {
+    let input = "> 1. Step:
+>
+>    | A | B |
+>    |---|---|
+>    | 1 | 2 |
+";
+    let expected = "> 1.  Step:
+>
+>     | A   | B   |
+>     | --- | --- |
+>     | 1   | 2   |
+";
+    assert_eq!(crate::format(input, &Options::default()).unwrap(), expected);
+}
+
+#[test]
+fn test_heading_setext_h1_disabled() {
+    let options = Options {
+        setext_h1: false,
+        ..Options::default()
+    };
+    let result = parse_and_serialize_with_options("# Document Title", &options);
+    assert_eq!(result, "# Document Title\n");
 }
 
 #[test]
@@ -1967,7 +3114,10 @@ fn test_ordered_list_alternating_markers() {
     assert!(result.contains("2)  Nested second"), "got: {}", result);
 }
 
-use crate::{FenceChar, IndentWidth, LeadingSpaces, MinFenceLength, TrailingSpaces};
+use crate::{
+    AlertName, DefinitionMarkerSpaces, FenceChar, IndentWidth, LeadingSpaces, MinFenceLength,
+    TrailingSpaces,
+};
 
 #[test]
 fn test_code_block_fence_char_backtick() {
@@ -1980,6 +3130,30 @@ fn test_code_block_fence_char_backtick() {
     assert!(result.contains("rust"), "got: {}", result);
 }
 
+#[test]
+fn test_code_block_fence_char_backtick_nested() {
+    let options = Options {
+        fence_char: FenceChar::Backtick,
+        min_fence_length: MinFenceLength::new(3).unwrap(),
+        ..Options::default()
+    };
+    let input = "~~~~ markdown\n```\ncode\n```\n~~~~\n\n~~~~~ md\n  `````\n~~~~~\n";
+    assert_eq!(
+        parse_and_serialize_with_options(input, &options),
+        "```` markdown\n```\ncode\n```\n````\n\n`````` md\n  `````\n``````\n"
+    );
+}
+
+#[test]
+fn test_code_block_fence_char_backtick_info_with_backticks() {
+    let options = Options {
+        fence_char: FenceChar::Backtick,
+        ..Options::default()
+    };
+    let input = "~~~~ title=`a`\ncode\n~~~~\n";
+    assert_eq!(parse_and_serialize_with_options(input, &options), input);
+}
+
 #[test]
 fn test_code_block_fence_char_default() {
     let options = Options::default();
@@ -2085,6 +3259,16 @@ fn test_undefined_reference_warning() {
     );
 }
 
+#[test]
+fn test_undefined_reference_warning_position_and_code() {
+    let input = "Intro paragraph.\n\nSee **this** and [missing] here.";
+    let result = parse_and_serialize_with_warnings(input);
+    assert_eq!(result.warnings.len(), 1);
+    assert_eq!(result.warnings[0].line, 3);
+    assert_eq!(result.warnings[0].column, 18);
+    assert_eq!(result.warnings[0].code, WarningCode::UndefinedReference);
+}
+
 #[test]
 fn test_defined_reference_no_warning() {
     // When a reference link is properly defined, no warning should be emitted
@@ -2377,10 +3561,71 @@ fn test_definition_list_with_blockquote() {
 
 #[test]
 fn test_definition_list_with_alert_as_first_child() {
-    // Alert as first child in definition list (note: `:   >` format required)
+    // Alert as first child in definition list starts on the `:   ` line,
+    // since a bare `:` marker is not recognized as definition details
     let input = "term\n:   > [!TIP]\n    > This is a tip.";
     let result = parse_and_serialize(input);
-    assert_eq!(result, "term\n:\n    > [!TIP]\n    > This is a tip.\n");
+    assert_eq!(result, "term\n:   > [!TIP]\n    > This is a tip.\n");
+}
+
+#[test]
+fn test_alert_in_list_in_blockquote_in_definition() {
+    // Containers nested at any depth share one prefix stack
+    let input = "Term\n:   Details\n\n    > - Item\n    >\n    >   > [!NOTE]\n    >   > Note text.\n    >   >\n    >   > ```rust\n    >   > fn main() {}\n    >   > ```";
+    let result = parse_and_serialize(input);
+    let expected = "Term\n:   Details\n\n    >  -  Item\n    >\n    >     > [!NOTE]\n    >     > Note text.\n    >     >\n    >     > ~~~~ rust\n    >     > fn main() {}\n    >     > ~~~~\n";
+    assert_eq!(result, expected);
+    assert_eq!(parse_and_serialize(&result), expected);
+}
+
+#[test]
+fn test_blocks_in_nested_blockquote() {
+    // Code blocks, tables, and headings keep every level of `>` markers
+    let input = "> > Quoted\n> >\n> > ```\n> > code\n> > ```\n> >\n> > | a | b |\n> > |---|---|\n> > | 1 | 2 |\n> >\n> > ## Heading";
+    let result = parse_and_serialize(input);
+    let expected = "> > Quoted\n> >\n> > ~~~~\n> > code\n> > ~~~~\n> >\n> > | a   | b   |\n> > | --- | --- |\n> > | 1   | 2   |\n> >\n> > Heading\n> > -------\n";
+    assert_eq!(result, expected);
+    assert_eq!(parse_and_serialize(&result), expected);
+}
+
+#[test]
+fn test_blocks_in_list_item() {
+    // Blocks in a list item are indented, and the first one starts on the
+    // marker line
+    let input = "- > Quote\n\n  ---\n\n  <div>\n  html\n  </div>\n- ```\n  code\n  ```";
+    let result = parse_and_serialize(input);
+    let expected = " -  > Quote\n\n       - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -\n\n    <div>\n    html\n    </div>\n\n -  ~~~~\n    code\n    ~~~~\n";
+    assert_eq!(result, expected);
+    assert_eq!(parse_and_serialize(&result), expected);
+}
+
+#[test]
+fn test_list_in_blockquote_wraps_continuation_within_line_width() {
+    // Continuation lines count the marker and the `>` prefix toward the line
+    // width, while the first line does not, as in top-level lists
+    let input = format!("> - {}", "word ".repeat(20).trim_end());
+    let result = parse_and_serialize_with_width(&input, 40);
+    assert_eq!(
+        result,
+        ">  -  word word word word word word word word\n>     word word word word word word\n>     word word word word word word\n"
+    );
+}
+
+#[test]
+fn test_empty_list_item_keeps_marker_spaces() {
+    let input = "- \n- Item\n\n> 1. \n";
+    let result = parse_and_serialize(input);
+    assert_eq!(result, " -  \n -  Item\n\n> 1.  \n");
+}
+
+#[test]
+fn test_alert_after_code_block_in_list_item() {
+    // A single blank line separates the blocks of a list item
+    let input = "- Text\n\n  ```\n  code\n  ```\n\n  > [!NOTE]\n  > Note.\n";
+    let result = parse_and_serialize(input);
+    let expected = " -  Text\n\n    ~~~~\n    code\n    ~~~~\n\n    > [!NOTE]\n    > Note.\n";
+    assert_eq!(result, expected);
+    assert_eq!(parse_and_serialize(&result), expected);
 }
 
 #[test]
@@ -3809,6 +5054,56 @@ fn test_code_block_formatter_failure_preserves_original() {
     assert!(result.warnings[0].message.contains("failed"));
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_code_block_formatter_failure_reports_line() {
+    use crate::CodeFormatter;
+
+    let options = Options {
+        code_formatters: std::collections::HashMap::from([(
+            "text".to_string(),
+            CodeFormatter {
+                command: vec!["false".to_string()],
+                timeout_secs: 5,
+            },
+        )]),
+        ..Options::default()
+    };
+    let input = "Title\n=====\n\n -  Item\n\n    ~~~~ text\n    hello\n    ~~~~\n";
+    let result = parse_and_serialize_with_options_and_warnings(input, &options);
+    assert_eq!(result.warnings[0].code, crate::WarningCode::CodeFormatter);
+    assert_eq!(result.warnings[0].line, 6);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_code_block_formatter_not_allowed() {
+    use crate::CodeFormatter;
+
+    let mut options = Options::default();
+    options.code_formatters.insert(
+        "text".to_string(),
+        CodeFormatter {
+            command: vec!["tr".to_string(), "a-z".to_string(), "A-Z".to_string()],
+            timeout_secs: 5,
+        },
+    );
+    options.allowed_code_formatters = Some(vec![vec!["tr".to_string(), "A-Z".to_string()]]);
+
+    let input = "~~~~ text
+hello world
+~~~~
+";
+    let result = parse_and_serialize_with_options_and_warnings(input, &options);
+    assert_eq!(result.output, input);
+    assert_eq!(result.warnings[0].code, crate::WarningCode::CodeFormatter);
+    assert!(result.warnings[0].message.contains("not among the allowed"));
+
+    options.allowed_code_formatters = Some(vec![vec!["tr".to_string()]]);
+    let result = parse_and_serialize_with_options_and_warnings(input, &options);
+    assert_eq!(result.output, "~~~~ text\nHELLO WORLD\n~~~~\n");
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[test]
 fn test_code_block_no_formatter_for_language() {
@@ -3866,6 +5161,10 @@ fn test_code_block_formatter_timeout() {
     assert_eq!(result.output, "~~~~ slow\nhello\n~~~~\n");
     // Warning should be generated
     assert!(!result.warnings.is_empty());
+    assert_eq!(
+        result.warnings[0].code,
+        crate::WarningCode::CodeFormatterTimeout
+    );
     assert!(result.warnings[0].message.contains("timed out"));
 }
 
@@ -3940,6 +5239,50 @@ fn test_code_block_no_format_without_formatter_configured() {
     assert_eq!(result, "~~~~ rust hongdown-no-format\nfn main() {}\n~~~~\n");
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_code_block_format_with_directive() {
+    use crate::CodeFormatter;
+
+    let mut options = Options::default();
+    options.code_formatters.insert(
+        "text".to_string(),
+        CodeFormatter {
+            command: vec!["tr".to_string(), "a-z".to_string(), "A-Z".to_string()],
+            timeout_secs: 5,
+        },
+    );
+
+    // Chooses the formatter of another language, even for a block without
+    // a language
+    let input = "<!-- hongdown-format-with: tr a-z A-Z -->\n\n~~~~ python\nhello\n~~~~\n\n\
+                 <!-- hongdown-format-with: tr a-z A-Z -->\n~~~~\nworld\n~~~~\n";
+    let result = parse_and_serialize_with_options_and_warnings(input, &options);
+    assert_eq!(
+        result.output,
+        "<!-- hongdown-format-with: tr a-z A-Z -->\n\n~~~~ python\nHELLO\n~~~~\n\n\
+         <!-- hongdown-format-with: tr a-z A-Z -->\n\n~~~~\nWORLD\n~~~~\n"
+    );
+    assert!(result.warnings.is_empty());
+
+    // Suppresses the formatter of the language, also within containers
+    let input =
+        " -  Item\n\n    <!-- hongdown-no-format-code -->\n\n    ~~~~ text\n    hello\n    ~~~~\n";
+    assert_eq!(parse_and_serialize_with_options(input, &options), input);
+
+    // Commands from documents must be configured or allowed
+    let input = "<!-- hongdown-format-with: tr a-z x -->\n\n~~~~ text\nhello\n~~~~\n";
+    let result = parse_and_serialize_with_options_and_warnings(input, &options);
+    assert_eq!(result.output, input);
+    assert!(result.warnings[0].message.contains("not among the allowed"));
+    options.allowed_code_formatters = Some(vec![vec!["tr".to_string()]]);
+    let result = parse_and_serialize_with_options_and_warnings(input, &options);
+    assert_eq!(
+        result.output,
+        "<!-- hongdown-format-with: tr a-z x -->\n\n~~~~ text\nxxxxx\n~~~~\n"
+    );
+}
+
 #[test]
 fn test_code_block_no_format_idempotent() {
     let options = Options::default();
@@ -4318,3 +5661,738 @@ fn test_possessive_apostrophe_after_digit_curly_when_enabled() {
         result
     );
 }
+
+#[test]
+fn test_flushed_definitions_are_separate_output_segments() {
+    let input = "Intro [link][1].[^a]\n\n[^a]: A note.\n\n[1]: https://example.com/\n\nSection\n-------\n\nText.\n";
+    let arena = Arena::new();
+    let root = parse_document(&arena, input, &comrak_options());
+    let format_options = Options::default();
+    let mut serializer = Serializer::new(&format_options, input.lines().collect(), true);
+    serializer.serialize_node(root);
+    let kinds: Vec<output::SegmentKind> = serializer
+        .output
+        .segments()
+        .iter()
+        .map(|segment| segment.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            output::SegmentKind::Content,
+            output::SegmentKind::Footnotes,
+            output::SegmentKind::References,
+            output::SegmentKind::Content,
+        ]
+    );
+    let references = &serializer.output.segments()[2].text;
+    assert_eq!(references, "\n[1]: https://example.com/\n");
+}
+
+#[test]
+fn test_bracketed_span_attributes_preserved() {
+    let input = "This is [a span]{.smallcaps #id key=\"a_b c\"} here.";
+    let result = parse_and_serialize_with_warnings(input);
+    assert_eq!(
+        result.output,
+        "This is [a span]{.smallcaps #id key=\"a_b c\"} here.\n"
+    );
+    assert!(result.warnings.is_empty());
+}
+
+#[test]
+fn test_link_attributes_keep_link_inline() {
+    let input = "A link [x](https://example.com/){target=\"_blank\"} follows.";
+    let result = parse_and_serialize_with_source(input);
+    assert_eq!(
+        result,
+        "A link [x](https://example.com/){target=\"_blank\"} follows.\n"
+    );
+}
+
+#[test]
+fn test_image_attributes_not_split_when_wrapping() {
+    let input = "Some words to push the image to the end of the line so it wraps ![alt](img.png){width=300 height=200}.";
+    let result = parse_and_serialize_with_source(input);
+    assert_eq!(
+        result,
+        "Some words to push the image to the end of the line so it wraps\n![alt](img.png){width=300 height=200}.\n"
+    );
+}
+
+#[test]
+fn test_unicode_normalization_nfc_applies_to_prose_only() {
+    let input =
+        "# Cafe\u{301}\n\nA re\u{301}sume\u{301} with `cafe\u{301}`.\n\n~~~~\ncafe\u{301}\n~~~~\n";
+    let options = Options {
+        unicode_normalization: UnicodeNormalization::Nfc,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Caf\u{e9}\n====\n\nA r\u{e9}sum\u{e9} with `cafe\u{301}`.\n\n~~~~\ncafe\u{301}\n~~~~\n"
+    );
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert!(result.starts_with("Cafe\u{301}\n====\n\nA re\u{301}sume\u{301}"));
+}
+
+#[test]
+fn test_unicode_normalization_nfkc_keeps_escapes() {
+    let input = "The \u{fb01}le node\\_modules.\n";
+    let options = Options {
+        unicode_normalization: UnicodeNormalization::Nfkc,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(result, "The file node\\_modules.\n");
+}
+
+#[test]
+fn test_bom_stripped_by_default() {
+    let input =
+        "\u{feff}---\ntitle: Test\n---\n\n# Title\n\n[link][1]\n\n[1]: https://example.com/\n";
+    let expected =
+        "---\ntitle: Test\n---\n\nTitle\n=====\n\n[link][1]\n\n[1]: https://example.com/\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(result, expected);
+    assert_eq!(
+        crate::format("\u{feff}\u{feff}", &Options::default()).unwrap(),
+        ""
+    );
+}
+
+#[test]
+fn test_bom_preserved() {
+    let options = Options {
+        bom: ByteOrderMark::Preserve,
+        ..Options::default()
+    };
+    let input = "\u{feff}\u{feff}# Title\n\nSome *text*.\n";
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(result, "\u{feff}Title\n=====\n\nSome *text*.\n");
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+    let result = crate::format("# Title\n", &options).unwrap();
+    assert_eq!(result, "Title\n=====\n");
+}
+
+#[test]
+fn test_line_ending_crlf() {
+    let input = "# Title\n\nText  \nmore.\n\n<div>\r\nHTML\r\n</div>\n";
+    let options = Options {
+        line_ending: LineEnding::Crlf,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Title\r\n=====\r\n\r\nText  \r\nmore.\r\n\r\n<div>\r\nHTML\r\n</div>\r\n"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+}
+
+#[test]
+fn test_line_ending_crlf_inline_html_across_lines_is_stable() {
+    let input = "The project is licensed under the GPL, and some data files included in the repository is governed by the <a\nhref=\"LICENSE-UNICODE\">Unicode license</a>.\n";
+    let options = Options {
+        line_ending: LineEnding::Crlf,
+        line_width: LineWidth::new(108).unwrap(),
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+    let crlf_input = input.replace('\n', "\r\n");
+    assert_eq!(crate::format(&crlf_input, &options).unwrap(), result);
+}
+
+#[test]
+fn test_line_ending_preserve() {
+    let options = Options {
+        line_ending: LineEnding::Preserve,
+        ..Options::default()
+    };
+    let input = "Title\r\n=====\r\n\r\n -  Item\r\n";
+    assert_eq!(crate::format(input, &options).unwrap(), input);
+    let input = "Title\n=====\n\n -  Item\n";
+    assert_eq!(crate::format(input, &options).unwrap(), input);
+    let result = crate::format("Title\r\n=====\r\n", &Options::default()).unwrap();
+    assert_eq!(result, "Title\n=====\n");
+}
+
+#[test]
+fn test_strip_trailing_whitespace() {
+    let input = "Hard   \nbreak\\\nand more.\n\n > Quoted  \n > text.\n\n```\ncode  \n\t\n```\n\n<div>  \nHTML\t\n</div>\n\n<!-- hongdown-disable -->\n\nKept  \n\n<!-- hongdown-enable -->\n";
+    let options = Options {
+        strip_trailing_whitespace: true,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Hard  \nbreak  \nand more.\n\n> Quoted  \n> text.\n\n~~~~\ncode\n\n~~~~\n\n<div>\nHTML\n</div>\n\n<!-- hongdown-disable -->\n\nKept  \n\n<!-- hongdown-enable -->\n"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert!(result.contains("~~~~\ncode  \n\t\n~~~~\n\n<div>  \nHTML\t\n</div>"));
+}
+
+#[test]
+fn test_final_newline() {
+    let format = |input: &str, final_newline: FinalNewline| {
+        let options = Options {
+            final_newline,
+            ..Options::default()
+        };
+        crate::format(input, &options).unwrap()
+    };
+    assert_eq!(format("Text.", FinalNewline::Always), "Text.\n");
+    assert_eq!(format("Text.\n\n\n", FinalNewline::Always), "Text.\n");
+    assert_eq!(format("Text.\n", FinalNewline::Never), "Text.");
+    assert_eq!(format("Text.", FinalNewline::Preserve), "Text.");
+    assert_eq!(format("Text.\n\n", FinalNewline::Preserve), "Text.\n");
+    // Source copied after a disable-file directive ends the same way
+    let input = "<!-- hongdown-disable-file -->\n\nKept  *as is*";
+    assert_eq!(format(input, FinalNewline::Always), format!("{}\n", input));
+}
+
+#[test]
+fn test_expand_tabs() {
+    let input = "Name:\tvalue\n\n> a\tb\n\n| x\ty | z |\n|---|---|\n\n`co\tde` and\ttext\n\n```\nco\tde\n```\n";
+    let options = Options {
+        expand_tabs: true,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Name:   value\n\n> a b\n\n| x y | z   |\n| --- | --- |\n\n`co\tde` and text\n\n~~~~\nco\tde\n~~~~\n"
+    );
+    let options = Options {
+        expand_tabs: true,
+        tab_width: crate::TabWidth::new(8).unwrap(),
+        ..Options::default()
+    };
+    let result = crate::format("Name:\tvalue\n", &options).unwrap();
+    assert_eq!(result, "Name:   value\n");
+    let result = crate::format("Key\tvalue\n", &options).unwrap();
+    assert_eq!(result, "Key     value\n");
+    let result = crate::format("Key\tvalue\n", &Options::default()).unwrap();
+    assert_eq!(result, "Key\tvalue\n");
+}
+
+#[test]
+fn test_expand_tabs_after_emoji_sequence() {
+    // The family emoji is three emoji joined into one glyph two columns wide
+    let options = Options {
+        expand_tabs: true,
+        tab_width: crate::TabWidth::new(8).unwrap(),
+        ..Options::default()
+    };
+    let result = crate::format("👨\u{200d}👩\u{200d}👧\tx\n", &options).unwrap();
+    assert_eq!(result, "👨\u{200d}👩\u{200d}👧      x\n");
+}
+
+#[test]
+fn test_max_blank_lines() {
+    let input = "Title\n=====\n\n\n\n\nPara.\n\n\nAnother.\n\n\n\n\nSection\n-------\n\nText.\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(
+        result,
+        "Title\n=====\n\nPara.\n\nAnother.\n\n\nSection\n-------\n\nText.\n"
+    );
+    let options = Options {
+        max_blank_lines: crate::MaxBlankLines::new(2).unwrap(),
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Title\n=====\n\n\nPara.\n\n\nAnother.\n\n\nSection\n-------\n\nText.\n"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+}
+
+#[test]
+fn test_heading_blank_lines_before() {
+    let input = "# Title\n\nIntro.\n\n## Section\n\n### Sub\n\nText.\n\n## Next\n\nMore.\n";
+    let options = Options {
+        heading_blank_lines_before: crate::HeadingBlankLines::new([1, 1, 1, 1, 1, 1]).unwrap(),
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Title\n=====\n\nIntro.\n\nSection\n-------\n\n### Sub\n\nText.\n\nNext\n----\n\nMore.\n"
+    );
+
+    let options = Options {
+        heading_blank_lines_before: crate::HeadingBlankLines::new([1, 2, 2, 1, 1, 1]).unwrap(),
+        heading_collapse_consecutive: false,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Title\n=====\n\nIntro.\n\n\nSection\n-------\n\n\n### Sub\n\nText.\n\n\nNext\n----\n\nMore.\n"
+    );
+    let result = crate::format("# A\n\n## B\n", &options).unwrap();
+    assert_eq!(result, "A\n=\n\n\nB\n-\n");
+}
+
+#[test]
+fn test_setext_underline() {
+    let input = "# Title\n\n## 한국어\n\n> Quoted\n> ------\n";
+    let format = |setext_underline| {
+        let options = Options {
+            line_width: LineWidth::new(12).unwrap(),
+            setext_underline,
+            ..Options::default()
+        };
+        crate::format(input, &options).unwrap()
+    };
+    assert_eq!(
+        format(crate::SetextUnderline::Match),
+        "Title\n=====\n\n한국어\n------\n\n> Quoted\n> ------\n"
+    );
+    assert_eq!(
+        format(crate::SetextUnderline::FullWidth),
+        "Title\n============\n\n한국어\n------------\n\n> Quoted\n> ----------\n"
+    );
+    assert_eq!(
+        format(crate::SetextUnderline::Fixed(3)),
+        "Title\n===\n\n한국어\n---\n\n> Quoted\n> ---\n"
+    );
+}
+
+#[test]
+fn test_setext_underline_measure() {
+    use crate::UnderlineMeasure;
+
+    let input = "# 제목 Title\n\n## café\n";
+    let format = |setext_underline_measure| {
+        let options = Options {
+            setext_underline_measure,
+            ..Options::default()
+        };
+        crate::format(input, &options).unwrap()
+    };
+    assert_eq!(
+        format(UnderlineMeasure::DisplayWidth),
+        "제목 Title\n==========\n\ncafé\n----\n"
+    );
+    assert_eq!(
+        format(UnderlineMeasure::Characters),
+        "제목 Title\n========\n\ncafé\n----\n"
+    );
+}
+
+#[test]
+fn test_heading_overlong() {
+    use crate::{OverlongHeading, OverlongHeadings, format_with_warnings};
+
+    let input = "# A rather long title\n\n### A long subsection\n\n## Short\n";
+    let format = |policy: OverlongHeading| {
+        let options = Options {
+            line_width: LineWidth::new(12).unwrap(),
+            heading_overlong: policy.into(),
+            ..Options::default()
+        };
+        format_with_warnings(input, &options).unwrap()
+    };
+    let keep = format(OverlongHeading::Keep);
+    assert_eq!(
+        keep.output,
+        "A rather long title\n===================\n\n### A long subsection\n\nShort\n-----\n"
+    );
+    assert!(keep.warnings.is_empty());
+    let warn = format(OverlongHeading::Warn);
+    assert_eq!(warn.output, keep.output);
+    let lines: Vec<_> = warn.warnings.iter().map(|w| (w.code, w.line)).collect();
+    assert_eq!(
+        lines,
+        [
+            (WarningCode::HeadingTooLong, 1),
+            (WarningCode::HeadingTooLong, 3)
+        ]
+    );
+    assert_eq!(
+        format(OverlongHeading::Atx).output,
+        "# A rather long title\n\n### A long subsection\n\nShort\n-----\n"
+    );
+    assert_eq!(
+        format(OverlongHeading::Wrap).output,
+        "A rather\nlong title\n==========\n\n### A long subsection\n\nShort\n-----\n"
+    );
+
+    // Policies are per level
+    let mut policies = [OverlongHeading::Keep; 6];
+    policies[2] = OverlongHeading::Warn;
+    let options = Options {
+        line_width: LineWidth::new(12).unwrap(),
+        heading_overlong: OverlongHeadings::new(policies),
+        ..Options::default()
+    };
+    let result = format_with_warnings(input, &options).unwrap();
+    let lines: Vec<_> = result.warnings.iter().map(|w| w.line).collect();
+    assert_eq!(lines, [3]);
+}
+
+#[test]
+fn test_heading_links() {
+    let input = "# Using [*Hongdown*](https://example.com) with <https://x.org>\n\nText.\n";
+    let format = |heading_links| {
+        let options = Options {
+            heading_links,
+            ..Options::default()
+        };
+        crate::format(input, &options).unwrap()
+    };
+    assert_eq!(
+        format(crate::HeadingLinks::Reference),
+        "Using [*Hongdown*] with <https://x.org>\n\
+         =======================================\n\n\
+         Text.\n\n[*Hongdown*]: https://example.com\n"
+    );
+    assert_eq!(
+        format(crate::HeadingLinks::Inline),
+        "Using [*Hongdown*](https://example.com) with <https://x.org>\n\
+         ============================================================\n\n\
+         Text.\n"
+    );
+    assert_eq!(
+        format(crate::HeadingLinks::Text),
+        "Using *Hongdown* with <https://x.org>\n\
+         =====================================\n\n\
+         Text.\n"
+    );
+}
+
+#[test]
+fn test_heading_link_with_image_kept_inline() {
+    // An image in link text cannot be part of a reference label
+    let input =
+        "## Status [![Build](https://ci.example.com/badge.svg)](https://ci.example.com/job)\n";
+    let result = crate::format(input, &Options::default()).unwrap();
+    assert_eq!(
+        result,
+        "Status [![Build](https://ci.example.com/badge.svg)](https://ci.example.com/job)\n\
+         -------------------------------------------------------------------------------\n"
+    );
+    assert_eq!(crate::format(&result, &Options::default()).unwrap(), result);
+}
+
+#[test]
+fn test_escape_underscores_disabled() {
+    let options = Options {
+        escape_underscores: false,
+        ..Options::default()
+    };
+    let input = "# MAX\\_SIZE\n\nSet CONFIG\\_FILE\\_NAME in src/\\_\\_init\\_\\_.py.\n";
+    assert_eq!(
+        crate::format(input, &options).unwrap(),
+        "MAX_SIZE\n========\n\nSet CONFIG_FILE_NAME in src/\\_\\_init\\_\\_.py.\n"
+    );
+    assert_eq!(
+        crate::format("Set CONFIG_FILE_NAME.\n", &Options::default()).unwrap(),
+        "Set CONFIG\\_FILE\\_NAME.\n"
+    );
+}
+
+#[test]
+fn test_verify_escaping() {
+    let options = Options {
+        verify_escaping: true,
+        ..Options::default()
+    };
+    let input = "# A \\*B\\*\n\nUse `x` and \\[not a link](here), 2 \\* 3 \\<tag>.\n\n| a \\| b |\n|---|\n| \\_c\\_ |\n";
+    let result = crate::format_with_warnings(input, &options).unwrap();
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+}
+
+#[test]
+fn test_heading_title_case() {
+    let options = Options {
+        heading_title_case: TitleCase::Ap,
+        ..Options::default()
+    };
+    let result = parse_and_serialize_with_options("## a guide to the galaxy", &options);
+    assert_eq!(result, "A Guide to the Galaxy\n---------------------\n");
+
+    // Sentence case takes precedence over title case
+    let options = Options {
+        heading_sentence_case: true,
+        ..options
+    };
+    let result = parse_and_serialize_with_options("## A Guide To The Galaxy", &options);
+    assert_eq!(result, "A guide to the galaxy\n---------------------\n");
+}
+
+#[test]
+fn test_heading_case_skips_link_destinations_and_html() {
+    use crate::HeadingLinks;
+
+    let input = "### see the [cc crate](https://github.com/rust-lang/cc-rs \"the docs\") and <span class=\"x\">html</span> and [ref link][cc-rs]\n\n[cc-rs]: https://example.com/\n";
+    let options = Options {
+        heading_title_case: TitleCase::Chicago,
+        heading_links: HeadingLinks::Inline,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "### See the [Cc Crate](https://github.com/rust-lang/cc-rs \"the docs\") and <span class=\"x\">Html</span> and [ref link][cc-rs]\n\n[cc-rs]: https://example.com/\n"
+    );
+
+    let options = Options {
+        heading_sentence_case: true,
+        ..options
+    };
+    let input = "### See The [CC Crate](https://github.com/rust-Lang/cc-rs) And <span class=\"X\">Html</span>\n";
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "### See the [CC crate](https://github.com/rust-Lang/cc-rs) and <span class=\"X\">html</span>\n"
+    );
+}
+
+#[test]
+fn test_heading_case_keeps_reference_links_idempotent() {
+    let input = "## See the [API docs]\n\n## [v0.3.0] - 2020\n\n[API docs]: https://example.com/api\n[v0.3.0]: https://example.com/v030\n";
+    let options = Options {
+        heading_title_case: TitleCase::Chicago,
+        ..Options::default()
+    };
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "See the [API docs]\n------------------\n\n[API docs]: https://example.com/api\n\n[v0.3.0] - 2020\n---------------\n\n[v0.3.0]: https://example.com/v030\n"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+
+    let options = Options {
+        heading_sentence_case: true,
+        ..options
+    };
+    let input = "## The [API Docs]\n\n[API Docs]: https://example.com/api\n";
+    let result = crate::format(input, &options).unwrap();
+    assert!(result.starts_with("The [API Docs]\n"), "{result}");
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+}
+
+#[test]
+fn test_heading_title_case_disabled_by_directive() {
+    let options = Options {
+        heading_title_case: TitleCase::Simple,
+        ..Options::default()
+    };
+    let input = "### first heading\n\n<!-- hongdown-disable title-case -->\n\n### second heading";
+    let result = crate::format(input, &options).unwrap();
+    assert!(
+        result.contains("### First Heading") && result.contains("### second heading"),
+        "title-case rule should be disabled after the directive, got:\n{}",
+        result
+    );
+}
+
+#[test]
+fn test_heading_overlong_wrap_keeps_block_markers_off_line_start() {
+    use crate::OverlongHeading;
+
+    let options = Options {
+        line_width: LineWidth::new(22).unwrap(),
+        heading_overlong: OverlongHeading::Wrap.into(),
+        ..Options::default()
+    };
+    let input = "## Release 0.1.6 title x - 2025-12-26\n";
+    let result = crate::format(input, &options).unwrap();
+    assert_eq!(
+        result,
+        "Release 0.1.6 title\nx - 2025-12-26\n-------------------\n"
+    );
+    assert_eq!(crate::format(&result, &options).unwrap(), result);
+}
+
+#[test]
+fn test_wrap_keeps_block_markers_off_line_start() {
+    for marker in ["-", "+", "#", "##", ">", "1.", "1)", "="] {
+        let input = format!("Some words here {marker} more");
+        let result = parse_and_serialize_with_width(&input, 16);
+        assert_eq!(
+            result,
+            format!("Some words\nhere {marker} more\n"),
+            "{marker} should not start a line"
+        );
+    }
+    // Ordered list markers other than 1. cannot interrupt a paragraph
+    assert_eq!(
+        parse_and_serialize_with_width("Some words here 2. more", 16),
+        "Some words here\n2. more\n"
+    );
+}
+
+#[test]
+fn test_heading_blank_lines_after() {
+    let input = "# Title\nIntro.\n\n## Section\n### Sub\n\n - Item\n";
+    let format = |blank_lines_after: usize| {
+        let options = Options {
+            heading_blank_lines_after: blank_lines_after,
+            ..Options::default()
+        };
+        crate::format(input, &options).unwrap()
+    };
+    assert_eq!(
+        format(0),
+        "Title\n=====\nIntro.\n\n\nSection\n-------\n\n### Sub\n -  Item\n"
+    );
+    assert_eq!(
+        format(2),
+        "Title\n=====\n\n\nIntro.\n\n\nSection\n-------\n\n### Sub\n\n\n -  Item\n"
+    );
+    assert_eq!(
+        crate::format(&format(0), &Options::default()).unwrap(),
+        format(1)
+    );
+}
+
+#[test]
+fn test_trace() {
+    use crate::{TraceKind, format_with_warnings};
+    let input = "# Title\n\nSome \"quoted\" text with a [link](https://example.com/) that is long enough to be wrapped at the default width.\n\n    unchanged\n";
+    let options = Options {
+        trace: true,
+        ..Options::default()
+    };
+    let result = format_with_warnings(input, &options).unwrap();
+    let events: Vec<(usize, TraceKind)> = result
+        .trace
+        .iter()
+        .map(|event| (event.line, event.kind))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (3, TraceKind::Punctuation),
+            (3, TraceKind::LinkToReference),
+            (3, TraceKind::Wrapped),
+        ]
+    );
+    assert_eq!(
+        result.trace[1].detail,
+        "link to https://example.com/ converted to reference [link]"
+    );
+
+    let result = format_with_warnings(input, &Options::default()).unwrap();
+    assert!(result.trace.is_empty());
+}
+
+#[test]
+fn test_trace_respects_disabled_rules() {
+    use crate::format_with_warnings;
+    let input = "<!-- hongdown-disable punctuation -->\n\nSome \"quoted\" text.\n";
+    let options = Options {
+        trace: true,
+        ..Options::default()
+    };
+    let result = format_with_warnings(input, &options).unwrap();
+    assert!(result.trace.is_empty(), "{:?}", result.trace);
+}
+
+#[test]
+fn test_code_block_long_lines() {
+    use crate::format_with_warnings;
+
+    let input = "Text.\n\n~~~~ python\nshort = 1\nlonger_name = 12345\n~~~~\n\n\
+                 -  Item\n\n    ~~~~\n    \tindented = 1\n    ~~~~\n\n        plain = 123456\n";
+    let format = |warn: bool, code_line_width: Option<usize>| {
+        let options = Options {
+            line_width: LineWidth::new(12).unwrap(),
+            warn_long_code_lines: warn,
+            code_line_width: code_line_width.map(|width| LineWidth::new(width).unwrap()),
+            ..Options::default()
+        };
+        format_with_warnings(input, &options).unwrap()
+    };
+    assert!(format(false, None).warnings.is_empty());
+    let result = format(true, None);
+    assert_eq!(result.output, format(false, None).output);
+    let lines: Vec<_> = result.warnings.iter().map(|w| (w.code, w.line)).collect();
+    assert_eq!(
+        lines,
+        [
+            (WarningCode::CodeLineTooLong, 5),
+            (WarningCode::CodeLineTooLong, 11),
+            (WarningCode::CodeLineTooLong, 14)
+        ]
+    );
+    assert_eq!(
+        result.warnings[0].message,
+        "line of python code block is 19 columns wide, which exceeds the code line width of 12"
+    );
+    assert!(
+        result.warnings[1]
+            .message
+            .starts_with("line of code block is 16 columns")
+    );
+    assert!(format(true, Some(20)).warnings.is_empty());
+}
+
+#[test]
+fn test_code_block_expand_tabs() {
+    use crate::TabWidth;
+
+    let mut options = Options::default();
+    options
+        .code_expand_tabs
+        .insert("yaml".to_string(), TabWidth::new(2).unwrap());
+    let input = "~~~~ yaml\na:\n\tb:\n \t\tc: \"\\td\"\n~~~~\n\n\
+                 ~~~~ make\nall:\n\techo\n~~~~\n\n\
+                 ~~~~ yaml hongdown-no-format\na:\n\tb: 1\n~~~~\n";
+    assert_eq!(
+        parse_and_serialize_with_options(input, &options),
+        "~~~~ yaml\na:\n  b:\n    c: \"\\td\"\n~~~~\n\n\
+         ~~~~ make\nall:\n\techo\n~~~~\n\n\
+         ~~~~ yaml hongdown-no-format\na:\n\tb: 1\n~~~~\n"
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_code_block_expand_tabs_before_formatter() {
+    use crate::{CodeFormatter, TabWidth};
+
+    let mut options = Options::default();
+    options
+        .code_expand_tabs
+        .insert("text".to_string(), TabWidth::new(4).unwrap());
+    options.code_formatters.insert(
+        "text".to_string(),
+        CodeFormatter {
+            command: vec!["tr".to_string(), " ".to_string(), ".".to_string()],
+            timeout_secs: 5,
+        },
+    );
+    let input = "~~~~ text\n\tx\n~~~~\n";
+    assert_eq!(
+        parse_and_serialize_with_options(input, &options),
+        "~~~~ text\n....x\n~~~~\n"
+    );
+}
+
+#[test]
+fn test_cjk_quotes() {
+    use crate::{CjkQuotes, format_with_warnings};
+
+    let options = Options {
+        cjk_quotes: CjkQuotes::Corner,
+        verify_escaping: true,
+        verify_stability: true,
+        ..Options::default()
+    };
+    let input = "他说\"你好\"，*\"世界\"*。\n\nShe said \"hi\".\n";
+    let result = format_with_warnings(input, &options).unwrap();
+    assert_eq!(
+        result.output,
+        "他说「你好」，*「世界」*。\n\nShe said \u{201C}hi\u{201D}.\n"
+    );
+    assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+}
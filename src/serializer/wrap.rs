@@ -1,99 +1,190 @@
 //! Text wrapping utilities for Markdown serialization.
 
+use std::borrow::Cow;
+
 use unicode_width::UnicodeWidthStr;
 
-/// Wrap text at the specified line width.
+use crate::AutolinkHost;
+
+/// A placeholder for spaces inside protected inline spans
+/// (`<!-- hongdown-off -->...<!-- hongdown-on -->`).
 ///
-/// This function handles soft break markers (`\x00`) which represent where
-/// the original document had line breaks. Short lines are preserved as-is,
-/// while long lines are merged and rewrapped.
+/// It is not whitespace, so the wrapper never breaks a line there.  The
+/// serializer replaces it with a regular space once the document is done.
+pub const PROTECTED_SPACE: char = '\u{E000}';
+
+/// A placeholder for the two trailing spaces of a hard line break.
 ///
-/// Hard line breaks (`\n`) are preserved with two trailing spaces before the
-/// newline, and the prefix is added to the continuation line.
-pub fn wrap_text(text: &str, prefix: &str, line_width: usize) -> String {
-    // First, split by hard line breaks (actual newlines)
-    // These must be preserved with two trailing spaces
-    let hard_break_segments: Vec<&str> = text.split('\n').collect();
+/// The serializer replaces it with the spaces once the document is done,
+/// after it may have stripped the trailing whitespace of every other line.
+pub const HARD_BREAK: char = '\u{E001}';
+
+/// The characters that the serializer uses as placeholders in its output.
+const PLACEHOLDERS: [char; 4] = [
+    PROTECTED_SPACE,
+    HARD_BREAK,
+    super::heading::VERBATIM_START,
+    super::heading::VERBATIM_END,
+];
+
+/// Replace the placeholder characters that occur in `input`, e.g., a
+/// [`PROTECTED_SPACE`] the user wrote, with private use characters that
+/// occur nowhere in it, so that the serializer does not take them for its
+/// own placeholders.  Returns the escaped input and the substitutes to pass
+/// to [`restore_placeholders`] along with the output.
+pub fn escape_placeholders(input: &str) -> (Cow<'_, str>, Vec<(char, char)>) {
+    let mut substitutes = Vec::new();
+    let mut candidates = ('\u{E004}'..='\u{F8FF}').filter(|ch| !input.contains(*ch));
+    for placeholder in PLACEHOLDERS {
+        if input.contains(placeholder)
+            && let Some(substitute) = candidates.next()
+        {
+            substitutes.push((placeholder, substitute));
+        }
+    }
+    if substitutes.is_empty() {
+        return (Cow::Borrowed(input), substitutes);
+    }
+    let escaped = input
+        .chars()
+        .map(|ch| {
+            substitutes
+                .iter()
+                .find(|(placeholder, _)| *placeholder == ch)
+                .map_or(ch, |(_, substitute)| *substitute)
+        })
+        .collect();
+    (Cow::Owned(escaped), substitutes)
+}
 
-    if hard_break_segments.len() == 1 {
-        // No hard line breaks, process normally with soft breaks
-        return wrap_text_segment(text, prefix, line_width);
+/// Put back the placeholder characters of the input that
+/// [`escape_placeholders`] replaced with `substitutes`.
+pub fn restore_placeholders(output: String, substitutes: &[(char, char)]) -> String {
+    if substitutes.is_empty() {
+        return output;
     }
+    output
+        .chars()
+        .map(|ch| {
+            substitutes
+                .iter()
+                .find(|(_, substitute)| *substitute == ch)
+                .map_or(ch, |(placeholder, _)| *placeholder)
+        })
+        .collect()
+}
 
-    // Process each segment separated by hard line breaks
-    let mut result = String::new();
-    for (idx, segment) in hard_break_segments.iter().enumerate() {
-        if idx > 0 {
-            // Add two trailing spaces for hard line break, then newline and prefix
-            result.push_str("  \n");
+/// Replace the space before each issue reference that `host` links
+/// automatically, e.g., `#123`, with [`PROTECTED_SPACE`], so that the
+/// reference never starts a line.  Git takes such a line for a comment, and
+/// drops it when the text ends up in a commit message.
+pub fn protect_issue_references(text: &str, host: AutolinkHost) -> String {
+    if host == AutolinkHost::None {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch == ' ' && is_issue_reference(&chars[i + 1..]) {
+            result.push(PROTECTED_SPACE);
+        } else {
+            result.push(ch);
         }
-        // First segment uses the normal prefix, subsequent segments also need prefix
-        // (wrap_text_segment handles adding the prefix to the first line)
-        let wrapped = wrap_text_segment(segment, prefix, line_width);
-        result.push_str(&wrapped);
     }
-
     result
 }
 
-/// Wrap a single segment of text (between hard line breaks).
-fn wrap_text_segment(text: &str, prefix: &str, line_width: usize) -> String {
-    // Split by soft break markers (original line breaks)
-    // \x00 represents where the original document had line breaks
-    let original_lines: Vec<&str> = text.split('\x00').collect();
-
-    if original_lines.len() == 1 {
-        // No original line breaks, just wrap normally
-        return wrap_single_segment(text, prefix, prefix, line_width);
-    }
+/// Check whether `chars` starts with an issue reference like `#123`.
+fn is_issue_reference(chars: &[char]) -> bool {
+    let Some(('#', rest)) = chars.split_first() else {
+        return false;
+    };
+    let digits = rest.iter().take_while(|ch| ch.is_ascii_digit()).count();
+    digits > 0 && rest.get(digits).is_none_or(|ch| !ch.is_alphanumeric())
+}
 
-    // Process lines: keep short lines as-is until we hit a long line,
-    // then merge everything from that point onward and rewrap
-    let mut result = String::new();
+/// Replace the spaces before each word that would start a block if a line
+/// started with it, e.g., `-`, `1.`, `#`, or `>`, with [`PROTECTED_SPACE`]s,
+/// so that wrapping never moves the word to the start of a line, where it
+/// would turn the rest of the text into a list item, a heading, or the like.
+pub fn protect_block_markers(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
     let mut i = 0;
-
-    while i < original_lines.len() {
-        let line = original_lines[i].trim();
-        let line_with_prefix_len = prefix.width() + line.width();
-
-        if line_with_prefix_len <= line_width {
-            // Line fits within limit, keep it as-is
-            if !result.is_empty() {
-                result.push('\n');
-            }
-            result.push_str(prefix);
-            result.push_str(line);
+    while i < chars.len() {
+        if chars[i] != ' ' {
+            result.push(chars[i]);
             i += 1;
+            continue;
+        }
+        let spaces = chars[i..].iter().take_while(|&&ch| ch == ' ').count();
+        let space = if is_block_marker(&chars[i + spaces..]) {
+            PROTECTED_SPACE
         } else {
-            // Line exceeds limit, merge ALL remaining lines and rewrap
-            let mut merged = String::from(line);
-            i += 1;
-            while i < original_lines.len() {
-                let next_line = original_lines[i].trim();
-                merged.push(' ');
-                merged.push_str(next_line);
-                i += 1;
-            }
+            ' '
+        };
+        result.extend(std::iter::repeat_n(space, spaces));
+        i += spaces;
+    }
+    result
+}
 
-            // Wrap the merged content
-            let wrapped = wrap_single_segment(&merged, prefix, prefix, line_width);
+/// Check whether `chars` starts with a word that starts a block when it is
+/// at the start of a line in the middle of a paragraph: a bullet list
+/// marker, an ordered list marker that may interrupt a paragraph, an ATX
+/// heading marker, a block quote marker, a code fence, or a setext heading
+/// underline.
+fn is_block_marker(chars: &[char]) -> bool {
+    let len = chars
+        .iter()
+        .position(|ch| matches!(ch, ' ' | '\x00' | '\n'))
+        .unwrap_or(chars.len());
+    let word: String = chars[..len].iter().collect();
+    let all = |marker: char| !word.is_empty() && word.chars().all(|ch| ch == marker);
+    matches!(word.as_str(), "+" | "*" | "1." | "1)")
+        || word.starts_with('>')
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+        || (all('#') && word.len() <= 6)
+        || all('-')
+        || all('=')
+}
 
-            if !result.is_empty() {
-                result.push('\n');
-            }
-            result.push_str(&wrapped);
+/// Returns the number of terminal columns `text` takes up, which all width
+/// calculations of the serializer go through.
+///
+/// Wide characters, e.g., CJK ones, take two columns, and combining marks
+/// none.  Emoji sequences, e.g., ones joined with zero-width joiners or
+/// followed by variation selectors, are measured as the single glyph they
+/// are rendered as, rather than as the sum of their characters.
+pub fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Expand the tabs in `text` to spaces, with a tab stop every `tab_width`
+/// columns.  `text` starts at display column `column`, counted from zero.
+pub fn expand_tabs(text: &str, mut column: usize, tab_width: usize) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (i, part) in text.split('\t').enumerate() {
+        if i > 0 {
+            let spaces = tab_width - column % tab_width;
+            result.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
         }
+        result.push_str(part);
+        column += display_width(part);
     }
-
     result
 }
 
 /// Wrap text where the first line has a different prefix than continuation lines.
 ///
-/// This is used for list items where the marker is already output and continuation
-/// lines need indentation.
+/// This function handles soft break markers (`\x00`) which represent where
+/// the original document had line breaks. Short lines are preserved as-is,
+/// while long lines are merged and rewrapped.  The first line starts with
+/// `first_prefix`, e.g., a list marker, and the others with `continuation_prefix`.
 ///
-/// Hard line breaks (`\n`) are preserved with two trailing spaces before the
+/// Hard line breaks (`\n`) are preserved with a [`HARD_BREAK`] before the
 /// newline, and the continuation prefix is added to the continuation line.
 pub fn wrap_text_first_line(
     text: &str,
@@ -115,8 +206,10 @@ pub fn wrap_text_first_line(
     let mut is_first_segment = true;
     for segment in hard_break_segments {
         if !is_first_segment {
-            // Add two trailing spaces for hard line break, then newline
-            result.push_str("  \n");
+            // Add the placeholder for the two trailing spaces of a hard line
+            // break, then newline
+            result.push(HARD_BREAK);
+            result.push('\n');
             result.push_str(continuation_prefix);
         }
         let (current_first, current_cont) = if is_first_segment {
@@ -161,7 +254,7 @@ fn wrap_text_first_line_segment(
         } else {
             continuation_prefix
         };
-        let line_with_prefix_len = current_prefix.width() + line.width();
+        let line_with_prefix_len = display_width(current_prefix) + display_width(line);
 
         if line_with_prefix_len <= line_width {
             // Line fits within limit, keep it as-is
@@ -211,7 +304,7 @@ pub fn wrap_single_segment(
     let mut result = String::new();
     let mut current_line = String::new();
     let mut is_first_line = true;
-    let first_prefix_width = first_prefix.width();
+    let first_prefix_width = display_width(first_prefix);
 
     // Add prefix to first line
     current_line.push_str(first_prefix);
@@ -220,12 +313,14 @@ pub fn wrap_single_segment(
     // - A word (non-space characters) followed by optional spaces
     // - Content inside backticks (treated as a single unbreakable unit)
     // - Content inside brackets (treated as a single unbreakable unit for links)
+    // - Link destinations in angle brackets, which may contain spaces
     // We preserve double spaces after periods.
     let chars = text.chars();
     let mut current_token = String::new();
     let mut trailing_spaces = String::new();
     let mut in_backticks = false;
     let mut bracket_depth = 0;
+    let mut in_angle_destination = false;
 
     for ch in chars {
         if ch == '`' && bracket_depth == 0 {
@@ -256,6 +351,13 @@ pub fn wrap_single_segment(
         } else if in_backticks {
             // Inside backticks, everything is part of the token
             current_token.push(ch);
+        } else if in_angle_destination {
+            // Inside `<...>`, everything up to an unescaped `>` is part of the token
+            in_angle_destination = ch != '>' || current_token.ends_with('\\');
+            current_token.push(ch);
+        } else if ch == '<' && bracket_depth == 0 && current_token.ends_with("](") {
+            current_token.push(ch);
+            in_angle_destination = true;
         } else if ch == '[' {
             // Start of bracket region
             if bracket_depth == 0 && !current_token.is_empty() && !trailing_spaces.is_empty() {
@@ -339,19 +441,19 @@ fn add_token_to_line_with_prefix(
     line_width: usize,
     is_first_line: &mut bool,
 ) {
-    let token_width = token.width();
+    let token_width = display_width(token);
     let spaces_len = trailing_spaces.len();
     let current_prefix_width = if *is_first_line {
         first_prefix_width
     } else {
-        prefix.width()
+        display_width(prefix)
     };
 
-    if current_line.width() == current_prefix_width {
+    if display_width(current_line) == current_prefix_width {
         // First word on this line (prefix already added)
         current_line.push_str(token);
         current_line.push_str(trailing_spaces);
-    } else if current_line.width() + token_width + spaces_len <= line_width {
+    } else if display_width(current_line) + token_width + spaces_len <= line_width {
         // Token fits on current line
         current_line.push_str(token);
         current_line.push_str(trailing_spaces);
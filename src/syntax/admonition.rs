@@ -0,0 +1,67 @@
+//! MkDocs admonitions.
+//!
+//! MkDocs, through Python-Markdown's admonition extension, supports callouts
+//! made of a header line and a body indented by four spaces:
+//!
+//! ```markdown
+//! !!! note "Title"
+//!     The body, which is Markdown as well.
+//! ```
+//!
+//! comrak parses the body either as an indented code block or as the
+//! continuation of the header's paragraph.
+
+use comrak::nodes::AstNode;
+use regex::Regex;
+
+use super::{Container, is_indented};
+
+/// The indentation of an admonition's body relative to its header.
+const BODY_INDENT: usize = 4;
+
+/// Finds admonitions in the source.
+pub(super) struct Finder {
+    header: Regex,
+}
+
+impl Finder {
+    pub(super) fn new() -> Self {
+        Self {
+            // `!!!`, one or more classes (the first being the type), and an
+            // optional quoted title
+            header: Regex::new(r#"^!!!\s+[\w-]+(\s+[\w-]+)*(\s+"[^"]*")?\s*$"#).unwrap(),
+        }
+    }
+
+    /// Returns the admonition whose header is the first line of `paragraph`,
+    /// if any.
+    pub(super) fn find<'a>(&self, paragraph: &'a AstNode<'a>, lines: &[&str]) -> Option<Container> {
+        let sourcepos = paragraph.data.borrow().sourcepos;
+        let start = sourcepos.start;
+        let header = lines
+            .get(start.line.checked_sub(1)?)?
+            .get(start.column.checked_sub(1)?..)?;
+        if !self.header.is_match(header) {
+            return None;
+        }
+        let indent = start.column - 1 + BODY_INDENT;
+
+        // The body spans the following lines that are indented or blank
+        let mut end_line = start.line;
+        for (i, line) in lines.iter().enumerate().skip(start.line) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !is_indented(line, indent) {
+                break;
+            }
+            end_line = i + 1;
+        }
+        Some(Container {
+            opening: header.trim_end().to_string(),
+            end_line,
+            content: start.line + 1..=end_line,
+            indent,
+        })
+    }
+}
@@ -0,0 +1,100 @@
+//! Pandoc fenced divs.
+//!
+//! Pandoc's `fenced_divs` extension encloses blocks in fences of three or
+//! more colons, the opening one followed by attributes or a class name:
+//!
+//! ```markdown
+//! ::: {.warning #id}
+//! The content, which is Markdown as well.
+//! :::
+//! ```
+//!
+//! Divs can be nested, and a fence without attributes closes the innermost
+//! one.  comrak parses the fences as paragraphs, or as part of the blocks
+//! right before or after them.
+
+use std::collections::HashSet;
+
+use comrak::nodes::{AstNode, NodeValue};
+use regex::Regex;
+
+use super::{Container, is_indented};
+
+/// Finds fenced divs in the source.
+pub(super) struct Finder {
+    opening: Regex,
+    closing: Regex,
+    /// The lines within code and HTML blocks, where fences are not fences.
+    verbatim: HashSet<usize>,
+}
+
+impl Finder {
+    pub(super) fn new<'a>(root: &'a AstNode<'a>) -> Self {
+        let mut verbatim = HashSet::new();
+        for node in root.descendants() {
+            let ast = node.data.borrow();
+            if matches!(ast.value, NodeValue::CodeBlock(_) | NodeValue::HtmlBlock(_)) {
+                verbatim.extend(ast.sourcepos.start.line..=ast.sourcepos.end.line);
+            }
+        }
+        Self {
+            // Three or more colons, attributes or a class name, and
+            // optionally more colons
+            opening: Regex::new(r"^:{3,}\s*(\{[^}]*\}|[\w-]+)\s*:*\s*$").unwrap(),
+            closing: Regex::new(r"^:{3,}\s*$").unwrap(),
+            verbatim,
+        }
+    }
+
+    /// Returns the fenced div whose opening fence is the first line of
+    /// `paragraph`, if it is closed.
+    pub(super) fn find<'a>(&self, paragraph: &'a AstNode<'a>, lines: &[&str]) -> Option<Container> {
+        let start = paragraph.data.borrow().sourcepos.start;
+        let opening = lines
+            .get(start.line.checked_sub(1)?)?
+            .get(start.column.checked_sub(1)?..)?;
+        if !self.opening.is_match(opening) {
+            return None;
+        }
+
+        let indent = content_indent(paragraph);
+        let mut depth = 0;
+        for (i, line) in lines.iter().enumerate().skip(start.line) {
+            if line.trim().is_empty() || self.verbatim.contains(&(i + 1)) {
+                continue;
+            }
+            if !is_indented(line, indent) {
+                return None;
+            }
+            let line = line[indent..].trim_start();
+            if self.closing.is_match(line) {
+                if depth == 0 {
+                    return Some(Container {
+                        opening: opening.trim_end().to_string(),
+                        end_line: i + 1,
+                        content: start.line + 1..=i,
+                        indent,
+                    });
+                }
+                depth -= 1;
+            } else if self.opening.is_match(line) {
+                depth += 1;
+            }
+        }
+        None
+    }
+}
+
+/// Returns the column where the content of the container of `paragraph`
+/// starts, which the lines of a div within it are indented by.
+fn content_indent<'a>(paragraph: &'a AstNode<'a>) -> usize {
+    let parent = paragraph.parent().map(|parent| parent.data.borrow());
+    match parent
+        .as_ref()
+        .map(|parent| (&parent.value, parent.sourcepos))
+    {
+        Some((NodeValue::Document, _)) => 0,
+        Some((NodeValue::Item(list), sourcepos)) => sourcepos.start.column - 1 + list.padding,
+        _ => paragraph.data.borrow().sourcepos.start.column - 1,
+    }
+}
@@ -0,0 +1,224 @@
+//! Block containers that comrak does not know.
+//!
+//! Some Markdown dialects have block containers whose content is Markdown,
+//! which comrak parses as paragraphs and code blocks instead:
+//!
+//!  -  [MkDocs admonitions](admonition), whose body is indented by four
+//!     spaces below a `!!! type` line
+//!  -  [Pandoc fenced divs](fenced_div), whose content is enclosed in `:::`
+//!     fences
+//!
+//! After parsing, [`expand`] replaces each of them with a block-level
+//! [`NodeValue::Raw`] node holding its opening line, whose children are its
+//! content parsed on its own.
+
+mod admonition;
+mod fenced_div;
+
+use std::ops::RangeInclusive;
+
+use comrak::Arena;
+use comrak::nodes::{Ast, AstNode, NodeValue};
+
+use crate::{Extensions, parse_markdown};
+
+/// The kind of a container made by [`expand`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContainerKind {
+    /// A MkDocs admonition.
+    Admonition,
+    /// A Pandoc fenced div.
+    FencedDiv,
+}
+
+/// Returns the kind of container that `node` is, if it was made by
+/// [`expand`].
+pub(crate) fn container_kind<'a>(node: &'a AstNode<'a>) -> Option<ContainerKind> {
+    let is_block = node
+        .parent()
+        .is_some_and(|parent| !parent.data.borrow().value.contains_inlines());
+    match &node.data.borrow().value {
+        NodeValue::Raw(opening) if is_block && opening.starts_with("!!!") => {
+            Some(ContainerKind::Admonition)
+        }
+        NodeValue::Raw(opening) if is_block && opening.starts_with(":::") => {
+            Some(ContainerKind::FencedDiv)
+        }
+        _ => None,
+    }
+}
+
+/// A container found in the source.
+struct Container {
+    /// The opening line, e.g., `!!! note`.
+    opening: String,
+    /// The last line of the container.
+    end_line: usize,
+    /// The lines of the content.
+    content: RangeInclusive<usize>,
+    /// The number of columns each line of the content is indented by.
+    indent: usize,
+}
+
+/// Replace the containers in the document parsed from `input` with
+/// block-level [`NodeValue::Raw`] nodes.
+///
+/// The positions of the content's nodes refer to `input`, as if they had
+/// been parsed along with the rest of the document.  Containers within block
+/// quotes are not recognized.
+pub(crate) fn expand<'a>(
+    root: &'a AstNode<'a>,
+    arena: &'a Arena<'a>,
+    input: &str,
+    extensions: &Extensions,
+) {
+    let admonitions = input.contains("!!!").then(admonition::Finder::new);
+    let fenced_divs = input.contains(":::").then(|| fenced_div::Finder::new(root));
+    if admonitions.is_none() && fenced_divs.is_none() {
+        return;
+    }
+    let lines: Vec<&str> = input.lines().collect();
+    let paragraphs: Vec<_> = root
+        .descendants()
+        .filter(|node| {
+            matches!(node.data.borrow().value, NodeValue::Paragraph)
+                && !node.ancestors().any(|ancestor| {
+                    matches!(
+                        ancestor.data.borrow().value,
+                        NodeValue::BlockQuote | NodeValue::Alert(_)
+                    )
+                })
+        })
+        .collect();
+    let mut definitions = None;
+
+    // Containers within the content of an earlier one have been expanded
+    // along with that content
+    let mut expanded_until = 0;
+    for paragraph in paragraphs {
+        if paragraph.data.borrow().sourcepos.start.line <= expanded_until {
+            continue;
+        }
+        let container = admonitions
+            .as_ref()
+            .and_then(|finder| finder.find(paragraph, &lines))
+            .or_else(|| fenced_divs.as_ref()?.find(paragraph, &lines));
+        let Some(container) = container else {
+            continue;
+        };
+        let definitions = definitions.get_or_insert_with(|| collect_definitions(root, &lines));
+        if replace(
+            paragraph,
+            &container,
+            arena,
+            &lines,
+            definitions,
+            extensions,
+        ) {
+            expanded_until = container.end_line;
+        }
+    }
+}
+
+/// Replace `paragraph`, which starts `container`, and the blocks after it
+/// with the container.  Returns `false` if any of those blocks continues
+/// beyond the container, in which case nothing is replaced.
+fn replace<'a>(
+    paragraph: &'a AstNode<'a>,
+    container: &Container,
+    arena: &'a Arena<'a>,
+    lines: &[&str],
+    definitions: &str,
+    extensions: &Extensions,
+) -> bool {
+    let start = paragraph.data.borrow().sourcepos.start;
+    let end_line = container.end_line;
+    let mut blocks = Vec::new();
+    let mut next = Some(paragraph);
+    while let Some(block) = next {
+        let sourcepos = block.data.borrow().sourcepos;
+        if sourcepos.start.line > end_line {
+            break;
+        }
+        // Indented code blocks may end with blank lines
+        let rest = lines.get(end_line..sourcepos.end.line).unwrap_or_default();
+        if !rest.iter().all(|line| line.trim().is_empty()) {
+            return false;
+        }
+        blocks.push(block);
+        next = block.next_sibling();
+    }
+
+    let sourcepos = (
+        start.line,
+        start.column,
+        end_line,
+        lines[end_line - 1].len().max(1),
+    );
+    let node: &'a AstNode<'a> = arena.alloc(
+        Ast::new_with_sourcepos(NodeValue::Raw(container.opening.clone()), sourcepos.into()).into(),
+    );
+    paragraph.insert_before(node);
+    for block in blocks {
+        block.detach();
+    }
+
+    let (first, last) = (*container.content.start(), *container.content.end());
+    if first <= last {
+        // Definitions follow the content so that its links resolve as they
+        // would in the whole document
+        let mut content = String::new();
+        for line in &lines[first - 1..last] {
+            content.push_str(line.get(container.indent..).unwrap_or_default());
+            content.push('\n');
+        }
+        content.push('\n');
+        content.push_str(definitions);
+
+        let content_root = parse_markdown(arena, &content, extensions);
+        for descendant in content_root.descendants().skip(1) {
+            let sourcepos = &mut descendant.data.borrow_mut().sourcepos;
+            for position in [&mut sourcepos.start, &mut sourcepos.end] {
+                if position.line > 0 {
+                    position.line += first - 1;
+                    position.column += container.indent;
+                }
+            }
+        }
+        let children: Vec<_> = content_root.children().collect();
+        for child in children {
+            node.append(child);
+        }
+    }
+    true
+}
+
+/// Whether `line` starts with `indent` spaces.
+fn is_indented(line: &str, indent: usize) -> bool {
+    line.get(..indent)
+        .is_some_and(|prefix| prefix.bytes().all(|b| b == b' '))
+}
+
+/// Collect the lines of the link reference definitions in the document.
+///
+/// comrak does not keep definitions in the AST, so these are the non-blank
+/// lines not covered by any top-level block.
+fn collect_definitions<'a>(root: &'a AstNode<'a>, lines: &[&str]) -> String {
+    let mut covered = vec![false; lines.len()];
+    for child in root.children() {
+        let sourcepos = child.data.borrow().sourcepos;
+        let start = sourcepos.start.line.max(1) - 1;
+        let end = sourcepos.end.line.min(lines.len());
+        for line in covered.iter_mut().take(end).skip(start) {
+            *line = true;
+        }
+    }
+    let mut definitions = String::new();
+    for (line, is_covered) in lines.iter().zip(covered) {
+        if !is_covered && !line.trim().is_empty() {
+            definitions.push_str(line);
+            definitions.push('\n');
+        }
+    }
+    definitions
+}
@@ -0,0 +1,393 @@
+//! Detection of stale tables of contents.
+//!
+//! Many documents start with a hand-written table of contents: a list of
+//! links to the headings of the document, e.g., `[Usage](#usage)`.  Such a
+//! list goes stale as soon as a heading is renamed or removed, so its
+//! anchors are compared with those of the current headings, computed the way
+//! GitHub does, and the ones that match no heading are reported.
+//!
+//! The anchors of headings are also available to library users through
+//! [`slugify`], so that other tools link to the same anchors.
+
+use std::collections::{HashMap, HashSet};
+
+use comrak::nodes::{AstNode, NodeValue};
+use regex::Regex;
+
+use crate::WarningCode;
+use crate::serializer::Warning;
+
+/// Return a `stale-toc` warning for each table of contents near the top of
+/// the document that links to anchors no heading has.
+pub(crate) fn stale_toc_warnings<'a>(root: &'a AstNode<'a>) -> Vec<Warning> {
+    let tocs = find_tocs(root);
+    if tocs.is_empty() {
+        return Vec::new();
+    }
+    let anchors = collect_anchors(root);
+    let mut warnings = Vec::new();
+    for toc in tocs {
+        let mut missing: Vec<String> = Vec::new();
+        for link in toc.descendants() {
+            if let NodeValue::Link(link) = &link.data.borrow().value {
+                let fragment = &link.url[1..];
+                if !anchors.contains(&percent_decode(fragment).to_lowercase())
+                    && !missing.contains(&link.url)
+                {
+                    missing.push(link.url.clone());
+                }
+            }
+        }
+        if missing.is_empty() {
+            continue;
+        }
+        let start = toc.data.borrow().sourcepos.start;
+        warnings.push(Warning::new(
+            WarningCode::StaleToc,
+            start.line,
+            start.column,
+            format!(
+                "table of contents links to {} that no heading has: {}",
+                if missing.len() == 1 {
+                    "an anchor"
+                } else {
+                    "anchors"
+                },
+                missing.join(", ")
+            ),
+        ));
+    }
+    warnings
+}
+
+/// Find the lists of links to anchors that come before the second section,
+/// i.e., before the second top-level h1 or h2 heading.
+fn find_tocs<'a>(root: &'a AstNode<'a>) -> Vec<&'a AstNode<'a>> {
+    let mut tocs = Vec::new();
+    let mut sections = 0;
+    for child in root.children() {
+        match &child.data.borrow().value {
+            NodeValue::Heading(heading) if heading.level <= 2 => {
+                sections += 1;
+                if sections > 2 {
+                    break;
+                }
+            }
+            NodeValue::List(_) if is_toc(child) => tocs.push(child),
+            _ => {}
+        }
+    }
+    tocs
+}
+
+/// Check if `list` is a table of contents: each of its items starts with
+/// a link, and all of its links, at least two, point to anchors.
+fn is_toc<'a>(list: &'a AstNode<'a>) -> bool {
+    let mut links = 0;
+    for node in list.descendants() {
+        match &node.data.borrow().value {
+            NodeValue::Link(link) => {
+                if !link.url.starts_with('#') || link.url.len() < 2 {
+                    return false;
+                }
+                links += 1;
+            }
+            NodeValue::Item(_) => {
+                let starts_with_link = node
+                    .first_child()
+                    .filter(|first| matches!(first.data.borrow().value, NodeValue::Paragraph))
+                    .and_then(|paragraph| paragraph.first_child())
+                    .is_some_and(|first| matches!(first.data.borrow().value, NodeValue::Link(_)));
+                if !starts_with_link {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    links >= 2
+}
+
+/// Collect the anchors of the document in lowercase: those GitHub generates
+/// for headings, explicit heading IDs like `{#id}`, and `id` and `name`
+/// attributes of HTML elements.
+fn collect_anchors<'a>(root: &'a AstNode<'a>) -> HashSet<String> {
+    let id_attribute = Regex::new(r#"\b(?:id|name)\s*=\s*["']?([^"'\s>]+)"#).unwrap();
+    let heading_id = Regex::new(r"\{#([^\s}]+)[^}]*\}\s*$").unwrap();
+    let mut anchors = HashSet::new();
+    for heading in headings(root) {
+        if let Some(caps) = heading_id.captures(&heading.text) {
+            anchors.insert(caps[1].to_lowercase());
+        }
+        anchors.insert(heading.slug);
+    }
+    for node in root.descendants() {
+        match &node.data.borrow().value {
+            NodeValue::HtmlBlock(html) => {
+                for caps in id_attribute.captures_iter(&html.literal) {
+                    anchors.insert(caps[1].to_lowercase());
+                }
+            }
+            NodeValue::HtmlInline(html) => {
+                for caps in id_attribute.captures_iter(html) {
+                    anchors.insert(caps[1].to_lowercase());
+                }
+            }
+            _ => {}
+        }
+    }
+    anchors
+}
+
+/// A heading of the document.
+pub(crate) struct Heading<'a> {
+    pub node: &'a AstNode<'a>,
+    /// The level of the heading, from 1 to 6.
+    pub level: u8,
+    /// The plain text of the heading.
+    pub text: String,
+    /// The anchor GitHub generates for the heading, with a numeric suffix if
+    /// an earlier heading has the same one, e.g., `usage-1`.
+    pub slug: String,
+}
+
+/// Collect the headings of the document in order.
+pub(crate) fn headings<'a>(root: &'a AstNode<'a>) -> Vec<Heading<'a>> {
+    let mut headings = Vec::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for node in root.descendants() {
+        let level = match &node.data.borrow().value {
+            NodeValue::Heading(heading) => heading.level,
+            _ => continue,
+        };
+        let mut text = String::new();
+        collect_text(node, &mut text);
+        let slug = slugify(&text, SlugStyle::GitHub);
+        let count = counts.entry(slug.clone()).or_insert(0);
+        let slug = if *count == 0 {
+            slug
+        } else {
+            format!("{}-{}", slug, count)
+        };
+        *count += 1;
+        headings.push(Heading {
+            node,
+            level,
+            text,
+            slug,
+        });
+    }
+    headings
+}
+
+/// Collect the plain text of a heading or a link, including code spans and
+/// image alt text.
+pub(crate) fn collect_text<'a>(node: &'a AstNode<'a>, text: &mut String) {
+    for child in node.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(t) => text.push_str(t),
+            NodeValue::Code(code) => text.push_str(&code.literal),
+            NodeValue::SoftBreak | NodeValue::LineBreak => text.push(' '),
+            _ => collect_text(child, text),
+        }
+    }
+}
+
+/// How heading text is turned into an anchor.
+#[derive(Debug, Clone, Copy)]
+pub enum SlugStyle {
+    /// The anchors GitHub generates: lowercased, without punctuation, and
+    /// with spaces replaced by hyphens, e.g., `whats-new` for `What's new`.
+    GitHub,
+    /// The anchors GitLab generates: like GitHub's, but with runs of hyphens
+    /// collapsed into one, e.g., `a-b` for `A - B`.
+    GitLab,
+    /// A custom function turning heading text into an anchor.
+    Custom(fn(&str) -> String),
+}
+
+/// Turn the plain text of a heading into its anchor, without the leading
+/// `#`, in the given `style`.
+///
+/// Note that a heading whose anchor an earlier heading already has gets
+/// a numeric suffix, e.g., `usage-1` for the second `Usage` heading.
+///
+/// # Example
+///
+/// ```
+/// use hongdown::{SlugStyle, slugify};
+///
+/// assert_eq!(slugify("What's new in v2.0?", SlugStyle::GitHub), "whats-new-in-v20");
+/// assert_eq!(slugify("Foo - Bar", SlugStyle::GitHub), "foo---bar");
+/// assert_eq!(slugify("Foo - Bar", SlugStyle::GitLab), "foo-bar");
+/// ```
+pub fn slugify(text: &str, style: SlugStyle) -> String {
+    let slug: String = match style {
+        SlugStyle::Custom(slugify) => return slugify(text),
+        SlugStyle::GitHub | SlugStyle::GitLab => text
+            .trim()
+            .to_lowercase()
+            .chars()
+            .filter_map(|ch| match ch {
+                ' ' => Some('-'),
+                '-' | '_' => Some(ch),
+                _ if ch.is_alphanumeric() => Some(ch),
+                _ => None,
+            })
+            .collect(),
+    };
+    if !matches!(style, SlugStyle::GitLab) {
+        return slug;
+    }
+    let mut collapsed = String::with_capacity(slug.len());
+    for ch in slug.chars() {
+        if !(ch == '-' && collapsed.ends_with('-')) {
+            collapsed.push(ch);
+        }
+    }
+    collapsed
+}
+
+/// Decode percent-encoded bytes in a URL fragment, e.g., `%ED%95%9C`.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = text
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Options, format_with_warnings};
+
+    #[test]
+    fn test_slugify() {
+        let github = |text| slugify(text, SlugStyle::GitHub);
+        assert_eq!(github("Getting Started"), "getting-started");
+        assert_eq!(github("What's new in v2.0?"), "whats-new-in-v20");
+        assert_eq!(github("`foo_bar` and -baz-"), "foo_bar-and--baz-");
+        assert_eq!(github("한국어 문서"), "한국어-문서");
+    }
+
+    #[test]
+    fn test_slugify_gitlab() {
+        let gitlab = |text| slugify(text, SlugStyle::GitLab);
+        assert_eq!(gitlab("Getting Started"), "getting-started");
+        assert_eq!(gitlab("`foo_bar` and -baz-"), "foo_bar-and-baz-");
+        assert_eq!(gitlab("A -- B"), "a-b");
+    }
+
+    #[test]
+    fn test_slugify_custom() {
+        let style = SlugStyle::Custom(|text| text.to_uppercase().replace(' ', "_"));
+        assert_eq!(slugify("Getting Started", style), "GETTING_STARTED");
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("%ED%95%9C%EA%B8%80"), "한글");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    fn toc_warnings(input: &str) -> Vec<(String, usize)> {
+        format_with_warnings(input, &Options::default())
+            .unwrap()
+            .warnings
+            .into_iter()
+            .filter(|warning| warning.code == WarningCode::StaleToc)
+            .map(|warning| (warning.message, warning.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_stale_toc() {
+        let input = "# Title
+
+ -  [Installation](#installation)
+ -  [Usage](#usage)
+     -  [Options](#options)
+ -  [FAQ](#faq)
+
+## Installation
+
+## Usage
+
+### Command-line options
+
+### Options
+
+### Options
+";
+        assert_eq!(
+            toc_warnings(input),
+            vec![(
+                "table of contents links to an anchor that no heading has: #faq".to_string(),
+                3
+            )]
+        );
+    }
+
+    #[test]
+    fn test_up_to_date_toc() {
+        let input = "Title
+=====
+
+ 1. [What's new?](#whats-new)
+ 2. [Setup](#setup)
+ 3. [Setup again](#setup-1)
+ 4. [Anchor](#custom)
+ 5. [한글](#%ED%95%9C%EA%B8%80)
+
+What's new?
+-----------
+
+Setup
+-----
+
+Setup
+-----
+
+<a id=\"custom\"></a>
+
+한글
+----
+";
+        assert!(toc_warnings(input).is_empty());
+    }
+
+    #[test]
+    fn test_not_a_toc() {
+        // Lists mixing other links, lists far from the top, and items not
+        // starting with links are not tables of contents
+        let input = "# Title
+
+ -  [Usage](#usage)
+ -  [Website](https://example.com/)
+
+ -  See [usage](#usage).
+ -  See [options](#options).
+
+## First
+
+## Second
+
+ -  [Missing](#missing)
+ -  [Also missing](#also-missing)
+";
+        assert!(toc_warnings(input).is_empty());
+    }
+}
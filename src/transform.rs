@@ -0,0 +1,804 @@
+//! Pre-serialization AST transforms.
+//!
+//! Transforms receive the parsed comrak AST and may rewrite it before the
+//! serializer runs.  This lets library users implement custom policies, such
+//! as rewriting internal link prefixes, without forking the serializer.
+
+use comrak::Arena;
+use comrak::nodes::{AlertType, Ast, AstNode, NodeAlert, NodeHtmlBlock, NodeLink, NodeValue};
+
+use crate::Options;
+use crate::config::{FigureStyle, HtmlImageAttributes};
+use crate::rewriter::rewrite_urls;
+use crate::serializer::figure_parts;
+
+/// A transform applied to the parsed document before serialization.
+///
+/// Transforms are run in the order they appear in [`Options::transforms`].
+/// New nodes can be allocated in `arena` and attached to the tree.
+///
+/// Functions with the matching signature implement this trait as well.
+///
+/// MkDocs admonitions and Pandoc fenced divs, which comrak does not know,
+/// are represented by block-level
+/// [`NodeValue::Raw`](comrak::nodes::NodeValue::Raw) nodes that hold the
+/// opening line, e.g., `!!! note "Title"` or `::: {.warning}`, and contain
+/// the blocks of the content.
+///
+/// The serializer reuses the original source text of some nodes, such as code
+/// spans, to preserve their spelling.  When rewriting such a node, set its
+/// `sourcepos` to `(0, 0, 0, 0).into()` so that it is serialized from the AST
+/// instead.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use hongdown::comrak::Arena;
+/// use hongdown::comrak::nodes::{AstNode, NodeValue};
+/// use hongdown::{AstTransform, Options, format};
+///
+/// /// Rewrites links to the old documentation host.
+/// struct RewriteDocsLinks;
+///
+/// impl AstTransform for RewriteDocsLinks {
+///     fn transform<'a>(&self, root: &'a AstNode<'a>, _arena: &'a Arena<'a>) {
+///         for node in root.descendants() {
+///             if let NodeValue::Link(ref mut link) = node.data.borrow_mut().value {
+///                 link.url = link.url.replace("https://old.example/", "/docs/");
+///             }
+///         }
+///     }
+/// }
+///
+/// let options = Options {
+///     transforms: vec![Arc::new(RewriteDocsLinks)],
+///     ..Options::default()
+/// };
+/// let output = format("[Guide](https://old.example/guide)", &options).unwrap();
+/// assert_eq!(output, "[Guide](/docs/guide)\n");
+/// ```
+pub trait AstTransform: Send + Sync {
+    /// Rewrites the document rooted at `root` in place.
+    fn transform<'a>(&self, root: &'a AstNode<'a>, arena: &'a Arena<'a>);
+
+    /// Returns a name identifying this transform in debug output.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<F> AstTransform for F
+where
+    F: for<'a> Fn(&'a AstNode<'a>, &'a Arena<'a>) + Send + Sync,
+{
+    fn transform<'a>(&self, root: &'a AstNode<'a>, arena: &'a Arena<'a>) {
+        self(root, arena)
+    }
+}
+
+impl std::fmt::Debug for dyn AstTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AstTransform").field(&self.name()).finish()
+    }
+}
+
+/// Run all transforms configured in `options`, then the URL rewriter, over
+/// the document.
+pub(crate) fn apply_transforms<'a>(root: &'a AstNode<'a>, arena: &'a Arena<'a>, options: &Options) {
+    if options.alert_convert_bold_labels {
+        convert_bold_labels(root, arena);
+    }
+    if options.image_convert_html {
+        convert_html_images(root, arena, options.image_html_attributes);
+    }
+    match options.figure_style {
+        FigureStyle::Preserve => {}
+        FigureStyle::Markdown => convert_html_figures(root, arena),
+        FigureStyle::Html => convert_markdown_figures(root, arena),
+    }
+    for transform in &options.transforms {
+        transform.transform(root, arena);
+    }
+    if let Some(rewriter) = &options.url_rewriter {
+        rewrite_urls(root, rewriter.as_ref());
+    }
+}
+
+/// Rewrite block quotes and paragraphs that start with a bold label, e.g.,
+/// `**Note:**` or `**Warning**:`, as GitHub alerts of the same type.
+///
+/// Paragraphs already within a block quote are left alone, as are labels
+/// that nothing follows.
+fn convert_bold_labels<'a>(root: &'a AstNode<'a>, arena: &'a Arena<'a>) {
+    let candidates: Vec<_> = root
+        .descendants()
+        .filter(|node| {
+            matches!(
+                node.data.borrow().value,
+                NodeValue::BlockQuote | NodeValue::Paragraph
+            )
+        })
+        .collect();
+    for node in candidates {
+        let is_block_quote = matches!(node.data.borrow().value, NodeValue::BlockQuote);
+        if is_block_quote {
+            let Some(paragraph) = node.first_child() else {
+                continue;
+            };
+            let Some(alert_type) = bold_label(paragraph) else {
+                continue;
+            };
+            if paragraph.next_sibling().is_none() && !has_content_after_label(paragraph) {
+                continue;
+            }
+            strip_bold_label(paragraph);
+            node.data.borrow_mut().value = alert(alert_type);
+        } else {
+            let within_quote = node.ancestors().skip(1).any(|ancestor| {
+                matches!(
+                    ancestor.data.borrow().value,
+                    NodeValue::BlockQuote | NodeValue::Alert(_)
+                )
+            });
+            if within_quote || !has_content_after_label(node) {
+                continue;
+            }
+            let Some(alert_type) = bold_label(node) else {
+                continue;
+            };
+            strip_bold_label(node);
+            // Keep the paragraph's position so that the lines it spans are
+            // still known to be covered by a block
+            let sourcepos = node.data.borrow().sourcepos;
+            let alert: &'a AstNode<'a> =
+                arena.alloc(Ast::new_with_sourcepos(alert(alert_type), sourcepos).into());
+            node.insert_before(alert);
+            alert.append(node);
+        }
+    }
+}
+
+/// Returns a GitHub alert of the given type without a title.
+fn alert(alert_type: AlertType) -> NodeValue {
+    NodeValue::Alert(Box::new(NodeAlert {
+        alert_type,
+        title: None,
+        multiline: false,
+        fence_length: 0,
+        fence_offset: 0,
+    }))
+}
+
+/// Returns the alert type named by the bold label that `paragraph` starts
+/// with, if any.
+fn bold_label<'a>(paragraph: &'a AstNode<'a>) -> Option<AlertType> {
+    if !matches!(paragraph.data.borrow().value, NodeValue::Paragraph) {
+        return None;
+    }
+    let strong = paragraph.first_child()?;
+    if !matches!(strong.data.borrow().value, NodeValue::Strong) {
+        return None;
+    }
+    let mut label = String::new();
+    for child in strong.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(text) => label.push_str(text),
+            _ => return None,
+        }
+    }
+    // The colon may be either inside or right after the bold label
+    let label = match label.trim_end().strip_suffix(':') {
+        Some(label) => label,
+        None => {
+            let next = strong.next_sibling()?;
+            match &next.data.borrow().value {
+                NodeValue::Text(text) if text.starts_with(':') => {}
+                _ => return None,
+            }
+            label.trim_end()
+        }
+    };
+    match label.trim().to_ascii_lowercase().as_str() {
+        "note" => Some(AlertType::Note),
+        "tip" => Some(AlertType::Tip),
+        "important" => Some(AlertType::Important),
+        "warning" => Some(AlertType::Warning),
+        "caution" => Some(AlertType::Caution),
+        _ => None,
+    }
+}
+
+/// Whether `paragraph` has any content other than its leading bold label.
+fn has_content_after_label<'a>(paragraph: &'a AstNode<'a>) -> bool {
+    paragraph
+        .children()
+        .skip(1)
+        .any(|child| match &child.data.borrow().value {
+            NodeValue::Text(text) => !text.trim_start_matches(':').trim().is_empty(),
+            NodeValue::SoftBreak | NodeValue::LineBreak => false,
+            _ => true,
+        })
+}
+
+/// Remove the bold label that `paragraph` starts with, along with the colon
+/// and whitespace after it.
+fn strip_bold_label<'a>(paragraph: &'a AstNode<'a>) {
+    let Some(strong) = paragraph.first_child() else {
+        return;
+    };
+    // Unless the label includes it, the colon follows the label
+    let mut colon = !strong
+        .last_child()
+        .is_some_and(|text| match &text.data.borrow().value {
+            NodeValue::Text(text) => text.trim_end().ends_with(':'),
+            _ => false,
+        });
+    strong.detach();
+    while let Some(child) = paragraph.first_child() {
+        let mut ast = child.data.borrow_mut();
+        match ast.value {
+            NodeValue::Text(ref mut text) => {
+                let rest = match text.strip_prefix(':') {
+                    Some(rest) if colon => rest,
+                    _ => text,
+                };
+                let rest = rest.trim_start().to_string();
+                colon = false;
+                if !rest.is_empty() {
+                    *text = rest.into();
+                    break;
+                }
+            }
+            NodeValue::SoftBreak | NodeValue::LineBreak => {}
+            _ => break,
+        }
+        drop(ast);
+        child.detach();
+    }
+    if paragraph.first_child().is_none() {
+        paragraph.detach();
+    }
+}
+
+/// Rewrite HTML `<img>` elements, either inline or as a whole HTML block,
+/// as Markdown images, e.g., `<img src="a.png" alt="A">` as `![A](a.png)`.
+///
+/// Elements with attributes other than `src`, `alt`, and `title` are left
+/// alone, unless `attributes` asks to keep them in an attribute block.
+fn convert_html_images<'a>(
+    root: &'a AstNode<'a>,
+    arena: &'a Arena<'a>,
+    attributes: HtmlImageAttributes,
+) {
+    let candidates: Vec<_> = root
+        .descendants()
+        .filter(|node| {
+            matches!(
+                node.data.borrow().value,
+                NodeValue::HtmlInline(_) | NodeValue::HtmlBlock(_)
+            )
+        })
+        .collect();
+    for node in candidates {
+        let (html, is_block) = match &node.data.borrow().value {
+            NodeValue::HtmlInline(html) => (html.clone(), false),
+            NodeValue::HtmlBlock(block) => (block.literal.clone(), true),
+            _ => continue,
+        };
+        let Some((link, alt, rest)) = html_image(&html, attributes) else {
+            continue;
+        };
+        // Text starting with `{` right after the image would be taken for
+        // its attribute block
+        let followed_by_brace = node.next_sibling().is_some_and(|next| {
+            matches!(&next.data.borrow().value, NodeValue::Text(text) if text.starts_with('{'))
+        });
+        if followed_by_brace {
+            continue;
+        }
+        let image = new_node(arena, NodeValue::Image(Box::new(link)));
+        if !alt.is_empty() {
+            image.append(new_node(arena, NodeValue::Text(alt.into())));
+        }
+        let attribute_block = (!rest.is_empty()).then(|| format!("{{{}}}", rest.join(" ")));
+        if is_block {
+            // Keep the block's position so that the lines it spans are still
+            // known to be covered by a block
+            let sourcepos = node.data.borrow().sourcepos;
+            let paragraph: &'a AstNode<'a> =
+                arena.alloc(Ast::new_with_sourcepos(NodeValue::Paragraph, sourcepos).into());
+            paragraph.append(image);
+            if let Some(block) = attribute_block {
+                paragraph.append(new_node(arena, NodeValue::Text(block.into())));
+            }
+            node.insert_before(paragraph);
+        } else {
+            node.insert_before(image);
+            if let Some(block) = attribute_block {
+                match node.next_sibling() {
+                    Some(next) if matches!(next.data.borrow().value, NodeValue::Text(_)) => {
+                        if let NodeValue::Text(ref mut text) = next.data.borrow_mut().value {
+                            *text = format!("{}{}", block, text).into();
+                        }
+                    }
+                    _ => node.insert_after(new_node(arena, NodeValue::Text(block.into()))),
+                }
+            }
+        }
+        node.detach();
+    }
+}
+
+/// Rewrite simple HTML `<figure>` elements, i.e., an `<img>` and
+/// a `<figcaption>` of plain text, as an image followed by an emphasized
+/// caption on the next line.
+fn convert_html_figures<'a>(root: &'a AstNode<'a>, arena: &'a Arena<'a>) {
+    let candidates: Vec<_> = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::HtmlBlock(_)))
+        .collect();
+    for node in candidates {
+        let Some((link, alt, caption)) = (match &node.data.borrow().value {
+            NodeValue::HtmlBlock(block) => html_figure(&block.literal),
+            _ => None,
+        }) else {
+            continue;
+        };
+        // Keep the block's position so that the lines it spans are still
+        // known to be covered by a block
+        let sourcepos = node.data.borrow().sourcepos;
+        let paragraph: &'a AstNode<'a> =
+            arena.alloc(Ast::new_with_sourcepos(NodeValue::Paragraph, sourcepos).into());
+        let image = new_node(arena, NodeValue::Image(Box::new(link)));
+        if !alt.is_empty() {
+            image.append(new_node(arena, NodeValue::Text(alt.into())));
+        }
+        let emph = new_node(arena, NodeValue::Emph);
+        emph.append(new_node(arena, NodeValue::Text(caption.into())));
+        paragraph.append(image);
+        paragraph.append(new_node(arena, NodeValue::SoftBreak));
+        paragraph.append(emph);
+        node.insert_before(paragraph);
+        node.detach();
+    }
+}
+
+/// Parse `html` if it is a simple `<figure>` element.  Returns the
+/// destination and title of its image, the image's alt text, and the
+/// caption.
+fn html_figure(html: &str) -> Option<(NodeLink, String, String)> {
+    let inner = strip_prefix_ignore_case(html.trim(), "<figure>")?;
+    let inner = strip_suffix_ignore_case(inner, "</figure>")?.trim();
+    let img_end = inner.find('>')? + 1;
+    let (link, alt, rest) = html_image(&inner[..img_end], HtmlImageAttributes::Skip)?;
+    let caption = strip_prefix_ignore_case(inner[img_end..].trim(), "<figcaption>")?;
+    let caption = strip_suffix_ignore_case(caption, "</figcaption>")?;
+    if !rest.is_empty() || caption.contains('<') {
+        return None;
+    }
+    let caption = html_escape::decode_html_entities(caption);
+    let caption = caption.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!caption.is_empty()).then_some((link, alt, caption))
+}
+
+fn strip_prefix_ignore_case<'s>(text: &'s str, prefix: &str) -> Option<&'s str> {
+    let head = text.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &text[prefix.len()..])
+}
+
+fn strip_suffix_ignore_case<'s>(text: &'s str, suffix: &str) -> Option<&'s str> {
+    let start = text.len().checked_sub(suffix.len())?;
+    let tail = text.get(start..)?;
+    tail.eq_ignore_ascii_case(suffix).then(|| &text[..start])
+}
+
+/// Rewrite images followed by an emphasized caption on the next line as
+/// HTML `<figure>` elements.  Figures whose image is linked, or whose alt
+/// text or caption has any formatting, are left alone, since HTML blocks
+/// cannot contain Markdown.
+fn convert_markdown_figures<'a>(root: &'a AstNode<'a>, arena: &'a Arena<'a>) {
+    let candidates: Vec<_> = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))
+        .collect();
+    for node in candidates {
+        let Some((image, caption)) = figure_parts(node) else {
+            continue;
+        };
+        let link = match &image.data.borrow().value {
+            NodeValue::Image(link) => link.clone(),
+            _ => continue,
+        };
+        let (Some(alt), Some(caption)) = (plain_text(image), plain_text(caption)) else {
+            continue;
+        };
+        let mut html = String::from("<figure>\n  <img src=\"");
+        html.push_str(&html_escape::encode_double_quoted_attribute(&link.url));
+        html.push_str("\" alt=\"");
+        html.push_str(&html_escape::encode_double_quoted_attribute(&alt));
+        html.push('"');
+        if !link.title.is_empty() {
+            html.push_str(" title=\"");
+            html.push_str(&html_escape::encode_double_quoted_attribute(&link.title));
+            html.push('"');
+        }
+        html.push_str(">\n  <figcaption>");
+        html.push_str(&html_escape::encode_text(&caption));
+        html.push_str("</figcaption>\n</figure>\n");
+        let sourcepos = node.data.borrow().sourcepos;
+        let block = NodeValue::HtmlBlock(NodeHtmlBlock {
+            block_type: 6,
+            literal: html,
+        });
+        node.insert_before(arena.alloc(Ast::new_with_sourcepos(block, sourcepos).into()));
+        node.detach();
+    }
+}
+
+/// Returns the text of `node` with its line breaks as spaces, if it has no
+/// formatting.
+fn plain_text<'a>(node: &'a AstNode<'a>) -> Option<String> {
+    let mut text = String::new();
+    for child in node.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(t) => text.push_str(t),
+            NodeValue::SoftBreak => text.push(' '),
+            _ => return None,
+        }
+    }
+    Some(text)
+}
+
+/// Returns a new node without a source position, so that it is serialized
+/// from the AST.
+fn new_node<'a>(arena: &'a Arena<'a>, value: NodeValue) -> &'a AstNode<'a> {
+    arena.alloc(Ast::new_with_sourcepos(value, (0, 0, 0, 0).into()).into())
+}
+
+/// Parse `html` if it is a single `<img>` element with a `src` attribute.
+/// Returns the destination and title of the image, its alt text, and its
+/// other attributes in the attribute block syntax, e.g., `width="100"`.
+///
+/// Returns `None` if there are other attributes and `attributes` is
+/// [`HtmlImageAttributes::Skip`], or if they cannot be written in an
+/// attribute block.
+fn html_image(
+    html: &str,
+    attributes: HtmlImageAttributes,
+) -> Option<(NodeLink, String, Vec<String>)> {
+    let html = html.trim();
+    let tag = html.get(..4)?;
+    if !tag.eq_ignore_ascii_case("<img") {
+        return None;
+    }
+    let body = html[4..].strip_suffix('>')?;
+    let body = body.strip_suffix('/').unwrap_or(body);
+    if !body.is_empty() && !body.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let mut url = None;
+    let mut title = String::new();
+    let mut alt = String::new();
+    let mut rest = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    for (name, value) in html_attributes(body)? {
+        if seen.contains(&name) {
+            return None;
+        }
+        seen.push(name.clone());
+        let value = value.map(|value| html_escape::decode_html_entities(&value).into_owned());
+        match name.as_str() {
+            "src" => url = Some(value?),
+            "alt" => alt = value.unwrap_or_default(),
+            "title" => title = value.unwrap_or_default(),
+            _ if attributes == HtmlImageAttributes::Skip => return None,
+            _ => rest.extend(attribute_block_entries(&name, value.as_deref())?),
+        }
+    }
+    let url = url.filter(|url| !url.is_empty())?;
+    // Alt text spanning lines would end up in a paragraph of its own, and
+    // brackets in it could be taken for a link
+    if alt.contains(['\n', '[', ']']) || title.contains('\n') {
+        return None;
+    }
+    Some((NodeLink { url, title }, alt, rest))
+}
+
+/// Parse the attributes of an HTML start tag, i.e., what follows its name.
+/// Returns `None` if they are malformed.
+fn html_attributes(mut body: &str) -> Option<Vec<(String, Option<String>)>> {
+    let mut attributes = Vec::new();
+    loop {
+        body = body.trim_start();
+        if body.is_empty() {
+            return Some(attributes);
+        }
+        let end = body
+            .find(|ch: char| ch.is_whitespace() || matches!(ch, '=' | '"' | '\'' | '<' | '>'))
+            .unwrap_or(body.len());
+        if end == 0 {
+            return None;
+        }
+        let name = body[..end].to_ascii_lowercase();
+        body = body[end..].trim_start();
+        let Some(after_equals) = body.strip_prefix('=') else {
+            attributes.push((name, None));
+            continue;
+        };
+        let after_equals = after_equals.trim_start();
+        let (value, after_value) = match after_equals.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let close = after_equals[1..].find(quote)? + 1;
+                (&after_equals[1..close], &after_equals[close + 1..])
+            }
+            _ => {
+                let end = after_equals
+                    .find(|ch: char| ch.is_whitespace())
+                    .unwrap_or(after_equals.len());
+                if end == 0 {
+                    return None;
+                }
+                after_equals.split_at(end)
+            }
+        };
+        attributes.push((name, Some(value.to_string())));
+        body = after_value;
+    }
+}
+
+/// Returns the entries of an attribute block, e.g., `.class`, `#id`, or
+/// `width="100"`, for an HTML attribute, or `None` if it cannot be written
+/// in one.
+fn attribute_block_entries(name: &str, value: Option<&str>) -> Option<Vec<String>> {
+    let value = value.unwrap_or_default();
+    if value.contains(['"', '{', '}', '\n']) {
+        return None;
+    }
+    match name {
+        "class" => Some(
+            value
+                .split_whitespace()
+                .map(|c| format!(".{}", c))
+                .collect(),
+        ),
+        "id" if !value.is_empty() && !value.contains(char::is_whitespace) => {
+            Some(vec![format!("#{}", value)])
+        }
+        _ => Some(vec![format!("{}=\"{}\"", name, value)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use comrak::nodes::NodeValue;
+
+    use super::*;
+    use crate::format;
+
+    fn uppercase_text<'a>(root: &'a AstNode<'a>, _arena: &'a Arena<'a>) {
+        for node in root.descendants() {
+            if let NodeValue::Text(ref mut text) = node.data.borrow_mut().value {
+                *text = text.to_uppercase().into();
+            }
+        }
+    }
+
+    #[test]
+    fn test_function_transform() {
+        let options = Options {
+            transforms: vec![Arc::new(uppercase_text)],
+            ..Options::default()
+        };
+        let output = format("Hello, *world*.", &options).unwrap();
+        assert_eq!(output, "HELLO, *WORLD*.\n");
+    }
+
+    #[test]
+    fn test_transform_with_reset_sourcepos() {
+        fn uppercase_code<'a>(root: &'a AstNode<'a>, _arena: &'a Arena<'a>) {
+            for node in root.descendants() {
+                let mut ast = node.data.borrow_mut();
+                if let NodeValue::Code(ref mut code) = ast.value {
+                    code.literal = code.literal.to_uppercase();
+                    ast.sourcepos = (0, 0, 0, 0).into();
+                }
+            }
+        }
+        let options = Options {
+            transforms: vec![Arc::new(uppercase_code)],
+            ..Options::default()
+        };
+        let output = format("Use `foo` here.", &options).unwrap();
+        assert_eq!(output, "Use `FOO` here.\n");
+    }
+
+    /// Appends a suffix to every text node.
+    struct AppendText(&'static str);
+
+    impl AstTransform for AppendText {
+        fn transform<'a>(&self, root: &'a AstNode<'a>, _arena: &'a Arena<'a>) {
+            for node in root.descendants() {
+                if let NodeValue::Text(ref mut text) = node.data.borrow_mut().value {
+                    text.to_mut().push_str(self.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_transforms_run_in_order() {
+        let options = Options {
+            transforms: vec![Arc::new(AppendText(" one")), Arc::new(AppendText(" two"))],
+            ..Options::default()
+        };
+        let output = format("Count:", &options).unwrap();
+        assert_eq!(output, "Count: one two\n");
+    }
+
+    fn format_converting_bold_labels(input: &str) -> String {
+        let options = Options {
+            alert_convert_bold_labels: true,
+            ..Options::default()
+        };
+        format(input, &options).unwrap()
+    }
+
+    #[test]
+    fn test_convert_bold_label_paragraph() {
+        let output = format_converting_bold_labels("**Note:** Read this first.\n");
+        assert_eq!(output, "> [!NOTE]\n> Read this first.\n");
+        let output = format_converting_bold_labels("__Warning__: Be careful.\n");
+        assert_eq!(output, "> [!WARNING]\n> Be careful.\n");
+    }
+
+    #[test]
+    fn test_convert_bold_label_block_quote() {
+        let input = "> **Tip:**\n> Use a config file.\n>\n> It is simpler.\n";
+        let output = format_converting_bold_labels(input);
+        assert_eq!(
+            output,
+            "> [!TIP]\n> Use a config file.\n>\n> It is simpler.\n"
+        );
+        let input = "> **Important:**\n>\n> Back up first.\n";
+        let output = format_converting_bold_labels(input);
+        assert_eq!(output, "> [!IMPORTANT]\n>\n> Back up first.\n");
+    }
+
+    #[test]
+    fn test_convert_bold_label_keeps_others() {
+        // Unknown labels, lone labels, and labels within a quote are kept
+        let input = "**Notice:** Not an alert.\n\n**Caution:**\n\n> Quoted\n> **Note:** text.\n";
+        assert_eq!(format_converting_bold_labels(input), input);
+        // Disabled by default
+        let input = "**Note:** Read this first.\n";
+        assert_eq!(format(input, &Options::default()).unwrap(), input);
+    }
+
+    #[test]
+    fn test_transform_debug() {
+        struct Named;
+        impl AstTransform for Named {
+            fn transform<'a>(&self, _root: &'a AstNode<'a>, _arena: &'a Arena<'a>) {}
+            fn name(&self) -> &str {
+                "named"
+            }
+        }
+        let transform: Arc<dyn AstTransform> = Arc::new(Named);
+        assert_eq!(format!("{:?}", transform), "AstTransform(\"named\")");
+    }
+
+    fn format_converting_html_images(input: &str, attributes: HtmlImageAttributes) -> String {
+        let options = Options {
+            image_convert_html: true,
+            image_html_attributes: attributes,
+            ..Options::default()
+        };
+        format(input, &options).unwrap()
+    }
+
+    #[test]
+    fn test_convert_html_images() {
+        let input = "Logo: <img src=\"logo.png\" alt=\"Tom &amp; Jerry\"> and \
+<IMG SRC='a b.png' title=\"T\" />.
+
+<img src=\"hero.png\" alt=\"Hero\">
+";
+        assert_eq!(
+            format_converting_html_images(input, HtmlImageAttributes::Skip),
+            "Logo: ![Tom & Jerry](logo.png) and ![](<a b.png> \"T\").
+
+![Hero](hero.png)
+"
+        );
+    }
+
+    #[test]
+    fn test_convert_html_images_with_attributes() {
+        let input = "Sized <img src=\"s.png\" width=\"100\" class=\"a b\" id=\"x\" alt=\"S\">.\n";
+        assert_eq!(
+            format_converting_html_images(input, HtmlImageAttributes::Skip),
+            input
+        );
+        assert_eq!(
+            format_converting_html_images(input, HtmlImageAttributes::Preserve),
+            "Sized ![S](s.png){width=\"100\" .a .b #x}.\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_html_images_leaves_others_alone() {
+        let input = "<p align=\"center\"><img src=\"c.png\"></p>
+
+No source <img alt=\"A\">, brackets <img src=\"b.png\" alt=\"[b]\">,
+and <img src=\"d.png\">{braces}.
+";
+        assert_eq!(
+            format_converting_html_images(input, HtmlImageAttributes::Preserve),
+            input
+        );
+    }
+
+    fn format_with_figure_style(input: &str, figure_style: FigureStyle) -> String {
+        let options = Options {
+            figure_style,
+            ..Options::default()
+        };
+        format(input, &options).unwrap()
+    }
+
+    #[test]
+    fn test_convert_html_figures() {
+        let input = "<figure>
+  <img src=\"a.png\" alt=\"A &amp; B\">
+  <figcaption>Figure 1: The   first
+  one.</figcaption>
+</figure>
+
+<figure>
+  <img src=\"b.png\" width=\"100\">
+  <figcaption>Figure 2</figcaption>
+</figure>
+";
+        assert_eq!(
+            format_with_figure_style(input, FigureStyle::Markdown),
+            "![A & B](a.png)
+*Figure 1: The first one.*
+
+<figure>
+  <img src=\"b.png\" width=\"100\">
+  <figcaption>Figure 2</figcaption>
+</figure>
+"
+        );
+        assert_eq!(
+            format_with_figure_style(input, FigureStyle::Preserve),
+            input
+        );
+    }
+
+    #[test]
+    fn test_convert_markdown_figures() {
+        let input = "![A & B](a.png \"Title\")
+*Figure 1: 1 < 2 & 3.*
+
+![C](c.png)
+*Figure 2: The **second** one.*
+";
+        assert_eq!(
+            format_with_figure_style(input, FigureStyle::Html),
+            "<figure>
+  <img src=\"a.png\" alt=\"A &amp; B\" title=\"Title\">
+  <figcaption>Figure 1: 1 &lt; 2 &amp; 3.</figcaption>
+</figure>
+
+![C](c.png)
+*Figure 2: The **second** one.*
+"
+        );
+    }
+}
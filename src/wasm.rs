@@ -2,244 +2,34 @@
 //!
 //! This module provides JavaScript-friendly bindings for the Hongdown formatter.
 
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 use crate::Options;
-use crate::config::{
-    DashPattern, DashSetting, FenceChar, IndentWidth, LeadingSpaces, LineWidth, MinFenceLength,
-    OrderedListPad, OrderedMarker, ThematicBreakStyle, TrailingSpaces, UnorderedMarker,
-};
+use crate::js_options::JsOptions;
 
-/// JavaScript-friendly options struct.
-///
-/// All fields are optional and use camelCase naming for JavaScript conventions.
-#[derive(Debug, Clone, Default, Deserialize)]
-#[serde(rename_all = "camelCase", default)]
-pub struct JsOptions {
-    /// Line width for wrapping (default: 80).
-    pub line_width: Option<usize>,
-
-    /// Use setext-style for h1 headings (default: true).
-    pub setext_h1: Option<bool>,
-
-    /// Use setext-style for h2 headings (default: true).
-    pub setext_h2: Option<bool>,
-
-    /// Convert headings to sentence case (default: false).
-    pub heading_sentence_case: Option<bool>,
-
-    /// Additional proper nouns to preserve in sentence case.
-    /// These are merged with built-in proper nouns.
-    pub heading_proper_nouns: Option<Vec<String>>,
-
-    /// Words to treat as common nouns in sentence case.
-    /// These are excluded from built-in proper nouns.
-    pub heading_common_nouns: Option<Vec<String>>,
-
-    /// Marker for unordered lists: "-", "*", or "+" (default: "-").
-    pub unordered_marker: Option<String>,
-
-    /// Leading spaces before list marker (default: 1).
-    pub leading_spaces: Option<usize>,
-
-    /// Trailing spaces after list marker (default: 2).
-    pub trailing_spaces: Option<usize>,
-
-    /// Indent width for nested items (default: 4).
-    pub indent_width: Option<usize>,
-
-    /// Marker for odd-level ordered lists (default: ".").
-    pub odd_level_marker: Option<String>,
-
-    /// Marker for even-level ordered lists (default: ")").
-    pub even_level_marker: Option<String>,
-
-    /// Padding style for ordered list numbers: "start" or "end" (default: "start").
-    pub ordered_list_pad: Option<String>,
-
-    /// Indent width for nested ordered lists (default: 4).
-    pub ordered_list_indent_width: Option<usize>,
-
-    /// Fence character: "~" or "`" (default: "~").
-    pub fence_char: Option<String>,
-
-    /// Minimum fence length (default: 4).
-    pub min_fence_length: Option<usize>,
-
-    /// Space after fence character (default: true).
-    pub space_after_fence: Option<bool>,
-
-    /// Default language for code blocks (default: "").
-    pub default_language: Option<String>,
-
-    /// Thematic break style (default: spaced dashes).
-    pub thematic_break_style: Option<String>,
-
-    /// Leading spaces for thematic breaks (default: 3).
-    pub thematic_break_leading_spaces: Option<usize>,
-
-    /// Convert straight double quotes to curly (default: true).
-    pub curly_double_quotes: Option<bool>,
-
-    /// Convert straight single quotes to curly (default: true).
-    pub curly_single_quotes: Option<bool>,
-
-    /// Convert apostrophes to curly (default: false).
-    pub curly_apostrophes: Option<bool>,
-
-    /// Convert ... to ellipsis (default: true).
-    pub ellipsis: Option<bool>,
-
-    /// En-dash setting: false to disable, or a string pattern (default: false).
-    pub en_dash: Option<JsDashSetting>,
-
-    /// Em-dash setting: false to disable, or a string pattern (default: "--").
-    pub em_dash: Option<JsDashSetting>,
-}
-
-/// JavaScript-friendly dash setting.
-///
-/// Can be either `false` (disabled) or a string pattern.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(untagged)]
-pub enum JsDashSetting {
-    /// Disabled when false.
-    Disabled(bool),
-    /// Pattern to transform to dash.
-    Pattern(String),
-}
-
-impl JsDashSetting {
-    fn to_dash_setting(&self) -> DashSetting {
-        match self {
-            JsDashSetting::Disabled(false) => DashSetting::Disabled,
-            JsDashSetting::Disabled(true) => DashSetting::Disabled,
-            JsDashSetting::Pattern(s) => DashPattern::new(s.clone())
-                .map(DashSetting::Pattern)
-                .unwrap_or(DashSetting::Disabled),
+impl JsOptions {
+    /// Parse options from a JavaScript value.
+    ///
+    /// Accepts `undefined`/`null` (defaults), a plain object, or a JSON string
+    /// encoding such an object.
+    fn from_js_value(value: JsValue) -> Result<Self, JsError> {
+        if value.is_undefined() || value.is_null() {
+            Ok(JsOptions::default())
+        } else if let Some(json) = value.as_string() {
+            Self::from_json(&json).map_err(|e| JsError::new(&e))
+        } else {
+            serde_wasm_bindgen::from_value(value).map_err(|e| JsError::new(&e.to_string()))
         }
     }
 }
 
-impl JsOptions {
-    /// Convert JavaScript options to Rust Options.
-    fn to_options(&self) -> Options {
-        let mut opts = Options::default();
-
-        if let Some(v) = self.line_width {
-            if let Ok(lw) = LineWidth::new(v) {
-                opts.line_width = lw;
-            }
-        }
-        if let Some(v) = self.setext_h1 {
-            opts.setext_h1 = v;
-        }
-        if let Some(v) = self.setext_h2 {
-            opts.setext_h2 = v;
-        }
-        if let Some(v) = self.heading_sentence_case {
-            opts.heading_sentence_case = v;
-        }
-        if let Some(ref v) = self.heading_proper_nouns {
-            opts.heading_proper_nouns = v.clone();
-        }
-        if let Some(ref v) = self.heading_common_nouns {
-            opts.heading_common_nouns = v.clone();
-        }
-        if let Some(ref v) = self.unordered_marker {
-            opts.unordered_marker = match v.as_str() {
-                "*" => UnorderedMarker::Asterisk,
-                "+" => UnorderedMarker::Plus,
-                _ => UnorderedMarker::Hyphen,
-            };
-        }
-        if let Some(v) = self.leading_spaces {
-            if let Ok(leading) = LeadingSpaces::new(v) {
-                opts.leading_spaces = leading;
-            }
-        }
-        if let Some(v) = self.trailing_spaces {
-            if let Ok(trailing) = TrailingSpaces::new(v) {
-                opts.trailing_spaces = trailing;
-            }
-        }
-        if let Some(v) = self.indent_width {
-            if let Ok(width) = IndentWidth::new(v) {
-                opts.indent_width = width;
-            }
-        }
-        if let Some(ref v) = self.odd_level_marker {
-            opts.odd_level_marker = match v.as_str() {
-                ")" => OrderedMarker::Parenthesis,
-                _ => OrderedMarker::Period,
-            };
-        }
-        if let Some(ref v) = self.even_level_marker {
-            opts.even_level_marker = match v.as_str() {
-                "." => OrderedMarker::Period,
-                _ => OrderedMarker::Parenthesis,
-            };
-        }
-        if let Some(ref v) = self.ordered_list_pad {
-            opts.ordered_list_pad = match v.as_str() {
-                "end" => OrderedListPad::End,
-                _ => OrderedListPad::Start,
-            };
-        }
-        if let Some(v) = self.ordered_list_indent_width {
-            if let Ok(width) = IndentWidth::new(v) {
-                opts.ordered_list_indent_width = width;
-            }
-        }
-        if let Some(ref v) = self.fence_char {
-            opts.fence_char = match v.as_str() {
-                "`" => FenceChar::Backtick,
-                _ => FenceChar::Tilde,
-            };
-        }
-        if let Some(v) = self.min_fence_length {
-            if let Ok(min_len) = MinFenceLength::new(v) {
-                opts.min_fence_length = min_len;
-            }
-        }
-        if let Some(v) = self.space_after_fence {
-            opts.space_after_fence = v;
-        }
-        if let Some(ref v) = self.default_language {
-            opts.default_language = v.clone();
-        }
-        if let Some(ref v) = self.thematic_break_style {
-            if let Ok(style) = ThematicBreakStyle::new(v.clone()) {
-                opts.thematic_break_style = style;
-            }
-        }
-        if let Some(v) = self.thematic_break_leading_spaces {
-            if let Ok(leading) = LeadingSpaces::new(v) {
-                opts.thematic_break_leading_spaces = leading;
-            }
-        }
-        if let Some(v) = self.curly_double_quotes {
-            opts.curly_double_quotes = v;
-        }
-        if let Some(v) = self.curly_single_quotes {
-            opts.curly_single_quotes = v;
-        }
-        if let Some(v) = self.curly_apostrophes {
-            opts.curly_apostrophes = v;
-        }
-        if let Some(v) = self.ellipsis {
-            opts.ellipsis = v;
-        }
-        if let Some(ref v) = self.en_dash {
-            opts.en_dash = v.to_dash_setting();
-        }
-        if let Some(ref v) = self.em_dash {
-            opts.em_dash = v.to_dash_setting();
-        }
-
-        opts
-    }
+/// Convert JavaScript options to Rust [`Options`], reporting invalid values
+/// as JavaScript errors.
+fn parse_options(options: JsValue) -> Result<Options, JsError> {
+    JsOptions::from_js_value(options)?
+        .to_options()
+        .map_err(|e| JsError::new(&e))
 }
 
 /// Format result with warnings.
@@ -257,29 +47,43 @@ pub struct JsFormatResult {
 pub struct JsWarning {
     /// Line number where the warning was generated (1-indexed).
     pub line: usize,
+    /// Column number where the warning was generated (1-indexed), or 0 if
+    /// unknown.
+    pub column: usize,
+    /// Machine-readable warning code (e.g., `"undefined-reference"`).
+    pub code: String,
+    /// Category of the warning (e.g., `"tables"`).
+    pub kind: String,
     /// Warning message.
     pub message: String,
 }
 
+impl From<crate::Warning> for JsWarning {
+    fn from(warning: crate::Warning) -> Self {
+        JsWarning {
+            line: warning.line,
+            column: warning.column,
+            code: warning.code.to_string(),
+            kind: warning.kind.as_str().to_string(),
+            message: warning.message,
+        }
+    }
+}
+
 /// Format Markdown according to Hong Minhee's style conventions.
 ///
 /// # Arguments
 ///
 /// * `input` - Markdown source to format
-/// * `options` - Optional formatting options as a JavaScript object
+/// * `options` - Optional formatting options as a JavaScript object or a JSON
+///   string
 ///
 /// # Returns
 ///
 /// The formatted Markdown string.
 #[wasm_bindgen]
 pub fn format(input: &str, options: JsValue) -> Result<String, JsError> {
-    let js_opts: JsOptions = if options.is_undefined() || options.is_null() {
-        JsOptions::default()
-    } else {
-        serde_wasm_bindgen::from_value(options).map_err(|e| JsError::new(&e.to_string()))?
-    };
-
-    let opts = js_opts.to_options();
+    let opts = parse_options(options)?;
     crate::format(input, &opts).map_err(|e| JsError::new(&e.to_string()))
 }
 
@@ -288,33 +92,22 @@ pub fn format(input: &str, options: JsValue) -> Result<String, JsError> {
 /// # Arguments
 ///
 /// * `input` - Markdown source to format
-/// * `options` - Optional formatting options as a JavaScript object
+/// * `options` - Optional formatting options as a JavaScript object or a JSON
+///   string
 ///
 /// # Returns
 ///
-/// An object with `output` (formatted string) and `warnings` (array of warning objects).
+/// An object with `output` (formatted string) and `warnings` (array of
+/// `{ line, column, code, message }` objects).
 #[wasm_bindgen(js_name = formatWithWarnings)]
 pub fn format_with_warnings(input: &str, options: JsValue) -> Result<JsValue, JsError> {
-    let js_opts: JsOptions = if options.is_undefined() || options.is_null() {
-        JsOptions::default()
-    } else {
-        serde_wasm_bindgen::from_value(options).map_err(|e| JsError::new(&e.to_string()))?
-    };
-
-    let opts = js_opts.to_options();
+    let opts = parse_options(options)?;
     let result =
         crate::format_with_warnings(input, &opts).map_err(|e| JsError::new(&e.to_string()))?;
 
     let js_result = JsFormatResult {
         output: result.output,
-        warnings: result
-            .warnings
-            .into_iter()
-            .map(|w| JsWarning {
-                line: w.line,
-                message: w.message,
-            })
-            .collect(),
+        warnings: result.warnings.into_iter().map(JsWarning::from).collect(),
     };
 
     serde_wasm_bindgen::to_value(&js_result).map_err(|e| JsError::new(&e.to_string()))
@@ -325,7 +118,8 @@ pub fn format_with_warnings(input: &str, options: JsValue) -> Result<JsValue, Js
 /// # Arguments
 ///
 /// * `input` - Markdown source to format
-/// * `options` - Optional formatting options as a JavaScript object
+/// * `options` - Optional formatting options as a JavaScript object or a JSON
+///   string
 /// * `code_formatter` - Optional JavaScript callback function `(language: string, code: string) => string | null`
 ///   that formats code blocks. Return the formatted code, or null/undefined to keep the original.
 ///
@@ -338,155 +132,249 @@ pub fn format_with_code_formatter(
     options: JsValue,
     code_formatter: Option<js_sys::Function>,
 ) -> Result<JsValue, JsError> {
-    use comrak::{Arena, Options as ComrakOptions, parse_document};
-
-    let js_opts: JsOptions = if options.is_undefined() || options.is_null() {
-        JsOptions::default()
-    } else {
-        serde_wasm_bindgen::from_value(options).map_err(|e| JsError::new(&e.to_string()))?
-    };
-
-    let opts = js_opts.to_options();
-
-    if input.is_empty() {
-        let js_result = JsFormatResult {
-            output: String::new(),
-            warnings: Vec::new(),
-        };
-        return serde_wasm_bindgen::to_value(&js_result).map_err(|e| JsError::new(&e.to_string()));
-    }
-
-    let arena = Arena::new();
-    let mut comrak_options = ComrakOptions::default();
-    comrak_options.extension.front_matter_delimiter = Some("---".to_string());
-    comrak_options.extension.table = true;
-    comrak_options.extension.description_lists = true;
-    comrak_options.extension.alerts = true;
-    comrak_options.extension.footnotes = true;
-    comrak_options.extension.tasklist = true;
-
-    let root = parse_document(&arena, input, &comrak_options);
+    let opts = parse_options(options)?;
+    let js_result = format_with_callbacks(input, &opts, code_formatter, None)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&js_result).map_err(|e| JsError::new(&e.to_string()))
+}
 
+/// Format Markdown, calling the given JavaScript functions to format code
+/// blocks and to report progress through the document's top-level blocks.
+fn format_with_callbacks(
+    input: &str,
+    opts: &Options,
+    code_formatter: Option<js_sys::Function>,
+    on_progress: Option<js_sys::Function>,
+) -> Result<JsFormatResult, crate::FormatError> {
     // Create callback closure if provided
     let callback: crate::serializer::CodeFormatterCallback = code_formatter.map(|func| {
-        Box::new(move |language: &str, code: &str| -> Option<String> {
-            let this = JsValue::null();
-            let lang_js = JsValue::from_str(language);
-            let code_js = JsValue::from_str(code);
-
-            match func.call2(&this, &lang_js, &code_js) {
-                Ok(result) => {
-                    if result.is_null() || result.is_undefined() {
-                        None
-                    } else {
-                        result.as_string()
+        Box::new(
+            move |language: &str, command: Option<&[String]>, code: &str, _line: usize| {
+                // Commands chosen by hongdown-format-with directives cannot run
+                // here
+                if command.is_some() {
+                    return None;
+                }
+                let this = JsValue::null();
+                let lang_js = JsValue::from_str(language);
+                let code_js = JsValue::from_str(code);
+
+                match func.call2(&this, &lang_js, &code_js) {
+                    Ok(result) => {
+                        if result.is_null() || result.is_undefined() {
+                            None
+                        } else {
+                            result.as_string()
+                        }
                     }
+                    Err(_) => None,
                 }
-                Err(_) => None,
-            }
-        }) as Box<dyn Fn(&str, &str) -> Option<String>>
+            },
+        ) as Box<dyn Fn(&str, Option<&[String]>, &str, usize) -> Option<String>>
     });
 
-    let result =
-        crate::serializer::serialize_with_code_formatter(root, &opts, Some(input), callback);
+    let progress: crate::serializer::ProgressCallback = on_progress.map(|func| {
+        Box::new(move |done: usize, total: usize| {
+            let this = JsValue::null();
+            let _ = func.call2(
+                &this,
+                &JsValue::from_f64(done as f64),
+                &JsValue::from_f64(total as f64),
+            );
+        }) as Box<dyn FnMut(usize, usize)>
+    });
 
-    let js_result = JsFormatResult {
+    let result = crate::format_with_serializer(input, opts, |root, input| {
+        crate::serializer::serialize_with_code_formatter(
+            root,
+            opts,
+            Some(input),
+            callback,
+            progress,
+        )
+    })?;
+
+    Ok(JsFormatResult {
         output: result.output,
-        warnings: result
-            .warnings
-            .into_iter()
-            .map(|w| JsWarning {
-                line: w.line,
-                message: w.message,
-            })
-            .collect(),
-    };
+        warnings: result.warnings.into_iter().map(JsWarning::from).collect(),
+    })
+}
 
-    serde_wasm_bindgen::to_value(&js_result).map_err(|e| JsError::new(&e.to_string()))
+/// An incremental formatter for large documents.
+///
+/// Input is fed in chunks (e.g., as it arrives from a stream or file reader)
+/// and formatted once [`Formatter::finish`] is called.  A progress callback
+/// can be used to report progress, which makes it suitable for running
+/// inside a Web Worker without leaving the user in the dark.
+#[wasm_bindgen]
+pub struct Formatter {
+    options: Options,
+    input: String,
+    code_formatter: Option<js_sys::Function>,
+    on_progress: Option<js_sys::Function>,
+}
+
+#[wasm_bindgen]
+impl Formatter {
+    /// Append a chunk of Markdown source to the document.
+    pub fn feed(&mut self, chunk: &str) {
+        self.input.push_str(chunk);
+    }
+
+    /// Number of bytes fed so far.
+    #[wasm_bindgen(getter, js_name = bytesFed)]
+    pub fn bytes_fed(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Format the fed document and return `{ output, warnings }`.
+    ///
+    /// The formatter is reset afterwards, so it can be reused for another
+    /// document with the same options.
+    pub fn finish(&mut self) -> Result<JsValue, JsError> {
+        let js_result = self
+            .finish_result()
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        serde_wasm_bindgen::to_value(&js_result).map_err(|e| JsError::new(&e.to_string()))
+    }
+}
+
+impl Formatter {
+    fn finish_result(&mut self) -> Result<JsFormatResult, crate::FormatError> {
+        let input = std::mem::take(&mut self.input);
+        format_with_callbacks(
+            &input,
+            &self.options,
+            self.code_formatter.clone(),
+            self.on_progress.clone(),
+        )
+    }
+}
+
+/// Create an incremental [`Formatter`].
+///
+/// # Arguments
+///
+/// * `options` - Optional formatting options as a JavaScript object or a JSON
+///   string
+/// * `code_formatter` - Optional JavaScript callback function
+///   `(language: string, code: string) => string | null` that formats code
+///   blocks
+/// * `on_progress` - Optional JavaScript callback function
+///   `(done: number, total: number) => void` called as top-level blocks of
+///   the document are formatted
+#[wasm_bindgen(js_name = createFormatter)]
+pub fn create_formatter(
+    options: JsValue,
+    code_formatter: Option<js_sys::Function>,
+    on_progress: Option<js_sys::Function>,
+) -> Result<Formatter, JsError> {
+    Ok(Formatter {
+        options: parse_options(options)?,
+        input: String::new(),
+        code_formatter,
+        on_progress,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_js_options_default() {
-        let js_opts = JsOptions::default();
-        let opts = js_opts.to_options();
-        assert_eq!(opts.line_width.get(), 80);
-        assert!(opts.setext_h1);
-        assert!(opts.setext_h2);
-    }
-
-    #[test]
-    fn test_js_options_partial() {
-        let js_opts = JsOptions {
-            line_width: Some(100),
-            setext_h1: Some(false),
-            ..Default::default()
-        };
-        let opts = js_opts.to_options();
-        assert_eq!(opts.line_width.get(), 100);
-        assert!(!opts.setext_h1);
-        assert!(opts.setext_h2); // default
+    /// The hand-maintained TypeScript definitions shipped with the npm package.
+    const TYPES_TS: &str = include_str!("../packages/wasm/src/types.ts");
+
+    /// Extract the property names declared in a TypeScript interface.
+    fn ts_interface_fields(name: &str) -> Vec<String> {
+        let header = format!("export interface {} ", name);
+        let start = TYPES_TS
+            .find(&header)
+            .unwrap_or_else(|| panic!("interface {} not found in types.ts", name));
+        let body = &TYPES_TS[start..];
+        let body = &body[..body.find("\n}").expect("unterminated interface")];
+        body.lines()
+            .filter_map(|line| {
+                let line = line.strip_prefix("  ")?;
+                let end = line.find(['?', ':'])?;
+                let field = &line[..end];
+                (!field.is_empty() && field.chars().all(|c| c.is_ascii_alphanumeric()))
+                    .then(|| field.to_string())
+            })
+            .collect()
     }
 
-    #[test]
-    fn test_js_dash_setting_disabled() {
-        let setting = JsDashSetting::Disabled(false);
-        assert!(matches!(setting.to_dash_setting(), DashSetting::Disabled));
+    /// Extract the camelCase field names of a serializable value.
+    fn json_fields<T: Serialize>(value: &T) -> Vec<String> {
+        let mut fields: Vec<String> = serde_json::to_value(value)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        fields.sort();
+        fields
     }
 
     #[test]
-    fn test_js_dash_setting_pattern() {
-        let setting = JsDashSetting::Pattern("--".to_string());
-        match setting.to_dash_setting() {
-            DashSetting::Pattern(p) => assert_eq!(p.as_str(), "--"),
-            _ => panic!("Expected Pattern"),
-        }
+    fn test_ts_format_options_in_sync() {
+        let mut ts_fields = ts_interface_fields("FormatOptions");
+        ts_fields.sort();
+        assert_eq!(ts_fields, json_fields(&JsOptions::default()));
     }
 
     #[test]
-    fn test_js_options_heading_sentence_case() {
-        let js_opts = JsOptions {
-            heading_sentence_case: Some(true),
-            ..Default::default()
+    fn test_ts_warning_in_sync() {
+        let mut ts_fields = ts_interface_fields("Warning");
+        ts_fields.sort();
+        let warning = JsWarning {
+            line: 1,
+            column: 1,
+            code: String::new(),
+            kind: String::new(),
+            message: String::new(),
         };
-        let opts = js_opts.to_options();
-        assert!(opts.heading_sentence_case);
+        assert_eq!(ts_fields, json_fields(&warning));
     }
 
     #[test]
-    fn test_js_options_heading_proper_nouns() {
-        let js_opts = JsOptions {
-            heading_proper_nouns: Some(vec!["MyApp".to_string(), "OpenAI".to_string()]),
-            ..Default::default()
+    fn test_ts_format_result_in_sync() {
+        let mut ts_fields = ts_interface_fields("FormatResult");
+        ts_fields.sort();
+        let result = JsFormatResult {
+            output: String::new(),
+            warnings: Vec::new(),
         };
-        let opts = js_opts.to_options();
-        assert_eq!(opts.heading_proper_nouns, vec!["MyApp", "OpenAI"]);
+        assert_eq!(ts_fields, json_fields(&result));
     }
 
     #[test]
-    fn test_js_options_heading_common_nouns() {
-        let js_opts = JsOptions {
-            heading_common_nouns: Some(vec!["react".to_string()]),
-            ..Default::default()
-        };
-        let opts = js_opts.to_options();
-        assert_eq!(opts.heading_common_nouns, vec!["react"]);
+    fn test_js_warning_from_warning() {
+        let result = crate::format_with_warnings("See [foo][missing].", &Options::default())
+            .expect("format should succeed");
+        let warnings: Vec<JsWarning> = result.warnings.into_iter().map(JsWarning::from).collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].line, 1);
+        assert_eq!(warnings[0].column, 5);
+        assert_eq!(warnings[0].code, "undefined-reference");
+        assert_eq!(warnings[0].kind, "references");
+        assert!(warnings[0].message.contains("[missing]"));
     }
 
     #[test]
-    fn test_js_options_heading_all() {
-        let js_opts = JsOptions {
-            heading_sentence_case: Some(true),
-            heading_proper_nouns: Some(vec!["Fedify".to_string()]),
-            heading_common_nouns: Some(vec!["api".to_string()]),
-            ..Default::default()
+    fn test_formatter_feed_and_finish() {
+        let mut formatter = Formatter {
+            options: Options::default(),
+            input: String::new(),
+            code_formatter: None,
+            on_progress: None,
         };
-        let opts = js_opts.to_options();
-        assert!(opts.heading_sentence_case);
-        assert_eq!(opts.heading_proper_nouns, vec!["Fedify"]);
-        assert_eq!(opts.heading_common_nouns, vec!["api"]);
+        formatter.feed("# Hello\n\nSee ");
+        formatter.feed("[foo][missing].\n");
+        assert_eq!(formatter.bytes_fed(), 29);
+        let result = formatter.finish_result().unwrap();
+        assert_eq!(result.output, "Hello\n=====\n\nSee [foo][missing].\n");
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(formatter.bytes_fed(), 0);
     }
 }
@@ -131,8 +131,22 @@ mod cli_tests {
 
     /// Helper function to run hongdown CLI with given args and stdin input.
     fn run_hongdown(args: &[&str], stdin_input: Option<&str>) -> (String, String, i32) {
+        run_hongdown_with_env(args, stdin_input, &[])
+    }
+
+    /// Helper function to run hongdown CLI with given args, stdin input, and
+    /// environment variables.  `NO_COLOR` and `CLICOLOR_FORCE` are cleared
+    /// unless given.
+    fn run_hongdown_with_env(
+        args: &[&str],
+        stdin_input: Option<&str>,
+        env: &[(&str, &str)],
+    ) -> (String, String, i32) {
         let mut cmd = Command::new(env!("CARGO_BIN_EXE_hongdown"));
         cmd.args(args);
+        cmd.env_remove("NO_COLOR");
+        cmd.env_remove("CLICOLOR_FORCE");
+        cmd.envs(env.iter().copied());
 
         if stdin_input.is_some() {
             cmd.stdin(Stdio::piped());
@@ -216,6 +230,245 @@ mod cli_tests {
         );
     }
 
+    /// Test --diff with multiple files prints diffs in argument order.
+    #[test]
+    fn test_diff_multiple_files_preserves_order() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let names = ["c.md", "a.md", "b.md"];
+        let mut args = vec!["--diff".to_string()];
+        for name in names {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, "# Heading\n\nA paragraph.").expect("Failed to write file");
+            args.push(path.to_str().unwrap().to_string());
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        let (stdout, _stderr, exit_code) = run_hongdown(&args, None);
+
+        assert_eq!(exit_code, 0);
+        let positions: Vec<usize> = names
+            .iter()
+            .map(|name| stdout.find(&format!("{}\n+++", name)).expect(name))
+            .collect();
+        assert!(
+            positions.windows(2).all(|w| w[0] < w[1]),
+            "Diffs should appear in argument order: got stdout: {}",
+            stdout
+        );
+    }
+
+    /// Test diffs and warnings are not colored by default when piped.
+    #[test]
+    fn test_color_auto_disabled_when_piped() {
+        let (stdout, stderr, exit_code) =
+            run_hongdown(&["--diff", "--stdin"], Some("# Title\n\n[a][missing]\n"));
+
+        assert_eq!(exit_code, 0);
+        assert!(stdout.contains("-# Title"), "got stdout: {}", stdout);
+        assert!(stderr.contains(": warning: "), "got stderr: {}", stderr);
+        assert!(!stdout.contains('\x1b'), "got stdout: {:?}", stdout);
+        assert!(!stderr.contains('\x1b'), "got stderr: {:?}", stderr);
+    }
+
+    /// Test --color always colors diffs and warnings even when piped.
+    #[test]
+    fn test_color_always() {
+        let (stdout, stderr, exit_code) = run_hongdown(
+            &["--diff", "--stdin", "--color", "always"],
+            Some("# Title\n\n[a][missing]\n"),
+        );
+
+        assert_eq!(exit_code, 0);
+        assert!(stdout.contains("\x1b[36m@@ "), "got stdout: {:?}", stdout);
+        assert!(
+            stdout.contains("\x1b[31m-# Title\x1b[0m\n"),
+            "got stdout: {:?}",
+            stdout
+        );
+        assert!(
+            stdout.contains("\x1b[32m+Title\x1b[0m\n"),
+            "got stdout: {:?}",
+            stdout
+        );
+        assert!(
+            stderr.contains("\x1b[33mwarning:\x1b[0m"),
+            "got stderr: {:?}",
+            stderr
+        );
+    }
+
+    /// Test CLICOLOR_FORCE colors output and NO_COLOR takes precedence over it,
+    /// while --color overrides both.
+    #[test]
+    fn test_color_environment_variables() {
+        let input = Some("# Title\n");
+        let (stdout, _stderr, _exit_code) =
+            run_hongdown_with_env(&["--diff", "--stdin"], input, &[("CLICOLOR_FORCE", "1")]);
+        assert!(stdout.contains('\x1b'), "got stdout: {:?}", stdout);
+
+        let (stdout, _stderr, _exit_code) =
+            run_hongdown_with_env(&["--diff", "--stdin"], input, &[("CLICOLOR_FORCE", "0")]);
+        assert!(!stdout.contains('\x1b'), "got stdout: {:?}", stdout);
+
+        let (stdout, _stderr, _exit_code) = run_hongdown_with_env(
+            &["--diff", "--stdin"],
+            input,
+            &[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")],
+        );
+        assert!(!stdout.contains('\x1b'), "got stdout: {:?}", stdout);
+
+        let (stdout, _stderr, _exit_code) = run_hongdown_with_env(
+            &["--diff", "--stdin", "--color", "always"],
+            input,
+            &[("NO_COLOR", "1")],
+        );
+        assert!(stdout.contains('\x1b'), "got stdout: {:?}", stdout);
+
+        let (stdout, _stderr, _exit_code) = run_hongdown_with_env(
+            &["--diff", "--stdin", "--color", "never"],
+            input,
+            &[("CLICOLOR_FORCE", "1")],
+        );
+        assert!(!stdout.contains('\x1b'), "got stdout: {:?}", stdout);
+    }
+
+    /// Test --progress always prints one line per file when not on a terminal.
+    #[test]
+    fn test_progress_always_prints_lines() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let first = temp_dir.path().join("first.md");
+        let second = temp_dir.path().join("second.md");
+        fs::write(&first, "First\n=====\n\nContent.\n").expect("Failed to write file");
+        fs::write(&second, "Second\n======\n\nContent.\n").expect("Failed to write file");
+
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &[
+                "--check",
+                "--progress",
+                "always",
+                first.to_str().unwrap(),
+                second.to_str().unwrap(),
+            ],
+            None,
+        );
+
+        assert_eq!(exit_code, 0);
+        assert!(stderr.contains("[1/2] "), "got stderr: {}", stderr);
+        assert!(stderr.contains("[2/2] "), "got stderr: {}", stderr);
+        assert!(!stderr.contains('\x1b'), "No escape codes when piped");
+    }
+
+    /// Test progress is not reported by default when stderr is piped.
+    #[test]
+    fn test_progress_auto_disabled_when_piped() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let first = temp_dir.path().join("first.md");
+        let second = temp_dir.path().join("second.md");
+        fs::write(&first, "First\n=====\n\nContent.\n").expect("Failed to write file");
+        fs::write(&second, "Second\n======\n\nContent.\n").expect("Failed to write file");
+
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &["--check", first.to_str().unwrap(), second.to_str().unwrap()],
+            None,
+        );
+
+        assert_eq!(exit_code, 0);
+        assert!(stderr.is_empty(), "got stderr: {}", stderr);
+    }
+
+    /// Test --check-idempotent succeeds when formatting is stable.
+    #[test]
+    fn test_check_idempotent_stable() {
+        let input = "# Title\n\nA paragraph with a [link](https://example.com).";
+        let (stdout, stderr, exit_code) =
+            run_hongdown(&["--check-idempotent", "--stdin"], Some(input));
+
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+        assert!(
+            stdout.is_empty(),
+            "No diff expected: got stdout: {}",
+            stdout
+        );
+    }
+
+    /// Test --check-idempotent with files does not modify them.
+    #[test]
+    fn test_check_idempotent_with_file() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("doc.md");
+        let input = "# Test Heading\n\n * item one\n * item two\n";
+        fs::write(&path, input).expect("Failed to write file");
+
+        let (stdout, stderr, exit_code) =
+            run_hongdown(&["--check-idempotent", path.to_str().unwrap()], None);
+
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+        assert!(stdout.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), input);
+    }
+
+    /// Test --check-idempotent and --check are mutually exclusive.
+    #[test]
+    fn test_check_idempotent_check_mutually_exclusive() {
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &["--check-idempotent", "--check", "--stdin"],
+            Some("# Test"),
+        );
+
+        assert_ne!(exit_code, 0);
+        assert!(stderr.contains("cannot be used with"));
+    }
+
+    /// Test --verify accepts formatting that only changes style.
+    #[test]
+    fn test_verify_equivalent() {
+        let input = "Title\n=====\n\n * item one\n * item two\n\n<div>HTML</div>\n";
+        let (stdout, stderr, exit_code) = run_hongdown(&["--verify", "--stdin"], Some(input));
+
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+        assert!(stdout.is_empty(), "got stdout: {}", stdout);
+    }
+
+    /// Test --verify with files does not modify them.
+    #[test]
+    fn test_verify_with_file() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("doc.md");
+        let input = "# Test Heading\n\nA \"quoted\" [link][1].\n\n[1]: https://example.com\n";
+        fs::write(&path, input).expect("Failed to write file");
+
+        let (stdout, stderr, exit_code) = run_hongdown(&["--verify", path.to_str().unwrap()], None);
+
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+        assert!(stdout.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), input);
+    }
+
+    /// Test --verify and --write are mutually exclusive.
+    #[test]
+    fn test_verify_write_mutually_exclusive() {
+        let (_stdout, stderr, exit_code) =
+            run_hongdown(&["--verify", "--write", "--stdin"], Some("# Test"));
+
+        assert_ne!(exit_code, 0);
+        assert!(stderr.contains("cannot be used with"));
+    }
+
     /// Test --diff and --check are mutually exclusive.
     #[test]
     fn test_diff_check_mutually_exclusive() {
@@ -534,6 +787,339 @@ mod cli_tests {
         assert_eq!(exit_code, 0, "All files should pass check");
         assert!(stdout.is_empty());
     }
+
+    /// Test --max-warnings fails when warnings exceed the threshold.
+    #[test]
+    fn test_max_warnings_exceeded() {
+        let input = "See [foo][missing] and [bar][unknown].\n";
+        let (stdout, stderr, exit_code) =
+            run_hongdown(&["--max-warnings", "1", "--stdin"], Some(input));
+
+        assert_ne!(exit_code, 0, "Should fail when warnings exceed threshold");
+        assert!(stdout.contains("See [foo][missing]"));
+        assert!(
+            stderr.contains("exceeds the maximum of 1"),
+            "Should report the exceeded threshold: got stderr: {}",
+            stderr
+        );
+    }
+
+    /// Test --max-warnings succeeds when warnings are within the threshold.
+    #[test]
+    fn test_max_warnings_within_threshold() {
+        let input = "See [foo][missing] and [bar][unknown].\n";
+        let (_stdout, stderr, exit_code) =
+            run_hongdown(&["--max-warnings", "2", "--stdin"], Some(input));
+
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+        assert!(stderr.contains("warning:"));
+    }
+
+    /// Test --max-warnings 0 fails on any warning in --check mode.
+    #[test]
+    fn test_max_warnings_zero_with_check() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("warn.md");
+        fs::write(&path, "See [foo][missing].\n").expect("Failed to write file");
+
+        let (_stdout, _stderr, exit_code) =
+            run_hongdown(&["--check", path.to_str().unwrap()], None);
+        assert_eq!(exit_code, 0, "Warnings alone should not fail --check");
+
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &["--check", "--max-warnings", "0", path.to_str().unwrap()],
+            None,
+        );
+        assert_ne!(exit_code, 0);
+        assert!(stderr.contains("exceeds the maximum of 0"));
+    }
+
+    /// Test --warnings-as-errors fails --check on formatted files with warnings.
+    #[test]
+    fn test_warnings_as_errors_with_check() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("warn.md");
+        fs::write(&path, "See [foo][missing].\n").expect("Failed to write file");
+        let clean = temp_dir.path().join("clean.md");
+        fs::write(&clean, "No warnings here.\n").expect("Failed to write file");
+
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &["--check", "--warnings-as-errors", path.to_str().unwrap()],
+            None,
+        );
+        assert_ne!(exit_code, 0);
+        assert!(!stderr.contains("not formatted"), "got stderr: {}", stderr);
+        assert!(
+            stderr.contains("found 1 warning(s) treated as errors"),
+            "got stderr: {}",
+            stderr
+        );
+
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &["--check", "--warnings-as-errors", clean.to_str().unwrap()],
+            None,
+        );
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+    }
+
+    /// Test warnings_as_errors in the configuration file selects rules.
+    #[test]
+    fn test_warnings_as_errors_config() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("warn.md");
+        fs::write(&path, "See [foo][missing].\n").expect("Failed to write file");
+        let references = temp_dir.path().join("references.toml");
+        fs::write(&references, "warnings_as_errors = [\"references\"]\n")
+            .expect("Failed to write config");
+        let tables = temp_dir.path().join("tables.toml");
+        fs::write(&tables, "warnings_as_errors = [\"HD002\"]\n").expect("Failed to write config");
+
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &[
+                "--check",
+                "--config",
+                references.to_str().unwrap(),
+                path.to_str().unwrap(),
+            ],
+            None,
+        );
+        assert_ne!(exit_code, 0, "got stderr: {}", stderr);
+
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &[
+                "--check",
+                "--config",
+                tables.to_str().unwrap(),
+                path.to_str().unwrap(),
+            ],
+            None,
+        );
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+
+        // The command-line flag makes all warnings errors
+        let (_stdout, _stderr, exit_code) = run_hongdown(
+            &[
+                "--check",
+                "--warnings-as-errors",
+                "--config",
+                tables.to_str().unwrap(),
+                path.to_str().unwrap(),
+            ],
+            None,
+        );
+        assert_ne!(exit_code, 0);
+    }
+
+    /// Test --check respects the line_ending option.
+    #[test]
+    fn test_check_line_ending() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("crlf.md");
+        fs::write(&path, "Title\r\n=====\r\n\r\nText.\r\n").expect("Failed to write file");
+        let config = temp_dir.path().join(".hongdown.toml");
+        fs::write(&config, "line_ending = \"preserve\"\n").expect("Failed to write config");
+
+        let (_stdout, stderr, exit_code) = run_hongdown(&["--check", path.to_str().unwrap()], None);
+        assert_ne!(exit_code, 0, "CRLF should not pass --check by default");
+        assert!(stderr.contains("not formatted"));
+
+        let (_stdout, stderr, exit_code) = run_hongdown(
+            &[
+                "--check",
+                "--config",
+                config.to_str().unwrap(),
+                path.to_str().unwrap(),
+            ],
+            None,
+        );
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+    }
+    /// Test --cache remembers formatted files and notices changes.
+    #[test]
+    fn test_cache_with_check_and_write() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let cache = temp_dir.path().join("cache");
+        let formatted = temp_dir.path().join("formatted.md");
+        let unformatted = temp_dir.path().join("unformatted.md");
+        let warning = temp_dir.path().join("warning.md");
+        fs::write(&formatted, "Title\n=====\n\nText.\n").expect("Failed to write file");
+        fs::write(&unformatted, "# Title\n").expect("Failed to write file");
+        fs::write(&warning, "See [foo][missing].\n").expect("Failed to write file");
+        let files = [
+            formatted.to_str().unwrap(),
+            unformatted.to_str().unwrap(),
+            warning.to_str().unwrap(),
+        ];
+        let run = |mode: &str| {
+            let mut args = vec![mode, "--cache", cache.to_str().unwrap()];
+            args.extend(files);
+            run_hongdown(&args, None)
+        };
+
+        // Only the already formatted file without warnings is cached
+        let (_stdout, _stderr, exit_code) = run("--check");
+        assert_eq!(exit_code, 1);
+        let content = fs::read_to_string(&cache).expect("Cache should be written");
+        assert!(content.starts_with("hongdown-cache 1 "));
+        assert!(content.contains("formatted.md"));
+        assert!(!content.contains("unformatted.md"));
+        assert!(!content.contains("warning.md"));
+
+        // Files written by --write are cached with their new contents
+        let (_stdout, _stderr, exit_code) = run("--write");
+        assert_eq!(exit_code, 0);
+        assert!(
+            fs::read_to_string(&cache)
+                .unwrap()
+                .contains("unformatted.md")
+        );
+
+        // Warnings of uncached files are still reported
+        let (_stdout, stderr, exit_code) = run("--check");
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+        assert!(stderr.contains("warning.md:1: warning:"));
+
+        // Changed contents are formatted again
+        fs::write(&formatted, "# Title\n").expect("Failed to write file");
+        let (_stdout, stderr, exit_code) = run("--check");
+        assert_eq!(exit_code, 1);
+        assert!(stderr.contains("formatted.md: not formatted"));
+    }
+
+    /// Test the --cache fingerprint is stable across runs with alert synonyms.
+    #[test]
+    fn test_cache_fingerprint_stable_with_alert_synonyms() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let cache = temp_dir.path().join("cache");
+        let config = temp_dir.path().join(".hongdown.toml");
+        let file = temp_dir.path().join("test.md");
+        fs::write(
+            &config,
+            "[alert.synonyms]\ninfo = \"NOTE\"\ndanger = \"CAUTION\"\n\
+             hint = \"TIP\"\nwarn = \"WARNING\"\nerror = \"CAUTION\"\n",
+        )
+        .expect("Failed to write config");
+        fs::write(&file, "Title\n=====\n\nText.\n").expect("Failed to write file");
+        let header = || {
+            let (_stdout, stderr, exit_code) = run_hongdown(
+                &[
+                    "--check",
+                    "--config",
+                    config.to_str().unwrap(),
+                    "--cache",
+                    cache.to_str().unwrap(),
+                    file.to_str().unwrap(),
+                ],
+                None,
+            );
+            assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+            let content = fs::read_to_string(&cache).expect("Cache should be written");
+            content.lines().next().unwrap().to_string()
+        };
+
+        let first = header();
+        for _ in 0..4 {
+            assert_eq!(header(), first);
+        }
+    }
+
+    /// Test the --cache fingerprint is stable across runs with per-language
+    /// tab widths.
+    #[test]
+    fn test_cache_fingerprint_stable_with_code_expand_tabs() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let cache = temp_dir.path().join("cache");
+        let config = temp_dir.path().join(".hongdown.toml");
+        let file = temp_dir.path().join("test.md");
+        fs::write(
+            &config,
+            "[code_block.expand_tabs]\nyaml = 2\npython = 4\ngo = 8\n\
+             make = 8\nrust = 4\n",
+        )
+        .expect("Failed to write config");
+        fs::write(&file, "Title\n=====\n\nText.\n").expect("Failed to write file");
+        let header = || {
+            let (_stdout, stderr, exit_code) = run_hongdown(
+                &[
+                    "--check",
+                    "--config",
+                    config.to_str().unwrap(),
+                    "--cache",
+                    cache.to_str().unwrap(),
+                    file.to_str().unwrap(),
+                ],
+                None,
+            );
+            assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+            let content = fs::read_to_string(&cache).expect("Cache should be written");
+            content.lines().next().unwrap().to_string()
+        };
+
+        let first = header();
+        for _ in 0..4 {
+            assert_eq!(header(), first);
+        }
+    }
+
+    /// Test bench reports phase timings instead of the formatted output.
+    #[cfg(feature = "bench")]
+    #[test]
+    fn test_bench_reports_timings() {
+        let (stdout, stderr, exit_code) = run_hongdown(&["bench", "-"], Some("# Title\n"));
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+        assert!(stdout.starts_with("<stdin>: parse "));
+        assert!(stdout.contains(", serialize "));
+        assert!(stdout.contains(", code formatters "));
+        assert!(!stdout.contains("Title"));
+
+        let (stdout, stderr, exit_code) = run_hongdown(
+            &["bench", "--line-width", "40", "--parallel-sections", "-"],
+            Some("# Title\n"),
+        );
+        assert_eq!(exit_code, 0, "got stderr: {}", stderr);
+        assert!(stdout.starts_with("<stdin>: parse "));
+    }
+
+    /// Test explain describes a rule, and warnings name their rules.
+    #[test]
+    fn test_explain() {
+        let (stdout, _stderr, exit_code) = run_hongdown(&["explain", "HD004"], None);
+        assert_eq!(exit_code, 0);
+        assert!(stdout.starts_with("HD004: table-cell-html\n"));
+        assert!(stdout.contains("escape_html"));
+
+        let (stdout, _stderr, exit_code) = run_hongdown(&["explain"], None);
+        assert_eq!(exit_code, 0);
+        assert!(stdout.contains("HD001  undefined-reference"));
+
+        let (_stdout, stderr, exit_code) = run_hongdown(&["explain", "HD999"], None);
+        assert_eq!(exit_code, 1);
+        assert!(stderr.contains("Unknown rule: HD999"));
+
+        let (_stdout, stderr, _exit_code) = run_hongdown(&["-"], Some("[a][b]\n"));
+        assert!(stderr.contains("<stdin>:1: warning: undefined reference link: [b] [HD001]"));
+    }
 }
 
 /// Test proper nouns directive in sentence case.